@@ -1,22 +1,37 @@
 //! Interpret `.chirp` files, spawning entities with a provided [`Commands`].
 
 use std::borrow::Cow;
-use std::{any, fmt, fmt::Debug, mem, str};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+use std::{any, fmt, fmt::Debug, iter, mem, str};
 
-use bevy::asset::LoadContext;
+use bevy::asset::{io::AssetSourceId, AssetPath, LoadContext, ParseAssetPathError};
 use bevy::ecs::prelude::{Commands, Entity};
 use bevy::hierarchy::BuildChildren;
 use bevy::log::{error, trace};
 use bevy::reflect::TypeRegistry;
-use bevy::utils::HashMap;
-use cuicui_dsl::EntityCommands;
-use miette::{Diagnostic, NamedSource, SourceSpan};
+use bevy::utils::{get_short_name, Duration, HashMap, Instant};
+use cuicui_dsl::{DslDefaults, EntityCommands};
+use miette::{Diagnostic, LabeledSpan, NamedSource, Severity, SourceCode, SourceSpan};
 use smallvec::SmallVec;
 use thiserror::Error;
-use winnow::BStr;
+use winnow::{stream::Stream, BStr};
 
-use crate::parse_dsl::{self, MethodCtx, ParseDsl};
-use crate::parser::{self, chirp_file, Arguments, ChirpFile, FnIndex, Input, Name};
+use crate::migrate::Migrations;
+use crate::parse_dsl::{self, raw_string, MethodCtx, ParseDsl};
+use crate::parser::{self, chirp_file, Arguments, ChirpFile, Declaration, Input, Name, Token};
+use crate::report;
+use dsls::{BlockDsl, Dsls, ErasedDsl};
+use imports::{import_path, Source, SourceError};
+use templates::{Missing, Templates};
+
+pub(crate) use imports::Sources;
+
+mod dsls;
+mod imports;
+mod templates;
 
 type Span = (u32, u32);
 
@@ -37,34 +52,112 @@ pub enum InterpError {
     BadUtf8Argument,
     #[error("Method '{0}' is uppercase.")]
     UppercaseMethod(Box<str>),
-    #[error("Imports are not supported as of cuicui 0.10")]
-    Import,
+    #[error("Couldn't import '{0}': {1}")]
+    Import(Box<str>, Box<str>),
+    #[error("Invalid import path: {0}")]
+    ImportPath(#[from] ParseAssetPathError),
     #[error("Tried to call {}!, but this template doesn't exist.", BStr::new(&.0))]
     TemplateNotFound(Box<[u8]>),
+    #[error(
+        "Tried to call {}!, but the '{}' namespace isn't declared.",
+        BStr::new(&.0),
+        BStr::new(&.1)
+    )]
+    UnknownNamespace(Box<[u8]>, Box<[u8]>),
+    #[error("Tried to call {}!, but several imports declare this template.", BStr::new(&.0))]
+    AmbiguousTemplate(Box<[u8]>, Box<str>),
+    #[error("'{0}' was already called on this statement with different arguments")]
+    DuplicateMethod(Box<str>),
+    #[error("'{0}' was already called on this statement, only the last call is kept")]
+    OverridenMethod(Box<str>),
+    #[error("'{0}' was already called on this statement with the same arguments")]
+    RedundantMethod(Box<str>),
+    #[error("'{0}' is deprecated: {1}")]
+    DeprecatedMethod(Box<str>, &'static str),
+    #[error("Several DSLs have a '{0}' method, only the '{}' one is called", .1[0])]
+    ShadowedMethod(Box<str>, Box<[&'static str]>),
+    #[error("This file is written for the {} DSL, but is loaded with {1}", BStr::new(&.0))]
+    DslMismatch(Box<[u8]>, &'static str),
+    #[error("The {} DSL wasn't added to the chirp loader", BStr::new(&.0))]
+    UnknownDsl(Box<[u8]>, Box<str>),
+    #[error(transparent)]
+    TemplateArgument(#[from] parser::ArgumentError),
 }
 const UTF8_ERROR: &str =
     "Chirp requires UTF8, your file is either corrupted or saved with the wrong encoding.";
 impl InterpError {
-    fn help_message<D>(&self) -> Option<Box<str>> {
+    fn help_message(&self, dsl: &str) -> Option<Box<str>> {
         use crate::parse_dsl::DslParseError;
         use InterpError::{BadUtf8Argument, BadUtf8MethodName, Import, TemplateNotFound};
+        use InterpError::{OverridenMethod, RedundantMethod};
 
         match self {
-            Self::CodeNotPresent(_) | TemplateNotFound(_) | Import => None,
-            Self::DslError(err) => Some(if err.downcast_ref::<DslParseError>().is_some() {
+            Self::CodeNotPresent(_) | TemplateNotFound(_) => None,
+            Import(..) | Self::ImportPath(_) => None,
+            Self::UnknownNamespace(_, namespace) => Some(
+                format!(
+                    "Import the file declaring this template with \
+                    `use \"file.chirp\" as {}` at the top of the file.",
+                    BStr::new(namespace)
+                )
+                .into(),
+            ),
+            Self::AmbiguousTemplate(_, imports) => Some(
                 format!(
-                    "{} doesn't contain a method with this name.",
-                    any::type_name::<D>()
+                    "The template is declared in the following imports: {imports}. \
+                    Add an alias to the imports (`use widgets as w`) and prefix the \
+                    template call with the alias of the one to use (`w::button!()`)."
                 )
-                .into()
+                .into(),
+            ),
+            OverridenMethod(_) | RedundantMethod(_) | Self::DeprecatedMethod(..) => None,
+            Self::ShadowedMethod(name, namespaces) => {
+                let calls: Vec<_> = namespaces.iter().map(|ns| format!("`{ns}:{name}`")).collect();
+                Some(
+                    format!(
+                        "Prefix the method with the namespace of the DSL to call: {}. \
+                        Only DSLs with a `namespace` meta-attribute in their \
+                        `parse_dsl_impl` attribute can be called this way.",
+                        calls.join(", ")
+                    )
+                    .into(),
+                )
+            }
+            Self::DuplicateMethod(_) => Some(
+                format!(
+                    "Remove one of the calls. To keep the last call instead, \
+                    add `duplicate_methods = LastWins` to the `parse_dsl_impl` \
+                    attribute of {dsl}."
+                )
+                .into(),
+            ),
+            Self::DslError(err) => Some(if err.downcast_ref::<DslParseError>().is_some() {
+                format!("{dsl} doesn't contain a method with this name.").into()
             } else {
                 "The error comes from the ParseDsl implementation.".into()
             }),
             Self::ParseError(err) => Some(err.help().into()),
+            Self::TemplateArgument(err) => Some(err.help().into()),
             Self::UppercaseMethod(_) => {
                 Some("You probably forgot to close a parenthesis in the last method list.".into())
             }
             BadUtf8MethodName | BadUtf8Argument => Some(UTF8_ERROR.into()),
+            Self::DslMismatch(hint, _) => Some(
+                format!(
+                    "Load it with `cuicui_chirp::loader::Plugin::new::<{}>()`, \
+                    or remove the `dsl` hint at the top of the file.",
+                    BStr::new(hint)
+                )
+                .into(),
+            ),
+            Self::UnknownDsl(name, added) => Some(
+                format!(
+                    "Add it with `WorldHandles::add_dsl::<{}>` before loading \
+                    this file. DSLs added: [{added}].",
+                    BStr::new(name)
+                )
+                .into(),
+            ),
         }
     }
     fn dsl_offset(&self) -> Option<u32> {
@@ -76,27 +169,92 @@ impl InterpError {
         err.downcast_ref().and_then(ReflectError::maybe_offset)
     }
 }
-// TODO(feat): print call stack.
-#[derive(Debug, Error, Diagnostic)]
-#[error("{error}")]
+/// The `dsl Name` hint of `input`, following the optional `chirp N` version
+/// header, and its span.
+fn dsl_hint(input: &[u8]) -> Option<(&[u8], Span)> {
+    let mut input = Input::new(input, ());
+    let mut token = input.next_token();
+    if token == Some(Token::Ident(b"chirp")) {
+        input.next_token();
+        token = input.next_token();
+    }
+    if token != Some(Token::Ident(b"dsl")) {
+        return None;
+    }
+    let start = input.next_start();
+    let Some(Token::Ident(hint)) = input.next_token() else {
+        return None;
+    };
+    Some((hint, (start, input.current_offset())))
+}
+/// Whether `hint` is the name of the `D` type, with or without its module path
+/// and generic parameters.
+fn is_dsl<D>(hint: &[u8]) -> bool {
+    is_dsl_name(any::type_name::<D>(), hint)
+}
+/// Whether `hint` is `full_name`, with or without its module path and
+/// generic parameters.
+fn is_dsl_name(full_name: &str, hint: &[u8]) -> bool {
+    let name = get_short_name(full_name);
+    let bare_name = name.split('<').next().unwrap_or(&name);
+    [full_name, &name, bare_name].iter().any(|name| name.as_bytes() == hint)
+}
+#[derive(Debug, Error)]
+#[error("{error}{}", NiceCallStack(&self.call_stack))]
 struct SpannedError {
-    #[label]
     span: SourceSpan,
     error: InterpError,
-    #[help]
     help: Option<Box<str>>,
+    /// The imported file the error is in, `None` when it is in the
+    /// interpreted file, the source code of [`Errors`].
+    file: Option<NamedSource>,
+    /// The template calls leading to the error, outermost first, see
+    /// [`Interpreter::call_stack`].
+    call_stack: Box<[Box<str>]>,
+    /// Where the error is, as `path:line:column`, see [`location`].
+    location: Box<str>,
+}
+impl Diagnostic for SpannedError {
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = self.help.as_ref()?;
+        Some(Box::new(help))
+    }
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.file.as_ref().map(|file| file as &dyn SourceCode)
+    }
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(iter::once(LabeledSpan::new_with_span(None, self.span))))
+    }
 }
 impl SpannedError {
-    fn new<D>(error: impl Into<InterpError>, (mut start, mut end): Span) -> Self {
+    /// A new error, `dsl` is the type name of the DSL of the statement.
+    fn new(error: impl Into<InterpError>, (mut start, mut end): Span, dsl: &str) -> Self {
         let as_usize = |x: u32| usize::try_from(x).unwrap();
         let error: InterpError = error.into();
-        let help = error.help_message::<D>();
+        let help = error.help_message(dsl);
         if let Some(offset) = error.dsl_offset() {
             start += offset;
             end = start;
         }
         let span = (as_usize(start)..as_usize(end)).into();
-        Self { span, error, help }
+        let (file, call_stack, location) = (None, Box::default(), Box::default());
+        Self { span, error, help, file, call_stack, location }
+    }
+    /// Show the error in the imported `source` file rather than the
+    /// interpreted one.
+    fn in_file(mut self, source: Option<&Source>, asset_root: Option<&Path>) -> Self {
+        self.file = source.map(|source| {
+            let path = source.path.to_string();
+            let disk_path = disk_path(asset_root, &source.path);
+            self.location = location(&disk_path, &source.bytes, self.span.offset()).into();
+            let content = String::from_utf8_lossy(&source.bytes).into_owned();
+            NamedSource::new(path, content)
+        });
+        self
+    }
+    fn with_call_stack(mut self, call_stack: Box<[Box<str>]>) -> Self {
+        self.call_stack = call_stack;
+        self
     }
 }
 /// Describe errors encountered while parsing and interpreting a chirp file.
@@ -108,14 +266,109 @@ pub struct Errors {
     source_code: NamedSource,
     #[related]
     errors: Vec<SpannedError>,
+    /// The `path:line:column` of each error, clickable in most terminals and
+    /// editors, rendered after the errors.
+    #[help]
+    locations: Option<String>,
 }
 impl Errors {
-    fn new(errors: Vec<SpannedError>, input: &[u8], load_ctx: Option<&LoadContext>) -> Self {
-        let str = Cow::Borrowed("Static str");
-        let file_name = load_ctx.map_or(str, |l| l.path().to_string_lossy());
+    fn new(
+        mut errors: Vec<SpannedError>,
+        input: &[u8],
+        load_ctx: Option<&LoadContext>,
+        asset_root: Option<&Path>,
+    ) -> Self {
+        let path = load_ctx.map_or(Cow::Borrowed(""), |l| l.path().to_string_lossy());
+        let disk_path = |l: &LoadContext| disk_path(asset_root, l.asset_path());
+        let disk_path = load_ctx.map_or_else(String::new, disk_path);
+        for error in errors.iter_mut().filter(|e| e.location.is_empty()) {
+            error.location = location(&disk_path, input, error.span.offset()).into();
+        }
+        let locations = errors.iter().map(|e| format!("\n{}: {}", e.location, e.error));
+        let locations = Some(format!("Locations:{}", locations.collect::<String>()));
+        let file_name = if path.is_empty() { Cow::Borrowed("Static str") } else { path };
         let input = String::from(String::from_utf8_lossy(input));
         let source_code = NamedSource::new(file_name, input);
-        Self { source_code, errors }
+        Self { source_code, errors, locations }
+    }
+}
+/// Where the byte `offset` is in `bytes`, the content of the file at `path`,
+/// as `path:line:column`, starting at 1. Only `line:column` when `path` is empty.
+fn location(path: &str, bytes: &[u8], offset: usize) -> String {
+    let before = &bytes[..offset.min(bytes.len())];
+    let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let column = String::from_utf8_lossy(&before[line_start..]).chars().count() + 1;
+    match path {
+        "" => format!("{line}:{column}"),
+        path => format!("{path}:{line}:{column}"),
+    }
+}
+/// Where `path` is on disk, so that error locations are clickable in terminals
+/// and editors. `path` as is when it's not in the default asset source, or
+/// `asset_root`, the directory of the default asset source, is unknown.
+fn disk_path(asset_root: Option<&Path>, path: &AssetPath) -> String {
+    match (asset_root, path.source()) {
+        (Some(root), AssetSourceId::Default) if path != &AssetPath::default() => {
+            root.join(path.path()).display().to_string()
+        }
+        _ => path.to_string(),
+    }
+}
+/// Describe warnings encountered while interpreting a chirp file.
+///
+/// Unlike [`Errors`], those do not prevent the chirp file from loading.
+#[derive(Debug, Error, Diagnostic)]
+#[diagnostic(severity(Warning))]
+#[error("Suspicious chirp file: {}", NiceErrors(&self.lints))]
+pub struct Lints {
+    #[source_code]
+    source_code: NamedSource,
+    #[related]
+    lints: Vec<SpannedError>,
+    /// See [`Errors::locations`].
+    #[help]
+    locations: Option<String>,
+}
+impl Lints {
+    fn new(
+        lints: Vec<SpannedError>,
+        input: &[u8],
+        load_ctx: Option<&LoadContext>,
+        asset_root: Option<&Path>,
+    ) -> Self {
+        let errors = Errors::new(lints, input, load_ctx, asset_root);
+        let Errors { source_code, errors, locations } = errors;
+        Self { source_code, lints: errors, locations }
+    }
+}
+/// Statistics of loading a chirp file, to track how costly UI files are to load.
+///
+/// See [`Chirp::stats`], and the [`ChirpStats`] resource for the stats of
+/// all loaded files.
+///
+/// [`ChirpStats`]: crate::loader::ChirpStats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadStats {
+    /// Time spent parsing the file.
+    pub parse_time: Duration,
+    /// Time spent interpreting the parsed file, calling DSL methods and
+    /// `code` functions.
+    pub interpret_time: Duration,
+    /// How many entities the file spawned.
+    pub entities: u32,
+    /// How many templates were expanded, including template calls within
+    /// templates.
+    pub template_calls: u32,
+}
+impl fmt::Display for LoadStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { parse_time, interpret_time, entities, template_calls } = self;
+        write!(
+            f,
+            "parsed in {parse_time:.2?}, interpreted in {interpret_time:.2?}, \
+            {entities} entities, {template_calls} template calls"
+        )
     }
 }
 struct NiceSpan(SourceSpan);
@@ -130,11 +383,22 @@ impl fmt::Display for NiceSpan {
     }
 }
 
+/// Show the innermost template call first, like a stack trace.
+struct NiceCallStack<'c>(&'c [Box<str>]);
+impl fmt::Display for NiceCallStack<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for call in self.0.iter().rev() {
+            write!(f, "\n    in {call}")?;
+        }
+        Ok(())
+    }
+}
+
 struct NiceErrors<'e>(&'e [SpannedError]);
 impl fmt::Display for NiceErrors<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for (i, error) in self.0.iter().enumerate() {
-            write!(f, "({i}) {error}, ")?;
+            write!(f, "({i}) {}: {error}, ", error.location)?;
         }
         Ok(())
     }
@@ -146,18 +410,36 @@ impl fmt::Display for NiceErrors<'_> {
 /// - `&TypeRegistry`: the main app type registry.
 /// - `Option<&LoadContext>`: The load context, if in the context of asset loading.
 ///   this can be used to get arbitrary `Handle<T>`s.
+/// - `&Arguments`: The arguments following the function name in the `code`
+///   statement. For `code(list_item, "Hello", 42)`, this is `"Hello"` and `42`.
+///   Template [parameter substitution] is applied.
 /// - `&mut EntityCommands`: Entity to use for this `code` function.
-pub type CodeFunctionBox =
-    Box<dyn Fn(&TypeRegistry, Option<&LoadContext>, &mut EntityCommands) + Send + Sync>;
+///
+/// Functions are reference-counted so that [`Handles`] can be cheaply cloned.
+///
+/// [`Arguments`]: parse_dsl::Arguments
+/// [parameter substitution]: crate#parameter-substitution
+pub type CodeFunctionArc = Arc<
+    dyn Fn(&TypeRegistry, Option<&LoadContext>, &parse_dsl::Arguments, &mut EntityCommands)
+        + Send
+        + Sync,
+>;
 
-/// Reference-based pendant of [`CodeFunctionBox`]. See `CodeFunctionBox` docs for details.
+/// Reference-based pendant of [`CodeFunctionArc`]. See `CodeFunctionArc` docs for details.
 pub type CodeFunctionRef<'a> =
-    &'a (dyn Fn(&TypeRegistry, Option<&LoadContext>, &mut EntityCommands) + Send + Sync);
+    &'a (dyn Fn(&TypeRegistry, Option<&LoadContext>, &parse_dsl::Arguments, &mut EntityCommands)
+             + Send
+             + Sync);
 
 /// Registry of functions used in `code` block in [`crate::Chirp`]s.
-#[derive(Default)]
+///
+/// Cloning `Handles` is cheap, functions, migrations and DSLs are shared
+/// between clones.
+#[derive(Default, Clone)]
 pub struct Handles {
-    funs: HashMap<Box<[u8]>, CodeFunctionBox>,
+    funs: HashMap<Box<[u8]>, CodeFunctionArc>,
+    migrations: Migrations,
+    dsls: Dsls,
 }
 impl Handles {
     /// Create a new empty chirp handle registry.
@@ -168,32 +450,75 @@ impl Handles {
     /// Associate `name` with `function` in `chirp` code statements.
     ///
     /// `function` may be called from a `chirp` file from a `code` statement if
-    /// `name` is passed as argument.
+    /// `name` is passed as argument. Further arguments of the `code` statement
+    /// are passed to `function`, see [`CodeFunctionArc`].
     ///
     /// Returns any function already associated with provided name, if present.
     pub fn add_function(
         &mut self,
         name: impl Into<String>,
-        function: impl Fn(&TypeRegistry, Option<&LoadContext>, &mut EntityCommands)
-            + Send
+        function: impl Fn(
+                &TypeRegistry,
+                Option<&LoadContext>,
+                &parse_dsl::Arguments,
+                &mut EntityCommands,
+            ) + Send
             + Sync
             + 'static,
-    ) -> Option<CodeFunctionBox> {
+    ) -> Option<CodeFunctionArc> {
         let name = name.into().into_bytes().into_boxed_slice();
-        self.funs.insert(name, Box::new(function))
+        self.funs.insert(name, Arc::new(function))
     }
     /// Get function registered with provided `name`.
     pub fn get_function(&self, name: &impl AsRef<str>) -> Option<CodeFunctionRef> {
-        self.funs.get(name.as_ref().as_bytes()).map(Box::as_ref)
+        self.funs.get(name.as_ref().as_bytes()).map(Arc::as_ref)
     }
     fn get_function_u8(&self, name: &[u8]) -> Option<CodeFunctionRef> {
-        self.funs.get(name).map(Box::as_ref)
+        self.funs.get(name).map(Arc::as_ref)
+    }
+    /// Run `migration` on chirp files of version `version` or lower before
+    /// interpreting them.
+    ///
+    /// See the [`crate::migrate`] module for details.
+    pub fn add_migration(
+        &mut self,
+        version: u32,
+        migration: impl for<'a> Fn(&'a [u8]) -> Cow<'a, [u8]> + Send + Sync + 'static,
+    ) {
+        self.migrations.add(version, migration);
+    }
+    pub(crate) fn migrate<'a>(&self, input: &'a [u8]) -> Cow<'a, [u8]> {
+        self.migrations.migrate(input)
+    }
+    /// Allow `with dsl D { statements }` blocks in chirp files, the statements
+    /// of the block are interpreted with `D` rather than the loader's DSL.
+    ///
+    /// Each statement of the block starts with the `defaults` applied. Pass the
+    /// `DslDefaults<D>` resource to use the same defaults as the chirp loader
+    /// of `D`, if any.
+    ///
+    /// As with the [DSL hint](crate#dsl-hint), `D` can be named by the full
+    /// path of the type, its name, or its name without generic parameters.
+    pub fn add_dsl<D: ParseDsl + 'static>(&mut self, defaults: DslDefaults<D>) {
+        self.dsls.add(defaults);
     }
 }
 
-struct LoadCtx<'h, 'r> {
-    reg: &'r TypeRegistry,
-    handles: &'h Handles,
+impl Extend<(String, CodeFunctionArc)> for Handles {
+    fn extend<T: IntoIterator<Item = (String, CodeFunctionArc)>>(&mut self, iter: T) {
+        let iter = iter.into_iter().map(|(name, f)| (name.into_bytes().into(), f));
+        self.funs.extend(iter);
+    }
+}
+
+/// What the interpreter reads chirp files with.
+#[derive(Clone, Copy)]
+pub(crate) struct LoadCtx<'h, 'r> {
+    pub(crate) reg: &'r TypeRegistry,
+    pub(crate) handles: &'h Handles,
+    /// The directory of the default asset source, to show where errors are on
+    /// disk, see [`disk_path`].
+    pub(crate) asset_root: Option<&'h Path>,
 }
 impl Debug for LoadCtx<'_, '_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -210,10 +535,39 @@ pub(crate) struct Interpreter<'w, 's, 'a, 'l, D> {
     ///
     /// Or the current parent if we are not on the root entity.
     root_entity: Entity,
-    templates: HashMap<&'a [u8], FnIndex<'a>>,
+    /// The templates callable from each file, indexed by file id, see
+    /// [`Sources`]. The first one is the interpreted file's.
+    templates: Vec<Templates<'a, Declaration<'a, 'a>>>,
+    /// The files imported by the interpreted file, directly or not.
+    sources: &'a Sources,
+    /// The file nodes are read from, innermost last, empty when reading
+    /// the interpreted file. See [`parser::Interpreter::enter_file`].
+    files: Vec<u32>,
+    /// The template calls nodes are expanded from, innermost last: the name of
+    /// the template, and the file and span of the call.
+    /// See [`parser::Interpreter::enter_template`].
+    templates_called: Vec<(&'a [u8], u32, Span)>,
+    /// The interpreted file, to locate template calls in it.
+    input: &'a [u8],
+    /// Methods called on the current statement, with a hash of their arguments
+    /// and the span of their name.
+    called_methods: Vec<(&'a [u8], u64, Span)>,
+    /// Index in `called_methods` of the first template extra method of the
+    /// current template call level. Methods before it may be overriden.
+    extras_start: usize,
     errors: Vec<SpannedError>,
+    lints: Vec<SpannedError>,
     load_ctx: Option<&'a mut LoadContext<'l>>,
+    /// The initial value of `dsl` for each statement.
+    defaults: &'a DslDefaults<D>,
     dsl: D,
+    /// The DSLs of the `with dsl` blocks containing the current statement,
+    /// innermost last.
+    delegates: Vec<BlockDsl>,
+    /// Buffer for [`ParseDsl::method_namespaces`], to avoid allocating on
+    /// each method call.
+    namespaces: Vec<&'static str>,
+    stats: LoadStats,
 }
 impl<'w, 's, 'a, 'l, D> fmt::Debug for Interpreter<'w, 's, 'a, 'l, D> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -229,28 +583,48 @@ impl<'w, 's, 'a, 'l, D> fmt::Debug for Interpreter<'w, 's, 'a, 'l, D> {
 
 impl<'w, 's, 'a, 'l> Interpreter<'w, 's, 'a, 'l, ()> {
     pub(crate) fn interpret<D: ParseDsl>(
-        input_u8: &[u8],
+        input_u8: &'a [u8],
         builder: &'a mut EntityCommands<'w, 's, 'a>,
         load_ctx: Option<&'a mut LoadContext<'l>>,
-        reg: &'a TypeRegistry,
-        handles: &'a Handles,
-    ) -> Result<(), Errors> {
+        ctx: LoadCtx<'a, 'a>,
+        defaults: &'a DslDefaults<D>,
+        sources: &'a Sources,
+    ) -> Result<LoadStats, Errors> {
+        if let Some((hint, span)) = dsl_hint(input_u8) {
+            if !is_dsl::<D>(hint) {
+                let dsl = any::type_name::<D>();
+                let error = InterpError::DslMismatch(hint.into(), dsl);
+                let error = SpannedError::new(error, span, dsl);
+                return Err(Errors::new(vec![error], input_u8, load_ctx.as_deref(), ctx.asset_root));
+            }
+        }
+        let start = Instant::now();
         let input = Input::new(input_u8, ());
         let ast = match chirp_file(input) {
             Ok(v) => v,
             Err((err, span)) => {
-                let error = SpannedError::new::<D>(err, span);
-                return Err(Errors::new(vec![error], input_u8, load_ctx.as_deref()));
+                let error = SpannedError::new(err, span, any::type_name::<D>());
+                return Err(Errors::new(vec![error], input_u8, load_ctx.as_deref(), ctx.asset_root));
             }
         };
+        let parse_time = start.elapsed();
         let chirp_file = ChirpFile::new(input, ast.as_ref());
-        let mut interpreter = Interpreter::<D>::new(builder, load_ctx, reg, handles);
+        let asset_root = ctx.asset_root;
+        let mut interpreter = Interpreter::new(builder, load_ctx, ctx, defaults, sources);
+        interpreter.input = input_u8;
+        interpreter.import_libraries();
         chirp_file.interpret(&mut interpreter);
+        let interpret_time = start.elapsed() - parse_time;
+        if !interpreter.lints.is_empty() {
+            let lints = mem::take(&mut interpreter.lints);
+            let ctx = interpreter.load_ctx.as_deref();
+            report::report(&Lints::new(lints, input_u8, ctx, asset_root), Severity::Warning);
+        }
         if interpreter.errors.is_empty() {
-            Ok(())
+            Ok(LoadStats { parse_time, interpret_time, ..interpreter.stats })
         } else {
             let ctx = interpreter.load_ctx.as_deref();
-            Err(Errors::new(interpreter.errors, input_u8, ctx))
+            Err(Errors::new(interpreter.errors, input_u8, ctx, asset_root))
         }
     }
 }
@@ -258,31 +632,256 @@ impl<'w, 's, 'a, 'l, D: ParseDsl> Interpreter<'w, 's, 'a, 'l, D> {
     fn new(
         builder: &'a mut EntityCommands<'w, 's, 'a>,
         load_ctx: Option<&'a mut LoadContext<'l>>,
-        reg: &'a TypeRegistry,
-        handles: &'a Handles,
+        ctx: LoadCtx<'a, 'a>,
+        defaults: &'a DslDefaults<D>,
+        sources: &'a Sources,
     ) -> Self {
         let root_entity = builder.id();
         let cmds = builder.commands();
         Interpreter {
-            ctx: LoadCtx { reg, handles },
+            ctx,
             cmds,
             parent_chain: SmallVec::new(),
-            templates: HashMap::new(),
+            templates: iter::repeat_with(Templates::default).take(sources.len() + 1).collect(),
+            sources,
+            files: Vec::new(),
+            templates_called: Vec::new(),
+            input: &[],
+            called_methods: Vec::new(),
+            extras_start: 0,
             errors: Vec::new(),
-            dsl: D::default(),
+            lints: Vec::new(),
+            dsl: defaults.dsl(),
+            delegates: Vec::new(),
+            defaults,
             load_ctx,
             root_entity,
+            namespaces: Vec::new(),
+            stats: LoadStats::default(),
         }
     }
+    /// Declare the templates of the imported files, and their imports.
+    fn import_libraries(&mut self) {
+        let sources = self.sources;
+        let mut libraries = Vec::new();
+        for (file, Source { bytes, ast, .. }) in sources.iter() {
+            match ast {
+                Ok(ast) => {
+                    let library = ChirpFile::library(Input::new(bytes, ()), ast.as_ref(), file);
+                    libraries.push((file, library));
+                }
+                Err(SourceError::Parse(error, span)) => self.push_error_in(file, *span, *error),
+                // Reported by the files importing it.
+                Err(SourceError::Read(_)) => {}
+            }
+        }
+        for (file, library) in &libraries {
+            for ((name, _), declaration) in library.templates() {
+                self.templates[*file as usize].insert(name, declaration);
+            }
+        }
+        for (file, library) in &libraries {
+            for (name, alias) in library.imports() {
+                self.import_in(*file, name, alias);
+            }
+        }
+    }
+    /// Add the templates of the file imported with `use name as alias` to
+    /// the templates callable from `file`.
+    fn import_in(&mut self, file: u32, (name, span): Name<'a>, alias: Option<Name<'a>>) {
+        let alias = alias.map(|(alias, _)| alias);
+        let path = match import_path(&self.file_path(file), name) {
+            Ok(path) => path,
+            Err(err) => {
+                self.templates[file as usize].import(name, alias);
+                self.push_error_in(file, span, err);
+                return;
+            }
+        };
+        let reason = match self.sources.get(&path) {
+            Some((imported, Source { ast: Ok(_), .. })) => {
+                let declared: Vec<_> = self.templates[imported as usize].local().collect();
+                self.templates[file as usize].import_loaded(name, alias, declared);
+                return;
+            }
+            Some((_, Source { ast: Err(SourceError::Read(reason)), .. })) => reason.clone(),
+            // The parse error is reported in the imported file.
+            Some((_, Source { ast: Err(SourceError::Parse(..)), .. })) => {
+                self.templates[file as usize].import(name, alias);
+                return;
+            }
+            None if path == self.file_path(0) => "cyclic imports are not supported".into(),
+            None => "imports are only read when loading chirp files as assets".into(),
+        };
+        self.templates[file as usize].import(name, alias);
+        self.push_error_in(file, span, InterpError::Import(path.to_string().into(), reason));
+    }
+    /// The asset path of `file`, to resolve the imports of `file`.
+    fn file_path(&self, file: u32) -> AssetPath<'static> {
+        match self.sources.source(file) {
+            Some(source) => source.path.clone(),
+            None => self.load_ctx.as_deref().map_or_else(AssetPath::default, |ctx| {
+                ctx.asset_path().clone()
+            }),
+        }
+    }
+    /// The file nodes are currently read from, see [`Sources`].
+    fn current_file(&self) -> u32 {
+        self.files.last().copied().unwrap_or(0)
+    }
     #[cold]
     fn push_error(&mut self, span: Span, error: impl Into<InterpError>) {
-        self.errors.push(SpannedError::new::<D>(error, span));
+        self.push_error_in(self.current_file(), span, error);
+    }
+    #[cold]
+    fn push_error_in(&mut self, file: u32, span: Span, error: impl Into<InterpError>) {
+        let error = SpannedError::new(error, span, self.dsl_name());
+        let error = error.in_file(self.sources.source(file), self.ctx.asset_root);
+        self.errors.push(error.with_call_stack(self.call_stack()));
+    }
+    #[cold]
+    fn push_lint(&mut self, span: Span, lint: InterpError) {
+        let lint = SpannedError::new(lint, span, self.dsl_name());
+        let lint = lint.in_file(self.sources.source(self.current_file()), self.ctx.asset_root);
+        self.lints.push(lint.with_call_stack(self.call_stack()));
+    }
+    /// The template calls the current node is expanded from, outermost first,
+    /// as "template `name!` called at path:line:column".
+    fn call_stack(&self) -> Box<[Box<str>]> {
+        let call = |&(name, file, span): &(&[u8], u32, Span)| {
+            let location = self.location(file, span);
+            format!("template `{}!` called at {location}", BStr::new(name)).into()
+        };
+        self.templates_called.iter().map(call).collect()
+    }
+    /// Where `span` starts in `file`, see [`location`].
+    fn location(&self, file: u32, (start, _): Span) -> String {
+        let bytes = self.sources.source(file).map_or(self.input, |source| &source.bytes);
+        let start = usize::try_from(start).unwrap();
+        location(&disk_path(self.ctx.asset_root, &self.file_path(file)), bytes, start)
+    }
+    /// The type name of the DSL of the current statement.
+    fn dsl_name(&self) -> &'static str {
+        match self.delegates.last() {
+            Some(BlockDsl::Delegate(delegate)) => delegate.type_name,
+            _ => any::type_name::<D>(),
+        }
+    }
+    /// Whether the current statement is in a `with dsl` block of a DSL that
+    /// wasn't added.
+    fn in_unknown_dsl(&self) -> bool {
+        matches!(self.delegates.last(), Some(BlockDsl::Unknown))
+    }
+    /// The DSL of the current statement.
+    fn dsl(&mut self) -> &mut dyn ErasedDsl {
+        current_dsl(&mut self.dsl, &mut self.delegates)
+    }
+    /// Report methods of several DSLs called without namespace, see
+    /// [`ParseDsl::method_namespaces`].
+    fn check_shadowed(&mut self, name: &str, span: Span) {
+        if name.contains(':') {
+            return;
+        }
+        self.namespaces.clear();
+        let dsl = current_dsl(&mut self.dsl, &mut self.delegates);
+        dsl.method_namespaces(name, &mut self.namespaces);
+        if self.namespaces.len() > 1 {
+            let namespaces = self.namespaces.as_slice().into();
+            self.push_lint(span, InterpError::ShadowedMethod(name.into(), namespaces));
+        }
+    }
+    /// Check the identifier `ident` doesn't contain characters looking like
+    /// delimiters or spaces, see [`parser::confusable`].
+    ///
+    /// Returns `false` if it does.
+    fn check_confusable(&mut self, ident: &[u8], (start, _): Span) -> bool {
+        let Some((offset, found, ascii)) = parser::confusable(ident) else {
+            return true;
+        };
+        let start = start + u32::try_from(offset).unwrap();
+        let end = start + u32::try_from(found.len_utf8()).unwrap();
+        let error = parser::Error::ConfusableChar(found, ascii);
+        self.push_error((start, end), error);
+        false
+    }
+    /// Check `name` wasn't already called on this statement, according to
+    /// [`ParseDsl::DUPLICATE_METHODS`].
+    ///
+    /// Template extras always override methods of the template they extend.
+    ///
+    /// Returns `false` if the method shouldn't be called.
+    fn check_duplicate(&mut self, name: &'a [u8], span: Span, arguments: &Arguments) -> bool {
+        use crate::parse_dsl::DuplicateMethods::{Error, LastWins};
+
+        let mut hasher = DefaultHasher::new();
+        for i in 0..arguments.len() {
+            arguments.get(i).hash(&mut hasher);
+        }
+        let hash = hasher.finish();
+        let previous = self.called_methods.iter().position(|(n, ..)| *n == name);
+        let Some(previous) = previous else {
+            self.called_methods.push((name, hash, span));
+            return true;
+        };
+        if previous < self.extras_start {
+            trace!("Template extra overrides {}", BStr::new(name));
+            self.called_methods.remove(previous);
+            self.extras_start -= 1;
+            self.called_methods.push((name, hash, span));
+            return true;
+        }
+        let previous_hash = self.called_methods[previous].1;
+        let str_name = String::from_utf8_lossy(name).into();
+        match self.dsl().duplicate_methods() {
+            _ if previous_hash == hash => {
+                self.push_lint(span, InterpError::RedundantMethod(str_name));
+                true
+            }
+            Error => {
+                self.push_error(span, InterpError::DuplicateMethod(str_name));
+                false
+            }
+            LastWins => {
+                self.push_lint(span, InterpError::OverridenMethod(str_name));
+                true
+            }
+        }
     }
 
+    /// Report a [`ParseDsl::conflict`] of the current statement, on the span of
+    /// the last conflicting method called.
+    fn check_conflict(&mut self) {
+        let Some(conflict) = self.dsl().conflict() else {
+            return;
+        };
+        let is_conflicting = |(name, ..): &&(&[u8], u64, Span)| {
+            conflict.methods.iter().any(|method| method.as_bytes() == *name)
+        };
+        let called = &self.called_methods;
+        let span = called.iter().rev().find(is_conflicting).or_else(|| called.last());
+        match span {
+            Some(&(_, _, span)) => self.push_error(span, conflict.error),
+            None => error!("{}", conflict.error),
+        }
+    }
     fn statement_spawn(&mut self) -> Option<Entity> {
         trace!("Inserting DSL");
+        self.check_conflict();
 
-        let mut dsl = mem::take(&mut self.dsl); // we set to the default D
+        let (mut file_dsl, mut delegate_dsl);
+        let dsl: &mut dyn ErasedDsl = match self.delegates.last_mut() {
+            Some(BlockDsl::Delegate(delegate)) => {
+                delegate_dsl = delegate.take();
+                &mut *delegate_dsl
+            }
+            _ => {
+                file_dsl = mem::replace(&mut self.dsl, self.defaults.dsl());
+                &mut file_dsl
+            }
+        };
+        self.called_methods.clear();
+        self.extras_start = 0;
+        self.stats.entities += 1;
 
         // - no parent: we are root, use root_entity
         // - parent, but equal to root_entity: means we have a single parent use any
@@ -307,6 +906,9 @@ impl<'w, 's, 'a, 'l, D: ParseDsl> parser::Interpreter<'a, 'a> for Interpreter<'w
     fn method(&mut self, (name, name_span): Name<'a>, arguments: &Arguments) {
         use crate::parse_dsl::DslParseError;
 
+        if self.in_unknown_dsl() {
+            return;
+        }
         let Ok(name) = str::from_utf8(name) else {
             let error = InterpError::BadUtf8MethodName;
             self.push_error(name_span, error);
@@ -317,8 +919,19 @@ impl<'w, 's, 'a, 'l, D: ParseDsl> parser::Interpreter<'a, 'a> for Interpreter<'w
             self.push_error(name_span, error);
             return;
         }
+        if !self.check_confusable(name.as_bytes(), name_span) {
+            return;
+        }
         trace!("Method: {name}{arguments}");
-        let Self { load_ctx, dsl, .. } = self;
+        if !self.check_duplicate(name.as_bytes(), name_span, arguments) {
+            return;
+        }
+        if let Some(message) = self.dsl().deprecation(name) {
+            self.push_lint(name_span, InterpError::DeprecatedMethod(name.into(), message));
+        }
+        self.check_shadowed(name, name_span);
+        let Self { load_ctx, dsl, delegates, .. } = self;
+        let dsl = current_dsl(dsl, delegates);
         let args_span = arguments.span().unwrap_or(name_span);
         let ctx = MethodCtx {
             name,
@@ -332,6 +945,9 @@ impl<'w, 's, 'a, 'l, D: ParseDsl> parser::Interpreter<'a, 'a> for Interpreter<'w
             self.push_error(span, err);
         }
     }
+    fn template_extras(&mut self) {
+        self.extras_start = self.called_methods.len();
+    }
     fn start_children(&mut self) {
         let inserted = self.statement_spawn();
         trace!(">>> Going deeper now…");
@@ -350,9 +966,9 @@ impl<'w, 's, 'a, 'l, D: ParseDsl> parser::Interpreter<'a, 'a> for Interpreter<'w
             Some(_) => parent_chain.push(mem::replace(root_entity, inserted)),
         }
     }
-    fn code(&mut self, (identifier, span): Name<'a>) {
+    fn code(&mut self, (identifier, span): Name<'a>, arguments: &Arguments) {
         let b_name = BStr::new(identifier);
-        trace!("Calling registered function {b_name}");
+        trace!("Calling registered function {b_name}{arguments}");
         let Some(code) = self.ctx.handles.get_function_u8(identifier) else {
             let name = String::from_utf8_lossy(identifier);
             self.push_error(span, InterpError::CodeNotPresent(name.into()));
@@ -361,18 +977,26 @@ impl<'w, 's, 'a, 'l, D: ParseDsl> parser::Interpreter<'a, 'a> for Interpreter<'w
         let load_ctx = self.load_ctx.as_deref();
         let mut cmds = self.cmds.spawn_empty();
         cmds.set_parent(self.root_entity);
-        code(self.ctx.reg, load_ctx, &mut cmds);
+        self.stats.entities += 1;
+        code(self.ctx.reg, load_ctx, &arguments.into(), &mut cmds);
     }
 
     fn set_name(&mut self, (name, span): Name) {
         trace!("= node {} =", BStr::new(name));
+        if self.in_unknown_dsl() {
+            return;
+        }
+        let is_quoted = name.starts_with(b"\"") || name.starts_with(b"'");
+        if !is_quoted && raw_string(name).is_none() && !self.check_confusable(name, span) {
+            return;
+        }
         let ctx = MethodCtx {
             name: "named",
             arguments: parse_dsl::Arguments::for_name(name),
             ctx: self.load_ctx.as_deref_mut(),
             registry: self.ctx.reg,
         };
-        if let Err(err) = self.dsl.method(ctx) {
+        if let Err(err) = current_dsl(&mut self.dsl, &mut self.delegates).method(ctx) {
             self.push_error(span, err);
         }
     }
@@ -391,28 +1015,113 @@ impl<'w, 's, 'a, 'l, D: ParseDsl> parser::Interpreter<'a, 'a> for Interpreter<'w
         *root_entity = entity;
     }
 
-    fn import(&mut self, (_name, span): Name<'a>, _alias: Option<Name>) {
-        self.push_error(span, InterpError::Import);
+    fn import(&mut self, name: Name<'a>, alias: Option<Name<'a>>) {
+        self.import_in(0, name, alias);
     }
 
-    fn register_fn(&mut self, (name, _): Name<'a>, index: FnIndex<'a>) {
-        self.templates.insert(name, index);
+    fn register_fn(&mut self, (name, _): Name<'a>, declaration: Declaration<'a, 'a>) {
+        self.templates[0].insert(name, declaration);
     }
 
-    fn get_template(&mut self, (name, span): Name<'a>) -> Option<FnIndex<'a>> {
-        if let Some(key) = self.templates.get(name) {
-            trace!("<<--- {}", BStr::new(name));
-            return Some(*key);
-        }
-        self.push_error(span, InterpError::TemplateNotFound(name.into()));
+    fn get_template(&mut self, (name, span): Name<'a>) -> Option<Declaration<'a, 'a>> {
+        let error = match self.templates[self.current_file() as usize].get(name) {
+            Ok(key) => {
+                trace!("<<--- {}", BStr::new(name));
+                self.stats.template_calls += 1;
+                return Some(key);
+            }
+            // The failed import is already reported.
+            Err(Missing::NotLoaded) => return None,
+            Err(Missing::NotFound) => InterpError::TemplateNotFound(name.into()),
+            Err(Missing::UnknownNamespace(namespace)) => {
+                InterpError::UnknownNamespace(name.into(), namespace.into())
+            }
+            Err(Missing::Ambiguous(imports)) => {
+                let imports = imports.iter().map(|i| String::from_utf8_lossy(i));
+                let imports = imports.collect::<Vec<_>>().join(", ");
+                InterpError::AmbiguousTemplate(name.into(), imports.into())
+            }
+        };
+        self.push_error(span, error);
         None
     }
+
+    fn argument_error(&mut self, span: Span, error: parser::ArgumentError) {
+        self.push_error(span, error);
+    }
+
+    fn enter_file(&mut self, file: u32, _: Span) {
+        self.files.push(file);
+    }
+    fn exit_file(&mut self) {
+        self.files.pop();
+    }
+    fn enter_template(&mut self, name: &'a [u8], file: u32, call: Span) {
+        self.templates_called.push((name, file, call));
+    }
+    fn exit_template(&mut self) {
+        self.templates_called.pop();
+    }
+
+    fn start_dsl(&mut self, (name, span): Name<'a>) {
+        trace!("with dsl {}", BStr::new(name));
+        let dsls = &self.ctx.handles.dsls;
+        let block_dsl = match dsls.get(name) {
+            _ if is_dsl::<D>(name) => BlockDsl::Loader,
+            Some(delegate) => BlockDsl::Delegate(delegate),
+            None => {
+                let added = dsls.names().map(get_short_name).collect::<Vec<_>>().join(", ");
+                self.push_error(span, InterpError::UnknownDsl(name.into(), added.into()));
+                BlockDsl::Unknown
+            }
+        };
+        self.delegates.push(block_dsl);
+    }
+    fn complete_dsl(&mut self) {
+        self.delegates.pop();
+    }
 }
 
-#[cfg(never)]
+/// The DSL of the current statement, the innermost `with dsl` block's or `dsl`.
+fn current_dsl<'d, D: ParseDsl>(
+    dsl: &'d mut D,
+    delegates: &'d mut [BlockDsl],
+) -> &'d mut dyn ErasedDsl {
+    match delegates.last_mut() {
+        Some(BlockDsl::Delegate(delegate)) => &mut *delegate.dsl,
+        _ => dsl,
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn dsl_hints() {
+        struct UiDsl<T>(T);
+        let hint = |input| dsl_hint(input).map(|(hint, span)| (BStr::new(hint), span));
+        assert_eq!(hint(b"chirp 1\ndsl UiDsl\nEntity()"), Some((BStr::new("UiDsl"), (12, 17))));
+        assert_eq!(hint(b"dsl UiDsl Entity()"), Some((BStr::new("UiDsl"), (4, 9))));
+        assert_eq!(hint(b"dsl(row) {}"), None);
+        assert_eq!(hint(b"Entity() {}"), None);
+
+        assert!(is_dsl::<UiDsl<()>>(b"UiDsl"));
+        assert!(is_dsl::<UiDsl<()>>(b"UiDsl<()>"));
+        assert!(!is_dsl::<UiDsl<()>>(b"LayoutDsl"));
+
+        let full_name = "cuicui_layout_bevy_ui::dsl::UiDsl<cuicui_layout::dsl::LayoutDsl>";
+        assert!(is_dsl_name(full_name, full_name.as_bytes()));
+        assert!(is_dsl_name(full_name, b"UiDsl<LayoutDsl>"));
+        assert!(is_dsl_name(full_name, b"UiDsl"));
+        assert!(!is_dsl_name(full_name, b"LayoutDsl"));
+    }
+}
+
+#[cfg(never)]
+mod escape_tests {
+    use super::*;
+
     #[test]
     fn test_escape() {
         let output = escape_literal(br#"ab\\c\\\d\e"#);
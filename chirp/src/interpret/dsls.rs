@@ -0,0 +1,96 @@
+//! The DSLs of `with dsl` blocks, see [`Handles::add_dsl`].
+//!
+//! Chirp files are loaded with the DSL of the loader [`Plugin`], but subtrees
+//! can be written for another DSL: `with dsl SpriteDsl { statements }`.
+//! Since the interpreter is generic over the loader's DSL, other DSLs are
+//! type-erased as [`ErasedDsl`].
+//!
+//! [`Handles::add_dsl`]: super::Handles::add_dsl
+//! [`Plugin`]: crate::loader::Plugin
+use std::{any, sync::Arc};
+
+use anyhow::Result;
+use cuicui_dsl::{DslDefaults, EntityCommands};
+
+use super::is_dsl_name;
+use crate::parse_dsl::{DuplicateMethods, MethodConflict, MethodCtx, ParseDsl};
+
+/// A [`ParseDsl`] usable as a trait object.
+pub(crate) trait ErasedDsl {
+    fn method(&mut self, ctx: MethodCtx) -> Result<()>;
+    fn insert(&mut self, cmds: &mut EntityCommands);
+    fn deprecation(&self, name: &str) -> Option<&'static str>;
+    fn conflict(&self) -> Option<MethodConflict>;
+    fn method_namespaces(&self, name: &str, namespaces: &mut Vec<&'static str>);
+    fn duplicate_methods(&self) -> DuplicateMethods;
+}
+impl<D: ParseDsl> ErasedDsl for D {
+    fn method(&mut self, ctx: MethodCtx) -> Result<()> {
+        ParseDsl::method(self, ctx)
+    }
+    fn insert(&mut self, cmds: &mut EntityCommands) {
+        cuicui_dsl::DslBundle::insert(self, cmds);
+    }
+    fn deprecation(&self, name: &str) -> Option<&'static str> {
+        ParseDsl::deprecation(self, name)
+    }
+    fn conflict(&self) -> Option<MethodConflict> {
+        ParseDsl::conflict(self)
+    }
+    fn method_namespaces(&self, name: &str, namespaces: &mut Vec<&'static str>) {
+        ParseDsl::method_namespaces(self, name, namespaces);
+    }
+    fn duplicate_methods(&self) -> DuplicateMethods {
+        D::DUPLICATE_METHODS
+    }
+}
+
+/// Create the DSL of a statement, with its defaults applied.
+type NewDsl = Arc<dyn Fn() -> Box<dyn ErasedDsl> + Send + Sync>;
+
+/// The DSLs usable in `with dsl` blocks, by type name.
+#[derive(Clone, Default)]
+pub(crate) struct Dsls(Vec<(&'static str, NewDsl)>);
+impl Dsls {
+    /// Add `D`, replacing it if it was already added.
+    pub(crate) fn add<D: ParseDsl + 'static>(&mut self, defaults: DslDefaults<D>) {
+        let name = any::type_name::<D>();
+        self.0.retain(|(added, _)| *added != name);
+        self.0.push((name, Arc::new(move || Box::new(defaults.dsl()))));
+    }
+    /// The DSL named `name` in a `with dsl` block, see [`is_dsl_name`].
+    pub(crate) fn get(&self, name: &[u8]) -> Option<Delegate> {
+        let (type_name, new) = self.0.iter().find(|(type_name, _)| is_dsl_name(type_name, name))?;
+        Some(Delegate { type_name, dsl: new(), new: Arc::clone(new) })
+    }
+    pub(crate) fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.0.iter().map(|(type_name, _)| *type_name)
+    }
+}
+
+/// The DSL of the statements of a `with dsl` block.
+pub(crate) enum BlockDsl {
+    /// The DSL of the chirp loader.
+    Loader,
+    Delegate(Delegate),
+    /// A DSL that wasn't added, methods of the block are not called, since
+    /// the missing DSL is already reported.
+    Unknown,
+}
+
+/// A DSL added with [`Handles::add_dsl`].
+///
+/// [`Handles::add_dsl`]: super::Handles::add_dsl
+pub(crate) struct Delegate {
+    pub(crate) type_name: &'static str,
+    /// The DSL of the current statement.
+    pub(crate) dsl: Box<dyn ErasedDsl>,
+    new: NewDsl,
+}
+impl Delegate {
+    /// Take the DSL of the current statement, the next statement starts with
+    /// a new DSL.
+    pub(crate) fn take(&mut self) -> Box<dyn ErasedDsl> {
+        std::mem::replace(&mut self.dsl, (self.new)())
+    }
+}
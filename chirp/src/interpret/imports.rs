@@ -0,0 +1,132 @@
+//! The files imported by a chirp file, read before interpreting it.
+//!
+//! The interpreter runs synchronously, while reading files with the asset
+//! server is async. So [`Sources::read`] reads all the files imported by a
+//! chirp file, directly or not, before the file is interpreted.
+//!
+//! Files are read with [`LoadContext::read_asset_bytes`], this makes them
+//! loader dependencies of the importing file: bevy reloads the importing file
+//! when an imported file changes.
+use bevy::asset::{AssetPath, LoadContext};
+use bevy::log::trace;
+
+use super::{InterpError, Span};
+use crate::parse_dsl::{escape_literal, raw_string};
+use crate::parser::{chirp_file, Ast, ChirpFile, Input};
+use crate::{parser, Handles};
+
+/// Why an imported file can't be used.
+pub(crate) enum SourceError {
+    /// The asset server couldn't read the file.
+    Read(Box<str>),
+    /// The file isn't a valid chirp file.
+    Parse(parser::Error, Span),
+}
+
+/// A chirp file imported by the interpreted file, directly or not.
+pub(crate) struct Source {
+    pub(crate) path: AssetPath<'static>,
+    /// The migrated content of the file, empty if it couldn't be read.
+    pub(crate) bytes: Box<[u8]>,
+    pub(crate) ast: Result<Ast, SourceError>,
+}
+impl Source {
+    /// Parse the already migrated `bytes` of the file at `path`.
+    fn new(path: AssetPath<'static>, bytes: Box<[u8]>) -> Self {
+        let ast = chirp_file(Input::new(&bytes, ()));
+        let ast = ast.map_err(|(err, span)| SourceError::Parse(err, span));
+        Self { path, bytes, ast }
+    }
+}
+
+/// The chirp files imported by a chirp file, see the [module docs](self).
+///
+/// The interpreter identifies imported files by their index in `Sources`
+/// plus one, `0` being the interpreted file.
+#[derive(Default)]
+pub(crate) struct Sources(Vec<Source>);
+impl Sources {
+    /// Read the files imported by `input`, the content of the file loaded
+    /// with `ctx`, and the files they import.
+    ///
+    /// The file loaded with `ctx` is never read again, importing it from one
+    /// of its imports is an error reported by the interpreter.
+    pub(crate) async fn read(ctx: &mut LoadContext<'_>, handles: &Handles, input: &[u8]) -> Self {
+        let root = ctx.asset_path().clone();
+        let mut sources = Self::default();
+        let mut to_read = match chirp_file(Input::new(input, ())) {
+            Ok(ast) => import_paths(&root, input, &ast),
+            // The interpreter reports it.
+            Err(_) => Vec::new(),
+        };
+        while let Some(path) = to_read.pop() {
+            if path == root || sources.get(&path).is_some() {
+                continue;
+            }
+            trace!("Reading imported chirp file {path}");
+            let source = match ctx.read_asset_bytes(&path).await {
+                Ok(bytes) => {
+                    let source = Source::new(path, handles.migrate(&bytes).into());
+                    if let Ok(ast) = &source.ast {
+                        to_read.extend(import_paths(&source.path, &source.bytes, ast));
+                    }
+                    source
+                }
+                Err(err) => {
+                    let ast = Err(SourceError::Read(err.to_string().into()));
+                    Source { path, bytes: Box::default(), ast }
+                }
+            };
+            sources.0.push(source);
+        }
+        sources
+    }
+    /// The file at `path` and its id, if it was read.
+    pub(crate) fn get(&self, path: &AssetPath) -> Option<(u32, &Source)> {
+        let index = self.0.iter().position(|source| &source.path == path)?;
+        Some((u32::try_from(index).unwrap() + 1, &self.0[index]))
+    }
+    /// The file with the `file` id, `None` for `0`, the interpreted file.
+    pub(crate) fn source(&self, file: u32) -> Option<&Source> {
+        let index = usize::try_from(file.checked_sub(1)?).unwrap();
+        self.0.get(index)
+    }
+    /// Already read files, with their path and migrated content.
+    #[cfg(test)]
+    pub(crate) fn from_files<'a>(files: impl IntoIterator<Item = (&'a str, &'a [u8])>) -> Self {
+        let files = files.into_iter().map(|(path, bytes)| {
+            Source::new(AssetPath::parse(path).clone_owned(), bytes.into())
+        });
+        Self(files.collect())
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (u32, &Source)> {
+        (1..).zip(&self.0)
+    }
+}
+
+/// The paths of the files imported by `input`, ignoring invalid paths.
+fn import_paths(importer: &AssetPath, input: &[u8], ast: &Ast) -> Vec<AssetPath<'static>> {
+    let file = ChirpFile::new(Input::new(input, ()), ast.as_ref());
+    let paths = file.imports().filter_map(|((name, _), _)| import_path(importer, name).ok());
+    paths.collect()
+}
+
+/// The path of the file imported with `use name`, relative to the `importer`.
+///
+/// `name` is either a string literal (`"widgets.chirp"` or `r"widgets.chirp"`)
+/// or a path without the `.chirp` extension (`ui/widgets`).
+pub(crate) fn import_path(
+    importer: &AssetPath,
+    name: &[u8],
+) -> Result<AssetPath<'static>, InterpError> {
+    let quoted = name.strip_prefix(b"\"").and_then(|n| n.strip_suffix(b"\""));
+    let path = match (quoted, raw_string(name)) {
+        (Some(literal), _) => String::from_utf8_lossy(&escape_literal(literal)).into_owned(),
+        (None, Some(raw)) => String::from_utf8_lossy(raw).into_owned(),
+        (None, None) => format!("{}.chirp", String::from_utf8_lossy(name)),
+    };
+    Ok(importer.resolve_embed(&path)?)
+}
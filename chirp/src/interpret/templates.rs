@@ -0,0 +1,170 @@
+//! The template registry of a chirp file, resolving template calls to their
+//! declaration.
+//!
+//! Templates can be called by their bare name (`button!()`) or prefixed by
+//! the alias of an import (`w::button!()` or `w.button!()` after a
+//! `use "widgets.chirp" as w`).
+//!
+//! Bare names are resolved in this order:
+//! 1. Templates declared in the file itself, they shadow imported templates.
+//! 2. Templates of imports without an alias. If several of those declare
+//!    a template with the same name, the call is ambiguous.
+use bevy::utils::HashMap;
+
+/// An imported file's templates.
+struct Namespace<'a, T> {
+    /// The path of the imported file, as written in the `use` statement.
+    path: &'a [u8],
+    /// The `as` alias of the import, `None` when the templates of the import
+    /// can be called by their bare name.
+    alias: Option<&'a [u8]>,
+    /// Whether the import was successfully loaded. When it wasn't, we do not
+    /// report missing templates, since the failed import is already reported.
+    loaded: bool,
+    templates: HashMap<&'a [u8], T>,
+}
+
+/// Why a template couldn't be resolved.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Missing<'a> {
+    /// No template with this name exist.
+    NotFound,
+    /// The template may be declared in an import that failed to load.
+    NotLoaded,
+    /// The namespace prefix of the template call isn't declared with a `use`.
+    UnknownNamespace(&'a [u8]),
+    /// The bare template name is declared in all those imports.
+    Ambiguous(Vec<&'a [u8]>),
+}
+
+pub(super) struct Templates<'a, T> {
+    local: HashMap<&'a [u8], T>,
+    namespaces: Vec<Namespace<'a, T>>,
+}
+impl<'a, T> Default for Templates<'a, T> {
+    fn default() -> Self {
+        Self { local: HashMap::new(), namespaces: Vec::new() }
+    }
+}
+impl<'a, T: Copy> Templates<'a, T> {
+    /// Declare an import that couldn't be loaded.
+    pub(super) fn import(&mut self, path: &'a [u8], alias: Option<&'a [u8]>) {
+        let templates = HashMap::new();
+        self.namespaces.push(Namespace { path, alias, loaded: false, templates });
+    }
+    /// Declare an import, with the `templates` declared in the imported file.
+    pub(super) fn import_loaded(
+        &mut self,
+        path: &'a [u8],
+        alias: Option<&'a [u8]>,
+        templates: impl IntoIterator<Item = (&'a [u8], T)>,
+    ) {
+        let templates = templates.into_iter().collect();
+        self.namespaces.push(Namespace { path, alias, loaded: true, templates });
+    }
+    /// The templates declared in this file.
+    pub(super) fn local(&self) -> impl Iterator<Item = (&'a [u8], T)> + '_ {
+        self.local.iter().map(|(name, value)| (*name, *value))
+    }
+    /// Whether `alias` is the alias of an import.
+    fn is_alias(&self, alias: &[u8]) -> bool {
+        self.namespaces.iter().any(|ns| ns.alias == Some(alias))
+    }
+    /// Declare a template in this file.
+    pub(super) fn insert(&mut self, name: &'a [u8], value: T) {
+        self.local.insert(name, value);
+    }
+    /// Split `name` into its namespace and template name.
+    ///
+    /// `::` always separates a namespace, while `.` only does when the prefix
+    /// is the alias of an import, since `.` is a valid identifier character.
+    fn split<'n>(&self, name: &'n [u8]) -> (Option<&'n [u8]>, &'n [u8]) {
+        let double_colon = name.windows(2).rposition(|w| w == b"::");
+        if let Some(i) = double_colon {
+            return (Some(&name[..i]), &name[i + 2..]);
+        }
+        match name.iter().position(|c| *c == b'.') {
+            Some(i) if self.is_alias(&name[..i]) => (Some(&name[..i]), &name[i + 1..]),
+            _ => (None, name),
+        }
+    }
+    /// Get the template called `name`, which may be prefixed by a namespace.
+    pub(super) fn get(&self, name: &'a [u8]) -> Result<T, Missing<'a>> {
+        match self.split(name) {
+            (Some(namespace), name) => self.get_namespaced(namespace, name),
+            (None, name) => self.get_bare(name),
+        }
+    }
+    fn get_namespaced(&self, alias: &'a [u8], name: &[u8]) -> Result<T, Missing<'a>> {
+        let namespace = self.namespaces.iter().find(|ns| ns.alias == Some(alias));
+        let Some(namespace) = namespace else {
+            return Err(Missing::UnknownNamespace(alias));
+        };
+        match namespace.templates.get(name) {
+            Some(value) => Ok(*value),
+            None if namespace.loaded => Err(Missing::NotFound),
+            None => Err(Missing::NotLoaded),
+        }
+    }
+    fn get_bare(&self, name: &[u8]) -> Result<T, Missing<'a>> {
+        if let Some(value) = self.local.get(name) {
+            return Ok(*value);
+        }
+        let mut globs = self.namespaces.iter().filter(|ns| ns.alias.is_none());
+        let mut found = globs.clone().filter_map(|ns| Some((ns.path, *ns.templates.get(name)?)));
+        match (found.next(), found.next()) {
+            (Some((_, value)), None) => Ok(value),
+            (Some((first, _)), Some((second, _))) => {
+                let mut paths = vec![first, second];
+                paths.extend(found.map(|(path, _)| path));
+                Err(Missing::Ambiguous(paths))
+            }
+            (None, _) if globs.any(|ns| !ns.loaded) => Err(Missing::NotLoaded),
+            (None, _) => Err(Missing::NotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaced_calls() {
+        let mut templates = Templates::default();
+        templates.import_loaded(b"\"widgets.chirp\"", Some(b"w"), [(&b"button"[..], 1)]);
+        templates.import_loaded(b"\"other.chirp\"", Some(b"o"), [(&b"button"[..], 2)]);
+        templates.insert(b"button", 0);
+
+        assert_eq!(templates.get(b"button"), Ok(0));
+        assert_eq!(templates.get(b"w::button"), Ok(1));
+        assert_eq!(templates.get(b"w.button"), Ok(1));
+        assert_eq!(templates.get(b"o::button"), Ok(2));
+        assert_eq!(templates.get(b"w::label"), Err(Missing::NotFound));
+        assert_eq!(templates.get(b"x::button"), Err(Missing::UnknownNamespace(b"x")));
+        assert_eq!(templates.get(b"x.button"), Err(Missing::NotFound));
+    }
+    #[test]
+    fn ambiguous_bare_calls() {
+        let mut templates = Templates::default();
+        templates.import_loaded(b"widgets", None, [(&b"button"[..], 1), (b"label", 3)]);
+        templates.import_loaded(b"other", None, [(&b"button"[..], 2)]);
+
+        assert_eq!(templates.get(b"label"), Ok(3));
+        let expected = Missing::Ambiguous(vec![&b"widgets"[..], b"other"]);
+        assert_eq!(templates.get(b"button"), Err(expected));
+
+        templates.insert(b"button", 0);
+        assert_eq!(templates.get(b"button"), Ok(0));
+    }
+    #[test]
+    fn failed_imports() {
+        let mut templates = Templates::<u32>::default();
+        templates.import(b"\"widgets.chirp\"", Some(b"w"));
+        assert_eq!(templates.get(b"w::button"), Err(Missing::NotLoaded));
+        assert_eq!(templates.get(b"button"), Err(Missing::NotFound));
+
+        templates.import(b"widgets", None);
+        assert_eq!(templates.get(b"button"), Err(Missing::NotLoaded));
+    }
+}
@@ -2,6 +2,7 @@
 [`cuicui_dsl`]: cuicui_dsl
 [dsl-inheritance]: cuicui_dsl#inheritance
 [`loader::Plugin`]: loader::Plugin
+[`loader::process`]: loader::process
 [`parse_dsl::args::Arguments`]: parse_dsl::args::Arguments
 [`parse_dsl::args::from_reflect`]: parse_dsl::args::from_reflect
 [`parse_dsl::args`]: parse_dsl::args
@@ -11,35 +12,20 @@
 [`parse_dsl_impl::delegate`]: parse_dsl_impl::delegate
 [`ParseDsl`]: ParseDsl
 [`ReflectDsl`]: reflect::ReflectDsl
+[`report::set_config`]: report::set_config
 [`Reflect`]: bevy::prelude::Reflect
 [`WorldHandles`]: WorldHandles
 */
 #![doc = include_str!("../README.md")]
 
-macro_rules! log_miette_error {
-    ($err: expr) => {
-        #[cfg(feature = "fancy_errors")]
-        let message = {
-            let mut s = String::new();
-            miette::GraphicalReportHandler::new()
-                .with_context_lines(2)
-                .with_width(90)
-                .with_footer("\n".into())
-                .render_report(&mut s, $err)
-                .unwrap();
-            s
-        };
-        #[cfg(not(feature = "fancy_errors"))]
-        let message = $err;
-        bevy::log::error!("{message:#}");
-    };
-}
-
 use bevy::asset::LoadContext;
 use bevy::ecs::{prelude::*, system::SystemState};
 use bevy::reflect::TypeRegistry;
+use cuicui_chirp_parser as parser;
+use cuicui_dsl::DslDefaults;
+use miette::Severity;
 
-use crate::interpret::Interpreter;
+use crate::interpret::{Interpreter, LoadCtx, Sources};
 
 pub use anyhow;
 /// `impl` block macro to implement [`ParseDsl`].
@@ -48,18 +34,31 @@ pub use anyhow;
 #[cfg(feature = "macros")]
 pub use cuicui_chirp_macros::parse_dsl_impl;
 pub use interpret::{Handles, InterpError};
-pub use loader::{Chirp, ChirpBundle, ChirpState, WorldHandles};
-pub use parse_dsl::{MethodCtx, ParseDsl};
+pub use loader::{Chirp, ChirpBundle, ChirpEvent, ChirpInstances, ChirpState, ChirpStats};
+pub use loader::WorldHandles;
+pub use parse_dsl::{DuplicateMethods, MethodConflict, MethodCtx, ParseDsl};
 pub use reflect::ReflectDsl;
 
-mod parser;
-
 pub mod interpret;
 pub mod loader;
+pub mod migrate;
 pub mod parse_dsl;
 #[cfg(feature = "macros")]
 pub mod parse_dsl_impl;
 pub mod reflect;
+pub mod report;
+
+/// The most common `cuicui_chirp` items, `use cuicui_chirp::prelude::*;`
+/// to import them.
+///
+/// The [`loader::Plugin`] is exported as `ChirpPlugin`, to not conflict with
+/// bevy's `Plugin` trait.
+pub mod prelude {
+    #[cfg(feature = "macros")]
+    pub use crate::parse_dsl_impl;
+    pub use crate::{loader::Plugin as ChirpPlugin, MethodCtx, ParseDsl};
+    pub use crate::{Chirp, ChirpBundle, ChirpEvent, ChirpInstances, ChirpState};
+}
 
 #[doc(hidden)]
 #[cfg(feature = "test_and_doc")]
@@ -120,26 +119,55 @@ impl<'a> ChirpReader<'a> {
     /// - Errors returned by [`ParseDsl::method`] (usually parsing or invalid
     ///   method errors)
     ///
+    /// Before interpreting, `input` goes through the [`Handles`]'s
+    /// [migrations](migrate).
+    ///
     /// The [`interpret::Errors`] implement [`miette::Diagnostic`] and lists
     /// **all interpretation errors** (either it stops at the first syntax
     // error or it tries to read and interpret the whole file)
-    pub fn interpret<D: ParseDsl>(
+    ///
+    /// Statements start with the [`DslDefaults<D>`] resource of [`Self::world`]
+    /// applied, if it exists.
+    pub fn interpret<D: ParseDsl + 'static>(
         &mut self,
         handles: &Handles,
         load_context: Option<&mut LoadContext>,
         registry: &TypeRegistry,
         input: &[u8],
     ) -> Result<Entity, interpret::Errors> {
+        let defaults = &self.defaults::<D>();
+        let sources = &Sources::default();
+        let ctx = LoadCtx { reg: registry, handles, asset_root: None };
+        let result = self.interpret_imports(ctx, defaults, load_context, input, sources);
+        result.map(|(id, _)| id)
+    }
+    fn defaults<D: ParseDsl + 'static>(&self) -> DslDefaults<D> {
+        self.world.get_resource::<DslDefaults<D>>().cloned().unwrap_or_default()
+    }
+    /// Same as [`Self::interpret`], but with the `sources` of the chirp files
+    /// imported by `input`, and returns the [`LoadStats`] of `input`.
+    ///
+    /// [`LoadStats`]: interpret::LoadStats
+    pub(crate) fn interpret_imports<D: ParseDsl>(
+        &mut self,
+        ctx: LoadCtx,
+        defaults: &DslDefaults<D>,
+        load_context: Option<&mut LoadContext>,
+        input: &[u8],
+        sources: &Sources,
+    ) -> Result<(Entity, interpret::LoadStats), interpret::Errors> {
+        let input = &ctx.handles.migrate(input);
         let mut state = SystemState::<Commands>::new(self.world);
         let mut cmds = state.get_mut(self.world);
         let mut cmds = cmds.spawn_empty();
         let id = cmds.id();
-        let result = Interpreter::interpret::<D>(input, &mut cmds, load_context, registry, handles);
+        let cmds = &mut cmds;
+        let result = Interpreter::interpret(input, cmds, load_context, ctx, defaults, sources);
 
         if result.is_ok() {
             state.apply(self.world);
         }
-        result.map(|()| id)
+        result.map(|stats| (id, stats))
     }
     /// Same as [`Self::interpret`], but directly logs error message instead
     /// of returning the result.
@@ -149,20 +177,24 @@ impl<'a> ChirpReader<'a> {
     /// it failed.
     #[allow(clippy::missing_panics_doc)] // panics only on `fmt::write` errors.
     #[must_use]
-    pub fn interpret_logging<D: ParseDsl>(
+    pub fn interpret_logging<D: ParseDsl + 'static>(
         &mut self,
         handles: &Handles,
         load_context: Option<&mut LoadContext>,
         registry: &TypeRegistry,
         input: &[u8],
     ) -> bool {
+        let input = &handles.migrate(input);
+        let defaults = &self.defaults::<D>();
         let mut state = SystemState::<Commands>::new(self.world);
         let mut cmds = state.get_mut(self.world);
         let mut cmds = cmds.spawn_empty();
-        let result = Interpreter::interpret::<D>(input, &mut cmds, load_context, registry, handles);
+        let sources = &Sources::default();
+        let (ctx, cmds) = (LoadCtx { reg: registry, handles, asset_root: None }, &mut cmds);
+        let result = Interpreter::interpret(input, cmds, load_context, ctx, defaults, sources);
 
         if let Err(err) = &result {
-            log_miette_error!(err);
+            report::report(err, Severity::Error);
             false
         } else {
             state.apply(self.world);
@@ -170,3 +202,211 @@ impl<'a> ChirpReader<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::core::Name;
+    use bevy::reflect::Reflect;
+    use cuicui_dsl::{BaseDsl, DslBundle, EntityCommands};
+    use miette::Diagnostic;
+
+    use super::*;
+
+    #[derive(Component, Debug, PartialEq, Eq)]
+    struct Tag(String);
+
+    #[derive(Default)]
+    struct TagDsl {
+        tag: Option<String>,
+        base: BaseDsl,
+    }
+    impl DslBundle for TagDsl {
+        fn insert(&mut self, cmds: &mut EntityCommands) {
+            if let Some(tag) = self.tag.take() {
+                cmds.insert(Tag(tag));
+            }
+            self.base.insert(cmds);
+        }
+    }
+    impl ParseDsl for TagDsl {
+        fn method(&mut self, ctx: MethodCtx) -> anyhow::Result<()> {
+            if ctx.name == "tag" {
+                self.tag = ctx.arguments.get_str(0).map(|tag| tag.into_owned());
+                Ok(())
+            } else {
+                self.base.method(ctx)
+            }
+        }
+    }
+
+    #[test]
+    fn load_stats() {
+        let input = br#"
+fn button(text) {
+    Entity(named(text)) {
+        Label()
+    }
+}
+Root {
+    button!("a")
+    button!("b")
+    Leaf()
+}
+"#;
+        let (mut world, registry) = (World::new(), TypeRegistry::new());
+        let (handles, defaults) = (Handles::new(), DslDefaults::<BaseDsl>::default());
+        let ctx = LoadCtx { reg: &registry, handles: &handles, asset_root: None };
+        let mut chirp = ChirpReader::new(&mut world);
+        let sources = &Sources::default();
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let (_, stats) = result.unwrap();
+        assert_eq!((stats.entities, stats.template_calls), (6, 2));
+        assert_eq!(world.entities().len(), 6);
+    }
+    #[test]
+    fn reflect_dsl_enum() {
+        #[derive(Component, Reflect, Default, Debug, PartialEq)]
+        enum State {
+            #[default]
+            Idle,
+            Loading(f32),
+            Moving { speed: f32 },
+        }
+        // `ReflectDsl` doesn't have a `named` method, so statements are `Entity`.
+        let input = br#"
+Entity {
+    Entity(idle)
+    Entity(loading(0.5))
+    Entity(moving(speed: 2.0))
+}
+"#;
+        let mut world = World::new();
+        let mut registry = TypeRegistry::new();
+        registry.register::<State>();
+        let (handles, defaults) = (Handles::new(), DslDefaults::<ReflectDsl<State>>::default());
+        let ctx = LoadCtx { reg: &registry, handles: &handles, asset_root: None };
+        let mut chirp = ChirpReader::new(&mut world);
+        let sources = &Sources::default();
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        result.unwrap();
+        let mut states = world.query::<&State>();
+        let states: Vec<_> = states.iter(&world).collect();
+        let moving = State::Moving { speed: 2.0 };
+        for state in [&State::Idle, &State::Loading(0.5), &moving] {
+            assert!(states.contains(&state), "{state:?} not in {states:?}");
+        }
+
+        let input = b"Entity(running)";
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("doesn't have such a variant"), "{error}");
+    }
+    #[test]
+    fn imported_templates() {
+        let library = br#"
+fn label(text) { Label(tag(text)) }
+fn broken() { missing!() }
+Library()
+"#;
+        let input = br#"
+use widgets as w
+Root {
+    w::label!(hud)
+}
+"#;
+        let (mut world, registry) = (World::new(), TypeRegistry::new());
+        let (handles, defaults) = (Handles::new(), DslDefaults::<TagDsl>::default());
+        let sources = &Sources::from_files([("widgets.chirp", &library[..])]);
+        let ctx = LoadCtx { reg: &registry, handles: &handles, asset_root: None };
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let (_, stats) = result.unwrap();
+        assert_eq!((stats.entities, stats.template_calls), (2, 1));
+        let mut tags = world.query::<(&Name, Option<&Tag>)>();
+        let mut tags: Vec<_> = tags.iter(&world).map(|(n, t)| (n.as_str(), t)).collect();
+        tags.sort_by_key(|(name, _)| *name);
+        let hud = Tag("hud".to_owned());
+        assert_eq!(tags, [("Label", Some(&hud)), ("Root", None)]);
+
+        let input = b"use widgets as w\nRoot { w::broken!() }";
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let errors = result.unwrap_err();
+        let help = errors.help().unwrap().to_string();
+        assert!(help.contains("\nwidgets.chirp:3:15: "), "{help}");
+        assert!(errors.to_string().contains("(0) widgets.chirp:3:15: "), "{errors}");
+        let [error] = &errors.related().unwrap().collect::<Vec<_>>()[..] else {
+            panic!("expected a single error: {errors}");
+        };
+        assert!(error.to_string().contains("missing!"), "{error}");
+        // The error is shown in the imported file.
+        assert!(error.source_code().is_some());
+        // With the template call leading to it.
+        let call_stack = "\n    in template `broken!` called at 2:8";
+        assert!(error.to_string().ends_with(call_stack), "{error}");
+
+        let input = b"use widgets as w\nfn wrap() { w::broken!() }\nRoot { wrap!() }";
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let error = result.unwrap_err().to_string();
+        let call_stack = "\n    in template `broken!` called at 2:13\
+            \n    in template `wrap!` called at 3:8";
+        assert!(error.contains(call_stack), "{error}");
+
+        let input = b"use other as o\nRoot()";
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("Couldn't import 'other.chirp'"), "{error}");
+
+        // Locations in the default asset source are resolved against the asset root.
+        let root = std::path::PathBuf::from("game").join("assets");
+        let ctx = LoadCtx { asset_root: Some(&root), ..ctx };
+        let input = b"use widgets as w\nRoot { w::broken!() }";
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let error = result.unwrap_err().to_string();
+        let location = format!("(0) {}:3:15: ", root.join("widgets.chirp").display());
+        assert!(error.contains(&location), "{error}");
+    }
+    #[test]
+    fn with_dsl_blocks() {
+        let input = br#"
+Root {
+    with dsl TagDsl {
+        Hud(tag(hud)) {
+            with dsl BaseDsl { Label() }
+        }
+    }
+    Leaf()
+}
+"#;
+        let (mut world, registry) = (World::new(), TypeRegistry::new());
+        let mut handles = Handles::new();
+        let defaults = DslDefaults::<BaseDsl>::default();
+        let mut chirp = ChirpReader::new(&mut world);
+        let sources = &Sources::default();
+        let ctx = LoadCtx { reg: &registry, handles: &handles, asset_root: None };
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("The TagDsl DSL wasn't added"), "{error}");
+
+        handles.add_dsl(DslDefaults::<TagDsl>::default());
+        let ctx = LoadCtx { reg: &registry, handles: &handles, asset_root: None };
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        result.unwrap();
+        let mut tags = world.query::<(&Name, Option<&Tag>)>();
+        let mut tags: Vec<_> = tags.iter(&world).map(|(n, t)| (n.as_str(), t)).collect();
+        tags.sort_by_key(|(name, _)| *name);
+        let hud = Tag("hud".to_owned());
+        let expected = [("Hud", Some(&hud)), ("Label", None), ("Leaf", None), ("Root", None)];
+        assert_eq!(tags, expected);
+
+        let input = b"Root(tag(hud))";
+        let mut chirp = ChirpReader::new(&mut world);
+        let result = chirp.interpret_imports(ctx, &defaults, None, input, sources);
+        assert!(result.is_err());
+    }
+}
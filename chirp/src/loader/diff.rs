@@ -0,0 +1,163 @@
+//! Update spawned chirp scenes in place when their file is hot reloaded.
+//!
+//! When the new scene has the same hierarchy as the spawned one, with the same
+//! [`Name`]s in the same order, scene entities are matched by their position
+//! in the hierarchy. Only the components that changed are replaced, components
+//! the previous scene inserted but the new one doesn't are removed, and
+//! components added at runtime are kept.
+//!
+//! Otherwise, the scene is despawned and spawned again.
+
+use std::any::{self, TypeId};
+
+use bevy::core::Name;
+use bevy::ecs::component::ComponentInfo;
+use bevy::ecs::prelude::*;
+use bevy::ecs::reflect::{ReflectComponent, ReflectMapEntities};
+use bevy::hierarchy::{Children, Parent};
+use bevy::log::trace;
+use bevy::reflect::TypeRegistry;
+use bevy::utils::HashMap;
+
+use super::scene::Error;
+
+/// The position of an entity in the hierarchy of a chirp scene.
+#[derive(Debug, Clone, PartialEq)]
+struct Shape {
+    depth: usize,
+    name: Option<Name>,
+}
+
+/// The entities of the scene at `root`, in depth-first order.
+fn depth_first(world: &World, root: Entity) -> Vec<(Shape, Entity)> {
+    let mut entities = Vec::new();
+    let mut stack = vec![(0, root)];
+    while let Some((depth, entity)) = stack.pop() {
+        let name = world.get::<Name>(entity).cloned();
+        entities.push((Shape { depth, name }, entity));
+        if let Some(children) = world.get::<Children>(entity) {
+            stack.extend(children.iter().rev().map(|child| (depth + 1, *child)));
+        }
+    }
+    entities
+}
+
+/// The components of `entity`, except for the hierarchy components,
+/// which are the same when the scene structure didn't change.
+fn scene_components(world: &World, entity: Entity) -> impl Iterator<Item = &ComponentInfo> {
+    let hierarchy = [TypeId::of::<Parent>(), TypeId::of::<Children>()];
+    let location = world.entities().get(entity).unwrap();
+    let components = world.archetypes()[location.archetype_id].components();
+    let components = components.filter_map(|id| world.components().get_info(id));
+    components.filter(move |info| !matches!(info.type_id(), Some(id) if hierarchy.contains(&id)))
+}
+
+/// The entities of a spawned chirp scene, used to update it in place.
+#[derive(Debug, Default)]
+pub(super) struct SceneTree {
+    shape: Vec<Shape>,
+    /// The spawned entities, in depth-first order, with the components the
+    /// scene inserted on them.
+    entities: Vec<(Entity, Vec<TypeId>)>,
+    /// Components of the root entity before the scene was spawned. The scene
+    /// doesn't overwrite them.
+    preserved: Vec<TypeId>,
+}
+impl SceneTree {
+    pub(super) fn new(
+        source: &World,
+        source_root: Entity,
+        entity_map: &HashMap<Entity, Entity>,
+        preserved: Vec<TypeId>,
+    ) -> Self {
+        let mut tree = Self { preserved, ..Self::default() };
+        for (shape, entity) in depth_first(source, source_root) {
+            let is_preserved = |id: &TypeId| entity == source_root && tree.preserved.contains(id);
+            let components = scene_components(source, entity).filter_map(ComponentInfo::type_id);
+            let components = components.filter(|id| !is_preserved(id)).collect();
+            tree.shape.push(shape);
+            tree.entities.push((entity_map[&entity], components));
+        }
+        tree
+    }
+
+    /// Update the spawned scene to match the scene at `source_root` in `source`.
+    ///
+    /// Returns `None` and leaves `target` untouched if the scene structure
+    /// changed. Otherwise, returns the new map from `source` to `target` entities.
+    pub(super) fn update<D>(
+        &mut self,
+        reg: &TypeRegistry,
+        source: &World,
+        source_root: Entity,
+        target: &mut World,
+    ) -> Result<Option<HashMap<Entity, Entity>>, Error> {
+        let new_entities = depth_first(source, source_root);
+        if !new_entities.iter().map(|(shape, _)| shape).eq(&self.shape) {
+            trace!("The chirp scene structure changed, it can't be updated in place");
+            return Ok(None);
+        }
+        // Check that all components are registered before changing anything.
+        let mut updates = Vec::with_capacity(new_entities.len());
+        for (_, entity) in new_entities {
+            let mut components = Vec::new();
+            for info in scene_components(source, entity) {
+                let dsl = any::type_name::<D>();
+                let unregistered = || Error::UnregisteredType(info.name().into(), dsl);
+                let id = info.type_id().ok_or_else(unregistered)?;
+                if entity == source_root && self.preserved.contains(&id) {
+                    continue;
+                }
+                let reflect = reg.get(id).and_then(|r| r.data::<ReflectComponent>());
+                components.push((id, reflect.ok_or_else(unregistered)?));
+            }
+            updates.push((entity, components));
+        }
+        let mut entity_map = HashMap::default();
+        let mut copied = HashMap::<_, Vec<_>>::default();
+        let updates = updates.into_iter().zip(&mut self.entities);
+        for ((s_entity, components), (w_entity, old)) in updates {
+            let w_entity = *w_entity;
+            entity_map.insert(s_entity, w_entity);
+            for &(id, reflect) in &components {
+                let new = reflect.reflect(source.entity(s_entity));
+                let current = reflect.reflect(target.entity(w_entity));
+                let unchanged = new.zip(current).and_then(|(new, cur)| new.reflect_partial_eq(cur));
+                if unchanged != Some(true) {
+                    reflect.copy(source, target, s_entity, w_entity);
+                    copied.entry(id).or_default().push(w_entity);
+                }
+            }
+            let new_ids: Vec<_> = components.iter().map(|(id, _)| *id).collect();
+            for removed in old.iter().filter(|id| !new_ids.contains(id)) {
+                let reflect = reg.get(*removed).and_then(|r| r.data::<ReflectComponent>());
+                if let Some(reflect) = reflect {
+                    reflect.remove(&mut target.entity_mut(w_entity));
+                }
+            }
+            *old = new_ids;
+        }
+        // Only map entities of copied components, the others already point
+        // to entities of `target`.
+        for (id, entities) in &copied {
+            if let Some(map) = reg.get(*id).and_then(|r| r.data::<ReflectMapEntities>()) {
+                map.map_entities(target, &mut entity_map, entities);
+            }
+        }
+        let copied_count = copied.values().map(Vec::len).sum::<usize>();
+        trace!("Updated {copied_count} components of the chirp scene");
+        Ok(Some(entity_map))
+    }
+    /// Remove from the root entity the components the scene inserted on it.
+    pub(super) fn remove_root_components(&self, reg: &TypeRegistry, world: &mut World) {
+        let Some((root, components)) = self.entities.first() else {
+            return;
+        };
+        let mut root = world.entity_mut(*root);
+        for id in components {
+            if let Some(reflect) = reg.get(*id).and_then(|r| r.data::<ReflectComponent>()) {
+                reflect.remove(&mut root);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Control the lifecycle of chirp instances, see [`ChirpInstances`].
+
+use bevy::asset::Handle;
+use bevy::ecs::{prelude::*, system::SystemParam};
+use thiserror::Error;
+
+use super::spawn::{Chirp, ChirpState};
+
+/// Sent when a chirp instance goes through a [`ChirpState`] transition.
+///
+/// The [`Entity`] is the chirp instance, the entity with the `Handle<Chirp>`.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChirpEvent {
+    /// The scene's entities were spawned, the instance is now [`ChirpState::Loaded`].
+    Loaded(Entity),
+    /// The instance is now [`ChirpState::Hidden`].
+    Hidden(Entity),
+    /// The instance was [`ChirpState::Hidden`] and is now [`ChirpState::Loaded`].
+    Shown(Entity),
+    /// The instance is now [`ChirpState::Loading`], its scene will be spawned
+    /// again, or updated in place if its chirp file changed.
+    Reloading(Entity),
+    /// The instance and the scene's entities were despawned.
+    Despawned(Entity),
+}
+
+/// A [`ChirpInstances`] method couldn't change the state of a chirp instance.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)] // Error messages already good documentation.
+pub enum TransitionError {
+    #[error("{0:?} is not a chirp instance: it has no `ChirpState` or `Handle<Chirp>`")]
+    NotInstance(Entity),
+    #[error("Can't {action} chirp instance {instance:?}, it is {state:?}")]
+    Invalid { instance: Entity, state: ChirpState, action: &'static str },
+}
+
+/// Control the lifecycle of chirp instances, entities with a `Handle<Chirp>`.
+///
+/// Methods of `ChirpInstances` set the [`ChirpState`] of the instance, the change
+/// is applied next time the chirp scene management systems run, in `PostUpdate`.
+/// A [`ChirpEvent`] is sent once it is applied.
+///
+/// | method        | from                           | to                        |
+/// |---------------|--------------------------------|---------------------------|
+/// | [`hide`]      | `Loaded`                       | `Hidden`                  |
+/// | [`show`]      | `Hidden`                       | `Loaded`                  |
+/// | [`respawn`]   | `Loaded`, `Hidden`             | `MustReload` → `Loading`  |
+/// | [`despawn`]   | any                            | `MustDelete` → despawned  |
+///
+/// Calling a method on an instance already in the target state does nothing.
+/// Other transitions fail with a [`TransitionError`].
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_chirp::{Chirp, ChirpInstances, ChirpState};
+///
+/// #[derive(Resource)]
+/// struct Menu(Handle<Chirp>);
+///
+/// fn toggle_menus(menu: Res<Menu>, keys: Res<Input<KeyCode>>, mut instances: ChirpInstances) {
+///     if !keys.just_pressed(KeyCode::Escape) {
+///         return;
+///     }
+///     let menus: Vec<_> = instances.instances_of(&menu.0).collect();
+///     for menu in menus {
+///         let toggled = match instances.state(menu) {
+///             Some(ChirpState::Hidden) => instances.show(menu),
+///             _ => instances.hide(menu),
+///         };
+///         if let Err(err) = toggled {
+///             warn!("{err}");
+///         }
+///     }
+/// }
+/// ```
+///
+/// [`hide`]: Self::hide
+/// [`show`]: Self::show
+/// [`respawn`]: Self::respawn
+/// [`despawn`]: Self::despawn
+#[derive(SystemParam)]
+pub struct ChirpInstances<'w, 's> {
+    instances: Query<'w, 's, (Entity, &'static mut ChirpState, &'static Handle<Chirp>)>,
+}
+impl ChirpInstances<'_, '_> {
+    /// The state of `instance`, `None` if it isn't a chirp instance.
+    #[must_use]
+    pub fn state(&self, instance: Entity) -> Option<ChirpState> {
+        self.instances.get(instance).ok().map(|(_, state, _)| *state)
+    }
+    /// All instances of `chirp`.
+    pub fn instances_of<'a>(
+        &'a self,
+        chirp: &'a Handle<Chirp>,
+    ) -> impl Iterator<Item = Entity> + 'a {
+        let of_chirp = move |(entity, _, handle): (_, _, &Handle<_>)| {
+            (handle == chirp).then_some(entity)
+        };
+        self.instances.iter().filter_map(of_chirp)
+    }
+    fn transition(
+        &mut self,
+        instance: Entity,
+        action: &'static str,
+        to: ChirpState,
+        from: &[ChirpState],
+    ) -> Result<(), TransitionError> {
+        let Ok((_, mut state, _)) = self.instances.get_mut(instance) else {
+            return Err(TransitionError::NotInstance(instance));
+        };
+        if *state != to && !from.contains(&state) {
+            let state = *state;
+            return Err(TransitionError::Invalid { instance, state, action });
+        }
+        state.set_if_neq(to);
+        Ok(())
+    }
+    /// Hide a [`ChirpState::Loaded`] `instance`.
+    ///
+    /// With the `hide` feature, this sets the instance's [`Visibility`] to
+    /// `Hidden`, the scene's entities are kept.
+    ///
+    /// [`Visibility`]: https://docs.rs/bevy/0.12/bevy/render/view/enum.Visibility.html
+    ///
+    /// # Errors
+    /// When `instance` is not `Loaded`.
+    pub fn hide(&mut self, instance: Entity) -> Result<(), TransitionError> {
+        self.transition(instance, "hide", ChirpState::Hidden, &[ChirpState::Loaded])
+    }
+    /// Show a [`ChirpState::Hidden`] `instance`.
+    ///
+    /// With the `hide` feature, this sets the instance's [`Visibility`] to
+    /// `Inherited`.
+    ///
+    /// [`Visibility`]: https://docs.rs/bevy/0.12/bevy/render/view/enum.Visibility.html
+    ///
+    /// # Errors
+    /// When `instance` is not `Hidden`.
+    pub fn show(&mut self, instance: Entity) -> Result<(), TransitionError> {
+        self.transition(instance, "show", ChirpState::Loaded, &[ChirpState::Hidden])
+    }
+    /// Despawn the scene's entities of `instance` and spawn them again.
+    ///
+    /// A hidden instance is shown again once respawned.
+    ///
+    /// # Errors
+    /// When `instance` is not `Loaded` or `Hidden`. A `Loading` instance
+    /// will be spawned anyway, so this is a no-op.
+    pub fn respawn(&mut self, instance: Entity) -> Result<(), TransitionError> {
+        let (to, from) = (ChirpState::MustReload, [ChirpState::Loaded, ChirpState::Hidden]);
+        match self.state(instance) {
+            Some(ChirpState::Loading) => Ok(()),
+            _ => self.transition(instance, "respawn", to, &from),
+        }
+    }
+    /// Despawn `instance` and the scene's entities.
+    ///
+    /// # Errors
+    /// When `instance` is not a chirp instance.
+    pub fn despawn(&mut self, instance: Entity) -> Result<(), TransitionError> {
+        use ChirpState::{Hidden, Loaded, Loading, MustReload};
+        let from = [Loading, Loaded, Hidden, MustReload];
+        self.transition(instance, "despawn", ChirpState::MustDelete, &from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+    use ChirpState::{Hidden, Loaded, Loading, MustDelete, MustReload};
+
+    #[test]
+    fn transitions() {
+        let mut world = World::new();
+        let loaded = world.spawn((Loaded, Handle::<Chirp>::default())).id();
+        let loading = world.spawn((Loading, Handle::<Chirp>::default())).id();
+        let not_chirp = world.spawn(Loaded).id();
+
+        let mut state = SystemState::<ChirpInstances>::new(&mut world);
+        let mut instances = state.get_mut(&mut world);
+        let invalid =
+            |instance, state, action| TransitionError::Invalid { instance, state, action };
+
+        assert_eq!(instances.show(loaded), Ok(()));
+        assert_eq!(instances.hide(loaded), Ok(()));
+        assert_eq!(instances.hide(loaded), Ok(()));
+        assert_eq!(instances.state(loaded), Some(Hidden));
+        assert_eq!(instances.respawn(loaded), Ok(()));
+        assert_eq!(instances.state(loaded), Some(MustReload));
+
+        assert_eq!(instances.hide(loading), Err(invalid(loading, Loading, "hide")));
+        assert_eq!(instances.show(loading), Err(invalid(loading, Loading, "show")));
+        assert_eq!(instances.respawn(loading), Ok(()));
+        assert_eq!(instances.despawn(loading), Ok(()));
+        assert_eq!(instances.respawn(loading), Err(invalid(loading, MustDelete, "respawn")));
+
+        assert_eq!(instances.hide(not_chirp), Err(TransitionError::NotInstance(not_chirp)));
+        assert_eq!(instances.instances_of(&Handle::default()).count(), 2);
+    }
+}
@@ -1,40 +1,59 @@
-use std::marker::PhantomData;
+use std::path::Path;
 
 use bevy::asset::LoadContext;
 use bevy::ecs::prelude::*;
 use bevy::reflect::TypeRegistry;
 use bevy::scene::Scene;
+use cuicui_dsl::DslDefaults;
+use miette::Severity;
 
-use super::spawn::Chirp_;
-use crate::{interpret, ChirpReader, Handles, ParseDsl};
+use super::spawn::{Chirp, Chirp_};
+use crate::interpret::{self, LoadCtx, LoadStats, Sources};
+use crate::{report, ChirpReader, Handles, ParseDsl};
 
 pub(super) struct Loader<'a, 'r, 'w, 'h, D> {
     ctx: &'a mut LoadContext<'w>,
     registry: &'r TypeRegistry,
     handles: &'h Handles,
-    _dsl: PhantomData<fn(D)>,
+    defaults: &'h DslDefaults<D>,
+    asset_root: Option<&'h Path>,
 }
 
 impl<'a, 'r, 'w, 'h, D: ParseDsl + 'static> Loader<'a, 'r, 'w, 'h, D> {
-    pub(super) fn new(ctx: &'a mut LoadContext<'w>, reg: &'r TypeRegistry, h: &'h Handles) -> Self {
-        Self { ctx, registry: reg, handles: h, _dsl: PhantomData }
+    pub(super) fn new(
+        ctx: &'a mut LoadContext<'w>,
+        registry: &'r TypeRegistry,
+        handles: &'h Handles,
+        defaults: &'h DslDefaults<D>,
+        asset_root: Option<&'h Path>,
+    ) -> Self {
+        Self { ctx, registry, handles, defaults, asset_root }
     }
 
-    pub(super) fn load(&mut self, file: &[u8]) -> Chirp_ {
-        match self.load_scene(file) {
-            Ok((root, scene)) => {
-                Chirp_::Loaded(root, self.ctx.add_labeled_asset("Scene".to_owned(), scene))
+    pub(super) fn load(&mut self, file: &[u8], sources: &Sources) -> Chirp {
+        let (chirp, stats) = match self.load_scene(file, sources) {
+            Ok((root, scene, stats)) => {
+                let scene = self.ctx.add_labeled_asset("Scene".to_owned(), scene);
+                (Chirp_::Loaded(root, scene), stats)
             }
             Err(errors) => {
-                log_miette_error!(&errors);
-                Chirp_::Error(errors)
+                report::report(&errors, Severity::Error);
+                (Chirp_::Error(errors), LoadStats::default())
             }
-        }
+        };
+        Chirp(chirp, stats)
     }
-    fn load_scene(&mut self, file: &[u8]) -> Result<(Entity, Scene), interpret::Errors> {
+    fn load_scene(
+        &mut self,
+        file: &[u8],
+        sources: &Sources,
+    ) -> Result<(Entity, Scene, LoadStats), interpret::Errors> {
         let mut world = World::new();
         let mut chirp = ChirpReader::new(&mut world);
-        let result = chirp.interpret::<D>(self.handles, Some(self.ctx), self.registry, file);
-        result.map(|root| (root, Scene::new(world)))
+        let (handles, reg, asset_root) = (self.handles, self.registry, self.asset_root);
+        let ctx = LoadCtx { reg, handles, asset_root };
+        let load_ctx = Some(&mut *self.ctx);
+        let result = chirp.interpret_imports(ctx, self.defaults, load_ctx, file, sources);
+        result.map(|(root, stats)| (root, Scene::new(world), stats))
     }
 }
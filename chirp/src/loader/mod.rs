@@ -8,6 +8,37 @@
 //! The [`crate::loader::Plugin`] defined in this module adds `ChirpLoader` as
 //! an asset loader. Any [`Entity`] with a `Handle<Chirp>` **will be replaced**
 //! by several entities, the one at the root of the `.chirp` file.
+//!
+//! # Concurrent loading
+//!
+//! Bevy runs asset loaders in parallel, so several `.chirp` files may be loaded
+//! at the same time. When it starts loading a file, [`ChirpLoader`] takes
+//! a snapshot of the [`WorldHandles`]. This has two consequences:
+//!
+//! - Adding functions or migrations to `WorldHandles` never waits for files
+//!   to finish loading, and never causes a load to fail.
+//! - Files that started loading before a function was added do not see it.
+//!   Make sure to add your functions before loading the files that use them,
+//!   for example in a `Startup` system.
+//!
+//! # Default methods
+//!
+//! Each statement of a chirp file starts with the defaults of the
+//! [`DslDefaults<D>`] resource applied. Unlike functions, defaults are not
+//! snapshotted, loads started after adding a default use it.
+//!
+//! # Hot reloading
+//!
+//! [`ChirpLoader`] loads the files imported with `use` statements alongside
+//! the file importing them. When an imported file changes, all files importing
+//! it, directly or not, are reloaded, and so are their spawned instances.
+//!
+//! When a chirp file changes, its spawned instances are updated in place if
+//! the new scene has the same entities as the old one: the same hierarchy, with
+//! the same entity names in the same order. Only the components that changed
+//! are replaced, and components added at runtime to the scene's entities are
+//! kept. Otherwise, the scene's entities are despawned and spawned again.
+//! [`ChirpInstances::respawn`] always despawns and spawns the scene again.
 
 // ## Jargon
 //
@@ -22,31 +53,47 @@
 //
 // 1. `update_asset_changed`: Reacts to asset event and orders reloading of spawned
 //    chirp scenes. Note that it is more powerful than the `Scene` system, as it actually
-//    works with hot reloading.
-// 2. `update_marked`: Reacts to chirp instances changed through the [`ChirpState`] component.
+//    works with hot reloading. Files imported with `use` are loader dependencies
+//    of the files importing them, so bevy reloads the importing files when
+//    they change, which in turn reloads their instances.
+// 2. `manage_chirp_state`: Reacts to chirp instances changed through the [`ChirpState`]
+//    component, usually with `ChirpInstances`.
 // 3. `consume_seeds`: Reacts to `Entity` spawned with a `Handle<Chirp>`, request
 //    to `SceneSpawner` that the chirp's scene be loaded into the world, add
 //    the instance's metadata to [`ChirpInstances`], and when loading is completed,
 //    re-parent & add chirp metadata to spawned scene entities.
 
-use std::{any::type_name, marker::PhantomData, sync::Arc, sync::RwLock, sync::TryLockError};
+use std::path::Path;
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use std::{any::type_name, borrow::Cow, marker::PhantomData};
 
 use anyhow::Result;
 use bevy::app::{App, Plugin as BevyPlugin, PostUpdate};
-use bevy::asset::{prelude::*, AssetLoader, AsyncReadExt, LoadContext};
-use bevy::ecs::{prelude::*, schedule::ScheduleLabel, system::EntityCommands};
-use bevy::log::{error, info};
-use bevy::reflect::{TypeRegistry, TypeRegistryArc};
+use bevy::asset::{prelude::*, AssetLoader, AssetPath, AsyncReadExt, LoadContext};
+use bevy::ecs::{prelude::*, reflect::ReflectComponent, schedule::ScheduleLabel};
+use bevy::ecs::system::EntityCommands;
+use bevy::log::{error, info, warn};
+use bevy::reflect::{Reflect, TypeRegistry, TypeRegistryArc};
 use bevy::scene::scene_spawner_system;
 use bevy::transform::TransformSystem;
-use bevy::utils::get_short_name;
+use bevy::utils::{get_short_name, HashMap};
+use cuicui_dsl::DslDefaults;
 use thiserror::Error;
 
+use crate::interpret::{CodeFunctionArc, LoadStats, Sources};
+use crate::parse_dsl::Arguments;
 use crate::{Handles, ParseDsl};
 
+#[cfg(feature = "asset_processor")]
+pub use process::{ChirpSaver, ProcessError, ProcessedChirpLoader};
+pub use instances::{ChirpEvent, ChirpInstances, TransitionError};
 pub use spawn::{Chirp, ChirpState};
 
+mod diff;
+mod instances;
 mod internal;
+#[cfg(feature = "asset_processor")]
+pub mod process;
 mod scene;
 pub(super) mod spawn;
 
@@ -57,26 +104,41 @@ pub(super) mod spawn;
 #[derive(Debug, Error)]
 #[allow(missing_docs)] // Error messages already good documentation.
 pub enum AddError {
-    #[error("Failed to set function '{0}' in chirp handle registry: Lock poisoned")]
+    #[error("Failed to add {0} to chirp handle registry: Lock poisoned")]
     Poisoned(String),
-    #[error("Failed to set function '{0}' in chirp handle registry: Lock already taken")]
-    WouldBlock(String),
 }
 
 /// Components necessary to load chirp files.
+///
+/// This requires adding the loader [`Plugin`] for the DSL of the chirp file.
+/// Without it, an error is logged when spawning the bundle, see
+/// [`check_loader_plugin`].
 #[derive(Bundle)]
 pub struct ChirpBundle {
     /// The load state of the chirp file.
     pub state: ChirpState,
     /// The chirp scene.
     pub scene: Handle<Chirp>,
+    /// The DSL the chirp scene is written for.
+    pub dsl: ChirpDsl,
 }
 impl ChirpBundle {
     /// Load a new chirp scene.
     #[must_use]
     #[allow(clippy::missing_const_for_fn)]
     pub fn new(scene: Handle<Chirp>) -> Self {
-        Self { state: ChirpState::Loading, scene }
+        Self { state: ChirpState::Loading, scene, dsl: ChirpDsl::default() }
+    }
+    /// Load a new chirp scene written for the `D` DSL.
+    ///
+    /// If the loader [`Plugin`] isn't for `D`, an error is logged when
+    /// spawning the bundle, rather than an error per unknown method when
+    /// loading the file.
+    ///
+    /// See also the [`dsl` hint](crate#dsl-hint) of chirp files.
+    #[must_use]
+    pub fn for_dsl<D: ParseDsl>(scene: Handle<Chirp>) -> Self {
+        Self { state: ChirpState::Loading, scene, dsl: ChirpDsl(Some(type_name::<D>().into())) }
     }
 }
 impl From<Handle<Chirp>> for ChirpBundle {
@@ -85,53 +147,168 @@ impl From<Handle<Chirp>> for ChirpBundle {
     }
 }
 
+/// The DSL a chirp instance is written for, see [`ChirpBundle::for_dsl`].
+///
+/// `None` when it can be loaded with any DSL.
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct ChirpDsl(pub Option<Cow<'static, str>>);
+
 /// Global [`ChirpLoader`] handle registry. Used in the `code` statements of the
 /// chirp language.
 #[derive(Resource)]
 pub struct WorldHandles<D>(pub(crate) HandlesArc, PhantomData<fn(D)>);
-type HandlesArc = Arc<RwLock<Handles>>;
+/// Loaders clone the inner `Arc<Handles>` and release the lock immediately,
+/// writers replace it with an updated copy, see [`Arc::make_mut`].
+type HandlesArc = Arc<RwLock<Arc<Handles>>>;
 
 impl<D> WorldHandles<D> {
+    fn write(
+        &self,
+        name: impl FnOnce() -> String,
+    ) -> Result<RwLockWriteGuard<'_, Arc<Handles>>, AddError> {
+        self.0.write().map_err(|_| AddError::Poisoned(name()))
+    }
     /// Associate `name` with `function` in `chirp` code statements.
     ///
     /// `function` may be called from a `chirp` file from a `code` statement if
-    /// `name` is passed as argument.
+    /// `name` is passed as argument. Further arguments of the `code` statement
+    /// are passed to `function`, see [`CodeFunctionArc`].
+    ///
+    /// Files already loading do not see the new function, see the
+    /// [module-level documentation](self#concurrent-loading).
     ///
     /// # Errors
-    /// - When this operation would otherwise block (ie: a chirp file is loading)
-    /// - When some other lock panicked.
+    /// When some other lock panicked.
     pub fn add_function(
         &mut self,
         name: String,
-        function: impl Fn(&TypeRegistry, Option<&LoadContext>, &mut EntityCommands)
+        function: impl Fn(&TypeRegistry, Option<&LoadContext>, &Arguments, &mut EntityCommands)
             + Send
             + Sync
             + 'static,
     ) -> Result<(), AddError> {
-        let mut handles = self.0.try_write().map_err(|err| match err {
-            TryLockError::Poisoned(_) => AddError::Poisoned(name.clone()),
-            TryLockError::WouldBlock => AddError::WouldBlock(name.clone()),
-        })?;
-        handles.add_function(name, function);
+        let mut handles = self.write(|| format!("function '{name}'"))?;
+        Arc::make_mut(&mut handles).add_function(name, function);
+        drop(handles);
+        Ok(())
+    }
+    /// Associate each name in `functions` with its function, see
+    /// [`WorldHandles::add_function`].
+    ///
+    /// This is equivalent to calling `add_function` for each function,
+    /// but locks the registry only once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use bevy::prelude::*;
+    /// use cuicui_chirp::{interpret::CodeFunctionArc, WorldHandles};
+    /// # type MyDsl = cuicui_dsl::BaseDsl;
+    ///
+    /// fn register_functions(mut handles: ResMut<WorldHandles<MyDsl>>) {
+    ///     let hello: CodeFunctionArc = Arc::new(|_, _, _, cmds| {
+    ///         cmds.insert(Name::new("Hello"));
+    ///     });
+    ///     let world: CodeFunctionArc = Arc::new(|_, _, _, cmds| {
+    ///         cmds.insert(Name::new("World"));
+    ///     });
+    ///     let functions = [("hello".to_owned(), hello), ("world".to_owned(), world)];
+    ///     handles.with_functions(functions).unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// When some other lock panicked.
+    pub fn with_functions(
+        &mut self,
+        functions: impl IntoIterator<Item = (String, CodeFunctionArc)>,
+    ) -> Result<(), AddError> {
+        let mut handles = self.write(|| "functions".to_owned())?;
+        Arc::make_mut(&mut handles).extend(functions);
+        drop(handles);
+        Ok(())
+    }
+    /// Allow `with dsl D { statements }` blocks in chirp files, see
+    /// [`Handles::add_dsl`].
+    ///
+    /// Files already loading do not see the new DSL, see the
+    /// [module-level documentation](self#concurrent-loading).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// use cuicui_chirp::WorldHandles;
+    /// use cuicui_dsl::DslDefaults;
+    /// # type UiDsl = cuicui_dsl::BaseDsl;
+    /// # type SpriteDsl = cuicui_dsl::BaseDsl;
+    ///
+    /// fn add_sprite_dsl(mut handles: ResMut<WorldHandles<UiDsl>>) {
+    ///     handles.add_dsl(DslDefaults::<SpriteDsl>::default()).unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// When some other lock panicked.
+    pub fn add_dsl<T: ParseDsl + 'static>(
+        &mut self,
+        defaults: DslDefaults<T>,
+    ) -> Result<(), AddError> {
+        let mut handles = self.write(|| format!("DSL {}", type_name::<T>()))?;
+        Arc::make_mut(&mut handles).add_dsl(defaults);
+        drop(handles);
+        Ok(())
+    }
+    /// Run `migration` on chirp files of version `version` or lower before
+    /// loading them.
+    ///
+    /// See the [`crate::migrate`] module for details.
+    ///
+    /// # Errors
+    /// When some other lock panicked.
+    pub fn add_migration(
+        &mut self,
+        version: u32,
+        migration: impl for<'a> Fn(&'a [u8]) -> Cow<'a, [u8]> + Send + Sync + 'static,
+    ) -> Result<(), AddError> {
+        let mut handles = self.write(|| format!("migration {version}"))?;
+        Arc::make_mut(&mut handles).add_migration(version, migration);
         drop(handles);
         Ok(())
     }
 }
 
+/// The [`LoadStats`] of each chirp file, by asset path.
+///
+/// Updated each time a chirp file is loaded or reloaded, use it to track how
+/// costly your UI files are to load and spot pathological ones. The stats of
+/// each file are also logged at the `info` level when it completes loading.
+///
+/// Files that failed to load are not included.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct ChirpStats(pub HashMap<AssetPath<'static>, LoadStats>);
+
 /// Loads a bevy [`Scene`] declared in a `chirp` file.
 ///
 /// [`Scene`]: bevy::scene::Scene
 pub struct ChirpLoader<D> {
     registry: TypeRegistryArc,
     handles: HandlesArc,
-    _dsl: PhantomData<fn(D)>,
+    /// Shared with the world's `DslDefaults<D>` resource.
+    defaults: DslDefaults<D>,
+    /// The directory of the default asset source, see [`AssetRoot`].
+    asset_root: Option<Arc<Path>>,
 }
 impl<D: 'static> FromWorld for ChirpLoader<D> {
     fn from_world(world: &mut World) -> Self {
         let registry = world.resource::<AppTypeRegistry>().0.clone();
         let handles = HandlesArc::default();
         world.insert_resource(WorldHandles::<D>(Arc::clone(&handles), PhantomData));
-        Self { registry, handles, _dsl: PhantomData }
+        let defaults = world.get_resource_or_insert_with(DslDefaults::<D>::default).clone();
+        let asset_root = world.get_resource::<AssetRoot>().map(|root| Arc::clone(&root.0));
+        Self { registry, handles, defaults, asset_root }
     }
 }
 
@@ -149,17 +326,21 @@ impl<D: ParseDsl + 'static> AssetLoader for ChirpLoader<D> {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
-            let registry = self.registry.internal.read().unwrap();
-            let Ok(handles) = self.handles.as_ref().read() else {
+            let handles = Arc::clone(&self.handles.read().unwrap_or_else(|poisoned| {
                 let name = get_short_name(type_name::<D>());
-                error!("Can't read handles in ChirpLoader<{name}>");
-                return Ok(Chirp(spawn::Chirp_::LoadError));
-            };
-            let chirp = internal::Loader::<D>::new(load_context, &registry, &handles).load(&bytes);
+                warn!("A thread panicked while updating WorldHandles<{name}>, using it anyway");
+                poisoned.into_inner()
+            }));
+            let sources = Sources::read(load_context, &handles, &handles.migrate(&bytes)).await;
+            let registry = self.registry.internal.read().unwrap();
+            let (defaults, root) = (&self.defaults, self.asset_root.as_deref());
+            let ctx = &mut *load_context;
+            let mut loader = internal::Loader::new(ctx, &registry, &handles, defaults, root);
+            let chirp = loader.load(&bytes, &sources);
             drop(registry);
             let path = load_context.path().to_string_lossy();
-            info!("Complete loading of chirp: {path}");
-            Ok(Chirp(chirp))
+            info!("Complete loading of chirp: {path} ({})", chirp.stats());
+            Ok(chirp)
         })
     }
 
@@ -173,8 +354,29 @@ impl<D: ParseDsl + 'static> AssetLoader for ChirpLoader<D> {
 /// The loader is specific to the DSL. This is what the `D` is here for.
 ///
 /// Hot reloading should work out of the box.
+///
+/// # Duplicate plugins
+///
+/// Chirp files can only be loaded with a single DSL. Adding this plugin twice
+/// logs a warning, and the second plugin is ignored. Adding it for several
+/// DSLs logs an error, and chirp files are loaded with the DSL of the first
+/// plugin. Use [`WorldHandles::add_dsl`] to write parts of chirp files for
+/// other DSLs.
+///
+/// Note that `cuicui_layout`'s `add_cuicui` already adds this plugin when
+/// `chirp` is `true`.
 pub struct Plugin<D>(PhantomData<fn(D)>);
 
+/// The DSL of the [`Plugin`] added to the app.
+#[derive(Resource)]
+struct LoaderDsl(&'static str);
+
+/// The directory of the default asset source, read by the [`ChirpLoader`] to
+/// report error locations relative to it, so that they are clickable in
+/// terminals and editors.
+#[derive(Resource)]
+struct AssetRoot(Arc<Path>);
+
 /// The `SpawnChirp` schedule spawns chirp scenes between `Update` and `PostUpdate`.
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SpawnChirp;
@@ -190,8 +392,31 @@ impl Plugin<()> {
 }
 impl<D: ParseDsl + 'static> BevyPlugin for Plugin<D> {
     fn build(&self, app: &mut App) {
+        let dsl = type_name::<D>();
+        if let Some(&LoaderDsl(previous)) = app.world.get_resource::<LoaderDsl>() {
+            let short = get_short_name(dsl);
+            if previous == dsl {
+                warn!(
+                    "cuicui_chirp::loader::Plugin::<{short}> was added twice, ignoring \
+                    the second one. Note that `add_cuicui` already adds it when \
+                    `chirp` is true.",
+                );
+            } else {
+                error!(
+                    "Chirp loader plugins were added for several DSLs: {} and {short}. \
+                    Chirp files can only be loaded with a single DSL, they are loaded \
+                    with {0}. Keep only one cuicui_chirp::loader::Plugin, and add the \
+                    other DSLs with WorldHandles::add_dsl.",
+                    get_short_name(previous),
+                );
+            }
+            return;
+        }
+        app.insert_resource(LoaderDsl(dsl));
+
         // TODO(perf): Run-condition to avoid useless apply_deferred
         let chirp_asset_systems = (
+            spawn::check_dsl::<D>,
             spawn::update_asset_changed,
             spawn::manage_chirp_state,
             spawn::spawn_chirps::<D>,
@@ -206,9 +431,107 @@ impl<D: ParseDsl + 'static> BevyPlugin for Plugin<D> {
             .before(bevy::ui::UiSystem::Layout)
             .before(bevy::ui::UiSystem::Focus)
             .before(bevy::ui::UiSystem::Stack);
-        app.add_systems(PostUpdate, chirp_asset_systems);
+        app.add_systems(PostUpdate, (chirp_asset_systems, spawn::record_stats));
         app.init_asset::<Chirp>()
-            .register_type::<ChirpState>()
-            .init_asset_loader::<ChirpLoader<D>>();
+            .init_resource::<ChirpStats>()
+            .add_event::<ChirpEvent>()
+            .register_type::<ChirpDsl>()
+            .register_type::<ChirpState>();
+        insert_asset_root(app);
+        app.init_asset_loader::<ChirpLoader<D>>();
+
+        #[cfg(feature = "asset_processor")]
+        app.init_asset_loader::<ProcessedChirpLoader>();
+    }
+    // Duplicate plugins are detected in `build`, with a more helpful message.
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+/// Insert the [`AssetRoot`] of the `AssetPlugin`, if added.
+#[cfg(not(target_arch = "wasm32"))]
+fn insert_asset_root(app: &mut App) {
+    use bevy::asset::io::file::FileAssetReader;
+
+    let Some(asset_plugin) = app.get_added_plugins::<AssetPlugin>().first().copied() else {
+        return;
+    };
+    let root = FileAssetReader::get_base_path().join(&asset_plugin.file_path);
+    app.insert_resource(AssetRoot(root.into()));
+}
+#[cfg(target_arch = "wasm32")]
+fn insert_asset_root(_: &mut App) {}
+
+/// Report chirp instances spawned without a loader [`Plugin`].
+///
+/// Call this in the [`BevyPlugin::finish`] of plugins using chirp files, once
+/// all plugins are built. When no loader `Plugin` was added, this initializes
+/// the [`Chirp`] asset, so that loading a chirp file doesn't panic, and logs an
+/// error explaining how to add the loader for each spawned [`ChirpBundle`].
+///
+/// This does nothing without bevy's `AssetPlugin`. `cuicui_layout::Plugin`
+/// already calls this.
+pub fn check_loader_plugin(app: &mut App) {
+    let has_server = app.world.contains_resource::<AssetServer>();
+    if !has_server || app.world.contains_resource::<Assets<Chirp>>() {
+        return;
+    }
+    app.init_asset::<Chirp>().add_systems(PostUpdate, spawn::missing_loader);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{AssetPlugin, HierarchyPlugin, MinimalPlugins};
+    use bevy::scene::Scene;
+    use cuicui_dsl::{BaseDsl, DslBundle};
+
+    use super::*;
+    use crate::parse_dsl::MethodCtx;
+
+    #[derive(Default)]
+    struct OtherDsl(BaseDsl);
+    impl DslBundle for OtherDsl {
+        fn insert(&mut self, cmds: &mut EntityCommands) {
+            self.0.insert(cmds);
+        }
+    }
+    impl ParseDsl for OtherDsl {
+        fn method(&mut self, ctx: MethodCtx) -> anyhow::Result<()> {
+            self.0.method(ctx)
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), HierarchyPlugin));
+        app.init_asset::<Scene>();
+        app
+    }
+    fn loader_dsl(app: &App) -> &'static str {
+        app.world.resource::<LoaderDsl>().0
+    }
+
+    #[test]
+    fn duplicate_plugins() {
+        let mut app = app();
+        app.add_plugins(Plugin::new::<BaseDsl>());
+        app.add_plugins(Plugin::new::<BaseDsl>());
+        app.add_plugins(Plugin::new::<OtherDsl>());
+        assert_eq!(loader_dsl(&app), type_name::<BaseDsl>());
+
+        check_loader_plugin(&mut app);
+        app.update();
+    }
+    #[test]
+    fn missing_plugin() {
+        let mut app = app();
+        check_loader_plugin(&mut app);
+        // Loading a chirp file used to panic, since the `Chirp` asset wasn't initialized.
+        let scene = app.world.resource::<AssetServer>().load("menu.chirp");
+        let instance = app.world.spawn(ChirpBundle::for_dsl::<BaseDsl>(scene)).id();
+        app.update();
+        assert_eq!(app.world.get::<ChirpState>(instance), Some(&ChirpState::Loading));
+        assert!(!app.world.contains_resource::<LoaderDsl>());
     }
 }
@@ -0,0 +1,475 @@
+//! Pre-process `.chirp` files at build time with the bevy asset processor.
+//!
+//! Interpreting a `.chirp` file requires parsing it, calling [`ParseDsl`] methods
+//! and running `code` statements. This can be done once, ahead of time, with
+//! bevy's asset processor. The processed file only contains the resulting
+//! scene, serialized through [`Reflect`].
+//!
+//! # Processing pipeline
+//!
+//! 1. The `bevy` asset processor reads the source `.chirp` file with the
+//!    regular [`ChirpLoader`].
+//! 2. [`ChirpSaver`] serializes the loaded [`Chirp`]'s scene into the processed
+//!    asset directory (`imported_assets/` by default).
+//! 3. A `.meta` file is written next to it, telling bevy to use
+//!    [`ProcessedChirpLoader`] to load the processed file.
+//! 4. At run time, the [`ProcessedChirpLoader`] reads the scene as-is,
+//!    no chirp parsing or interpretation happens.
+//!
+//! # Example
+//!
+//! Enable the `asset_processor` cargo feature on `cuicui_chirp` and set the
+//! asset mode to [`AssetMode::Processed`]:
+//!
+//! ```rust,no_run
+//! use bevy::asset::{processor::LoadAndSave, AssetMode};
+//! use bevy::prelude::*;
+//! use cuicui_chirp::loader::{ChirpLoader, ChirpSaver, Plugin as ChirpPlugin};
+//! # type MyDsl = cuicui_dsl::BaseDsl;
+//!
+//! type ChirpProcessor = LoadAndSave<ChirpLoader<MyDsl>, ChirpSaver>;
+//!
+//! fn main() {
+//!     let mut app = App::new();
+//!     app.add_plugins(DefaultPlugins.set(AssetPlugin {
+//!         mode: AssetMode::Processed,
+//!         ..default()
+//!     }))
+//!     .add_plugins(ChirpPlugin::new::<MyDsl>());
+//!
+//!     let saver = ChirpSaver::from_world(&mut app.world);
+//!     app.register_asset_processor(ChirpProcessor::from(saver))
+//!         .set_default_asset_processor::<ChirpProcessor>("chirp")
+//!         .run();
+//! }
+//! ```
+//!
+//! Asset handles can't be serialized, so the processed file stores the path of
+//! each handle in the scene, and [`ProcessedChirpLoader`] loads them again.
+//! Only handles to assets `cuicui_chirp` knows how to load are supported:
+//! `Handle<Image>` with the `load_image` feature and `Handle<Font>` with the
+//! `load_font` feature.
+//!
+//! Note that `code` statements are run at processing time. This means that
+//! [`WorldHandles`] functions must be registered before the processor runs,
+//! and that their effect is saved in the processed scene.
+//!
+//! [`AssetMode::Processed`]: bevy::asset::AssetMode::Processed
+//! [`ParseDsl`]: crate::ParseDsl
+//! [`Reflect`]: bevy::reflect::Reflect
+//! [`WorldHandles`]: super::WorldHandles
+use std::any::TypeId;
+use std::fmt::{self, Write};
+
+use bevy::asset::io::{Reader, Writer};
+use bevy::asset::saver::{AssetSaver, SavedAsset};
+use bevy::asset::{AssetIndex, ReflectHandle, UntypedHandle};
+use bevy::asset::{AssetLoader, AsyncReadExt, AsyncWriteExt, LoadContext};
+use bevy::ecs::{prelude::*, reflect::ReflectComponent};
+use bevy::log::info;
+use bevy::reflect::{GetPath, Reflect, ReflectMut, TypeRegistry};
+use bevy::scene::{serde::SceneDeserializer, DynamicScene, Scene};
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::de::DeserializeSeed;
+use thiserror::Error;
+
+use super::spawn::{Chirp, Chirp_};
+#[cfg(doc)]
+use super::ChirpLoader;
+use crate::interpret::LoadStats;
+
+const U64_SIZE: usize = std::mem::size_of::<u64>();
+
+/// Loads the asset at the given path as a `Box<Handle<A>>`.
+type LoadHandle = fn(&mut LoadContext, String) -> Box<dyn Reflect>;
+/// An asset handle of the saved scene: `(entity, component, field, asset path)`.
+type SavedHandle = (u64, String, String, String);
+
+#[cfg(any(feature = "load_image", feature = "load_font"))]
+fn load_handle<A: bevy::asset::Asset>(ctx: &mut LoadContext, path: String) -> Box<dyn Reflect> {
+    Box::new(ctx.load::<A>(path))
+}
+/// The handles that can be saved, keyed by the `TypeId` of `Handle<A>`.
+#[allow(unused_mut)] // When neither `load_image` nor `load_font` is enabled
+fn handle_loaders() -> HashMap<TypeId, LoadHandle> {
+    let mut loaders = HashMap::<_, LoadHandle>::default();
+    #[cfg(feature = "load_image")]
+    {
+        use bevy::{asset::Handle, render::texture::Image};
+        loaders.insert(TypeId::of::<Handle<Image>>(), load_handle::<Image>);
+    }
+    #[cfg(feature = "load_font")]
+    {
+        use bevy::{asset::Handle, text::Font};
+        loaders.insert(TypeId::of::<Handle<Font>>(), load_handle::<Font>);
+    }
+    loaders
+}
+
+/// Call `f` on each asset handle in `value`, with its path relative to `value`.
+fn visit_handles(
+    reg: &TypeRegistry,
+    value: &mut dyn Reflect,
+    path: &mut String,
+    f: &mut dyn FnMut(&str, &ReflectHandle, &mut dyn Reflect) -> Result<(), ProcessError>,
+) -> Result<(), ProcessError> {
+    if let Some(reflect_handle) = reg.get_type_data::<ReflectHandle>(value.as_any().type_id()) {
+        return f(path, reflect_handle, value);
+    }
+    let mut field = |access: fmt::Arguments, field: Option<&mut dyn Reflect>| {
+        let len = path.len();
+        write!(path, "{access}").unwrap();
+        let result = field.map_or(Ok(()), |field| visit_handles(reg, field, path, f));
+        path.truncate(len);
+        result
+    };
+    match value.reflect_mut() {
+        ReflectMut::Struct(value) => (0..value.field_len())
+            .try_for_each(|i| field(format_args!("#{i}"), value.field_at_mut(i))),
+        ReflectMut::TupleStruct(value) => {
+            (0..value.field_len()).try_for_each(|i| field(format_args!(".{i}"), value.field_mut(i)))
+        }
+        ReflectMut::Tuple(value) => {
+            (0..value.field_len()).try_for_each(|i| field(format_args!(".{i}"), value.field_mut(i)))
+        }
+        ReflectMut::List(value) => {
+            (0..value.len()).try_for_each(|i| field(format_args!("[{i}]"), value.get_mut(i)))
+        }
+        ReflectMut::Array(value) => {
+            (0..value.len()).try_for_each(|i| field(format_args!("[{i}]"), value.get_mut(i)))
+        }
+        ReflectMut::Enum(value) => {
+            let tuple = value.variant_type() == bevy::reflect::VariantType::Tuple;
+            (0..value.field_len()).try_for_each(|i| match tuple {
+                true => field(format_args!(".{i}"), value.field_at_mut(i)),
+                false => field(format_args!("#{i}"), value.field_at_mut(i)),
+            })
+        }
+        ReflectMut::Map(_) | ReflectMut::Value(_) => Ok(()),
+    }
+}
+
+/// Errors occuring when saving or loading a processed [`Chirp`].
+#[derive(Debug, Error)]
+#[allow(missing_docs)] // Error messages already good documentation.
+pub enum ProcessError {
+    #[error("Can't save a chirp file that failed to load")]
+    NotLoaded,
+    #[error("The chirp file has no scene, this is a cuicui_chirp bug")]
+    NoScene,
+    #[error("The processed chirp file is too short to contain a root entity")]
+    NoRoot,
+    #[error("The processed chirp root entity doesn't exist in the scene")]
+    BadRoot,
+    #[error("The processed chirp file is truncated")]
+    Truncated,
+    #[error(
+        "A `{0}` component holds a handle to an asset that wasn't loaded from \
+        a file, processed chirp files can only refer to assets with a path"
+    )]
+    UnsavedHandle(String),
+    #[error(
+        "Processed chirp files can't contain `{0}`, only `Handle<Image>` with the \
+        `load_image` feature and `Handle<Font>` with the `load_font` feature are supported"
+    )]
+    UnsupportedHandle(String),
+    #[error("An asset handle of the processed chirp file doesn't exist in the scene")]
+    BadHandle,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize chirp scene: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Failed to deserialize chirp scene: {0}")]
+    Deserialize(#[from] ron::error::SpannedError),
+    #[error("Failed to instantiate chirp scene: {0}")]
+    Spawn(#[from] bevy::scene::SceneSpawnError),
+}
+
+/// Saves a loaded [`Chirp`] so that [`ProcessedChirpLoader`] can read it.
+///
+/// The format is the root entity bits as a little endian `u64`, the length of
+/// the asset handle table as a little endian `u64`, the handle table as a
+/// `ron` list of `(entity, component, field, asset path)`, followed by
+/// the scene serialized as a `.scn.ron` file.
+///
+/// See the [module-level documentation](self) for how to use it.
+pub struct ChirpSaver {
+    registry: AppTypeRegistry,
+    handles: HashMap<TypeId, LoadHandle>,
+}
+impl FromWorld for ChirpSaver {
+    fn from_world(world: &mut World) -> Self {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        // Saved scenes contain weak handles, which hold an `AssetIndex`.
+        registry.write().register::<AssetIndex>();
+        Self { registry, handles: handle_loaders() }
+    }
+}
+impl ChirpSaver {
+    fn write_scene(&self, root: Entity, scene: &Scene) -> Result<Vec<u8>, ProcessError> {
+        // `DynamicScene` reads the `AppTypeRegistry` of the world it extracts
+        // from, chirp scene worlds don't have one.
+        let mut world = World::new();
+        world.insert_resource(self.registry.clone());
+        let instance = scene.write_to_world_with(&mut world, &self.registry)?;
+        let root = *instance
+            .entity_map
+            .get(&root)
+            .ok_or(ProcessError::BadRoot)?;
+        let handles = ron::to_string(&self.take_handles(&mut world)?)?;
+        let scene = DynamicScene::from_world(&world);
+        let serialized = scene.serialize_ron(&self.registry)?;
+
+        let mut bytes = root.to_bits().to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(handles.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(handles.as_bytes());
+        bytes.extend_from_slice(serialized.as_bytes());
+        Ok(bytes)
+    }
+    /// Replace the asset handles in `world` by weak handles, returning their paths.
+    fn take_handles(&self, world: &mut World) -> Result<Vec<SavedHandle>, ProcessError> {
+        let registry = self.registry.read();
+        let mut handles = Vec::new();
+        let entities: Vec<_> = world.iter_entities().map(|entity| entity.id()).collect();
+        for entity in entities {
+            let entity_ref = world.entity(entity);
+            let archetype = entity_ref.archetype();
+            let type_id = |id| world.components().get_info(id)?.type_id();
+            let components: Vec<_> = archetype.components().filter_map(type_id).collect();
+            for type_id in components {
+                let Some(registration) = registry.get(type_id) else {
+                    continue;
+                };
+                let Some(reflect_component) = registration.data::<ReflectComponent>() else {
+                    continue;
+                };
+                let component_path = registration.type_info().type_path();
+                let mut entity_mut = world.entity_mut(entity);
+                let mut component = reflect_component.reflect_mut(&mut entity_mut).unwrap();
+                let mut save =
+                    |field: &str, reflect_handle: &ReflectHandle, value: &mut dyn Reflect| {
+                        let handle = reflect_handle
+                            .downcast_handle_untyped(value.as_any())
+                            .unwrap();
+                        if !self.handles.contains_key(&value.as_any().type_id()) {
+                            let handle_path = value.reflect_type_path().to_owned();
+                            return Err(ProcessError::UnsupportedHandle(handle_path));
+                        }
+                        let Some(path) = handle.path() else {
+                            return Err(ProcessError::UnsavedHandle(component_path.to_owned()));
+                        };
+                        let (entity, component) = (entity.to_bits(), component_path.to_owned());
+                        handles.push((entity, component, field.to_owned(), path.to_string()));
+                        let weak = reflect_handle.typed(UntypedHandle::Weak(handle.id()));
+                        value
+                            .set(weak)
+                            .expect("`ReflectHandle::typed` returns the same handle type");
+                        Ok(())
+                    };
+                visit_handles(&registry, &mut *component, &mut String::new(), &mut save)?;
+            }
+        }
+        Ok(handles)
+    }
+}
+impl AssetSaver for ChirpSaver {
+    type Asset = Chirp;
+    type Settings = ();
+    type OutputLoader = ProcessedChirpLoader;
+    type Error = ProcessError;
+
+    fn save<'a>(
+        &'a self,
+        writer: &'a mut Writer,
+        asset: SavedAsset<'a, Chirp>,
+        _: &'a Self::Settings,
+    ) -> BoxedFuture<'a, Result<(), ProcessError>> {
+        Box::pin(async move {
+            let Chirp(Chirp_::Loaded(root, _), ..) = asset.get() else {
+                return Err(ProcessError::NotLoaded);
+            };
+            let scene = asset.get_labeled::<Scene>("Scene");
+            let scene = scene.ok_or(ProcessError::NoScene)?;
+            writer
+                .write_all(&self.write_scene(*root, scene.get())?)
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+/// Loads `.chirp` files pre-processed by [`ChirpSaver`].
+///
+/// This loader is not associated with any file extension, bevy selects it
+/// based on the `.meta` file written by the asset processor.
+pub struct ProcessedChirpLoader {
+    registry: AppTypeRegistry,
+    handles: HashMap<TypeId, LoadHandle>,
+}
+impl FromWorld for ProcessedChirpLoader {
+    fn from_world(world: &mut World) -> Self {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        // Saved scenes contain weak handles, which hold an `AssetIndex`.
+        registry.write().register::<AssetIndex>();
+        Self { registry, handles: handle_loaders() }
+    }
+}
+fn split_u64(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let (value, rest) = (bytes.len() >= U64_SIZE).then(|| bytes.split_at(U64_SIZE))?;
+    Some((u64::from_le_bytes(value.try_into().unwrap()), rest))
+}
+impl ProcessedChirpLoader {
+    /// Read a scene written by [`ChirpSaver`], `load` loads its asset handles.
+    fn read_scene(
+        &self,
+        bytes: &[u8],
+        mut load: impl FnMut(LoadHandle, String) -> Box<dyn Reflect>,
+    ) -> Result<(Entity, Scene), ProcessError> {
+        let (root, bytes) = split_u64(bytes).ok_or(ProcessError::NoRoot)?;
+        let root = Entity::from_bits(root);
+        let (handles_len, bytes) = split_u64(bytes).ok_or(ProcessError::Truncated)?;
+        let handles_len = usize::try_from(handles_len).map_err(|_| ProcessError::Truncated)?;
+        if bytes.len() < handles_len {
+            return Err(ProcessError::Truncated);
+        }
+        let (handles, scene) = bytes.split_at(handles_len);
+        let handles: Vec<SavedHandle> = ron::de::from_bytes(handles)?;
+
+        let registry = self.registry.read();
+        let mut deserializer = ron::de::Deserializer::from_bytes(scene)?;
+        let scene_deserializer = SceneDeserializer { type_registry: &registry };
+        let scene = scene_deserializer
+            .deserialize(&mut deserializer)
+            .map_err(|e| deserializer.span_error(e))?;
+        drop(registry);
+
+        let mut world = World::new();
+        let mut entity_map = HashMap::default();
+        scene.write_to_world_with(&mut world, &mut entity_map, &self.registry)?;
+        let root = *entity_map.get(&root).ok_or(ProcessError::BadRoot)?;
+
+        let registry = self.registry.read();
+        for (entity, component, field, path) in handles {
+            let entity = entity_map.get(&Entity::from_bits(entity));
+            let registration = registry.get_with_type_path(&component);
+            let reflect_component = registration.and_then(|r| r.data::<ReflectComponent>());
+            let (Some(&entity), Some(reflect_component)) = (entity, reflect_component) else {
+                return Err(ProcessError::BadHandle);
+            };
+            let mut entity_mut = world.entity_mut(entity);
+            let mut component = reflect_component.reflect_mut(&mut entity_mut);
+            let component = component.as_deref_mut().ok_or(ProcessError::BadHandle)?;
+            let value = match field.as_str() {
+                "" => component,
+                field => component
+                    .reflect_path_mut(field)
+                    .map_err(|_| ProcessError::BadHandle)?,
+            };
+            let Some(&load_handle) = self.handles.get(&value.as_any().type_id()) else {
+                let handle_path = value.reflect_type_path().to_owned();
+                return Err(ProcessError::UnsupportedHandle(handle_path));
+            };
+            value
+                .set(load(load_handle, path))
+                .expect("`LoadHandle` returns the same handle type");
+        }
+        drop(registry);
+        Ok((root, Scene::new(world)))
+    }
+}
+impl AssetLoader for ProcessedChirpLoader {
+    type Asset = Chirp;
+    type Settings = ();
+    type Error = ProcessError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Chirp, ProcessError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let load_handle = |load: LoadHandle, path| load(load_context, path);
+            let (root, scene) = self.read_scene(&bytes, load_handle)?;
+            let scene = load_context.add_labeled_asset("Scene".to_owned(), scene);
+            let path = load_context.path().to_string_lossy();
+            info!("Complete loading of processed chirp: {path}");
+            Ok(Chirp(Chirp_::Loaded(root, scene), LoadStats::default()))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+}
+
+#[cfg(all(test, feature = "load_image"))]
+mod tests {
+    use bevy::asset::{AssetApp, AssetPlugin, AssetServer, Assets, Handle};
+    use bevy::hierarchy::{BuildWorldChildren, Children, HierarchyPlugin};
+    use bevy::prelude::{App, MinimalPlugins};
+    use bevy::render::texture::Image;
+
+    use super::*;
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Icon(f32, Vec<Handle<Image>>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), HierarchyPlugin));
+        app.init_asset::<Image>().register_asset_reflect::<Image>();
+        app.register_type::<Icon>()
+            .register_type::<Vec<Handle<Image>>>();
+        app
+    }
+
+    #[test]
+    fn save_load_roundtrip() {
+        let mut app = app();
+        let server = app.world.resource::<AssetServer>().clone();
+        let path = |handle: &Handle<Image>| handle.path().map(ToString::to_string);
+
+        let mut scene_world = World::new();
+        scene_world.spawn_empty();
+        let root = scene_world.spawn(server.load::<Image>("logo.png")).id();
+        let icon = Icon(1., vec![server.load("a.png"), server.load("b.png")]);
+        let child = scene_world.spawn(icon).id();
+        scene_world.entity_mut(root).add_child(child);
+
+        let saver = ChirpSaver::from_world(&mut app.world);
+        let bytes = saver.write_scene(root, &Scene::new(scene_world)).unwrap();
+        let loader = ProcessedChirpLoader::from_world(&mut app.world);
+        let load = |_, path| Box::new(server.load::<Image>(path)) as Box<dyn Reflect>;
+        let (root, scene) = loader.read_scene(&bytes, load).unwrap();
+
+        let handle = scene.world.get::<Handle<Image>>(root).unwrap();
+        assert_eq!(path(handle), Some("logo.png".to_owned()));
+
+        let children = scene.world.get::<Children>(root).unwrap();
+        let Icon(size, handles) = scene.world.get::<Icon>(children[0]).unwrap();
+        let paths: Vec<_> = handles.iter().filter_map(path).collect();
+        assert_eq!(
+            (*size, paths),
+            (1., vec!["a.png".to_owned(), "b.png".to_owned()])
+        );
+    }
+    #[test]
+    fn unsaved_handle() {
+        let mut app = app();
+        let image = app
+            .world
+            .resource_mut::<Assets<Image>>()
+            .add(Image::default());
+
+        let mut scene_world = World::new();
+        let root = scene_world.spawn(image).id();
+
+        let saver = ChirpSaver::from_world(&mut app.world);
+        let result = saver.write_scene(root, &Scene::new(scene_world));
+        assert!(matches!(result, Err(ProcessError::UnsavedHandle(_))));
+    }
+}
@@ -4,12 +4,15 @@
 use std::any;
 
 use bevy::ecs::prelude::*;
+use bevy::hierarchy::{BuildChildren, BuildWorldChildren, Children};
 use bevy::ecs::{query::QuerySingleError, reflect::ReflectMapEntities};
 use bevy::log::{info, trace, warn};
 use bevy::reflect::TypeRegistry;
 use bevy::scene::Scene;
 use bevy::utils::HashMap;
 
+use super::diff::SceneTree;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(
@@ -28,11 +31,19 @@ pub enum Error {
 #[component(storage = "SparseSet")]
 pub(super) struct ChirpInstance {
     pub(super) map: HashMap<Entity, Entity>,
+    /// Whether the instance was hidden, see `ChirpState::Hidden`.
+    pub(super) hidden: bool,
+    /// Whether the chirp file was modified, and the scene should be updated
+    /// in place rather than respawned.
+    pub(super) hot_reload: bool,
+    pub(super) tree: SceneTree,
 }
 impl ChirpInstance {
     pub(super) fn despawn_scene(&self, root: Entity, cmds: &mut Commands<'_, '_>) {
-        for e in self.map.values().filter(|e| **e != root) {
-            cmds.entity(*e).despawn();
+        let scene_entities: Vec<_> = self.map.values().filter(|e| **e != root).copied().collect();
+        cmds.entity(root).remove_children(&scene_entities);
+        for entity in scene_entities {
+            cmds.entity(entity).despawn();
         }
     }
 }
@@ -64,6 +75,7 @@ pub(super) fn insert_on<D>(
     let stash = stash_components(reg, target, &mut source_scene.world, target_root);
 
     let source = &source_scene.world;
+    let scene_children = source.get::<Children>(source_root).map(|c| c.to_vec());
     let get_info = |id| source.components().get_info(id);
     let dsl = any::type_name::<D>();
     let mut entity_map = HashMap::default();
@@ -102,11 +114,22 @@ pub(super) fn insert_on<D>(
         }
     }
 
-    trace!("Overwritting changes to pre-existing components");
     let source = &mut source_scene.world;
+    let stashed = source.entity(stash);
+    let stashed = stashed.archetype().components();
+    let preserved = stashed.filter_map(|id| source.components().get_info(id)?.type_id());
+    let preserved = preserved.collect();
+    let tree = SceneTree::new(source, source_root, &entity_map, preserved);
+
+    trace!("Overwritting changes to pre-existing components");
     unstash_components(reg, target, source, target_root, stash);
 
-    Ok(ChirpInstance { map: entity_map })
+    // The pre-existing `Children` of the root overwrite the scene's children,
+    // add them back so that `despawn_recursive` on the root despawns the scene.
+    let scene_children: Vec<_> = scene_children.iter().flatten().map(|e| entity_map[e]).collect();
+    target.entity_mut(target_root).push_children(&scene_children);
+
+    Ok(ChirpInstance { map: entity_map, hidden: false, hot_reload: false, tree })
 }
 fn copy_components(
     reg: &TypeRegistry,
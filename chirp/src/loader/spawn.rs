@@ -1,15 +1,22 @@
-use std::mem;
+use std::{any, mem};
 
-use bevy::asset::{AssetEvent, Assets, Handle};
-use bevy::ecs::{prelude::*, reflect::ReflectComponent, system::SystemState};
+use bevy::asset::{AssetEvent, AssetServer, Assets, Handle};
+use bevy::ecs::prelude::*;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::ecs::system::{CommandQueue, SystemState};
+use bevy::hierarchy::DespawnRecursiveExt;
 use bevy::log::{error, trace};
-use bevy::prelude::{Asset, Children};
+use bevy::prelude::Asset;
 use bevy::reflect::{Reflect, TypePath};
+#[cfg(feature = "hide")]
+use bevy::render::view::Visibility;
 use bevy::scene::Scene;
+use bevy::utils::get_short_name;
 use thiserror::Error;
 
+use super::{instances::ChirpEvent, ChirpDsl, ChirpStats};
 use super::scene::{self, ChirpInstance};
-use crate::interpret;
+use crate::interpret::{self, LoadStats};
 
 #[allow(missing_docs)] // allow: described by error message.
 #[derive(Debug, Error)]
@@ -27,6 +34,10 @@ pub enum ChirpState {
     Loading,
     /// The scene's entities are now in the `World`.
     Loaded,
+    /// The scene's entities are in the `World`, but hidden.
+    ///
+    /// With the `hide` feature, the `Visibility` of the instance is `Hidden`.
+    Hidden,
     /// Reload the scene next time the internal `Chirp` scene management systems run.
     MustReload,
     /// Remove the scene from the world next time the internal `Chirp` scene
@@ -46,10 +57,41 @@ pub enum ChirpState {
 /// The root entity, once the `Chirp` spawned — in addition to the scene's root
 /// components — will have a [`ChirpState`] component added.
 ///
-/// Modify this component to control the scene state. It can be used to reload
-/// the scene or despawn the scene.
+/// Modify this component to control the scene state. It can be used to hide,
+/// reload or despawn the scene. See [`ChirpInstances`] for the supported
+/// transitions. The scene's entities are children of the root entity, so
+/// `despawn_recursive` on the root entity also despawns them.
+///
+/// When a chirp file imported by this `Chirp` changes, this `Chirp` is reloaded
+/// as well, and so are its instances.
+///
+/// [`ChirpInstances`]: super::ChirpInstances
 #[derive(Debug, TypePath, Asset)]
-pub struct Chirp(pub(crate) Chirp_);
+pub struct Chirp(pub(crate) Chirp_, pub(crate) LoadStats);
+impl Chirp {
+    /// The [`LoadStats`] of this chirp file, how long it took to load and
+    /// how many entities it spawned.
+    ///
+    /// Stats are zero for files that failed to load, and for files loaded by
+    /// the `ProcessedChirpLoader`, which doesn't interpret them.
+    #[must_use]
+    pub const fn stats(&self) -> &LoadStats {
+        &self.1
+    }
+    /// The errors of this chirp file, if it failed to load.
+    ///
+    /// Render them with [`ReportConfig::render`], for example to display them
+    /// in game.
+    ///
+    /// [`ReportConfig::render`]: crate::report::ReportConfig::render
+    #[must_use]
+    pub const fn errors(&self) -> Option<&interpret::Errors> {
+        match &self.0 {
+            Chirp_::Error(errors) => Some(errors),
+            Chirp_::Loaded(..) => None,
+        }
+    }
+}
 
 #[derive(Debug, TypePath)]
 pub enum Chirp_ {
@@ -60,20 +102,22 @@ pub enum Chirp_ {
     /// Note: this exists because this enables us to use hot reloading even
     /// when loading the file failed.
     Error(interpret::Errors),
-    LoadError,
 }
 
 #[allow(clippy::needless_pass_by_value)] // false positive, bevy systems
 pub(super) fn update_asset_changed(
     mut asset_events: EventReader<AssetEvent<Chirp>>,
-    mut chirp_instances: Query<(&mut ChirpState, &Handle<Chirp>), With<ChirpInstance>>,
+    mut chirp_instances: Query<(&mut ChirpState, &Handle<Chirp>, &mut ChirpInstance)>,
 ) {
     use AssetEvent::{Added, LoadedWithDependencies, Modified, Removed};
     for event in asset_events.read() {
-        for (mut state, instance_handle) in &mut chirp_instances {
+        for (mut state, instance_handle, mut instance) in &mut chirp_instances {
             let instance_id = instance_handle.id();
             match event {
-                Modified { id } if id == &instance_id => *state = ChirpState::MustReload,
+                Modified { id } if id == &instance_id => {
+                    *state = ChirpState::MustReload;
+                    instance.hot_reload = true;
+                }
                 Removed { id } if id == &instance_id => *state = ChirpState::MustDelete,
                 Added { .. } | Modified { .. } | Removed { .. } | LoadedWithDependencies { .. } => {
                 }
@@ -82,22 +126,83 @@ pub(super) fn update_asset_changed(
     }
 }
 
+/// Add the [`LoadStats`] of loaded chirp files to [`ChirpStats`].
+#[allow(clippy::needless_pass_by_value)] // false positive, bevy systems
+pub(super) fn record_stats(
+    mut asset_events: EventReader<AssetEvent<Chirp>>,
+    mut stats: ResMut<ChirpStats>,
+    chirps: Res<Assets<Chirp>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(Chirp(Chirp_::Loaded(..), file_stats)) = chirps.get(*id) else {
+            continue;
+        };
+        let Some(path) = asset_server.get_path(*id) else {
+            continue;
+        };
+        stats.0.insert(path.into_owned(), *file_stats);
+    }
+}
+
+/// Log an error for chirp instances written for another DSL than `D`.
+#[allow(clippy::needless_pass_by_value)] // false positive, bevy systems
+pub(super) fn check_dsl<D>(instances: Query<(Entity, &ChirpDsl), Added<ChirpDsl>>) {
+    let loader_dsl = any::type_name::<D>();
+    for (entity, dsl) in &instances {
+        match &dsl.0 {
+            Some(dsl) if dsl != loader_dsl => error!(
+                "The chirp instance {entity:?} is written for the {dsl} DSL, but chirp \
+                files are loaded with {loader_dsl}. Load them with \
+                `cuicui_chirp::loader::Plugin::new::<{}>()` instead.",
+                get_short_name(dsl),
+            ),
+            Some(_) | None => {}
+        }
+    }
+}
+
+/// Log an error for chirp instances spawned without a loader `Plugin`, see
+/// [`super::check_loader_plugin`].
+#[allow(clippy::needless_pass_by_value)] // false positive, bevy systems
+pub(super) fn missing_loader(instances: Query<(Entity, Option<&ChirpDsl>), Added<Handle<Chirp>>>) {
+    for (entity, dsl) in &instances {
+        let dsl = dsl.and_then(|dsl| dsl.0.as_deref());
+        let dsl = dsl.map_or_else(|| "YourDsl".to_owned(), get_short_name);
+        error!(
+            "The chirp instance {entity:?} can't be loaded, no chirp loader plugin \
+            was added. Add it with `cuicui_chirp::loader::Plugin::new::<{dsl}>()`, \
+            or with `add_cuicui` and `chirp: true`.",
+        );
+    }
+}
+
 pub(super) struct SpawnRequest {
     target: Entity,
     source: Entity,
     scene_handle: Handle<Scene>,
+    /// Update the already spawned scene in place rather than spawning it.
+    hot_reload: bool,
 }
 type Chirps = (Entity, &'static mut ChirpState, &'static Handle<Chirp>);
+type MarkState<'w, 's> = (
+    Res<'w, Assets<Chirp>>,
+    Query<'w, 's, Chirps, Without<ChirpInstance>>,
+    Query<'w, 's, (Chirps, &'static mut ChirpInstance)>,
+);
 
 #[allow(clippy::needless_pass_by_value)] // false positive, bevy systems
 pub(super) fn spawn_chirps<D>(
     world: &mut World,
     mut to_load: Local<Vec<SpawnRequest>>,
-    mut mark_state: Local<SystemState<(Res<Assets<Chirp>>, Query<Chirps, Without<ChirpInstance>>)>>,
+    mut mark_state: Local<SystemState<MarkState<'static, 'static>>>,
 ) {
     to_load.extend(mark_loaded(mark_state.get_mut(world)));
 
-    for SpawnRequest { target, source, scene_handle } in to_load.drain(..) {
+    for SpawnRequest { target, source, scene_handle, hot_reload } in to_load.drain(..) {
         // A better impl would use `assets.remove(handle)` followed by `assets.insert` but currently
         // Assets::remove is broken, see: https://github.com/bevyengine/bevy/issues/10444
         let scene = change_scenes(world, |s| {
@@ -105,7 +210,10 @@ pub(super) fn spawn_chirps<D>(
         });
         let mut own_scene = Scene::new(scene.unwrap());
 
-        let Some(instance) = spawn_scene::<D>(&mut own_scene, world, source, target) else {
+        let updated = hot_reload.then(|| update_scene::<D>(&own_scene, world, source, target));
+        let instance = updated.flatten();
+        let instance = instance.or_else(|| spawn_scene::<D>(&mut own_scene, world, source, target));
+        let Some(instance) = instance else {
             continue;
         };
         change_scenes(world, |s| {
@@ -115,6 +223,7 @@ pub(super) fn spawn_chirps<D>(
         });
 
         world.entity_mut(target).insert(instance);
+        world.send_event(ChirpEvent::Loaded(target));
     }
 }
 
@@ -149,48 +258,250 @@ fn spawn_scene<D>(
     target.entity_mut(target_root).insert(handle);
     instance
 }
+/// Update in place the scene spawned on `target_root`, see the `diff` module.
+///
+/// If it can't be updated in place, despawn the scene and return `None`.
+fn update_scene<D>(
+    scene: &Scene,
+    target: &mut World,
+    source_root: Entity,
+    target_root: Entity,
+) -> Option<ChirpInstance> {
+    let mut instance = target.entity_mut(target_root).take::<ChirpInstance>()?;
+    let type_registry = target.resource::<AppTypeRegistry>().clone();
+    let type_registry = &*type_registry.read();
+    // On error, `spawn_scene` fails the same way and logs the error.
+    let tree = &mut instance.tree;
+    if let Ok(Some(map)) = tree.update::<D>(type_registry, &scene.world, source_root, target) {
+        trace!("Updated in place the chirp scene of {target_root:?}");
+        instance.map = map;
+        return Some(instance);
+    }
+    instance.tree.remove_root_components(type_registry, target);
+    let mut queue = CommandQueue::default();
+    instance.despawn_scene(target_root, &mut Commands::new(&mut queue, target));
+    queue.apply(target);
+    None
+}
 // TODO(perf): Theoretically it _should_ be possible to implement this without cloning.
-fn mark_loaded(
-    (chirps, mut to_spawn): (Res<Assets<Chirp>>, Query<Chirps, Without<ChirpInstance>>),
-) -> Vec<SpawnRequest> {
-    let iter = to_spawn.iter_mut();
-    let iter = iter.filter_map(|(target, mut state, handle)| {
-        let Some(&Chirp(Chirp_::Loaded(source, ref scene))) = chirps.get(handle) else {
-            return None;
+fn mark_loaded((chirps, mut to_spawn, mut to_update): MarkState) -> Vec<SpawnRequest> {
+    let mut requests = Vec::new();
+    let mut mark = |chirp: (Entity, Mut<ChirpState>, &Handle<_>), hot_reload| {
+        let (target, mut state, handle) = chirp;
+        let Some(&Chirp(Chirp_::Loaded(source, ref scene), ..)) = chirps.get(handle) else {
+            return false;
         };
-        matches!(*state, ChirpState::Loading).then(|| {
-            trace!("Instance {target:?} is ready marking as loaded.");
-            *state = ChirpState::Loaded;
-            SpawnRequest { target, source, scene_handle: scene.clone() }
-        })
-    });
-    iter.collect()
+        if !matches!(*state, ChirpState::Loading) {
+            return false;
+        }
+        trace!("Instance {target:?} is ready marking as loaded.");
+        *state = ChirpState::Loaded;
+        let scene_handle = scene.clone();
+        requests.push(SpawnRequest { target, source, scene_handle, hot_reload });
+        true
+    };
+    for chirp in &mut to_spawn {
+        mark(chirp, false);
+    }
+    for (chirp, mut instance) in &mut to_update {
+        if instance.hot_reload && mark(chirp, true) {
+            instance.hot_reload = false;
+        }
+    }
+    requests
 }
 
+/// The `Visibility` of chirp instances, used to hide them.
+#[cfg(feature = "hide")]
+type Visibilities<'w, 's> = Query<'w, 's, &'static mut Visibility>;
+#[cfg(not(feature = "hide"))]
+type Visibilities<'w, 's> = ();
+
+#[cfg(feature = "hide")]
+fn set_hidden(visibilities: &mut Visibilities, entity: Entity, hidden: bool) {
+    let visibility = if hidden { Visibility::Hidden } else { Visibility::Inherited };
+    if let Ok(mut current) = visibilities.get_mut(entity) {
+        current.set_if_neq(visibility);
+    }
+}
+#[cfg(not(feature = "hide"))]
+const fn set_hidden(_: &mut Visibilities, _: Entity, _: bool) {}
+
 #[allow(clippy::needless_pass_by_value)] // false positive, bevy systems
 pub(super) fn manage_chirp_state(
     mut cmds: Commands,
-    mut to_update: Query<(Chirps, &ChirpInstance), Changed<ChirpState>>,
+    mut events: EventWriter<ChirpEvent>,
+    mut visibilities: Visibilities,
+    mut to_update: Query<(Chirps, &mut ChirpInstance), Changed<ChirpState>>,
 ) {
-    for ((chirp_id, mut state, _), instance) in &mut to_update {
+    for ((chirp_id, mut state, _), mut instance) in &mut to_update {
         match &*state {
             ChirpState::MustReload => {
                 trace!("Reloading instance {chirp_id:?} marked as MustReload",);
                 *state = ChirpState::Loading;
-
-                // TODO(BUG): This also despawns the pre-existing components, which
-                // is problematic.
-                cmds.entity(chirp_id).remove::<(ChirpInstance, Children)>();
-                instance.despawn_scene(chirp_id, &mut cmds);
+                if instance.hidden {
+                    instance.hidden = false;
+                    set_hidden(&mut visibilities, chirp_id, false);
+                }
+                // The scene is updated in place once the chirp file is loaded.
+                if !instance.hot_reload {
+                    // TODO(BUG): This keeps the components the scene added to the root.
+                    cmds.entity(chirp_id).remove::<ChirpInstance>();
+                    instance.despawn_scene(chirp_id, &mut cmds);
+                }
+                events.send(ChirpEvent::Reloading(chirp_id));
             }
             ChirpState::MustDelete => {
                 trace!("Deleting instance {chirp_id:?} marked as MustDelete",);
                 instance.despawn_scene(chirp_id, &mut cmds);
-                cmds.entity(chirp_id).despawn();
+                cmds.entity(chirp_id).despawn_recursive();
+                events.send(ChirpEvent::Despawned(chirp_id));
+            }
+            ChirpState::Hidden if !instance.hidden => {
+                trace!("Hiding instance {chirp_id:?} marked as Hidden");
+                instance.hidden = true;
+                set_hidden(&mut visibilities, chirp_id, true);
+                events.send(ChirpEvent::Hidden(chirp_id));
+            }
+            ChirpState::Loaded if instance.hidden => {
+                trace!("Showing hidden instance {chirp_id:?} marked as Loaded");
+                instance.hidden = false;
+                set_hidden(&mut visibilities, chirp_id, false);
+                events.send(ChirpEvent::Shown(chirp_id));
             }
             // This system doesn't need to do anything in this situations, also
-            // currently this should never happen.
-            ChirpState::Loading | ChirpState::Loaded => {}
+            // currently `Loading` should never happen.
+            ChirpState::Loading | ChirpState::Loaded | ChirpState::Hidden => {}
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::{BuildWorldChildren, Children, DespawnRecursiveExt, Name, Parent};
+    use bevy::reflect::Reflect;
+
+    use super::*;
+    use crate::loader::ChirpBundle;
+
+    /// A world with an instance of a chirp scene of 3 entities, the instance
+    /// having a child not part of the scene.
+    fn spawned_instance() -> (World, Entity) {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        let mut registry_mut = registry.write();
+        registry_mut.register::<Name>();
+        registry_mut.register::<Parent>();
+        registry_mut.register::<Children>();
+        drop(registry_mut);
+        world.insert_resource(registry);
+        world.init_resource::<Assets<Scene>>();
+        world.init_resource::<Assets<Chirp>>();
+        world.init_resource::<Events<ChirpEvent>>();
+
+        let mut scene = World::new();
+        let root = scene.spawn(Name::new("root")).id();
+        let child = scene.spawn(Name::new("child")).set_parent(root).id();
+        scene.spawn(Name::new("grandchild")).set_parent(child);
+        let scene = world.resource_mut::<Assets<Scene>>().add(Scene::new(scene));
+        let chirp = Chirp(Chirp_::Loaded(root, scene), LoadStats::default());
+        let chirp = world.resource_mut::<Assets<Chirp>>().add(chirp);
+
+        let seed = world.spawn(ChirpBundle::new(chirp)).id();
+        world.spawn(Name::new("not scene")).set_parent(seed);
+        world.run_system_once(spawn_chirps::<()>);
+        (world, seed)
+    }
+    fn names(world: &mut World) -> Vec<String> {
+        let mut names: Vec<_> = world.query::<&Name>().iter(world).map(Name::to_string).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn despawn_recursive_seed() {
+        let (mut world, seed) = spawned_instance();
+        assert_eq!(world.get::<ChirpState>(seed), Some(&ChirpState::Loaded));
+        assert_eq!(world.get::<Children>(seed).map(|c| c.len()), Some(2));
+        assert_eq!(names(&mut world), ["child", "grandchild", "not scene", "root"]);
+
+        world.entity_mut(seed).despawn_recursive();
+        assert_eq!(world.entities().len(), 0);
+    }
+    #[test]
+    fn reload_and_delete_seed() {
+        let (mut world, seed) = spawned_instance();
+
+        world.get_mut::<ChirpState>(seed).unwrap().set_if_neq(ChirpState::MustReload);
+        world.run_system_once(manage_chirp_state);
+        assert_eq!(names(&mut world), ["not scene", "root"]);
+        assert_eq!(world.get::<Children>(seed).map(|c| c.len()), Some(1));
+
+        world.run_system_once(spawn_chirps::<()>);
+        assert_eq!(names(&mut world), ["child", "grandchild", "not scene", "root"]);
+
+        world.get_mut::<ChirpState>(seed).unwrap().set_if_neq(ChirpState::MustDelete);
+        world.run_system_once(manage_chirp_state);
+        assert_eq!(world.entities().len(), 0);
+    }
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Value(u32);
+
+    #[derive(Component)]
+    struct Runtime;
+
+    /// Replace the scene of the chirp of `seed` by `names`, a list of
+    /// `(name, parent index)`, the first one is the root.
+    fn modify_chirp(world: &mut World, seed: Entity, names: &[(&str, usize)], value: u32) {
+        let mut scene = World::new();
+        let mut entities = Vec::new();
+        for &(name, parent) in names {
+            let mut entity = scene.spawn((Name::new(name.to_owned()), Value(value)));
+            if let Some(parent) = entities.get(parent) {
+                entity.set_parent(*parent);
+            }
+            entities.push(entity.id());
+        }
+        let scene = world.resource_mut::<Assets<Scene>>().add(Scene::new(scene));
+        let handle = world.get::<Handle<Chirp>>(seed).unwrap().clone();
+        let chirp = Chirp(Chirp_::Loaded(entities[0], scene), LoadStats::default());
+        world.resource_mut::<Assets<Chirp>>().insert(handle.id(), chirp);
+        world.send_event(AssetEvent::Modified { id: handle.id() });
+
+        world.run_system_once(update_asset_changed);
+        world.run_system_once(manage_chirp_state);
+        world.run_system_once(spawn_chirps::<()>);
+    }
+    fn entity_named(world: &mut World, name: &str) -> Entity {
+        let mut query = world.query::<(Entity, &Name)>();
+        query.iter(world).find_map(|(e, n)| (n.as_str() == name).then_some(e)).unwrap()
+    }
+
+    #[test]
+    fn hot_reload_in_place() {
+        let (mut world, seed) = spawned_instance();
+        world.resource::<AppTypeRegistry>().write().register::<Value>();
+        world.init_resource::<Events<AssetEvent<Chirp>>>();
+        let child = entity_named(&mut world, "child");
+        world.entity_mut(child).insert(Runtime);
+
+        let same = [("root", 0), ("child", 0), ("grandchild", 1)];
+        modify_chirp(&mut world, seed, &same, 1);
+        assert_eq!(world.get::<ChirpState>(seed), Some(&ChirpState::Loaded));
+        assert_eq!(entity_named(&mut world, "child"), child);
+        assert_eq!(world.get::<Value>(child), Some(&Value(1)));
+        assert_eq!(world.get::<Value>(seed), Some(&Value(1)));
+        assert!(world.get::<Runtime>(child).is_some(), "runtime components are kept");
+        assert_eq!(world.get::<Children>(seed).map(|c| c.len()), Some(2));
+
+        let renamed = [("root", 0), ("renamed", 0), ("grandchild", 1)];
+        modify_chirp(&mut world, seed, &renamed, 2);
+        assert_eq!(names(&mut world), ["grandchild", "not scene", "renamed", "root"]);
+        assert!(world.get_entity(child).is_none(), "scene was respawned");
+        assert_eq!(world.get::<Value>(seed), Some(&Value(2)));
+        assert_eq!(world.get::<Children>(seed).map(|c| c.len()), Some(2));
+    }
+}
@@ -0,0 +1,311 @@
+//! Migrate `.chirp` files written for older versions of the chirp grammar.
+//!
+//! A chirp file may start with a version header, such as `chirp 1`.
+//! Files without a version header are assumed to be version `0`.
+//!
+//! Before parsing a file, the loader runs all [`Migrations`] registered for
+//! a version equal or higher to the file's version. Migrations are source to
+//! source transformations. They take the file's content and return the updated
+//! file's content.
+//!
+//! Since files without a header go through all migrations, a migration **must**
+//! leave files already written in the new syntax untouched.
+//!
+//! Note that error messages refer to the migrated text, not the text on disk.
+//!
+//! # Builtin migrations
+//!
+//! - `0`: Convert the pre-0.10 statement syntax to the modern one.
+//!   `spawn("name", rules(50%, 51px), empty_px 60);` becomes
+//!   `"name"(rules(50%, 51px) empty_px(60))` and `row("name", width 10px) {}`
+//!   becomes `"name"(row width(10px)) {}`.
+use std::{borrow::Cow, fmt, sync::Arc};
+
+use bevy::log::{debug, warn};
+use winnow::{stream::Stream, BStr};
+
+use crate::parser::{Input, Token};
+
+/// The version of the chirp grammar implemented by this crate.
+///
+/// Files with a `chirp N` header where `N` is higher than this are not migrated.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// A source to source transformation of a chirp file. See [`Migrations::add`].
+pub type MigrationArc = Arc<dyn for<'a> Fn(&'a [u8]) -> Cow<'a, [u8]> + Send + Sync>;
+
+/// Ordered list of migrations applied to chirp files before parsing them.
+///
+/// [`Migrations::default`] contains the builtin migrations.
+#[derive(Clone)]
+pub struct Migrations {
+    steps: Vec<(u32, MigrationArc)>,
+}
+impl Default for Migrations {
+    fn default() -> Self {
+        let mut ret = Self::empty();
+        ret.add(0, old_statements);
+        ret
+    }
+}
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let versions = self.steps.iter().map(|(version, _)| version);
+        f.debug_struct("Migrations")
+            .field("steps", &versions.collect::<Vec<_>>())
+            .finish()
+    }
+}
+impl Migrations {
+    /// Migrations without the builtin migrations.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self { steps: Vec::new() }
+    }
+    /// Add a `migration` to run on files of version `version` or lower.
+    ///
+    /// Migrations are ran in order of `version`. Migrations with the same
+    /// `version` are ran in the order they were added.
+    pub fn add(
+        &mut self,
+        version: u32,
+        migration: impl for<'a> Fn(&'a [u8]) -> Cow<'a, [u8]> + Send + Sync + 'static,
+    ) {
+        let index = self.steps.partition_point(|(v, _)| *v <= version);
+        self.steps.insert(index, (version, Arc::new(migration)));
+    }
+    /// Run all migrations relevant to `input`'s version header.
+    #[must_use]
+    pub fn migrate<'a>(&self, input: &'a [u8]) -> Cow<'a, [u8]> {
+        let version = match version(input) {
+            Ok(Some(version)) if version > CURRENT_VERSION => {
+                warn!("chirp file version {version} is newer than {CURRENT_VERSION}, skipping migrations");
+                return Cow::Borrowed(input);
+            }
+            Ok(version) => version.unwrap_or(0),
+            Err(version) => {
+                let version = BStr::new(version);
+                warn!("Invalid chirp file version '{version}', skipping migrations");
+                return Cow::Borrowed(input);
+            }
+        };
+        let mut current = Cow::Borrowed(input);
+        let relevant = self.steps.iter().filter(|(v, _)| version <= *v);
+        for (_, migration) in relevant {
+            if let Cow::Owned(migrated) = migration(&current) {
+                current = Cow::Owned(migrated);
+            }
+        }
+        current
+    }
+}
+
+/// Read the `chirp N` header of `input`.
+///
+/// # Errors
+/// When the header exists, but `N` is not a number, returns `N`.
+pub fn version(input: &[u8]) -> Result<Option<u32>, &[u8]> {
+    let mut input = Input::new(input, ());
+    let (Some(Token::Ident(b"chirp")), Some(Token::Ident(version))) =
+        (input.next_token(), input.next_token())
+    else {
+        return Ok(None);
+    };
+    let parsed = std::str::from_utf8(version).ok().and_then(|v| v.parse().ok());
+    parsed.map(Some).ok_or(version)
+}
+
+/// Tokens of `input` with their start and end offset.
+fn spanned_tokens(input: &[u8]) -> Vec<(usize, usize, Token<'_>)> {
+    let input = Input::new(input, ());
+    let iter = input.iter_offsets();
+    iter.map(|(start, token)| (start, start + token.len() as usize, token))
+        .collect()
+}
+
+/// Index of the token closing the delimiter opened at `open`.
+fn closing(tokens: &[(usize, usize, Token)], open: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    for (i, (_, _, token)) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Lparen | Token::Lbracket | Token::Lcurly => depth += 1,
+            Token::Rparen | Token::Rbracket | Token::Rcurly => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Split `tokens` in ranges separated by top-level commas.
+fn comma_separated(tokens: &[(usize, usize, Token)]) -> Vec<(usize, usize)> {
+    let mut items = Vec::new();
+    let (mut depth, mut item_start) = (0_usize, 0);
+    for (i, (_, _, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::Lparen | Token::Lbracket | Token::Lcurly => depth += 1,
+            Token::Rparen | Token::Rbracket | Token::Rcurly => depth = depth.saturating_sub(1),
+            Token::Comma if depth == 0 => {
+                items.push((item_start, i));
+                item_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if item_start < tokens.len() {
+        items.push((item_start, tokens.len()));
+    }
+    items
+}
+
+/// Convert an old-style method (`empty_px 60`) into a modern method (`empty_px(60)`).
+fn old_method(input: &[u8], tokens: &[(usize, usize, Token)], out: &mut Vec<u8>) {
+    let text = |from: usize, to: usize| &input[tokens[from].0..tokens[to - 1].1];
+    match tokens {
+        [] => {}
+        [_] | [_, (_, _, Token::Lparen), ..] => out.extend_from_slice(text(0, tokens.len())),
+        [_, ..] => {
+            out.extend_from_slice(text(0, 1));
+            out.push(b'(');
+            let mut arg = 1;
+            while arg < tokens.len() {
+                let mut arg_end = arg + 1;
+                if let Some((_, _, Token::Lparen | Token::Lbracket)) = tokens.get(arg_end) {
+                    arg_end = closing(tokens, arg_end).map_or(tokens.len(), |i| i + 1);
+                }
+                if arg != 1 {
+                    out.extend_from_slice(b", ");
+                }
+                out.extend_from_slice(text(arg, arg_end));
+                arg = arg_end;
+            }
+            out.push(b')');
+        }
+    }
+}
+
+/// Migration for statements of the form `spawn("name", method arg, method2);`
+///
+/// Modern statements never have top-level commas in their method list and
+/// never end with a `;`, so they are left untouched.
+fn old_statements(input: &[u8]) -> Cow<'_, [u8]> {
+    let tokens = spanned_tokens(input);
+    let mut out = Vec::new();
+    let (mut copied_until, mut depth, mut i) = (0, 0_usize, 0);
+    while i < tokens.len() {
+        let (start, _, token) = tokens[i];
+        let previous = i.checked_sub(1).map(|i| tokens[i].2);
+        match token {
+            Token::Lparen | Token::Lbracket => depth += 1,
+            Token::Rparen | Token::Rbracket => depth = depth.saturating_sub(1),
+            Token::Ident(name)
+                if depth == 0
+                    && !name.ends_with(b"!")
+                    && name != b"code"
+                    && previous != Some(Token::Ident(b"fn"))
+                    && matches!(tokens.get(i + 1), Some((_, _, Token::Lparen))) =>
+            {
+                let Some(close) = closing(&tokens, i + 1) else {
+                    break;
+                };
+                let items = comma_separated(&tokens[i + 2..close]);
+                let semicolon = matches!(tokens.get(close + 1), Some((_, _, Token::Ident(b";"))));
+                let named = matches!(items.first(), Some(&(s, e)) if e == s + 1
+                    && matches!(tokens[i + 2 + s].2, Token::String(_)));
+
+                if items.len() > 1 || semicolon || named {
+                    out.extend_from_slice(&input[copied_until..start]);
+                    let methods = if named {
+                        let (name_start, name_end, _) = tokens[i + 2];
+                        out.extend_from_slice(&input[name_start..name_end]);
+                        &items[1..]
+                    } else {
+                        out.extend_from_slice(b"Entity");
+                        &items[..]
+                    };
+                    out.push(b'(');
+                    let no_kind = name == b"spawn" || name == b"Entity";
+                    if !no_kind {
+                        out.extend_from_slice(name);
+                    }
+                    let mut first = no_kind;
+                    for &(s, e) in methods {
+                        if !first {
+                            out.push(b' ');
+                        }
+                        first = false;
+                        old_method(input, &tokens[i + 2 + s..i + 2 + e], &mut out);
+                    }
+                    out.push(b')');
+                    let end = if semicolon { close + 1 } else { close };
+                    copied_until = tokens[end].1;
+                    i = end + 1;
+                    continue;
+                }
+                i = close + 1;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if copied_until == 0 {
+        return Cow::Borrowed(input);
+    }
+    out.extend_from_slice(&input[copied_until..]);
+    debug!("Migrated chirp file from pre-0.10 syntax");
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn migrate(input: &str) -> String {
+        let migrated = Migrations::default().migrate(input.as_bytes());
+        String::from_utf8(migrated.into_owned()).unwrap()
+    }
+
+    #[test]
+    fn old_syntax() {
+        let input = r#"row("20", rules(40px, 41%)) {
+    spawn("23", rules(50%, 51px), empty_px 60);
+    spawn(empty_px 61);
+}"#;
+        let expected = r#""20"(row rules(40px, 41%)) {
+    "23"(rules(50%, 51px) empty_px(60))
+    Entity(empty_px(61))
+}"#;
+        assert_eq!(expected, migrate(input));
+    }
+
+    #[test]
+    fn modern_syntax_untouched() {
+        let input = r#"
+use cuicui_layout_bevy_ui::UiDsl as Dsl
+fn button(text, width) {
+    Entity(text(text) width(width))
+}
+row(rules(10px, 20%) main_margin(10.)) {
+    button!(hello, 10px)(bg(red)) { Entity() }
+    code(some_code)
+    "spawn"(column)
+}"#;
+        assert_eq!(input, migrate(input));
+    }
+
+    #[test]
+    fn versioned_file_skipped() {
+        let input = "chirp 1\nspawn(\"name\", width 10px);";
+        assert_eq!(input, migrate(input));
+        assert_eq!(Ok(Some(1)), version(input.as_bytes()));
+        assert_eq!(Ok(None), version(b"Entity()"));
+        assert_eq!(Err(&b"one"[..]), version(b"chirp one Entity()"));
+
+        let parsed = crate::parser::chirp_file(Input::new(b"chirp 1\nEntity()", ()));
+        assert!(parsed.is_ok());
+    }
+}
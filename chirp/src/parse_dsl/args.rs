@@ -14,12 +14,14 @@
 use std::{any, borrow::Cow, convert::Infallible, io, marker::PhantomData, str, str::FromStr};
 
 use bevy::asset::{Asset, Handle, LoadContext};
+#[cfg(feature = "image_size")]
+use bevy::{math::UVec2, render::texture::Image};
 use bevy::reflect::erased_serde::__private::serde::de::DeserializeSeed;
 use bevy::reflect::serde::TypedReflectDeserializer;
-use bevy::reflect::{FromReflect, Reflect, TypeRegistry};
+use bevy::reflect::{FromReflect, Reflect, TypeInfo, TypeRegistry};
 use thiserror::Error;
 
-use super::escape_literal;
+use super::{escape_literal, raw_string};
 use crate::parser;
 
 fn tyname<T>() -> &'static str {
@@ -49,7 +51,9 @@ pub enum HandleDslDeserError<T> {
 // NOTE: we use `&'static str` instead of storing the type as a generic parameter
 // so that we can downcast to this error type in crate::interpret, in order to
 // pick up the underlying error offset.
-/// Error occuring in [`from_reflect`].
+/// Error occuring in [`from_reflect`] and [`ReflectDsl`] methods.
+///
+/// [`ReflectDsl`]: crate::ReflectDsl
 #[allow(missing_docs)] // Already documented by error message
 #[derive(Debug, Error)]
 pub enum ReflectDslDeserError {
@@ -62,6 +66,39 @@ pub enum ReflectDslDeserError {
         generated reflect proxy type couldn't be converted into '{0}'"
     )]
     BadReflect(&'static str),
+    #[error(
+        "Method '{field}' of ReflectDsl<{ty}> was called with {got} arguments, \
+        it accepts exactly one. Try having double parenthesis around the method argument"
+    )]
+    NotExactlyOneArgument { field: String, ty: &'static str, got: usize },
+    #[error(
+        "Tried to set the field '{field}' of ReflectDsl<{ty}>, but {ty} \
+        doesn't have such a field"
+    )]
+    BadField { field: String, ty: &'static str },
+    #[error(
+        "The field '{field}' of '{ty}' is not registered. \
+        Please register the type '{missing}' to be able to use ReflectDsl<{ty}>."
+    )]
+    FieldNotRegistered { field: String, ty: &'static str, missing: String },
+    #[error("Failed to deserialize the field '{field}' of ReflectDsl<{ty}>: {error}")]
+    BadFieldDeser { field: String, ty: &'static str, error: anyhow::Error },
+    #[error(
+        "The field '{field}' of ReflectDsl<{ty}> was parsed by bevy in RON, but the \
+        generated reflect proxy type couldn't be converted into '{expected}'"
+    )]
+    BadFieldReflect { field: String, ty: &'static str, expected: &'static str },
+    #[error("Method '{field}' was called on ReflectDsl<{ty}> after it was inserted")]
+    AlreadyInserted { field: String, ty: &'static str },
+    #[error(
+        "Tried to set ReflectDsl<{ty}> to the '{variant}' variant, but {ty} \
+        doesn't have such a variant"
+    )]
+    BadVariant { variant: String, ty: &'static str },
+    #[error("Failed to deserialize the '{variant}' variant of ReflectDsl<{ty}>: {error}")]
+    BadVariantDeser { variant: String, ty: &'static str, error: anyhow::Error },
+    #[error("ReflectDsl<{0}> only supports structs and enums, but '{0}' is neither")]
+    NotStructOrEnum(&'static str),
 }
 
 impl ReflectDslDeserError {
@@ -76,7 +113,17 @@ impl ReflectDslDeserError {
     }
     pub(crate) fn maybe_offset(&self) -> Option<u32> {
         match self {
-            Self::BadReflect(_) | Self::NotRegistered(_) => None,
+            Self::BadReflect(_)
+            | Self::NotRegistered(_)
+            | Self::NotExactlyOneArgument { .. }
+            | Self::BadField { .. }
+            | Self::FieldNotRegistered { .. }
+            | Self::BadFieldDeser { .. }
+            | Self::BadFieldReflect { .. }
+            | Self::AlreadyInserted { .. }
+            | Self::BadVariant { .. }
+            | Self::BadVariantDeser { .. }
+            | Self::NotStructOrEnum(_) => None,
             Self::RonDeser(ron, _) => {
                 (ron.position.line <= 1).then(|| u32::try_from(ron.position.col).unwrap())
             }
@@ -99,6 +146,9 @@ pub struct ArgumentError {
 /// This argument parser only requires deriving and registering `T`, unlike
 /// the other parsers.
 ///
+/// Enum variants can be written with their type name, such as
+/// `Visibility::Hidden`, or without, such as `Hidden`.
+///
 /// # Other parsers
 ///
 /// [self#functions]
@@ -115,15 +165,44 @@ pub fn from_reflect<T: Reflect + FromReflect>(
 
     let id = any::TypeId::of::<T>();
     let registration = registry.get(id).ok_or_else(Error::not_registered::<T>)?;
-    let mut ron_de = Ronzer::from_str(input).map_err(Error::ron_deser::<T>)?;
+    let (input, prefix) = strip_variant_path(input, registration.type_info());
+    let ron_deser = |mut err: ron::error::SpannedError| {
+        if err.position.line <= 1 {
+            err.position.col += prefix;
+        }
+        Error::ron_deser::<T>(err)
+    };
+    let mut ron_de = Ronzer::from_str(input).map_err(ron_deser)?;
     let de = TypedReflectDeserializer::new(registration, registry);
     let deserialized = match de.deserialize(&mut ron_de) {
         Ok(ok) => ok,
-        Err(err) => return Err(Error::ron_deser::<T>(ron_de.span_error(err))),
+        Err(err) => return Err(ron_deser(ron_de.span_error(err))),
     };
     T::from_reflect(deserialized.as_ref()).ok_or_else(Error::bad_reflect::<T>)
 }
 
+/// `input` without the `Type::` path of its variant if `info` is an enum, and
+/// the length of the removed path.
+///
+/// `ron` only accepts the bare variant name, this allows `Visibility::Hidden`
+/// as well as `Hidden` in chirp files.
+pub(crate) fn strip_variant_path<'a>(input: &'a str, info: &TypeInfo) -> (&'a str, usize) {
+    let TypeInfo::Enum(info) = info else {
+        return (input, 0);
+    };
+    let variant_end = input.find('(').unwrap_or(input.len());
+    let Some(path_end) = input[..variant_end].rfind("::") else {
+        return (input, 0);
+    };
+    let path = input[..path_end].trim_start();
+    let table = info.type_path_table();
+    if path == table.short_path() || path == table.path() {
+        (&input[path_end + 2..], path_end + 2)
+    } else {
+        (input, 0)
+    }
+}
+
 /// Deserialize a method argument using the [`FromStr`] `std` trait.
 ///
 /// For your own types, it might be more succint to define your own parser
@@ -173,8 +252,79 @@ pub fn to_handle<T: Asset>(
     Ok(ctx.load(String::from(input)))
 }
 
+/// A [`Handle<Image>`] with the size of its image, see [`to_sized_image`].
+#[cfg(feature = "image_size")]
+#[derive(Debug, Clone)]
+pub struct SizedImage {
+    /// The image.
+    pub handle: Handle<Image>,
+    /// The image's size in pixels, `None` if it couldn't be read.
+    pub size: Option<UVec2>,
+}
+
+/// Load an image from the path declared in `input`, and read its size.
+///
+/// Unlike [`to_handle`], this reads and decodes the image while interpreting
+/// the chirp file, so that content-sized nodes already have the image's size
+/// on the first layout, rather than when the image is loaded. The image
+/// becomes a dependency of the chirp file: it is reloaded when the image changes.
+///
+/// The size is `None` when the image can't be read or decoded.
+///
+/// This argument parser only works on [`SizedImage`].
+///
+/// # Other parsers
+///
+/// [self#functions]
+///
+/// # Errors
+/// See [`HandleDslDeserError`] for possible errors.
+#[cfg(feature = "image_size")]
+pub fn to_sized_image(
+    reg: &TypeRegistry,
+    mut load_context: Option<&mut LoadContext>,
+    input: &str,
+) -> Result<SizedImage, HandleDslDeserError<Image>> {
+    let input = match quoted(reg, load_context.as_deref_mut(), input) {
+        Ok(input) => input,
+        Err(_infallible) => unreachable!(),
+    };
+    let Some(ctx) = load_context else {
+        return Err(HandleDslDeserError::<Image>::NoLoadContext);
+    };
+    let size = image_size(ctx, &input);
+    Ok(SizedImage { handle: ctx.load(String::from(input)), size })
+}
+#[cfg(feature = "image_size")]
+fn image_size(ctx: &mut LoadContext, path: &str) -> Option<UVec2> {
+    use bevy::asset::AssetPath;
+    use bevy::log::debug;
+    use bevy::render::texture::{CompressedImageFormats, ImageSampler, ImageType};
+
+    let extension = AssetPath::parse(path).get_full_extension()?;
+    let bytes = match bevy::tasks::block_on(ctx.read_asset_bytes(path.to_owned())) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            debug!("Couldn't read size of image '{path}': {err}");
+            return None;
+        }
+    };
+    let image_type = ImageType::Extension(&extension);
+    let formats = CompressedImageFormats::all();
+    match Image::from_buffer(&bytes, image_type, formats, true, ImageSampler::Default) {
+        Ok(image) => Some(image.size()),
+        Err(err) => {
+            debug!("Couldn't read size of image '{path}': {err}");
+            None
+        }
+    }
+}
+
 /// Returns the input as a `&str`, removing quotes applying backslash escapes.
 ///
+/// Raw strings (`r"raw"` or `r#"raw"#`) have their delimiters removed, but
+/// backslash escapes are not applied to them.
+///
 /// This allocates whenever a backslash is used in the input string.
 ///
 /// # Other parsers
@@ -195,6 +345,11 @@ pub fn quoted<'a>(
 }
 
 fn interpret_str(mut input: &str) -> Cow<str> {
+    if let Some(raw) = raw_string(input.as_bytes()) {
+        // SAFETY: `raw_string` only removes ASCII characters from the start
+        // and end of `input`, so `raw` is valid UTF8.
+        return Cow::Borrowed(unsafe { str::from_utf8_unchecked(raw) });
+    }
     if input.len() > 2 && input.starts_with('"') && input.ends_with('"') {
         input = &input[1..input.len() - 1];
     }
@@ -208,9 +363,109 @@ fn interpret_str(mut input: &str) -> Cow<str> {
     }
 }
 
+/// Error occuring in [`method_calls`].
+#[allow(missing_docs)] // Already documented by error message
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum MethodCallsError {
+    #[error("Expected a method name, got '{0}'")]
+    NotAMethod(char),
+    #[error("The arguments of '{0}' are missing a closing parenthesis")]
+    Unclosed(String),
+    #[error("A string in the arguments of '{0}' is missing its closing quote")]
+    UnclosedString(String),
+}
+
+/// A method call in a list of methods, see [`method_calls`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodCall<'i> {
+    /// The method name.
+    pub name: &'i str,
+    /// The method arguments, stripped of surrounding spaces.
+    pub arguments: Vec<&'i str>,
+}
+impl<'i> MethodCall<'i> {
+    /// The [`Arguments`] of this call, to pass to [`ParseDsl::method`].
+    ///
+    /// [`ParseDsl::method`]: crate::ParseDsl::method
+    #[must_use]
+    pub fn arguments(&self) -> Arguments<'i, '_> {
+        Arguments(ArgumentsInner::List(&self.arguments))
+    }
+}
+
+/// Split `input` into a list of method calls, such as
+/// `width(220px) margin(10) row`.
+///
+/// Use it for methods that accept other methods as argument. Unlike in
+/// chirp files, comments and template parameters are not supported.
+///
+/// # Errors
+/// When `input` is not a list of methods, see [`MethodCallsError`].
+pub fn method_calls(input: &str) -> Result<Vec<MethodCall<'_>>, MethodCallsError> {
+    let mut calls = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+        let name_len = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+        if name_len == 0 {
+            return Err(MethodCallsError::NotAMethod(first));
+        }
+        let (name, tail) = rest.split_at(name_len);
+        let tail = tail.trim_start();
+        let Some(tail) = tail.strip_prefix('(') else {
+            calls.push(MethodCall { name, arguments: Vec::new() });
+            rest = tail;
+            continue;
+        };
+        let (arguments, tail) = split_arguments(name, tail)?;
+        calls.push(MethodCall { name, arguments });
+        rest = tail.trim_start();
+    }
+    Ok(calls)
+}
+/// Split `input` at top level commas, up to the parenthesis closing the
+/// arguments of `name`. Returns the arguments and what follows them.
+fn split_arguments<'i>(
+    name: &str,
+    input: &'i str,
+) -> Result<(Vec<&'i str>, &'i str), MethodCallsError> {
+    let mut arguments = Vec::new();
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            ')' => {
+                let last = input[start..i].trim();
+                if !last.is_empty() || !arguments.is_empty() {
+                    arguments.push(last);
+                }
+                return Ok((arguments, &input[i + 1..]));
+            }
+            ',' if depth == 0 => {
+                arguments.push(input[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    match in_string {
+        true => Err(MethodCallsError::UnclosedString(name.to_owned())),
+        false => Err(MethodCallsError::Unclosed(name.to_owned())),
+    }
+}
+
 enum ArgumentsInner<'i, 'a> {
     Parser(&'a parser::Arguments<'i, 'a>),
     Named(Cow<'i, [u8]>),
+    List(&'a [&'i str]),
 }
 
 /// Arguments passed to a method.
@@ -270,7 +525,9 @@ pub struct Arguments<'i, 'a>(ArgumentsInner<'i, 'a>);
 impl<'i, 'a> Arguments<'i, 'a> {
     pub(crate) fn for_name(name: &'i [u8]) -> Self {
         let surrounded_by = |quote| name.starts_with(quote) && name.ends_with(quote);
-        let name = if name.len() >= 2 && (surrounded_by(b"\"") || surrounded_by(b"'")) {
+        let name = if let Some(raw) = raw_string(name) {
+            Cow::Borrowed(raw)
+        } else if name.len() >= 2 && (surrounded_by(b"\"") || surrounded_by(b"'")) {
             escape_literal(&name[1..name.len() - 1])
         } else {
             Cow::Borrowed(name)
@@ -288,6 +545,7 @@ impl<'i, 'a> Arguments<'i, 'a> {
         match &self.0 {
             ArgumentsInner::Parser(p) => p.len(),
             ArgumentsInner::Named(_) => 1,
+            ArgumentsInner::List(list) => list.len(),
         }
     }
     /// Get the `index`th argument passed to the method.
@@ -307,6 +565,7 @@ impl<'i, 'a> Arguments<'i, 'a> {
             ArgumentsInner::Parser(p) => p.get(index),
             ArgumentsInner::Named(n) if index == 0 => Some(Cow::Borrowed(n.as_ref())),
             ArgumentsInner::Named(_) => None,
+            ArgumentsInner::List(list) => list.get(index).map(|a| Cow::Borrowed(a.as_bytes())),
         }
     }
     /// Get the `index`th argument passed to the method as a `str`.
@@ -326,6 +585,7 @@ impl<'i, 'a> Arguments<'i, 'a> {
             }),
             ArgumentsInner::Named(n) if index == 0 => Some(String::from_utf8_lossy(n)),
             ArgumentsInner::Named(_) => None,
+            ArgumentsInner::List(list) => list.get(index).map(|a| Cow::Borrowed(*a)),
         }
     }
 }
@@ -334,3 +594,38 @@ impl<'i, 'a> From<&'a parser::Arguments<'i, 'a>> for Arguments<'i, 'a> {
         Self(ArgumentsInner::Parser(value))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_method_calls() {
+        let calls = method_calls(r#" width(220px) row text("a, (b") rules(1px, pct(5))"#);
+        let calls: Vec<_> = calls.unwrap().into_iter().map(|c| (c.name, c.arguments)).collect();
+        assert_eq!(calls, vec![
+            ("width", vec!["220px"]),
+            ("row", vec![]),
+            ("text", vec![r#""a, (b""#]),
+            ("rules", vec!["1px", "pct(5)"]),
+        ]);
+        assert_eq!(method_calls("empty()").unwrap()[0].arguments().len(), 0);
+        assert_eq!(method_calls("width(px(2)"), Err(MethodCallsError::Unclosed("width".into())));
+        assert_eq!(method_calls("row, column"), Err(MethodCallsError::NotAMethod(',')));
+    }
+    #[test]
+    fn from_reflect_variant_path() {
+        use bevy::render::view::Visibility;
+
+        let mut registry = TypeRegistry::new();
+        registry.register::<Visibility>();
+        let path = "bevy_render::view::visibility::Visibility::Hidden";
+        for input in ["Hidden", "Visibility::Hidden", path] {
+            let visibility = from_reflect::<Visibility>(&registry, None, input);
+            assert_eq!(visibility.unwrap(), Visibility::Hidden, "{input}");
+        }
+        let error = from_reflect::<Visibility>(&registry, None, "Visibility::Hiden").unwrap_err();
+        // The error points right after `Hiden`, not after the removed path.
+        assert_eq!(error.maybe_offset(), Some(18));
+    }
+}
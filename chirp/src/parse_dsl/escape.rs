@@ -60,3 +60,30 @@ pub fn escape_literal(to_escape: &[u8]) -> Cow<[u8]> {
         Cow::Borrowed(to_escape)
     }
 }
+
+/// The content of `literal` if it is a raw string, such as `r"raw"` or `r#"raw"#`.
+///
+/// Raw strings have no escape sequences, so the content is returned as-is.
+#[must_use]
+pub(crate) fn raw_string(literal: &[u8]) -> Option<&[u8]> {
+    let literal = literal.strip_prefix(b"r")?;
+    let hashes = literal.iter().take_while(|x| **x == b'#').count();
+    let content = literal[hashes..].strip_prefix(b"\"")?;
+    let content = content.strip_suffix(&literal[..hashes])?;
+    content.strip_suffix(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_string_content() {
+        assert_eq!(raw_string(br#"r"hello""#), Some(&b"hello"[..]));
+        assert_eq!(raw_string(br##"r#"say "hi"\n"#"##), Some(&br#"say "hi"\n"#[..]));
+        assert_eq!(raw_string(b"r#\"multi\nline\"#"), Some(&b"multi\nline"[..]));
+        assert_eq!(raw_string(br#""hello""#), None);
+        assert_eq!(raw_string(b"r#hello"), None);
+        assert_eq!(raw_string(br##"r#"hello""##), None);
+    }
+}
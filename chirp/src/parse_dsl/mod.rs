@@ -28,6 +28,7 @@ use thiserror::Error;
 
 pub use args::Arguments;
 pub use escape::escape_literal;
+pub(crate) use escape::raw_string;
 
 mod escape;
 
@@ -51,6 +52,18 @@ impl DslParseError {
     }
 }
 
+/// Methods called on a statement that can't work together,
+/// see [`ParseDsl::conflict`].
+#[derive(Debug, Error)]
+#[error("{error}")]
+pub struct MethodConflict {
+    /// The methods that may have caused the conflict. The interpreter reports
+    /// the error on the last of them called on the statement.
+    pub methods: &'static [&'static str],
+    /// The error reported by the interpreter.
+    pub error: anyhow::Error,
+}
+
 /// Context to run a method on [`ParseDsl::method`].
 ///
 /// # Call format
@@ -73,8 +86,36 @@ pub struct MethodCtx<'i, 'c, 'cc> {
     // TODO(feat): bindings/references
 }
 
+/// How the interpreter handles a method called several times on the same statement.
+///
+/// Set with the [`ParseDsl::DUPLICATE_METHODS`] constant, or the
+/// `duplicate_methods` meta-attribute of [`parse_dsl_impl`].
+///
+/// Calling the same method twice with the same arguments is always accepted,
+/// but reported as a warning. Template extras (methods added after a template
+/// call, as in `button!("OK")(width(px(200)))`) always override the methods
+/// of the template they extend, regardless of `DuplicateMethods`.
+///
+/// [`parse_dsl_impl`]: mod@crate::parse_dsl_impl
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateMethods {
+    /// Calling the same method twice with different arguments is an error.
+    #[default]
+    Error,
+    /// All calls are applied in order, so (for setter methods) the last call wins.
+    ///
+    /// A warning is logged nevertheless.
+    LastWins,
+}
+
 /// A [`DslBundle`] that can be parsed.
 pub trait ParseDsl: DslBundle {
+    /// How to handle methods called several times on the same statement.
+    ///
+    /// Note that only the value of the outermost DSL (the one used for the
+    /// chirp loader) is considered, delegated DSLs' values are ignored.
+    const DUPLICATE_METHODS: DuplicateMethods = DuplicateMethods::Error;
+
     /// Apply method named `name` to `self`.
     ///
     /// # Calling format
@@ -90,11 +131,46 @@ pub trait ParseDsl: DslBundle {
     ///
     /// [parent node]: cuicui_dsl::dsl#parent-node
     fn method(&mut self, ctx: MethodCtx) -> Result<()>;
+
+    /// The deprecation message of the method named `name`, `None` if it is
+    /// not deprecated.
+    ///
+    /// The interpreter reports calls to deprecated methods as warnings, but
+    /// still calls them. [`parse_dsl_impl`] implements this for methods with
+    /// the `#[parse_dsl(deprecated = "message")]` attribute.
+    ///
+    /// [`parse_dsl_impl`]: mod@crate::parse_dsl_impl
+    fn deprecation(&self, _name: &str) -> Option<&'static str> {
+        None
+    }
+
+    /// Check that the methods called on a statement work together, called
+    /// right before the statement is spawned.
+    ///
+    /// The interpreter reports the returned [`MethodConflict`] as an error and
+    /// doesn't spawn the scene. [`parse_dsl_impl`] implements this with the
+    /// `conflicts` meta-attribute.
+    ///
+    /// [`parse_dsl_impl`]: mod@crate::parse_dsl_impl
+    fn conflict(&self) -> Option<MethodConflict> {
+        None
+    }
+
+    /// Add to `namespaces` the namespace of each DSL with a `name` method,
+    /// `self` first, then its delegates.
+    ///
+    /// Only the outermost DSL's method is called, so the interpreter reports
+    /// a warning when several DSLs have a `name` method. Chirp files can call
+    /// a specific DSL's method with `namespace:name`. [`parse_dsl_impl`]
+    /// implements this with the `namespace` meta-attribute.
+    ///
+    /// [`parse_dsl_impl`]: mod@crate::parse_dsl_impl
+    fn method_namespaces(&self, _name: &str, _namespaces: &mut Vec<&'static str>) {}
 }
 impl ParseDsl for BaseDsl {
     fn method(&mut self, data: MethodCtx) -> Result<()> {
         let MethodCtx { name, arguments: args, .. } = data;
-        if name == "named" {
+        if matches!(name, "named" | "base:named") {
             let name = args.get(0).unwrap();
             let str = String::from(String::from_utf8_lossy(name.as_ref()));
             self.named(str);
@@ -103,4 +179,151 @@ impl ParseDsl for BaseDsl {
             Err(DslParseError::new(name).into())
         }
     }
+    fn method_namespaces(&self, name: &str, namespaces: &mut Vec<&'static str>) {
+        if name == "named" {
+            namespaces.push("base");
+        }
+    }
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod tests {
+    use bevy::reflect::TypeRegistry;
+    use cuicui_dsl::{BaseDsl, DslBundle, EntityCommands};
+
+    use super::{Arguments, MethodConflict, MethodCtx, ParseDsl};
+
+    #[derive(Default)]
+    struct TestDsl {
+        inner: BaseDsl,
+        width: Option<String>,
+    }
+    impl DslBundle for TestDsl {
+        fn insert(&mut self, cmds: &mut EntityCommands) {
+            self.inner.insert(cmds);
+        }
+    }
+    #[crate::parse_dsl_impl(
+        cuicui_chirp_path = crate,
+        namespace = test,
+        delegate = inner,
+        conflicts = Self::empty,
+        parts(trait_methods, extra_methods),
+    )]
+    impl TestDsl {
+        #[parse_dsl(alias = "w", alias = "wide")]
+        fn width(&mut self, width: &str) {
+            self.width = Some(width.to_owned());
+        }
+        #[parse_dsl(alias = "old_w", deprecated = "use `width` instead")]
+        fn old_width(&mut self, width: &str) {
+            self.width = Some(format!("old {width}"));
+        }
+        fn empty(&self) -> Option<MethodConflict> {
+            let empty = self.width.as_deref() == Some("");
+            let error = || anyhow::anyhow!("width is empty");
+            empty.then(|| MethodConflict { methods: &["width", "old_width"], error: error() })
+        }
+    }
+    trait Sized2d {
+        fn width(&mut self, width: &str);
+        fn height(&mut self, height: &str);
+    }
+    #[crate::parse_dsl_impl(cuicui_chirp_path = crate, part = trait_methods)]
+    impl Sized2d for TestDsl {
+        fn width(&mut self, width: &str) {
+            self.width = Some(format!("trait {width}"));
+        }
+        fn height(&mut self, height: &str) {
+            self.width = Some(format!("height {height}"));
+        }
+    }
+    #[crate::parse_dsl_impl(cuicui_chirp_path = crate, part = extra_methods)]
+    impl TestDsl {
+        #[parse_dsl(deprecated = "use `height` instead")]
+        fn tall(&mut self, height: &str) {
+            self.width = Some(format!("tall {height}"));
+        }
+        #[parse_dsl(rename = "named")]
+        fn label(&mut self, name: &str) {
+            self.width = Some(format!("label {name}"));
+        }
+    }
+    fn call(dsl: &mut TestDsl, name: &str, argument: &str) -> anyhow::Result<()> {
+        let registry = TypeRegistry::new();
+        let arguments = Arguments::for_name(argument.as_bytes());
+        dsl.method(MethodCtx { name, arguments, ctx: None, registry: &registry })
+    }
+
+    #[test]
+    fn aliases() {
+        let mut dsl = TestDsl::default();
+        for name in ["width", "w", "wide"] {
+            dsl.width = None;
+            call(&mut dsl, name, "10").unwrap();
+            assert_eq!(dsl.width.as_deref(), Some("10"), "{name}");
+        }
+        call(&mut dsl, "old_w", "10").unwrap();
+        assert_eq!(dsl.width.as_deref(), Some("old 10"));
+        assert!(call(&mut dsl, "wid", "10").is_err());
+    }
+    #[test]
+    fn deprecations() {
+        let dsl = TestDsl::default();
+        assert_eq!(dsl.deprecation("width"), None);
+        assert_eq!(dsl.deprecation("w"), None);
+        assert_eq!(dsl.deprecation("named"), None);
+        assert_eq!(dsl.deprecation("old_width"), Some("use `width` instead"));
+        assert_eq!(dsl.deprecation("old_w"), Some("use `width` instead"));
+    }
+    #[test]
+    fn conflicts() {
+        let mut dsl = TestDsl::default();
+        assert!(dsl.conflict().is_none());
+        call(&mut dsl, "width", "10").unwrap();
+        assert!(dsl.conflict().is_none());
+        call(&mut dsl, "width", "").unwrap();
+        let conflict = dsl.conflict().unwrap();
+        assert_eq!(conflict.methods, ["width", "old_width"]);
+        assert_eq!(conflict.to_string(), "width is empty");
+    }
+    #[test]
+    fn parts() {
+        let mut dsl = TestDsl::default();
+        call(&mut dsl, "height", "10").unwrap();
+        assert_eq!(dsl.width.as_deref(), Some("height 10"));
+        call(&mut dsl, "tall", "10").unwrap();
+        assert_eq!(dsl.width.as_deref(), Some("tall 10"));
+        // Methods of the main block win over the ones of its parts.
+        call(&mut dsl, "width", "10").unwrap();
+        assert_eq!(dsl.width.as_deref(), Some("10"));
+        call(&mut dsl, "named", "node").unwrap();
+        assert!(call(&mut dsl, "wid", "10").is_err());
+
+        assert_eq!(dsl.deprecation("tall"), Some("use `height` instead"));
+        assert_eq!(dsl.deprecation("height"), None);
+    }
+    #[test]
+    fn namespaces() {
+        let mut dsl = TestDsl::default();
+        call(&mut dsl, "test:named", "node").unwrap();
+        assert_eq!(dsl.width.as_deref(), Some("label node"));
+        call(&mut dsl, "test:w", "10").unwrap();
+        assert_eq!(dsl.width.as_deref(), Some("10"));
+        call(&mut dsl, "base:named", "node").unwrap();
+        assert_eq!(dsl.width.as_deref(), Some("10"));
+        assert!(call(&mut dsl, "test:label", "10").is_err());
+        assert!(call(&mut dsl, "base:width", "10").is_err());
+        assert!(call(&mut dsl, "other:named", "node").is_err());
+
+        assert_eq!(dsl.deprecation("test:tall"), Some("use `height` instead"));
+        assert_eq!(dsl.deprecation("base:tall"), None);
+
+        let mut namespaces = Vec::new();
+        dsl.method_namespaces("named", &mut namespaces);
+        assert_eq!(namespaces, ["test", "base"]);
+        namespaces.clear();
+        dsl.method_namespaces("height", &mut namespaces);
+        assert_eq!(namespaces, ["test"]);
+    }
 }
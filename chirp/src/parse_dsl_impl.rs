@@ -10,8 +10,11 @@
 //!
 //! #[parse_dsl_impl(
 //!     cuicui_chirp_path = ::cuicui_chirp,
+//!     namespace = my,
 //!     delegate = inner,
 //!     type_parsers(color = css_color_parser),
+//!     duplicate_methods = LastWins,
+//!     conflicts = Self::check_conflicts,
 //!     set_params<>,
 //! )]
 //! impl MyDsl {
@@ -30,6 +33,10 @@
 //!     // `parse_dsl(ignore)`
 //!     #[parse_dsl(ignore)]
 //!     pub fn ignored_method(&mut self, style: &Style) {}
+//!
+//!     // Generic methods can't be called from chirp files, they must be ignored.
+//!     #[parse_dsl(ignore)]
+//!     pub fn generic_method(&mut self, bundle: impl Bundle) {}
 //! }
 //! ```
 //!
@@ -49,6 +56,9 @@
 //! `parse_dsl_impl` also accepts a `parse_dsl` attribute on individual methods
 //! within the `impl` block, see [`parse_dsl_impl::parse_dsl`] for details.
 //!
+//! The methods of a DSL can be split over several `impl` blocks, including
+//! trait `impl` blocks, see [`part`] and [`parts`].
+//!
 //! [`ParseDsl::method`]: crate::ParseDsl::method
 //! [`parse_dsl_impl::parse_dsl`]: parse_dsl
 #![allow(unused_variables, clippy::needless_pass_by_value)]
@@ -64,11 +74,43 @@ pub struct Ident;
 #[doc(hidden)]
 pub struct Ignore;
 
-/// Do not add this method to the chirp methods list.
+/// Configure how a method is exposed to chirp files.
+///
+/// Accepted `parse_dsl` meta-attributes are:
+///
+/// - `ignore`: Do not add this method to the chirp methods list. Note that any
+///   function other than `&mut self` methods are already ignored, and
+///   adding a `parse_dsl` attribute to them is an error.
+/// - `alias = "name"`: The method can also be called as `name` in chirp files.
+///   Can be repeated to add several aliases.
+/// - `rename = "name"`: The method is called `name` in chirp files rather than
+///   its rust name. Use it to avoid shadowing a method of a `delegate` DSL.
+/// - `deprecated = "message"`: Calling this method (or one of its aliases) in
+///   a chirp file logs a warning with `message`. The method is still called.
 ///
-/// Note that any function other than `&mut self` methods are already ignored.
+/// Use `alias` and `deprecated` to rename methods without breaking existing
+/// chirp files: rename the method, and add its old name as alias of a
+/// deprecated method calling the new one.
+///
+/// With `namespace`, methods can also be called as `namespace:name`, see
+/// [`namespace`].
+///
+/// Methods with a `#[cfg(…)]` attribute are only exposed to chirp files when
+/// the `cfg` holds. `#[cfg_attr(predicate, parse_dsl(ignore))]` only ignores
+/// the method when `predicate` holds. Use both to swap a method for another
+/// one of the same chirp name depending on a feature:
+///
+/// ```ignore
+/// #[parse_dsl_impl]
+/// impl MyDsl {
+///     #[cfg_attr(feature = "fancy", parse_dsl(ignore))]
+///     fn border(&mut self, width: f32) {}
 ///
-/// This is the only accepted `parse_dsl` meta-attribute.
+///     #[cfg(feature = "fancy")]
+///     #[parse_dsl(alias = "border")]
+///     fn fancy_border(&mut self, width: f32) {}
+/// }
+/// ```
 ///
 /// # Example
 /// ```ignore
@@ -78,10 +120,20 @@ pub struct Ignore;
 /// impl MyDsl {
 ///     #[parse_dsl(ignore)]
 ///     fn to_ignore(&mut self) {}
+///
+///     // Can be called as `width(10px)` or `w(10px)`
+///     #[parse_dsl(alias = "w")]
+///     fn width(&mut self, rule: Rule) {}
+///
+///     // Warns "'main_width' is deprecated: use `width` instead"
+///     #[parse_dsl(deprecated = "use `width` instead")]
+///     fn main_width(&mut self, rule: Rule) {
+///         self.width(rule);
+///     }
 ///     // ...
 /// # }
 /// ```
-pub fn parse_dsl(ignore: Ignore) {}
+pub fn parse_dsl(ignore: Ignore, alias: &str, rename: &str, deprecated: &str) {}
 
 /// Specify which path to use for the `cuicui_chirp` crate.
 ///
@@ -133,6 +185,136 @@ pub fn cuicui_chirp_path(alternate_path: Path) {}
 /// [`dsl!`]: cuicui_dsl::dsl
 pub fn delegate(inner_field: Ident) {}
 
+/// Name of this DSL in chirp files, to call its methods with `name:method`.
+///
+/// **Default**: None, the DSL's methods can only be called without namespace.
+///
+/// When a DSL and its `delegate` both have a method with the same name, only
+/// the outer one is called, and the chirp interpreter logs a warning. With
+/// namespaces, chirp files pick which one to call: `ui:image` only calls
+/// the `image` method of the DSL with `namespace = ui`, it's an error if this
+/// DSL has no `image` method.
+///
+/// Prefer this to rename methods with `#[parse_dsl(rename = "new_name")]`
+/// when methods should keep their name, for example because they are common
+/// to several DSLs.
+///
+/// # Example
+/// ```ignore
+/// use cuicui_chirp::parse_dsl_impl;
+///
+/// #[parse_dsl_impl(namespace = fx, delegate = inner)]
+/// impl FxDsl {
+///     // Shadows `UiDsl::image`, call `ui:image` in chirp files to use
+///     // the `UiDsl` one.
+///     pub fn image(&mut self, shader: &str) {}
+/// }
+/// ```
+pub fn namespace(name: Ident) {}
+
+/// What to do when the same method is called several times on the same entity.
+///
+/// **Default**: `Error`, calling a method twice with different arguments is an error.
+///
+/// With `LastWins`, the last call overrides the previous ones, and a warning
+/// is logged. Calling a method twice with the same arguments always only logs
+/// a warning. See [`DuplicateMethods`] for details.
+///
+/// # Example
+/// ```ignore
+/// use cuicui_chirp::parse_dsl_impl;
+///
+/// #[parse_dsl_impl(duplicate_methods = LastWins)]
+/// impl MyDsl {
+///     // ...
+/// # }
+/// ```
+///
+/// [`DuplicateMethods`]: crate::parse_dsl::DuplicateMethods
+pub fn duplicate_methods(semantics: Ident) {}
+
+/// Function checking that the methods called on a statement work together.
+///
+/// **Default**: None, only the `delegate` DSL is checked.
+///
+/// The function takes a `&Self` and returns an `Option<E>` where `E` converts
+/// into a [`MethodConflict`]. It is called before the statement is spawned,
+/// see [`ParseDsl::conflict`]. With `delegate`, the delegate DSL is checked
+/// when the function returns `None`.
+///
+/// # Example
+/// ```ignore
+/// use cuicui_chirp::{parse_dsl_impl, MethodConflict};
+///
+/// #[parse_dsl_impl(conflicts = Self::conflict)]
+/// impl MyDsl {
+///     // ...
+/// # }
+/// impl MyDsl {
+///     fn conflict(&self) -> Option<MethodConflict> {
+///         let both = self.hidden && self.highlighted;
+///         both.then(|| MethodConflict {
+///             methods: &["hidden", "highlighted"],
+///             error: anyhow::anyhow!("A hidden entity can't be highlighted"),
+///         })
+///     }
+/// }
+/// ```
+///
+/// [`MethodConflict`]: crate::MethodConflict
+/// [`ParseDsl::conflict`]: crate::ParseDsl::conflict
+pub fn conflicts(check: Path) {}
+
+/// Split the chirp methods of a DSL over several `impl` blocks, naming this
+/// block `name`.
+///
+/// **Default**: None, this block generates the `ParseDsl` impl.
+///
+/// A `part` block doesn't implement `ParseDsl`, instead, the block with
+/// `parts(name)` calls its methods. Only `cuicui_chirp_path` and `type_parsers`
+/// are accepted with `part`, other meta-attributes go on the `parts` block,
+/// including `namespace`.
+///
+/// `parse_dsl_impl` also works on trait `impl` blocks, use `part` to add the
+/// methods of a trait implemented by a DSL to its chirp methods.
+///
+/// This adds a hidden `name` and `name_deprecation` method to the DSL.
+///
+/// # Example
+/// ```ignore
+/// use cuicui_chirp::parse_dsl_impl;
+///
+/// #[parse_dsl_impl(part = animation_methods)]
+/// impl Animated for MyDsl {
+///     fn fade_in(&mut self, duration: f32) { /* ... */ }
+/// }
+/// ```
+/// See [`parts`] for the block implementing `ParseDsl`.
+pub fn part(name: Ident) {}
+
+/// Call the methods of the `part` blocks named in `parts(..)` from chirp files.
+///
+/// **Default**: None, only methods of this block and the `delegate` DSL are called.
+///
+/// When a method name exists in several blocks, the first one found is called,
+/// looking in this block first, then in each part in the order of `parts(..)`,
+/// then in the `delegate` DSL.
+///
+/// # Example
+/// ```ignore
+/// use cuicui_chirp::parse_dsl_impl;
+///
+/// #[parse_dsl_impl(delegate = inner, parts(animation_methods, style_methods))]
+/// impl MyDsl {
+///     // ...
+/// # }
+/// #[parse_dsl_impl(part = style_methods)]
+/// impl MyDsl {
+///     // ...
+/// # }
+/// ```
+pub fn parts(names: &[Ident]) {}
+
 /// Use a custom set of type bounds on the `impl` blocks generics.
 ///
 /// **Default**: The default is whatever the bounds are in the `impl` generic
@@ -1,66 +1,29 @@
 //! [`ReflectDsl`] and helper types.
 //!
 //! Instead of using [`ParseDsl`]
-use std::{any::type_name, convert::Infallible, fmt, marker::PhantomData};
+use std::{any::type_name, any::TypeId, marker::PhantomData, str};
 
 use anyhow::Result;
 use bevy::ecs::prelude::Bundle;
 use bevy::prelude::{Deref, DerefMut};
 use bevy::reflect::erased_serde::__private::serde::de::DeserializeSeed;
-use bevy::reflect::{serde::TypedReflectDeserializer, Reflect, Struct};
+use bevy::reflect::{serde::TypedReflectDeserializer, Reflect, ReflectFromReflect};
+use bevy::reflect::{Enum, ReflectMut, Struct, TypeInfo, TypeRegistration};
 use cuicui_dsl::DslBundle;
-use thiserror::Error;
+use ron::error::SpannedError;
 
+use crate::parse_dsl::args::{strip_variant_path, ReflectDslDeserError};
 use crate::parse_dsl::{MethodCtx, ParseDsl};
 
-/// Occurs in [`ReflectDsl::typed_method`].
-#[derive(Error)]
-enum ReflectDslError<T> {
-    #[error(
-        "Method on `ReflectDsl` was called with not exactly one argument. \
-        Try having double parenthesis around the method argument"
-    )]
-    NotExactlyOneArgument,
-    #[error(
-        "Tried to set the field '{0}' of ReflectDsl<{ty}>, but {ty} \
-        doesn't have such a field",
-        ty=type_name::<T>()
-    )]
-    BadField(String),
-    #[error(
-        "The field {path} of '{ty}' is not registered. \
-        Please register the type '{missing}' to be able to use ReflectDsl<{ty}>.",
-        ty=type_name::<T>(),
-    )]
-    NotRegistered { path: String, missing: String },
-    #[error("Failed to deserialize ReflectDsl<{}>: {0}", type_name::<T>())]
-    BadDeser(anyhow::Error),
-    #[error("This error never happens")]
-    _Ignonre(PhantomData<fn(T)>, Infallible),
-}
-impl<T> fmt::Debug for ReflectDslError<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use ReflectDslError::{BadDeser, BadField, NotRegistered, _Ignonre};
-        match self {
-            BadField(field) => f.debug_tuple("BadField").field(field).finish(),
-            NotRegistered { path, missing } => f
-                .debug_struct("NotRegistered")
-                .field("path", path)
-                .field("missing", missing)
-                .finish(),
-            BadDeser(error) => f.debug_tuple("BadDeser").field(error).finish(),
-            Self::NotExactlyOneArgument => write!(f, "NotExactlyOneArgument"),
-            _Ignonre(..) => unreachable!(),
-        }
-    }
-}
-
 /// A `serde` deserializer used to parse some `input` into a `Box<dyn Reflect>`.
 ///
 /// This is used in [`ReflectDsl`] to deserialize method arguments (`T`'s field values).
 /// A default implementation is provided with [`RonFormat`].
 pub trait Format {
     /// Deserialize into a `Box<dyn Reflect>`, any error is propagated by [`ReflectDsl::method`].
+    ///
+    /// Return a [`ron::error::SpannedError`] to point to the error location
+    /// within the method argument in error messages.
     #[allow(clippy::missing_errors_doc)] // false+: We can't say what our users will fail with.
     fn deserialize(input: &[u8], de: TypedReflectDeserializer) -> Result<Box<dyn Reflect>>;
 }
@@ -70,11 +33,16 @@ pub trait Format {
 pub struct RonFormat;
 impl Format for RonFormat {
     fn deserialize(input: &[u8], de: TypedReflectDeserializer) -> Result<Box<dyn Reflect>> {
-        Ok(de.deserialize(&mut ron::de::Deserializer::from_bytes(input)?)?)
+        let mut ron_de = ron::de::Deserializer::from_bytes(input)?;
+        match de.deserialize(&mut ron_de) {
+            Ok(value) => Ok(value),
+            Err(err) => Err(ron_de.span_error(err).into()),
+        }
     }
 }
 
-/// Automatic [`ParseDsl`] implementation for any [`Bundle`] + [`Reflect`] `struct`.
+/// Automatic [`ParseDsl`] implementation for any [`Bundle`] + [`Reflect`] `struct`
+/// or `enum`.
 ///
 /// If you find using the `parse_dsl_impl` macro burdensome, and just want to
 /// use any bevy `Bundle` as a DSL, you can use `ReflectDsl` to use the `struct`
@@ -133,6 +101,23 @@ impl Format for RonFormat {
 /// ));
 /// ```
 ///
+/// # Enums
+///
+/// When `T` is an `enum`, such as `Visibility` or your own state component,
+/// the methods are `T`'s variant names in snake_case. Each method sets the
+/// component to its variant:
+///
+/// - Unit variants take no arguments: `hidden` for `Visibility::Hidden`.
+/// - Tuple variants take their fields as arguments: `loading(0.5)` for
+///   `State::Loading(f32)`.
+/// - Struct variants take their named fields: `moving(speed: 2.0)` for
+///   `State::Moving { speed: f32 }`.
+///
+/// ```text
+/// Entity(hidden)
+/// Entity(moving(speed: 2.0))
+/// ```
+///
 /// # Caveats
 ///
 /// This doesn't work with the `dsl!` macro. You can only use `ReflectDsl` with
@@ -140,14 +125,14 @@ impl Format for RonFormat {
 ///
 /// [refl-deser]: https://docs.rs/bevy_reflect/latest/bevy_reflect/#serialization
 #[derive(Debug, Clone, Deref, DerefMut)]
-pub struct ReflectDsl<T: Struct, D: DslBundle = (), F: Format = RonFormat> {
+pub struct ReflectDsl<T: Reflect, D: DslBundle = (), F: Format = RonFormat> {
     inner: Option<T>,
     #[deref]
     delegate_dsl: D,
     _format: PhantomData<F>,
 }
 
-impl<T: Default + Struct, D: DslBundle, F: Format> Default for ReflectDsl<T, D, F> {
+impl<T: Default + Reflect, D: DslBundle, F: Format> Default for ReflectDsl<T, D, F> {
     fn default() -> Self {
         Self {
             inner: Some(T::default()),
@@ -158,52 +143,151 @@ impl<T: Default + Struct, D: DslBundle, F: Format> Default for ReflectDsl<T, D,
 }
 impl<T, D, F> DslBundle for ReflectDsl<T, D, F>
 where
-    T: Bundle + Default + Struct,
+    T: Bundle + Default + Reflect,
     D: DslBundle,
     F: Format,
 {
     fn insert(&mut self, cmds: &mut cuicui_dsl::EntityCommands) {
-        // unwrap: This `Self::default` in `Some` state, and only becomes `None` when `insert`
-        // is called. Since it is only called once, it is fine to unwrap.
-        cmds.insert(self.inner.take().unwrap());
+        if let Some(inner) = self.inner.take() {
+            cmds.insert(inner);
+        }
         self.delegate_dsl.insert(cmds);
     }
 }
 impl<T, D, F> ReflectDsl<T, D, F>
 where
-    T: Bundle + Default + Struct,
+    T: Bundle + Default + Reflect,
     D: DslBundle,
     F: Format,
 {
     /// This is just so the error type is easier to convert in the `ParseDsl::method` impl.
-    #[allow(deprecated)]
-    fn typed_method(&mut self, ctx: &MethodCtx) -> Result<(), ReflectDslError<T>> {
-        use ReflectDslError::{BadDeser, BadField};
-        // unwrap: Same logic as in `DslBundle::insert`
-        let inner = self.inner.as_mut().unwrap();
-        if ctx.arguments.len() != 1 {
-            return Err(ReflectDslError::NotExactlyOneArgument);
+    fn typed_method(&mut self, ctx: &MethodCtx) -> Result<(), ReflectDslDeserError> {
+        use ReflectDslDeserError as Error;
+
+        let ty = type_name::<T>();
+        let Some(inner) = self.inner.as_mut() else {
+            return Err(Error::AlreadyInserted { field: ctx.name.to_string(), ty });
+        };
+        match inner.reflect_mut() {
+            ReflectMut::Struct(inner) => Self::set_field(inner, ctx),
+            ReflectMut::Enum(inner) => Self::set_variant(inner, ctx),
+            _ => Err(Error::NotStructOrEnum(ty)),
         }
-        let argument = ctx.arguments.get(0).unwrap();
+    }
+    /// Set the `ctx.name` field of `inner` to the method argument.
+    fn set_field(inner: &mut dyn Struct, ctx: &MethodCtx) -> Result<(), ReflectDslDeserError> {
+        use ReflectDslDeserError as Error;
+
+        let field = || ctx.name.to_string();
+        let ty = type_name::<T>();
+        let (1, Some(argument)) = (ctx.arguments.len(), ctx.arguments.get(0)) else {
+            let got = ctx.arguments.len();
+            return Err(Error::NotExactlyOneArgument { field: field(), ty, got });
+        };
         let Some(field_to_update) = inner.field_mut(ctx.name) else {
-            return Err(BadField(ctx.name.to_string()));
+            return Err(Error::BadField { field: field(), ty });
+        };
+        let Some(registration) = ctx.registry.get(field_to_update.type_id()) else {
+            let missing = field_to_update.reflect_type_path().to_string();
+            return Err(Error::FieldNotRegistered { field: field(), ty, missing });
+        };
+        let expected = registration.type_info().type_path();
+        let (argument, prefix) = match str::from_utf8(&argument) {
+            Ok(argument) => {
+                let (argument, prefix) = strip_variant_path(argument, registration.type_info());
+                (argument.as_bytes(), prefix)
+            }
+            Err(_) => (&argument[..], 0),
+        };
+        let de = TypedReflectDeserializer::new(registration, ctx.registry);
+        let field_value = match F::deserialize(argument, de) {
+            Ok(value) => value,
+            Err(error) => match error.downcast::<SpannedError>() {
+                Ok(mut ron) => {
+                    if ron.position.line <= 1 {
+                        ron.position.col += prefix;
+                    }
+                    return Err(Error::RonDeser(Box::new(ron), expected));
+                }
+                Err(error) => return Err(Error::BadFieldDeser { field: field(), ty, error }),
+            },
+        };
+        let bad_reflect = || Error::BadFieldReflect { field: field(), ty, expected };
+        let field_value = concrete(registration, field_value).ok_or_else(bad_reflect)?;
+        field_to_update.set(field_value).map_err(|_| bad_reflect())
+    }
+    /// Set `inner` to the variant with the `ctx.name` snake_case name, the
+    /// method arguments are the variant fields.
+    fn set_variant(inner: &mut dyn Enum, ctx: &MethodCtx) -> Result<(), ReflectDslDeserError> {
+        use ReflectDslDeserError as Error;
+
+        let variant = || ctx.name.to_string();
+        let ty = type_name::<T>();
+        let registration = ctx.registry.get(TypeId::of::<T>()).ok_or(Error::NotRegistered(ty))?;
+        let TypeInfo::Enum(info) = registration.type_info() else {
+            return Err(Error::NotStructOrEnum(ty));
         };
-        let id = field_to_update.type_id();
-        let not_registered = || ReflectDslError::NotRegistered {
-            path: ctx.name.to_string(),
-            missing: field_to_update.type_name().to_string(),
+        let is_called = |name: &&&str| snake_case(name) == ctx.name;
+        let Some(name) = info.variant_names().iter().find(is_called) else {
+            return Err(Error::BadVariant { variant: variant(), ty });
         };
-        let registration = ctx.registry.get(id).ok_or_else(not_registered)?;
+        // The variant as written in ron: `Name` or `Name(arguments)`.
+        let mut input = name.as_bytes().to_vec();
+        let prefix = input.len() + 1;
+        if !ctx.arguments.is_empty() {
+            input.push(b'(');
+            for i in 0..ctx.arguments.len() {
+                if i != 0 {
+                    input.extend_from_slice(b", ");
+                }
+                input.extend_from_slice(&ctx.arguments.get(i).unwrap());
+            }
+            input.push(b')');
+        }
         let de = TypedReflectDeserializer::new(registration, ctx.registry);
-        let field_value = F::deserialize(&argument, de).map_err(BadDeser)?;
-        // unwrap: Error should never happen, since we get the registration for field.
-        field_to_update.set(field_value).unwrap();
-        Ok(())
+        let value = match F::deserialize(&input, de) {
+            Ok(value) => value,
+            Err(error) => match error.downcast::<SpannedError>() {
+                Ok(mut ron) => {
+                    if ron.position.line <= 1 {
+                        ron.position.col = ron.position.col.saturating_sub(prefix);
+                    }
+                    return Err(Error::RonDeser(Box::new(ron), ty));
+                }
+                Err(error) => return Err(Error::BadVariantDeser { variant: variant(), ty, error }),
+            },
+        };
+        let value = concrete(registration, value).ok_or(Error::BadReflect(ty))?;
+        inner.set(value).map_err(|_| Error::BadReflect(ty))
+    }
+}
+/// `value` as the concrete type of `registration`.
+///
+/// `TypedReflectDeserializer` returns dynamic proxies (such as `DynamicStruct`)
+/// for types without `ReflectDeserialize`, this converts them into the concrete type.
+// NOTE: we compare type ids rather than using `Reflect::is_dynamic`, since
+// `DynamicEnum::is_dynamic` returns `false`.
+fn concrete(registration: &TypeRegistration, value: Box<dyn Reflect>) -> Option<Box<dyn Reflect>> {
+    if value.as_any().type_id() == registration.type_id() {
+        return Some(value);
+    }
+    let from_reflect = registration.data::<ReflectFromReflect>()?;
+    from_reflect.from_reflect(value.as_ref())
+}
+/// `name` in snake_case, such as `mouse_locked` for `MouseLocked`.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
     }
+    snake
 }
 impl<T, D, F> ParseDsl for ReflectDsl<T, D, F>
 where
-    T: Bundle + Default + Struct,
+    T: Bundle + Default + Reflect,
     D: DslBundle,
     F: Format,
 {
@@ -0,0 +1,200 @@
+//! Configure how chirp file errors and warnings are reported, see [`ReportConfig`].
+//!
+//! By default, reports are logged with `bevy::log`, rendered with miette's
+//! graphical report handler when the `fancy_errors` feature is enabled.
+//!
+//! Chirp files are interpreted in bevy's asset loader threads, so the
+//! configuration is global. Set it with [`set_config`] before loading chirp
+//! files, for example when building your app:
+//!
+//! ```
+//! use std::sync::{Arc, Mutex};
+//! use cuicui_chirp::report::{self, ReportConfig, ReportFormat};
+//!
+//! // Collect reports, for example to show them in a dev overlay panel.
+//! let collected = Arc::new(Mutex::new(Vec::new()));
+//! let sink = Arc::clone(&collected);
+//! report::set_config(ReportConfig {
+//!     format: ReportFormat::Json,
+//!     sink: Some(Arc::new(move |report| {
+//!         sink.lock().unwrap().push(report.rendered.to_owned());
+//!     })),
+//!     ..Default::default()
+//! });
+//! ```
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use bevy::log::{error, warn};
+use miette::{Diagnostic, JSONReportHandler, NarratableReportHandler, Severity};
+
+static CONFIG: RwLock<Option<ReportConfig>> = RwLock::new(None);
+
+/// How to render chirp errors and warnings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Source code snippets with the error spans underlined, rendered by
+    /// miette's `GraphicalReportHandler`.
+    ///
+    /// Without the `fancy_errors` feature, reports are a plain list of errors
+    /// and their position in the file.
+    #[default]
+    Graphical,
+    /// Plain text, each piece of information on its own line, rendered by
+    /// miette's `NarratableReportHandler`. Fits narrow terminals and log files.
+    Narrow,
+    /// A JSON object, rendered by miette's `JSONReportHandler`. Use it to
+    /// collect reports with other tools, for example in CI.
+    Json,
+}
+
+/// An error or warning about a chirp file, passed to [`ReportConfig::sink`].
+pub struct Report<'a> {
+    /// [`Severity::Error`] if the chirp file failed to load,
+    /// [`Severity::Warning`] for suspicious but valid chirp files.
+    pub severity: Severity,
+    /// The report, rendered with [`ReportConfig::format`].
+    pub rendered: &'a str,
+    /// The report itself, to render it another way or read its labels.
+    pub diagnostic: &'a dyn Diagnostic,
+}
+impl fmt::Debug for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Report")
+            .field("severity", &self.severity)
+            .field("rendered", &self.rendered)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A function receiving all chirp error and warning [`Report`]s.
+///
+/// It is called from the thread that loaded the chirp file, usually one of
+/// bevy's asset loader threads.
+pub type ReportSink = Arc<dyn Fn(&Report) + Send + Sync>;
+
+/// How chirp errors and warnings are reported, see the [module docs](self).
+#[derive(Clone)]
+pub struct ReportConfig {
+    /// How to render reports, [`ReportFormat::Graphical`] by default.
+    pub format: ReportFormat,
+    /// Whether graphical reports use ANSI colors, `true` by default.
+    ///
+    /// Even when `true`, colors are disabled when the `NO_COLOR` environment
+    /// variable is set, or when the output is not a terminal.
+    pub color: bool,
+    /// Whether to log reports with `bevy::log`, `true` by default.
+    pub log: bool,
+    /// Also send reports to this function, `None` by default.
+    pub sink: Option<ReportSink>,
+}
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self { format: ReportFormat::Graphical, color: true, log: true, sink: None }
+    }
+}
+impl fmt::Debug for ReportConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sink = self.sink.as_ref().map(|_| "Fn(&Report)");
+        f.debug_struct("ReportConfig")
+            .field("format", &self.format)
+            .field("color", &self.color)
+            .field("log", &self.log)
+            .field("sink", &sink)
+            .finish()
+    }
+}
+impl ReportConfig {
+    /// Render `diagnostic` with this configuration's format.
+    ///
+    /// # Panics
+    /// If the report handler returns a formatting error.
+    #[must_use]
+    pub fn render(&self, diagnostic: &dyn Diagnostic) -> String {
+        let mut rendered = String::new();
+        let result = match self.format {
+            #[cfg(feature = "fancy_errors")]
+            ReportFormat::Graphical => {
+                use miette::{GraphicalReportHandler, GraphicalTheme};
+
+                let theme = match self.color {
+                    true => GraphicalTheme::default(),
+                    false => GraphicalTheme::unicode_nocolor(),
+                };
+                GraphicalReportHandler::new_themed(theme)
+                    .with_context_lines(2)
+                    .with_width(90)
+                    .with_footer("\n".into())
+                    .render_report(&mut rendered, diagnostic)
+            }
+            #[cfg(not(feature = "fancy_errors"))]
+            ReportFormat::Graphical => fmt::write(&mut rendered, format_args!("{diagnostic:#}")),
+            ReportFormat::Narrow => NarratableReportHandler::new()
+                .with_context_lines(2)
+                .render_report(&mut rendered, diagnostic),
+            ReportFormat::Json => JSONReportHandler::new().render_report(&mut rendered, diagnostic),
+        };
+        result.expect("Failed to render chirp report");
+        rendered
+    }
+}
+
+/// Set how chirp errors and warnings are reported, for all chirp files loaded
+/// from now on.
+pub fn set_config(config: ReportConfig) {
+    let mut current = CONFIG.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *current = Some(config);
+}
+/// The current [`ReportConfig`], as set by [`set_config`].
+#[must_use]
+pub fn config() -> ReportConfig {
+    let current = CONFIG.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    current.clone().unwrap_or_default()
+}
+
+/// Report `diagnostic` according to the current [`ReportConfig`].
+pub(crate) fn report(diagnostic: &dyn Diagnostic, severity: Severity) {
+    let config = config();
+    let rendered = config.render(diagnostic);
+    if config.log {
+        match severity {
+            Severity::Error => error!("{rendered}"),
+            Severity::Warning | Severity::Advice => warn!("{rendered}"),
+        }
+    }
+    if let Some(sink) = &config.sink {
+        sink(&Report { severity, rendered: &rendered, diagnostic });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use thiserror::Error;
+
+    use super::*;
+
+    #[derive(Debug, Error, Diagnostic)]
+    #[error("Bad method")]
+    struct TestError {
+        #[source_code]
+        source_code: String,
+        #[label("here")]
+        span: (usize, usize),
+    }
+
+    #[test]
+    fn render_formats() {
+        let error = TestError { source_code: "Entity(bad_method)".to_owned(), span: (7, 10) };
+        let render = |format, color| {
+            let config = ReportConfig { format, color, ..ReportConfig::default() };
+            config.render(&error)
+        };
+
+        let json = render(ReportFormat::Json, true);
+        assert!(json.starts_with('{') && json.contains(r#""message": "Bad method""#), "{json}");
+        let narrow = render(ReportFormat::Narrow, true);
+        assert!(narrow.contains("Bad method") && narrow.contains("here"), "{narrow}");
+        let graphical = render(ReportFormat::Graphical, false);
+        assert!(graphical.contains("Bad method") && !graphical.contains('\x1b'), "{graphical}");
+    }
+}
@@ -1,12 +1,16 @@
 use proc_macro2::TokenStream;
-use quote::{quote, quote_spanned};
+use quote::{format_ident, quote, quote_spanned};
 use syn::{meta::ParseNestedMeta, punctuated::Punctuated, spanned::Spanned};
 
-#[derive(Default, Debug, PartialEq)]
-enum FnConfig {
-    #[default]
-    Method,
-    Ignore,
+#[derive(Default)]
+struct FnConfig {
+    ignore: bool,
+    aliases: Vec<syn::LitStr>,
+    rename: Option<syn::LitStr>,
+    deprecated: Option<syn::LitStr>,
+    /// `cfg` predicates of the method, the method's match branches are only
+    /// generated when they all hold.
+    cfgs: Vec<TokenStream>,
 }
 #[allow(clippy::trivially_copy_pass_by_ref)] // false positive. Type necessary to avoid eta-expension
 fn is_parse_dsl_attr(attr: &&syn::Attribute) -> bool {
@@ -28,6 +32,13 @@ impl FnConfig {
             let Ok(nested) = attr.parse_args_with(parser) else {
                 return Ok(false);
             };
+            let mut nested = nested.into_iter();
+            let Some(predicate) = nested.next() else {
+                return Ok(false);
+            };
+            // `ignore` is the only meta attribute we can enact conditionally,
+            // by only generating the method's branch when `predicate` is false.
+            let ignore = self.ignore;
             for meta in nested {
                 match meta {
                     syn::Meta::List(meta) if meta.path.is_ident("parse_dsl") => {
@@ -37,14 +48,46 @@ impl FnConfig {
                     _ => {}
                 }
             }
+            if !ignore && self.ignore {
+                self.ignore = false;
+                self.cfgs.push(quote!(not(#predicate)));
+            }
+        } else if attr.path().is_ident("cfg") {
+            self.cfgs.push(attr.meta.require_list()?.tokens.clone());
         }
         Ok(ret)
     }
+    /// The `#[cfg]` attribute to add to this method's match branches.
+    fn cfg_attr(&self) -> Option<TokenStream> {
+        let cfgs = &self.cfgs;
+        (!cfgs.is_empty()).then(|| quote!(#[cfg(all(#(#cfgs),*))]))
+    }
+    /// The match pattern of the method: its name in chirp files and its aliases.
+    fn pattern(&self, fun: &syn::ImplItemFn) -> TokenStream {
+        let (ident, aliases) = (&fun.sig.ident, &self.aliases);
+        let name = self.rename.as_ref().map_or_else(|| quote!(stringify!(#ident)), |r| quote!(#r));
+        quote!(#name #(| #aliases)*)
+    }
     #[allow(clippy::needless_pass_by_value)] // false positive. Type necessary for calling it
     fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
         match () {
             () if meta.path.is_ident("ignore") => {
-                *self = Self::Ignore;
+                self.ignore = true;
+                Ok(())
+            }
+            () if meta.path.is_ident("alias") => {
+                let value = meta.value()?;
+                self.aliases.push(value.parse()?);
+                Ok(())
+            }
+            () if meta.path.is_ident("rename") => {
+                let value = meta.value()?;
+                self.rename = Some(value.parse()?);
+                Ok(())
+            }
+            () if meta.path.is_ident("deprecated") => {
+                let value = meta.value()?;
+                self.deprecated = Some(value.parse()?);
                 Ok(())
             }
             () => {
@@ -57,9 +100,17 @@ impl FnConfig {
     }
     fn parse_list(attrs: &[syn::Attribute]) -> syn::Result<Self> {
         let mut fn_config = Self::default();
-        for attr in attrs.iter().filter(is_parse_dsl_attr) {
+        for attr in attrs {
             fn_config.parse_attr(attr)?;
         }
+        if fn_config.ignore {
+            let alias = fn_config.aliases.first().or(fn_config.rename.as_ref());
+            if let Some(meta) = alias.or(fn_config.deprecated.as_ref()) {
+                let msg = "`alias`, `rename` and `deprecated` have no effect on `ignore`d \
+                    methods, chirp files can't call them";
+                return Err(syn::Error::new(meta.span(), msg));
+            }
+        }
         Ok(fn_config)
     }
 }
@@ -88,6 +139,11 @@ pub(crate) struct ImplConfig {
     delegate: Option<syn::Ident>,
     set_params: Option<syn::Generics>,
     type_parsers: Vec<TypeParser>,
+    duplicate_methods: Option<syn::Ident>,
+    conflicts: Option<syn::Path>,
+    namespace: Option<syn::Ident>,
+    part: Option<syn::Ident>,
+    parts: Vec<syn::Ident>,
 }
 impl Default for ImplConfig {
     fn default() -> Self {
@@ -96,6 +152,11 @@ impl Default for ImplConfig {
             delegate: None,
             set_params: None,
             type_parsers: Vec::new(),
+            duplicate_methods: None,
+            conflicts: None,
+            namespace: None,
+            part: None,
+            parts: Vec::new(),
         }
     }
 }
@@ -112,13 +173,41 @@ impl ImplConfig {
                 let value = meta.value()?;
                 self.delegate = Some(value.parse()?);
             }
+            () if meta.path.is_ident("duplicate_methods") => {
+                let value = meta.value()?;
+                self.duplicate_methods = Some(value.parse()?);
+            }
+            () if meta.path.is_ident("conflicts") => {
+                let value = meta.value()?;
+                self.conflicts = Some(value.parse()?);
+            }
+            () if meta.path.is_ident("namespace") => {
+                let value = meta.value()?;
+                self.namespace = Some(value.parse()?);
+            }
+            () if meta.path.is_ident("part") => {
+                let value = meta.value()?;
+                self.part = Some(value.parse()?);
+            }
+            () if meta.path.is_ident("parts") => {
+                meta.parse_nested_meta(|meta| {
+                    let Some(ident) = meta.path.get_ident() else {
+                        return Err(meta.error("parts must be identifiers"));
+                    };
+                    self.parts.push(ident.clone());
+                    Ok(())
+                })?;
+            }
             () if meta.path.is_ident("set_params") => {
                 self.set_params = Some(meta.input.parse()?);
             }
             () if meta.path.is_ident("type_parsers") => {
                 meta.parse_nested_meta(|meta| {
-                    let Some(ident) = meta.path.get_ident() else {
-                        return Err(meta.error("type_parsers type must be an identifier"));
+                    let ident = meta.path.get_ident();
+                    let Some(ident) = ident.filter(|_| meta.input.peek(syn::Token![=])) else {
+                        let msg = "type_parsers type must be an identifier, \
+                            use a type alias for generic types";
+                        return Err(meta.error(msg));
                     };
                     let value = meta.value()?;
                     self.type_parsers
@@ -155,8 +244,64 @@ fn dsl_function_mut(item: &mut syn::ImplItem) -> Option<&mut syn::ImplItemFn> {
     };
     parse_dsl_receiver(fn_item).map(|()| fn_item)
 }
+/// Remove `parse_dsl` attributes from functions that are not `&mut self` methods,
+/// returning an error for each of them.
+fn misplaced_attributes(block: &mut syn::ItemImpl) -> TokenStream {
+    let mut errors = TokenStream::new();
+    for item in &mut block.items {
+        let syn::ImplItem::Fn(fn_item) = item else {
+            continue;
+        };
+        if parse_dsl_receiver(fn_item).is_some() {
+            continue;
+        }
+        let ident = &fn_item.sig.ident;
+        for attr in fn_item.attrs.iter().filter(is_parse_dsl_attr) {
+            let msg = format!(
+                "`parse_dsl` only applies to methods with a `&mut self` receiver, \
+                chirp files can't call `{ident}`"
+            );
+            errors.extend(syn::Error::new_spanned(attr, msg).into_compile_error());
+        }
+        fn_item.attrs.retain(|a| !is_parse_dsl_attr(&a));
+    }
+    errors
+}
 
 pub(crate) fn parse_dsl_impl(config: &mut ImplConfig, block: &mut syn::ItemImpl) -> TokenStream {
+    let trait_path = block.trait_.as_ref().map(|(_, path, _)| path);
+    let funs = block.items.iter().filter_map(dsl_function);
+    let names: Vec<_> = funs.clone().filter_map(name_pattern).collect();
+    let deprecations: Vec<_> = funs.clone().filter_map(deprecation_branch).collect();
+    let funs: Vec<_> = funs.map(|f| method_branch(f, &config.type_parsers, trait_path)).collect();
+
+    let branches = Branches { funs, names, deprecations };
+    let generated = match &config.part {
+        Some(part) => part_block(config, part, block, &branches),
+        None => parse_dsl_block(config, block, &branches),
+    };
+    // Remove `parse_dsl` attributes from block items, as otherwise rust
+    // vainly tries to understand them.
+    for item_fn in block.items.iter_mut().filter_map(dsl_function_mut) {
+        item_fn.attrs.retain(|a| !is_parse_dsl_attr(&a));
+    }
+    let errors = misplaced_attributes(block);
+    quote!(#block #generated #errors)
+}
+/// The match branches generated from the methods of an `impl` block.
+struct Branches {
+    /// Call the method, see [`method_branch`].
+    funs: Vec<TokenStream>,
+    /// The method's patterns, see [`name_pattern`].
+    names: Vec<TokenStream>,
+    /// The method's deprecation message, see [`deprecation_branch`].
+    deprecations: Vec<TokenStream>,
+}
+fn parse_dsl_block(
+    config: &mut ImplConfig,
+    block: &syn::ItemImpl,
+    branches: &Branches,
+) -> TokenStream {
     let this_generics = config.set_params.get_or_insert_with(|| {
         let mut generics = block.generics.clone();
         bind_to_parse_dsl(&config.chirp_crate, &mut generics);
@@ -164,37 +309,180 @@ pub(crate) fn parse_dsl_impl(config: &mut ImplConfig, block: &mut syn::ItemImpl)
     });
     let this_type = block.self_ty.as_ref();
     let this_crate = &config.chirp_crate;
+    let Branches { funs, names, deprecations } = branches;
+    let parts = &config.parts;
+    let part_deprecations: Vec<_> =
+        parts.iter().map(|p| format_ident!("{p}_deprecation", span = p.span())).collect();
+
+    // `namespace:method` calls only the methods of the DSL with this namespace.
+    let namespace = config.namespace.as_ref().map(ToString::to_string);
+    let own_namespace = namespace.as_ref().map(|ns| quote!(Some((#ns, name)) => (name, true),));
+    let namespace_label = namespace.as_ref().map_or_else(
+        || quote!(::core::any::type_name::<Self>()),
+        |namespace| quote!(#namespace),
+    );
 
-    let funs = block.items.iter().filter_map(dsl_function);
-    let funs = funs.map(|f| method_branch(f, &config.type_parsers));
     let catchall = config.delegate.as_ref().map_or_else(
-        || quote!(Err(DslParseError::<Self>::new(name))),
-        |ident| quote!(self.#ident.method(MethodCtx { name, arguments, ctx, registry })),
+        || quote!(Err(DslParseError::new(data.name).into())),
+        |ident| quote!(self.#ident.method(data)),
     );
-    let parse_dsl_block = quote! {
+    // Parts are tried in order, the first one with a `name` method wins.
+    let mutability = (!parts.is_empty()).then(|| quote!(mut));
+    let parts_catchall = quote! {
+        #(match self.#parts(&mut data) {
+            Err(error) if error.is::<DslParseError>() => {}
+            result => return result,
+        })*
+    };
+    let deprecation_catchall = config.delegate.as_ref().map_or_else(
+        || quote!(None),
+        |ident| quote!(self.#ident.deprecation(name)),
+    );
+    let namespaces_catchall = config.delegate.as_ref().map(|ident| {
+        quote!(self.#ident.method_namespaces(name, namespaces);)
+    });
+    let conflict_catchall = config.delegate.as_ref().map(|ident| quote!(self.#ident.conflict()));
+    let conflict = match (&config.conflicts, conflict_catchall) {
+        (None, None) => None,
+        (None, Some(catchall)) => Some(catchall),
+        (Some(path), None) => Some(quote!(#path(self).map(Into::into))),
+        (Some(path), Some(catchall)) => {
+            Some(quote!(#path(self).map(Into::into).or_else(|| #catchall)))
+        }
+    };
+    let conflict = conflict.map(|body| {
+        let conflict = quote!(#this_crate::parse_dsl::MethodConflict);
+        quote!(fn conflict(&self) -> Option<#conflict> { #body })
+    });
+    let duplicate_methods = config.duplicate_methods.as_ref().map(|ident| {
+        let semantics = quote!(#this_crate::parse_dsl::DuplicateMethods);
+        quote!(const DUPLICATE_METHODS: #semantics = #semantics::#ident;)
+    });
+    quote! {
         #[automatically_derived]
         #[allow(clippy::let_unit_value)]
         impl #this_generics #this_crate::ParseDsl for #this_type {
+            #duplicate_methods
+
             fn method(
                 &mut self,
                 data: #this_crate::parse_dsl::MethodCtx,
             ) -> Result<(), #this_crate::anyhow::Error> {
                 use #this_crate::parse_dsl::{MethodCtx, DslParseError, args};
 
-                let MethodCtx { name, arguments, mut ctx, registry } = data;
+                let full_name = data.name;
+                let (name, is_namespaced) = match full_name.split_once(':') {
+                    #own_namespace
+                    Some(_) => return #catchall,
+                    None => (full_name, false),
+                };
+                let MethodCtx { arguments, mut ctx, registry, .. } = data;
                 match name {
                     #(#funs)*
-                    _name => { #catchall }
+                    _name => {
+                        let #mutability data = MethodCtx { name, arguments, ctx, registry };
+                        #parts_catchall
+                        if is_namespaced {
+                            return Err(DslParseError::new(full_name).into());
+                        }
+                        let data = MethodCtx { name: full_name, ..data };
+                        #catchall
+                    }
+                }
+            }
+            fn deprecation(&self, name: &str) -> Option<&'static str> {
+                let (name, is_namespaced) = match name.split_once(':') {
+                    #own_namespace
+                    Some(_) => return #deprecation_catchall,
+                    None => (name, false),
+                };
+                match name {
+                    #(#deprecations)*
+                    _name => {
+                        #(if let Some(deprecation) = self.#part_deprecations(name) {
+                            return deprecation;
+                        })*
+                        if is_namespaced {
+                            return None;
+                        }
+                        #deprecation_catchall
+                    }
+                }
+            }
+            fn method_namespaces(&self, name: &str, namespaces: &mut Vec<&'static str>) {
+                let is_own = match name {
+                    #(#names => true,)*
+                    _name => false,
+                };
+                if is_own #(|| self.#part_deprecations(name).is_some())* {
+                    namespaces.push(#namespace_label);
                 }
+                #namespaces_catchall
             }
+            #conflict
         }
+    }
+}
+/// An inherent `impl` block with the `part` and `{part}_deprecation` methods,
+/// called by the `ParseDsl` impl of the block with `parts(part)`.
+fn part_block(
+    config: &ImplConfig,
+    part: &syn::Ident,
+    block: &syn::ItemImpl,
+    branches: &Branches,
+) -> TokenStream {
+    let main_only = match () {
+        () if config.delegate.is_some() => Some("delegate"),
+        () if config.duplicate_methods.is_some() => Some("duplicate_methods"),
+        () if config.conflicts.is_some() => Some("conflicts"),
+        () if config.namespace.is_some() => Some("namespace"),
+        () if config.set_params.is_some() => Some("set_params"),
+        () if !config.parts.is_empty() => Some("parts"),
+        () => None,
     };
-    // Remove `parse_dsl` attributes from block items, as otherwise rust
-    // vainly tries to understand them.
-    for item_fn in block.items.iter_mut().filter_map(dsl_function_mut) {
-        item_fn.attrs.retain(|a| !is_parse_dsl_attr(&a));
+    let error = main_only.map(|meta| {
+        let msg = format!(
+            "`part` blocks can't have a `{meta}` meta-attribute, \
+            add it to the block with `parts({part})` instead"
+        );
+        syn::Error::new(part.span(), msg).into_compile_error()
+    });
+    let (impl_generics, _, where_clause) = block.generics.split_for_impl();
+    let this_type = block.self_ty.as_ref();
+    let this_crate = &config.chirp_crate;
+    let Branches { funs, deprecations, .. } = branches;
+    let part_deprecation = format_ident!("{part}_deprecation");
+    quote! {
+        #error
+        #[automatically_derived]
+        #[allow(clippy::let_unit_value)]
+        impl #impl_generics #this_type #where_clause {
+            #[doc(hidden)]
+            pub(crate) fn #part(
+                &mut self,
+                data: &mut #this_crate::parse_dsl::MethodCtx,
+            ) -> Result<(), #this_crate::anyhow::Error> {
+                use #this_crate::parse_dsl::{MethodCtx, DslParseError, args};
+
+                let MethodCtx { name, arguments, ctx, registry } = data;
+                let (name, arguments, registry) = (*name, &*arguments, *registry);
+                match name {
+                    #(#funs)*
+                    _name => Err(DslParseError::new(name).into()),
+                }
+            }
+            /// `None` if this part has no `name` method, otherwise its
+            /// deprecation message.
+            #[doc(hidden)]
+            pub(crate) fn #part_deprecation(&self, name: &str) -> Option<Option<&'static str>> {
+                let deprecation = match name {
+                    #(#deprecations)*
+                    _name => return None,
+                };
+                Some(deprecation)
+            }
+        }
     }
-    quote!(#block #parse_dsl_block)
 }
 
 /// Add `: ParseDsl` type bound to `generics`, with given `chirp_crate` as
@@ -214,11 +502,29 @@ fn bind_to_parse_dsl(chirp_crate: &syn::Path, generics: &mut syn::Generics) {
         type_param.bounds.push(bound);
     }
 }
+/// The `deprecation` match branch of `fun`, if it is deprecated.
+/// The match pattern of `fun`, `None` if chirp files can't call it.
+fn name_pattern(fun: &syn::ImplItemFn) -> Option<TokenStream> {
+    let config = FnConfig::parse_list(&fun.attrs).ok().filter(|c| !c.ignore)?;
+    let (cfg, pattern) = (config.cfg_attr(), config.pattern(fun));
+    Some(quote!(#cfg #pattern))
+}
+/// The deprecation message of `fun`, `None` if chirp files can't call it.
+fn deprecation_branch(fun: &syn::ImplItemFn) -> Option<TokenStream> {
+    let config = FnConfig::parse_list(&fun.attrs).ok().filter(|c| !c.ignore)?;
+    let (cfg, pattern) = (config.cfg_attr(), config.pattern(fun));
+    let message = config.deprecated.map_or_else(|| quote!(None), |m| quote!(Some(#m)));
+    Some(quote!(#cfg #pattern => #message,))
+}
 // Note: assumes cuicui_chirp::parse_dsl::split is in scope and used correctly
-fn method_branch(fun: &syn::ImplItemFn, parsers: &[TypeParser]) -> TokenStream {
-    match FnConfig::parse_list(&fun.attrs) {
-        Ok(FnConfig::Ignore) => return TokenStream::new(),
-        Ok(FnConfig::Method) => {}
+fn method_branch(
+    fun: &syn::ImplItemFn,
+    parsers: &[TypeParser],
+    trait_path: Option<&syn::Path>,
+) -> TokenStream {
+    let (cfg, pattern) = match FnConfig::parse_list(&fun.attrs) {
+        Ok(config) if config.ignore => return TokenStream::new(),
+        Ok(config) => (config.cfg_attr(), config.pattern(fun)),
         Err(err) => {
             // Since we use this as a `pat => match_branch`, we can't simply return
             // the value of err.into_compile_error(). We need to add the pattern,
@@ -227,27 +533,58 @@ fn method_branch(fun: &syn::ImplItemFn, parsers: &[TypeParser]) -> TokenStream {
             return quote!(_ => {#compile_error});
         }
     };
-    let arg_parsers = fun.sig.inputs.iter().skip(1);
-    let arg_parsers = arg_parsers.map(|a| argument_parser(a, parsers));
-
-    let arg_count = arg_parsers.len();
-    let index = syn::Index::from;
-    let fun_args = (0..arg_count)
-        .map(index)
-        .map(|i| quote!(arguments.get_str(#i).unwrap().as_ref()));
-
     let ident = &fun.sig.ident;
+    if let Some(err) = generic_error(fun) {
+        let compile_error = err.into_compile_error();
+        return quote!(#cfg #pattern => {#compile_error});
+    }
+    let arguments = fun.sig.inputs.iter().skip(1);
+    let arg_count = arguments.len();
+    // Spanned to each argument, so that errors in the parser call point to the
+    // argument it parses.
+    let arguments = arguments.enumerate().map(|(i, arg)| {
+        let parser = argument_parser(arg, parsers);
+        let i = syn::Index::from(i);
+        quote_spanned! { arg.span() =>
+            #parser(registry, ctx.as_deref_mut(), arguments.get_str(#i).unwrap().as_ref())?
+        }
+    });
+    // Trait methods are called through the trait, so that the trait needs not
+    // be in scope where the `ParseDsl` impl is.
+    let call = match trait_path {
+        Some(path) => quote!(<Self as #path>::#ident(self, #(#arguments),*)),
+        None => quote!(self.#ident(#(#arguments),*)),
+    };
 
     quote_spanned! { fun.sig.inputs.span() =>
-        stringify!(#ident) => {
+        #cfg #pattern => {
             if arguments.len() != #arg_count {
                 return Err(args::ArgumentError { expected: #arg_count, got: arguments.len() }.into());
             }
-            self.#ident(#(#arg_parsers(registry, ctx.as_deref_mut(), #fun_args)?),*);
+            #call;
             Ok(())
         }
     }
 }
+/// An error if `fun` has type or const parameters, as we can't pick them
+/// when calling `fun` from a chirp file.
+fn generic_error(fun: &syn::ImplItemFn) -> Option<syn::Error> {
+    let is_generic = |param: &&syn::GenericParam| !matches!(param, syn::GenericParam::Lifetime(_));
+    let impl_trait = fun.sig.inputs.iter().find_map(|arg| match arg {
+        syn::FnArg::Typed(arg) => matches!(*arg.ty, syn::Type::ImplTrait(_)).then_some(&arg.ty),
+        syn::FnArg::Receiver(_) => None,
+    });
+    let span = match (fun.sig.generics.params.iter().find(is_generic), impl_trait) {
+        (Some(param), _) => param.span(),
+        (None, Some(ty)) => ty.span(),
+        (None, None) => return None,
+    };
+    let msg = format!(
+        "chirp files can't call generic methods, add `#[parse_dsl(ignore)]` to `{}`",
+        fun.sig.ident
+    );
+    Some(syn::Error::new(span, msg))
+}
 fn argument_parser(argument: &syn::FnArg, parsers: &[TypeParser]) -> TokenStream {
     use syn::Type::{Path, Reference as Ref};
     use syn::TypeReference as TRef;
@@ -0,0 +1,10 @@
+//! Check that misuses of `parse_dsl_impl` fail with helpful errors.
+//!
+//! Run with `TRYBUILD=overwrite cargo test -p cuicui_chirp_macros` to update
+//! the expected errors in `tests/ui/*.stderr`.
+
+#[test]
+fn compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
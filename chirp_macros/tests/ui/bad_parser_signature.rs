@@ -0,0 +1,20 @@
+use cuicui_chirp::parse_dsl_impl;
+use cuicui_dsl::{DslBundle, EntityCommands};
+
+#[derive(Default)]
+struct MyDsl;
+impl DslBundle for MyDsl {
+    fn insert(&mut self, _: &mut EntityCommands) {}
+}
+
+fn parse_count(input: &str) -> Result<u32, std::num::ParseIntError> {
+    input.parse()
+}
+
+#[parse_dsl_impl(type_parsers(u32 = parse_count))]
+impl MyDsl {
+    fn name(&mut self, _: &str) {}
+    fn count(&mut self, _: f32, _: u32) {}
+}
+
+fn main() {}
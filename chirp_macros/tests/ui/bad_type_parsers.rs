@@ -0,0 +1,15 @@
+use cuicui_chirp::parse_dsl_impl;
+use cuicui_dsl::{DslBundle, EntityCommands};
+
+#[derive(Default)]
+struct MyDsl;
+impl DslBundle for MyDsl {
+    fn insert(&mut self, _: &mut EntityCommands) {}
+}
+
+#[parse_dsl_impl(type_parsers(Vec<u32> = parse_list))]
+impl MyDsl {
+    fn list(&mut self, _: Vec<u32>) {}
+}
+
+fn main() {}
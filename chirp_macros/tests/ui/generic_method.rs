@@ -0,0 +1,17 @@
+use cuicui_chirp::parse_dsl_impl;
+use cuicui_dsl::{DslBundle, EntityCommands};
+
+#[derive(Default)]
+struct MyDsl;
+impl DslBundle for MyDsl {
+    fn insert(&mut self, _: &mut EntityCommands) {}
+}
+
+#[parse_dsl_impl]
+impl MyDsl {
+    fn value<T: Default>(&mut self, _: T) {}
+    fn display(&mut self, _: impl std::fmt::Display) {}
+    fn text<'a>(&mut self, _: &'a str) {}
+}
+
+fn main() {}
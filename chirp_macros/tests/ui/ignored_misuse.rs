@@ -0,0 +1,18 @@
+use cuicui_chirp::parse_dsl_impl;
+use cuicui_dsl::{DslBundle, EntityCommands};
+
+#[derive(Default)]
+struct MyDsl;
+impl DslBundle for MyDsl {
+    fn insert(&mut self, _: &mut EntityCommands) {}
+}
+
+#[parse_dsl_impl]
+impl MyDsl {
+    #[parse_dsl(ignore, alias = "w")]
+    fn width(&mut self, _: f32) {}
+    #[parse_dsl(deprecated = "use `width` instead", ignore)]
+    fn main_width(&mut self, _: f32) {}
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use cuicui_chirp::parse_dsl_impl;
+use cuicui_dsl::{DslBundle, EntityCommands};
+
+#[derive(Default)]
+struct MyDsl;
+impl DslBundle for MyDsl {
+    fn insert(&mut self, _: &mut EntityCommands) {}
+}
+
+#[parse_dsl_impl]
+impl MyDsl {
+    #[parse_dsl(alias = "size")]
+    fn get_size(&self) -> f32 {
+        0.
+    }
+    #[parse_dsl(ignore)]
+    fn new() -> Self {
+        Self
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use cuicui_chirp::parse_dsl_impl;
+use cuicui_dsl::{BaseDsl, DslBundle, EntityCommands};
+
+#[derive(Default)]
+struct MyDsl {
+    inner: BaseDsl,
+}
+impl DslBundle for MyDsl {
+    fn insert(&mut self, cmds: &mut EntityCommands) {
+        self.inner.insert(cmds);
+    }
+}
+
+#[parse_dsl_impl(delegate = inner, parts(width_methods))]
+impl MyDsl {
+    fn height(&mut self, _: f32) {}
+}
+#[parse_dsl_impl(part = width_methods, delegate = inner)]
+impl MyDsl {
+    fn width(&mut self, _: f32) {}
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+//! Lex and parse a large generated chirp file, like the ones exporters write.
+//!
+//! - `lex`: Split the file into tokens.
+//! - `parse_and_interpret`: Parse the file, then walk it with an interpreter
+//!   that does nothing.
+//!
+//! Run with `cargo bench -p cuicui_chirp_parser`.
+use criterion::{criterion_group, criterion_main, Criterion};
+use cuicui_chirp_parser::{chirp_file, large_file, ChirpFile, Input};
+use winnow::stream::Stream;
+
+/// Rows in the generated file, each row is 3 lines long.
+const ROWS: usize = 4000;
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_file");
+
+    let file = large_file(ROWS);
+    let input = Input::new(file.as_bytes(), ());
+    group.bench_function("lex", |b| b.iter(|| input.iter_offsets().count()));
+    group.bench_function("parse_and_interpret", |b| {
+        b.iter(|| {
+            let chirp_file = chirp_file(input).unwrap();
+            ChirpFile::new(input, chirp_file.as_ref()).interpret(&mut ());
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -2,12 +2,12 @@ use std::marker::PhantomData;
 
 use super::{as_usize, header, Ast};
 
-pub(in crate::parser) struct AstBuilder {
+pub(crate) struct AstBuilder {
     ast: Vec<header::Block>,
     #[cfg(not(feature = "more_unsafe"))]
     zero_header: Vec<(usize, &'static str)>,
 }
-pub(in crate::parser) struct Buffer<'a, const N: usize>(pub(super) &'a mut [header::Block; N]);
+pub(crate) struct Buffer<'a, const N: usize>(pub(super) &'a mut [header::Block; N]);
 
 impl AstBuilder {
     pub fn new() -> Self {
@@ -43,14 +43,14 @@ impl AstBuilder {
             self.zero_header.pop();
         }
         let (start, end) = (head.index, head.index + as_usize(T::SIZE));
-        bevy::log::trace!("{start} - {writer:?}");
+        tracing::trace!("{start} - {writer:?}");
         writer.write_header(Buffer((&mut self.ast[start..end]).try_into().unwrap()));
     }
     pub fn build(self) -> Ast {
         #[cfg(not(feature = "more_unsafe"))]
         {
             for (index, name) in &self.zero_header {
-                bevy::log::error!(
+                tracing::error!(
                     "{index} - {name}: Created header that never was initialized. \
                         This is a cuicui_chirp bug, please open an issue at\n\n\
                         https://github.com/nicopap/cuicui_layout/issues/new\n",
@@ -65,12 +65,12 @@ impl AstBuilder {
     }
 }
 
-pub(in crate::parser) struct AstBuilderHead<T: WriteHeader> {
+pub(crate) struct AstBuilderHead<T: WriteHeader> {
     index: usize,
     p: PhantomData<T>,
 }
 
-pub(in crate::parser) trait WriteHeader: Sized {
+pub(crate) trait WriteHeader: Sized {
     const SIZE: u32;
     type Buffer<'a>;
 
@@ -48,6 +48,10 @@ mod from_mask32 {
         }
         fn as_u32(self) -> u32 { self.start }
     }
+    impl FromMask32 for super::super::NameOffset {
+        fn from_mask32<const SPLIT: usize>(start: u32) -> Self { Self { start } }
+        fn as_u32(self) -> u32 { self.start }
+    }
     impl FromMask32 for super::super::node::IdentOffset {
         fn from_mask32<const SPLIT: usize>(start: u32) -> Self { Self { start } }
         fn as_u32(self) -> u32 { self.start }
@@ -58,6 +62,7 @@ mod from_mask32 {
                 b if b == Self::Spawn as u32 => Self::Spawn,
                 b if b == Self::Template as u32 => Self::Template,
                 b if b == Self::Code as u32 => Self::Code,
+                b if b == Self::WithDsl as u32 => Self::WithDsl,
                 #[cfg(feature = "more_unsafe")]
                 _ => Self::Spawn,
                 #[cfg(not(feature = "more_unsafe"))]
@@ -128,13 +133,13 @@ impl<U: FromMask32, L: FromMask32, const B: usize, const I: usize> HeaderFieldAc
     fn write_to(field: U, blocks: &mut [Block]) {
         let mask = (1u32 << B).wrapping_sub(1);
         blocks[I].0 &= mask;
-        blocks[I].0 |= (field.as_u32() & mask) << B;
+        blocks[I].0 |= field.as_u32() << B;
     }
 }
 
 #[repr(transparent)]
 #[derive(Clone, Copy)]
-pub(in crate::parser) struct Block(pub(super) u32);
+pub(crate) struct Block(pub(super) u32);
 
 #[derive(Clone, Copy)]
 pub(super) struct Usplit<Uppr, Lwr, const SPLIT: usize>(u32, PhantomData<(Uppr, Lwr)>);
@@ -14,9 +14,10 @@
 //!   the absence of value.
 //! - [`OptNameOffset`] is similar to `IdentOffset`, but the parsed token may either
 //!   be a string or identifier (it is also optional).
+//! - [`NameOffset`] is the non-optional version of `OptNameOffset`.
 use super::as_u32;
 use super::node::{Argument, IdentOffset};
-use crate::parser::stream::Input;
+use crate::stream::Input;
 
 /// Offset in an [`Input`] of an entity name, may be an identifier or string literal,
 /// and **is optional**.
@@ -24,6 +25,11 @@ use crate::parser::stream::Input;
 pub struct OptNameOffset {
     pub(super) start: u32,
 }
+/// Offset in an [`Input`] of a name, may be an identifier or string literal.
+#[derive(Clone, Copy, Debug)]
+pub struct NameOffset {
+    pub(super) start: u32,
+}
 #[derive(Clone, Debug, Copy)]
 pub struct OptIdentOffset {
     pub(super) start: u32,
@@ -34,14 +40,22 @@ impl OptNameOffset {
             return None;
         }
         // SAFETY:
-        // - We can only create OptNameOffset in crate::parser
-        // - We only create OptNameOffset in crate::parser::grammar
+        // - We can only create OptNameOffset in this crate
+        // - We only create OptNameOffset in crate::grammar
         // - And they are the starting offset of either an identifier or string, always
         let ident = unsafe { input.starting_at(self.start).next_statement_name() };
         let end = self.start + as_u32(ident.len());
         Some((ident, (self.start, end)))
     }
 }
+impl NameOffset {
+    pub fn read_spanned<'i>(self, input: &Input<'i>) -> (&'i [u8], (u32, u32)) {
+        // SAFETY: Same as `OptNameOffset`, `NameOffset` is always the
+        // starting offset of either an identifier or string.
+        let name = unsafe { input.starting_at(self.start).next_statement_name() };
+        (name, (self.start, self.start + as_u32(name.len())))
+    }
+}
 impl OptIdentOffset {
     pub fn read_spanned<'i>(self, input: &Input<'i>) -> Option<(&'i [u8], (u32, u32))> {
         if self.start == u32::MAX {
@@ -53,8 +67,8 @@ impl OptIdentOffset {
 impl IdentOffset {
     pub fn read_spanned<'i>(self, input: &Input<'i>) -> (&'i [u8], (u32, u32)) {
         // SAFETY:
-        // - We can only create IdentOffset in crate::parser
-        // - We only create IdentOffset in crate::parser::grammar
+        // - We can only create IdentOffset in this crate
+        // - We only create IdentOffset in crate::grammar
         // - And they are the starting offset of identifiers, always
         let ident = unsafe { input.starting_at(self.start).next_ident() };
         (ident, (self.start, self.start + as_u32(ident.len())))
@@ -71,6 +85,7 @@ impl Argument<'_> {
 }
 
 #[rustfmt::skip] impl From<u32> for IdentOffset { fn from(start: u32) -> Self { Self { start } } }
+#[rustfmt::skip] impl From<u32> for NameOffset { fn from(start: u32) -> Self { Self { start } } }
 #[rustfmt::skip] impl From<Option<IdentOffset>> for OptIdentOffset {
     fn from(value: Option<IdentOffset>) -> Self { Self { start: value.map_or(u32::MAX, |i| i.start) } }
 }
@@ -10,8 +10,8 @@
 
 use std::marker::PhantomData;
 
-use super::node::{Argument, Code, IdentOffset, Import, StType};
-use super::node::{ChirpFile, Fn, Method, Spawn, Statement, Template};
+use super::node::{Argument, Code, IdentOffset, Import, Parameter, StType, TemplateArgument};
+use super::node::{ChirpFile, Fn, Method, Spawn, Statement, Template, WithDsl};
 use super::{as_usize, header::Block};
 
 macro_rules! dummy {
@@ -33,8 +33,8 @@ macro_rules! impl_simple_node {
 }
 
 // TODO(clean): Figure out a way to not expose Block in this trait, so that I
-// don't need to make Block pub(in crate::parser)
-pub(in crate::parser) trait Node<'a>: Copy {
+// don't need to make Block pub(crate)
+pub(crate) trait Node<'a>: Copy {
     /// The length.
     ///
     /// `Some` if it is possible to compute it based only on reading the header,
@@ -46,7 +46,7 @@ pub(in crate::parser) trait Node<'a>: Copy {
     fn len(self) -> u32;
     fn first(blocks: &'a [Block]) -> Self;
 }
-pub(in crate::parser) trait SimpleNode {
+pub(crate) trait SimpleNode {
     const SIZE: u32;
 }
 impl_node! {
@@ -55,13 +55,16 @@ impl_node! {
     Method:    header |it| Self::HEADER_SIZE + it.argument_len(),
     Template:  header |it| Self::HEADER_SIZE + it.argument_len() + it.methods_len() + it.children_len(),
     Spawn:     header |it| Self::HEADER_SIZE + it.methods_len() + it.children_len(),
+    Code:      header |it| Self::HEADER_SIZE + it.argument_len(),
+    WithDsl:   header |it| Self::HEADER_SIZE + it.children_len(),
     Statement: header |it| match it.typed() {
         StType::Spawn(s) => s.len(),
         StType::Template(s) => s.len(),
-        StType::Code(_) => Code::SIZE,
+        StType::Code(s) => s.len(),
+        StType::WithDsl(s) => s.len(),
     },
 }
-impl_simple_node! {Import: 2, Argument: 2, Code: 1}
+impl_simple_node! {Import: 2, Argument: 2, Parameter: 3, TemplateArgument: 3}
 
 #[rustfmt::skip] impl SimpleNode for IdentOffset { const SIZE: u32 = 1; }
 #[rustfmt::skip] impl<'a> Node<'a> for IdentOffset {
@@ -70,15 +73,12 @@ impl_simple_node! {Import: 2, Argument: 2, Code: 1}
 }
 
 #[derive(Clone, Copy)]
-pub(in crate::parser) struct List<'a, T: Node<'a>>(&'a [Block], PhantomData<T>);
+pub(crate) struct List<'a, T: Node<'a>>(&'a [Block], PhantomData<T>);
 
 #[derive(Clone)]
-pub(in crate::parser) struct ListIter<'a, T: Node<'a>>(List<'a, T>);
+pub(crate) struct ListIter<'a, T: Node<'a>>(List<'a, T>);
 
 impl<'a, T: Node<'a>> List<'a, T> {
-    pub fn empty() -> Self {
-        Self(&[], PhantomData)
-    }
     #[inline]
     pub(super) fn new(blocks: &'a [Block]) -> Self {
         Self(blocks, PhantomData)
@@ -62,7 +62,7 @@
 //! trait, it knows about the layout and size of each AST node headers (as
 //! `WriteHeader` is implemented for each AST nodes in the [`node`] macro).
 //!
-//! In [`crate::parser::grammar::chirp_file`] each time we encounter a new node, we:
+//! In [`crate::grammar::chirp_file`] each time we encounter a new node, we:
 //!
 //! - "reserve" an unitialized header with [`AstBuilder::reserve_header`].
 //! - Add the sub-item nodes of this node, counting them
@@ -74,9 +74,10 @@ pub(super) use build::{AstBuilder, WriteHeader};
 pub use ident::*;
 pub(super) use list::List;
 pub use node::FnIndex;
-pub(super) use node::{Argument, IdentOffset, Spawn, StKind, StType, Statement, Template};
+pub(super) use node::{Code, IdentOffset, Spawn, StKind, StType, Statement, Template};
 pub(super) use node::{ArgumentHeader, ChirpFileHeader, FnHeader, ImportHeader, MethodHeader};
-pub(super) use node::{CodeHeader, SpawnHeader, TemplateHeader};
+pub(super) use node::{CodeHeader, SpawnHeader, TemplateHeader, WithDsl, WithDslHeader};
+pub(super) use node::{Parameter, ParameterHeader, TemplateArgument, TemplateArgumentHeader};
 
 mod build;
 mod header;
@@ -87,15 +88,20 @@ mod node;
 pub(super) type Methods<'a> = List<'a, node::Method<'a>>;
 pub(super) type Statements<'a> = List<'a, node::Statement<'a>>;
 pub(super) type Arguments<'a> = List<'a, node::Argument<'a>>;
-pub(super) type IdentOffsets<'a> = List<'a, node::IdentOffset>;
+pub(super) type Parameters<'a> = List<'a, node::Parameter<'a>>;
+pub(super) type TemplateArguments<'a> = List<'a, node::TemplateArgument<'a>>;
 
+/// A parsed chirp file, see the [module docs](self).
 pub struct Ast(Box<[header::Block]>);
 impl Ast {
+    /// A reference to this AST, to interpret it with [`ChirpFile`](crate::ChirpFile).
+    #[must_use]
     pub fn as_ref(&self) -> AstRef {
         AstRef(&self.0)
     }
 }
 
+/// A reference to an [`Ast`].
 #[derive(Clone, Copy)]
 pub struct AstRef<'a>(&'a [header::Block]);
 impl<'a> AstRef<'a> {
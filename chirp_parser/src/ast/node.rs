@@ -14,7 +14,7 @@ use super::header::{Block, HeaderFieldAccess, Idx, Lower, Upper, Usplit};
 #[cfg(not(feature = "more_unsafe"))]
 use super::list::Node;
 use super::list::{List, SimpleNode};
-use super::{as_u32, as_usize, AstRef, OptIdentOffset, OptNameOffset};
+use super::{as_u32, as_usize, AstRef, NameOffset, OptIdentOffset, OptNameOffset};
 
 /// A reference to an untyped node header, keeping track of AST node sizes for runtime
 /// bound checks.
@@ -22,7 +22,7 @@ use super::{as_u32, as_usize, AstRef, OptIdentOffset, OptNameOffset};
 /// The safe-er version keeps track of the total slice length and always checks
 /// that we are reading memory from the same allocation.
 ///
-/// Afaik, all invariants are upheld within `cuicui_chirp::parser`. But the
+/// Afaik, all invariants are upheld within `cuicui_chirp_parser`. But the
 /// upholding of invariant must be holistic. Most notably, we need to ensure that:
 ///
 /// - When writting the AST, we correctly layout the blocks according to the
@@ -49,11 +49,14 @@ struct Header<'a, const N: usize>(&'a [Block]);
 #[derive(Clone, Copy)]
 struct Header<'a, const N: usize>(&'a [Block; N]);
 
+/// A template declaration, see [`Declaration`].
+///
+/// [`Declaration`]: crate::Declaration
 #[derive(Clone, Copy)]
 pub struct FnIndex<'a>(pub(super) Fn<'a>);
 
 impl<'a> FnIndex<'a> {
-    pub(in crate::parser) fn get(self) -> Fn<'a> {
+    pub(crate) fn get(self) -> Fn<'a> {
         self.0
     }
 }
@@ -73,10 +76,10 @@ impl<'a, const N: usize> Header<'a, N> {
             // SAFETY: This is extremely unsafe. It is however sound because:
             // - Header is only constructed in `new` methods on nodes in `impl_header!`
             // - Header::new is only called within this module
-            // - Which itself is only used in crate::parser::interpret
-            // - crate::parser::interpret can only walk ASTs built by crate::parser::grammar
-            // - crate::parser::grammar can only build VALID ASTs because it can
-            //   only build ASTs through methods in crate::parser::ast::build
+            // - Which itself is only used in crate::interpret
+            // - crate::interpret can only walk ASTs built by crate::grammar
+            // - crate::grammar can only build VALID ASTs because it can
+            //   only build ASTs through methods in crate::ast::build
             let ptr = unsafe { self.0.as_ptr().add(N + as_usize(offset)) };
             unsafe { std::slice::from_raw_parts(ptr, as_usize(len)) }
         }
@@ -122,7 +125,7 @@ macro_rules! impl_header {
     ) => {
 
         #[derive(Clone, Copy)]
-        pub(in crate::parser) struct $node_name<'a>(Header<'a, $size>);
+        pub(crate) struct $node_name<'a>(Header<'a, $size>);
         impl<'a> $node_name<'a> {
             #[allow(unused)]
             pub(super) const HEADER_SIZE: u32 = $size;
@@ -168,7 +171,7 @@ macro_rules! impl_header {
             }
 
             #[allow(unused)]
-            pub(in crate::parser) fn block_index(self, ast: AstRef<'a>) -> isize {
+            pub(crate) fn block_index(self, ast: AstRef<'a>) -> isize {
                 unsafe { self.0 .0.as_ptr().offset_from(ast.0.as_ptr()) }
             }
             $(
@@ -210,10 +213,11 @@ macro_rules! impl_header {
 }
 
 #[derive(Clone, Copy)]
-pub(in crate::parser) enum StType<'a> {
+pub(crate) enum StType<'a> {
     Spawn(Spawn<'a>),
     Template(Template<'a>),
     Code(Code<'a>),
+    WithDsl(WithDsl<'a>),
 }
 #[repr(u32)]
 #[derive(Clone, Copy, Debug)]
@@ -221,6 +225,7 @@ pub enum StKind {
     Spawn,
     Template,
     Code,
+    WithDsl,
 }
 
 impl_header![ChirpFile, ChirpFileHeader, 2, {
@@ -253,9 +258,9 @@ impl_header![Fn, FnHeader, 1, {
 }];
 impl<'a> Fn<'a> {
     pub(super) fn parameter_len(self) -> u32 {
-        self.parameter_count() * <IdentOffset as SimpleNode>::SIZE
+        self.parameter_count() * Parameter::SIZE
     }
-    pub fn parameters(self) -> List<'a, IdentOffset> {
+    pub fn parameters(self) -> List<'a, Parameter<'a>> {
         List::new(unsafe { self.0.offset(0, self.parameter_len()) })
     }
     #[inline]
@@ -294,6 +299,9 @@ impl<'a> Statement<'a> {
             StKind::Spawn => unsafe { StType::Spawn(Spawn::new_unchecked(self.0.raw_block())) },
             KTemplate => unsafe { StType::Template(Template::new_unchecked(self.0.raw_block())) },
             StKind::Code => unsafe { StType::Code(Code::new_unchecked(self.0.raw_block())) },
+            StKind::WithDsl => unsafe {
+                StType::WithDsl(WithDsl::new_unchecked(self.0.raw_block()))
+            },
         }
     }
 }
@@ -323,10 +331,10 @@ impl_header![Template, TemplateHeader, 3, {
 }];
 impl<'a> Template<'a> {
     pub(super) fn argument_len(self) -> u32 {
-        self.argument_count() * Argument::SIZE
+        self.argument_count() * TemplateArgument::SIZE
     }
     #[inline]
-    pub fn arguments(self) -> List<'a, Argument<'a>> {
+    pub fn arguments(self) -> List<'a, TemplateArgument<'a>> {
         List::new(unsafe { self.0.offset(0, self.argument_len()) })
     }
     #[inline]
@@ -339,14 +347,65 @@ impl<'a> Template<'a> {
         List::new(unsafe { self.0.offset(offset, self.children_len()) })
     }
 }
-impl_header![Code, CodeHeader, 1, { pub name: (THeader0, Lower) => IdentOffset }];
+impl_header![Code, CodeHeader, 2, {
+    pub name: (THeader0, Lower) => IdentOffset,
+    argument_count: Idx<1> => u32,
+}];
+impl<'a> Code<'a> {
+    pub(super) fn argument_len(self) -> u32 {
+        self.argument_count() * Argument::SIZE
+    }
+    #[inline]
+    pub fn arguments(self) -> List<'a, Argument<'a>> {
+        List::new(unsafe { self.0.offset(0, self.argument_len()) })
+    }
+}
+impl_header![WithDsl, WithDslHeader, 2, {
+    pub name: (THeader0, Lower) => IdentOffset,
+    pub(super) children_len: Idx<1> => u32,
+}];
+impl<'a> WithDsl<'a> {
+    #[inline]
+    pub fn children(self) -> List<'a, Statement<'a>> {
+        List::new(unsafe { self.0.offset(0, self.children_len()) })
+    }
+}
 
 type IdxT<T, const I: usize> = ((Idx<I>, Usplit<T, (), 0>), Upper);
 impl_header![Import, ImportHeader, 2, {
-    pub name: IdxT<IdentOffset, 0> => IdentOffset,
+    pub name: IdxT<NameOffset, 0> => NameOffset,
     pub alias: IdxT<OptIdentOffset, 1> => OptIdentOffset,
 }];
 impl_header![Argument, ArgumentHeader, 2, { pub start: Idx<0> => u32, pub end: Idx<1> => u32 }];
+impl_header![Parameter, ParameterHeader, 3, {
+    pub name: IdxT<IdentOffset, 0> => IdentOffset,
+    default_start: Idx<1> => u32,
+    default_end: Idx<2> => u32,
+}];
+impl<'a> Parameter<'a> {
+    /// The `= default` value of this parameter, if any.
+    ///
+    /// The last two blocks of the header are laid out as an [`Argument`].
+    #[inline]
+    pub fn default_value(self) -> Option<Argument<'a>> {
+        let has_default = self.default_start() != u32::MAX;
+        has_default.then(|| Argument::new(&self.0.raw_block()[1..]))
+    }
+}
+impl_header![TemplateArgument, TemplateArgumentHeader, 3, {
+    pub name: IdxT<OptIdentOffset, 0> => OptIdentOffset,
+    value_start: Idx<1> => u32,
+    value_end: Idx<2> => u32,
+}];
+impl<'a> TemplateArgument<'a> {
+    /// The value of this argument, without the `name:` of named arguments.
+    ///
+    /// The last two blocks of the header are laid out as an [`Argument`].
+    #[inline]
+    pub fn value(self) -> Argument<'a> {
+        Argument::new(&self.0.raw_block()[1..])
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct IdentOffset {
@@ -0,0 +1,310 @@
+use winnow::combinator::{opt, preceded};
+use winnow::error::ErrMode::{Backtrack, Cut};
+use winnow::stream::Stream;
+use winnow::token::any;
+use winnow::Parser;
+
+use super::generic::{Delimited, Many, SepList, Terminated};
+use super::tokens::{ident, many_tts, name};
+use super::{AddNodes, BlockResult};
+use crate::ast::{Ast, AstBuilder, ChirpFileHeader, MethodHeader, WriteHeader};
+use crate::ast::{ArgumentHeader, IdentOffset, ImportHeader, ParameterHeader};
+use crate::ast::{OptIdentOffset, TemplateArgumentHeader};
+use crate::ast::{CodeHeader, FnHeader, SpawnHeader, StKind, TemplateHeader};
+use crate::ast::WithDslHeader;
+use crate::stream::{tokens as t, Input, Token};
+use crate::{confusable, Error};
+
+type Opt<T> = Option<T>;
+type Sep<T> = SepList<T>;
+type Paren<T> = Delimited<T, t::Lparen, t::Rparen>;
+type Curly<T> = Delimited<T, t::Lcurly, t::Rcurly>;
+
+#[rustfmt::skip]
+macro_rules! Tokens {
+    ($inner:ty, ')') => { Terminated<$inner, t::Rparen> };
+    ($inner:ty, ']') => { Terminated<$inner, t::Rbracket> };
+    ($inner:ty, '}') => { Terminated<$inner, t::Rcurly> };
+}
+#[rustfmt::skip]
+macro_rules! token {
+    ($first:tt $(| $many:tt)*) => { token!(@ $first) $(| token!(@ $many))* };
+    (@ "ident")  => { Token::Ident(_) };
+    (@ "string") => { Token::String(_) };
+    (@ '(') => { Token::Lparen };
+    (@ ')') => { Token::Rparen };
+    (@ '{') => { Token::Lcurly };
+    (@ '}') => { Token::Rcurly };
+    (@ '[') => { Token::Lbracket };
+    (@ ']') => { Token::Rbracket };
+    (@ ',') => { Token::Comma };
+    (@ '=') => { Token::Equal };
+}
+
+struct Import;
+impl AddNodes for Import {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        let import = |input: &mut Input| {
+            preceded(t::Use, (name, opt(preceded(t::As, ident)))).parse_next(input)
+        };
+        let (name, alias) = import(input)?;
+        builder.write_header(ImportHeader { name, alias: alias.into() });
+        Ok(ImportHeader::SIZE)
+    }
+}
+
+struct Argument;
+impl AddNodes for Argument {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        let (start, end) = many_tts::<true>(input)?;
+        builder.write_header(ArgumentHeader { start, end });
+        Ok(ArgumentHeader::SIZE)
+    }
+}
+/// A template parameter, `name` or `name = default value`.
+struct Parameter;
+impl AddNodes for Parameter {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        let name = ident(input)?;
+        let default = opt(preceded(t::Equal, many_tts::<true>)).parse_next(input)?;
+        let (default_start, default_end) = default.unwrap_or((u32::MAX, u32::MAX));
+        builder.write_header(ParameterHeader { name, default_start, default_end });
+        Ok(ParameterHeader::SIZE)
+    }
+}
+/// An argument to a template call, `value` or `name: value`.
+struct TemplateArgument;
+impl AddNodes for TemplateArgument {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        let named = |input: &mut Input| {
+            let start = input.next_start();
+            match input.next_token() {
+                Some(Token::Ident(name)) if is_argument_name(name) => Ok(IdentOffset { start }),
+                _ => Err(Backtrack(Error::Unexpected)),
+            }
+        };
+        let name = opt(named).parse_next(input)?;
+        let (value_start, value_end) = many_tts::<true>(input)?;
+        let name = OptIdentOffset::from(name);
+        builder.write_header(TemplateArgumentHeader { name, value_start, value_end });
+        Ok(TemplateArgumentHeader::SIZE)
+    }
+}
+/// Whether `ident` is the `name:` of a named template argument.
+fn is_argument_name(ident: &[u8]) -> bool {
+    ident.len() > 1 && ident.ends_with(b":") && !ident.ends_with(b"::")
+}
+
+struct Fn;
+impl AddNodes for Fn {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        t::Fn.parse_next(input)?;
+        let header = builder.reserve_header();
+
+        let name = ident(input)?;
+        let parameter_len = Paren::<Sep<Parameter>>::add_node(input, builder)?;
+        let body_len = Curly::<Sep<St>>::add_node(input, builder)?;
+
+        let parameter_count = parameter_len / ParameterHeader::SIZE;
+        builder.write(header, FnHeader { parameter_count, name });
+        Ok(FnHeader::SIZE + parameter_len + body_len)
+    }
+}
+
+struct St;
+impl AddNodes for St {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        use Token::{Ident, String as TStr};
+
+        let start = input.next_start();
+        match any.parse_next(input)? {
+            TStr(name) | Ident(name) if name.ends_with(b"!") => {
+                add_template(start.into(), input, builder)
+            }
+            TStr(name) | Ident(name) if name == b"code" => add_code(input, builder),
+            Ident(b"with") if opt(t::Dsl).parse_next(input)?.is_some() => {
+                add_with_dsl::<Many<Self>>(input, builder)
+            }
+            TStr(name) | Ident(name) => {
+                let not_empty = ![b"Entity", &b"spawn"[..], b"_"].contains(&name);
+                add_spawn(not_empty.then_some(start), input, builder)
+            }
+            token!('}') => Err(Backtrack(Error::Unexpected)),
+            bad_token => Err(Cut(Error::StartStatement(Some(bad_token).into()))),
+        }
+    }
+}
+fn add_template(name: IdentOffset, input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+    let header = builder.reserve_header();
+
+    let argument_len = Paren::<Sep<TemplateArgument>>::add_node(input, builder)?;
+    let methods_len = Opt::<Paren<Many<Method>>>::add_node(input, builder)?;
+    let children_len = Opt::<Curly<Many<St>>>::add_node(input, builder)?;
+
+    let argument_count = argument_len / TemplateArgumentHeader::SIZE;
+    let writer = (
+        StKind::Template,
+        TemplateHeader { name, argument_count, methods_len, children_len },
+    );
+    builder.write(header, writer);
+    Ok(TemplateHeader::SIZE + argument_len + methods_len + children_len)
+}
+
+/// A `with dsl Name { statements }` block, following the `with dsl` tokens.
+///
+/// `Body` is the statements of the block, a single statement at the root of
+/// the file, since chirp files have a single root entity.
+fn add_with_dsl<Body: AddNodes>(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+    let header = builder.reserve_header();
+
+    let name = ident(input)?;
+    let children_len = Curly::<Body>::add_node(input, builder)?;
+
+    builder.write(header, (StKind::WithDsl, WithDslHeader { name, children_len }));
+    Ok(WithDslHeader::SIZE + children_len)
+}
+
+/// The root statement of a chirp file.
+///
+/// Same as [`St`], but `with dsl` blocks contain a single statement.
+struct RootSt;
+impl AddNodes for RootSt {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        let start = input.checkpoint();
+        let is_with = matches!(input.next_token(), Some(Token::Ident(b"with")));
+        if is_with && opt(t::Dsl).parse_next(input)?.is_some() {
+            return add_with_dsl::<Self>(input, builder);
+        }
+        input.reset(start);
+        St::add_node(input, builder)
+    }
+}
+
+fn add_code(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+    t::Lparen.parse_next(input)?;
+    let header = builder.reserve_header();
+
+    let name = ident(input)?;
+    let argument_len = <Tokens![Many<CodeArgument>, ')']>::add_node(input, builder)?;
+
+    let argument_count = argument_len / ArgumentHeader::SIZE;
+    builder.write(header, (StKind::Code, CodeHeader { name, argument_count }));
+    Ok(CodeHeader::SIZE + argument_len)
+}
+
+/// An [`Argument`] following the name of a `code` statement, `, argument`.
+struct CodeArgument;
+impl AddNodes for CodeArgument {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        t::Comma.parse_next(input)?;
+        Argument::add_node(input, builder)
+    }
+}
+
+fn add_spawn(name: Option<u32>, input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+    let header = builder.reserve_header();
+
+    let (methods_len, children_len) = match any.parse_next(input)? {
+        token!('(') => {
+            let methods = <Tokens![Many<Method>, ')']>::add_node(input, builder)?;
+            let children = Opt::<Curly<Many<St>>>::add_node(input, builder)?;
+            (methods, children)
+        }
+        token!('{') => (0, <Tokens![Many<St>, '}']>::add_node(input, builder)?),
+        bad_token => return Err(Cut(Error::StatementDelimiter(Some(bad_token).into()))),
+    };
+    let name = name.into();
+    let writer = (
+        StKind::Spawn,
+        SpawnHeader { name, methods_len, children_len },
+    );
+    builder.write(header, writer);
+    Ok(SpawnHeader::SIZE + methods_len + children_len)
+}
+
+struct Method;
+impl AddNodes for Method {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        // Note: header created after name so that we don't create it when closing method zone
+        let name = ident.parse_next(input)?;
+        let header = builder.reserve_header();
+
+        let argument_len = Opt::<Paren<SepList<Argument>>>::add_node(input, builder)?;
+
+        let argument_count = argument_len / ArgumentHeader::SIZE;
+        builder.write(header, MethodHeader { argument_count, name });
+        Ok(MethodHeader::SIZE + argument_len)
+    }
+}
+
+struct ChirpFile;
+impl AddNodes for ChirpFile {
+    fn add_node(input: &mut Input, builder: &mut AstBuilder) -> BlockResult {
+        // The version header is only relevant to `cuicui_chirp::migrate`.
+        opt(preceded(t::Chirp, ident)).parse_next(input)?;
+        // The DSL hint is checked before parsing, see `dsl_hint` in `cuicui_chirp::interpret`.
+        opt(preceded(t::Dsl, ident)).parse_next(input)?;
+        let header = builder.reserve_header();
+
+        let import_len = Many::<Import>::add_node(input, builder)?;
+        let fn_len = Many::<Fn>::add_node(input, builder)?;
+        let root_statement_len = RootSt::add_node(input, builder)?;
+
+        let import_count = import_len / ImportHeader::SIZE;
+        let root_statement_offset = ChirpFileHeader::SIZE + import_len + fn_len;
+        builder.write(
+            header,
+            ChirpFileHeader { import_count, root_statement_offset },
+        );
+        Ok(root_statement_offset + root_statement_len)
+    }
+}
+
+/// Parse `input` into an [`Ast`], or return the error and its span.
+///
+/// # Errors
+/// When `input` is not a valid chirp file.
+pub fn chirp_file(mut input: Input) -> Result<Ast, (Error, (u32, u32))> {
+    let initial_input = input;
+    let mut ast_builder = AstBuilder::new();
+    let result = ChirpFile::add_node(&mut input, &mut ast_builder);
+    let offset = input.current_offset();
+
+    let error = match result {
+        Ok(_) if input.is_empty() => return Ok(ast_builder.build()),
+        Ok(_) => Error::TrailingText,
+        Err(Cut(err) | Backtrack(err)) => err,
+        _ => unreachable!(),
+    };
+    let delimiter_error = unterminated_string(initial_input, offset)
+        .or_else(|| confusable_char(initial_input, offset));
+    Err(delimiter_error.unwrap_or((error, (offset, offset))))
+}
+
+/// An error pointing to the opening quote of the unterminated string literal
+/// in `input`, if the parser reached it before failing at `offset`.
+///
+/// Unterminated strings extend to the end of the file, so the parser fails
+/// far from where the actual mistake is.
+fn unterminated_string(input: Input, offset: u32) -> Option<(Error, (u32, u32))> {
+    let mut tokens = input.iter_offsets();
+    let (start, _) = tokens.find(|(_, token)| matches!(token, Token::UnterminatedString(_)))?;
+    let start = u32::try_from(start).unwrap();
+    (start < offset).then_some((Error::UnterminatedString, (start, start + 1)))
+}
+
+/// An error pointing to the first character looking like a delimiter in an
+/// identifier in `input`, if the parser reached it before failing at `offset`.
+///
+/// Such identifiers swallow what the user intended as delimiters, so the parser
+/// fails later than where the actual mistake is.
+fn confusable_char(input: Input, offset: u32) -> Option<(Error, (u32, u32))> {
+    let mut tokens = input.iter_offsets();
+    let (start, (char_offset, found, ascii)) = tokens.find_map(|(start, token)| match token {
+        Token::Ident(ident) => Some((start, confusable(ident)?)),
+        _ => None,
+    })?;
+    let start = u32::try_from(start + char_offset).unwrap();
+    let end = start + u32::try_from(found.len_utf8()).unwrap();
+    (start < offset).then_some((Error::ConfusableChar(found, ascii), (start, end)))
+}
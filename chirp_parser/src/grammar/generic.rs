@@ -4,8 +4,8 @@ use winnow::stream::Stream;
 use winnow::{error::ErrMode, Parser};
 
 use super::{AddNodes, BlockResult, Error};
-use crate::parser::ast::AstBuilder;
-use crate::parser::stream::{tokens, Input, Token};
+use crate::ast::AstBuilder;
+use crate::stream::{tokens, Input, Token};
 
 pub(super) struct Delimited<T, P1, P2>(PhantomData<(T, P1, P2)>);
 impl<T: AddNodes, P1, P2> AddNodes for Delimited<T, P1, P2>
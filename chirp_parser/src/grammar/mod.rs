@@ -14,21 +14,37 @@
 //! Method = 'ident' ('(' (TokenTree)* ')')?
 //!
 //! Statement
-//!    = 'code'      '(' 'ident' ')'
+//!    = 'code'      '(' 'ident' (',' TokenTree)* ')'
 //!    | 'Entity'    StatementTail
-//!    | 'ident' '!' '(' (TokenTree (',' TokenTree)*)? ')' (StatementTail)?
+//!    | 'ident' '!' '(' (Argument (',' Argument)*)? ')' (StatementTail)?
 //!    | 'ident'     StatementTail
 //!    | StringLit   StatementTail
+//!    | 'with' 'dsl' 'ident' '{' (Statement)* '}'
+//!
+//! Argument = ('ident:')? (TokenTree)*
 //!
 //! StatementTail
 //!    = '(' (Method)* ')' ('{' (Statement)* '}')?
 //!    | '{' (Statement)* '}'
 //!
 //! Path = 'ident' ('/' 'ident')*
-//! Use = 'use' Path ('as' 'ident')?
-//! Fn = ('pub')? 'fn' 'ident' '(' ('ident' (',' 'ident')*)? ')' '{' Statement '}'
-//! ChirpFile = (Use)* (Fn)* Statement
+//! Use = 'use' (Path | StringLit) ('as' 'ident')?
+//! Parameter = 'ident' ('=' (TokenTree)*)?
+//! Fn = ('pub')? 'fn' 'ident' '(' (Parameter (',' Parameter)*)? ')' '{' Statement '}'
+//! Version = 'chirp' 'ident'
+//! ChirpFile = (Version)? (Use)* (Fn)* Statement
 //! ```
+//!
+//! A `with dsl` block at the root of the file contains a single statement.
+//!
+//! Template names in statements may be prefixed by the alias of an import,
+//! as in `w::button!()` or `w.button!()`. Since `:` and `.` are valid
+//! identifier characters, the prefix is part of the `'ident'` token, and is
+//! resolved by the interpreter.
+//!
+//! Named template arguments, as in `button!(text: "Hi")`, start with an
+//! identifier ending with `:`. For the same reason, `text:` is a single
+//! `'ident'` token.
 #![allow(clippy::inline_always)]
 // allow: The generated code is fine, it's in line with how winnow does things
 // internally.
@@ -2,7 +2,7 @@ use winnow::combinator::{delimited, opt, separated};
 use winnow::Parser;
 
 use super::{tokens::many_tts, *};
-use crate::parser::stream::tokens;
+use crate::stream::tokens;
 
 fn split_tt(str_input: &'static str) -> Vec<&'static str> {
     let input = Input::new(str_input.as_bytes(), ());
@@ -67,6 +67,29 @@ fn valid_str_escape() {
     assert_eq!(split_tt(escape_quotes), vec![r#""\'hello world\"""#]);
 }
 #[test]
+fn valid_raw_str() {
+    assert_eq!(split_tt(r#"(r"hello\")"#), vec![r#"r"hello\""#]);
+    assert_eq!(split_tt(r##"(r#"say "hi", bye"#)"##), vec![r##"r#"say "hi", bye"#"##]);
+    assert_eq!(split_tt("(r\"multi\nline\", r)"), vec!["r\"multi\nline\"", "r"]);
+    assert_eq!(split_tt(r###"(r#hello, r##"a"#"##)"###), vec!["r#hello", r###"r##"a"#"##"###]);
+}
+#[test]
+fn unterminated_str_span() {
+    let error = |input: &str| chirp_file(Input::new(input.as_bytes(), ())).err();
+    let unterminated = Some((Error::UnterminatedString, (12, 13)));
+    assert_eq!(error("Entity(text(\"hello) {}"), unterminated);
+    assert_eq!(error("Entity(text(r#\"hello\") {}"), unterminated);
+    assert_eq!(error("Entity(text(r#\"hello\"#))"), None);
+}
+#[test]
+fn confusable_char_span() {
+    let error = |input: &str| chirp_file(Input::new(input.as_bytes(), ())).err();
+    let paren = Some((Error::ConfusableChar('（', '('), (6, 9)));
+    assert_eq!(error("Entity（row）"), paren);
+    assert_eq!(error("Entity（row） {}"), None);
+    assert_eq!(error("\"名前（一）\"(text(\"（\"))"), None);
+}
+#[test]
 fn valid_split_tt() {
     let fn_call = "(pct(100), px(34))";
     let fn_call_ws = "(  pct (\"hi\") , px   ( 34 ) )";
@@ -6,8 +6,8 @@ use winnow::trace::trace;
 use winnow::{PResult, Parser};
 
 use super::Error;
-use crate::parser::stream::{tokens, Token, TokenType};
-use crate::parser::{ast, Input};
+use crate::stream::{tokens, Token, TokenType};
+use crate::{ast, Input};
 
 #[rustfmt::skip]
 macro_rules! token {
@@ -38,6 +38,15 @@ pub(super) fn ident(input: &mut Input) -> PResult<ast::IdentOffset, Error> {
         got => Err(Backtrack(Error::Expected(TokenType::Ident, got.into()))),
     }
 }
+/// An identifier or a string literal.
+#[inline(always)]
+pub(super) fn name(input: &mut Input) -> PResult<ast::NameOffset, Error> {
+    let start = input.next_start();
+    match input.next_token() {
+        Some(token!("ident" | "string")) => Ok(start.into()),
+        got => Err(Backtrack(Error::Expected(TokenType::Ident, got.into()))),
+    }
+}
 fn span_from_len(start: u32, len: usize) -> (u32, u32) {
     (start, start + u32::try_from(len).unwrap())
 }
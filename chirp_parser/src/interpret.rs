@@ -0,0 +1,420 @@
+//! Interpret the [`ast`] using an [`Interpreter`]
+//!
+//! # Architecture
+//!
+//! The AST starts at [`ChirpFile`], the API used outside of this module is:
+//!
+//! - Create a [`ChirpFile`] using [`ChirpFile::new`]
+//! - Run an interpreter through the AST using [`ChirpFile::interpret`]
+//!
+//! [`Interpreter`] formalizes the operations available on the `ChirpFile` AST,
+//! it is basically an implementation of the rust Visitor pattern.
+//!
+//! ## Templates
+//!
+//! Since chirp files support templates, we need to be able to call templates.
+//! This requires both being able to interpret the nodes of the `fn` statements,
+//! but also being able to replace the template parameters by the values provided
+//! as argument to the template when it was called.
+//!
+//! An _extra_ tricky bit are the "template extras". One is able to add children
+//! and methods to the statement of a template.
+//!
+//! It is particularly pernicious when the extras contain template parameters,
+//! as it needs to expand according to the calling context's arguments, not
+//! the final context.
+//!
+//! This is why we split [`ChirpTemplate`] off from [`ChirpFile`]. `ChirpTemplate`
+//! only works for a single statement, and accmulates to that single statements
+//! the "template extras" from the chain of templates that led to the current
+//! statement.
+//!
+//! To do that, we need to insert methods and children at precise locations. So we
+//! somehow need to keep track of that chain. We can do that without a single heap
+//! allocation. What we do is track the call chain to the current template site
+//! through a single `parent: Option<&Self>` field. When spawning the root statement,
+//! we walk back the whole call stack (in the actual machine stack) and read all
+//! the extras for each stack level. We can read the parent's `Parameters` field
+//! to get the correct parameter substitution for that particular extra.
+//!
+//! ## Imported templates
+//!
+//! Templates may be declared in another file than the one interpreted, see
+//! [`ChirpFile::library`]. A [`Declaration`] keeps track of the file declaring
+//! it, the body of a template is read from the file declaring it, while its
+//! template extras are read from the file of the template call.
+//!
+//! The [`Interpreter`] is told when nodes start being read from another file
+//! with [`Interpreter::enter_file`], so that it can resolve template names
+//! with the templates of that file, and report errors in the right file.
+use tracing::trace;
+
+use super::ast::{self, AstRef, FnIndex, Template};
+use super::scope::{check_arguments, ArgumentError, Arguments, Parameters};
+use super::Input;
+
+/// The start and end byte offsets of a piece of text in the [`Input`].
+pub type Span = (u32, u32);
+/// An identifier or string in the [`Input`], with its [`Span`].
+pub type Name<'a> = (&'a [u8], Span);
+
+/// A template declaration, and the file declaring it.
+///
+/// See [`Interpreter::register_fn`] and [`Interpreter::get_template`].
+#[derive(Clone, Copy)]
+pub struct Declaration<'i, 'a> {
+    index: FnIndex<'a>,
+    file: u32,
+    input: Input<'i>,
+    ast: AstRef<'a>,
+}
+
+// TODO(clean): There is a bit of duplicate code between ChirpTemplate and ChirpFile
+struct ChirpCall<'t, 'i, 'a> {
+    /// The name of the template, as declared.
+    name: &'i [u8],
+    /// The file declaring the template, the template body is read from it.
+    file: u32,
+    input: Input<'i>,
+    ast: AstRef<'a>,
+    params: Parameters<'i>,
+    /// The scope of the template call, template extras are read from it.
+    caller: ChirpFile<'i, 'a>,
+    /// The span of the template call, in the `caller` file.
+    call_span: Span,
+    parent: Option<&'t ChirpCall<'t, 'i, 'a>>,
+    trailing_methods: ast::Methods<'a>,
+    trailing_children: ast::Statements<'a>,
+}
+impl<'t, 'i, 'a> ChirpCall<'t, 'i, 'a> {
+    fn new(
+        caller: ChirpFile<'i, 'a>,
+        (declr, params, call_span): (Declaration<'i, 'a>, Parameters<'i>, Span),
+        template: Template<'a>,
+        parent: Option<&'t Self>,
+    ) -> Self {
+        ChirpCall {
+            name: declr.index.get().name().read(&declr.input),
+            file: declr.file,
+            input: declr.input,
+            ast: declr.ast,
+            params,
+            caller,
+            call_span,
+            trailing_methods: template.methods(),
+            trailing_children: template.children(),
+            parent,
+        }
+    }
+    fn interpret_template(&self, tpl: Template<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        let file = self.file();
+        let Some(call) = file.expand(tpl, runner) else {
+            return;
+        };
+        let body = call.0.index.get().body();
+        ChirpCall::new(file, call, tpl, Some(self)).interpret_body(body, runner);
+    }
+    /// Interpret the template `body`, from the file declaring the template.
+    fn interpret_body(&self, body: ast::Statement<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        let other_file = self.file != self.caller.file;
+        runner.enter_template(self.name, self.caller.file, self.call_span);
+        if other_file {
+            runner.enter_file(self.file, self.call_span);
+        }
+        self.interpret_root(body, runner);
+        if other_file {
+            runner.exit_file();
+        }
+        runner.exit_template();
+    }
+    // This function is similar to [`ChirpFile::interpret_spawn`] with the
+    // difference that it inlines the passed "template extras" to the root expression.
+    //
+    // To do this is extra tricky, because:
+    // 1. "template extras" should be evaulated with the scope of their caller
+    // 2. We may inherit template extras from deeper ancestors than the direct parent.
+    // 3. And of course, those deeper template extras need to be evaluated with their own
+    //    caller.
+    fn interpret_spawn(&self, spawn: ast::Spawn<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        trace!("{} - {spawn:?}", spawn.block_index(self.ast));
+        let inp = &self.input;
+        if let Some(name) = spawn.name().get_with_span(inp) {
+            runner.set_name(name);
+        }
+        for method in spawn.methods().iter() {
+            trace!("{} - {method:?}", method.block_index(self.ast));
+            let (name, arguments) = (method.name(), method.arguments());
+            let arguments = Arguments::new(*inp, arguments, &self.params);
+            runner.method(name.read_spanned(inp), &arguments);
+        }
+        let mut no_children = spawn.children().is_empty();
+        let mut this = self;
+        loop {
+            if !this.trailing_methods.is_empty() {
+                runner.template_extras();
+                self.enter_caller_file(this, runner);
+            }
+            let caller = &this.caller;
+            for method in this.trailing_methods.iter() {
+                trace!("{} - {method:?}", method.block_index(caller.ast));
+                let (name, arguments) = (method.name(), method.arguments());
+                let arguments = Arguments::new(caller.input, arguments, &caller.params);
+                runner.method(name.read_spanned(&caller.input), &arguments);
+            }
+            if !this.trailing_methods.is_empty() {
+                self.exit_caller_file(this, runner);
+            }
+            no_children &= this.trailing_children.is_empty();
+            this = match this.parent {
+                None => break,
+                Some(v) => v,
+            };
+        }
+        if no_children {
+            runner.spawn_leaf();
+        } else {
+            runner.start_children();
+            for statement in spawn.children().iter() {
+                self.file().interpret_statement(statement, runner);
+            }
+            let mut this = self;
+            loop {
+                if !this.trailing_children.is_empty() {
+                    self.enter_caller_file(this, runner);
+                    for statement in this.trailing_children.iter() {
+                        this.caller.interpret_statement(statement, runner);
+                    }
+                    self.exit_caller_file(this, runner);
+                }
+                this = match this.parent {
+                    None => break,
+                    Some(v) => v,
+                };
+            }
+            runner.complete_children();
+        }
+    }
+    /// Template extras of `call` are read from the file of its caller, which
+    /// may not be the file of this template body.
+    fn enter_caller_file(&self, call: &Self, runner: &mut impl Interpreter<'i, 'a>) {
+        if call.caller.file != self.file {
+            runner.enter_file(call.caller.file, call.call_span);
+        }
+    }
+    fn exit_caller_file(&self, call: &Self, runner: &mut impl Interpreter<'i, 'a>) {
+        if call.caller.file != self.file {
+            runner.exit_file();
+        }
+    }
+    fn file(&self) -> ChirpFile<'i, 'a> {
+        ChirpFile {
+            file: self.file,
+            input: self.input,
+            ast: self.ast,
+            params: self.params.clone(),
+        }
+    }
+    fn interpret_root(&self, statement: ast::Statement<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        match statement.typed() {
+            ast::StType::Template(template) => self.interpret_template(template, runner),
+            ast::StType::Spawn(spawn) => self.interpret_spawn(spawn, runner),
+            // TODO(bug): Need to add the template extras here.
+            ast::StType::Code(code) => self.file().interpret_code(code, runner),
+            ast::StType::WithDsl(with) => self.file().interpret_with_dsl(with, runner),
+        }
+    }
+}
+/// A parsed chirp file, to walk with an [`Interpreter`].
+#[derive(Clone)]
+pub struct ChirpFile<'i, 'a> {
+    /// Identifies the file in [`Interpreter::enter_file`], `0` for the
+    /// interpreted file.
+    file: u32,
+    input: Input<'i>,
+    ast: AstRef<'a>,
+    params: Parameters<'i>,
+}
+impl<'i, 'a> ChirpFile<'i, 'a> {
+    /// The `ast` of `input`, as parsed by [`chirp_file`](crate::chirp_file).
+    #[must_use]
+    pub fn new(input: Input<'i>, ast: AstRef<'a>) -> Self {
+        Self::library(input, ast, 0)
+    }
+    /// A file imported by the interpreted file, to get its [`Self::templates`].
+    ///
+    /// `file` identifies it in [`Interpreter::enter_file`], it should be unique
+    /// and not `0`, which is the file created with [`Self::new`].
+    #[must_use]
+    pub fn library(input: Input<'i>, ast: AstRef<'a>, file: u32) -> Self {
+        Self { file, input, ast, params: Parameters::empty() }
+    }
+    /// The `use name as alias` imports of this file.
+    pub fn imports(&self) -> impl Iterator<Item = (Name<'i>, Option<Name<'i>>)> + '_ {
+        let file = self.ast.chirp_file();
+        trace!("{} - {file:?}", file.block_index(self.ast));
+        file.imports().iter().map(|import| {
+            trace!("{} - {import:?}", import.block_index(self.ast));
+            let (name, alias) = (import.name(), import.alias());
+            (name.read_spanned(&self.input), alias.read_spanned(&self.input))
+        })
+    }
+    /// The `fn name()` template declarations of this file.
+    pub fn templates(&self) -> impl Iterator<Item = (Name<'i>, Declaration<'i, 'a>)> + '_ {
+        self.ast.chirp_file().fn_declrs().iter().map(|fn_declr| {
+            trace!("{} - {fn_declr:?}", fn_declr.block_index(self.ast));
+            let (file, input, ast) = (self.file, self.input, self.ast);
+            let declaration = Declaration { index: fn_declr.index(), file, input, ast };
+            (fn_declr.name().read_spanned(&self.input), declaration)
+        })
+    }
+
+    /// Find the template called by `tpl`, and its parameters in this scope.
+    fn expand(
+        &self,
+        tpl: Template<'a>,
+        runner: &mut impl Interpreter<'i, 'a>,
+    ) -> Option<(Declaration<'i, 'a>, Parameters<'i>, Span)> {
+        let inp = &self.input;
+        let (mut name, span) = tpl.name().read_spanned(inp);
+        name = &name[..name.len() - 1];
+        let declr = runner.get_template((name, span))?;
+        let report = |span, error| runner.argument_error(span, error);
+        let parameters = declr.index.get().parameters();
+        check_arguments((name, span), parameters, &declr.input, tpl.arguments(), inp, report);
+        let parameters = self.params.scope(parameters, &declr.input, tpl.arguments(), inp);
+        Some((declr, parameters, span))
+    }
+    fn interpret_spawn(&self, spawn: ast::Spawn<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        trace!("{} - {spawn:?}", spawn.block_index(self.ast));
+        let inp = &self.input;
+        if let Some(name) = spawn.name().get_with_span(inp) {
+            runner.set_name(name);
+        }
+        for method in spawn.methods().iter() {
+            trace!("{} - {method:?}", method.block_index(self.ast));
+            let (name, arguments) = (method.name(), method.arguments());
+            let arguments = Arguments::new(*inp, arguments, &self.params);
+            runner.method(name.read_spanned(inp), &arguments);
+        }
+        if spawn.children().is_empty() {
+            runner.spawn_leaf();
+        } else {
+            runner.start_children();
+            for statement in spawn.children().iter() {
+                self.interpret_statement(statement, runner);
+            }
+            runner.complete_children();
+        }
+    }
+    fn interpret_template(&self, tpl: Template<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        trace!("{} - {tpl:?}", tpl.block_index(self.ast));
+        let Some(call) = self.expand(tpl, runner) else {
+            return;
+        };
+        let body = call.0.index.get().body();
+        ChirpCall::new(self.clone(), call, tpl, None).interpret_body(body, runner);
+    }
+    fn interpret_statement(&self, st: ast::Statement<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        match st.typed() {
+            ast::StType::Template(template) => self.interpret_template(template, runner),
+            ast::StType::Spawn(spawn) => self.interpret_spawn(spawn, runner),
+            ast::StType::Code(code) => self.interpret_code(code, runner),
+            ast::StType::WithDsl(with) => self.interpret_with_dsl(with, runner),
+        }
+    }
+    fn interpret_code(&self, code: ast::Code<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        trace!("{} - {code:?}", code.block_index(self.ast));
+        let inp = &self.input;
+        let arguments = Arguments::new(*inp, code.arguments(), &self.params);
+        runner.code(code.name().read_spanned(inp), &arguments);
+    }
+    fn interpret_with_dsl(&self, with: ast::WithDsl<'a>, runner: &mut impl Interpreter<'i, 'a>) {
+        trace!("{} - {with:?}", with.block_index(self.ast));
+        runner.start_dsl(with.name().read_spanned(&self.input));
+        for statement in with.children().iter() {
+            self.interpret_statement(statement, runner);
+        }
+        runner.complete_dsl();
+    }
+    /// Call the methods of `runner` for each node of the file, in order,
+    /// expanding templates.
+    pub fn interpret(&self, runner: &mut impl Interpreter<'i, 'a>) {
+        for (name, alias) in self.imports() {
+            runner.import(name, alias);
+        }
+        for (name, declaration) in self.templates() {
+            runner.register_fn(name, declaration);
+        }
+        self.interpret_statement(self.ast.chirp_file().root_statement(), runner);
+    }
+}
+/// Operations on the nodes of a [`ChirpFile`], see [`ChirpFile::interpret`].
+pub trait Interpreter<'i, 'a> {
+    /// A `use name as alias` import.
+    fn import(&mut self, name: Name<'i>, alias: Option<Name<'i>>);
+    /// A `fn name()` template declaration.
+    fn register_fn(&mut self, name: Name<'i>, declaration: Declaration<'i, 'a>);
+    /// The template declaration called with `name!()`, `None` to skip the call.
+    ///
+    /// This may return templates of imported files, see [`ChirpFile::templates`].
+    fn get_template(&mut self, name: Name<'i>) -> Option<Declaration<'i, 'a>>;
+    /// A `code(name, arguments)` statement.
+    fn code(&mut self, code: Name<'i>, arguments: &Arguments);
+    /// The current statement has no children.
+    fn spawn_leaf(&mut self) {
+        self.start_children();
+        self.complete_children();
+    }
+    /// The name of the current statement.
+    fn set_name(&mut self, name: Name);
+    /// Following statements are children of the current statement.
+    fn start_children(&mut self);
+    /// The statement whose children were started last is complete.
+    fn complete_children(&mut self);
+    /// A method of the current statement.
+    fn method(&mut self, name: Name<'i>, arguments: &Arguments);
+    /// An argument of the last template returned by [`Self::get_template`]
+    /// doesn't match its parameters.
+    fn argument_error(&mut self, _span: Span, _error: ArgumentError) {}
+    /// Following [`Self::method`] calls are template extras of the current
+    /// statement, applied after the methods declared in the template body.
+    fn template_extras(&mut self) {}
+    /// Following statements, until the matching [`Self::complete_dsl`], are
+    /// written for the `name` DSL.
+    fn start_dsl(&mut self, _name: Name<'i>) {}
+    /// The last `with dsl` block is complete.
+    fn complete_dsl(&mut self) {}
+    /// Following nodes, until the matching [`Self::exit_file`], are read from
+    /// another file, `file` as passed to [`ChirpFile::library`].
+    ///
+    /// This happens when interpreting the body of a template declared in
+    /// another file, or the template extras of such a template call.
+    /// `call` is the span of the template call, in the file of the call.
+    /// Spans of following nodes are in `file`.
+    fn enter_file(&mut self, _file: u32, _call: Span) {}
+    /// Nodes are read again from the file they were read from before the last
+    /// [`Self::enter_file`].
+    fn exit_file(&mut self) {}
+    /// Following nodes, until the matching [`Self::exit_template`], are
+    /// expanded from the template `name` (as declared), called at `call` in
+    /// `file`.
+    ///
+    /// This happens before the [`Self::enter_file`] of templates declared in
+    /// another file. Use it to track the chain of template calls leading to
+    /// a node, for example to report it in errors.
+    fn enter_template(&mut self, _name: &'i [u8], _file: u32, _call: Span) {}
+    /// The template of the last [`Self::enter_template`] is fully expanded.
+    fn exit_template(&mut self) {}
+}
+impl<'i, 'a> Interpreter<'i, 'a> for () {
+    fn code(&mut self, _: Name, _: &Arguments) {}
+    fn import(&mut self, _: Name, _: Option<Name>) {}
+    fn register_fn(&mut self, _: Name, _: Declaration<'i, 'a>) {}
+    fn get_template(&mut self, _: Name) -> Option<Declaration<'i, 'a>> {
+        None
+    }
+    fn set_name(&mut self, _: Name) {}
+    fn start_children(&mut self) {}
+    fn method(&mut self, _: Name, _: &Arguments) {}
+    fn complete_children(&mut self) {}
+}
@@ -7,6 +7,7 @@ use super::stream::Token;
 const RECOGNIZED_SYMBOL_COUNT: usize = 15;
 const RECOGNIZED_SYMBOLS: [u8; RECOGNIZED_SYMBOL_COUNT] = *b"=(){}[],\"'/ \n\t\r";
 
+#[derive(Clone, Copy)]
 enum Recognized {
     Equal = 0,
     Lparen = 1,
@@ -22,60 +23,52 @@ enum Recognized {
     Space = 11,
     Ident = 15,
 }
+/// What each byte value is recognized as, indexed by the byte.
+///
+/// A lookup table is much faster than comparing the byte with each of
+/// the [`RECOGNIZED_SYMBOLS`].
+const RECOGNIZE_TABLE: [Recognized; 256] = {
+    let mut table = [Recognized::Ident; 256];
+    let mut i = 0;
+    while i < RECOGNIZED_SYMBOL_COUNT {
+        table[RECOGNIZED_SYMBOLS[i] as usize] = match i {
+            0 => Recognized::Equal,
+            1 => Recognized::Lparen,
+            2 => Recognized::Rparen,
+            3 => Recognized::Lcurly,
+            4 => Recognized::Rcurly,
+            5 => Recognized::Lbracket,
+            6 => Recognized::Rbracket,
+            7 => Recognized::Comma,
+            8 => Recognized::Quote,
+            9 => Recognized::Apostrophe,
+            10 => Recognized::Slash,
+            _ => Recognized::Space,
+        };
+        i += 1;
+    }
+    table
+};
 // allow: I manually reviewed the generated code and it is better with the inline.
 #[allow(clippy::inline_always)]
 #[inline(always)]
-fn recognize(x: u8) -> Recognized {
-    let mut cmp_table = [x; RECOGNIZED_SYMBOL_COUNT];
-
-    for i in 0..RECOGNIZED_SYMBOL_COUNT {
-        cmp_table[i] ^= RECOGNIZED_SYMBOLS[i];
-    }
-    match cmp_table.iter().position(|x| *x == 0) {
-        Some(0) => Recognized::Equal,
-        Some(1) => Recognized::Lparen,
-        Some(2) => Recognized::Rparen,
-        Some(3) => Recognized::Lcurly,
-        Some(4) => Recognized::Rcurly,
-        Some(5) => Recognized::Lbracket,
-        Some(6) => Recognized::Rbracket,
-        Some(7) => Recognized::Comma,
-        Some(8) => Recognized::Quote,
-        Some(9) => Recognized::Apostrophe,
-        Some(10) => Recognized::Slash,
-        Some(11..=14) => Recognized::Space,
-        None => Recognized::Ident,
-        Some(_) => unreachable!("==OPTIMIZEDOUT== match position in const size array"),
-    }
+const fn recognize(x: u8) -> Recognized {
+    RECOGNIZE_TABLE[x as usize]
 }
 
 struct Ident<'i>(&'i [u8]);
 
 impl<'i> Ident<'i> {
     fn next(&mut self, input: &mut &[u8]) -> &'i [u8] {
-        use Recognized::{
-            Apostrophe, Comma, Equal, Lbracket, Lcurly, Lparen, Quote, Rbracket, Rcurly, Rparen,
-            Space,
-        };
-        loop {
-            let Some(&next) = input.first() else {
-                return self.0;
-            };
-            match recognize(next) {
-                Equal | Lparen | Rparen | Lcurly | Rcurly | Quote | Apostrophe | Space
-                | Lbracket | Rbracket | Comma => return self.0,
-
-                // TODO(feat): comment on double slash
-                Recognized::Slash | Recognized::Ident => {
-                    let len = self.0.len();
-                    let ptr = <[u8]>::as_ptr(self.0);
-                    // SAFETY: the `.0` slice is a subslice of `input`,
-                    // we just checked that input has an additional slot.
-                    self.0 = unsafe { slice::from_raw_parts(ptr, len + 1) };
-                    *input = &input[1..];
-                }
-            }
-        }
+        // TODO(feat): comment on double slash
+        let is_ident = |x: &u8| matches!(recognize(*x), Recognized::Slash | Recognized::Ident);
+        let len = input.iter().position(|x| !is_ident(x)).unwrap_or(input.len());
+        *input = &input[len..];
+        let ptr = <[u8]>::as_ptr(self.0);
+        // SAFETY: the `.0` slice is a subslice of `input`, immediately followed
+        // by the rest of `input`, and `input` has at least `len` more bytes.
+        self.0 = unsafe { slice::from_raw_parts(ptr, self.0.len() + len) };
+        self.0
     }
 }
 
@@ -95,7 +88,14 @@ pub(super) unsafe fn next_statement_name<'i>(input: &mut &'i [u8]) -> &'i [u8] {
             q.next(input);
             q.0
         }
-        Recognized::Ident => Ident(first).next(input),
+        Recognized::Ident => match raw_string_hashes(first, input) {
+            Some(hashes) => {
+                let mut q = RawQuoted(first);
+                q.next(input, hashes);
+                q.0
+            }
+            None => Ident(first).next(input),
+        },
         // SAFETY: Caller ensures the token is either `Quote`, `Apostrophe` or `Ident`
         _ => unsafe { std::hint::unreachable_unchecked() },
     }
@@ -137,7 +137,10 @@ fn after_space<'i>(input: &mut &'i [u8]) -> Option<Token<'i>> {
             Recognized::Quote => return Quoted::<b'"'>(first).next(input),
             Recognized::Apostrophe => return Quoted::<b'\''>(first).next(input),
             Recognized::Slash => return next_maybe_comment(input),
-            Recognized::Ident => return Some(Token::Ident(Ident(first).next(input))),
+            Recognized::Ident => match raw_string_hashes(first, input) {
+                Some(hashes) => return Some(RawQuoted(first).next(input, hashes)),
+                None => return Some(Token::Ident(Ident(first).next(input))),
+            },
             Recognized::Space => {}
         }
     }
@@ -145,36 +148,118 @@ fn after_space<'i>(input: &mut &'i [u8]) -> Option<Token<'i>> {
 
 struct Quoted<'i, const Q: u8>(&'i [u8]);
 impl<'i, const Q: u8> Quoted<'i, Q> {
-    fn advance(&mut self, input: &mut &'i [u8]) -> Option<u8> {
-        let first = *input.first()?;
-        let len = self.0.len();
+    /// Add the `len` first bytes of `input` to this string.
+    fn advance(&mut self, input: &mut &'i [u8], len: usize) {
+        *input = &input[len..];
         let ptr = <[u8]>::as_ptr(self.0);
-        // SAFETY: the `.0` slice is a subslice of `input`,
-        // we just checked that input has an additional slot.
-        self.0 = unsafe { slice::from_raw_parts(ptr, len + 1) };
-        *input = &input[1..];
-        Some(first)
+        // SAFETY: the `.0` slice is a subslice of `input`, immediately followed
+        // by the rest of `input`. The previous line checks `input` has `len` bytes.
+        self.0 = unsafe { slice::from_raw_parts(ptr, self.0.len() + len) };
     }
-    // TODO(perf): have a "rough" pass checking for Q or backslash. If not, we
-    // don't care about the N next u8.
+    /// Only the closing quote and backslashes are relevant in a string, so we
+    /// skip to them with `memchr2`.
     fn next(&mut self, input: &mut &'i [u8]) -> Option<Token<'i>> {
-        let mut esc = false;
         loop {
-            let Some(last) = self.advance(input) else {
-                // TODO(bug): when the string is not complete,
-                // The file is invalid, we should somehow handle that.
-                return (!esc).then_some(Token::String(self.0));
+            let Some(offset) = memchr::memchr2(Q, b'\\', input) else {
+                self.advance(input, input.len());
+                return Some(Token::UnterminatedString(self.0));
             };
-            match last {
-                b'\\' => esc = !esc,
-                q if q == Q && esc => esc = !esc,
-                q if q == Q => return Some(Token::String(self.0)),
-                _ => esc = false,
+            let last = input[offset];
+            self.advance(input, offset + 1);
+            if last == Q {
+                return Some(Token::String(self.0));
+            }
+            // `last` is a backslash, the next byte is escaped.
+            if input.is_empty() {
+                return Some(Token::UnterminatedString(self.0));
             }
+            self.advance(input, 1);
         }
     }
 }
 
+/// If `first` and `input` start a raw string (`r"raw"` or `r#"raw"#`), how many
+/// `#` delimit it.
+fn raw_string_hashes(first: &[u8], input: &[u8]) -> Option<usize> {
+    if first != b"r" {
+        return None;
+    }
+    let hashes = input.iter().position(|x| *x != b'#')?;
+    (input[hashes] == b'"').then_some(hashes)
+}
+/// A raw string, starting with `r`.
+///
+/// Raw strings end with a `"` followed by as many `#` as between their `r` and
+/// opening `"`. They do not have escape sequences.
+struct RawQuoted<'i>(&'i [u8]);
+impl<'i> RawQuoted<'i> {
+    /// Add the `len` first bytes of `input` to this string.
+    fn advance(&mut self, input: &mut &'i [u8], len: usize) {
+        *input = &input[len..];
+        let ptr = <[u8]>::as_ptr(self.0);
+        // SAFETY: the `.0` slice is a subslice of `input`, immediately followed
+        // by the rest of `input`. The previous line checks `input` has `len` bytes.
+        self.0 = unsafe { slice::from_raw_parts(ptr, self.0.len() + len) };
+    }
+    /// `input` starts with `hashes` `#` followed by the opening `"`.
+    fn next(&mut self, input: &mut &'i [u8], hashes: usize) -> Token<'i> {
+        let is_closing = |rest: &[u8]| rest.iter().take_while(|x| **x == b'#').count() >= hashes;
+        self.advance(input, hashes + 1);
+        loop {
+            let Some(offset) = memchr::memchr(b'"', input) else {
+                self.advance(input, input.len());
+                return Token::UnterminatedString(self.0);
+            };
+            self.advance(input, offset + 1);
+            if is_closing(input) {
+                self.advance(input, hashes);
+                return Token::String(self.0);
+            }
+        }
+    }
+}
+
+/// Non-ASCII characters that look like a [`RECOGNIZED_SYMBOLS`] delimiter, and
+/// the delimiter they look like.
+///
+/// Identifiers may contain any non-ASCII character, so text typed with an input
+/// method (`Entity（row）`) or pasted from a word processor (`text(“hi”)`) ends
+/// up in an identifier rather than being split by delimiters.
+const CONFUSABLES: [(char, char); 15] = [
+    ('＝', '='),
+    ('（', '('),
+    ('）', ')'),
+    ('｛', '{'),
+    ('｝', '}'),
+    ('［', '['),
+    ('］', ']'),
+    ('，', ','),
+    ('、', ','),
+    ('＂', '"'),
+    ('“', '"'),
+    ('”', '"'),
+    ('＇', '\''),
+    ('‘', '\''),
+    ('’', '\''),
+];
+/// The first character of `ident` that looks like a delimiter or a space, its
+/// byte offset in `ident` and the ASCII character it looks like.
+#[must_use]
+pub fn confusable(ident: &[u8]) -> Option<(usize, char, char)> {
+    if ident.is_ascii() {
+        return None;
+    }
+    let ident = std::str::from_utf8(ident).ok()?;
+    ident.char_indices().find_map(|(i, c)| {
+        let looks_like = |(confusable, _): &&(char, char)| *confusable == c;
+        match CONFUSABLES.iter().find(looks_like) {
+            Some((_, ascii)) => Some((i, c, *ascii)),
+            None if c.is_whitespace() => Some((i, c, ' ')),
+            None => None,
+        }
+    })
+}
+
 #[derive(Clone, Copy)]
 struct Swar8(u64);
 impl Swar8 {
@@ -342,4 +427,43 @@ mod tests {
         full_valid(br#""  hello world\"""#); // |  hello world"|
         full_valid(br#""\'hello world\"""#); // 'hello world"
     }
+
+    #[test]
+    fn valid_raw_string() {
+        #[track_caller]
+        fn valid(input: &[u8], expected: Token, remaining: &[u8]) {
+            let mut input = input;
+            assert_eq!(next_token(&mut input), Some(expected));
+            assert_eq!(remaining, input);
+        }
+        valid(br#"r"hello\" world"#, Token::String(br#"r"hello\""#), b" world");
+        valid(br##"r#"say "hi""#)"##, Token::String(br##"r#"say "hi""#"##), b")");
+        valid(b"r\"multi\nline\"", Token::String(b"r\"multi\nline\""), b"");
+        valid(br#"r#hello"#, Token::Ident(b"r#hello"), b"");
+        valid(br#"r#"hello""#, Token::UnterminatedString(br#"r#"hello""#), b"");
+        valid(br#""hello\""#, Token::UnterminatedString(br#""hello\""#), b"");
+    }
+
+    #[test]
+    fn valid_multibyte_ident() {
+        let mut input = "日本語(テキスト(\"こんにちは\"))".as_bytes();
+        let mut next = || next_token(&mut input);
+        assert_eq!(next(), Some(Token::Ident("日本語".as_bytes())));
+        assert_eq!(next(), Some(Token::Lparen));
+        assert_eq!(next(), Some(Token::Ident("テキスト".as_bytes())));
+        assert_eq!(next(), Some(Token::Lparen));
+        assert_eq!(next(), Some(Token::String("\"こんにちは\"".as_bytes())));
+        assert_eq!(next(), Some(Token::Rparen));
+        assert_eq!(next(), Some(Token::Rparen));
+        assert_eq!(next(), None);
+    }
+
+    #[test]
+    fn confusable_chars() {
+        assert_eq!(confusable(b"hello"), None);
+        assert_eq!(confusable("日本語".as_bytes()), None);
+        assert_eq!(confusable("Entity（row）".as_bytes()), Some((6, '（', '(')));
+        assert_eq!(confusable("名前\u{3000}空白".as_bytes()), Some((6, '\u{3000}', ' ')));
+        assert_eq!(confusable("text(“hi”)".as_bytes()), Some((5, '“', '"')));
+    }
 }
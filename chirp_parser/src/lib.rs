@@ -1,12 +1,22 @@
-//! The actual parser for `chirp` files.
+//! The parser for `chirp` files, used by [`cuicui_chirp`].
+//!
+//! This crate doesn't depend on bevy, so that tools working with chirp files,
+//! such as formatters or linters, can parse them without pulling bevy.
+//!
+//! - [`chirp_file`] parses an [`Input`] into an [`Ast`].
+//! - [`ChirpFile::interpret`] walks the `Ast` with an [`Interpreter`],
+//!   expanding templates.
+//!
+//! [`cuicui_chirp`]: https://docs.rs/cuicui_chirp/latest/cuicui_chirp/
 
 use stream::TokenType;
 
-pub use ast::{Ast, FnIndex};
-pub(crate) use grammar::chirp_file;
-pub use interpret::{ChirpFile, Interpreter, Name, Span};
-pub use scope::Arguments;
-pub use stream::{Input, StateCheckpoint};
+pub use ast::{Ast, AstRef};
+pub use grammar::chirp_file;
+pub use interpret::{ChirpFile, Declaration, Interpreter, Name, Span};
+pub use lex::confusable;
+pub use scope::{ArgumentError, Arguments};
+pub use stream::{Input, StateCheckpoint, Token};
 
 mod ast;
 mod grammar;
@@ -17,25 +27,60 @@ mod stream;
 #[cfg(test)]
 mod tests;
 
+/// A chirp file similar to what UI exporters generate, with `rows` rows of
+/// 3 lines each. Used in tests and benchmarks.
+#[doc(hidden)]
+#[must_use]
+pub fn large_file(rows: usize) -> String {
+    use std::fmt::Write;
+
+    let mut file = String::from("// Generated file, do not edit\nRoot(column) {\n");
+    for i in 0..rows {
+        let row = format!("    \"row {i}\"(row width(100%) text(\"Item \\\"{i}\\\"\") bg('#{i:06x}')) {{");
+        writeln!(file, "{row}").unwrap();
+        writeln!(file, "        Entity(image(\"icons/{i}.png\") rules(20px, 20px)) // icon").unwrap();
+        writeln!(file, "    }}").unwrap();
+    }
+    file.push('}');
+    file
+}
+
+/// An error while parsing a chirp file, see [`chirp_file`].
 #[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
+    /// Expected a token of the first type, got one of the second type.
     #[error("Expected {0}. Instead got {1}")]
     Expected(TokenType, TokenType),
+    /// A method argument's delimiters are not balanced.
     #[error("Unbalanced delimiter")]
     Unbalanced,
+    /// A bug in the parser.
     #[error("Unexpected error: the crate author didn't expect it")]
     Unexpected,
+    /// A statement name is not followed by a method or children list.
     #[error("Expected '{{' for children statements or '(' for method list. Instead got {0}")]
     StatementDelimiter(TokenType),
+    /// Expected a statement, got this token.
     #[error("Expected Entity Name. Instead got {0}")]
     StartStatement(TokenType),
+    /// Expected a method, got this token.
     #[error("Expected a method name (ident). Instead got {0}")]
     BadMethod(TokenType),
+    /// Several root statements.
     #[error("The chirp file is invalid: got additional text after the root statement")]
     TrailingText,
+    /// A string literal without closing quote.
+    #[error("This string literal is never closed")]
+    UnterminatedString,
+    /// A character looking like an ASCII delimiter or space in an identifier,
+    /// see [`confusable`].
+    #[error("'{0}' looks like '{1}', but it is part of an identifier")]
+    ConfusableChar(char, char),
 }
 impl Error {
-    pub(crate) const fn help(self) -> &'static str {
+    /// A longer explanation of the error, and how to fix it.
+    #[must_use]
+    pub const fn help(self) -> &'static str {
         match self {
             Self::Expected(..) => {
                 "Check the other errors in the error output. If they are not \
@@ -84,6 +129,19 @@ impl Error {
                 only be a single root statement. Try wrapping your statements \
                 inside a single root statement."
             }
+            Self::UnterminatedString => {
+                "String literals end with the same quote they start with. \
+                Quotes preceded by a backslash, such as `\\\"`, do not end the \
+                string. Raw strings, such as `r#\"raw\"#`, end with a `\"` \
+                followed by as many `#` as they start with.\n"
+            }
+            Self::ConfusableChar(..) => {
+                "Identifiers, such as entity and method names, may contain any \
+                non-ASCII character, so this character doesn't act as a delimiter \
+                or a space. Replace it with the ASCII character it looks like. \
+                If it is meant to be part of an entity name, quote the name, \
+                such as `\"名前（一）\"(row)`.\n"
+            }
         }
     }
 }
@@ -0,0 +1,236 @@
+use std::{borrow::Cow, fmt};
+
+use winnow::BStr;
+
+use super::{ast, Input, Name, Span};
+
+/// An error in the arguments of a template call.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArgumentError {
+    /// A named argument doesn't match any parameter of the template.
+    #[error("{}! doesn't have a '{}' parameter", BStr::new(.0), BStr::new(.1))]
+    UnknownParameter(Box<[u8]>, Box<[u8]>),
+    /// Several arguments are passed to the same parameter.
+    #[error("{}! got several arguments for the '{}' parameter", BStr::new(.0), BStr::new(.1))]
+    DuplicateArgument(Box<[u8]>, Box<[u8]>),
+    /// A parameter without default value has no argument.
+    #[error("{}! is missing an argument for the '{}' parameter", BStr::new(.0), BStr::new(.1))]
+    MissingArgument(Box<[u8]>, Box<[u8]>),
+    /// More positional arguments than parameters.
+    #[error("{}! only has {1} parameters", BStr::new(.0))]
+    TooManyArguments(Box<[u8]>, usize),
+    /// A positional argument following a `name: value` argument.
+    #[error("Positional argument after a named argument in {}!", BStr::new(.0))]
+    PositionalAfterNamed(Box<[u8]>),
+}
+impl ArgumentError {
+    /// A longer explanation of the error, and how to fix it.
+    #[must_use]
+    pub const fn help(&self) -> &'static str {
+        match self {
+            Self::UnknownParameter(..) | Self::TooManyArguments(..) => {
+                "Check the parameters of the template's `fn` declaration."
+            }
+            Self::DuplicateArgument(..) => {
+                "Remove one of the arguments. Positional arguments are passed \
+                to parameters in declaration order, named arguments (`name: value`) \
+                to the parameter with the same name."
+            }
+            Self::MissingArgument(..) => {
+                "Pass an argument for this parameter, or declare a default value \
+                for it in the template's `fn` declaration: `fn button(text = \"Ok\")`."
+            }
+            Self::PositionalAfterNamed(..) => {
+                "Named arguments (`name: value`) must come after all positional \
+                arguments, name the argument or move it before the named ones."
+            }
+        }
+    }
+}
+
+/// The parameter an argument of a template call is passed to.
+enum Bound<'i> {
+    Parameter(usize),
+    Unknown(&'i [u8]),
+    TooMany,
+    AfterNamed,
+}
+/// The parameter in `names` each of `arguments` is passed to.
+///
+/// Positional arguments are passed in order, `name: value` arguments are passed
+/// to the parameter `name`.
+fn bind<'i, 'a, 'n>(
+    names: &'n [&'i [u8]],
+    arguments: ast::TemplateArguments<'a>,
+    inp: &'n Input<'i>,
+) -> impl Iterator<Item = (Bound<'i>, ast::TemplateArgument<'a>)> + 'n
+where
+    'a: 'n,
+{
+    let (mut positional, mut any_named) = (0, false);
+    arguments.iter().map(move |argument| {
+        let bound = match argument.name().read_spanned(inp) {
+            Some((name, _)) => {
+                any_named = true;
+                let name = &name[..name.len() - 1];
+                names.iter().position(|p| *p == name).map_or(Bound::Unknown(name), Bound::Parameter)
+            }
+            None if any_named => Bound::AfterNamed,
+            None if positional >= names.len() => Bound::TooMany,
+            None => {
+                positional += 1;
+                Bound::Parameter(positional - 1)
+            }
+        };
+        (bound, argument)
+    })
+}
+
+/// Report errors in the `arguments` of a call to the `template` declared with
+/// `parameters` to `report`.
+///
+/// `parameters` are read from `declr_inp`, the file declaring the template,
+/// `arguments` from `inp`, the file of the template call.
+pub(super) fn check_arguments<'i>(
+    (template, span): Name,
+    parameters: ast::Parameters,
+    declr_inp: &Input<'i>,
+    arguments: ast::TemplateArguments,
+    inp: &Input<'i>,
+    mut report: impl FnMut(Span, ArgumentError),
+) {
+    let names: Box<[_]> = parameters.iter().map(|p| p.name().read(declr_inp)).collect();
+    let mut passed = vec![false; names.len()];
+    for (bound, argument) in bind(&names, arguments, inp) {
+        let value = argument.value();
+        let value_span = (value.start(), value.end());
+        let arg_span = argument.name().read_spanned(inp).map_or(value_span, |(_, span)| span);
+        let tpl = template.into();
+        let error = match bound {
+            Bound::Parameter(i) if !std::mem::replace(&mut passed[i], true) => continue,
+            Bound::Parameter(i) => ArgumentError::DuplicateArgument(tpl, names[i].into()),
+            Bound::Unknown(name) => ArgumentError::UnknownParameter(tpl, name.into()),
+            Bound::TooMany => ArgumentError::TooManyArguments(tpl, names.len()),
+            Bound::AfterNamed => ArgumentError::PositionalAfterNamed(tpl),
+        };
+        report(arg_span, error);
+    }
+    let params = parameters.iter().zip(&*names).zip(passed);
+    for ((param, name), _) in params.filter(|(_, passed)| !passed) {
+        if param.default_value().is_none() {
+            report(span, ArgumentError::MissingArgument(template.into(), (*name).into()));
+        }
+    }
+}
+
+/// The parameters of a template, and their value.
+///
+/// Values are read when the template is called, since the template may be
+/// declared in another file than the template call.
+#[derive(Clone)]
+pub(super) struct Parameters<'i> {
+    names: Box<[&'i [u8]]>,
+    /// The value of each parameter of `names`, `None` for missing arguments.
+    values: Box<[Option<&'i [u8]>]>,
+}
+
+impl<'i> Parameters<'i> {
+    pub(super) fn empty() -> Self {
+        Self { names: Box::new([]), values: Box::new([]) }
+    }
+    fn replace(&self, arg: &[u8]) -> Option<&'i [u8]> {
+        // TODO(bug): Need to replace also when identifer is not root
+        let index = self.names.iter().position(|name| *name == arg)?;
+        self.values[index]
+    }
+
+    /// The scope of a template declared with `idents` in `declr_inp`, called
+    /// with `arguments` in this scope, in `inp`.
+    ///
+    /// Arguments that are a parameter of this scope are replaced by their
+    /// value, parameters without arguments get their default value.
+    pub(crate) fn scope(
+        &self,
+        idents: ast::Parameters,
+        declr_inp: &Input<'i>,
+        arguments: ast::TemplateArguments,
+        inp: &Input<'i>,
+    ) -> Self {
+        let names: Box<[_]> = idents.iter().map(|p| p.name().read(declr_inp)).collect();
+        let default = |p: ast::Parameter| Some(p.default_value()?.read(declr_inp));
+        let mut values: Box<[_]> = idents.iter().map(default).collect();
+        for (bound, argument) in bind(&names, arguments, inp) {
+            let Bound::Parameter(i) = bound else { continue };
+            let value = argument.value().read(inp);
+            values[i] = Some(self.replace(value).unwrap_or(value));
+        }
+        Self { names, values }
+    }
+}
+
+/// The arguments of a method or `code` statement, with [template parameters]
+/// replaced by their value.
+///
+/// [template parameters]: https://docs.rs/cuicui_chirp/latest/cuicui_chirp/#parameter-substitution
+pub struct Arguments<'i, 'a> {
+    pub(super) input: Input<'i>,
+    pub(super) method_args: ast::Arguments<'a>,
+    parameters: &'a Parameters<'i>,
+}
+impl<'i, 'a> Arguments<'i, 'a> {
+    pub(super) const fn new(
+        input: Input<'i>,
+        method_args: ast::Arguments<'a>,
+        parameters: &'a Parameters<'i>,
+    ) -> Self {
+        Self { input, method_args, parameters }
+    }
+    /// The number of arguments.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.method_args.count()
+    }
+    /// Whether there are no arguments.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// The `index`th argument, `None` if there are not enough arguments.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<Cow<'i, [u8]>> {
+        let content = self.method_args.get(index)?.read(&self.input);
+        Some(Cow::Borrowed(self.replace(content)))
+    }
+
+    /// The span in the input of all arguments, `None` if there are none.
+    #[must_use]
+    pub fn span(&self) -> Option<Span> {
+        let start = self.method_args.first()?.start();
+        let end = self.method_args.last()?.end();
+        Some((start, end))
+    }
+
+    fn replace(&self, method_arg: &'i [u8]) -> &'i [u8] {
+        self.parameters.replace(method_arg).unwrap_or(method_arg)
+    }
+}
+
+impl fmt::Display for Arguments<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.method_args.count() == 0 {
+            return Ok(());
+        }
+        f.write_str("(")?;
+        let mut first_in_list = true;
+        for method_arg in self.method_args.iter() {
+            if !first_in_list {
+                f.write_str(", ")?;
+            }
+            first_in_list = false;
+            let content = method_arg.read(&self.input);
+            let tree = BStr::new(self.replace(content));
+            write!(f, "{tree}")?;
+        }
+        f.write_str(")")
+    }
+}
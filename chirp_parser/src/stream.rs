@@ -20,19 +20,33 @@ fn as_u32(usize: usize) -> u32 {
     unsafe { u32::try_from(usize).unwrap_unchecked() }
 }
 
+/// A token of a chirp file.
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Token<'i> {
+    /// `=`
     Equal,
+    /// `(`
     Lparen,
+    /// `)`
     Rparen,
+    /// `{`
     Lcurly,
+    /// `}`
     Rcurly,
+    /// `[`
     Lbracket,
+    /// `]`
     Rbracket,
+    /// `,`
     Comma,
+    /// A keyword.
     Reserved(&'i [u8]),
+    /// An identifier, any sequence of characters that are not delimiters or spaces.
     Ident(&'i [u8]),
+    /// A string literal, including its quotes.
     String(&'i [u8]),
+    /// A string literal missing its closing quote, it extends to the end of the file.
+    UnterminatedString(&'i [u8]),
 }
 impl fmt::Debug for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -48,6 +62,10 @@ impl fmt::Debug for Token<'_> {
             Token::Reserved(bytes) => f.debug_tuple("Reserved").field(&BStr::new(bytes)).finish(),
             Token::Ident(bytes) => f.debug_tuple("Ident").field(&BStr::new(bytes)).finish(),
             Token::String(bytes) => f.debug_tuple("String").field(&BStr::new(bytes)).finish(),
+            Token::UnterminatedString(bytes) => f
+                .debug_tuple("UnterminatedString")
+                .field(&BStr::new(bytes))
+                .finish(),
         }
     }
 }
@@ -64,10 +82,13 @@ pub enum TokenType {
     Reserved,
     Ident,
     String,
+    UnterminatedString,
     Code,
     As,
     Use,
     Fn,
+    Chirp,
+    Dsl,
     None,
 }
 impl From<Option<Token<'_>>> for TokenType {
@@ -85,6 +106,7 @@ impl From<Option<Token<'_>>> for TokenType {
             Some(Token::Reserved(_)) => Self::Reserved,
             Some(Token::Ident(_)) => Self::Ident,
             Some(Token::String(_)) => Self::String,
+            Some(Token::UnterminatedString(_)) => Self::UnterminatedString,
             None => Self::None,
         }
     }
@@ -106,18 +128,25 @@ impl fmt::Display for TokenType {
             Self::Fn => "'fn'",
             Self::Use => "'use'",
             Self::As => "'as'",
+            Self::Chirp => "'chirp'",
+            Self::Dsl => "'dsl'",
             Self::String => "\"a string literal\"",
+            Self::UnterminatedString => "a string literal without closing quote",
             Self::None => "nothing, the end of file",
         };
         f.write_str(nice_token)
     }
 }
 impl Token<'_> {
-    pub(crate) fn len(&self) -> u32 {
+    /// The length in bytes of this token in the input.
+    #[allow(clippy::len_without_is_empty)] // tokens are never empty
+    #[must_use]
+    pub fn len(&self) -> u32 {
         use Token::{Comma, Equal, Lbracket, Lcurly, Lparen, Rbracket, Rcurly, Rparen};
         match self {
             Comma | Equal | Lparen | Rparen | Lcurly | Rcurly | Lbracket | Rbracket => 1,
             Token::Reserved(s) | Token::Ident(s) | Token::String(s) => as_u32(s.len()),
+            Token::UnterminatedString(s) => as_u32(s.len()),
         }
     }
 }
@@ -191,7 +220,9 @@ pub mod tokens {
     use winnow::{error::ErrMode::Backtrack, PResult};
 
     grammar![Equal, Lparen, Rparen, Lcurly, Rcurly, Lbracket, Rbracket, Comma];
-    grammar_identifiers![b"as" as As, b"use" as Use, b"fn" as Fn, b"code" as Code];
+    grammar_identifiers![
+        b"as" as As, b"use" as Use, b"fn" as Fn, b"code" as Code, b"chirp" as Chirp, b"dsl" as Dsl
+    ];
 }
 
 pub struct TokenIter<'i, S> {
@@ -214,12 +245,14 @@ pub struct StateCheckpoint {
     start: u32,
 }
 impl StateCheckpoint {
+    /// The offset in the input of this checkpoint.
+    #[must_use]
     pub const fn start(self) -> u32 {
         self.start
     }
 }
 
-/// Custom stream impl.
+/// The text of a chirp file, a [`winnow`] stream of [`Token`]s.
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub struct Input<'i, S = ()> {
     // TODO(clean): replace with &'i BStr when new lexer is validated.
@@ -264,6 +297,7 @@ impl<'i, S> Input<'i, S> {
         // - `self.start` is always below `self.end`
         unsafe { slice::from_raw_parts::<'i, u8>(self.initial.add(offset), subslice_len) }
     }
+    /// The remaining text.
     #[inline(always)]
     pub fn input_u8(&self) -> &'i [u8] {
         let len = as_usize(self.end - self.start);
@@ -276,21 +310,26 @@ impl<'i, S> Input<'i, S> {
     fn input(&self) -> &'i BStr {
         BStr::new(self.input_u8())
     }
+    /// Whether there are no tokens left.
     pub fn is_empty(&self) -> bool {
         let mut input = self.input_u8();
         lex::next_token(&mut input).is_none()
     }
 
+    /// The offset of the remaining text in the whole input.
     pub const fn current_offset(&self) -> u32 {
         self.start
     }
 
-    pub(crate) fn next_start(&self) -> u32 {
+    /// The offset of the next token in the whole input.
+    pub fn next_start(&self) -> u32 {
         let mut slice = self.input_u8();
         lex::consume_spaces(&mut slice);
         self.end - as_u32(slice.len())
     }
+    /// This input, starting at the `start` offset of the whole input.
     #[inline(always)]
+    #[must_use]
     pub fn starting_at(&self, start: u32) -> Self
     where
         S: Clone + fmt::Debug,
@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+
+use super::*;
+
+macro_rules! hier {
+    (@inner [$_:ident]) => {};
+    (@inner [$acc:ident] $name:ident ($( $method:ident $methd_arg:literal )*)
+        {$($inner:tt)*} $($($rem:tt)+)?
+    ) => {
+        $acc.push(Hier {
+            name: stringify!($name).to_string(),
+            methods: {
+                let mut r = HashMap::new();
+                $(r.insert(
+                    stringify!($method).to_string(),
+                    $methd_arg.to_string(),
+                );)*
+                r
+            },
+            code: HashMap::new(),
+            children: {
+                let mut r = Vec::new();
+                hier!(@inner [r] $($inner)*);
+                r
+            },
+        })
+        $(; hier!(@inner $($rem)+))?
+    };
+    ($name:ident ($( $method:ident $methd_arg:literal )*) {$($inner:tt)*}) => {{
+        #[allow(unused_mut)]
+        let x = Hier {
+            name: stringify!($name).to_string(),
+            methods: {
+                let mut r = HashMap::new();
+                $(r.insert(
+                    stringify!($method).to_string(),
+                    $methd_arg.to_string(),
+                );)*
+                r
+            },
+            code: HashMap::new(),
+            children: {
+                let mut r = Vec::new();
+                hier!(@inner [r] $($inner)*);
+                r
+            },
+        };
+        x
+    }};
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hier {
+    name: String,
+    methods: HashMap<String, String>,
+    code: HashMap<String, Span>,
+    children: Vec<Hier>,
+}
+impl Hier {
+    fn insert_code(&mut self, code: &[u8], range: Span) {
+        let utf8 = String::from_utf8_lossy(code).to_string();
+        self.code.insert(utf8, range);
+    }
+    fn get_index_mut<'a>(&'a mut self, path: &[usize]) -> &'a mut Self {
+        let Some((head, tail)) = path.split_first() else {
+            return self;
+        };
+        if let Some(child) = self.children.get_mut(*head) {
+            return child.get_index_mut(tail);
+        } else {
+            panic!("bad")
+        }
+    }
+
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            methods: HashMap::new(),
+            code: HashMap::new(),
+            children: Vec::new(),
+        }
+    }
+}
+#[derive(Debug, PartialEq, Eq)]
+struct TestState {
+    hierarchy: Hier,
+    current: Vec<usize>,
+}
+impl TestState {
+    fn new() -> Self {
+        Self {
+            hierarchy: Hier::new(String::new()),
+            current: vec![],
+        }
+    }
+    fn set_name(&mut self, name: &[u8]) {
+        let hier = self.hierarchy.get_index_mut(&self.current);
+        hier.name = String::from_utf8_lossy(name).to_string();
+    }
+    fn set_method(&mut self, name: &[u8], args: &Arguments) {
+        let hier = self.hierarchy.get_index_mut(&self.current);
+        let name = String::from_utf8_lossy(name).to_string();
+        let args = args.to_string();
+        hier.methods.insert(name, args);
+    }
+}
+#[derive(Debug)]
+struct TestInterpreter(TestState);
+impl TestInterpreter {
+    fn new() -> Self {
+        Self(TestState::new())
+    }
+}
+
+impl<'i, 'a> Interpreter<'i, 'a> for TestInterpreter {
+    fn code(&mut self, (code, range): (&[u8], Span), _: &Arguments) {
+        let current = self.0.hierarchy.get_index_mut(&self.0.current);
+        current.insert_code(code, range);
+    }
+
+    fn set_name(&mut self, (name, _): Name) {
+        self.0.set_name(name);
+    }
+
+    fn complete_children(&mut self) {
+        if let Some(last) = self.0.current.last_mut() {
+            *last += 1;
+        }
+    }
+
+    fn method(&mut self, (name, _): Name, args: &Arguments) {
+        self.0.set_method(name, args);
+    }
+
+    fn start_children(&mut self) {
+        self.0.current.push(0);
+    }
+
+    fn get_template(&mut self, _name: Name<'i>) -> Option<Declaration<'i, 'a>> {
+        todo!()
+    }
+
+    fn import(&mut self, _name: Name<'i>, _alias: Option<Name<'i>>) {
+        todo!()
+    }
+
+    fn register_fn(&mut self, _name: Name<'i>, _declaration: Declaration<'i, 'a>) {
+        todo!()
+    }
+}
+#[track_caller]
+fn interpret(input: &str) -> Hier {
+    let input = Input::new(input.as_bytes(), ());
+    let chirp_file = super::chirp_file(input).unwrap();
+    let mut state = TestInterpreter::new();
+    let file = ChirpFile::new(input, chirp_file.as_ref());
+    file.interpret(&mut state);
+    state.0.hierarchy
+}
+
+#[test]
+fn simple() {
+    let actual = interpret("Name()");
+    assert_eq!(actual, hier!(Name() {}));
+}
+#[test]
+fn padded() {
+    let actual = interpret("  Name()  ");
+    assert_eq!(actual, hier!(Name() {}));
+}
+#[test]
+fn very_padded() {
+    let actual = interpret("  Name     (     )  ");
+    assert_eq!(actual, hier!(Name() {}));
+}
+#[test]
+fn comment_padded() {
+    let actual = interpret(
+        r#"// Foo bar
+Name     (     )
+// final comment
+"#,
+    );
+    assert_eq!(actual, hier!(Name() {}));
+}
+#[test]
+fn with_method() {
+    let actual = interpret("Name(method(10))");
+    assert_eq!(actual, hier!(Name(method "(10)") {}));
+    let actual = interpret("Name(method  (10)  )");
+    assert_eq!(actual, hier!(Name(method "(10)") {}));
+}
+#[test]
+fn dsl_hint() {
+    let actual = interpret("chirp 1\ndsl UiDsl\nName(method(10))");
+    assert_eq!(actual, hier!(Name(method "(10)") {}));
+    let actual = interpret("dsl(method(10))");
+    assert_eq!(actual, hier!(dsl(method "(10)") {}));
+}
+
+/// Records imports, template calls, statement names and methods in order,
+/// ignoring hierarchy.
+#[derive(Default)]
+struct LogInterpreter<'i, 'a>(Vec<String>, HashMap<Vec<u8>, Declaration<'i, 'a>>);
+impl<'i, 'a> Interpreter<'i, 'a> for LogInterpreter<'i, 'a> {
+    fn code(&mut self, (name, _): Name, args: &Arguments) {
+        self.0.push(format!("code {}{args}", String::from_utf8_lossy(name)));
+    }
+    fn import(&mut self, (name, _): Name, alias: Option<Name>) {
+        let name = String::from_utf8_lossy(name);
+        let alias = alias.map(|(alias, _)| String::from_utf8_lossy(alias));
+        match alias {
+            Some(alias) => self.0.push(format!("use {name} as {alias}")),
+            None => self.0.push(format!("use {name}")),
+        }
+    }
+    fn register_fn(&mut self, (name, _): Name, declaration: Declaration<'i, 'a>) {
+        self.1.insert(name.to_vec(), declaration);
+    }
+    fn get_template(&mut self, (name, _): Name) -> Option<Declaration<'i, 'a>> {
+        self.0.push(format!("{}!", String::from_utf8_lossy(name)));
+        self.1.get(name).copied()
+    }
+    fn argument_error(&mut self, _: Span, error: ArgumentError) {
+        self.0.push(format!("error: {error}"));
+    }
+    fn set_name(&mut self, (name, _): Name) {
+        self.0.push(String::from_utf8_lossy(name).to_string());
+    }
+    fn start_children(&mut self) {}
+    fn method(&mut self, (name, _): Name, args: &Arguments) {
+        self.0.push(format!("{}{args}", String::from_utf8_lossy(name)));
+    }
+    fn complete_children(&mut self) {}
+    fn start_dsl(&mut self, (name, _): Name) {
+        self.0.push(format!("with dsl {}", String::from_utf8_lossy(name)));
+    }
+    fn complete_dsl(&mut self) {
+        self.0.push("end dsl".to_owned());
+    }
+    fn enter_file(&mut self, file: u32, _: Span) {
+        self.0.push(format!("enter {file}"));
+    }
+    fn exit_file(&mut self) {
+        self.0.push("exit".to_owned());
+    }
+}
+fn interpret_log(input: &str) -> Vec<String> {
+    let input = Input::new(input.as_bytes(), ());
+    let chirp_file = super::chirp_file(input).unwrap();
+    let mut log = LogInterpreter::default();
+    ChirpFile::new(input, chirp_file.as_ref()).interpret(&mut log);
+    log.0
+}
+#[test]
+fn large_generated_file() {
+    let log = interpret_log(&large_file(1000));
+    assert_eq!(log.len(), 2 + 1000 * 7);
+    let expected = [
+        "\"row 999\"",
+        "row",
+        "width(100%)",
+        "text(\"Item \\\"999\\\"\")",
+        "bg('#0003e7')",
+        "image(\"icons/999.png\")",
+        "rules(20px, 20px)",
+    ];
+    assert_eq!(log[log.len() - expected.len()..], expected);
+}
+#[test]
+fn code_arguments() {
+    let log = interpret_log(
+        r#"Root(column) {
+    code(list_item, "Item \"1\"", 42, rules(10px, 20%))
+    code(no_args)
+    Entity(row)
+}"#,
+    );
+    let expected = [
+        "Root",
+        "column",
+        "code list_item(\"Item \\\"1\\\"\", 42, rules(10px, 20%))",
+        "code no_args",
+        "row",
+    ];
+    assert_eq!(log, expected);
+}
+#[test]
+fn namespaced_imports() {
+    let log = interpret_log(
+        r#"use "widgets.chirp" as w
+use different/file
+Root(column) {
+    w::button!("OK")
+    w.label!()
+    button!()
+}"#,
+    );
+    let expected = [
+        "use \"widgets.chirp\" as w",
+        "use different/file",
+        "Root",
+        "column",
+        "w::button!",
+        "w.label!",
+        "button!",
+    ];
+    assert_eq!(log, expected);
+}
+#[test]
+fn with_dsl_blocks() {
+    let log = interpret_log(
+        r#"with dsl SpriteDsl {
+    Hud(row) {
+        with dsl UiDsl { Menu(column) Button() }
+        with(row)
+    }
+}"#,
+    );
+    let expected = [
+        "with dsl SpriteDsl",
+        "Hud",
+        "row",
+        "with dsl UiDsl",
+        "Menu",
+        "column",
+        "Button",
+        "end dsl",
+        "with",
+        "row",
+        "end dsl",
+    ];
+    assert_eq!(log, expected);
+
+    let input = Input::new(b"with dsl UiDsl { Menu() Button() }", ());
+    assert!(super::chirp_file(input).is_err());
+}
+#[test]
+fn template_default_and_named_arguments() {
+    let log = interpret_log(
+        r#"fn button(label, width = px(100), color = gray) {
+    Button(text(label) width(width) bg(color))
+}
+Root {
+    button!("Ok")
+    button!("Cancel", px(50))
+    button!(color: red, label: "Quit")
+    button!("Oops", label: "Again", size: 3)
+    button!(width: px(10), "Late")
+}"#,
+    );
+    let expected = [
+        "Root",
+        "button!",
+        "Button",
+        "text(\"Ok\")",
+        "width(px(100))",
+        "bg(gray)",
+        "button!",
+        "Button",
+        "text(\"Cancel\")",
+        "width(px(50))",
+        "bg(gray)",
+        "button!",
+        "Button",
+        "text(\"Quit\")",
+        "width(px(100))",
+        "bg(red)",
+        "button!",
+        "error: button! got several arguments for the 'label' parameter",
+        "error: button! doesn't have a 'size' parameter",
+        "Button",
+        "text(\"Again\")",
+        "width(px(100))",
+        "bg(gray)",
+        "button!",
+        "error: Positional argument after a named argument in button!",
+        "error: button! is missing an argument for the 'label' parameter",
+        "Button",
+        "text(label)",
+        "width(px(10))",
+        "bg(gray)",
+    ];
+    assert_eq!(log, expected);
+}
+#[test]
+fn imported_templates() {
+    let library = r#"fn button(label, color = gray) {
+    Button(bg(color)) { Label(text(label)) }
+}
+fn row(label) {
+    Row(row) { button!(label) }
+}
+Library()"#;
+    let input = r#"fn root_label(line) { Label(text(line)) }
+Root {
+    row!("Quit")(bg(red)) {
+        root_label!("Hi")
+    }
+}"#;
+    let library = Input::new(library.as_bytes(), ());
+    let library_ast = super::chirp_file(library).unwrap();
+    let input = Input::new(input.as_bytes(), ());
+    let chirp_file = super::chirp_file(input).unwrap();
+
+    let mut log = LogInterpreter::default();
+    let library = ChirpFile::library(library, library_ast.as_ref(), 1);
+    for (name, declaration) in library.templates() {
+        log.1.insert(name.0.to_vec(), declaration);
+    }
+    ChirpFile::new(input, chirp_file.as_ref()).interpret(&mut log);
+    let expected = [
+        "Root",
+        "row!",
+        "enter 1",
+        "Row",
+        "row",
+        "enter 0",
+        "bg(red)",
+        "exit",
+        "button!",
+        "Button",
+        "bg(gray)",
+        "Label",
+        "text(\"Quit\")",
+        "enter 0",
+        "root_label!",
+        "Label",
+        "text(\"Hi\")",
+        "exit",
+        "exit",
+    ];
+    assert_eq!(log.0, expected);
+}
+#[test]
+fn unicode_identifiers() {
+    use winnow::stream::Stream;
+
+    let log = interpret_log("日本語(テキスト(\"こんにちは\") 幅(1)) {\n    ボタン()\n}");
+    assert_eq!(log, ["日本語", "テキスト(\"こんにちは\")", "幅(1)", "ボタン"]);
+
+    let input = Input::new("日本語(幅 \"名前\")".as_bytes(), ());
+    let offsets: Vec<_> = input.iter_offsets().map(|(offset, _)| offset).collect();
+    assert_eq!(offsets, [0, 9, 10, 14, 22]);
+}
@@ -0,0 +1,86 @@
+//! Per-app default values for [`DslBundle`]s.
+use std::sync::{Arc, PoisonError, RwLock};
+
+use bevy::ecs::system::Resource;
+
+use crate::DslBundle;
+
+type DefaultFn<D> = Arc<dyn Fn(&mut D) + Send + Sync>;
+
+/// Default values applied to every statement of the `D` DSL, before the
+/// statement's own methods.
+///
+/// This avoids repeating the same methods on each statement, such as
+/// a font or a margin. Since defaults run before the statement's methods,
+/// a statement can always override them.
+///
+/// - `chirp` files use the `DslDefaults<D>` resource of the world of the
+///   `ChirpLoader<D>`.
+/// - The [`dsl!`] macro uses the defaults passed with `defaults:`, see
+///   [the `dsl!` documentation](crate::dsl#default-methods).
+///
+/// Cloning a `DslDefaults` shares its defaults between the clones, so that
+/// defaults added to the resource are visible to the chirp loader. This is
+/// also why you should add defaults to the existing resource with
+/// `ResMut<DslDefaults<D>>` rather than inserting a new `DslDefaults`.
+///
+/// ```
+/// # use cuicui_dsl::macros::__doc_helpers::*;
+/// use bevy::prelude::ResMut;
+/// use cuicui_dsl::{dsl, DslDefaults};
+///
+/// fn setup(mut cmds: Commands, mut defaults: ResMut<DslDefaults<DocDsl>>) {
+///     defaults.add(|dsl: &mut DocDsl| dsl.main_margin(10.));
+///
+///     dsl!(<DocDsl> &mut cmds.spawn_empty(), defaults: &defaults,
+///         Entity(row) {
+///             Entity(main_margin(0.))
+///         }
+///     );
+/// }
+/// ```
+///
+/// [`dsl!`]: crate::dsl!
+#[derive(Resource)]
+pub struct DslDefaults<D>(Arc<RwLock<Vec<DefaultFn<D>>>>);
+
+impl<D> Default for DslDefaults<D> {
+    fn default() -> Self {
+        Self(Arc::default())
+    }
+}
+impl<D> Clone for DslDefaults<D> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+impl<D: DslBundle> DslDefaults<D> {
+    /// Create an empty set of defaults, equivalent to [`D::default`].
+    ///
+    /// [`D::default`]: Default::default
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Run `default` on every new `D`, after the defaults already added.
+    pub fn add(&mut self, default: impl Fn(&mut D) + Send + Sync + 'static) -> &mut Self {
+        let mut defaults = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        defaults.push(Arc::new(default));
+        drop(defaults);
+        self
+    }
+    /// Remove all defaults.
+    pub fn clear(&mut self) {
+        self.0.write().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+    /// A new `D` with all defaults applied to it.
+    #[must_use]
+    pub fn dsl(&self) -> D {
+        let mut dsl = D::default();
+        let defaults = self.0.read().unwrap_or_else(PoisonError::into_inner);
+        for default in defaults.iter() {
+            default(&mut dsl);
+        }
+        dsl
+    }
+}
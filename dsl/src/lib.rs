@@ -9,7 +9,15 @@
 use std::borrow::Cow;
 
 pub use bevy::prelude::{BuildChildren, ChildBuilder};
+use bevy::prelude::Entity;
 pub use bevy::{core::Name, ecs::system::EntityCommands};
+#[cfg(feature = "macros")]
+pub use cuicui_dsl_macros::{dsl_trait, DslBundle};
+pub use defaults::DslDefaults;
+pub use merge::merge_into;
+
+mod defaults;
+mod merge;
 
 /// This exports the dummy impls we make to test the documentation on the macro.
 #[doc(hidden)]
@@ -43,6 +51,40 @@ pub trait DslBundle: Default {
         self.insert(cmds);
         cmds.with_children(f);
     }
+
+    /// Spawn the entity as a parent of the existing `children` entities.
+    ///
+    /// Unlike [`DslBundle::node`], this doesn't spawn new children, use it to
+    /// reuse pre-allocated entities, for example with entity pools or
+    /// virtualized lists. `children` replace the entity's previous children,
+    /// which are not despawned, only detached.
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use cuicui_dsl::{BaseDsl, DslBundle};
+    ///
+    /// /// Show the items of a list in the `rows` entities, spawned once
+    /// /// and reused each time the list scrolls.
+    /// fn show_list(mut cmds: Commands, list: Entity, rows: &[Entity]) {
+    ///     let mut dsl = BaseDsl::default();
+    ///     dsl.named("List");
+    ///     dsl.node_with_entities(&mut cmds.entity(list), rows);
+    /// }
+    /// ```
+    fn node_with_entities(&mut self, cmds: &mut EntityCommands, children: &[Entity]) {
+        self.insert(cmds);
+        cmds.replace_children(children);
+    }
+
+    /// Like [`DslBundle::insert`], but keep the components the entity already
+    /// has rather than overwriting them.
+    ///
+    /// Use it to decorate entities spawned by other plugins or gameplay code.
+    /// Components not registered for reflection are dropped with a warning,
+    /// see [`merge_into`] for details.
+    fn merge(&mut self, cmds: &mut EntityCommands) {
+        merge_into(cmds, |cmds| self.insert(cmds));
+    }
 }
 impl DslBundle for () {
     fn insert(&mut self, _: &mut EntityCommands) {}
@@ -19,6 +19,7 @@
 ///   - [**parent node**](#parent-node)
 ///   - [**code**](#code)
 /// - [**dsl methods**](#dsl-methods)
+/// - [**default methods**](#default-methods)
 ///
 /// ## Extending `dsl!`
 ///
@@ -132,6 +133,10 @@
 ///
 /// And it would work as is.
 ///
+/// With the `macros` cargo feature, the `dsl_trait` attribute macro offers
+/// an alternative to `DerefMut` that composes any number of DSLs. See the
+/// "Composing with traits" section of the crate documentation.
+///
 /// # Syntax
 ///
 /// `dsl!` accepts as argument:
@@ -198,6 +203,19 @@
 /// });
 /// ```
 ///
+/// `_` is a shorthand for `Entity`, useful for nodes that are only there for
+/// spacing:
+/// ```
+/// # use cuicui_dsl::macros::__doc_helpers::*; use cuicui_dsl::dsl;
+/// # let mut w = WorldCheck::new(); let mut cmds = w.cmd(); let mut cmds = cmds.spawn_empty();
+/// dsl!{ &mut cmds,
+///     Entity(fill_main_axis) {
+///         _(width(pct(10)))
+///         Entity(color(Color::GREEN))
+///     }
+/// };
+/// ```
+///
 /// ### Leaf node
 ///
 /// Leaf node statements are statements without subsequent braces.
@@ -363,8 +381,67 @@
 /// x.method_with_args("hi folks", variable_name, Color::RED);
 /// ```
 ///
+/// ## Default methods
+///
+/// Pass a [`DslDefaults`] with `defaults:` right after the entity commands
+/// to run the same methods on every statement of the macro. Defaults run
+/// before the statement's methods, so statements can still override them:
+///
+/// ```
+/// # use cuicui_dsl::macros::__doc_helpers::*;
+/// # use cuicui_dsl::{dsl, DslDefaults};
+/// # let mut w = WorldCheck::new(); let mut cmds = w.cmd(); let mut cmds = cmds.spawn_empty();
+/// let mut defaults = DslDefaults::<DocDsl>::new();
+/// defaults.add(|dsl| dsl.color(Color::BLUE));
+///
+/// dsl!(<DocDsl> &mut cmds, defaults: &defaults,
+///     Entity(row) {
+///         BlueButton(button("Blue"))
+///         RedButton(button("Red") color(Color::RED))
+///     }
+/// )
+/// ```
+///
+/// ## Merging into an existing entity
+///
+/// By default, the components of the statements at the root of the macro
+/// overwrite the components of the same type the entity of `cmds` already has.
+/// Add `merge` right after the entity commands to keep them instead:
+/// components the entity already has are not inserted, and the statements'
+/// children are added after its existing children.
+///
+/// ```
+/// # use cuicui_dsl::macros::__doc_helpers::*;
+/// # use cuicui_dsl::dsl;
+/// use bevy::ecs::{reflect::AppTypeRegistry, system::CommandQueue};
+/// use bevy::prelude::{Children, Name, World};
+///
+/// let mut world = World::new();
+/// world.init_resource::<AppTypeRegistry>();
+/// world.resource::<AppTypeRegistry>().write().register::<Name>();
+/// let player = world.spawn(Name::new("Player")).id();
+///
+/// let mut queue = CommandQueue::default();
+/// let mut cmds = Commands::new(&mut queue, &world);
+/// dsl!(<BaseDsl> &mut cmds.entity(player), merge,
+///     HealthBar {
+///         Health
+///     }
+/// );
+/// queue.apply(&mut world);
+///
+/// assert_eq!(world.get::<Name>(player).unwrap().as_str(), "Player");
+/// assert_eq!(world.get::<Children>(player).unwrap().len(), 1);
+/// ```
+///
+/// Merging uses reflection, so the components of the root statements must be
+/// registered in the `AppTypeRegistry` with `#[reflect(Component)]`. Other
+/// components are dropped with a warning, see [`merge_into`].
+///
 /// [literal]: https://doc.rust-lang.org/reference/expressions/literal-expr.html
 /// [`DslBundle`]: crate::DslBundle
+/// [`DslDefaults`]: crate::DslDefaults
+/// [`merge_into`]: crate::merge_into
 /// [`DslBundle::insert`]: crate::DslBundle::insert
 /// [`BaseDsl`]: crate::BaseDsl
 /// [`IntoEntityCommands`]: crate::IntoEntityCommands
@@ -375,57 +452,73 @@ macro_rules! dsl {
     (@arg [$x:tt] $m:ident ($($arg:tt)*) $($t:tt)*)=>{$x.$m($($arg)*) ; dsl!(@arg [$x] $($t)*)};
     (@arg [$x:tt] $m:ident               $($t:tt)*)=>{$x.$m()         ; dsl!(@arg [$x] $($t)*)};
 
-    (@statement [$d_ty:ty, $cmds:expr] ) => { };
-    (@statement [$d_ty:ty, $cmds:expr] code (let $cmds_ident:ident) {$($code:tt)*} $($($t:tt)+)?) => {
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] ) => { };
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] code (let $cmds_ident:ident) {$($code:tt)*} $($($t:tt)+)?) => {
         let mut $cmds_ident: &mut EntityCommands = $cmds;
         $($code)*
         // Generate the rest of the code
-        $(; dsl!(@statement [$d_ty, $cmds] $($t)*))?
+        $(; dsl!(@statement [$d_ty, $new, $cmds] $($t)*))?
     };
-    (@statement [$d_ty:ty, $cmds:expr] Entity ($($args:tt)*) {} $($t:tt)*) => {
-        let mut x = <$d_ty>::default();
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] Entity ($($args:tt)*) {} $($t:tt)*) => {
+        let mut x: $d_ty = $new();
         dsl!(@arg [x] $($args)*);
         x.insert($cmds);
         // Generate the rest of the code
-        dsl!(@statement [$d_ty, $cmds] $($t)*)
+        dsl!(@statement [$d_ty, $new, $cmds] $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] Entity ($($args:tt)*) {$($inner:tt)*} $($t:tt)*) => {
-        let mut x = <$d_ty>::default();
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] Entity ($($args:tt)*) {$($inner:tt)*} $($t:tt)*) => {
+        let mut x: $d_ty = $new();
         dsl!(@arg [x] $($args)*);
         x.node($cmds, |mut child_builder| {
             // Generate code for statements inside curly braces
-            dsl!(@statement [$d_ty, &mut child_builder.spawn_empty()] $($inner)*);
+            dsl!(@statement [$d_ty, $new, &mut child_builder.spawn_empty()] $($inner)*);
         });
         // Generate the rest of the code
-        dsl!(@statement [$d_ty, $cmds] $($t)*)
+        dsl!(@statement [$d_ty, $new, $cmds] $($t)*)
+    };
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] spawn ($($args:tt)*) $($t:tt)*) => { // spawn: requires trailing ()
+        dsl!(@statement [$d_ty, $new, $cmds] Entity ($($args)*) $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] spawn ($($args:tt)*) $($t:tt)*) => { // spawn: requires trailing ()
-        dsl!(@statement [$d_ty, $cmds] Entity ($($args)*) $($t)*)
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] _ $($t:tt)*) => { // anonymous: same as Entity
+        dsl!(@statement [$d_ty, $new, $cmds] Entity $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] Entity ($($args:tt)*) $($t:tt)*) => { // no {}
-        dsl!(@statement [$d_ty, $cmds] Entity ($($args)*) {} $($t)*)
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] Entity ($($args:tt)*) $($t:tt)*) => { // no {}
+        dsl!(@statement [$d_ty, $new, $cmds] Entity ($($args)*) {} $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] Entity $($t:tt)*) => { // no ()
-        dsl!(@statement [$d_ty, $cmds] Entity () $($t)*)
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] Entity $($t:tt)*) => { // no ()
+        dsl!(@statement [$d_ty, $new, $cmds] Entity () $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] $entity_name:literal ($($args:tt)*) $($t:tt)*) => {
-        dsl!(@statement [$d_ty, $cmds] Entity (named($entity_name.to_string()) $($args)*) $($t)*)
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] $entity_name:literal ($($args:tt)*) $($t:tt)*) => {
+        dsl!(@statement [$d_ty, $new, $cmds] Entity (named($entity_name.to_string()) $($args)*) $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] $entity_name:literal $($t:tt)*) => {
-        dsl!(@statement [$d_ty, $cmds] Entity (named($entity_name.to_string())) $($t)*)
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] $entity_name:literal $($t:tt)*) => {
+        dsl!(@statement [$d_ty, $new, $cmds] Entity (named($entity_name.to_string())) $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] $entity_name:ident ($($args:tt)*) $($t:tt)*) => {
-        dsl!(@statement [$d_ty, $cmds] Entity (named(stringify!($entity_name)) $($args)*) $($t)*)
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] $entity_name:ident ($($args:tt)*) $($t:tt)*) => {
+        dsl!(@statement [$d_ty, $new, $cmds] Entity (named(stringify!($entity_name)) $($args)*) $($t)*)
     };
-    (@statement [$d_ty:ty, $cmds:expr] $entity_name:ident $($t:tt)*) => {
-        dsl!(@statement [$d_ty, $cmds] Entity (named(stringify!($entity_name))) $($t)*)
+    (@statement [$d_ty:ty, $new:ident, $cmds:expr] $entity_name:ident $($t:tt)*) => {
+        dsl!(@statement [$d_ty, $new, $cmds] Entity (named(stringify!($entity_name))) $($t)*)
     };
+    (<$builder:ty> $cmds:expr, merge, $($t:tt)*) => {{
+        let cmds: &mut $crate::EntityCommands = $cmds;
+        $crate::merge_into(cmds, |cmds| dsl!(<$builder> cmds, $($t)*));
+    }};
+    (<$builder:ty> $cmds:expr, defaults: $defaults:expr, $($t:tt)*) => {{
+        use $crate::{DslBundle, DslDefaults, EntityCommands};
+        let defaults: &DslDefaults<$builder> = $defaults;
+        let new_dsl = || defaults.dsl();
+        let cmds: &mut EntityCommands = $cmds;
+        // Generate code for all statements
+        dsl!(@statement [$builder, new_dsl, cmds] $($t)*);
+    }};
     (<$builder:ty> $cmds:expr, $($t:tt)*) => {{
         use $crate::{DslBundle, EntityCommands};
         fn is_dsl_bundle<D: DslBundle>() {} is_dsl_bundle::<$builder>();
+        let new_dsl = <$builder>::default;
         let cmds: &mut EntityCommands = $cmds;
         // Generate code for all statements
-        dsl!(@statement [$builder, cmds] $($t)*);
+        dsl!(@statement [$builder, new_dsl, cmds] $($t)*);
     }};
     // Just call the match above with <Dsl>
     ($cmds:expr, $($t:tt)*) => { dsl!(<Dsl> $cmds, $($t)*) };
@@ -0,0 +1,151 @@
+//! Add components to an existing entity without overwriting the ones it
+//! already has, see [`merge_into`].
+
+use std::any::TypeId;
+
+use bevy::ecs::{prelude::*, reflect::AppTypeRegistry, reflect::ReflectComponent};
+use bevy::hierarchy::{despawn_with_children_recursive, BuildWorldChildren, Children, Parent};
+use bevy::log::warn;
+
+use crate::EntityCommands;
+
+/// Run `spawn` on a new entity, then move its components to the entity of
+/// `cmds`, except for the component types this entity already has.
+///
+/// The children `spawn` adds are added after the existing children of the
+/// entity of `cmds`. If the entity of `cmds` is despawned before the merge
+/// is applied, the new entity and its children are despawned.
+///
+/// # Components must be registered for reflection
+///
+/// Components are moved using reflection. A component type not registered
+/// in the [`AppTypeRegistry`], or registered without `#[reflect(Component)]`,
+/// can't be moved: it is dropped, and a warning is logged. Register the
+/// components your DSL inserts with `app.register_type::<MyComponent>()`.
+///
+/// This is what `dsl!(<D> cmds, merge, …)` and [`DslBundle::merge`] use.
+///
+/// [`DslBundle::merge`]: crate::DslBundle::merge
+pub fn merge_into(cmds: &mut EntityCommands, spawn: impl FnOnce(&mut EntityCommands)) {
+    let target = cmds.id();
+    let mut source = cmds.commands().spawn_empty();
+    spawn(&mut source);
+    let source = source.id();
+    cmds.commands().add(move |world: &mut World| move_missing(world, source, target));
+}
+
+fn move_missing(world: &mut World, source: Entity, target: Entity) {
+    let Some(source_ref) = world.get_entity(source) else {
+        return;
+    };
+    let Some(target_ref) = world.get_entity(target) else {
+        warn!("Can't merge into {target:?}, it doesn't exist anymore");
+        despawn_with_children_recursive(world, source);
+        return;
+    };
+    let hierarchy = [TypeId::of::<Parent>(), TypeId::of::<Children>()];
+    let missing = source_ref.archetype().components().filter(|id| !target_ref.contains_id(*id));
+    let missing = missing.filter_map(|id| world.components().get_info(id));
+    let missing: Vec<_> = missing
+        .filter(|info| !matches!(info.type_id(), Some(id) if hierarchy.contains(&id)))
+        .map(|info| (info.type_id(), info.name().to_owned()))
+        .collect();
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    for (type_id, name) in missing {
+        let Some(registration) = type_id.and_then(|id| registry.get(id)) else {
+            warn!(
+                "Can't merge {name} into {target:?}, it is not registered. \
+                Register it with `app.register_type::<{name}>()`"
+            );
+            continue;
+        };
+        let Some(reflect) = registration.data::<ReflectComponent>() else {
+            warn!(
+                "Can't merge {name} into {target:?}, it is registered without \
+                `ReflectComponent`. Add `#[reflect(Component)]` to its definition"
+            );
+            continue;
+        };
+        let Some(value) = reflect.reflect(world.entity(source)).map(|v| v.clone_value()) else {
+            continue;
+        };
+        reflect.insert(&mut world.entity_mut(target), &*value);
+    }
+    if let Some(children) = world.get::<Children>(source) {
+        let children = children.to_vec();
+        world.entity_mut(target).push_children(&children);
+    }
+    world.despawn(source);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::core::Name;
+    use bevy::hierarchy::BuildChildren;
+    use bevy::ecs::system::CommandQueue;
+    use bevy::reflect::Reflect;
+
+    use super::*;
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Health(u32);
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Unregistered;
+
+    fn world() -> World {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        registry.write().register::<Health>();
+        registry.write().register::<Name>();
+        world.insert_resource(registry);
+        world
+    }
+    fn merge(world: &mut World, target: Entity, spawn: impl FnOnce(&mut EntityCommands)) {
+        let mut queue = CommandQueue::default();
+        let mut cmds = Commands::new(&mut queue, world);
+        merge_into(&mut cmds.entity(target), spawn);
+        queue.apply(world);
+    }
+
+    #[test]
+    fn keep_existing_components() {
+        let mut world = world();
+        let child = world.spawn_empty().id();
+        let target = world.spawn(Health(10)).add_child(child).id();
+        merge(&mut world, target, |cmds| {
+            cmds.insert((Health(1), Name::new("Player"), Unregistered));
+            cmds.with_children(|cmds| {
+                cmds.spawn(Name::new("Badge"));
+            });
+        });
+        assert_eq!(world.get::<Health>(target), Some(&Health(10)));
+        assert_eq!(world.get::<Name>(target).map(Name::as_str), Some("Player"));
+        assert_eq!(world.get::<Unregistered>(target), None);
+
+        let children = world.get::<Children>(target).unwrap().to_vec();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0], child);
+        assert_eq!(world.get::<Name>(children[1]).map(Name::as_str), Some("Badge"));
+        // Only the target, its two children and no leftover merged entity.
+        assert_eq!(world.entities().len(), 3);
+    }
+    #[test]
+    fn despawn_merged_without_target() {
+        let mut world = world();
+        let target = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+        let mut cmds = Commands::new(&mut queue, &world);
+        merge_into(&mut cmds.entity(target), |cmds| {
+            cmds.insert(Health(1)).with_children(|cmds| {
+                cmds.spawn(Name::new("Badge"));
+            });
+        });
+        world.despawn(target);
+        queue.apply(&mut world);
+        assert_eq!(world.entities().len(), 0);
+    }
+}
@@ -0,0 +1,126 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::meta::ParseNestedMeta;
+
+#[derive(Default)]
+pub(crate) struct TraitConfig {
+    name: Option<syn::Ident>,
+    accessor: Option<syn::Ident>,
+}
+impl TraitConfig {
+    #[allow(clippy::needless_pass_by_value)] // false positive. Type necessary for calling it
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        match () {
+            () if meta.path.is_ident("name") => {
+                self.name = Some(meta.value()?.parse()?);
+            }
+            () if meta.path.is_ident("accessor") => {
+                self.accessor = Some(meta.value()?.parse()?);
+            }
+            () => {
+                let path = &meta.path;
+                let ident = quote!(#path);
+                let msg = format!(
+                    "Unrecognized `dsl_trait` meta attribute: `{ident}`. Expected one of \
+                    `name = TraitName` or `accessor = method_name`"
+                );
+                return Err(meta.error(msg));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `name` in `snake_case`, such as `layout_dsl` for `LayoutDsl`.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// Whether `fun` is a `pub` method with a `&mut self` receiver.
+fn is_dsl_method(fun: &syn::ImplItemFn) -> bool {
+    let is_mut_ref = |receiver: &syn::Receiver| {
+        let is_ref = receiver.reference.is_some() && receiver.colon_token.is_none();
+        is_ref && receiver.mutability.is_some()
+    };
+    matches!(fun.vis, syn::Visibility::Public(_)) && fun.sig.receiver().is_some_and(is_mut_ref)
+}
+
+/// The trait method calling `fun` on the value returned by `accessor`.
+fn trait_method(accessor: &syn::Ident, fun: &syn::ImplItemFn) -> syn::TraitItemFn {
+    let kept = ["doc", "cfg", "deprecated"];
+    let attrs = fun.attrs.iter().filter(|attr| kept.iter().any(|k| attr.path().is_ident(k)));
+    let mut sig = fun.sig.clone();
+    sig.constness = None;
+    let mut arguments = Vec::with_capacity(sig.inputs.len());
+    for (i, input) in sig.inputs.iter_mut().enumerate() {
+        let syn::FnArg::Typed(input) = input else {
+            continue;
+        };
+        let ident = match &*input.pat {
+            syn::Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                pat.ident.clone()
+            }
+            _ => format_ident!("arg{i}"),
+        };
+        *input.pat = syn::parse_quote!(#ident);
+        arguments.push(ident);
+    }
+    let name = &sig.ident;
+    let attrs = attrs.collect::<Vec<_>>();
+    syn::parse_quote! {
+        #(#attrs)*
+        #sig {
+            self.#accessor().#name(#(#arguments),*)
+        }
+    }
+}
+
+pub(crate) fn dsl_trait(config: TraitConfig, input: &syn::ItemImpl) -> syn::Result<TokenStream> {
+    if let Some((_, path, _)) = &input.trait_ {
+        let msg = "`dsl_trait` only applies to inherent `impl` blocks, not trait implementations";
+        return Err(syn::Error::new_spanned(path, msg));
+    }
+    let self_ty = &input.self_ty;
+    let syn::Type::Path(syn::TypePath { qself: None, path }) = &**self_ty else {
+        let msg = "`dsl_trait` only applies to `impl` blocks of named types";
+        return Err(syn::Error::new_spanned(self_ty, msg));
+    };
+    let ty = &path.segments.last().unwrap().ident;
+    let name = config.name.unwrap_or_else(|| format_ident!("{ty}Trait"));
+    let snake_ty = snake_case(&ty.to_string());
+    let accessor = config.accessor.unwrap_or_else(|| format_ident!("from_{snake_ty}"));
+
+    let methods = input.items.iter().filter_map(|item| match item {
+        syn::ImplItem::Fn(fun) if is_dsl_method(fun) => Some(trait_method(&accessor, fun)),
+        _ => None,
+    });
+    let trait_doc = format!(
+        "The methods of [`{ty}`], for DSLs that contain a `{ty}`.\n\n\
+        Implement [`{name}::{accessor}`] on your DSL to call `{ty}` methods on it."
+    );
+    let accessor_doc = format!("The `{ty}` this DSL contains.");
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        #input
+
+        #[doc = #trait_doc]
+        pub trait #name #impl_generics #where_clause {
+            #[doc = #accessor_doc]
+            fn #accessor(&mut self) -> &mut #self_ty;
+            #(#methods)*
+        }
+        impl #impl_generics #name #ty_generics for #self_ty #where_clause {
+            fn #accessor(&mut self) -> &mut Self {
+                self
+            }
+        }
+    })
+}
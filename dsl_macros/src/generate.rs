@@ -0,0 +1,143 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+
+/// What to do with a field in the generated `DslBundle::insert`.
+enum FieldAction {
+    Delegate,
+    Insert,
+    InsertIfSome,
+    InsertIf(syn::Expr),
+}
+impl FieldAction {
+    /// The action of the `dsl` attribute in `attrs`, `None` if there is none.
+    fn parse_list(attrs: &[syn::Attribute]) -> syn::Result<Option<Self>> {
+        let mut action = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("dsl")) {
+            attr.parse_nested_meta(|meta| {
+                if action.is_some() {
+                    let msg = "a field can only have a single `dsl` meta attribute";
+                    return Err(meta.error(msg));
+                }
+                action = Some(Self::parse(&meta)?);
+                Ok(())
+            })?;
+        }
+        Ok(action)
+    }
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        match () {
+            () if meta.path.is_ident("delegate") => Ok(Self::Delegate),
+            () if meta.path.is_ident("insert") => Ok(Self::Insert),
+            () if meta.path.is_ident("insert_if_some") => Ok(Self::InsertIfSome),
+            () if meta.path.is_ident("insert_if") => Ok(Self::InsertIf(meta.value()?.parse()?)),
+            () => {
+                let path = &meta.path;
+                let ident = quote!(#path);
+                let msg = format!(
+                    "Unrecognized `dsl` field meta attribute: `{ident}`. Expected one of \
+                    `delegate`, `insert`, `insert_if_some` or `insert_if = expression`"
+                );
+                Err(meta.error(msg))
+            }
+        }
+    }
+}
+
+struct Config {
+    dsl_crate: syn::Path,
+    insert_with: Option<syn::Path>,
+}
+impl Config {
+    fn parse_list(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut config = Self { dsl_crate: syn::parse_quote!(::cuicui_dsl), insert_with: None };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("dsl")) {
+            attr.parse_nested_meta(|meta| config.parse(&meta))?;
+        }
+        Ok(config)
+    }
+    fn parse(&mut self, meta: &ParseNestedMeta) -> syn::Result<()> {
+        match () {
+            () if meta.path.is_ident("cuicui_dsl_path") => {
+                self.dsl_crate = meta.value()?.parse()?;
+            }
+            () if meta.path.is_ident("insert_with") => {
+                self.insert_with = Some(meta.value()?.parse()?);
+            }
+            () => {
+                let path = &meta.path;
+                let ident = quote!(#path);
+                let msg = format!(
+                    "Unrecognized `dsl` meta attribute: `{ident}`. Expected one of \
+                    `insert_with = path` or `cuicui_dsl_path = path`"
+                );
+                return Err(meta.error(msg));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `ty` mentions one of the generic type parameters of `generics`.
+fn is_generic(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    fn contains(tokens: TokenStream, ident: &syn::Ident) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(token) => &token == ident,
+            TokenTree::Group(group) => contains(group.stream(), ident),
+            TokenTree::Punct(_) | TokenTree::Literal(_) => false,
+        })
+    }
+    let ty = quote!(#ty);
+    generics.type_params().any(|param| contains(ty.clone(), &param.ident))
+}
+
+pub(crate) fn dsl_bundle(input: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let syn::Data::Struct(data) = &input.data else {
+        let msg = "`DslBundle` can only be derived for `struct`s";
+        return Err(syn::Error::new(input.ident.span(), msg));
+    };
+    let Config { dsl_crate, insert_with } = Config::parse_list(&input.attrs)?;
+
+    let mut generics = input.generics.clone();
+    let mut inserts = Vec::with_capacity(data.fields.len());
+    for (i, field) in data.fields.iter().enumerate() {
+        let Some(action) = FieldAction::parse_list(&field.attrs)? else {
+            continue;
+        };
+        let unnamed = || syn::Member::Unnamed(i.into());
+        let member = field.ident.clone().map_or_else(unnamed, syn::Member::Named);
+        inserts.push(match action {
+            FieldAction::Delegate => {
+                let ty = &field.ty;
+                if is_generic(ty, &input.generics) {
+                    let predicates = &mut generics.make_where_clause().predicates;
+                    predicates.push(syn::parse_quote!(#ty: #dsl_crate::DslBundle));
+                }
+                quote!(#dsl_crate::DslBundle::insert(&mut self.#member, cmds);)
+            }
+            FieldAction::Insert => quote!(cmds.insert(::core::mem::take(&mut self.#member));),
+            FieldAction::InsertIfSome => quote! {
+                if let ::core::option::Option::Some(bundle) = self.#member.take() {
+                    cmds.insert(bundle);
+                }
+            },
+            FieldAction::InsertIf(bundle) => quote! {
+                if self.#member {
+                    cmds.insert(#bundle);
+                }
+            },
+        });
+    }
+    let insert_with = insert_with.map(|path| quote!(#path(self, cmds);));
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #dsl_crate::DslBundle for #name #ty_generics #where_clause {
+            fn insert(&mut self, cmds: &mut #dsl_crate::EntityCommands) {
+                #insert_with
+                #(#inserts)*
+            }
+        }
+    })
+}
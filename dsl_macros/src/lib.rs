@@ -0,0 +1,172 @@
+#![doc = include_str!("../README.md")]
+
+use proc_macro::TokenStream as TokenStream1;
+use syn::{meta, parse_macro_input, DeriveInput, ItemImpl};
+
+mod dsl_trait;
+mod generate;
+
+/// Implement `DslBundle` by inserting the fields of a `struct`.
+///
+/// Fields are handled in declaration order, according to their `dsl` attribute:
+///
+/// - `#[dsl(delegate)]`: Call `DslBundle::insert` on the field, typically
+///   the inner DSL.
+/// - `#[dsl(insert)]`: Insert the field as a `Bundle`, leaving its
+///   `Default` value in its place.
+/// - `#[dsl(insert_if_some)]`: Insert the content of an `Option<impl Bundle>`
+///   field if it is `Some`, leaving `None` in its place.
+/// - `#[dsl(insert_if = expression)]`: Insert `expression` if the `bool`
+///   field is `true`.
+///
+/// Fields without a `dsl` attribute are not inserted.
+///
+/// The `struct` itself accepts the following `dsl` attributes:
+///
+/// - `#[dsl(insert_with = path)]`: Call `path(&mut self, cmds)` before handling
+///   the fields, to insert things the field attributes can't express.
+/// - `#[dsl(cuicui_dsl_path = path)]`: The path to the `cuicui_dsl` crate,
+///   `::cuicui_dsl` by default. Useful when re-exporting `cuicui_dsl` from
+///   another crate.
+///
+/// The derived `DslBundle` requires the type of `delegate` fields to
+/// implement `DslBundle` when it depends on a generic parameter.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_dsl::{BaseDsl, DslBundle, EntityCommands};
+///
+/// #[derive(Component)]
+/// struct Selected;
+/// #[derive(Component)]
+/// struct Tooltip(String);
+///
+/// #[derive(DslBundle, Default, Deref, DerefMut)]
+/// #[dsl(insert_with = Self::insert_tooltip)]
+/// struct MenuDsl<D = BaseDsl> {
+///     #[dsl(insert_if_some)]
+///     transform: Option<Transform>,
+///     #[dsl(insert_if = Selected)]
+///     is_selected: bool,
+///     tooltip: Option<String>,
+///     #[deref]
+///     #[dsl(delegate)]
+///     inner: D,
+/// }
+/// impl<D> MenuDsl<D> {
+///     fn insert_tooltip(&mut self, cmds: &mut EntityCommands) {
+///         if let Some(tooltip) = self.tooltip.take() {
+///             cmds.insert(Tooltip(format!("{tooltip} (press Enter)")));
+///         }
+///     }
+/// }
+/// # let mut world = World::new();
+/// # let mut queue = bevy::ecs::system::CommandQueue::default();
+/// # let mut cmds = Commands::new(&mut queue, &world);
+/// # let mut entity = cmds.spawn_empty();
+/// # let id = entity.id();
+/// let mut dsl = MenuDsl::<BaseDsl>::default();
+/// dsl.named("Quit");
+/// dsl.is_selected = true;
+/// dsl.tooltip = Some("Quit the game".to_owned());
+/// dsl.insert(&mut entity);
+/// # drop(cmds);
+/// # queue.apply(&mut world);
+/// # assert!(world.get::<Selected>(id).is_some());
+/// # assert_eq!(world.get::<Name>(id).unwrap().as_str(), "Quit");
+/// # assert!(world.get::<Transform>(id).is_none());
+/// # assert_eq!(world.get::<Tooltip>(id).unwrap().0, "Quit the game (press Enter)");
+/// ```
+#[proc_macro_derive(DslBundle, attributes(dsl))]
+pub fn derive_dsl_bundle(input: TokenStream1) -> TokenStream1 {
+    let input = parse_macro_input!(input as DeriveInput);
+    match generate::dsl_bundle(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.into_compile_error().into(),
+    }
+}
+
+/// Generate a trait with the methods of a DSL `impl` block, to compose DSLs
+/// without `DerefMut`.
+///
+/// Put `#[dsl_trait]` on the `impl` block of `MyDsl`. It generates the
+/// `MyDslTrait` trait with:
+///
+/// - A `from_my_dsl(&mut self) -> &mut MyDsl` accessor method.
+/// - A default method calling the same method on `from_my_dsl()` for each
+///   `pub` method of the `impl` block with a `&mut self` receiver.
+///
+/// `MyDslTrait` is implemented for `MyDsl`. Implement `from_my_dsl` on DSLs
+/// that contain a `MyDsl`, and they get all the methods of `MyDsl`. Unlike
+/// `DerefMut`, this works for any number of contained DSLs, and the compiler
+/// tells you which trait is missing when you call a method your DSL doesn't have.
+///
+/// The trait has the generic parameters of the `impl` block. The `doc`,
+/// `cfg` and `deprecated` attributes of methods are copied to the trait.
+///
+/// Use `#[dsl_trait(name = TraitName, accessor = method_name)]` to pick
+/// other names for the trait and its accessor.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_dsl::{dsl_trait, BaseDsl, DslBundle, EntityCommands};
+///
+/// #[derive(Component)]
+/// struct Blink(f32);
+///
+/// #[derive(Default, DslBundle)]
+/// struct BlinkDsl {
+///     #[dsl(insert_if_some)]
+///     blink: Option<Blink>,
+/// }
+/// #[dsl_trait]
+/// impl BlinkDsl {
+///     /// Make this entity blink every `period` seconds.
+///     pub fn blink(&mut self, period: f32) {
+///         self.blink = Some(Blink(period));
+///     }
+/// }
+///
+/// // Both `BaseDsl` and `BlinkDsl` methods are available on `MenuDsl`.
+/// #[derive(Default, Deref, DerefMut, DslBundle)]
+/// struct MenuDsl {
+///     #[dsl(delegate)]
+///     blink: BlinkDsl,
+///     #[deref]
+///     #[dsl(delegate)]
+///     base: BaseDsl,
+/// }
+/// impl BlinkDslTrait for MenuDsl {
+///     fn from_blink_dsl(&mut self) -> &mut BlinkDsl {
+///         &mut self.blink
+///     }
+/// }
+/// # let mut world = World::new();
+/// # let mut queue = bevy::ecs::system::CommandQueue::default();
+/// # let mut cmds = Commands::new(&mut queue, &world);
+/// # let mut entity = cmds.spawn_empty();
+/// # let id = entity.id();
+/// let mut dsl = MenuDsl::default();
+/// dsl.named("Secret menu");
+/// dsl.blink(0.5);
+/// dsl.insert(&mut entity);
+/// # drop(cmds);
+/// # queue.apply(&mut world);
+/// # assert_eq!(world.get::<Blink>(id).unwrap().0, 0.5);
+/// # assert_eq!(world.get::<Name>(id).unwrap().as_str(), "Secret menu");
+/// ```
+#[proc_macro_attribute]
+pub fn dsl_trait(attrs: TokenStream1, input: TokenStream1) -> TokenStream1 {
+    let mut config = dsl_trait::TraitConfig::default();
+    let config_parser = meta::parser(|meta| config.parse(meta));
+    parse_macro_input!(attrs with config_parser);
+    let input = parse_macro_input!(input as ItemImpl);
+    match dsl_trait::dsl_trait(config, &input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.into_compile_error().into(),
+    }
+}
@@ -0,0 +1,11 @@
+//! Check that misuses of `#[derive(DslBundle)]` and `#[dsl_trait]` fail with
+//! helpful errors.
+//!
+//! Run with `TRYBUILD=overwrite cargo test -p cuicui_dsl_macros` to update
+//! the expected errors in `tests/ui/*.stderr`.
+
+#[test]
+fn compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,13 @@
+use cuicui_dsl::DslBundle;
+
+#[derive(Default, DslBundle)]
+enum MyDsl {
+    #[default]
+    Empty,
+}
+
+#[derive(Default, DslBundle)]
+#[dsl(insert_after = MyDsl::insert)]
+struct UnknownAttribute;
+
+fn main() {}
@@ -0,0 +1,15 @@
+use cuicui_dsl::DslBundle;
+
+#[derive(Default, DslBundle)]
+struct UnknownAttribute {
+    #[dsl(insert_if_none)]
+    name: Option<String>,
+}
+
+#[derive(Default, DslBundle)]
+struct SeveralAttributes {
+    #[dsl(insert, delegate)]
+    inner: (),
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use cuicui_dsl::dsl_trait;
+
+trait Named {
+    fn name(&mut self);
+}
+#[dsl_trait]
+impl Named for cuicui_dsl::BaseDsl {
+    fn name(&mut self) {}
+}
+
+#[dsl_trait(trait_name = BaseTrait)]
+impl cuicui_dsl::BaseDsl {
+    pub fn nothing(&mut self) {}
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+//! Tests that `cuicui_layout_bevy_ui` and `cuicui_layout_bevy_sprite` lay out
+//! the same chirp file identically.
+//!
+//! See `tests/mirrored.rs`. This crate has no library code.
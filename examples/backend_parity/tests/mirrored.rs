@@ -0,0 +1,94 @@
+//! Lay out `menu.chirp` with both the `bevy_ui` and `bevy_sprite` backends,
+//! and check that they agree.
+//!
+//! Both backends use the position of the [`LayoutRect`] relative to the parent
+//! as is: `bevy_ui` as the `left` and `top` of the [`Style`], `bevy_sprite` as
+//! the `x` and `y` of the [`Transform`]. Since the Y axis points down in
+//! `bevy_ui` and up in `bevy_sprite`, the two layouts are mirrored over the
+//! X axis.
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy::ui::Val;
+use bevy::window::{PrimaryWindow, WindowResolution};
+use cuicui_chirp::{ChirpReader, Handles, ParseDsl};
+use cuicui_layout::{LayoutRect, RootSize};
+use cuicui_layout_bevy_sprite::SpriteDsl;
+use cuicui_layout_bevy_ui::UiDsl;
+use pretty_assertions::assert_eq;
+
+const MENU: &[u8] = include_bytes!("menu.chirp");
+
+/// The `LayoutRect` and the position the backend gives to each named entity.
+type Output = BTreeMap<String, (LayoutRect, Vec2)>;
+
+/// An `App` with `plugin` and the resources its systems need, without
+/// loading any asset or rendering anything.
+fn headless_app(plugin: impl Plugin) -> App {
+    let mut app = App::new();
+    // `Time` is read by `#[sysfail(log)]` systems, to throttle their errors.
+    app.init_resource::<Time>()
+        .init_resource::<Assets<Font>>()
+        .init_resource::<Assets<Image>>()
+        .init_resource::<Assets<Mesh>>()
+        .add_plugins(plugin);
+    app
+}
+fn spawn_menu<D: ParseDsl + 'static>(app: &mut App) {
+    let registry = app.world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let mut reader = ChirpReader::new(&mut app.world);
+    let spawned = reader.interpret_logging::<D>(&Handles::new(), None, &registry, MENU);
+    assert!(spawned, "menu.chirp should be a valid chirp file");
+
+    let resolution = WindowResolution::new(640., 360.);
+    app.world.spawn((Window { resolution, ..default() }, PrimaryWindow));
+    let mut names = app.world.query::<(Entity, &Name)>();
+    let is_menu = |(entity, name): (Entity, &Name)| (name.as_str() == "Menu").then_some(entity);
+    let menu = names.iter(&app.world).find_map(is_menu).unwrap();
+    app.world.entity_mut(menu).insert(RootSize::default());
+}
+fn layout_output<Q: bevy::ecs::query::WorldQuery>(
+    app: &mut App,
+    position: impl Fn(bevy::ecs::query::ROQueryItem<Q>) -> Vec2,
+) -> Output {
+    let mut query = app.world.query::<(&Name, &LayoutRect, Q)>();
+    let output = query.iter(&app.world);
+    output.map(|(name, rect, item)| (name.to_string(), (*rect, position(item)))).collect()
+}
+
+fn ui_layout() -> Output {
+    let mut app = headless_app(cuicui_layout_bevy_ui::Plugin);
+    spawn_menu::<UiDsl>(&mut app);
+    app.update();
+
+    let px = |val| match val {
+        Val::Px(pixels) => pixels,
+        val => panic!("bevy_ui node positions should be in pixels, got {val:?}"),
+    };
+    layout_output::<&Style>(&mut app, |style| Vec2::new(px(style.left), px(style.top)))
+}
+fn sprite_layout() -> Output {
+    let mut app = headless_app(cuicui_layout_bevy_sprite::Plugin);
+    spawn_menu::<SpriteDsl>(&mut app);
+    app.update();
+
+    layout_output::<&Transform>(&mut app, |transform| transform.translation.xy())
+}
+
+#[test]
+fn ui_and_sprite_mirrored() {
+    let ui = ui_layout();
+    let sprite = sprite_layout();
+
+    assert_eq!(ui.len(), 11, "all named nodes should be laid out: {ui:#?}");
+    assert_eq!(ui, sprite);
+
+    // The layout is actually computed, not left at the default `LayoutRect`.
+    let (icon, icon_pos) = ui["Icon"];
+    assert_eq!(icon.size(), cuicui_layout::Size::new(16., 16.));
+    assert_eq!(icon_pos, Vec2::new(619., 17.));
+    for (name, (rect, pos)) in &ui {
+        assert_eq!(rect.pos(), *pos, "{name}'s position doesn't match its LayoutRect");
+    }
+}
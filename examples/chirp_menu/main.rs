@@ -4,8 +4,8 @@
 
 use bevy::{log::LogPlugin, prelude::*};
 use cuicui_chirp::ChirpBundle;
-use cuicui_layout::LayoutRootCamera;
-use cuicui_layout_bevy_ui::UiDsl;
+use cuicui_layout::{AppCuicuiExt, CuicuiConfig, LayoutRootCamera};
+use cuicui_layout_bevy_ui::{UiBackend, UiDsl};
 
 struct DefaultPlugins;
 
@@ -28,12 +28,9 @@ fn main() {
 
     // ANCHOR: app
     App::new()
-        .add_plugins((
-            DefaultPlugins.set(AssetPlugin { file_path, ..default() }),
-            cuicui_layout_bevy_ui::Plugin,
-            // You still need to add manually the asset loader for UiDsl!
-            cuicui_chirp::loader::Plugin::new::<UiDsl>(),
-        ))
+        .add_plugins(DefaultPlugins.set(AssetPlugin { file_path, ..default() }))
+        // Adds the bevy_ui backend and the chirp asset loader for UiDsl
+        .add_cuicui::<UiDsl>(CuicuiConfig { backend: UiBackend, ..default() })
         .add_systems(Startup, setup)
         .run();
     // ANCHOR_END: app
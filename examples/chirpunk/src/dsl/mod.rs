@@ -105,7 +105,7 @@ pub struct BevypunkDsl {
     is_hidden: bool,
     arbitrary: Arbitrary,
 }
-#[parse_dsl_impl(delegate = inner)]
+#[parse_dsl_impl(namespace = punk, delegate = inner)]
 impl BevypunkDsl {
     fn named(&mut self, name: &str) {
         self.inner.named(name.to_string());
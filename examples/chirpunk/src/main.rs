@@ -6,7 +6,8 @@ use std::time::Duration;
 use bevy::log::LogPlugin;
 use bevy::{asset::ChangeWatcher, prelude::*};
 use cuicui_chirp::ChirpBundle;
-use cuicui_layout::LayoutRootCamera;
+use cuicui_layout::{AppCuicuiExt, CuicuiConfig, LayoutRootCamera};
+use cuicui_layout_bevy_ui::UiBackend;
 
 use animate::button_shift;
 use ui_offset::UiOffset;
@@ -47,13 +48,12 @@ fn main() {
             }),
             (style::Plugin, animate::Plugin, dsl::Plugin),
             (ui_offset::Plugin, ui_event::Plugin),
-            cuicui_layout_bevy_ui::Plugin,
-            cuicui_chirp::loader::Plugin::new::<dsl::BevypunkDsl>(),
             bevy_ui_navigation::DefaultNavigationPlugins,
             bevy_framepace::FramepacePlugin,
             #[cfg(feature = "inspector")]
             bevy_inspector_egui::quick::WorldInspectorPlugin::default(),
         ))
+        .add_cuicui::<dsl::BevypunkDsl>(CuicuiConfig { backend: UiBackend, ..default() })
         .add_systems(Startup, setup)
         .run();
 }
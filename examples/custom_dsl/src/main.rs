@@ -9,7 +9,8 @@ use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy_mod_picking::prelude::{Click, On, Pointer};
 use cuicui_chirp::ChirpBundle;
-use cuicui_layout::LayoutRootCamera;
+use cuicui_layout::{AppCuicuiExt, CuicuiConfig, LayoutRootCamera};
+use cuicui_layout_bevy_ui::UiBackend;
 
 use dsl::BetterFactorioDsl;
 use reflect_on_click::ReflectOnClick;
@@ -41,10 +42,6 @@ fn main() {
     App::new()
         .add_plugins((
             DefaultPlugins.set(AssetPlugin { file_path, ..default() }),
-            cuicui_layout_bevy_ui::Plugin,
-            // ANCHOR: add_plugin
-            cuicui_chirp::loader::Plugin::new::<BetterFactorioDsl>(),
-            // ANCHOR_END: add_plugin
             dsl::DslPlugin,
             cuicui_examples::HighlightPlugin,
             // ANCHOR: mirror_plugin
@@ -52,6 +49,9 @@ fn main() {
             cuicui_examples::MirrorPlugin::<OnClick, ReflectOnClick>::new_from(),
             // ANCHOR_END: mirror_plugin
         ))
+        // ANCHOR: add_plugin
+        .add_cuicui::<BetterFactorioDsl>(CuicuiConfig { backend: UiBackend, ..default() })
+        // ANCHOR_END: add_plugin
         .add_systems(Startup, setup)
         .run();
 }
@@ -221,8 +221,8 @@ fn main() {
         }
 "#;
     let mut handles: Handles = Handles::new();
-    handles.add_function("inner_children", |_, _, cmds| inner_children(cmds));
-    handles.add_function("outer_children", |_, _, cmds| outer_children(cmds));
+    handles.add_function("inner_children", |_, _, _, cmds| inner_children(cmds));
+    handles.add_function("outer_children", |_, _, _, cmds| outer_children(cmds));
 
     let mut world_chirp = ChirpReader::new(&mut world1);
     assert!(world_chirp.interpret_logging::<LayoutDsl>(
@@ -4,9 +4,7 @@
 
 // ANCHOR: imports
 use bevy::prelude::*;
-use cuicui_dsl::{dsl, EntityCommands};
-use cuicui_layout::{dsl_functions::*, LayoutRootCamera};
-use cuicui_layout_bevy_ui::UiDsl;
+use cuicui_layout_bevy_ui::prelude::*;
 // ANCHOR_END: imports
 
 struct DefaultPlugins;
@@ -28,7 +26,7 @@ fn main() {
         .add_plugins((
             DefaultPlugins,
             // Notice that we add the plugin here.
-            cuicui_layout_bevy_ui::Plugin,
+            UiLayoutPlugin,
         ))
         .add_systems(Startup, setup)
         .run();
@@ -11,12 +11,11 @@ use bevy::{
 };
 use cuicui_dsl::dsl;
 use cuicui_layout::dsl_functions::*;
-use cuicui_layout::{dsl::IntoUiBundle, ComputeLayoutSet, LayoutRect, Node, Root, Size};
+use cuicui_layout::{dsl::IntoUiBundle, LayoutRect, Node, Root, Size};
 use cuicui_layout_bevy_sprite as render;
 use cuicui_layout_bevy_sprite::SpriteDsl as Dsl;
 
 const UI_LAYER: RenderLayers = RenderLayers::none().with(20);
-const Z_OFFSET: f32 = 0.01;
 
 fn van_der_corput(bits: u32) -> f32 {
     let leading_zeros = if bits == 0 { 0 } else { bits.leading_zeros() };
@@ -39,19 +38,11 @@ fn main() {
         .insert_resource(ClearColor(Color::BLACK))
         .add_systems(Startup, setup)
         .add_systems(PostStartup, setup_debug)
-        .add_systems(
-            Update,
-            (stretch_boxes, forward_layout_nodes.before(ComputeLayoutSet)),
-        )
+        .insert_resource(render::depth::LayoutDepth { step: 0.01 })
+        .add_systems(Update, stretch_boxes)
         .run();
 }
 
-fn forward_layout_nodes(mut q: Query<&mut Transform, Added<LayoutRect>>) {
-    for mut t in &mut q {
-        t.translation.z = Z_OFFSET;
-    }
-}
-
 #[allow(clippy::needless_pass_by_value)] // `Query` required as a system
 fn setup_debug(
     mut cmds: Commands,
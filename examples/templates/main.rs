@@ -1,8 +1,8 @@
 //! Demonstates how to use templates.
 use bevy::prelude::*;
 use cuicui_chirp::ChirpBundle;
-use cuicui_layout::LayoutRootCamera;
-use cuicui_layout_bevy_ui::UiDsl;
+use cuicui_layout::{AppCuicuiExt, CuicuiConfig, LayoutRootCamera};
+use cuicui_layout_bevy_ui::{UiBackend, UiDsl};
 
 fn main() {
     App::new()
@@ -22,10 +22,9 @@ fn main() {
                     "
                     .to_string(),
                 }),
-            cuicui_chirp::loader::Plugin::new::<UiDsl>(),
-            cuicui_layout_bevy_ui::Plugin,
             // bevy_inspector_egui::quick::WorldInspectorPlugin::default(),
         ))
+        .add_cuicui::<UiDsl>(CuicuiConfig { backend: UiBackend, ..default() })
         .add_systems(Startup, setup)
         .run();
 }
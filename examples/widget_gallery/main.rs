@@ -0,0 +1,308 @@
+//! A gallery of `cuicui_layout` layouts, using the container options and
+//! the `LayoutDsl` and `UiDsl` methods.
+//!
+//! Pass the name of the scene to show as argument, the default is `containers`:
+//!
+//! ```sh
+//! cargo run --bin widget_gallery -- rules
+//! ```
+//!
+//! - `containers`: all distributions and alignments.
+//! - `rules`: all size rules, margins, spacers and media boxes.
+//! - `placement`: grids, wrapping, layered and anchored nodes, size bounds
+//!   and per-side margins.
+//! - `content`: content-sized text and images, and the `UiDsl` decorations.
+//!
+//! The gallery is also a test: the layout of each scene is compared to the
+//! snapshots in the `snapshots` directory. If you change the layout
+//! algorithm, run the following and review the changes to the snapshots:
+//!
+//! ```sh
+//! CUICUI_UPDATE_SNAPSHOTS=1 cargo test -p widget_gallery
+//! ```
+use std::{env, process};
+
+use bevy::prelude::*;
+use cuicui_layout::{dsl::StateMethods, Anchor};
+use cuicui_layout_bevy_ui::prelude::*;
+
+/// An image that never loads, to show placeholder sizes.
+const UNLOADED: Handle<Image> = Handle::weak_from_u128(0x6761_6c6c_6572_795f_756e_6c6f_6164_6564);
+
+struct DefaultPlugins;
+
+impl PluginGroup for DefaultPlugins {
+    fn build(self) -> bevy::app::PluginGroupBuilder {
+        let primary_window = Some(Window { resolution: (640., 360.).into(), ..default() });
+        let file_path = "../../assets".to_owned();
+        bevy::prelude::DefaultPlugins
+            .set(WindowPlugin { primary_window, ..default() })
+            .set(AssetPlugin { file_path, ..default() })
+    }
+}
+
+/// The score shown with `text_bind_res` in the `content` scene.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+struct Score(u32);
+
+/// A scene of the gallery.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+enum Scene {
+    Containers,
+    Rules,
+    Placement,
+    Content,
+}
+impl Scene {
+    const ALL: [Self; 4] = [Self::Containers, Self::Rules, Self::Placement, Self::Content];
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Containers => "containers",
+            Self::Rules => "rules",
+            Self::Placement => "placement",
+            Self::Content => "content",
+        }
+    }
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scene| scene.name() == name)
+    }
+    fn spawn(self, cmds: &mut EntityCommands, assets: &GalleryAssets) {
+        match self {
+            Self::Containers => containers(cmds),
+            Self::Rules => rules(cmds),
+            Self::Placement => placement(cmds),
+            Self::Content => content(cmds, assets),
+        }
+    }
+}
+
+/// The assets used in the gallery scenes.
+struct GalleryAssets {
+    image: Handle<Image>,
+    unloaded: Handle<Image>,
+}
+
+fn main() {
+    let scene = match env::args().nth(1) {
+        None => Scene::Containers,
+        Some(name) => Scene::from_name(&name).unwrap_or_else(|| {
+            let names = Scene::ALL.map(Scene::name).join(", ");
+            eprintln!("Unknown scene '{name}', expected one of: {names}");
+            process::exit(1);
+        }),
+    };
+    App::new()
+        .add_plugins((DefaultPlugins, UiLayoutPlugin))
+        .insert_resource(scene)
+        .insert_resource(Score(1234))
+        .register_type::<Score>()
+        .add_systems(Startup, setup)
+        .run();
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn setup(mut cmds: Commands, scene: Res<Scene>, serv: Res<AssetServer>) {
+    cmds.spawn((Camera2dBundle::default(), LayoutRootCamera));
+
+    let assets = GalleryAssets { image: serv.load("button.png"), unloaded: UNLOADED };
+    scene.spawn(&mut cmds.spawn_empty(), &assets);
+}
+
+/// A container with the given `layout` `spec` and three children of
+/// different sizes.
+fn showcase(cmds: &mut ChildBuilder, spec: &str) {
+    dsl! { <UiDsl> &mut cmds.spawn_empty(),
+        Entity(layout(spec) named(spec.to_owned()) rules(pct(15), pct(100)) border(1, Color::GRAY))
+        {
+            Small(rules(px(20), px(15)) bg(Color::RED))
+            Wide(rules(pct(70), px(25)) bg(Color::GREEN))
+            Tall(rules(px(15), pct(30)) bg(Color::BLUE))
+        }
+    }
+}
+
+fn containers(cmds: &mut EntityCommands) {
+    let distributions = ["vdSaC", "vdCaC", "vdEaC", "voSaC", "voCaC", "voEaC"];
+    let alignments = ["vdCaS", "vdCaC", "vdCaE"];
+    dsl! { <UiDsl> cmds,
+        Containers(screen_root column margin(5.)) {
+            code(let cmds) {
+                dsl!(<UiDsl> cmds, Distributions(row rules(pct(100), pct(40))));
+                cmds.with_children(|cmds| distributions.iter().for_each(|s| showcase(cmds, s)));
+            }
+            code(let cmds) {
+                dsl!(<UiDsl> cmds, Alignments(row distrib_start rules(pct(100), pct(30))));
+                cmds.with_children(|cmds| alignments.iter().for_each(|s| showcase(cmds, s)));
+            }
+            Shorthands(row rules(pct(100), pct(20)) distrib_end) {
+                Start(column distrib_start align_start rules(pct(20), pct(100)) bg(Color::MAROON)) {
+                    Child(rules(px(30), px(15)) bg(Color::WHITE))
+                }
+                End(column distrib_end align_end rules(pct(20), pct(100)) bg(Color::MAROON)) {
+                    Child(rules(px(30), px(15)) bg(Color::WHITE))
+                }
+                Fill(flow(Flow::Vertical) fill_main_axis rules(pct(20), pct(100)) bg(Color::MAROON))
+                {
+                    Child1(rules(px(30), px(15)) bg(Color::WHITE))
+                    Child2(rules(px(30), px(15)) bg(Color::WHITE))
+                }
+            }
+        }
+    };
+}
+
+fn rules(cmds: &mut EntityCommands) {
+    dsl! { <UiDsl> cmds,
+        Rules(screen_root row distrib_start margins(10., 20.)) {
+            Fixed(rules(px(80), px(50)) bg(Color::RED))
+            Parent(rules(pct(10), pct(50)) bg(Color::GREEN))
+            FitChildren(
+                column rules(child(1.5), child(2.)) main_margin(2.) cross_margin(4.) bg(Color::BLUE)
+            ) {
+                Inner(rules(px(40), px(40)) bg(Color::WHITE))
+            }
+            WidthAndHeight(width(px(30)) height(pct(80)) bg(Color::YELLOW))
+            Spacers(column distrib_start rules(px(60), pct(100)) margin(5.) bg(Color::GRAY)) {
+                Top(rules(pct(100), px(20)) bg(Color::WHITE))
+                _(spacer(pct(20)))
+                Middle(rules(pct(100), px(20)) bg(Color::WHITE))
+                _(empty_px(10))
+                _(empty_pct(10))
+                Bottom(rules(pct(100), px(20)) bg(Color::WHITE))
+            }
+            MediaBox(media_box(1.777) width(px(200)) bg(Color::DARK_GRAY)) {
+                Screen(rules(pct(100), pct(100)) bg(Color::NAVY))
+            }
+        }
+    };
+}
+
+fn placement(cmds: &mut EntityCommands) {
+    let bigger = || StateMethods::new(|dsl| dsl.rules(px(50), px(25)));
+    dsl! { <UiDsl> cmds,
+        Placement(screen_root column distrib_start align_start margin(10.)) {
+            Flows(row distrib_start rules(pct(100), child(1.))) {
+                Grid(grid(3) rules(px(150), px(90)) bg(Color::DARK_GRAY)) {
+                    Wide(cell_span(2, 1) rules(pct(100), pct(100)) bg(Color::RED))
+                    Cell(rules(pct(100), pct(100)) bg(Color::GREEN))
+                    Tall(cell_span(1, 2) rules(pct(100), pct(100)) bg(Color::BLUE))
+                    Cell(rules(pct(100), pct(100)) bg(Color::YELLOW))
+                    Cell(rules(pct(100), pct(100)) bg(Color::CYAN))
+                }
+                Wrapped(wrap distrib_start rules(px(100), child(1.)) bg(Color::GRAY)) {
+                    Item1(rules(px(30), px(15)) bg(Color::WHITE))
+                    Item2(rules(px(40), px(15)) bg(Color::WHITE))
+                    Item3(rules(px(30), px(15)) bg(Color::WHITE))
+                    Item4(rules(px(50), px(15)) bg(Color::WHITE))
+                }
+                Layered(overlapping rules(px(60), px(60))) {
+                    Back(rules(pct(100), pct(100)) bg(Color::NAVY))
+                    Front(rules(pct(50), pct(50)) bg(Color::ORANGE))
+                }
+                Anchored(column rules(px(100), px(60)) bg(Color::MAROON)) {
+                    Centered(rules(px(30), px(20)) bg(Color::WHITE))
+                    Badge(anchor(Anchor::TopRight) rules(px(15), px(15)) bg(Color::RED))
+                    Shifted(offset(5., 5.) rules(px(15), px(15)) bg(Color::GREEN))
+                }
+            }
+            Sizes(row distrib_start rules(pct(100), child(1.))) {
+                Bounded(width(pct(50)) max_width(px(120)) height(px(20)) bg(Color::PURPLE))
+                AtLeast(width(pct(5)) min_width(px(40)) height(px(20)) bg(Color::TEAL))
+                RootRelative(rules(vmin(10.), em(2.)) bg(Color::OLIVE))
+                Padded(column padding(2., 10., 4., 8.) rules(child(1.), child(1.)) bg(Color::GRAY)) {
+                    Inner(rules(px(20), px(20)) bg(Color::WHITE))
+                }
+                Hoverable(rules(px(40), px(20)) hover(bigger()) bg(Color::SILVER))
+            }
+        }
+    };
+}
+
+fn content(cmds: &mut EntityCommands, assets: &GalleryAssets) {
+    let GalleryAssets { image, unloaded } = assets;
+    dsl! { <UiDsl> cmds,
+        Content(screen_root column distrib_start align_start margin(10.)) {
+            Title(text("Widget gallery") font_size(20))
+            Texts(row distrib_start rules(pct(100), child(1.))) {
+                Wrapped(text("Breaks on characters") break_on_char width(px(60)))
+                NoWrap(text("Never wraps") no_wrap)
+                Centered(text("Center") text_center_align width(px(80)))
+                Right(text("Right") text_right_align width(px(80)))
+                UiText(ui("ui() text"))
+                Shadow(text("Shadow") text_shadow(1., 1., Color::BLACK))
+                Outline(text("Outline") text_outline(1., Color::BLACK))
+                Score(text_bind_res("Score.0", "Score: {:06}"))
+            }
+            Images(row distrib_start rules(pct(100), child(1.))) {
+                Image(image(image))
+                HalfScale(image(image) image_scale(2.))
+                Flipped(image(image) flip_x flip_y)
+                Tinted(image(image) tint(Color::GREEN))
+                Stretched(image(image) width(px(80)))
+                Placeholder(image(unloaded) placeholder_size(30., 30.))
+                UiImage(ui(image.clone()))
+            }
+            Decorations(row distrib_start rules(pct(100), px(50))) {
+                Bordered(rules(px(60), px(40)) border(2, Color::CYAN))
+                BorderParts(
+                    rules(px(60), px(40)) border_px(3) border_color(Color::YELLOW) bg(Color::MAROON)
+                )
+                Clipped(row rules(px(60), px(40)) clip_children bg(Color::GRAY)) {
+                    Inside(rules(px(20), px(20)) bg(Color::WHITE))
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use bevy::ui::widget::update_image_content_size_system;
+    use cuicui_layout::testing::{assert_snapshot, layout_snapshot, test_font, FakeAssets};
+    use cuicui_layout::{ComputeLayoutSet, Root};
+
+    use super::*;
+
+    /// Lay out `scene` in a 640×360 screen, without rendering.
+    fn scene_snapshot(scene: Scene) -> String {
+        let mut app = App::new();
+        // `Time` is read by `#[sysfail(log)]` systems, to throttle their errors.
+        app.init_resource::<Time>()
+            .insert_resource(Score(1234))
+            .register_type::<Score>()
+            .init_resource::<UiScale>()
+            .init_resource::<Assets<Font>>()
+            .add_plugins(cuicui_layout_bevy_ui::Plugin)
+            .add_systems(Update, update_image_content_size_system.before(ComputeLayoutSet));
+        // `UiDsl::text` uses the default font.
+        let mut fonts = app.world.resource_mut::<Assets<Font>>();
+        fonts.insert(Handle::<Font>::default(), test_font());
+        let assets = GalleryAssets { image: app.add_fake_image(40, 20), unloaded: UNLOADED };
+
+        let mut state = SystemState::<Commands>::new(&mut app.world);
+        let mut cmds = state.get_mut(&mut app.world);
+        let mut root = cmds.spawn_empty();
+        scene.spawn(&mut root, &assets);
+        let root = root.id();
+        state.apply(&mut app.world);
+
+        // What `update_ui_camera_root` does with a 640×360 camera.
+        let mut root_node = app.world.get_mut::<Root>(root).unwrap();
+        let bounds = root_node.size_mut();
+        (*bounds.width, *bounds.height) = (640., 360.);
+        app.update();
+
+        layout_snapshot(&app.world, root)
+    }
+
+    #[test]
+    fn scenes_match_snapshots() {
+        for scene in Scene::ALL {
+            let path = format!("{}/snapshots/{}.txt", env!("CARGO_MANIFEST_DIR"), scene.name());
+            assert_snapshot(path, &scene_snapshot(scene));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Lay out a deep tree of nested containers, like the `sprite_debug` example.
+//!
+//! - `resize_root`: The root changes size, every container is laid out again.
+//! - `change_leaf`: A single leaf changes size, only its ancestors are laid
+//!   out again, other containers are skipped by the layout cache.
+//! - `unchanged`: Nothing changes, `compute_layout` doesn't run.
+//!
+//! Run with `cargo bench -p cuicui_layout`.
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, Criterion};
+use cuicui_layout::bundles::LayoutBundle;
+use cuicui_layout::{Alignment, Container, Distribution, Flow, LeafRule, Node, Root, Rule, Size};
+
+/// Levels of containers in the tree.
+const DEPTH: u32 = 6;
+/// Children of each container.
+const WIDTH: u32 = 4;
+
+/// Spawn `depth` levels of `WIDTH` children under `parent`, containers of
+/// leaves are `Rule::Children` containers, others are `Rule::Parent`.
+/// Returns the last leaf.
+fn spawn_children(world: &mut World, parent: Entity, depth: u32) -> Entity {
+    let mut last = parent;
+    for i in 0..WIDTH {
+        let flow = if depth % 2 == 0 { Flow::Horizontal } else { Flow::Vertical };
+        let node = if depth == 0 {
+            Node::Box(Size::all(LeafRule::Fixed(10. + i as f32)))
+        } else if depth == 1 {
+            Node::Container(Container::compact(flow))
+        } else {
+            let rules = Size::all(Rule::Parent(1. / WIDTH as f32));
+            Node::Container(Container { rules, ..Container::stretch(flow) })
+        };
+        let child = world.spawn(LayoutBundle { node, ..default() }).id();
+        world.entity_mut(parent).add_child(child);
+        last = if depth == 0 { child } else { spawn_children(world, child, depth - 1) };
+    }
+    last
+}
+fn deep_tree() -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins(cuicui_layout::Plugin);
+    let size = Size::all(1_000_000.);
+    let root = Root::new(size, Flow::Horizontal, Alignment::Start, Distribution::Start, Size::ZERO);
+    let root = app.world.spawn((root, cuicui_layout::LayoutRect::default())).id();
+    let leaf = spawn_children(&mut app.world, root, DEPTH);
+    app.update();
+    (app, root, leaf)
+}
+
+fn layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deep_tree");
+
+    let (mut app, root, _) = deep_tree();
+    let mut toggle = false;
+    group.bench_function("resize_root", |b| {
+        b.iter(|| {
+            toggle = !toggle;
+            let mut root = app.world.get_mut::<Root>(root).unwrap();
+            *root.size_mut().width = if toggle { 900_000. } else { 1_000_000. };
+            app.update();
+        });
+    });
+
+    let (mut app, _, leaf) = deep_tree();
+    group.bench_function("change_leaf", |b| {
+        b.iter(|| {
+            toggle = !toggle;
+            let size = if toggle { 20. } else { 10. };
+            *app.world.get_mut::<Node>(leaf).unwrap() = Node::Box(Size::all(LeafRule::Fixed(size)));
+            app.update();
+        });
+    });
+
+    let (mut app, ..) = deep_tree();
+    group.bench_function("unchanged", |b| b.iter(|| app.update()));
+    group.finish();
+}
+
+criterion_group!(benches, layout);
+criterion_main!(benches);
@@ -4,8 +4,9 @@ use std::{mem::replace, str::FromStr};
 
 #[cfg(feature = "reflect")]
 use bevy::prelude::Reflect;
+use thiserror::Error;
 
-use crate::Oriented;
+use crate::{Oriented, Size};
 
 /// The cross axis alignment. Aka alignment.
 ///
@@ -111,13 +112,19 @@ impl Alignment {
         CrossAlign { cross_parent_size: parent_size.cross, align: self }
     }
 }
+impl Alignment {
+    /// The offset of a node of size `child` aligned within `parent`.
+    pub(crate) fn offset(self, parent: f32, child: f32) -> f32 {
+        match self {
+            Self::Start => 0.0,
+            Self::Center => (parent - child) / 2.0,
+            Self::End => parent - child,
+        }
+    }
+}
 impl CrossAlign {
     pub fn offset(self, cross_child_size: f32) -> f32 {
-        match self.align {
-            Alignment::Start => 0.0,
-            Alignment::Center => (self.cross_parent_size - cross_child_size) / 2.0,
-            Alignment::End => self.cross_parent_size - cross_child_size,
-        }
+        self.align.offset(self.cross_parent_size, cross_child_size)
     }
 }
 
@@ -197,3 +204,81 @@ impl FromStr for Alignment {
         }
     }
 }
+
+/// A point of a rectangle, where an [`Anchored`] node is attached to its parent.
+///
+/// The same point of the anchored node is placed at this point of its parent,
+/// so that a `BottomRight` node is in the bottom right corner of its parent.
+///
+/// [`Anchored`]: crate::Anchored
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[allow(missing_docs)] // Variant names are self-explanatory
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    TopCenter,
+    TopRight,
+    CenterLeft,
+    Center,
+    CenterRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+impl Anchor {
+    /// The alignment of this anchor on the horizontal (`width`) and
+    /// vertical (`height`) axis.
+    #[must_use]
+    pub const fn alignment(self) -> Size<Alignment> {
+        use Alignment::{Center, End, Start};
+        let (width, height) = match self {
+            Self::TopLeft => (Start, Start),
+            Self::TopCenter => (Center, Start),
+            Self::TopRight => (End, Start),
+            Self::CenterLeft => (Start, Center),
+            Self::Center => (Center, Center),
+            Self::CenterRight => (End, Center),
+            Self::BottomLeft => (Start, End),
+            Self::BottomCenter => (Center, End),
+            Self::BottomRight => (End, End),
+        };
+        Size::new(width, height)
+    }
+    /// The position of a node of size `child` anchored within `parent`,
+    /// relative to `parent`.
+    #[must_use]
+    pub fn offset(self, parent: Size<f32>, child: Size<f32>) -> Size<f32> {
+        let Size { width, height } = self.alignment();
+        let width = width.offset(parent.width, child.width);
+        Size::new(width, height.offset(parent.height, child.height))
+    }
+}
+
+/// Error parsing an [`Anchor`] from a string, see [`Anchor`]'s [`FromStr`] implementation.
+#[derive(Debug, Error)]
+#[error(
+    "'{0}' is not an anchor, expected one of: TopLeft, TopCenter, TopRight, CenterLeft, \
+    Center, CenterRight, BottomLeft, BottomCenter or BottomRight"
+)]
+pub struct AnchorParseError(String);
+
+impl FromStr for Anchor {
+    type Err = AnchorParseError;
+
+    /// Parse an anchor from its variant name, such as `"BottomRight"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TopLeft" => Ok(Self::TopLeft),
+            "TopCenter" => Ok(Self::TopCenter),
+            "TopRight" => Ok(Self::TopRight),
+            "CenterLeft" => Ok(Self::CenterLeft),
+            "Center" => Ok(Self::Center),
+            "CenterRight" => Ok(Self::CenterRight),
+            "BottomLeft" => Ok(Self::BottomLeft),
+            "BottomCenter" => Ok(Self::BottomCenter),
+            "BottomRight" => Ok(Self::BottomRight),
+            _ => Err(AnchorParseError(s.to_owned())),
+        }
+    }
+}
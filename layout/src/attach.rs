@@ -0,0 +1,92 @@
+//! Named points of nodes, to place effects at UI positions, see [`AttachmentPoint`].
+//!
+//! For example, to make a coin fly to the money counter, add an attachment
+//! point to the counter with [`LayoutDsl::attach`], then read its position
+//! with [`AttachmentPoints`]:
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use cuicui_layout::attach::AttachmentPoints;
+//!
+//! // The money counter is spawned with `attach("money_counter", Center)`.
+//! fn spawn_coin(mut cmds: Commands, points: AttachmentPoints) {
+//!     let Some(target) = points.get("money_counter") else {
+//!         return;
+//!     };
+//!     // spawn a coin flying to `target`…
+//! }
+//! ```
+//!
+//! [`LayoutDsl::attach`]: crate::dsl::LayoutDsl::attach
+
+use bevy::ecs::{prelude::*, system::SystemParam};
+use bevy::math::Vec2;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+use crate::{Anchor, Size};
+
+/// A named point of a node, such as its center or its top right corner.
+///
+/// The backend sets [`Self::position`] each frame, after transform propagation.
+/// It is in the same space as the node's `GlobalTransform`:
+///
+/// - With `cuicui_layout_bevy_sprite`, world coordinates.
+/// - With `cuicui_layout_bevy_ui`, logical pixels from the top left of the
+///   window, use `Camera::viewport_to_world_2d` to get world coordinates.
+///
+/// The position is updated in `PostUpdate`, systems reading it in `Update`
+/// get its position on the previous frame.
+///
+/// Use [`LayoutDsl::attach`] to spawn nodes with this component, and
+/// [`AttachmentPoints`] to get the position of a point by name.
+///
+/// [`LayoutDsl::attach`]: crate::dsl::LayoutDsl::attach
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct AttachmentPoint {
+    /// The name to get this point by in [`AttachmentPoints`].
+    pub name: String,
+    /// Where this point is in its node.
+    pub anchor: Anchor,
+    /// The position of the point, set by the backend.
+    pub position: Vec2,
+}
+impl AttachmentPoint {
+    /// A new point called `name` at `anchor` of its node.
+    #[must_use]
+    pub fn new(name: impl Into<String>, anchor: Anchor) -> Self {
+        Self { name: name.into(), anchor, position: Vec2::ZERO }
+    }
+    /// The position of this point in a node of `size`, relative to `origin`.
+    ///
+    /// `origin` is the position of the node's `Transform` in the node,
+    /// relative to its top left corner. Backends pass the result to the node's
+    /// `GlobalTransform` to get [`Self::position`].
+    #[must_use]
+    pub fn local_position(&self, size: Size<f32>, origin: Vec2) -> Vec2 {
+        Vec2::from(self.anchor.offset(size, Size::ZERO)) - origin
+    }
+}
+
+/// Get the position of [`AttachmentPoint`]s by name.
+#[derive(SystemParam)]
+pub struct AttachmentPoints<'w, 's> {
+    points: Query<'w, 's, &'static AttachmentPoint>,
+}
+impl AttachmentPoints<'_, '_> {
+    /// The position of a point called `name`, `None` if no node has such
+    /// a point.
+    ///
+    /// When several nodes have a point called `name`, any of them is returned,
+    /// use [`Self::iter`] to get all of them.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Vec2> {
+        self.iter(name).next()
+    }
+    /// The positions of all points called `name`.
+    pub fn iter<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Vec2> + 'a {
+        let named = self.points.iter().filter(move |point| point.name == name);
+        named.map(|point| point.position)
+    }
+}
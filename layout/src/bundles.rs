@@ -2,7 +2,7 @@
 
 use bevy::prelude::{default, Bundle};
 
-use crate::{Alignment, Distribution, Oriented, Size};
+use crate::{Alignment, Distribution, Oriented, Sides, Size};
 use crate::{Container, Flow, LayoutRect, LeafRule, Node, Root, Rule, ScreenRoot};
 
 /// Layout information.
@@ -16,8 +16,19 @@ pub struct Layout {
     pub distrib: Distribution,
     /// The [margin](Container::margin) size.
     pub margin: Oriented<f32>,
+    /// The [margin](Container::margin) of individual sides, overriding
+    /// `margin` on the sides that are `Some`. All `None` by default.
+    pub margin_sides: Sides<Option<f32>>,
+    /// The [padding](Container::padding), zero by default.
+    pub padding: Sides<f32>,
     /// The inner size, defaults to [`Rule::Children(1.5)`].
     pub size: Size<Option<Rule>>,
+    /// The [media box](Container::aspect) aspect ratio, `None` by default.
+    pub aspect: Option<f32>,
+    /// The number of [grid](Container::grid) cells per line, `None` by default.
+    pub grid: Option<u32>,
+    /// Whether children [wrap](Container::wrap) onto new lines, `false` by default.
+    pub wrap: bool,
 }
 impl Default for Layout {
     fn default() -> Self {
@@ -25,8 +36,13 @@ impl Default for Layout {
             align: Alignment::Center,
             distrib: Distribution::FillMain,
             margin: Oriented::default(),
+            margin_sides: Sides::all(None),
+            padding: Sides::ZERO,
             size: Size::all(None),
             flow: Flow::Horizontal,
+            aspect: None,
+            grid: None,
+            wrap: false,
         }
     }
 }
@@ -40,9 +56,17 @@ impl Layout {
             align: self.align,
             distrib: self.distrib,
             rules: self.size.map(|r| r.unwrap_or(Rule::Children(1.5))),
-            margin: self.flow.absolute(self.margin),
+            margin: self.container_margin(),
+            padding: self.padding,
+            aspect: self.aspect,
+            grid: self.grid,
+            wrap: self.wrap,
         }
     }
+    fn container_margin(&self) -> Sides<f32> {
+        let margin = Sides::symmetric(self.flow.absolute(self.margin));
+        self.margin_sides.unwrap_or(margin)
+    }
 }
 
 /// A [`Root`] container node, it will always span the entire screen.
@@ -60,13 +84,13 @@ pub struct RootBundle {
 impl RootBundle {
     /// Create a [`RootBundle`] based on given [`Layout`].
     #[must_use]
-    pub fn new(Layout { align, distrib, margin, flow, .. }: Layout) -> Self {
+    pub fn new(layout: Layout) -> Self {
+        let Layout { align, distrib, flow, padding, .. } = layout;
         let size = Size::all(f32::MAX);
-        Self {
-            pos_rect: default(),
-            root: Root::new(size, flow, align, distrib, flow.absolute(margin)),
-            screen_root: ScreenRoot,
-        }
+        let mut root = Root::new(size, flow, align, distrib, Size::ZERO);
+        root.node.margin = layout.container_margin();
+        root.node.padding = padding;
+        Self { pos_rect: default(), root, screen_root: ScreenRoot }
     }
 }
 
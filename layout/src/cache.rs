@@ -0,0 +1,311 @@
+//! Skip laying out containers which inputs didn't change since the last layout.
+//!
+//! [`compute_layout`] lays out whole [`Root`]s whenever any of their node
+//! changes. Before laying out a root, the _subtree hash_ of each container is
+//! computed: a hash of the [`Node`], [`Anchored`], [`CellSpan`] and
+//! [`SizeBounds`] of the container and all its descendants, their [`Children`]
+//! included. [`LeafRule::Content`] is part of the `Node`, so content sizes are
+//! included too.
+//!
+//! A container is skipped when its subtree hash, the size its parent gives
+//! it and the [`RootUnit`] lengths are the same as the last time it was laid
+//! out. The [`LayoutRect`]s of its descendants are then left untouched,
+//! since they are relative to their parent.
+//!
+//! # Limitations
+//!
+//! - Containers with a [`CustomConstraint`] node in their subtree are always
+//!   laid out, since [layout hooks](crate::hooks) may read anything in the `World`.
+//! - If something else than the layout modifies the [`LayoutRect`] of a node,
+//!   it is only restored when the container it is in is laid out again.
+//! - Subtree hashes are computed for all nodes of the roots laid out, the
+//!   cache only saves the layout of skipped containers.
+//!
+//! [`CellSpan`]: crate::CellSpan
+//! [`compute_layout`]: crate::compute_layout
+//! [`CustomConstraint`]: crate::CustomConstraint
+//! [`LayoutRect`]: crate::LayoutRect
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use bevy::ecs::prelude::*;
+use bevy::prelude::Children;
+use bevy::utils::{AHasher, HashMap};
+
+use crate::alignment::{Alignment, Anchor, Distribution};
+use crate::direction::{Flow, Oriented, Sides, Size};
+use crate::error::Computed;
+use crate::layout::{NodeExtras, NodeQuery, RootLengths};
+use crate::{Anchored, Container, LeafRule, Node, Root, RootUnit};
+use crate::{Rule, SizeBounds};
+
+/// How many containers the last run of [`compute_layout`] laid out, and how
+/// many it skipped, see the [module-level documentation](self).
+///
+/// Descendants of skipped containers are not counted. Note that
+/// `compute_layout` doesn't run at all when nothing changed, in which case
+/// this is not updated.
+///
+/// [`compute_layout`]: crate::compute_layout
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LayoutCacheStats {
+    /// Containers and [`Root`]s which layout was computed.
+    pub computed: usize,
+    /// Containers and [`Root`]s skipped, because their subtree didn't change.
+    pub skipped: usize,
+}
+
+/// The inputs and size of a container the last time it was laid out.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    key: u64,
+    size: Size<f32>,
+}
+
+/// The layout cache of [`crate::compute_layout`], see the [module-level
+/// documentation](self).
+#[derive(Default)]
+pub(crate) struct LayoutCache {
+    /// The subtree hash of containers in roots laid out during this run,
+    /// `None` if the container can't be cached.
+    subtrees: HashMap<Entity, Option<u64>>,
+    entries: HashMap<Entity, Entry>,
+    pub(crate) stats: LayoutCacheStats,
+}
+impl LayoutCache {
+    /// Prepare for a new run of `compute_layout`.
+    pub(crate) fn clear_run(&mut self) {
+        self.subtrees.clear();
+        self.stats = LayoutCacheStats::default();
+    }
+    /// Forget containers that were not part of this run, such as despawned
+    /// ones or those in paused roots.
+    pub(crate) fn retain_run(&mut self) {
+        let Self { subtrees, entries, .. } = self;
+        entries.retain(|entity, _| subtrees.contains_key(entity));
+    }
+    /// Compute the subtree hashes of `root` and all its descendants.
+    pub(crate) fn hash_root(
+        &mut self,
+        extras: &Query<NodeExtras>,
+        nodes: &Query<NodeQuery>,
+        (entity, root, children): (Entity, &Root, &Children),
+    ) {
+        let mut hasher = AHasher::default();
+        root.get().hash_input(&mut hasher);
+        let cachable = self.hash_children(extras, nodes, children, &mut hasher);
+        self.subtrees.insert(entity, cachable.then(|| hasher.finish()));
+    }
+    fn hash_node(
+        &mut self,
+        extras: &Query<NodeExtras>,
+        nodes: &Query<NodeQuery>,
+        (entity, node, children): (Entity, &Node, Option<&Children>),
+    ) -> Option<u64> {
+        let mut hasher = AHasher::default();
+        node.hash_input(&mut hasher);
+        let (anchored, span, bounds, hooked) = extras.get(entity).unwrap_or_default();
+        anchored.hash_input(&mut hasher);
+        span.map(|span| (span.main, span.cross)).hash(&mut hasher);
+        bounds.hash_input(&mut hasher);
+        let children_cachable = match children {
+            Some(children) => self.hash_children(extras, nodes, children, &mut hasher),
+            None => true,
+        };
+        let hash = (children_cachable && !hooked).then(|| hasher.finish());
+        if matches!(node, Node::Container(_)) {
+            self.subtrees.insert(entity, hash);
+        }
+        hash
+    }
+    /// Hash `children` in `hasher`, returns `false` if one can't be cached.
+    fn hash_children(
+        &mut self,
+        extras: &Query<NodeExtras>,
+        nodes: &Query<NodeQuery>,
+        children: &Children,
+        hasher: &mut AHasher,
+    ) -> bool {
+        let mut cachable = true;
+        for child in nodes.iter_many(children) {
+            child.0.hash(hasher);
+            match self.hash_node(extras, nodes, child) {
+                Some(hash) => hasher.write_u64(hash),
+                None => cachable = false,
+            }
+        }
+        cachable
+    }
+    /// The cache key of `entity` laid out in a parent of size `parent`,
+    /// `None` if `entity` can't be cached.
+    pub(crate) fn key(
+        &self,
+        entity: Entity,
+        parent: Size<Computed>,
+        root: &RootLengths,
+    ) -> Option<u64> {
+        let subtree = (*self.subtrees.get(&entity)?)?;
+        let mut hasher = AHasher::default();
+        subtree.hash(&mut hasher);
+        parent.hash_input(&mut hasher);
+        root.size.hash_input(&mut hasher);
+        root.em.hash_input(&mut hasher);
+        Some(hasher.finish())
+    }
+    /// The size of `entity` if it was last laid out with the same `key`.
+    ///
+    /// Otherwise, forget about `entity`, since it is about to be laid out
+    /// again, and its subtree may be left half updated on error.
+    pub(crate) fn get(&mut self, entity: Entity, key: Option<u64>) -> Option<Size<f32>> {
+        match (self.entries.get(&entity), key) {
+            (Some(entry), Some(key)) if entry.key == key => {
+                self.stats.skipped += 1;
+                Some(entry.size)
+            }
+            _ => {
+                self.stats.computed += 1;
+                self.entries.remove(&entity);
+                None
+            }
+        }
+    }
+    /// Remember that `entity` of `size` was laid out with `key`.
+    pub(crate) fn insert(&mut self, entity: Entity, key: Option<u64>, size: Size<f32>) {
+        if let Some(key) = key {
+            self.entries.insert(entity, Entry { key, size });
+        }
+    }
+}
+
+/// Hash the values that affect the layout of a node.
+///
+/// This is not [`Hash`], because it is implemented for `f32`.
+trait HashInput {
+    fn hash_input(&self, state: &mut AHasher);
+}
+impl HashInput for f32 {
+    fn hash_input(&self, state: &mut AHasher) {
+        state.write_u32(self.to_bits());
+    }
+}
+impl<T: HashInput> HashInput for Option<T> {
+    fn hash_input(&self, state: &mut AHasher) {
+        mem::discriminant(self).hash(state);
+        if let Some(value) = self {
+            value.hash_input(state);
+        }
+    }
+}
+impl<T: HashInput> HashInput for &'_ T {
+    fn hash_input(&self, state: &mut AHasher) {
+        (*self).hash_input(state);
+    }
+}
+impl<T: HashInput> HashInput for Size<T> {
+    fn hash_input(&self, state: &mut AHasher) {
+        self.width.hash_input(state);
+        self.height.hash_input(state);
+    }
+}
+impl<T: HashInput> HashInput for Oriented<T> {
+    fn hash_input(&self, state: &mut AHasher) {
+        self.main.hash_input(state);
+        self.cross.hash_input(state);
+    }
+}
+impl<T: HashInput> HashInput for Sides<T> {
+    fn hash_input(&self, state: &mut AHasher) {
+        self.left.hash_input(state);
+        self.right.hash_input(state);
+        self.top.hash_input(state);
+        self.bottom.hash_input(state);
+    }
+}
+/// Enums without fields are equal when their discriminant is.
+macro_rules! hash_discriminant {
+    ($($ty:ty),*) => {$(
+        impl HashInput for $ty {
+            fn hash_input(&self, state: &mut AHasher) {
+                mem::discriminant(self).hash(state);
+            }
+        }
+    )*};
+}
+hash_discriminant![Flow, Alignment, Distribution, Anchor, RootUnit];
+
+impl HashInput for Rule {
+    fn hash_input(&self, state: &mut AHasher) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::Fixed(value) | Self::Parent(value) | Self::Children(value) => {
+                value.hash_input(state);
+            }
+            Self::Root(value, unit) => {
+                value.hash_input(state);
+                unit.hash_input(state);
+            }
+        }
+    }
+}
+impl HashInput for LeafRule {
+    fn hash_input(&self, state: &mut AHasher) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::Fixed(value) | Self::Parent(value) | Self::Content(value) => {
+                value.hash_input(state);
+            }
+            Self::Root(value, unit) => {
+                value.hash_input(state);
+                unit.hash_input(state);
+            }
+        }
+    }
+}
+impl HashInput for Container {
+    fn hash_input(&self, state: &mut AHasher) {
+        let Self { flow, align, distrib, rules, margin, padding, aspect, grid, wrap } = self;
+        flow.hash_input(state);
+        align.hash_input(state);
+        distrib.hash_input(state);
+        rules.hash_input(state);
+        margin.hash_input(state);
+        padding.hash_input(state);
+        aspect.hash_input(state);
+        grid.hash(state);
+        wrap.hash(state);
+    }
+}
+impl HashInput for Node {
+    fn hash_input(&self, state: &mut AHasher) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::Container(container) => container.hash_input(state),
+            Self::Axis(oriented) => oriented.hash_input(state),
+            Self::Box(size) => size.hash_input(state),
+        }
+    }
+}
+impl HashInput for Anchored {
+    fn hash_input(&self, state: &mut AHasher) {
+        self.anchor.hash_input(state);
+        self.offset.hash_input(state);
+    }
+}
+impl HashInput for SizeBounds {
+    fn hash_input(&self, state: &mut AHasher) {
+        self.min.hash_input(state);
+        self.max.hash_input(state);
+    }
+}
+impl HashInput for Computed {
+    fn hash_input(&self, state: &mut AHasher) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Self::ChildDefined(ratio, entity) => {
+                ratio.hash_input(state);
+                entity.hash(state);
+            }
+            Self::Valid(value) => value.hash_input(state),
+        }
+    }
+}
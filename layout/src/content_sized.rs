@@ -1,35 +1,49 @@
 /*!
 [`AnyOf`]: AnyOf
 [`AppContentSizeExt::add_content_sized`]: AppContentSizeExt::add_content_sized
+[`AppContentSizeExt::add_content_sized_component`]: AppContentSizeExt::add_content_sized_component
 [`ComputeContentParam`]: ComputeContentParam
 [`ComputeContentParam::Components`]: ComputeContentParam::Components
 [`ComputeContentParam::condition`]: ComputeContentParam::condition
 [`ComputeContentSize::compute_content`]: ComputeContentSize::compute_content
+[`ContentSizedComponent`]: ContentSizedComponent
 [`Entity`]: Entity
 [`SystemParam`]: bevy::ecs::system::SystemParam
 */
 //! Define how leaf nodes should size based on arbitrary components.
 //!
 #![doc = include_str!("../content_sized.md")]
-use std::{any::type_name, convert::Infallible, marker::PhantomData};
+use std::any::{type_name, TypeId};
+use std::hash::{Hash, Hasher};
+use std::{convert::Infallible, fmt, marker::PhantomData};
 
 use bevy::app::{App, Update};
+use bevy::diagnostic::{Diagnostic, DiagnosticId, Diagnostics, RegisterDiagnostic};
+use bevy::diagnostic::MAX_DIAGNOSTIC_NAME_WIDTH;
 use bevy::ecs::prelude::*;
 use bevy::ecs::query::{ROQueryItem, ReadOnlyWorldQuery};
 use bevy::ecs::schedule::SystemSetConfigs;
 use bevy::ecs::system::{assert_is_system, StaticSystemParam, SystemParam};
 use bevy::log::{debug, error, trace};
-use bevy::prelude::{Name, Parent};
+use bevy::math::Vec2;
+use bevy::prelude::{Children, Name, Parent};
+use bevy::utils::{get_short_name, AHasher, HashMap, HashSet};
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent, ReflectResource};
 use bevy_mod_sysfail::{sysfail, FailureMode};
 use thiserror::Error;
 
 use crate::direction::Axis;
 use crate::error::Handle;
+use crate::layout::RootLengths;
 use crate::{
-    ComputeLayout, ComputeLayoutSet, Container, LeafNode, LeafRule, Node, Root, Rule, Size,
+    require_layout_recompute, ComputeLayout, ComputeLayoutSet, Container, LeafNode, LeafRule, Node,
+    Root, RootFontSize, Rule, Size,
 };
 
-pub use crate::labels::{ContentSizedComputeSystem, ContentSizedComputeSystemSet};
+pub use crate::labels::{
+    ContentSizedComputeSystem, ContentSizedComputeSystemSet, ContentSizedRemeasureSet,
+};
 
 type Result<T> = std::result::Result<T, BadRule>;
 
@@ -39,8 +53,6 @@ enum Why<T> {
     Nan(Axis, Handle),
     #[error("When computing content of {}: {0} depends on its parent, but it has no parents :(",  type_name::<T>())]
     Orphan(Handle),
-    #[error("Not shown, crate::error::Why::CyclicRule should do this job")]
-    CyclicRule,
     #[error("This error never occurs")]
     _Ignore(PhantomData<fn(T)>, Infallible),
 }
@@ -50,6 +62,216 @@ impl<T> FailureMode for Why<T> {
     fn identify(&self) {}
 }
 
+/// How many image pixels there are per logical pixel for images of this node.
+///
+/// Use `2.0` for images authored at 2x pixel density, so that they are measured
+/// at half their texture size. Only affects the content size of images, when
+/// neither axis is set. When one axis is set, the other follows the image's
+/// aspect ratio, which doesn't depend on the scale.
+///
+/// Nodes without this component use the [`DefaultImageScale`] resource.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct ImageScale(pub f32);
+impl Default for ImageScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+impl ImageScale {
+    /// Logical size of an image which texture is `size` pixels.
+    ///
+    /// `scale` is the [`ImageScale`] of the node, if any.
+    #[must_use]
+    pub fn logical_size(scale: Option<&Self>, default: &DefaultImageScale, size: Vec2) -> Vec2 {
+        size / scale.map_or(default.0, |s| s.0)
+    }
+}
+
+/// The [`ImageScale`] of nodes without an `ImageScale` component.
+///
+/// Defaults to `1.0`, images are measured at their texture size.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Resource))]
+pub struct DefaultImageScale(pub f32);
+impl Default for DefaultImageScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// The texture size of this node's image, used while the image is not loaded.
+///
+/// Images are measured as zero-sized until they are loaded, so the layout
+/// of content-sized image nodes jumps once they are. Add this component, for
+/// example with the `image_size` feature of `cuicui_chirp`, to measure the node
+/// at the image's size from the first layout.
+///
+/// Once the image is loaded, its actual size is used.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct ImageSizeHint(pub Vec2);
+
+/// The size of this content-sized node until its content reports a size.
+///
+/// Content that isn't loaded yet, such as an image or the font of a text,
+/// measures zero. Containers depending on the size of their children then
+/// collapse until the content is loaded, for a frame or more (especially on
+/// wasm). With a `PlaceholderSize`, the node is sized as the placeholder
+/// as long as its content measures zero on both axes.
+///
+/// Only the content-sized axes of the node use the placeholder.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct PlaceholderSize(pub Size<f32>);
+
+/// Scale and pad the measured size of this content-sized node.
+///
+/// The size of each content-sized axis is `content × factor + pad`, for
+/// example to leave some room around a text without wrapping it in a container.
+///
+/// Only the content-sized axes of the node are affected, the [`PlaceholderSize`]
+/// isn't.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct ContentSizeFactor {
+    /// Multiplier of the content size, per axis.
+    pub factor: Size<f32>,
+    /// Pixels added to the content size after `factor`, per axis.
+    pub pad: Size<f32>,
+}
+impl Default for ContentSizeFactor {
+    fn default() -> Self {
+        Self { factor: Size::all(1.), pad: Size::ZERO }
+    }
+}
+impl ContentSizeFactor {
+    /// The size of a node which content measures `content`.
+    #[must_use]
+    pub fn apply(&self, content: Size<f32>) -> Size<f32> {
+        Size {
+            width: content.width * self.factor.width + self.pad.width,
+            height: content.height * self.factor.height + self.pad.height,
+        }
+    }
+}
+
+/// Ignore content size changes smaller than this many pixels.
+///
+/// Text and image measurements may jitter by a fraction of a pixel between
+/// frames, with some fonts for example. Updating the node's size then triggers
+/// a relayout every frame. Instead, content-sized axes keep their current size
+/// when the new one is less than `ContentSizeEpsilon` away from it. See
+/// [`ContentSizeStats::avoided`] for how many nodes were not updated thanks to it.
+///
+/// Defaults to `0.25` pixels. Set to `0.0` to update nodes on any change.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Resource))]
+pub struct ContentSizeEpsilon(pub f32);
+impl Default for ContentSizeEpsilon {
+    fn default() -> Self {
+        Self(0.25)
+    }
+}
+
+/// How many nodes the last run of the `S` content-sized system computed the
+/// size of, how many it skipped and how many it didn't update.
+///
+/// Nodes in layout [`Root`]s without changes are skipped, when `S` supports it,
+/// see [`ComputeContentSize::all_changed`]. Note that the system doesn't run at
+/// all when nothing changed, in which case this is not updated.
+///
+/// [`ContentSizeStats::avoided`] is also measured as a [`Diagnostic`], see
+/// [`ContentSizeStats::avoided_diagnostic`].
+#[derive(Resource)]
+pub struct ContentSizeStats<S> {
+    /// Nodes which size was computed.
+    pub computed: usize,
+    /// Nodes skipped, because nothing changed in their layout root.
+    pub skipped: usize,
+    /// Nodes which size was computed, but not updated, because it changed by
+    /// less than the [`ContentSizeEpsilon`]. Each avoids a relayout.
+    pub avoided: usize,
+    _system: PhantomData<fn(S)>,
+}
+impl<S> Default for ContentSizeStats<S> {
+    fn default() -> Self {
+        Self { computed: 0, skipped: 0, avoided: 0, _system: PhantomData }
+    }
+}
+impl<S: 'static> ContentSizeStats<S> {
+    /// The [`Diagnostic`] measuring [`Self::avoided`] each time the `S` system
+    /// runs, registered in the [`DiagnosticsStore`] by
+    /// [`AppContentSizeExt::add_content_sized`].
+    ///
+    /// [`DiagnosticsStore`]: bevy::diagnostic::DiagnosticsStore
+    #[must_use]
+    pub fn avoided_diagnostic() -> DiagnosticId {
+        let mut hasher = AHasher::default();
+        TypeId::of::<S>().hash(&mut hasher);
+        DiagnosticId::from_u128(AVOIDED_DIAGNOSTIC | u128::from(hasher.finish()))
+    }
+    fn avoided_diagnostic_name() -> String {
+        let name = format!("avoided {}", get_short_name(type_name::<S>()));
+        name.chars().take(MAX_DIAGNOSTIC_NAME_WIDTH).collect()
+    }
+}
+/// The high bits of [`ContentSizeStats::avoided_diagnostic`], the low bits
+/// identify the content-sized system.
+const AVOIDED_DIAGNOSTIC: u128 = 0x7c1d_52e4_b3a0_4f9e << 64;
+impl<S> Clone for ContentSizeStats<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<S> Copy for ContentSizeStats<S> {}
+impl<S> fmt::Debug for ContentSizeStats<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentSizeStats")
+            .field("computed", &self.computed)
+            .field("skipped", &self.skipped)
+            .field("avoided", &self.avoided)
+            .finish()
+    }
+}
+
+/// The width the last layout gave to content-sized nodes which width is
+/// [`LeafRule::Content`], and the width they were last measured within.
+///
+/// This is the `available_width` of [`ComputeContentSize::compute_content_within`].
+#[derive(Resource, Default)]
+pub struct ContentWidths(HashMap<Entity, ContentWidth>);
+#[derive(Clone, Copy, Default)]
+struct ContentWidth {
+    available: Option<f32>,
+    measured: Option<f32>,
+}
+impl ContentWidths {
+    /// Update the `available` widths set by a layout, and forget entities
+    /// that are not layout nodes anymore.
+    pub(crate) fn set_available(
+        &mut self,
+        available: impl IntoIterator<Item = (Entity, Option<f32>)>,
+        is_node: impl Fn(Entity) -> bool,
+    ) {
+        for (entity, width) in available {
+            self.0.entry(entity).or_default().available = width;
+        }
+        self.0.retain(|entity, _| is_node(*entity));
+    }
+    /// Whether the layout gave `entity` another width than the one it was
+    /// measured within.
+    fn changed(&self, entity: Entity) -> bool {
+        self.0.get(&entity).is_some_and(|w| w.available != w.measured)
+    }
+    /// The width available to `entity`, remembering it was measured within it.
+    fn measure(&mut self, entity: Entity) -> Option<f32> {
+        let width = self.0.get_mut(&entity)?;
+        width.measured = width.available;
+        width.available
+    }
+}
+
 /// Extends [`App`] to support adding [`ComputeContentSize`].
 pub trait AppContentSizeExt {
     /// Add support for content-sized for given `T` [`ComputeContentSize`].
@@ -67,6 +289,14 @@ pub trait AppContentSizeExt {
     fn add_content_sized<S: ComputeContentParam>(&mut self) -> &mut Self
     where
         for<'w, 's> S::Item<'w, 's>: ComputeContentSize<Components = S::Components>;
+
+    /// Add support for content-sized nodes with the `T` [`ContentSizedComponent`].
+    ///
+    /// This is [`Self::add_content_sized`] with [`ComponentContentSize<T>`],
+    /// for components that can tell their size on their own.
+    fn add_content_sized_component<T: ContentSizedComponent>(&mut self) -> &mut Self {
+        self.add_content_sized::<ComponentContentSize<T>>()
+    }
 }
 impl AppContentSizeExt for App {
     fn add_content_sized<S: ComputeContentParam>(&mut self) -> &mut Self
@@ -74,15 +304,26 @@ impl AppContentSizeExt for App {
         for<'w, 's> S::Item<'w, 's>: ComputeContentSize<Components = S::Components>,
     {
         let set = ContentSizedComputeSystem::<S>::default();
+        let avoided = ContentSizeStats::<S>::avoided_diagnostic();
+        let name = ContentSizeStats::<S>::avoided_diagnostic_name();
+        self.register_diagnostic(Diagnostic::new(avoided, name, 20));
+        self.init_resource::<ContentSizeStats<S>>().init_resource::<ContentWidths>();
         self.add_systems(
             Update,
-            compute_content_size::<S>
-                .in_set(ComputeLayoutSet)
-                .in_set(ContentSizedComputeSystemSet)
-                .in_set(set),
+            (
+                compute_content_size::<S>
+                    .in_set(ComputeLayoutSet)
+                    .in_set(ContentSizedComputeSystemSet)
+                    .in_set(set),
+                remeasure_content_size::<S>
+                    .run_if(resource_changed::<ContentWidths>())
+                    .in_set(ComputeLayoutSet)
+                    .in_set(ContentSizedRemeasureSet),
+            ),
         );
         self.configure_sets(Update, S::condition(set));
         self.configure_sets(Update, ComputeLayout.after(set));
+        self.configure_sets(Update, ContentSizedRemeasureSet.after(ComputeLayout));
         self
     }
 }
@@ -127,11 +368,155 @@ pub trait ComputeContentSize: SystemParam {
     ///
     /// Note that non-content-sized axis will keep the pre-set size, even
     /// if a different value is returned for that axis.
+    ///
+    /// Return [`Size::ZERO`] when the content isn't loaded yet, so that
+    /// the node's [`PlaceholderSize`] is used, if any.
     fn compute_content(
         &self,
         components: ROQueryItem<Self::Components>,
         set_size: Size<Option<f32>>,
     ) -> Size<f32>;
+
+    /// Like [`Self::compute_content`], for nodes which width is content-sized,
+    /// `available_width` being the most width the node can take in its parent.
+    ///
+    /// Override this for content that can be narrower by being taller, such
+    /// as wrapping text. Return a width no larger than `available_width`
+    /// and the height for that width, so that the node fits its parent.
+    ///
+    /// `available_width` is the width the last layout left to the node in its
+    /// parent: the parent's inner width, or its grid cell, minus the width of
+    /// its siblings in rows. When the parent's width depends on its children,
+    /// it is the inner width of the closest ancestor which width is known.
+    /// It is `None` before the first layout, or when the node's width is set.
+    ///
+    /// Nodes are measured again after each layout that changed their
+    /// `available_width`, the layout then runs again if their size changed.
+    ///
+    /// By default, this calls `compute_content`, ignoring `available_width`.
+    fn compute_content_within(
+        &self,
+        components: ROQueryItem<Self::Components>,
+        set_size: Size<Option<f32>>,
+        available_width: Option<f32>,
+    ) -> Size<f32> {
+        let _ = available_width;
+        self.compute_content(components, set_size)
+    }
+
+    /// Whether the content of all nodes may have changed since the last run,
+    /// for example because an asset or resource used to compute sizes changed.
+    ///
+    /// When this is `false`, only the nodes of layout [`Root`]s with changes
+    /// are computed again. A root changed when one of its nodes changed, or when
+    /// [`Self::content_changed`] is `true` for one of its content-sized nodes.
+    ///
+    /// By default, this is always `true`: all nodes are computed each time.
+    fn all_changed(&self) -> bool {
+        true
+    }
+    /// Whether `components` changed since the last run.
+    ///
+    /// Use [`Ref`] in [`Self::Components`] to tell. Only used when
+    /// [`Self::all_changed`] is `false`. By default, this is always `true`.
+    fn content_changed(&self, components: &ROQueryItem<Self::Components>) -> bool {
+        let _ = components;
+        true
+    }
+}
+
+/// A [`Component`] which content has a size, for example a nine-patch
+/// widget or a video player.
+///
+/// Add content-sized support for it with
+/// [`AppContentSizeExt::add_content_sized_component`]. This is simpler than
+/// [`ComputeContentSize`] when the size only depends on the component itself.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_layout::content_sized::{AppContentSizeExt, ContentSizedComponent};
+/// use cuicui_layout::Size;
+///
+/// /// A video, which frames are `width`×`height` pixels.
+/// #[derive(Component)]
+/// struct Video { width: f32, height: f32 }
+///
+/// impl ContentSizedComponent for Video {
+///     fn content_size(&self, set_size: Size<Option<f32>>) -> Size<f32> {
+///         let ratio = self.width / self.height;
+///         match (set_size.width, set_size.height) {
+///             (Some(width), _) => Size::new(width, width / ratio),
+///             (_, Some(height)) => Size::new(height * ratio, height),
+///             (None, None) => Size::new(self.width, self.height),
+///         }
+///     }
+/// }
+/// App::new().add_content_sized_component::<Video>();
+/// ```
+pub trait ContentSizedComponent: Component {
+    /// The size of this component's content, given the `set_size` of the
+    /// node (`Some` if set, `None` if content-sized).
+    ///
+    /// Like [`ComputeContentSize::compute_content`], the size on axes that
+    /// are not content-sized is ignored, and [`Size::ZERO`] means the content
+    /// isn't loaded yet.
+    fn content_size(&self, set_size: Size<Option<f32>>) -> Size<f32>;
+
+    /// Like [`Self::content_size`], given the `available_width` of the node
+    /// in its parent, see [`ComputeContentSize::compute_content_within`].
+    ///
+    /// By default, this calls `content_size`, ignoring `available_width`.
+    fn content_size_within(
+        &self,
+        set_size: Size<Option<f32>>,
+        available_width: Option<f32>,
+    ) -> Size<f32> {
+        let _ = available_width;
+        self.content_size(set_size)
+    }
+}
+
+/// The [`ComputeContentSize`] of the `T` [`ContentSizedComponent`], added with
+/// [`AppContentSizeExt::add_content_sized_component`].
+///
+/// Use it to refer to the content-sized system of `T`, with
+/// [`ContentSizedComputeSystem`] and [`ContentSizeStats`].
+///
+/// Only nodes in layout [`Root`]s where `T` or a node changed are computed again.
+#[derive(SystemParam)]
+pub struct ComponentContentSize<T: ContentSizedComponent> {
+    _component: PhantomData<fn(T)>,
+}
+impl<T: ContentSizedComponent> ComputeContentParam for ComponentContentSize<T> {
+    type Components = Ref<'static, T>;
+
+    fn condition(label: ContentSizedComputeSystem<Self>) -> SystemSetConfigs {
+        let cond = |c: Query<(), (Changed<T>, With<Node>)>| !c.is_empty();
+        label.run_if(require_layout_recompute.or_else(cond))
+    }
+}
+impl<T: ContentSizedComponent> ComputeContentSize for ComponentContentSize<T> {
+    type Components = Ref<'static, T>;
+
+    fn compute_content(&self, component: Ref<T>, set_size: Size<Option<f32>>) -> Size<f32> {
+        component.content_size(set_size)
+    }
+    fn compute_content_within(
+        &self,
+        component: Ref<T>,
+        set_size: Size<Option<f32>>,
+        available_width: Option<f32>,
+    ) -> Size<f32> {
+        component.content_size_within(set_size, available_width)
+    }
+    fn all_changed(&self) -> bool {
+        false
+    }
+    fn content_changed(&self, component: &Ref<T>) -> bool {
+        component.is_changed()
+    }
 }
 
 type BasicQuery<'w, 's, C, F> =
@@ -140,11 +525,47 @@ type BasicQuery<'w, 's, C, F> =
 type NodeQuery<'w, 's> =
     BasicQuery<'w, 's, AnyOf<(&'static Node, &'static Root)>, Without<LeafNode>>;
 
+type ChangedContainers<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, Option<&'static Parent>),
+    (Without<LeafNode>, Or<(Changed<Node>, Changed<Root>, Changed<Children>)>),
+>;
+
+/// The layout root of `entity`, its farthest ancestor that is a layout node.
+fn root_of(mut entity: Entity, parent: Option<&Parent>, nodes: &NodeQuery) -> Entity {
+    let mut parent = parent.map(Parent::get);
+    while let Some((ancestor, _, grand_parent, _)) = parent.and_then(|p| nodes.get(p).ok()) {
+        entity = ancestor;
+        parent = grand_parent.map(Parent::get);
+    }
+    entity
+}
+
+/// The layout nodes of content-sized entities, with the components measured by `S`.
+type ContentSizedQuery<'w, 's, S> = BasicQuery<
+    'w,
+    's,
+    (
+        &'static mut Node,
+        (Option<&'static PlaceholderSize>, Option<Ref<'static, ContentSizeFactor>>),
+        <S as ComputeContentParam>::Components,
+    ),
+    With<LeafNode>,
+>;
+
 #[sysfail(log(level = "error"))]
 fn compute_content_size<S: ComputeContentParam>(
     compute_param: StaticSystemParam<S>,
-    mut content_sized: BasicQuery<(&mut Node, S::Components), With<LeafNode>>,
+    mut content_sized: ContentSizedQuery<S>,
     nodes: NodeQuery,
+    changed_containers: ChangedContainers,
+    mut changed_roots: Local<HashSet<Entity>>,
+    mut stats: ResMut<ContentSizeStats<S>>,
+    mut diagnostics: Diagnostics,
+    mut widths: ResMut<ContentWidths>,
+    font_size: Option<Res<RootFontSize>>,
+    epsilon: Option<Res<ContentSizeEpsilon>>,
 ) -> std::result::Result<(), Why<S>>
 where
     for<'w, 's> S::Item<'w, 's>: ComputeContentSize<Components = S::Components>,
@@ -155,29 +576,92 @@ where
         "Computing content-sized nodes for {}",
         bevy::utils::get_short_name(std::any::type_name::<S>())
     );
-    for (e, name, parent, (node, components)) in &mut content_sized {
+    let font_size_changed = font_size.as_ref().is_some_and(DetectChanges::is_changed);
+    let all_changed = font_size_changed || compute_param.all_changed();
+    changed_roots.clear();
+    if !all_changed {
+        for (e, parent) in &changed_containers {
+            changed_roots.insert(root_of(e, parent, &nodes));
+        }
+        for (e, _, parent, (node, (_, factor), components)) in &mut content_sized {
+            let factor_changed = factor.as_ref().is_some_and(DetectChanges::is_changed);
+            if node.is_changed() || factor_changed || compute_param.content_changed(&components) {
+                changed_roots.insert(root_of(e, parent, &nodes));
+            }
+        }
+    }
+    *stats = ContentSizeStats::default();
+    // Only the layout changes `widths`, see `remeasure_content_size`.
+    let widths = widths.bypass_change_detection();
+    let epsilon = epsilon.map_or_else(ContentSizeEpsilon::default, |e| *e).0;
+    for (e, name, parent, (node, (placeholder, factor), components)) in &mut content_sized {
         if !node.content_sized() {
             continue;
         }
-        trace!("Computing size of a node with constraints: {node:?}");
-        let size = match node_content_size(parent, &node, &nodes) {
-            Ok(size) => size,
+        let root = root_of(e, parent, &nodes);
+        if !all_changed && !changed_roots.contains(&root) {
+            stats.skipped += 1;
+            continue;
+        }
+        stats.computed += 1;
+        let root = nodes.get(root).ok().and_then(|(.., (_, root))| root);
+        let lengths = RootLengths::new(root, font_size.as_deref());
+        let content = (node, placeholder, factor, components);
+        let available = widths.measure(e);
+        match measure_node(&*compute_param, content, parent, &nodes, &lengths, available, epsilon) {
+            Ok(SizeUpdate::Avoided) => stats.avoided += 1,
+            Ok(SizeUpdate::Unchanged | SizeUpdate::Updated) => {}
             Err(err) => {
                 let errs = errs.get_or_insert((err.into_why(e, name), 0));
                 errs.1 += 1;
-                continue;
             }
-        };
-        let computed = compute_param.compute_content(components, size);
-        let computed = Size {
-            width: size.width.is_none().then_some(computed.width),
-            height: size.height.is_none().then_some(computed.height),
-        };
-        trace!("It is: {computed:?}");
-        if let Err(err) = set_node_content_size(node, computed) {
+        }
+    }
+    #[allow(clippy::cast_precision_loss)] // node counts as f64
+    let avoided = stats.avoided as f64;
+    diagnostics.add_measurement(ContentSizeStats::<S>::avoided_diagnostic(), || avoided);
+    if let Some((err, _)) = errs.take() {
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+/// Measure again the nodes which width is content-sized, when the layout gave
+/// them a different width than the one they were measured within.
+///
+/// This runs after [`ComputeLayout`], the layout then runs a second time
+/// if the size of a node changed.
+#[sysfail(log(level = "error"))]
+fn remeasure_content_size<S: ComputeContentParam>(
+    compute_param: StaticSystemParam<S>,
+    mut content_sized: ContentSizedQuery<S>,
+    nodes: NodeQuery,
+    mut widths: ResMut<ContentWidths>,
+    font_size: Option<Res<RootFontSize>>,
+    epsilon: Option<Res<ContentSizeEpsilon>>,
+) -> std::result::Result<(), Why<S>>
+where
+    for<'w, 's> S::Item<'w, 's>: ComputeContentSize<Components = S::Components>,
+{
+    let mut errs: Option<(Why<S>, usize)> = None;
+    let widths = widths.bypass_change_detection();
+    let epsilon = epsilon.map_or_else(ContentSizeEpsilon::default, |e| *e).0;
+    for (e, name, parent, (node, (placeholder, factor), components)) in &mut content_sized {
+        if !node.content_sized() || !widths.changed(e) {
+            continue;
+        }
+        let root = root_of(e, parent, &nodes);
+        let root = nodes.get(root).ok().and_then(|(.., (_, root))| root);
+        let lengths = RootLengths::new(root, font_size.as_deref());
+        let content = (node, placeholder, factor, components);
+        let available = widths.measure(e);
+        if let Err(err) =
+            measure_node(&*compute_param, content, parent, &nodes, &lengths, available, epsilon)
+        {
             let errs = errs.get_or_insert((err.into_why(e, name), 0));
             errs.1 += 1;
-        };
+        }
     }
     if let Some((err, _)) = errs.take() {
         Err(err)
@@ -186,11 +670,46 @@ where
     }
 }
 
+/// Measure the content of a content-sized `node`, within `available` width if
+/// its width is content-sized, and set its content-sized axes.
+fn measure_node<C: ComputeContentSize>(
+    compute_param: &C,
+    (node, placeholder, factor, components): (
+        Mut<Node>,
+        Option<&PlaceholderSize>,
+        Option<Ref<ContentSizeFactor>>,
+        ROQueryItem<C::Components>,
+    ),
+    parent: Option<&Parent>,
+    nodes: &NodeQuery,
+    lengths: &RootLengths,
+    available: Option<f32>,
+    epsilon: f32,
+) -> Result<SizeUpdate> {
+    trace!("Computing size of a node with constraints: {node:?}");
+    let size = node_content_size(parent, &node, nodes, lengths)?;
+    let available = match (size.width, &factor) {
+        (Some(_), _) => None,
+        (None, None) => available,
+        (None, Some(factor)) => available.map(|w| (w - factor.pad.width) / factor.factor.width),
+    };
+    let computed = compute_param.compute_content_within(components, size, available);
+    let computed = match (computed, placeholder) {
+        (computed, Some(placeholder)) if computed == Size::ZERO => placeholder.0,
+        (computed, _) => factor.map_or(computed, |factor| factor.apply(computed)),
+    };
+    let computed = Size {
+        width: size.width.is_none().then_some(computed.width),
+        height: size.height.is_none().then_some(computed.height),
+    };
+    trace!("It is: {computed:?}");
+    set_node_content_size(node, computed, epsilon)
+}
+
 enum BadRule {
     OrphanUnnamed,
     Orphan(Handle),
     Nan(Axis),
-    Cyclic,
 }
 impl BadRule {
     fn into_why<T>(self, e: Entity, name: Option<&Name>) -> Why<T> {
@@ -200,7 +719,6 @@ impl BadRule {
             Self::OrphanUnnamed => Why::Orphan(handle()),
             Self::Orphan(handle) => Why::Orphan(handle),
             Self::Nan(axis) => Why::Nan(axis, handle()),
-            Self::Cyclic => Why::CyclicRule,
         }
     }
 
@@ -209,7 +727,7 @@ impl BadRule {
         let handle = || name.map_or(Unnamed(e), |n| Named(n.clone()));
         match self {
             Self::OrphanUnnamed => Self::Orphan(handle()),
-            Self::Orphan(_) | Self::Nan(_) | Self::Cyclic => self,
+            Self::Orphan(_) | Self::Nan(_) => self,
         }
     }
 }
@@ -226,10 +744,12 @@ fn node_content_size(
     parent: Option<&Parent>,
     node: &Node,
     nodes: &NodeQuery,
+    root: &RootLengths,
 ) -> Result<Size<Option<f32>>> {
     let leaf_size = |axis, rule| match rule {
-        LeafRule::Parent(ratio) => Ok(Some(parent_size(ratio, axis, parent, nodes)?)),
+        LeafRule::Parent(ratio) => parent_size(ratio, axis, parent, nodes, root),
         LeafRule::Fixed(value) => Ok(Some(value)),
+        LeafRule::Root(ratio, unit) => Ok(Some(root.get(ratio, unit))),
         LeafRule::Content(_) => Ok(None),
     };
     // TODO(bug)TODO(feat): Node::Axis
@@ -245,37 +765,277 @@ fn node_content_size(
         );
     }
 }
-fn parent_size(ratio: f32, axis: Axis, this: Option<&Parent>, nodes: &NodeQuery) -> Result<f32> {
+/// The size of `ratio` times the parent's size on `axis`, `None` if it depends
+/// on the size of other children.
+fn parent_size(
+    ratio: f32,
+    axis: Axis,
+    this: Option<&Parent>,
+    nodes: &NodeQuery,
+    root: &RootLengths,
+) -> Result<Option<f32>> {
     use BadRule::OrphanUnnamed as Orphan;
     let this = this.ok_or(Orphan)?.get();
     let (e, n, parent, node) = nodes.get(this).map_err(|_| Orphan)?;
     let rules = get_rules(node)?;
     match axis.relative(rules.as_ref()).main {
-        Rule::Children(_) => Err(BadRule::Cyclic),
-        &Rule::Fixed(value) => Ok(ratio * value),
+        // The layout algorithm reports an error if this is a circular dependency.
+        Rule::Children(_) => Ok(None),
+        &Rule::Fixed(value) => Ok(Some(ratio * value)),
+        &Rule::Root(this_ratio, unit) => Ok(Some(ratio * root.get(this_ratio, unit))),
         Rule::Parent(this_ratio) => {
-            parent_size(ratio * this_ratio, axis, parent, nodes).map_err(|err| err.name(e, n))
+            let size = parent_size(ratio * this_ratio, axis, parent, nodes, root);
+            size.map_err(|err| err.name(e, n))
         }
     }
 }
-fn set_node_content_size(mut node: Mut<Node>, new: Size<Option<f32>>) -> Result<()> {
-    let Node::Box(size) = &mut *node else {
+/// What [`set_node_content_size`] did to a node.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SizeUpdate {
+    /// The content size is the same as the node's.
+    Unchanged,
+    /// The content size changed by less than the [`ContentSizeEpsilon`].
+    Avoided,
+    /// The node's size was updated.
+    Updated,
+}
+/// Set the content-sized axes of `node` to `new`, unless they are less than
+/// `epsilon` away from their current value.
+///
+/// `node` is only marked as changed if it was [`SizeUpdate::Updated`].
+fn set_node_content_size(
+    mut node: Mut<Node>,
+    new: Size<Option<f32>>,
+    epsilon: f32,
+) -> Result<SizeUpdate> {
+    let Node::Box(size) = node.bypass_change_detection() else {
         unreachable!(
             "set_node_content_size is only called on node.is_content_sized() \
             meaning this branch should never be reached"
         );
     };
-    if let (LeafRule::Content(to_update), Some(new)) = (&mut size.width, new.width) {
-        if new.is_nan() {
-            return Err(BadRule::Nan(Axis::Horizontal));
-        }
+    let width = set_axis_content_size(&mut size.width, new.width, epsilon, Axis::Horizontal);
+    let height = set_axis_content_size(&mut size.height, new.height, epsilon, Axis::Vertical);
+    if matches!(width, Ok(SizeUpdate::Updated)) || matches!(height, Ok(SizeUpdate::Updated)) {
+        node.set_changed();
+    }
+    Ok(width?.max(height?))
+}
+fn set_axis_content_size(
+    rule: &mut LeafRule,
+    new: Option<f32>,
+    epsilon: f32,
+    axis: Axis,
+) -> Result<SizeUpdate> {
+    let (LeafRule::Content(to_update), Some(new)) = (rule, new) else {
+        return Ok(SizeUpdate::Unchanged);
+    };
+    if new.is_nan() {
+        return Err(BadRule::Nan(axis));
+    }
+    let delta = (new - *to_update).abs();
+    if delta == 0. {
+        Ok(SizeUpdate::Unchanged)
+    } else if delta < epsilon {
+        Ok(SizeUpdate::Avoided)
+    } else {
         *to_update = new;
+        Ok(SizeUpdate::Updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::diagnostic::DiagnosticsStore;
+    use bevy::ecs::query::QueryItem;
+    use bevy::prelude::{BuildWorldChildren, DetectChanges};
+    use bevy::time::Time;
+
+    use super::*;
+    use crate::{Alignment, Distribution, Flow, LayoutRect};
+
+    #[derive(Component)]
+    struct Content(f32);
+
+    #[derive(SystemParam)]
+    struct ContentSize;
+    impl ComputeContentParam for ContentSize {
+        type Components = Ref<'static, Content>;
+
+        fn condition(label: ContentSizedComputeSystem<Self>) -> SystemSetConfigs {
+            label.into_configs()
+        }
     }
-    if let (LeafRule::Content(to_update), Some(new)) = (&mut size.height, new.height) {
-        if new.is_nan() {
-            return Err(BadRule::Nan(Axis::Vertical));
+    impl ComputeContentSize for ContentSize {
+        type Components = Ref<'static, Content>;
+
+        fn compute_content(&self, content: Ref<Content>, _: Size<Option<f32>>) -> Size<f32> {
+            Size::all(content.0)
         }
-        *to_update = new;
+        fn all_changed(&self) -> bool {
+            false
+        }
+        fn content_changed(&self, content: &QueryItem<Self::Components>) -> bool {
+            content.is_changed()
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>().init_resource::<crate::LastLayoutChange>();
+        app
+    }
+    /// Spawn a 100×100 vertical root with `margin`, parent of `children`.
+    fn spawn_root(world: &mut World, margin: f32, children: &[Entity]) {
+        let (size, margin) = (Size::all(100.), Size::all(margin));
+        let root = Root::new(size, Flow::Vertical, Alignment::Start, Distribution::Start, margin);
+        world.spawn(root).push_children(children);
+    }
+    fn box_size(app: &App, entity: Entity) -> Size<LeafRule> {
+        match app.world.get::<Node>(entity) {
+            Some(Node::Box(size)) => *size,
+            node => panic!("{node:?} is not a box"),
+        }
+    }
+
+    #[test]
+    fn skip_unchanged_roots() {
+        let mut app = app();
+        app.add_content_sized::<ContentSize>();
+        let spawn_leaf = |world: &mut World| {
+            let leaf = Node::Box(Size::all(LeafRule::Content(0.)));
+            let leaf = world.spawn((leaf, LeafNode, Content(10.))).id();
+            spawn_root(world, 0., &[leaf]);
+            leaf
+        };
+        let changed_leaf = spawn_leaf(&mut app.world);
+        spawn_leaf(&mut app.world);
+
+        let stats = |app: &App| *app.world.resource::<ContentSizeStats<ContentSize>>();
+        app.update();
+        assert_eq!((stats(&app).computed, stats(&app).skipped), (2, 0));
+        app.update();
+        assert_eq!((stats(&app).computed, stats(&app).skipped), (0, 2));
+
+        app.world.get_mut::<Content>(changed_leaf).unwrap().0 = 20.;
+        app.update();
+        assert_eq!((stats(&app).computed, stats(&app).skipped), (1, 1));
+        assert_eq!(box_size(&app, changed_leaf), Size::all(LeafRule::Content(20.)));
+    }
+    #[test]
+    fn ignore_jitter() {
+        let mut app = app();
+        app.add_content_sized::<ContentSize>();
+        let leaf = Node::Box(Size::all(LeafRule::Content(0.)));
+        let leaf = app.world.spawn((leaf, LeafNode, Content(10.))).id();
+        spawn_root(&mut app.world, 0., &[leaf]);
+
+        let stats = |app: &App| *app.world.resource::<ContentSizeStats<ContentSize>>();
+        let size = |app: &App| box_size(app, leaf).width;
+        app.update();
+        assert_eq!(size(&app), LeafRule::Content(10.));
+
+        app.world.get_mut::<Content>(leaf).unwrap().0 = 10.1;
+        app.update();
+        assert_eq!((stats(&app).computed, stats(&app).avoided), (1, 1));
+        let avoided = ContentSizeStats::<ContentSize>::avoided_diagnostic();
+        let diagnostics = app.world.resource::<DiagnosticsStore>();
+        assert_eq!(diagnostics.get(avoided).and_then(Diagnostic::value), Some(1.));
+        assert_eq!(size(&app), LeafRule::Content(10.));
+
+        app.world.get_mut::<Content>(leaf).unwrap().0 = 10.5;
+        app.update();
+        assert_eq!((stats(&app).computed, stats(&app).avoided), (1, 0));
+        assert_eq!(size(&app), LeafRule::Content(10.5));
+
+        app.insert_resource(ContentSizeEpsilon(0.));
+        app.world.get_mut::<Content>(leaf).unwrap().0 = 10.6;
+        app.update();
+        assert_eq!(size(&app), LeafRule::Content(10.6));
+    }
+    #[derive(Component)]
+    struct Square(f32);
+    impl ContentSizedComponent for Square {
+        fn content_size(&self, set_size: Size<Option<f32>>) -> Size<f32> {
+            Size::all(set_size.width.or(set_size.height).unwrap_or(self.0))
+        }
+    }
+
+    #[test]
+    fn content_sized_component() {
+        let mut app = app();
+        app.add_content_sized_component::<Square>();
+        let free = Node::Box(Size::all(LeafRule::Content(0.)));
+        let free = app.world.spawn((free, LeafNode, Square(10.))).id();
+        let fixed = Node::Box(Size::new(LeafRule::Fixed(30.), LeafRule::Content(0.)));
+        let fixed = app.world.spawn((fixed, LeafNode, Square(10.))).id();
+        spawn_root(&mut app.world, 0., &[free, fixed]);
+
+        app.update();
+        assert_eq!(box_size(&app, free), Size::all(LeafRule::Content(10.)));
+        let expected = Size::new(LeafRule::Fixed(30.), LeafRule::Content(30.));
+        assert_eq!(box_size(&app, fixed), expected);
+
+        app.world.get_mut::<Square>(free).unwrap().0 = 20.;
+        app.update();
+        assert_eq!(box_size(&app, free), Size::all(LeafRule::Content(20.)));
+    }
+    /// Text of `.0` characters, each 10 pixels wide and high, wrapping when
+    /// wider than the available width.
+    #[derive(Component)]
+    struct Paragraph(f32);
+    impl ContentSizedComponent for Paragraph {
+        fn content_size(&self, _: Size<Option<f32>>) -> Size<f32> {
+            Size::new(self.0 * 10., 10.)
+        }
+        fn content_size_within(&self, _: Size<Option<f32>>, available: Option<f32>) -> Size<f32> {
+            let per_line = available.map_or(self.0, |width| (width / 10.).floor().max(1.));
+            Size::new(per_line.min(self.0) * 10., (self.0 / per_line).ceil() * 10.)
+        }
+    }
+
+    #[test]
+    fn wrap_to_available_width() {
+        let mut app = app();
+        app.add_plugins(crate::Plugin).add_content_sized_component::<Paragraph>();
+        let container = |flow| (Node::Container(Container::compact(flow)), LayoutRect::default());
+        let column = app.world.spawn(container(Flow::Vertical)).id();
+        let leaf = |text| {
+            let node = Node::Box(Size::all(LeafRule::Content(0.)));
+            (node, LeafNode, Paragraph(text), LayoutRect::default())
+        };
+        let long = app.world.spawn(leaf(20.)).id();
+        let short = app.world.spawn(leaf(3.)).id();
+        app.world.entity_mut(column).push_children(&[long, short]);
+        let row = app.world.spawn(container(Flow::Horizontal)).id();
+        let sibling = Node::Box(Size::all(LeafRule::Fixed(30.)));
+        let sibling = app.world.spawn((sibling, LayoutRect::default())).id();
+        let beside = app.world.spawn(leaf(12.)).id();
+        app.world.entity_mut(row).push_children(&[sibling, beside]);
+        spawn_root(&mut app.world, 10., &[column, row]);
+
+        app.update();
+        let content = |w, h| Size::new(LeafRule::Content(w), LeafRule::Content(h));
+        // The root is 100 pixels wide, minus its 10 pixels margins: 80 pixels,
+        // that is 8 characters per line.
+        assert_eq!(box_size(&app, long), content(80., 30.));
+        assert_eq!(box_size(&app, short), content(30., 10.));
+        // 80 pixels, minus the 30 pixels of its sibling: 5 characters per line.
+        assert_eq!(box_size(&app, beside), content(50., 30.));
+        let rect = app.world.get::<LayoutRect>(beside).unwrap();
+        assert_eq!(rect.size(), Size::new(50., 30.));
+    }
+    #[test]
+    fn factor_and_pad() {
+        let mut app = app();
+        app.add_content_sized::<ContentSize>();
+        let leaf = Node::Box(Size::new(LeafRule::Content(0.), LeafRule::Content(0.)));
+        let factor = ContentSizeFactor { factor: Size::new(1., 1.5), pad: Size::all(8.) };
+        let leaf = app.world.spawn((leaf, LeafNode, Content(10.), factor)).id();
+        spawn_root(&mut app.world, 0., &[leaf]);
+
+        app.update();
+        let expected = Size::new(LeafRule::Content(18.), LeafRule::Content(23.));
+        assert_eq!(box_size(&app, leaf), expected);
     }
-    Ok(())
 }
@@ -0,0 +1,99 @@
+//! Positions of nodes in the layout space of their root, see [`LayoutPositions`].
+//!
+//! The _layout space_ of a [`Root`] has its origin at the top left corner of
+//! the root, with the Y axis going down, and uses the same units as
+//! [`LayoutRect`].
+//!
+//! Backends convert between layout space and world or screen space, taking
+//! into account their own Y axis direction, the camera projection and the
+//! position of the root. See the `coords` module of `cuicui_layout_bevy_ui`
+//! and `cuicui_layout_bevy_sprite`. Both have a `LayoutCoords` system parameter
+//! with the same methods:
+//!
+//! - `layout_to_world(entity)` and `layout_to_screen(entity)`: where the top
+//!   left corner of a node is.
+//! - `world_to_layout(root, point)` and `screen_to_layout(root, point)`: where
+//!   a point is in the layout space of `root`, to compare it with
+//!   [`LayoutPositions::rect_in_root`], for example to hit-test nodes.
+
+use bevy::ecs::{prelude::*, system::SystemParam};
+use bevy::hierarchy::Parent;
+use bevy::math::{Rect, Vec2};
+
+use crate::{LayoutRect, PixelSnap, Root};
+
+/// Get the position of nodes in the layout space of their [`Root`].
+///
+/// Positions follow [`PixelSnap`], like backends do when positioning nodes.
+#[derive(SystemParam)]
+pub struct LayoutPositions<'w, 's> {
+    snap: Res<'w, PixelSnap>,
+    nodes: Query<'w, 's, (&'static LayoutRect, Option<&'static Parent>, Has<Root>)>,
+}
+impl LayoutPositions<'_, '_> {
+    /// The [`LayoutRect`] of `entity`, [snapped](LayoutRect::snapped) if
+    /// [`PixelSnap`] is enabled.
+    #[must_use]
+    pub fn rect(&self, entity: Entity) -> Option<LayoutRect> {
+        let (rect, ..) = self.nodes.get(entity).ok()?;
+        Some(rect.snapped_if(*self.snap))
+    }
+    /// The [`Root`] of `entity` and the position of the top left corner of
+    /// `entity` in the layout space of this root.
+    ///
+    /// The position of a root in its own layout space is zero.
+    ///
+    /// `None` if `entity` or one of its ancestors is not a node, or if
+    /// `entity` is not in a root.
+    #[must_use]
+    pub fn in_root(&self, entity: Entity) -> Option<(Entity, Vec2)> {
+        let mut position = Vec2::ZERO;
+        let mut current = entity;
+        loop {
+            let (rect, parent, is_root) = self.nodes.get(current).ok()?;
+            if is_root {
+                return Some((current, position));
+            }
+            position += rect.snapped_if(*self.snap).pos();
+            current = parent?.get();
+        }
+    }
+    /// Like [`Self::in_root`], but the whole rectangle of `entity`. `min` is its
+    /// top left corner.
+    #[must_use]
+    pub fn rect_in_root(&self, entity: Entity) -> Option<(Entity, Rect)> {
+        let (root, position) = self.in_root(entity)?;
+        let size = Vec2::from(self.rect(entity)?.size());
+        Some((root, Rect::from_corners(position, position + size)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn positions_in_root() {
+        let mut world = World::new();
+        world.init_resource::<PixelSnap>();
+        let rect = |x, y, width, height| LayoutRect::from(Rect::new(x, y, x + width, y + height));
+        let root = world.spawn((Root::default(), rect(0., 0., 300., 200.))).id();
+        let menu = world.spawn(rect(10., 20., 100., 100.)).set_parent(root).id();
+        let button = world.spawn(rect(5.5, 30.25, 50., 10.)).set_parent(menu).id();
+        let orphan = world.spawn(rect(5., 5., 5., 5.)).id();
+
+        let mut state = bevy::ecs::system::SystemState::<LayoutPositions>::new(&mut world);
+        let positions = state.get(&world);
+        assert_eq!(positions.in_root(root), Some((root, Vec2::ZERO)));
+        assert_eq!(positions.in_root(button), Some((root, Vec2::new(15.5, 50.25))));
+        let expected = Rect::new(15.5, 50.25, 65.5, 60.25);
+        assert_eq!(positions.rect_in_root(button), Some((root, expected)));
+        assert_eq!(positions.in_root(orphan), None);
+
+        world.insert_resource(PixelSnap(true));
+        let positions = state.get(&world);
+        assert_eq!(positions.in_root(button), Some((root, Vec2::new(16., 50.))));
+    }
+}
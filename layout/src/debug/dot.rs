@@ -0,0 +1,177 @@
+//! Export a layout tree as a graphviz graph.
+
+use std::fmt::{self, Write};
+
+use bevy::ecs::prelude::*;
+use bevy::prelude::{Children, Name};
+
+use crate::{
+    Anchored, Container, LayoutRect, LeafRule, Node, Root, RootUnit, Rule, Sides, SizeBounds,
+};
+
+struct ShowRule(Rule);
+impl fmt::Display for ShowRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Rule::Fixed(pixels) => write!(f, "{pixels}px"),
+            Rule::Parent(ratio) => write!(f, "{}%", ratio * 100.),
+            Rule::Children(ratio) => write!(f, "child({ratio})"),
+            Rule::Root(ratio, unit) => write_root(f, ratio, unit),
+        }
+    }
+}
+fn write_root(f: &mut fmt::Formatter, ratio: f32, unit: RootUnit) -> fmt::Result {
+    match unit {
+        RootUnit::Vmin => write!(f, "{}vmin", ratio * 100.),
+        RootUnit::Vmax => write!(f, "{}vmax", ratio * 100.),
+        RootUnit::Em => write!(f, "{ratio}em"),
+    }
+}
+struct ShowBound(Option<Rule>);
+impl fmt::Display for ShowBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(rule) => ShowRule(rule).fmt(f),
+            None => f.write_str("_"),
+        }
+    }
+}
+struct ShowLeafRule(LeafRule);
+impl fmt::Display for ShowLeafRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            LeafRule::Fixed(pixels) => write!(f, "{pixels}px"),
+            LeafRule::Parent(ratio) => write!(f, "{}%", ratio * 100.),
+            LeafRule::Content(pixels) => write!(f, "content({pixels}px)"),
+            LeafRule::Root(ratio, unit) => write_root(f, ratio, unit),
+        }
+    }
+}
+
+fn write_container(label: &mut String, kind: &str, container: &Container) -> fmt::Result {
+    let Container { flow, align, distrib, rules, margin, padding, .. } = container;
+    let rules = rules.map(ShowRule);
+    writeln!(label, "{kind} {flow:?}, {distrib:?}, align {align:?}")?;
+    writeln!(label, "rules: {rules}")?;
+    if *margin != Sides::ZERO {
+        writeln!(label, "margin: {margin}")?;
+    }
+    if *padding != Sides::ZERO {
+        writeln!(label, "padding: {padding}")?;
+    }
+    Ok(())
+}
+fn write_label(label: &mut String, world: &World, entity: Entity) -> fmt::Result {
+    match world.get::<Name>(entity) {
+        Some(name) => writeln!(label, "{name}")?,
+        None => writeln!(label, "{entity:?}")?,
+    }
+    match (world.get::<Root>(entity), world.get::<Node>(entity)) {
+        (Some(root), _) => write_container(label, "Root", root.get())?,
+        (None, Some(Node::Container(container))) => write_container(label, "Container", container)?,
+        (None, Some(Node::Axis(oriented))) => writeln!(
+            label,
+            "Axis main: {}, cross: {}",
+            ShowLeafRule(oriented.main),
+            ShowLeafRule(oriented.cross),
+        )?,
+        (None, Some(Node::Box(size))) => writeln!(label, "Box {}", size.map(ShowLeafRule))?,
+        (None, None) => {}
+    }
+    if let Some(Anchored { anchor, offset }) = world.get::<Anchored>(entity) {
+        writeln!(label, "anchored {anchor:?} offset {offset}")?;
+    }
+    if let Some(SizeBounds { min, max }) = world.get::<SizeBounds>(entity) {
+        writeln!(label, "min {} max {}", min.map(ShowBound), max.map(ShowBound))?;
+    }
+    if let Some(rect) = world.get::<LayoutRect>(entity) {
+        let pos = rect.pos();
+        writeln!(label, "{} at ({}, {})", rect.size(), pos.x, pos.y)?;
+    }
+    Ok(())
+}
+/// Escape `label` so that it can be used as a graphviz string, each line
+/// is left-justified.
+fn escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\l"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+fn write_node(dot: &mut String, world: &World, entity: Entity) -> fmt::Result {
+    let mut label = String::new();
+    write_label(&mut label, world, entity)?;
+    writeln!(dot, "  \"{entity:?}\" [label=\"{}\"];", escape(&label))?;
+
+    let Some(children) = world.get::<Children>(entity) else {
+        return Ok(());
+    };
+    let is_layout = |e: &&Entity| world.get::<Node>(**e).is_some();
+    for child in children.iter().filter(is_layout) {
+        writeln!(dot, "  \"{entity:?}\" -> \"{child:?}\";")?;
+        write_node(dot, world, *child)?;
+    }
+    Ok(())
+}
+
+/// A [graphviz] `dot` graph of the layout tree of `root`.
+///
+/// Each layout node is a graph node showing its name, rules and computed
+/// size and position. Children that are not layout nodes are omitted.
+///
+/// This is useful to document layouts or debug deep trees, where the debug
+/// overlay gets too cluttered. Render the graph with `dot -Tsvg layout.dot`.
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_layout::{debug, Root};
+///
+/// fn export_layout(world: &World) -> Vec<String> {
+///     let mut roots = world.iter_entities().filter(|e| e.contains::<Root>());
+///     roots.map(|root| debug::to_dot(world, root.id())).collect()
+/// }
+/// ```
+///
+/// [graphviz]: https://graphviz.org/
+#[must_use]
+#[allow(clippy::missing_panics_doc)] // `fmt::Write` for String never fails.
+pub fn to_dot(world: &World, root: Entity) -> String {
+    let mut dot = String::from("digraph layout {\n  node [shape=box];\n");
+    write_node(&mut dot, world, root).unwrap();
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::BuildWorldChildren;
+
+    use super::*;
+    use crate::{Alignment, Distribution, Flow, Size};
+
+    #[test]
+    fn layout_graph() {
+        let mut world = World::new();
+        let size = Size::new(640., 360.);
+        let root = Root::new(size, Flow::Vertical, Alignment::Start, Distribution::Start, size);
+        let root = world.spawn((root, Name::new("Menu \"main\""))).id();
+        let leaf = Node::Box(Size::new(LeafRule::Parent(0.5), LeafRule::Content(20.)));
+        let leaf = world.spawn(leaf).set_parent(root).id();
+        world.spawn(Name::new("Not a layout node")).set_parent(root);
+
+        let expected = format!(
+            "digraph layout {{\n  node [shape=box];\n  \
+            \"{root:?}\" [label=\"Menu \\\"main\\\"\\lRoot Vertical, Start, align Start\\l\
+            rules: 640px×360px\\lmargin: 640×360\\l\"];\n  \
+            \"{root:?}\" -> \"{leaf:?}\";\n  \
+            \"{leaf:?}\" [label=\"{leaf:?}\\lBox 50%×content(20px)\\l\"];\n}}\n"
+        );
+        assert_eq!(to_dot(&world, root), expected);
+    }
+}
@@ -5,7 +5,7 @@ use bevy::utils::HashMap;
 use super::{CameraQuery, RuleArrow};
 use crate::debug::CHEVRON_RATIO;
 use crate::direction::Axis;
-use crate::{LayoutRect, Size};
+use crate::{LayoutRect, Sides};
 
 trait ApproxF32 {
     fn is(self, other: f32) -> bool;
@@ -17,10 +17,9 @@ impl ApproxF32 for f32 {
     }
 }
 
-fn rect_border_axis(rect: LayoutRect, margin: Size<f32>) -> (f32, f32, f32, f32) {
-    let pos = rect.pos() + Vec2::from(margin);
-    let size = Vec2::from(rect.size()) - Vec2::from(margin) * 2.;
-    let offset = pos + size;
+fn rect_border_axis(rect: LayoutRect, margin: Sides<f32>) -> (f32, f32, f32, f32) {
+    let pos = rect.pos() + Vec2::from(margin.start());
+    let offset = rect.pos() + Vec2::from(rect.size()) - Vec2::from(margin.end());
     (pos.x, offset.x, pos.y, offset.y)
 }
 
@@ -112,20 +111,27 @@ impl DrawnLines {
     }
 }
 
-pub(super) struct InsetGizmo<'w, 's> {
+/// Where [`InsetGizmo`] draws its lines, in layout coordinates.
+///
+/// This is [`GizmoLines`] in the debug overlay, tests record the lines instead.
+pub(super) trait DrawLines {
+    fn line_2d(&mut self, start: Vec2, end: Vec2, color: Color);
+    fn linestrip_2d(&mut self, strip: &[Vec2], color: Color) {
+        for segment in strip.windows(2) {
+            self.line_2d(segment[0], segment[1], color);
+        }
+    }
+}
+
+/// Draw lines with bevy [`Gizmos`], in screen space if the debug camera
+/// is a screen space camera.
+pub(super) struct GizmoLines<'w, 's> {
     draw: Gizmos<'s>,
     cam: CameraQuery<'w, 's>,
-    known_y: DrawnLines,
-    known_x: DrawnLines,
 }
-impl<'w, 's> InsetGizmo<'w, 's> {
-    pub(super) fn new(draw: Gizmos<'s>, cam: CameraQuery<'w, 's>, line_width: f32) -> Self {
-        InsetGizmo {
-            draw,
-            cam,
-            known_y: DrawnLines::new(line_width),
-            known_x: DrawnLines::new(line_width),
-        }
+impl<'w, 's> GizmoLines<'w, 's> {
+    pub(super) const fn new(draw: Gizmos<'s>, cam: CameraQuery<'w, 's>) -> Self {
+        GizmoLines { draw, cam }
     }
     fn relative(&self, mut position: Vec2) -> Vec2 {
         let zero = GlobalTransform::IDENTITY;
@@ -139,6 +145,33 @@ impl<'w, 's> InsetGizmo<'w, 's> {
         }
         position.xy()
     }
+}
+impl DrawLines for GizmoLines<'_, '_> {
+    fn line_2d(&mut self, start: Vec2, end: Vec2, color: Color) {
+        let (start, end) = (self.relative(start), self.relative(end));
+        self.draw.line_2d(start, end, color);
+    }
+    fn linestrip_2d(&mut self, strip: &[Vec2], color: Color) {
+        let strip = strip.iter().map(|v| self.relative(*v));
+        self.draw.linestrip_2d(strip.collect::<Vec<_>>(), color);
+    }
+}
+
+/// Draw lines, offset so that they do not overlap with the lines of the
+/// containers they are in.
+pub(super) struct InsetGizmo<D: DrawLines> {
+    pub(super) draw: D,
+    known_y: DrawnLines,
+    known_x: DrawnLines,
+}
+impl<D: DrawLines> InsetGizmo<D> {
+    pub(super) fn new(draw: D, line_width: f32) -> Self {
+        InsetGizmo {
+            draw,
+            known_y: DrawnLines::new(line_width),
+            known_x: DrawnLines::new(line_width),
+        }
+    }
     /// Draw rule at edge of container on given axis.
     pub(super) fn rule(
         &mut self,
@@ -172,24 +205,23 @@ impl<'w, 's> InsetGizmo<'w, 's> {
             start.y = self.known_y.inset(start.y);
             end.y = start.y;
         }
-        let (start, end) = (self.relative(start), self.relative(end));
         self.draw.line_2d(start, end, color);
     }
-    pub(super) fn set_scope(&mut self, rect: LayoutRect, margin: Size<f32>) {
+    pub(super) fn set_scope(&mut self, rect: LayoutRect, margin: Sides<f32>) {
         let (left, right, top, bottom) = rect_border_axis(rect, margin);
         self.known_x.add(left, 1);
         self.known_x.add(right, -1);
         self.known_y.add(top, 1);
         self.known_y.add(bottom, -1);
     }
-    pub(super) fn clear_scope(&mut self, rect: LayoutRect, margin: Size<f32>) {
+    pub(super) fn clear_scope(&mut self, rect: LayoutRect, margin: Sides<f32>) {
         let (left, right, top, bottom) = rect_border_axis(rect, margin);
         self.known_x.remove(left, 1);
         self.known_x.remove(right, -1);
         self.known_y.remove(top, 1);
         self.known_y.remove(bottom, -1);
     }
-    pub(super) fn rect_2d(&mut self, rect: LayoutRect, margin: Size<f32>, color: Color) {
+    pub(super) fn rect_2d(&mut self, rect: LayoutRect, margin: Sides<f32>, color: Color) {
         let (left, right, top, bottom) = rect_border_axis(rect, margin);
         if left.is(right) {
             self.line_2d(Vec2::new(left, top), Vec2::new(left, bottom), color);
@@ -207,11 +239,10 @@ impl<'w, 's> InsetGizmo<'w, 's> {
                 Vec2::new(right, top),
                 Vec2::new(left, top),
             ];
-            self.draw
-                .linestrip_2d(strip.map(|v| self.relative(v)), color);
+            self.draw.linestrip_2d(&strip, color);
         }
     }
-    fn arrow(&mut self, start: Vec2, end: Vec2, color: Color, chevron_size: f32) {
+    pub(super) fn arrow(&mut self, start: Vec2, end: Vec2, color: Color, chevron_size: f32) {
         let Some(angle) = (end - start).try_normalize() else {
             return;
         };
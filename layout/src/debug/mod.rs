@@ -15,11 +15,15 @@ use bevy::utils::default;
 use bevy::window::{PrimaryWindow, Window};
 
 use crate::direction::Axis;
-use crate::{Flow, LayoutRect, LayoutRootCamera, LeafRule, Node, Root, Rule, ScreenRoot, Size};
-use inset::InsetGizmo;
+use crate::layout::wrap_lines;
+use crate::{Anchored, Container, Flow, LastLayoutChange, LayoutRect, LayoutRootCamera};
+use crate::{LeafRule, Node, Oriented, Root, Rule, ScreenRoot, Sides, Size};
+use inset::{DrawLines, GizmoLines, InsetGizmo};
 
+pub use dot::to_dot;
 pub use enumset::{EnumSet, EnumSetType};
 
+mod dot;
 mod inset;
 
 /// The [`Camera::order`] index used by the layout debug camera.
@@ -31,6 +35,7 @@ const MARGIN_LIGHTNESS: f32 = 0.85;
 const NODE_LIGHTNESS: f32 = 0.7;
 const NODE_SATURATION: f32 = 0.8;
 const CHEVRON_RATIO: f32 = 1. / 4.;
+const OVERFLOW_COLOR: Color = Color::RED;
 
 #[allow(clippy::cast_precision_loss)]
 fn hue_from_entity(entity: Entity) -> f32 {
@@ -42,7 +47,11 @@ fn hue_from_entity(entity: Entity) -> f32 {
 /// The Kind of debug overlays available in `cuicui_layout`.
 #[derive(EnumSetType, Debug)]
 pub enum Flag {
-    /// Show layout node outlines, and their margin as lighter color.
+    /// Show layout node outlines, and their margin and padding as lighter color.
+    ///
+    /// The lines of [wrapping](Container::wrap) containers are also outlined
+    /// in the lighter color. An arrow goes from the anchor point of the
+    /// parent of [`Anchored`] nodes to their own anchor point.
     Outlines,
     /// Show rules as arrows, and rule percentages/ratio as numbers on top
     /// of them.
@@ -50,7 +59,8 @@ pub enum Flag {
     /// - [`Rule::Children`] are arrows pointing from edge of container inward
     /// - [`LeafRule::Fixed`] (content-sized), like above, but without number
     /// - [`Rule::Parent`], [`LeafRule::Parent`] are arrows pointing toward the edge of container
-    /// - [`Rule::Fixed`], [`LeafRule::Fixed`] (not content-sized) are not shown.
+    /// - [`Rule::Fixed`], [`LeafRule::Fixed`] (not content-sized) and
+    ///   [`Rule::Root`] are not shown.
     Rules,
     /// Hold shift to see detailed information about hovered container as tooltip.
     ///
@@ -180,10 +190,10 @@ fn cycle_flags(input: Res<Input<KeyCode>>, mut options: ResMut<Options>) {
     }
 }
 
-const fn node_margin(node: &Node) -> Size<f32> {
+fn node_margin(node: &Node) -> Sides<f32> {
     match node {
-        Node::Container(c) => c.margin,
-        _ => Size::ZERO,
+        Node::Container(c) => c.inset(),
+        _ => Sides::ZERO,
     }
 }
 fn node_rules(flow: Flow, node: &Node) -> Size<RuleArrow> {
@@ -195,7 +205,7 @@ fn node_rules(flow: Flow, node: &Node) -> Size<RuleArrow> {
 }
 fn outline_nodes(
     outline: &OutlineParam,
-    draw: &mut InsetGizmo,
+    draw: &mut InsetGizmo<impl DrawLines>,
     flow: Flow,
     this_entity: Entity,
     this: LayoutRect,
@@ -203,7 +213,7 @@ fn outline_nodes(
     let Ok(to_iter) = outline.children.get(this_entity) else {
         return;
     };
-    for (entity, node, child, vis) in outline.nodes.iter_many(to_iter) {
+    for (entity, node, child, vis, anchored) in outline.nodes.iter_many(to_iter) {
         let show_hidden = outline.options.show_hidden;
         let is_visible = |&v| ViewVisibility::get(v);
         if !(show_hidden || vis.is_some_and(is_visible)) {
@@ -214,9 +224,17 @@ fn outline_nodes(
         let mut rect = *child;
         rect.pos.width += this.pos.width;
         rect.pos.height += this.pos.height;
-        outline_node(entity, rect, margin, rules, outline.flags(), draw);
+        let flags = outline.flags();
+        let overflow = outline.last_layout.overflow() == Some(entity);
+        outline_node(entity, rect, margin, rules, flags, overflow, draw);
+        if let (Some(anchored), true) = (anchored, flags.contains(Flag::Outlines)) {
+            outline_anchor(draw, anchored, entity, this, rect);
+        }
 
         if let Node::Container(c) = node {
+            if c.wrap && flags.contains(Flag::Outlines) {
+                outline_lines(outline, draw, c, entity, rect);
+            }
             outline_nodes(outline, draw, c.flow, entity, rect);
         }
         if outline.flags().contains(Flag::Outlines) {
@@ -224,17 +242,75 @@ fn outline_nodes(
         }
     }
 }
+/// Outline each line of children of the [wrapping](Container::wrap) `container`.
+fn outline_lines(
+    outline: &OutlineParam,
+    draw: &mut InsetGizmo<impl DrawLines>,
+    container: &Container,
+    this_entity: Entity,
+    this: LayoutRect,
+) {
+    let Ok(to_iter) = outline.children.get(this_entity) else {
+        return;
+    };
+    if container.grid.is_some() || container.distrib.overlaps() {
+        return;
+    }
+    let flow = container.flow;
+    let margin = container.inset();
+    let start = flow.relative(margin.start()).main;
+    let inner_main = flow.relative(this.size() - margin.total()).main;
+    let in_flow = outline.nodes.iter_many(to_iter).filter(|node| node.4.is_none());
+    let children: Vec<_> = in_flow.map(|node| *node.2).collect();
+    let lines = wrap_lines(children.iter().map(|rect| flow.relative(rect.size)), inner_main);
+    if lines.len() < 2 {
+        return;
+    }
+    let color = Color::hsl(hue_from_entity(this_entity), NODE_SATURATION, MARGIN_LIGHTNESS);
+    let mut children = children.into_iter();
+    for line in lines {
+        let extent = |(start, end): (f32, f32), child: LayoutRect| {
+            let (pos, size) = (flow.relative(child.pos).cross, flow.relative(child.size).cross);
+            (start.min(pos), end.max(pos + size))
+        };
+        let bounds = (f32::INFINITY, f32::NEG_INFINITY);
+        let (line_start, end) = children.by_ref().take(line.count).fold(bounds, extent);
+        let rect = LayoutRect {
+            pos: this.pos + flow.absolute(Oriented::new(start, line_start)),
+            size: flow.absolute(Oriented::new(inner_main, end - line_start)),
+        };
+        draw.rect_2d(rect, Sides::ZERO, color);
+    }
+}
+
+/// Draw an arrow from the anchor point of `parent` to that of the
+/// [`Anchored`] node, showing its offset.
+fn outline_anchor(
+    draw: &mut InsetGizmo<impl DrawLines>,
+    anchored: &Anchored,
+    entity: Entity,
+    parent: LayoutRect,
+    this: LayoutRect,
+) {
+    let color = Color::hsl(hue_from_entity(entity), NODE_SATURATION, NODE_LIGHTNESS);
+    let anchor = anchored.anchor;
+    let start = parent.pos() + Vec2::from(anchor.offset(parent.size(), Size::ZERO));
+    let end = this.pos() + Vec2::from(anchor.offset(this.size(), Size::ZERO));
+    draw.arrow(start, end, color, start.distance(end) * CHEVRON_RATIO);
+}
 
 type OutlineParamQuery = (
     Entity,
     &'static Node,
     &'static LayoutRect,
     Option<&'static ViewVisibility>,
+    Option<&'static Anchored>,
 );
 #[derive(SystemParam)]
 struct OutlineParam<'w, 's> {
     gizmo_config: Res<'w, GizmoConfig>,
     options: Res<'w, Options>,
+    last_layout: Res<'w, LastLayoutChange>,
     children: Query<'w, 's, &'static Children>,
     nodes: Query<'w, 's, OutlineParamQuery>,
 }
@@ -244,13 +320,15 @@ impl OutlineParam<'_, '_> {
     }
 }
 type CameraQuery<'w, 's> = Query<'w, 's, (&'static Camera, &'static DebugOverlayCamera)>;
+type RootQuery<'w, 's> =
+    Query<'w, 's, (Entity, &'static Root, &'static LayoutRect, Has<ScreenRoot>)>;
 
 #[allow(clippy::cast_possible_truncation)] // The `window_scale` don't usually require f64 precision.
 fn outline_roots(
     outline: OutlineParam,
     draw: Gizmos,
     cam: CameraQuery,
-    roots: Query<(Entity, &Root, &LayoutRect, Has<ScreenRoot>)>,
+    roots: RootQuery,
     window: Query<&Window, With<PrimaryWindow>>,
     nonprimary_windows: Query<&Window, Without<PrimaryWindow>>,
 ) {
@@ -263,39 +341,48 @@ fn outline_roots(
     let scale_factor = Window::scale_factor;
     let window_scale = window.get_single().map_or(1., scale_factor) as f32;
     let line_width = outline.gizmo_config.line_width / window_scale;
-    let mut draw = InsetGizmo::new(draw, cam, line_width);
-    for (entity, root, rect, is_screen) in &roots {
+    let mut draw = InsetGizmo::new(GizmoLines::new(draw, cam), line_width);
+    outline_all(&outline, &roots, &mut draw);
+}
+/// Draw the debug overlay of all [`Root::debug`] `roots`.
+fn outline_all(outline: &OutlineParam, roots: &RootQuery, draw: &mut InsetGizmo<impl DrawLines>) {
+    for (entity, root, rect, is_screen) in roots {
         if !root.debug {
             continue;
         }
-        let margin = root.node.margin;
+        let margin = root.node.inset();
         let rules = root.node.rules.map_into();
         if is_screen {
             // inset so that the root container is fully visible.
-            draw.set_scope(*rect, Size::ZERO);
+            draw.set_scope(*rect, Sides::ZERO);
         }
-        outline_node(entity, *rect, margin, rules, outline.flags(), &mut draw);
+        outline_node(entity, *rect, margin, rules, outline.flags(), false, draw);
 
         let flow = root.node.flow;
-        outline_nodes(&outline, &mut draw, flow, entity, *rect);
+        outline_nodes(outline, draw, flow, entity, *rect);
     }
 }
 fn outline_node(
     entity: Entity,
     rect: LayoutRect,
-    margin: Size<f32>,
+    margin: Sides<f32>,
     rules: Size<RuleArrow>,
     flags: EnumSet<Flag>,
-    draw: &mut InsetGizmo,
+    overflow: bool,
+    draw: &mut InsetGizmo<impl DrawLines>,
 ) {
     let hue = hue_from_entity(entity);
-    let main_color = Color::hsl(hue, NODE_SATURATION, NODE_LIGHTNESS);
+    let main_color = if overflow {
+        OVERFLOW_COLOR
+    } else {
+        Color::hsl(hue, NODE_SATURATION, NODE_LIGHTNESS)
+    };
     let margin_color = Color::hsl(hue, NODE_SATURATION, MARGIN_LIGHTNESS);
 
     if flags.contains(Flag::Outlines) {
         // first draw margins, as we will draw the actual outline on top
         draw.rect_2d(rect, margin, margin_color);
-        draw.rect_2d(rect, Size::ZERO, main_color);
+        draw.rect_2d(rect, Sides::ZERO, main_color);
         draw.set_scope(rect, margin);
     }
     if flags.contains(Flag::Rules) {
@@ -328,7 +415,7 @@ impl From<LeafRule> for RuleArrow {
     fn from(value: LeafRule) -> Self {
         match value {
             LeafRule::Content(_) => Self::InwardBare,
-            LeafRule::Fixed(_) => Self::None,
+            LeafRule::Fixed(_) | LeafRule::Root(..) => Self::None,
             LeafRule::Parent(value) => Self::Outward(value),
         }
     }
@@ -336,7 +423,7 @@ impl From<LeafRule> for RuleArrow {
 impl From<Rule> for RuleArrow {
     fn from(value: Rule) -> Self {
         match value {
-            Rule::Fixed(_) => Self::None,
+            Rule::Fixed(_) | Rule::Root(..) => Self::None,
             Rule::Parent(value) => Self::Outward(value),
             Rule::Children(value) => Self::Inward(value),
         }
@@ -354,6 +441,8 @@ impl From<Rule> for RuleArrow {
 /// > and the outlines are drawn behind the UI, enable the `cuicui_layout/debug_bevy_ui`!
 ///
 /// disabling the plugin will give you back gizmo control.
+///
+/// Removing the [`Options`] resource also disables the debug overlay.
 pub struct Plugin;
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut bevy::prelude::App) {
@@ -364,14 +453,18 @@ impl BevyPlugin for Plugin {
                 update_debug_camera,
                 outline_roots.after(crate::ComputeLayoutSet),
             )
-                .chain(),
+                .chain()
+                .run_if(resource_exists::<Options>()),
         );
         app.insert_resource(Options {
             screen_space: cfg!(feature = "debug_bevy_ui"),
             ..default()
         });
     }
-    fn finish(&self, _app: &mut bevy::prelude::App) {
+    fn finish(&self, app: &mut bevy::prelude::App) {
+        if !app.world.contains_resource::<Options>() {
+            return;
+        }
         info!(
             "The cuicui_layout debug overlay is active!\n\
             ----------------------------------------------\n\
@@ -381,3 +474,81 @@ impl BevyPlugin for Plugin {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use bevy::prelude::BuildWorldChildren;
+
+    use super::*;
+
+    impl DrawLines for Vec<[Vec2; 2]> {
+        fn line_2d(&mut self, start: Vec2, end: Vec2, _: Color) {
+            self.push([start, end]);
+        }
+    }
+    /// The segments of a closed rectangle with corners `(left, top)` and
+    /// `(right, bottom)`, as drawn by `InsetGizmo::rect_2d`.
+    fn rect(left: f32, top: f32, right: f32, bottom: f32) -> Vec<[Vec2; 2]> {
+        let (lt, lb) = (Vec2::new(left, top), Vec2::new(left, bottom));
+        let (rb, rt) = (Vec2::new(right, bottom), Vec2::new(right, top));
+        vec![[lt, lb], [lb, rb], [rb, rt], [rt, lt]]
+    }
+    /// Draw the debug overlay of a 100×50 root with a single `leaf` child
+    /// at `(0, 0)` of size `leaf_size`, lines are 1 pixel wide.
+    fn outline(flags: EnumSet<Flag>, leaf: Node, leaf_size: Size<f32>) -> Vec<[Vec2; 2]> {
+        let mut world = World::new();
+        world.init_resource::<GizmoConfig>();
+        world.init_resource::<LastLayoutChange>();
+        world.insert_resource(Options { flags, show_hidden: true, ..default() });
+
+        let size = Size::new(100., 50.);
+        let root = Root::new(size, Flow::Horizontal, default(), default(), Size::ZERO);
+        let root_rect = LayoutRect { size, pos: Size::ZERO };
+        let leaf_rect = LayoutRect { size: leaf_size, pos: Size::ZERO };
+        world.spawn((root, root_rect)).with_children(|cmds| {
+            cmds.spawn((leaf, leaf_rect));
+        });
+        let mut state = SystemState::<(OutlineParam, RootQuery)>::new(&mut world);
+        let (outline, roots) = state.get(&world);
+        let mut draw = InsetGizmo::new(Vec::new(), 1.);
+        outline_all(&outline, &roots, &mut draw);
+        draw.draw
+    }
+    #[test]
+    fn outlines_are_inset() {
+        let leaf = Node::fixed(Size::new(10., 20.));
+        let lines = outline(Flag::Outlines.into(), leaf, Size::new(10., 20.));
+
+        // The root's margin outline, then its outline.
+        let mut expected = [rect(0., 0., 100., 50.), rect(0., 0., 100., 50.)].concat();
+        // The leaf's top and left sides are moved inward by a line width,
+        // so that they do not overlap the root's sides.
+        expected.extend([rect(1., 1., 10., 20.), rect(1., 1., 10., 20.)].concat());
+        assert_eq!(lines, expected);
+    }
+    #[test]
+    fn rule_arrows() {
+        let rules = Size::new(LeafRule::Parent(0.4), LeafRule::Content(20.));
+        let lines = outline(Flag::Rules.into(), Node::Box(rules), Size::new(40., 20.));
+
+        let v = Vec2::new;
+        let expected = vec![
+            // Width: outward arrows, pointing toward the left and right sides.
+            [v(5., 10.), v(0., 10.)],
+            [v(0., 10.), v(1.25, 8.75)],
+            [v(0., 10.), v(1.25, 11.25)],
+            [v(35., 10.), v(40., 10.)],
+            [v(40., 10.), v(38.75, 11.25)],
+            [v(40., 10.), v(38.75, 8.75)],
+            // Height: content-sized, inward arrows from the top and bottom sides.
+            [v(20., 0.), v(20., 2.5)],
+            [v(20., 2.5), v(19.375, 1.875)],
+            [v(20., 2.5), v(20.625, 1.875)],
+            [v(20., 20.), v(20., 17.5)],
+            [v(20., 17.5), v(20.625, 18.125)],
+            [v(20., 17.5), v(19.375, 18.125)],
+        ];
+        assert_eq!(lines, expected);
+    }
+}
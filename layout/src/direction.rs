@@ -27,6 +27,20 @@ pub struct Oriented<T> {
     pub cross: T,
 }
 
+/// A `T` that applies to each side of something.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct Sides<T> {
+    /// `T` on the left side.
+    pub left: T,
+    /// `T` on the right side.
+    pub right: T,
+    /// `T` on the top side.
+    pub top: T,
+    /// `T` on the bottom side.
+    pub bottom: T,
+}
+
 /// The layout direction of a [`Container`].
 ///
 /// [`Container`]: crate::Container
@@ -57,20 +71,84 @@ impl Flow {
             Self::Vertical => Size::new(cross, main),
         }
     }
+    /// The other axis, the cross axis of this [`Flow`].
+    pub(crate) const fn perpendicular(self) -> Self {
+        match self {
+            Self::Horizontal => Self::Vertical,
+            Self::Vertical => Self::Horizontal,
+        }
+    }
 }
 impl Size<f32> {
     /// A `Size<f32>` with 0 width and 0 height.
     pub const ZERO: Self = Self { width: 0., height: 0. };
 }
-impl ops::Add for Size<f32> {
-    type Output = Self;
-    fn add(self, other: Self) -> Self {
-        Self {
-            width: self.width + other.width,
-            height: self.height + other.height,
+
+/// Implement component-wise arithmetic for `$ty`.
+///
+/// Binary operations between two `$ty` apply to each field with the matching
+/// field of the other operand, `Mul` and `Div` by a scalar apply the scalar
+/// to each field.
+macro_rules! impl_ops {
+    ($ty:ident { $a:ident, $b:ident }) => {
+        impl<T: ops::Add<Output = T>> ops::Add for $ty<T> {
+            type Output = Self;
+            fn add(self, other: Self) -> Self {
+                $ty { $a: self.$a + other.$a, $b: self.$b + other.$b }
+            }
         }
-    }
+        impl<T: ops::Sub<Output = T>> ops::Sub for $ty<T> {
+            type Output = Self;
+            fn sub(self, other: Self) -> Self {
+                $ty { $a: self.$a - other.$a, $b: self.$b - other.$b }
+            }
+        }
+        impl<T: ops::Mul<Output = T> + Copy> ops::Mul<T> for $ty<T> {
+            type Output = Self;
+            fn mul(self, scalar: T) -> Self {
+                $ty { $a: self.$a * scalar, $b: self.$b * scalar }
+            }
+        }
+        impl<T: ops::Div<Output = T> + Copy> ops::Div<T> for $ty<T> {
+            type Output = Self;
+            fn div(self, scalar: T) -> Self {
+                $ty { $a: self.$a / scalar, $b: self.$b / scalar }
+            }
+        }
+        impl<T: ops::Neg<Output = T>> ops::Neg for $ty<T> {
+            type Output = Self;
+            fn neg(self) -> Self {
+                $ty { $a: -self.$a, $b: -self.$b }
+            }
+        }
+        impl<T: ops::AddAssign> ops::AddAssign for $ty<T> {
+            fn add_assign(&mut self, other: Self) {
+                self.$a += other.$a;
+                self.$b += other.$b;
+            }
+        }
+        impl<T: ops::SubAssign> ops::SubAssign for $ty<T> {
+            fn sub_assign(&mut self, other: Self) {
+                self.$a -= other.$a;
+                self.$b -= other.$b;
+            }
+        }
+        impl<T: ops::MulAssign + Copy> ops::MulAssign<T> for $ty<T> {
+            fn mul_assign(&mut self, scalar: T) {
+                self.$a *= scalar;
+                self.$b *= scalar;
+            }
+        }
+        impl<T: ops::DivAssign + Copy> ops::DivAssign<T> for $ty<T> {
+            fn div_assign(&mut self, scalar: T) {
+                self.$a /= scalar;
+                self.$b /= scalar;
+            }
+        }
+    };
 }
+impl_ops!(Size { width, height });
+impl_ops!(Oriented { main, cross });
 
 impl<T> Size<T> {
     /// Create a [`Size`] for given `width` and `height` `T`.
@@ -98,6 +176,51 @@ impl<T> Size<T> {
         let Self { width, height } = self;
         Size { width, height }
     }
+    /// Go from `&mut Size<T>` to `Size<&mut T>`.
+    pub fn as_mut(&mut self) -> Size<&mut T> {
+        let Self { width, height } = self;
+        Size { width, height }
+    }
+    /// The `T` on given `axis`, `width` for [`Axis::Horizontal`] and `height`
+    /// for [`Axis::Vertical`].
+    pub const fn on(&self, axis: Axis) -> &T {
+        match axis {
+            Axis::Horizontal => &self.width,
+            Axis::Vertical => &self.height,
+        }
+    }
+    /// Mutable version of [`Size::on`].
+    pub fn on_mut(&mut self, axis: Axis) -> &mut T {
+        match axis {
+            Axis::Horizontal => &mut self.width,
+            Axis::Vertical => &mut self.height,
+        }
+    }
+    /// Iterate over each axis and its `T`, `width` first, then `height`.
+    pub fn iter(&self) -> impl Iterator<Item = (Axis, &T)> {
+        [(Axis::Horizontal, &self.width), (Axis::Vertical, &self.height)].into_iter()
+    }
+    /// This `Size` relative to `flow`, same as [`Flow::relative`].
+    pub const fn relative_to(self, flow: Flow) -> Oriented<T>
+    where
+        T: Copy,
+    {
+        flow.relative(self)
+    }
+}
+impl<T: Copy + PartialOrd> Size<T> {
+    /// The component-wise minimum of `self` and `other`.
+    #[must_use]
+    pub fn min(self, other: Self) -> Self {
+        let min = |a: T, b: T| if b < a { b } else { a };
+        Self::new(min(self.width, other.width), min(self.height, other.height))
+    }
+    /// The component-wise maximum of `self` and `other`.
+    #[must_use]
+    pub fn max(self, other: Self) -> Self {
+        let max = |a: T, b: T| if b > a { b } else { a };
+        Self::new(max(self.width, other.width), max(self.height, other.height))
+    }
 }
 
 impl<T: Copy> Oriented<T> {
@@ -110,6 +233,16 @@ impl<T: Copy> Oriented<T> {
         let Self { main, cross } = self;
         Oriented { main, cross }
     }
+    /// Apply `f` on `main` and `cross`, returning an `Oriented` with the output
+    /// values of `f`.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Oriented<U> {
+        Oriented { main: f(self.main), cross: f(self.cross) }
+    }
+    /// This `Oriented` in absolute terms when relative to `flow`, same as
+    /// [`Flow::absolute`].
+    pub const fn absolute_from(self, flow: Flow) -> Size<T> {
+        flow.absolute(self)
+    }
 }
 
 impl fmt::Display for Flow {
@@ -132,6 +265,74 @@ impl FromStr for Flow {
     }
 }
 
+impl<T: Copy> Sides<T> {
+    /// Create [`Sides`] for given `left`, `right`, `top` and `bottom` `T`.
+    pub const fn new(left: T, right: T, top: T, bottom: T) -> Self {
+        Self { left, right, top, bottom }
+    }
+    /// Create [`Sides`] where all sides are set to `value`.
+    pub const fn all(value: T) -> Self {
+        Self::new(value, value, value, value)
+    }
+    /// Create [`Sides`] with `size.width` on the left and right sides, and
+    /// `size.height` on the top and bottom sides.
+    pub const fn symmetric(Size { width, height }: Size<T>) -> Self {
+        Self::new(width, width, height, height)
+    }
+    /// The `left` and `top` sides, where layout positions start.
+    pub const fn start(&self) -> Size<T> {
+        Size::new(self.left, self.top)
+    }
+    /// The `right` and `bottom` sides.
+    pub const fn end(&self) -> Size<T> {
+        Size::new(self.right, self.bottom)
+    }
+    /// Apply `f` on each side, returning `Sides` with the output values of `f`.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Sides<U> {
+        let Self { left, right, top, bottom } = self;
+        Sides { left: f(left), right: f(right), top: f(top), bottom: f(bottom) }
+    }
+}
+impl<T: Copy> Sides<Option<T>> {
+    /// The `Some` sides of `self`, and the sides of `default` where `self` is `None`.
+    pub fn unwrap_or(self, default: Sides<T>) -> Sides<T> {
+        Sides {
+            left: self.left.unwrap_or(default.left),
+            right: self.right.unwrap_or(default.right),
+            top: self.top.unwrap_or(default.top),
+            bottom: self.bottom.unwrap_or(default.bottom),
+        }
+    }
+}
+impl Sides<f32> {
+    /// `Sides<f32>` of 0 on all sides.
+    pub const ZERO: Self = Self::all(0.);
+
+    /// The sum of both sides on each axis: `left + right` as `width` and
+    /// `top + bottom` as `height`.
+    #[must_use]
+    pub fn total(&self) -> Size<f32> {
+        self.start() + self.end()
+    }
+}
+impl<T: ops::Add<Output = T>> ops::Add for Sides<T> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Sides {
+            left: self.left + other.left,
+            right: self.right + other.right,
+            top: self.top + other.top,
+            bottom: self.bottom + other.bottom,
+        }
+    }
+}
+impl<T: Copy> From<Size<T>> for Sides<T> {
+    /// Same as [`Sides::symmetric`].
+    fn from(size: Size<T>) -> Self {
+        Self::symmetric(size)
+    }
+}
+
 impl From<bevy::math::Vec2> for Size<f32> {
     fn from(value: bevy::math::Vec2) -> Self {
         Self::new(value.x, value.y)
@@ -142,11 +343,39 @@ impl From<Size<f32>> for bevy::math::Vec2 {
         Self::new(value.width, value.height)
     }
 }
+impl From<bevy::math::UVec2> for Size<f32> {
+    fn from(value: bevy::math::UVec2) -> Self {
+        value.as_vec2().into()
+    }
+}
+impl<T> From<(T, T)> for Size<T> {
+    /// `(width, height)` to a `Size`.
+    fn from((width, height): (T, T)) -> Self {
+        Self { width, height }
+    }
+}
+impl<T> From<Size<T>> for (T, T) {
+    /// A `Size` to `(width, height)`.
+    fn from(Size { width, height }: Size<T>) -> Self {
+        (width, height)
+    }
+}
 impl<T: fmt::Display> fmt::Display for Size<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}×{}", self.width, self.height)
     }
 }
+impl<T: fmt::Display + PartialEq> fmt::Display for Sides<T> {
+    /// `width×height` when symmetric, `left right top bottom` otherwise.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { left, right, top, bottom } = self;
+        if left == right && top == bottom {
+            write!(f, "{left}×{top}")
+        } else {
+            write!(f, "{left} {right} {top} {bottom}")
+        }
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,4 +400,43 @@ mod tests {
         assert_eq!(width_v, height_h);
         assert_eq!(width_h, height_v);
     }
+    #[test]
+    fn size_math() {
+        let mut size = Size::new(10., 4.);
+        assert_eq!(size + Size::all(1.), Size::new(11., 5.));
+        assert_eq!(size - Size::new(10., 5.), Size::new(0., -1.));
+        assert_eq!(size * 2., Size::new(20., 8.));
+        assert_eq!(size / 2., Size::new(5., 2.));
+        assert_eq!(-size, Size::new(-10., -4.));
+        assert_eq!(size.min(Size::new(5., 5.)), Size::new(5., 4.));
+        assert_eq!(size.max(Size::new(5., 5.)), Size::new(10., 5.));
+
+        size *= 3.;
+        size -= Size::all(10.);
+        assert_eq!(size, Size::new(20., 2.));
+
+        *size.on_mut(Axis::Vertical) = 7.;
+        assert_eq!(size.on(Axis::Vertical), &7.);
+        let axes: Vec<_> = size.iter().collect();
+        assert_eq!(axes, [(Axis::Horizontal, &20.), (Axis::Vertical, &7.)]);
+
+        let oriented = size.relative_to(Flow::Vertical);
+        assert_eq!(oriented, Oriented::new(7., 20.));
+        assert_eq!(oriented.absolute_from(Flow::Vertical), size);
+        assert_eq!(oriented + Oriented::new(1., 1.), Oriented::new(8., 21.));
+    }
+    #[test]
+    fn sides() {
+        let sides = Sides::new(1., 2., 3., 4.);
+        assert_eq!(sides.start(), Size::new(1., 3.));
+        assert_eq!(sides.end(), Size::new(2., 4.));
+        assert_eq!(sides.total(), Size::new(3., 7.));
+        assert_eq!(sides + Sides::all(1.), Sides::new(2., 3., 4., 5.));
+        assert_eq!(Sides::from(Size::new(5., 6.)), Sides::new(5., 5., 6., 6.));
+
+        let overrides = Sides::new(None, Some(0.), None, None);
+        assert_eq!(overrides.unwrap_or(sides), Sides::new(1., 0., 3., 4.));
+        assert_eq!(sides.to_string(), "1 2 3 4");
+        assert_eq!(Sides::symmetric(Size::new(5, 6)).to_string(), "5×6");
+    }
 }
@@ -3,13 +3,21 @@
 use std::{fmt, mem};
 
 use bevy::log::error;
-use bevy::prelude::{Bundle, Deref, DerefMut};
-use cuicui_dsl::{BaseDsl, DslBundle, EntityCommands};
+use bevy::prelude::{default, Bundle, Deref, DerefMut};
+use cuicui_dsl::{dsl_trait, BaseDsl, DslBundle, EntityCommands};
+use thiserror::Error;
 
+use crate::attach::AttachmentPoint;
 use crate::bundles::{Layout, LayoutBundle, RootBundle};
-use crate::{Alignment, Distribution, Flow, LeafRule, Node, Oriented, Rule};
+use crate::content_sized::{ContentSizeFactor, ImageScale, PlaceholderSize};
+use crate::states::{NodeState, StateNodes};
+use crate::{Alignment, Anchor, Anchored, CellSpan, Distribution, Flow, LeafRule, Node};
+use crate::{Oriented, RootUnit, Rule, Sides, Size, SizeBounds};
 #[cfg(doc)]
-use crate::{Container, Root, ScreenRoot};
+use crate::{content_sized::DefaultImageScale, Container, Root, ScreenRoot};
+
+/// Inserts the components of a [`LayoutDsl::ui`] node, see [`IntoUiBundle::into_insert_fn`].
+pub type InsertFn = Box<dyn FnOnce(&mut EntityCommands)>;
 
 /// Something that can be converted into a bevy [`Bundle`].
 ///
@@ -19,6 +27,39 @@ use crate::{Container, Root, ScreenRoot};
 /// `Marker` is completely ignored. It only exists to make it easier for
 /// consumers of the API to extend the DSL with their own bundle.
 ///
+/// Out of the box, `IntoUiBundle` is implemented for:
+///
+/// - Tuples of up to 8 [`Bundle`]s, they are inserted as is.
+/// - Closures taking a `&mut EntityCommands`, they are called with the
+///   [`EntityCommands`] of the node, after its layout components are inserted.
+///   Use them to insert components conditionally or spawn children.
+///
+/// Such nodes are leaf nodes. Like other `ui` nodes, the axes without set rules
+/// are [content-sized](crate::content_sized). Nodes without content are
+/// zero-sized, so give them a size with `width`, `height` or `rules`.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// use cuicui_dsl::EntityCommands;
+/// use cuicui_layout::{LayoutDsl, dsl};
+/// use cuicui_layout::dsl_functions::{pct, px};
+///
+/// # #[derive(Component)] struct Health;
+/// fn setup(mut cmds: Commands) {
+///     dsl! {
+///         <LayoutDsl> &mut cmds.spawn_empty(),
+///         Entity(row) {
+///             Entity(ui((Health, Name::new("Health bar"))) rules(px(50), pct(100)))
+///             Entity(ui(|cmds: &mut EntityCommands| {
+///                 cmds.insert(Health).with_children(|cmds| {
+///                     cmds.spawn(Name::new("Health icon"));
+///                 });
+///             }) rules(px(50), px(20)))
+///         }
+///     };
+/// }
+/// ```
+///
 /// # Example
 ///
 /// ```
@@ -63,12 +104,182 @@ pub trait IntoUiBundle<Marker> {
     /// Since `Target` is inserted _after_ the [`Node`] component, you can
     /// overwrite it by including it in the bundle.
     fn into_ui_bundle(self) -> Self::Target;
+
+    /// The function [`LayoutDsl::ui`] calls to insert `self` into the node.
+    ///
+    /// By default, it inserts [`Self::into_ui_bundle`].
+    fn into_insert_fn(self) -> InsertFn
+    where
+        Self: Sized,
+    {
+        let ui_bundle = self.into_ui_bundle();
+        Box::new(move |cmds| {
+            cmds.insert(ui_bundle);
+        })
+    }
 }
 impl IntoUiBundle<()> for () {
     type Target = ();
     fn into_ui_bundle(self) {}
 }
 
+/// The [`IntoUiBundle`] marker for tuples of [`Bundle`]s.
+pub enum Bundles {}
+
+macro_rules! impl_bundle_tuples {
+    ($( ($($bundle:ident),*) ),* $(,)?) => {$(
+        impl<$($bundle: Bundle),*> IntoUiBundle<Bundles> for ($($bundle,)*) {
+            type Target = Self;
+            fn into_ui_bundle(self) -> Self {
+                self
+            }
+        }
+    )*};
+}
+impl_bundle_tuples![
+    (B0),
+    (B0, B1),
+    (B0, B1, B2),
+    (B0, B1, B2, B3),
+    (B0, B1, B2, B3, B4),
+    (B0, B1, B2, B3, B4, B5),
+    (B0, B1, B2, B3, B4, B5, B6),
+    (B0, B1, B2, B3, B4, B5, B6, B7),
+];
+
+/// The [`IntoUiBundle`] marker for closures taking a `&mut EntityCommands`.
+pub enum WithCommands {}
+
+/// Note that [`Self::into_ui_bundle`] does nothing, since the closure
+/// needs the [`EntityCommands`] of the node, only [`Self::into_insert_fn`] calls it.
+/// `IntoUiBundle` implementations delegating to another type should delegate
+/// `into_insert_fn` as well.
+impl<F: FnOnce(&mut EntityCommands) + 'static> IntoUiBundle<WithCommands> for F {
+    type Target = ();
+    fn into_ui_bundle(self) {}
+    fn into_insert_fn(self) -> InsertFn {
+        Box::new(self)
+    }
+}
+
+/// [`LayoutDsl`] methods setting container rules that can't work together,
+/// see [`LayoutDsl::rule_conflict`].
+#[derive(Debug, Error, Clone, Copy, PartialEq)]
+#[allow(missing_docs)] // Error messages already good documentation.
+pub enum RuleConflict {
+    #[error(
+        "`{}` has no effect on this container: its `{axis}` is `child({ratio})`, \
+        exactly the size of its children, so there is no space left to distribute. \
+        Use `distrib_start`, or a `child` ratio larger than 1.",
+        distrib_method(*.distrib)
+    )]
+    NoFreeSpace { distrib: Distribution, axis: Flow, ratio: f32 },
+    #[error(
+        "This container's `{axis}` is `child({ratio})`, smaller than its children, \
+        they always overflow it. Use a `child` ratio of at least 1."
+    )]
+    SmallerThanChildren { axis: Flow, ratio: f32 },
+}
+const fn distrib_method(distrib: Distribution) -> &'static str {
+    match distrib {
+        Distribution::End => "distrib_end",
+        _ => "fill_main_axis",
+    }
+}
+impl RuleConflict {
+    /// The [`LayoutDsl`] methods that may have caused this conflict.
+    #[must_use]
+    pub const fn methods(&self) -> &'static [&'static str] {
+        use Distribution::End;
+        use Flow::{Horizontal as Width, Vertical as Height};
+
+        match *self {
+            Self::NoFreeSpace { distrib: End, axis: Width, .. } => {
+                &["distrib_end", "layout", "width", "rules"]
+            }
+            Self::NoFreeSpace { distrib: End, axis: Height, .. } => {
+                &["distrib_end", "layout", "height", "rules"]
+            }
+            Self::NoFreeSpace { axis: Width, .. } => {
+                &["fill_main_axis", "layout", "width", "rules"]
+            }
+            Self::NoFreeSpace { axis: Height, .. } => {
+                &["fill_main_axis", "layout", "height", "rules"]
+            }
+            Self::SmallerThanChildren { axis: Width, .. } => &["width", "rules"],
+            Self::SmallerThanChildren { axis: Height, .. } => &["height", "rules"],
+        }
+    }
+}
+#[cfg(feature = "chirp")]
+impl From<RuleConflict> for cuicui_chirp::MethodConflict {
+    fn from(conflict: RuleConflict) -> Self {
+        Self { methods: conflict.methods(), error: conflict.into() }
+    }
+}
+
+/// Layout methods called on a node's [`LayoutDsl`] to compute its [`Node`]
+/// in a given [`NodeState`], see [`LayoutDsl::hover`].
+///
+/// In chirp files, this is a list of [`LayoutDsl`] methods, such as
+/// `width(220px) margin(10)`. Methods of other DSLs, such as `bg` in
+/// `UiDsl`, are not supported.
+pub struct StateMethods(Box<dyn FnOnce(&mut LayoutDsl)>);
+impl StateMethods {
+    /// Call `methods` on a copy of the [`LayoutDsl`] of the node.
+    pub fn new(methods: impl FnOnce(&mut LayoutDsl) + 'static) -> Self {
+        Self(Box::new(methods))
+    }
+}
+impl fmt::Debug for StateMethods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StateMethods([FnOnce(&mut LayoutDsl)])")
+    }
+}
+
+/// Error occuring when parsing [`StateMethods`] in chirp files.
+#[cfg(feature = "chirp")]
+#[derive(Debug, Error)]
+#[allow(missing_docs)] // Error messages already good documentation.
+pub enum StateMethodsError {
+    #[error(transparent)]
+    MethodCalls(#[from] cuicui_chirp::parse_dsl::args::MethodCallsError),
+    #[error("In '{0}': {1}")]
+    Method(String, String),
+}
+#[cfg(feature = "chirp")]
+fn call_methods(dsl: &mut LayoutDsl, input: &str) -> Result<(), StateMethodsError> {
+    use bevy::reflect::TypeRegistry;
+    use cuicui_chirp::{parse_dsl::args::method_calls, MethodCtx, ParseDsl};
+
+    let registry = TypeRegistry::default();
+    for call in method_calls(input)? {
+        let ctx = MethodCtx {
+            name: call.name,
+            arguments: call.arguments(),
+            ctx: None,
+            registry: &registry,
+        };
+        let method_error = |err| StateMethodsError::Method(call.name.to_owned(), format!("{err}"));
+        dsl.method(ctx).map_err(method_error)?;
+    }
+    Ok(())
+}
+/// Parse a list of [`LayoutDsl`] methods, checking that they can be called.
+#[cfg(feature = "chirp")]
+impl std::str::FromStr for StateMethods {
+    type Err = StateMethodsError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        call_methods(&mut LayoutDsl::default(), input)?;
+        let input = input.to_owned();
+        Ok(Self::new(move |dsl| {
+            // Errors were already reported, when checking the methods above.
+            let _ = call_methods(dsl, &input);
+        }))
+    }
+}
+
 #[derive(Default, Debug)]
 enum RootKind {
     ScreenRoot,
@@ -98,27 +309,66 @@ pub struct LayoutDsl<T = BaseDsl> {
     layout: Layout,
     // TODO(clean): Shouldn't layout.flow: Option<Flow> instead?
     set_flow: bool,
-    ui_bundle: Option<Box<dyn FnOnce(&mut EntityCommands)>>,
+    ui_bundle: Option<InsertFn>,
     layout_bundle: Option<LayoutBundle>,
+    image_scale: Option<f32>,
+    placeholder_size: Option<Size<f32>>,
+    content_factor: Option<ContentSizeFactor>,
+    cell_span: Option<CellSpan>,
+    anchored: Option<Anchored>,
+    attach: Option<AttachmentPoint>,
+    bounds: Option<SizeBounds>,
+    hover: Option<StateMethods>,
+    press: Option<StateMethods>,
+    focus: Option<StateMethods>,
+    #[cfg(feature = "show_if")]
+    show_if: Option<String>,
+    #[cfg(feature = "sort_children")]
+    sort_children: Option<crate::sort_children::SortChildren>,
 }
 impl<D: fmt::Debug> fmt::Debug for LayoutDsl<D> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let has_bundle = self.ui_bundle.is_some();
         let ui_bundle = if has_bundle { "Some([FnOnce(EntityCommands)])" } else { "None" };
-        f.debug_struct("LayoutDsl")
+        let mut debug = f.debug_struct("LayoutDsl");
+        debug
             .field("inner", &self.inner)
             .field("root", &self.root)
             .field("layout", &self.layout)
             .field("set_flow", &self.set_flow)
             .field("ui_bundle", &ui_bundle)
             .field("layout_bundle", &self.layout_bundle)
-            .finish()
+            .field("image_scale", &self.image_scale)
+            .field("placeholder_size", &self.placeholder_size)
+            .field("content_factor", &self.content_factor)
+            .field("cell_span", &self.cell_span)
+            .field("anchored", &self.anchored)
+            .field("attach", &self.attach)
+            .field("bounds", &self.bounds)
+            .field("hover", &self.hover)
+            .field("press", &self.press)
+            .field("focus", &self.focus);
+        #[cfg(feature = "show_if")]
+        debug.field("show_if", &self.show_if);
+        #[cfg(feature = "sort_children")]
+        debug.field("sort_children", &self.sort_children);
+        debug.finish()
     }
 }
 
+#[dsl_trait]
 #[cfg_attr(
     feature = "chirp",
-    cuicui_chirp::parse_dsl_impl(delegate = inner, type_parsers(Rule = args::from_str))
+    cuicui_chirp::parse_dsl_impl(
+        namespace = layout,
+        delegate = inner,
+        conflicts = Self::rule_conflict,
+        type_parsers(
+            Rule = args::from_str,
+            Anchor = args::from_str,
+            StateMethods = args::from_str,
+        ),
+    )
 )]
 impl<D: DslBundle> LayoutDsl<D> {
     /// Set the flow direction of a container node.
@@ -155,25 +405,39 @@ impl<D: DslBundle> LayoutDsl<D> {
     pub fn fill_main_axis(&mut self) {
         self.layout.distrib = Distribution::FillMain;
     }
+    /// Spawn this [`Node`] as a [`Container`] where all children overlap,
+    /// centered on top of each other, see [`Distribution::OverlapCenter`].
+    ///
+    /// Use it to layer nodes, such as a background image and its content. A
+    /// [`Rule::Children`] axis is the size of the largest child, and children
+    /// with a [`Rule::Parent`] fill the size given by the other children.
+    ///
+    /// Use [`Self::layout`] to overlap children at the start or end of the
+    /// main axis.
+    pub fn overlapping(&mut self) {
+        self.set_flow = true;
+        self.layout.distrib = Distribution::OverlapCenter;
+    }
 
     /// Set properties based on the given `spec`.
     ///
     /// `spec` specifies the [flow][Self::flow], (d)istribution
-    /// and (a)lignment of the container in the form: `[v>]d[SEC]a[SEC]`.
+    /// and (a)lignment of the container in the form: `[v>][do][SEC]a[SEC]`.
     ///
-    /// legal values are: `S`tart, `E`nd or `C`enter.
+    /// legal values are: `S`tart, `E`nd or `C`enter. Use `o` rather than `d`
+    /// for [overlapping](Self::overlapping) children.
     ///
     /// An error is logged on illegal values.
     pub fn layout(&mut self, spec: &str) {
         let correct_len = spec.len() == 5;
         if !correct_len {
-            error!("'layout' method accpets '[v>]d[SEC]a[SEC]', got '{spec}'");
+            error!("'layout' method accpets '[v>][do][SEC]a[SEC]', got '{spec}'");
             return;
         };
         let (Ok(flow), Ok(distrib), Ok(align)) =
             (spec[0..1].parse(), spec[1..3].parse(), spec[3..5].parse())
         else {
-            error!("'layout' method accpets '[v>]d[SEC]a[SEC]', got '{spec}'");
+            error!("'layout' method accpets '[v>][do][SEC]a[SEC]', got '{spec}'");
             return;
         };
         self.set_flow = true;
@@ -181,6 +445,35 @@ impl<D: DslBundle> LayoutDsl<D> {
         self.layout.distrib = distrib;
         self.layout.align = align;
     }
+    /// Check that the rules and distribution of this container work together.
+    ///
+    /// This is checked when spawning the container: an error is logged with
+    /// the `dsl!` macro, and chirp files fail to load, pointing at the method
+    /// causing the conflict.
+    #[must_use]
+    pub fn rule_conflict(&self) -> Option<RuleConflict> {
+        let Layout { flow, distrib, size, aspect, grid, .. } = self.layout;
+        // Media boxes compute `Rule::Children` axes from the other axis.
+        if !self.set_flow || aspect.is_some() {
+            return None;
+        }
+        let smaller = size.iter().find_map(|(axis, rule)| match *rule {
+            Some(Rule::Children(ratio)) if ratio < 1. => {
+                Some(RuleConflict::SmallerThanChildren { axis, ratio })
+            }
+            _ => None,
+        });
+        // Grids ignore `distrib`.
+        let no_space =
+            grid.is_none() && matches!(distrib, Distribution::FillMain | Distribution::End);
+        let no_free_space = match *size.on(flow) {
+            Some(Rule::Children(ratio)) if no_space && ratio <= 1. => {
+                Some(RuleConflict::NoFreeSpace { distrib, axis: flow, ratio })
+            }
+            _ => None,
+        };
+        smaller.or(no_free_space)
+    }
     /// Set both the [cross][Self::cross_margin] and [main][Self::main_margin]
     /// margins.
     pub fn margins(&mut self, main: f32, cross: f32) {
@@ -201,6 +494,34 @@ impl<D: DslBundle> LayoutDsl<D> {
     pub fn cross_margin(&mut self, pixels: f32) {
         self.layout.margin.cross = pixels;
     }
+    /// Set this [`Container`]'s margin on the left side.
+    ///
+    /// Margins set on a single side override [`Self::margin`], [`Self::margins`],
+    /// [`Self::main_margin`] and [`Self::cross_margin`] on that side, whatever
+    /// the order they are called in.
+    pub fn margin_left(&mut self, pixels: f32) {
+        self.layout.margin_sides.left = Some(pixels);
+    }
+    /// Set this [`Container`]'s margin on the right side,
+    /// see [`Self::margin_left`].
+    pub fn margin_right(&mut self, pixels: f32) {
+        self.layout.margin_sides.right = Some(pixels);
+    }
+    /// Set this [`Container`]'s margin on the top side,
+    /// see [`Self::margin_left`].
+    pub fn margin_top(&mut self, pixels: f32) {
+        self.layout.margin_sides.top = Some(pixels);
+    }
+    /// Set this [`Container`]'s margin on the bottom side,
+    /// see [`Self::margin_left`].
+    pub fn margin_bottom(&mut self, pixels: f32) {
+        self.layout.margin_sides.bottom = Some(pixels);
+    }
+    /// Set this [`Container`]'s [padding](Container::padding) on each side,
+    /// added to its margin.
+    pub fn padding(&mut self, left: f32, right: f32, top: f32, bottom: f32) {
+        self.layout.padding = Sides::new(left, right, top, bottom);
+    }
     /// Set both [width](Self::width) and [height](Self::height) rules.
     pub fn rules(&mut self, width: Rule, height: Rule) {
         self.width(width);
@@ -215,6 +536,36 @@ impl<D: DslBundle> LayoutDsl<D> {
         self.layout.size.height = Some(rule);
     }
 
+    /// Set the minimum width of this [`Node`], it is never narrower than
+    /// `rule`, whatever its width rule.
+    ///
+    /// `rule` is a pixel or percent amount, such as `px(200)` or `pct(20)`.
+    /// See [`SizeBounds`].
+    pub fn min_width(&mut self, rule: Rule) {
+        if let Some(bounds) = self.bounds("min_width", rule) {
+            bounds.min.width = Some(rule);
+        }
+    }
+    /// Set the maximum width of this [`Node`], it is never wider than `rule`,
+    /// whatever its width rule. See [`Self::min_width`].
+    pub fn max_width(&mut self, rule: Rule) {
+        if let Some(bounds) = self.bounds("max_width", rule) {
+            bounds.max.width = Some(rule);
+        }
+    }
+    /// Set the minimum height of this [`Node`], see [`Self::min_width`].
+    pub fn min_height(&mut self, rule: Rule) {
+        if let Some(bounds) = self.bounds("min_height", rule) {
+            bounds.min.height = Some(rule);
+        }
+    }
+    /// Set the maximum height of this [`Node`], see [`Self::min_width`].
+    pub fn max_height(&mut self, rule: Rule) {
+        if let Some(bounds) = self.bounds("max_height", rule) {
+            bounds.max.height = Some(rule);
+        }
+    }
+
     /// Use [`Alignment::Start`] for this [`Node`], the default is [`Alignment::Center`].
     pub fn align_start(&mut self) {
         self.layout.align = Alignment::Start;
@@ -224,6 +575,123 @@ impl<D: DslBundle> LayoutDsl<D> {
         self.layout.align = Alignment::End;
     }
 
+    /// Spawn this [`Node`] as a media box [`Container`], centering and
+    /// letterboxing its single child at the `ratio` aspect ratio (width / height).
+    ///
+    /// Axis without set rules fill the parent. Use `width(pct(100))
+    /// height(pct(100))` on the child so that it fills the letterboxed area.
+    ///
+    /// See [`Container::aspect`] for details. An error is logged if `ratio`
+    /// is not positive.
+    pub fn media_box(&mut self, ratio: f32) {
+        if ratio <= 0. || !ratio.is_finite() {
+            error!("'media_box' expects a positive aspect ratio, got '{ratio}'");
+            return;
+        }
+        self.set_flow = true;
+        self.layout.aspect = Some(ratio);
+        self.layout.distrib = Distribution::FillMain;
+        self.layout.align = Alignment::Center;
+        let size = &mut self.layout.size;
+        size.width.get_or_insert(Rule::Parent(1.));
+        size.height.get_or_insert(Rule::Parent(1.));
+    }
+
+    /// Spawn this [`Node`] as a grid [`Container`] of `columns` cells per line.
+    ///
+    /// Lines follow the [flow][Self::flow] direction: with [`Self::row`] (the
+    /// default) children fill a line of `columns` cells left to right, then
+    /// the next line below it. Use [`Self::cell_span`] on children to take
+    /// several cells of a line, or several lines.
+    ///
+    /// See [`Container::grid`] for details. An error is logged if `columns`
+    /// is zero.
+    pub fn grid(&mut self, columns: u32) {
+        if columns == 0 {
+            error!("'grid' expects at least one column, got '0'");
+            return;
+        }
+        self.set_flow = true;
+        self.layout.grid = Some(columns);
+    }
+    /// Take `main` cells of its line, on `cross` lines, when this node is in
+    /// a [grid](Self::grid). In a row grid, this is `cell_span(columns, rows)`.
+    ///
+    /// See [`CellSpan`]. An error is logged if a span is zero.
+    pub fn cell_span(&mut self, main: u32, cross: u32) {
+        if main == 0 || cross == 0 {
+            error!("'cell_span' expects spans of at least one cell, got '{main}, {cross}'");
+            return;
+        }
+        self.cell_span = Some(CellSpan::new(main, cross));
+    }
+    /// Take this node out of its parent's flow, and place it at `anchor`
+    /// of its parent.
+    ///
+    /// In chirp files, `anchor` is the name of an [`Anchor`] variant, such
+    /// as `anchor(BottomRight)`. See [`Anchored`] for details.
+    pub fn anchor(&mut self, anchor: Anchor) {
+        self.anchored.get_or_insert_with(Anchored::default).anchor = anchor;
+    }
+    /// Move this node by `x` and `y` pixels from its [anchor](Self::anchor),
+    /// positive values move it right and down.
+    ///
+    /// This also takes the node out of its parent's flow, anchored to the
+    /// top left of its parent if [`Self::anchor`] isn't called.
+    pub fn offset(&mut self, x: f32, y: f32) {
+        self.anchored.get_or_insert_with(Anchored::default).offset = Size::new(x, y);
+    }
+    /// Add a point called `name` at `anchor` of this node, to get its
+    /// position after layout with [`AttachmentPoints`].
+    ///
+    /// In chirp files, `anchor` is the name of an [`Anchor`] variant, such
+    /// as `attach(gem_socket, Center)`. See [`AttachmentPoint`] for details.
+    ///
+    /// [`AttachmentPoints`]: crate::attach::AttachmentPoints
+    pub fn attach(&mut self, name: &str, anchor: Anchor) {
+        self.attach = Some(AttachmentPoint::new(name, anchor));
+    }
+    /// Spawn this [`Node`] as a [`Container`] where children that do not
+    /// fit on the main axis flow onto a new line, rather than overflowing.
+    ///
+    /// With [`Self::row`] (the default), children that do not fit in the
+    /// container's width start a new line below the previous ones. The main
+    /// axis [rule](Self::rules) should not depend on children, otherwise
+    /// children never wrap.
+    ///
+    /// See [`Container::wrap`] for details.
+    pub fn wrap(&mut self) {
+        self.set_flow = true;
+        self.layout.wrap = true;
+    }
+
+    /// Use the [`Node`] resulting from calling `methods` on this node while
+    /// it is hovered, for example to make a button slightly bigger.
+    ///
+    /// `methods` are called after all the other methods of this node, on
+    /// a copy of its `LayoutDsl`. Only methods changing the node's [`Node`]
+    /// have an effect, such as `width`, `rules`, `margin` or `align_start`.
+    ///
+    /// In chirp files, `methods` is a list of [`LayoutDsl`] methods, such as
+    /// `hover(width(220px) margin(10))`. With the `dsl!` macro, use
+    /// [`StateMethods::new`].
+    ///
+    /// See the [`states`](crate::states) module for details. An error is
+    /// logged if this node is a root.
+    pub fn hover(&mut self, methods: StateMethods) {
+        self.hover = Some(methods);
+    }
+    /// Use the [`Node`] resulting from calling `methods` on this node while
+    /// it is pressed, see [`Self::hover`].
+    pub fn press(&mut self, methods: StateMethods) {
+        self.press = Some(methods);
+    }
+    /// Use the [`Node`] resulting from calling `methods` on this node while
+    /// it is focused, see [`Self::hover`].
+    pub fn focus(&mut self, methods: StateMethods) {
+        self.focus = Some(methods);
+    }
+
     /// Set this node as the [`ScreenRoot`], its size will follow that of the
     /// [`LayoutRootCamera`] camera.
     ///
@@ -260,20 +728,133 @@ impl<D: DslBundle> LayoutDsl<D> {
         });
         self.layout_bundle = Some(LayoutBundle { node, ..Default::default() });
     }
+    /// Spawn an empty [`Node::Axis`] with the `main` axis set to `rule`
+    /// and the `cross` axis to 0.
+    ///
+    /// Use it with the `_` anonymous statement to add spacing between nodes:
+    /// `_(spacer(pct(10)))` in the `dsl!` macro, `_(spacer(10%))` in chirp files.
+    ///
+    /// Note that [`Rule::Children`] is meaningless for a spacer, it is treated as 0.
+    pub fn spacer(&mut self, rule: Rule) {
+        let node = Node::Axis(Oriented {
+            main: LeafRule::from_rule(Some(rule)),
+            cross: LeafRule::Fixed(0.0),
+        });
+        self.layout_bundle = Some(LayoutBundle { node, ..Default::default() });
+    }
+    /// Measure the image of this content-sized node as if it had `scale`
+    /// texture pixels per logical pixel, use `2.0` for 2x density art.
+    ///
+    /// This overrides the [`DefaultImageScale`] resource for this node.
+    pub fn image_scale(&mut self, scale: f32) {
+        self.image_scale = Some(scale);
+    }
+    /// Size this content-sized node `width`×`height` pixels until its content
+    /// reports a size, for example while its image or font is loading.
+    ///
+    /// See [`PlaceholderSize`].
+    pub fn placeholder_size(&mut self, width: f32, height: f32) {
+        self.placeholder_size = Some(Size::new(width, height));
+    }
+    /// Size this content-sized node `width` times its content's width and
+    /// `height` times its content's height.
+    ///
+    /// See [`ContentSizeFactor`].
+    pub fn content_sized(&mut self, width: f32, height: f32) {
+        let factor = self.content_factor.get_or_insert_with(ContentSizeFactor::default);
+        factor.factor = Size::new(width, height);
+    }
+    /// Add `pad` to the width and height of this content-sized node, after
+    /// [`Self::content_sized`] factors. `pad` is a pixel amount, such as `px(8)`.
+    ///
+    /// See [`ContentSizeFactor`].
+    pub fn content_pad(&mut self, pad: Rule) {
+        let Rule::Fixed(pixels) = pad else {
+            error!("'content_pad' expects a pixel amount such as 'px(8)', got '{pad:?}'");
+            return;
+        };
+        let factor = self.content_factor.get_or_insert_with(ContentSizeFactor::default);
+        factor.pad = Size::all(pixels);
+    }
+    /// Show this node only when the `flag` of the [`UiFlags`] resource is `true`.
+    ///
+    /// See [`ShowIf`].
+    ///
+    /// [`ShowIf`]: crate::show_if::ShowIf
+    /// [`UiFlags`]: crate::show_if::UiFlags
+    #[cfg(feature = "show_if")]
+    pub fn show_if(&mut self, flag: &str) {
+        self.show_if = Some(flag.to_owned());
+    }
+    /// Sort the children of this node by the value at `path` of one of their
+    /// components, such as `Score.0`, smallest first if `ascending`.
+    ///
+    /// See [`SortChildren`].
+    ///
+    /// [`SortChildren`]: crate::sort_children::SortChildren
+    #[cfg(feature = "sort_children")]
+    pub fn sort_children_by(&mut self, path: &str, ascending: bool) {
+        let path = path.to_owned();
+        self.sort_children = Some(crate::sort_children::SortChildren { path, ascending });
+    }
     /// Spawn `ui_bundle`.
     ///
     /// Note that axis without set rules or [`Rule::Children`]
     /// are considered [content-sized](crate::content_sized::ComputeContentSize).
     #[cfg_attr(feature = "chirp", parse_dsl(ignore))]
+    ///
+    /// `ui_bundle` can also be a tuple of bundles or a closure taking
+    /// a `&mut EntityCommands`, see [`IntoUiBundle`].
     pub fn ui<M>(&mut self, ui_bundle: impl IntoUiBundle<M>) {
-        let ui_bundle = ui_bundle.into_ui_bundle();
-        self.ui_bundle = Some(Box::new(move |cmds| {
-            cmds.insert(ui_bundle);
-        }));
+        self.ui_bundle = Some(ui_bundle.into_insert_fn());
+    }
+}
+impl<D> LayoutDsl<D> {
+    /// The [`SizeBounds`] of this node, `None` and an error is logged if
+    /// `rule` can't be used as a bound.
+    fn bounds(&mut self, method: &str, rule: Rule) -> Option<&mut SizeBounds> {
+        if let Rule::Children(_) = rule {
+            let expected = "a pixel or percent amount such as 'px(200)'";
+            error!("'{method}' expects {expected}, got '{rule:?}'");
+            return None;
+        }
+        Some(self.bounds.get_or_insert_with(SizeBounds::default))
+    }
+    fn node(&self) -> Node {
+        match (&self.layout_bundle, self.set_flow) {
+            (Some(bundle), _) => bundle.node,
+            (None, true) => Node::Container(self.layout.container()),
+            (None, false) => Node::Box(self.layout.size.map(LeafRule::from_rule)),
+        }
+    }
+    fn state_node(&self, methods: StateMethods) -> Node {
+        let mut dsl = LayoutDsl { layout: self.layout, set_flow: self.set_flow, ..default() };
+        (methods.0)(&mut dsl);
+        dsl.node()
+    }
+    fn insert_state_nodes(&mut self, cmds: &mut EntityCommands) {
+        let (hover, press, focus) = (self.hover.take(), self.press.take(), self.focus.take());
+        if hover.is_none() && press.is_none() && focus.is_none() {
+            return;
+        }
+        if !matches!(self.root, RootKind::None) {
+            error!("'hover', 'press' and 'focus' have no effect on roots");
+            return;
+        }
+        let state_nodes = StateNodes {
+            idle: self.node(),
+            hovered: hover.map(|methods| self.state_node(methods)),
+            pressed: press.map(|methods| self.state_node(methods)),
+            focused: focus.map(|methods| self.state_node(methods)),
+        };
+        cmds.insert((state_nodes, NodeState::default()));
     }
 }
 impl<D: DslBundle> DslBundle for LayoutDsl<D> {
     fn insert(&mut self, cmds: &mut EntityCommands) {
+        if let Some(conflict) = self.rule_conflict() {
+            error!("{conflict}");
+        }
         if self.set_flow {
             let container = self.layout.container();
             let root_bundle = || RootBundle::new(self.layout);
@@ -290,6 +871,7 @@ impl<D: DslBundle> DslBundle for LayoutDsl<D> {
             let size = self.layout.size.map(LeafRule::from_rule);
             cmds.insert(LayoutBundle::boxy(size));
         }
+        self.insert_state_nodes(cmds);
         if let Some(layout) = mem::take(&mut self.layout_bundle) {
             cmds.insert(layout);
         }
@@ -298,6 +880,35 @@ impl<D: DslBundle> DslBundle for LayoutDsl<D> {
             cmds.insert(LayoutBundle::boxy(size));
             ui_bundle_fn(cmds);
         }
+        if let Some(scale) = self.image_scale {
+            cmds.insert(ImageScale(scale));
+        }
+        if let Some(size) = self.placeholder_size {
+            cmds.insert(PlaceholderSize(size));
+        }
+        if let Some(factor) = self.content_factor.take() {
+            cmds.insert(factor);
+        }
+        if let Some(span) = self.cell_span {
+            cmds.insert(span);
+        }
+        if let Some(anchored) = self.anchored {
+            cmds.insert(anchored);
+        }
+        if let Some(point) = self.attach.take() {
+            cmds.insert(point);
+        }
+        if let Some(bounds) = self.bounds {
+            cmds.insert(bounds);
+        }
+        #[cfg(feature = "show_if")]
+        if let Some(flag) = self.show_if.take() {
+            cmds.insert(crate::show_if::ShowIf(flag));
+        }
+        #[cfg(feature = "sort_children")]
+        if let Some(sort) = self.sort_children.take() {
+            cmds.insert(sort);
+        }
         self.inner.insert(cmds);
     }
 }
@@ -316,6 +927,25 @@ pub fn pct(percent: u8) -> Rule {
     assert!(percent <= 100);
     Rule::Parent(f32::from(percent) / 100.0)
 }
+/// Returns [`Rule::Root`] as `percent` percent of the smallest axis of the
+/// node's [`Root`], like the CSS `vmin` unit.
+#[must_use]
+pub fn vmin(percent: f32) -> Rule {
+    Rule::Root(percent / 100., RootUnit::Vmin)
+}
+/// Returns [`Rule::Root`] as `percent` percent of the largest axis of the
+/// node's [`Root`], like the CSS `vmax` unit.
+#[must_use]
+pub fn vmax(percent: f32) -> Rule {
+    Rule::Root(percent / 100., RootUnit::Vmax)
+}
+/// Returns [`Rule::Root`] as `ems` times the [`RootFontSize`].
+///
+/// [`RootFontSize`]: crate::RootFontSize
+#[must_use]
+pub const fn em(ems: f32) -> Rule {
+    Rule::Root(ems, RootUnit::Em)
+}
 /// Returns [`Rule::Children`] as `ratio` of its children size.
 ///
 /// # Panics
@@ -5,7 +5,7 @@ use bevy::prelude::{Entity, Name, Query};
 use bevy_mod_sysfail::FailureMode;
 use thiserror::Error;
 
-use crate::{direction::Axis, direction::Size, layout::Layout};
+use crate::{direction::Axis, direction::Size, layout::Layout, Rule};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum Computed {
@@ -109,7 +109,7 @@ impl fmt::Display for Relative {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.size > 0.5 {
             let larger = self.size > 1.0;
-            write!(
+            writeln!(
                 f,
                 "- children have a total relative size on the parent's {} \
                 axis of {:0}% of the parent's {}.{}",
@@ -123,6 +123,54 @@ impl fmt::Display for Relative {
     }
 }
 
+/// How to fix a [`Why::ContainerOverflow`].
+pub(crate) struct Suggestion<'a> {
+    this: &'a Handle,
+    axis: &'a Axis,
+    rule: &'a Rule,
+    largest_child: &'a Handle,
+    overflow: &'a f32,
+}
+impl<'a> Suggestion<'a> {
+    const fn new(
+        this: &'a Handle,
+        axis: &'a Axis,
+        rule: &'a Rule,
+        largest_child: &'a Handle,
+        overflow: &'a f32,
+    ) -> Self {
+        Self { this, axis, rule, largest_child, overflow }
+    }
+}
+impl fmt::Display for Suggestion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { this, axis, largest_child, .. } = self;
+        let overflow = self.overflow.ceil();
+        match *self.rule {
+            Rule::Fixed(_) | Rule::Root(..) => write!(
+                f,
+                "Help: make `{this}.{axis}` `Rule::Children` or increase it by {overflow}px"
+            ),
+            Rule::Parent(ratio) if ratio > 0. => write!(
+                f,
+                "Help: make `{this}.{axis}` `Rule::Children` or increase the {axis} \
+                of {this}'s parent by {}px",
+                (self.overflow / ratio).ceil(),
+            ),
+            // A `Parent(0.)` node stays empty however large its parent is.
+            Rule::Parent(_) => write!(
+                f,
+                "Help: make `{this}.{axis}` `Rule::Children` or a non-zero \
+                percentage of its parent's {axis}"
+            ),
+            Rule::Children(_) => write!(
+                f,
+                "Help: reduce the {axis} of {largest_child} by {overflow}px"
+            ),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Error)]
 pub(crate) enum Why {
     #[error("Both axes of a `Root` container must be `Rule::Fixed`! {this}'s {axis} is not!")]
@@ -132,12 +180,20 @@ pub(crate) enum Why {
         for terminal nodes!"
     )]
     ChildlessContainer(Handle),
+    #[error(
+        "{0} is a media box, yet it has {1} children! A media box letterboxes \
+        a single child, wrap its children in another container."
+    )]
+    MediaBoxChildren(Handle, u32),
     #[error(
         "Cyclic rule definition detected!\n\
         - {this} depends on PARENT {parent} on {axis}\n\
         - {parent} depends on CHILD {this} on {axis}\n\
         It's impossible to make sense of this circular dependency!   \
-        Use different rules on {axis} for {parent} or {this} to fix this issue."
+        Use different rules on {axis} for {parent} or {this} to fix this issue.\n\
+        Note: children may depend on their parent on the cross axis of its flow \
+        (or any axis if it overlaps) if at least one other child doesn't, \
+        they then fit in the size of the largest other child."
     )]
     CyclicRule {
         this: Handle,
@@ -148,22 +204,31 @@ pub(crate) enum Why {
         "Node {this}'s {axis} is overflowed by its children!\n\
         Notes:\n\
         - {this}'s inner size (excluding margins) is {size}\n\
-        - There are {node_children_count} children of total {axis} {child_size}px.\n\
-        - The largest child is {largest_child}\n\
-        {child_relative_size}"
+        - There are {node_children_count} children of total {axis} {child_size}px, \
+        exceeding {this}'s {axis} by {overflow}px.\n\
+        - The largest child is {largest_child}, of {axis} {largest_child_size}px\n\
+        {child_relative_size}\
+        {suggestion}",
+        suggestion = Suggestion::new(.this, .axis, .rule, .largest_child, .overflow),
     )]
     ContainerOverflow {
         this: Handle,
         size: Size<f32>,
         largest_child: Handle,
+        largest_child_size: f32,
+        /// The largest child, shown in red in the debug overlay.
+        offender: Entity,
         node_children_count: u32,
         axis: Axis,
         child_relative_size: Relative,
         child_size: f32,
+        overflow: f32,
+        /// The rule of `this` on `axis`.
+        rule: Rule,
     },
     #[error(
-        "The margin of container {this} on axis {axis} has a negative value! ({margin}), \
-        cuicui_layout doesn't support negative margins."
+        "The margin plus padding of container {this} on a side of axis {axis} \
+        has a negative value! ({margin}), cuicui_layout doesn't support negative margins."
     )]
     NegativeMargin {
         this: Handle,
@@ -171,8 +236,8 @@ pub(crate) enum Why {
         margin: f32,
     },
     #[error(
-        "The margin of container {this} on axis {axis} is of {margin} pixels, \
-        yet, {this} has a {axis} of {this_size} pixels! This would require \
+        "The margins and padding of container {this} on axis {axis} add up to \
+        {margin} pixels, yet, {this} has a {axis} of {this_size} pixels! This would require \
         the content of {this} to have a negative size."
     )]
     TooMuchMargin {
@@ -209,6 +274,7 @@ pub struct ComputeLayoutError(#[from] Why);
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub enum ErrorId {
     ChildlessContainer(Handle),
+    MediaBoxChildren(Handle),
     CyclicRule(Handle),
     ContainerOverflow(Handle),
     NegativeMargin(Handle),
@@ -222,6 +288,7 @@ impl FailureMode for ComputeLayoutError {
     fn identify(&self) -> Self::ID {
         match &self.0 {
             Why::ChildlessContainer(this) => ErrorId::ChildlessContainer(this.clone()),
+            Why::MediaBoxChildren(this, _) => ErrorId::MediaBoxChildren(this.clone()),
             Why::CyclicRule { this, .. } => ErrorId::CyclicRule(this.clone()),
             Why::ContainerOverflow { this, .. } => ErrorId::ContainerOverflow(this.clone()),
             Why::NegativeMargin { this, .. } => ErrorId::NegativeMargin(this.clone()),
@@ -230,3 +297,38 @@ impl FailureMode for ComputeLayoutError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Flow;
+
+    fn suggestion(rule: Rule, overflow: f32) -> String {
+        let this = Handle::Named(Name::new("Menu"));
+        let largest_child = Handle::Named(Name::new("Button"));
+        let axis = Flow::Horizontal;
+        Suggestion::new(&this, &axis, &rule, &largest_child, &overflow).to_string()
+    }
+
+    #[test]
+    fn suggestion_wording() {
+        assert_eq!(
+            suggestion(Rule::Fixed(100.), 10.2),
+            "Help: make `Menu.width` `Rule::Children` or increase it by 11px",
+        );
+        assert_eq!(
+            suggestion(Rule::Parent(0.5), 10.),
+            "Help: make `Menu.width` `Rule::Children` or increase the width \
+            of Menu's parent by 20px",
+        );
+        assert_eq!(
+            suggestion(Rule::Parent(0.), 10.),
+            "Help: make `Menu.width` `Rule::Children` or a non-zero \
+            percentage of its parent's width",
+        );
+        assert_eq!(
+            suggestion(Rule::Children(1.), 10.),
+            "Help: reduce the width of Button by 10px",
+        );
+    }
+}
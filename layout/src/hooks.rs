@@ -0,0 +1,145 @@
+//! Custom constraints on the size of layout nodes.
+//!
+//! Some constraints can't be expressed with [`Rule`]s, such as snapping a node
+//! to a glyph grid or clamping it to the space left by a HUD of dynamic size.
+//!
+//! Register a _layout hook_ with [`AppLayoutHookExt::add_layout_hook`] and add
+//! the [`CustomConstraint`] component to the nodes it should apply to.
+//! While computing the layout, hooks are called with the size the layout
+//! algorithm proposes for the node, and return the size the node should have.
+//!
+//! # Limitations
+//!
+//! - [`Root`] containers are not affected by hooks.
+//! - The layout is only computed when layout components change. If the size
+//!   returned by a hook depends on other components or resources, trigger
+//!   a layout update when they change, for example by calling
+//!   [`DetectChangesMut::set_changed`] on the [`CustomConstraint`] component.
+//! - The `World` passed to hooks reflects the layout as it was before this
+//!   layout computation. The [`LayoutRect`] of nodes is updated after all
+//!   hooks ran.
+//!
+//! [`DetectChangesMut::set_changed`]: bevy::ecs::change_detection::DetectChangesMut::set_changed
+//! [`LayoutRect`]: crate::LayoutRect
+//! [`Root`]: crate::Root
+//! [`Rule`]: crate::Rule
+use bevy::app::App;
+use bevy::ecs::prelude::*;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+use crate::Size;
+
+/// A function constraining the size of a layout node, see [`AppLayoutHookExt`].
+pub type LayoutHook = Box<dyn Fn(Entity, Size<f32>, &World) -> Size<f32> + Send + Sync>;
+
+/// Apply the [layout hooks](self) to this node.
+#[derive(Component, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct CustomConstraint;
+
+/// The [layout hooks](self) registered with [`AppLayoutHookExt::add_layout_hook`].
+#[derive(Resource, Default)]
+pub struct LayoutHooks(Vec<LayoutHook>);
+
+impl LayoutHooks {
+    /// Run all hooks in registration order on `entity` of `size`.
+    pub(crate) fn constrain(&self, entity: Entity, size: Size<f32>, world: &World) -> Size<f32> {
+        self.0.iter().fold(size, |size, hook| hook(entity, size, world))
+    }
+}
+
+/// A run condition, `true` when no [layout hooks](self) are registered.
+///
+/// [`compute_layout`] runs only when it is `true`, [`compute_hooked_layout`]
+/// only when it is `false`.
+///
+/// [`compute_hooked_layout`]: crate::compute_hooked_layout
+/// [`compute_layout`]: crate::compute_layout
+#[allow(clippy::must_use_candidate, clippy::needless_pass_by_value)]
+pub fn no_layout_hooks(hooks: Res<LayoutHooks>) -> bool {
+    hooks.0.is_empty()
+}
+
+/// Extends [`App`] to register [layout hooks](self).
+pub trait AppLayoutHookExt {
+    /// Call `hook` while computing the layout of entities with
+    /// a [`CustomConstraint`] component.
+    ///
+    /// `hook` is called with the entity, the size the layout algorithm
+    /// proposes for it and the [`World`]. It returns the entity's size.
+    /// When several hooks are registered, they are called in registration
+    /// order, each with the size returned by the previous one.
+    ///
+    /// When the hook changes the size of a container, the container's children
+    /// are laid out again within the new size.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy::prelude::*;
+    /// use cuicui_layout::hooks::AppLayoutHookExt;
+    /// use cuicui_layout::Size;
+    ///
+    /// const GLYPH: f32 = 8.;
+    ///
+    /// let mut app = App::new();
+    /// app.add_plugins(cuicui_layout::Plugin)
+    ///     // Snap nodes with a `CustomConstraint` to a grid of 8×8 pixels glyphs.
+    ///     .add_layout_hook(|_, size, _| size.map(|axis| (axis / GLYPH).floor() * GLYPH));
+    /// ```
+    fn add_layout_hook(
+        &mut self,
+        hook: impl Fn(Entity, Size<f32>, &World) -> Size<f32> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+impl AppLayoutHookExt for App {
+    fn add_layout_hook(
+        &mut self,
+        hook: impl Fn(Entity, Size<f32>, &World) -> Size<f32> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let mut hooks = self.world.get_resource_or_insert_with(LayoutHooks::default);
+        hooks.0.push(Box::new(hook));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::{bundles::LayoutBundle, compute_hooked_layout, Container, Flow, LastLayoutChange};
+    use crate::{LayoutRect, LeafRule, Root, Rule};
+
+    #[test]
+    fn hooked_nodes() {
+        let mut app = App::new();
+        // Not `crate::Plugin`, since the debug overlay requires rendering plugins.
+        app.init_resource::<LastLayoutChange>()
+            .init_resource::<Time>()
+            .add_systems(Update, compute_hooked_layout)
+            .add_layout_hook(|_, size, _| size.map(|axis| (axis / 8.).floor() * 8.));
+
+        let leaf = |width| LayoutBundle::boxy(Size::new(width, 5.).map(LeafRule::Fixed));
+        let mut container = Container::compact(Flow::Horizontal);
+        container.rules = Size::all(Rule::Fixed(30.));
+        let root = Root::new(Size::all(100.), Flow::Horizontal, default(), default(), Size::ZERO);
+
+        let mut hooked = Entity::PLACEHOLDER;
+        let mut hooked_child = Entity::PLACEHOLDER;
+        let mut plain = Entity::PLACEHOLDER;
+        app.world.spawn((root, LayoutRect::default())).with_children(|cmds| {
+            let node = LayoutBundle::node(container);
+            let mut child = cmds.spawn((node, CustomConstraint));
+            hooked = child.id();
+            child.with_children(|cmds| hooked_child = cmds.spawn(leaf(20.)).id());
+            plain = cmds.spawn(leaf(13.)).id();
+        });
+        app.update();
+
+        let rect = |entity| app.world.get::<LayoutRect>(entity).unwrap().size;
+        assert_eq!(rect(hooked), Size::new(24., 24.));
+        assert_eq!(rect(hooked_child), Size::new(20., 5.));
+        assert_eq!(rect(plain), Size::new(13., 5.));
+    }
+}
@@ -2,13 +2,15 @@ use std::{fmt, marker::PhantomData};
 
 use bevy::prelude::SystemSet;
 
-/// Mark [`compute_layout`] as added by [`Plugin`].
+/// Mark [`compute_layout`] and [`compute_hooked_layout`] as added by [`Plugin`].
 ///
 /// Consider using [`ComputeLayoutSet`] instead. `ComputeLayout` marks
-/// `compute_layout` only, while `ComputeLayoutSet` also includes the
-/// content-sized node's computation.
+/// the layout systems only, while `ComputeLayoutSet` also includes the
+/// content-sized node's computation. The second layout pass, running after
+/// [`ContentSizedRemeasureSet`], is not part of `ComputeLayout`.
 ///
 /// [`Plugin`]: crate::Plugin
+/// [`compute_hooked_layout`]: crate::compute_hooked_layout
 /// [`compute_layout`]: crate::compute_layout
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, SystemSet)]
 pub struct ComputeLayout;
@@ -16,19 +18,75 @@ pub struct ComputeLayout;
 /// [`compute_layout`] and systems added by [`add_content_sized`].
 ///
 /// This first runs the systems updating the size of content-dependent nodes
-/// then run the global layouting system.
+/// then run the global layouting system. Content-sized nodes are then measured
+/// again within the width the layout gave them, in [`ContentSizedRemeasureSet`],
+/// and the layout runs a second time if their size changed.
 ///
 /// [`add_content_sized`]: crate::content_sized::AppContentSizeExt::add_content_sized
 /// [`compute_layout`]: crate::compute_layout
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, SystemSet)]
 pub struct ComputeLayoutSet;
 
-/// All systems added by [`add_content_sized`].
+/// [`update_leaf_nodes`] and systems added by [`add_leaf_component`].
+///
+/// Systems in this set may add or remove [`LeafNode`] and [`Node`] components
+/// through `Commands`. Those commands are applied before any system of
+/// [`ContentSizedComputeSystemSet`] runs.
+///
+/// [`add_leaf_component`]: crate::AppLeafNodeExt::add_leaf_component
+/// [`LeafNode`]: crate::LeafNode
+/// [`Node`]: crate::Node
+/// [`update_leaf_nodes`]: crate::update_leaf_nodes
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, SystemSet)]
+pub struct UpdateLeafNodes;
+
+/// Systems setting the size of [`Root`] containers.
+///
+/// This contains [`update_root_size`], systems added by [`add_root_size`]
+/// and the backends' systems tracking the size of the camera of
+/// [`ScreenRoot`]s. It runs before [`ComputeLayoutSet`] in `Update`, so
+/// a `Root` resized in this set is laid out on the same frame.
+///
+/// [`add_root_size`]: crate::root_size::AppRootSizeExt::add_root_size
+/// [`Root`]: crate::Root
+/// [`ScreenRoot`]: crate::ScreenRoot
+/// [`update_root_size`]: crate::root_size::update_root_size
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, SystemSet)]
+pub struct RootSizeSet;
+
+/// Backend systems applying the computed [`LayoutRect`]s to the entities.
+///
+/// Backends add to this set the systems reading [`LayoutRect`]s to update
+/// the components they render, such as `Transform` for `cuicui_layout_bevy_sprite`
+/// or `Style` for `cuicui_layout_bevy_ui`.
+///
+/// In `Update`, this set runs after [`ComputeLayoutSet`], once the
+/// `LayoutRect`s it computed are applied to the entities. Backends may also
+/// add systems to this set in other schedules, `cuicui_layout_bevy_ui` does so
+/// in `PostUpdate`. Order your systems `.after(LayoutOutputSet)` in the same
+/// schedule to read the backend components once they reflect the layout.
+///
+/// [`LayoutRect`]: crate::LayoutRect
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, SystemSet)]
+pub struct LayoutOutputSet;
+
+/// The systems added by [`add_content_sized`] measuring content-sized nodes
+/// before the layout.
 ///
 /// [`add_content_sized`]: crate::content_sized::AppContentSizeExt::add_content_sized
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, SystemSet)]
 pub struct ContentSizedComputeSystemSet;
 
+/// Systems added by [`add_content_sized`] measuring content-sized nodes again
+/// within the width the layout gave them.
+///
+/// It is part of [`ComputeLayoutSet`] and runs after [`ComputeLayout`]. The
+/// layout then runs a second time, if a node changed size.
+///
+/// [`add_content_sized`]: crate::content_sized::AppContentSizeExt::add_content_sized
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, SystemSet)]
+pub struct ContentSizedRemeasureSet;
+
 /// The system added by [`add_content_sized`] for `S`.
 ///
 /// It is part of [`ComputeLayoutSet`], but this happens just
@@ -1,18 +1,23 @@
 //! The `cuicui_layout` algorithm.
 
-use std::{num::ParseFloatError, str::FromStr};
+use std::{num::ParseFloatError, ops, str::FromStr};
 
-use bevy::ecs::{prelude::*, query::ReadOnlyWorldQuery};
+use bevy::ecs::prelude::*;
+use bevy::ecs::query::{Has, ReadOnlyWorldQuery};
 use bevy::log::trace;
-use bevy::prelude::{Children, Name, Vec2};
+#[cfg(feature = "profiling_containers")]
+use bevy::{log::info_span, utils::tracing::field};
+use bevy::prelude::{Children, Name, Rect, Vec2};
 #[cfg(feature = "reflect")]
-use bevy::prelude::{Reflect, ReflectComponent};
-use bevy::utils::FloatOrd;
+use bevy::prelude::{Reflect, ReflectComponent, ReflectResource};
+use bevy::utils::{FloatOrd, HashMap, HashSet};
 use thiserror::Error;
 
-use crate::alignment::{Alignment, Distribution};
-use crate::direction::{Flow, Oriented, Size};
+use crate::alignment::{Alignment, Anchor, Distribution};
+use crate::cache::LayoutCache;
+use crate::direction::{Flow, Oriented, Sides, Size};
 use crate::error::{self, Computed, Handle, Relative};
+use crate::hooks::{CustomConstraint, LayoutHooks};
 
 const WIDTH: Flow = Flow::Horizontal;
 const HEIGHT: Flow = Flow::Vertical;
@@ -40,6 +45,139 @@ impl LayoutRect {
     pub const fn size(&self) -> Size<f32> {
         self.size
     }
+    /// This rect with its position rounded to the nearest pixel, and its size
+    /// adjusted so that its bottom right corner is also rounded to the nearest pixel.
+    ///
+    /// The bottom right corner of a node is rounded the same way as the top
+    /// left corner of the node directly following it, so snapping doesn't create
+    /// gaps between adjacent nodes. See [`PixelSnap`].
+    #[must_use]
+    pub fn snapped(&self) -> Self {
+        let pos = self.pos.map(f32::round);
+        let end_width = (self.pos.width + self.size.width).round();
+        let end_height = (self.pos.height + self.size.height).round();
+        let size = Size::new(end_width - pos.width, end_height - pos.height);
+        Self { size, pos }
+    }
+    /// [`Self::snapped`] if `snap` is enabled, `self` otherwise.
+    #[must_use]
+    pub fn snapped_if(&self, snap: PixelSnap) -> Self {
+        if snap.0 {
+            self.snapped()
+        } else {
+            *self
+        }
+    }
+    /// This rect as a bevy [`Rect`], `min` is the top left corner.
+    ///
+    /// Like [`Self::pos`], it is relative to the containing node.
+    #[must_use]
+    pub fn to_rect(&self) -> Rect {
+        let pos = self.pos();
+        Rect::from_corners(pos, pos + Vec2::from(self.size))
+    }
+    /// Whether `point` is within this rect, edges included.
+    ///
+    /// `point` must be relative to the containing node, like [`Self::pos`].
+    #[must_use]
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.to_rect().contains(point)
+    }
+    /// The overlapping area of `self` and `other`, `None` if they do not overlap.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let rect = self.to_rect().intersect(other.to_rect());
+        (!rect.is_empty()).then(|| rect.into())
+    }
+    /// This rect grown by `margin` on each side, negative values shrink it.
+    ///
+    /// The size is clamped to zero.
+    #[must_use]
+    pub fn expand(&self, margin: Size<f32>) -> Self {
+        let size = Size::new(
+            (self.size.width + margin.width * 2.).max(0.),
+            (self.size.height + margin.height * 2.).max(0.),
+        );
+        let pos = Size::new(self.pos.width - margin.width, self.pos.height - margin.height);
+        Self { size, pos }
+    }
+}
+impl From<LayoutRect> for Rect {
+    fn from(rect: LayoutRect) -> Self {
+        rect.to_rect()
+    }
+}
+impl From<Rect> for LayoutRect {
+    fn from(rect: Rect) -> Self {
+        Self { size: rect.size().into(), pos: rect.min.into() }
+    }
+}
+/// Scale both the position and size of the rect.
+impl ops::Mul<f32> for LayoutRect {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        let scale = |size: Size<f32>| size.map(|axis| axis * rhs);
+        Self { size: scale(self.size), pos: scale(self.pos) }
+    }
+}
+
+/// Whether backends round the position of nodes to the nearest pixel.
+///
+/// Fractional positions may cause blurry text and images. When enabled,
+/// `cuicui_layout_bevy_ui` and `cuicui_layout_bevy_sprite` use the
+/// [`LayoutRect::snapped`] position and size of nodes.
+///
+/// Disabled by default.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Resource))]
+pub struct PixelSnap(pub bool);
+
+/// The length of the `em` unit, in pixels, see [`RootUnit::Em`].
+///
+/// Defaults to `16.0`. Changing it lays out all nodes again.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Resource))]
+pub struct RootFontSize(pub f32);
+impl Default for RootFontSize {
+    fn default() -> Self {
+        Self(16.)
+    }
+}
+
+/// A length relative to the layout [`Root`] of a node, see [`Rule::Root`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum RootUnit {
+    /// The smallest axis of the [`Root`]'s size, like the CSS `vmin` unit.
+    Vmin,
+    /// The largest axis of the [`Root`]'s size, like the CSS `vmax` unit.
+    Vmax,
+    /// The [`RootFontSize`], like the CSS `rem` unit.
+    Em,
+}
+
+/// The lengths of [`RootUnit`]s in a given [`Root`].
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub(crate) struct RootLengths {
+    pub(crate) size: Size<f32>,
+    pub(crate) em: f32,
+}
+impl RootLengths {
+    pub(crate) fn new(root: Option<&Root>, font_size: Option<&RootFontSize>) -> Self {
+        // Invalid roots are reported by the layout, not `Root::size`'s panic.
+        let fixed = |rule| if let Rule::Fixed(pixels) = rule { pixels } else { 0. };
+        let size = root.map_or(Size::ZERO, |root| root.node.rules.map(fixed));
+        Self { size, em: font_size.copied().unwrap_or_default().0 }
+    }
+    /// `ratio` times the length of `unit`, in pixels.
+    pub(crate) fn get(&self, ratio: f32, unit: RootUnit) -> f32 {
+        let length = match unit {
+            RootUnit::Vmin => self.size.width.min(self.size.height),
+            RootUnit::Vmax => self.size.width.max(self.size.height),
+            RootUnit::Em => self.em,
+        };
+        ratio * length
+    }
 }
 
 impl<T> Size<Result<T, Entity>> {
@@ -62,72 +200,160 @@ impl Oriented<Computed> {
     }
 }
 impl Size<Computed> {
-    pub(crate) fn set_margin(
+    /// Remove `inset`, the margin and padding of a container, from this size.
+    pub(crate) fn set_inset(
         &mut self,
-        margin: Size<f32>,
+        inset: Sides<f32>,
         queries: &Layout<impl ReadOnlyWorldQuery>,
     ) -> Result<(), error::Why> {
-        if let Computed::Valid(width) = &mut self.width {
-            if *width < 2. * margin.width {
-                return Err(error::Why::TooMuchMargin {
-                    this: Handle::of(queries),
-                    axis: WIDTH,
-                    margin: margin.width,
-                    this_size: *width,
-                });
-            }
-            if margin.width.is_sign_negative() {
-                return Err(error::Why::NegativeMargin {
-                    this: Handle::of(queries),
-                    axis: WIDTH,
-                    margin: margin.width,
-                });
-            }
-            *width -= 2. * margin.width;
-        }
-        if let Computed::Valid(height) = &mut self.height {
-            if *height < 2. * margin.height {
+        let total = inset.total();
+        let axes = [(WIDTH, inset.left, inset.right), (HEIGHT, inset.top, inset.bottom)];
+        for (axis, start, end) in axes {
+            let Computed::Valid(size) = self.on_mut(axis) else {
+                continue;
+            };
+            let margin = *total.on(axis);
+            if *size < margin {
                 return Err(error::Why::TooMuchMargin {
                     this: Handle::of(queries),
-                    axis: HEIGHT,
-                    margin: margin.height,
-                    this_size: *height,
+                    axis,
+                    margin,
+                    this_size: *size,
                 });
             }
-            if margin.height.is_sign_negative() {
-                return Err(error::Why::NegativeMargin {
-                    this: Handle::of(queries),
-                    axis: HEIGHT,
-                    margin: margin.height,
-                });
+            if let Some(margin) = [start, end].into_iter().find(|side| side.is_sign_negative()) {
+                return Err(error::Why::NegativeMargin { this: Handle::of(queries), axis, margin });
             }
-            *height -= 2. * margin.height;
+            *size -= margin;
         }
         Ok(())
     }
 
     fn container_size(
         self,
-        Container { rules, margin, .. }: &Container,
+        container @ Container { rules, .. }: &Container,
         queries: &Layout<impl ReadOnlyWorldQuery>,
     ) -> Result<Self, error::Why> {
         let bounds = Size {
-            width: rules.width.inside(self.width, queries.this),
-            height: rules.height.inside(self.height, queries.this),
+            width: rules.width.inside(self.width, queries.this, &queries.root),
+            height: rules.height.inside(self.height, queries.this, &queries.root),
         };
         let mut bounds = bounds.transpose(queries)?;
-        bounds.set_margin(*margin, queries)?;
+        bounds.set_inset(container.inset(), queries)?;
 
         Ok(bounds)
     }
 
-    fn leaf_size(self, Size { width, height }: Size<LeafRule>) -> Size<Result<f32, Entity>> {
+    /// The largest size of aspect ratio `ratio` (width / height) fitting in
+    /// `self`.
+    ///
+    /// When an axis is child-defined, it is set so that the other axis fits
+    /// entirely at aspect ratio `ratio`.
+    fn letterbox(self, ratio: f32) -> Self {
+        use Computed::Valid;
+        let (width, height) = match (self.width, self.height) {
+            (Valid(width), Valid(height)) if width > height * ratio => (height * ratio, height),
+            (Valid(width), _) => (width, width / ratio),
+            (_, Valid(height)) => (height * ratio, height),
+            _ => return self,
+        };
+        Size { width: Valid(width), height: Valid(height) }
+    }
+    fn leaf_size(
+        self,
+        Size { width, height }: Size<LeafRule>,
+        root: &RootLengths,
+    ) -> Size<Result<f32, Entity>> {
         Size {
-            width: width.inside(self.width),
-            height: height.inside(self.height),
+            width: width.inside(self.width, root),
+            height: height.inside(self.height, root),
+        }
+    }
+}
+
+/// A line of children in a wrapping container, see [`Container::wrap`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct WrapLine {
+    /// How many children are in this line.
+    pub(crate) count: usize,
+    /// The total main size and largest cross size of the children in this line.
+    pub(crate) size: Oriented<f32>,
+}
+
+/// Split children of given `sizes` in lines that fit in `max_main` on the
+/// main axis.
+///
+/// A child larger than `max_main` gets its own line.
+pub(crate) fn wrap_lines(
+    sizes: impl IntoIterator<Item = Oriented<f32>>,
+    max_main: f32,
+) -> Vec<WrapLine> {
+    // Children of `Rule::Parent` summing to 1 may add up to slightly more
+    // than `max_main` due to float rounding, they should still fit.
+    let max_main = max_main.abs().mul_add(1e-5, max_main);
+    let mut lines: Vec<WrapLine> = Vec::new();
+    for size in sizes {
+        match lines.last_mut() {
+            Some(line) if line.size.main + size.main <= max_main => {
+                line.count += 1;
+                line.size.main += size.main;
+                line.size.cross = line.size.cross.max(size.cross);
+            }
+            _ => lines.push(WrapLine { count: 1, size }),
+        }
+    }
+    lines
+}
+
+/// Size the `tracks` of a grid axis depending on its children, given the first
+/// track, the track count and the size of each child, see [`Container::grid`].
+///
+/// Tracks without children are as large as the largest track.
+#[allow(clippy::cast_precision_loss)] // track counts as f32
+fn size_tracks(tracks: &mut [f32], children: impl Iterator<Item = (u32, u32, f32)>) {
+    let mut empty = vec![true; tracks.len()];
+    let mut spanning = Vec::new();
+    for (start, span, size) in children {
+        let range = start as usize..(start + span) as usize;
+        empty[range.clone()].fill(false);
+        match &mut tracks[range.clone()] {
+            [track] => *track = track.max(size),
+            _ => spanning.push((range, size)),
+        }
+    }
+    // Like CSS grids, children spanning fewer tracks are accounted for first.
+    spanning.sort_by_key(|(range, _)| range.len());
+    for (range, size) in spanning {
+        let excess = size - tracks[range.clone()].iter().sum::<f32>();
+        if excess > 0. {
+            let share = excess / range.len() as f32;
+            tracks[range].iter_mut().for_each(|track| *track += share);
+        }
+    }
+    let largest = tracks.iter().copied().fold(0., f32::max);
+    for (track, empty) in tracks.iter_mut().zip(empty) {
+        if empty {
+            *track = largest;
         }
     }
 }
+/// Scale `tracks` so that they add up to `size`.
+#[allow(clippy::cast_precision_loss)] // track counts as f32
+fn fit_tracks(tracks: &mut [f32], size: f32) {
+    let sum: f32 = tracks.iter().sum();
+    if sum > 0. {
+        tracks.iter_mut().for_each(|track| *track *= size / sum);
+    } else {
+        tracks.fill(size / tracks.len() as f32);
+    }
+}
+/// The total size of `span` tracks starting at `start`.
+fn track_area(tracks: &[f32], start: u32, span: u32) -> f32 {
+    tracks[start as usize..][..span as usize].iter().sum()
+}
+
+/// A child of a grid, its first cell and how many cells it spans.
+type GridCell = (Entity, Oriented<u32>, Oriented<u32>);
 
 /// Parameters of a container, ie: a node that contains other nodes.
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -169,14 +395,73 @@ pub struct Container {
     /// See [`Rule`] for details.
     pub rules: Size<Rule>,
 
-    /// The empty space to leave between this `Container` and its content, in pixels.
-    ///
-    /// Note that margins are symetric, so that left/right and top/bottom margins
-    /// are identical.
+    /// The empty space to leave between the edges of this `Container` and its
+    /// content, in pixels, on each side.
     ///
-    /// Note also that when a child is [`Rule::Parent`], it will substract the margin
+    /// Note that when a child is [`Rule::Parent`], it will substract the margin
     /// of the parent container when calculating its own size.
-    pub margin: Size<f32>,
+    pub margin: Sides<f32>,
+
+    /// Additional empty space to leave between the [`margin`](Self::margin)
+    /// of this `Container` and its content, in pixels, on each side.
+    ///
+    /// `padding` is added to `margin`, children are laid out within both.
+    /// Use it to adjust the space around a container's content independently
+    /// of its margin, for example when the margin comes from a template.
+    ///
+    /// A side of `padding` may be negative, as long as it doesn't exceed
+    /// the same side of `margin`.
+    pub padding: Sides<f32>,
+
+    /// When set, this container is a _media box_ of the given aspect ratio
+    /// (width / height).
+    ///
+    /// A media box has a single child, the child is laid out as if this
+    /// container's inner size was the largest size of the given aspect ratio
+    /// fitting in it. Use [`Rule::Parent(1.)`] on the child so that it
+    /// fills the letterboxed area.
+    ///
+    /// When an axis of the media box is [`Rule::Children`], its size
+    /// is that of the other axis at the given aspect ratio.
+    pub aspect: Option<f32>,
+
+    /// When set, this container is a grid of `grid` cells per line.
+    ///
+    /// Children are placed in the cells one after the other on the main axis,
+    /// starting a new line on the cross axis every `grid` cells. With
+    /// [`Flow::Horizontal`], `grid` is the number of columns. A child with
+    /// a [`CellSpan`] takes several cells of its line, or several lines.
+    ///
+    /// [`Rule::Parent`] and [`LeafRule::Parent`] children are relative to the
+    /// cells they take, and `align` places children within their cells on both
+    /// axis. `distrib` and `aspect` are ignored.
+    ///
+    /// On an axis of the grid with a set size, all tracks (the columns or lines
+    /// on this axis) have the same size. When it is [`Rule::Children`], each
+    /// track is the size of its largest child. Children spanning several tracks
+    /// enlarge them evenly when they don't fit. Children depending on their
+    /// parent size then fit in the tracks given by the other children, tracks
+    /// without other children are as large as the largest track.
+    pub grid: Option<u32>,
+
+    /// Whether children that do not fit on the main axis flow onto a new line.
+    ///
+    /// With [`Flow::Horizontal`], children are placed left to right, and
+    /// when the next child would overflow this container's width, it starts
+    /// a new line below the previous ones, like text wrapping.
+    ///
+    /// Each line is distributed on the main axis following `distrib`, and
+    /// children are aligned following `align` within their line. The lines
+    /// themselves are aligned on the cross axis following `align`. Lines are
+    /// as large as their largest child on the cross axis. [`Rule::Parent`]
+    /// and [`LeafRule::Parent`] children are still relative to this
+    /// container's size.
+    ///
+    /// Lines only wrap when the main axis is not [`Rule::Children`]. A single
+    /// child larger than this container's main axis is still an overflow
+    /// error. `wrap` is ignored by [grids](Self::grid) and containers with
+    /// overlapping children.
+    pub wrap: bool,
 }
 impl Default for Container {
     fn default() -> Self {
@@ -184,8 +469,12 @@ impl Default for Container {
             flow: Flow::Horizontal,
             align: Alignment::Center,
             distrib: Distribution::FillMain,
-            margin: Size::ZERO,
+            margin: Sides::ZERO,
+            padding: Sides::ZERO,
             rules: Size::all(Rule::Parent(1.)),
+            aspect: None,
+            grid: None,
+            wrap: false,
         }
     }
 }
@@ -202,8 +491,9 @@ impl Container {
             _ => Rule::Parent(1.),
         };
         let rules = flow.absolute(Oriented::new(main, Rule::Children(1.)));
-        let margin = Size::ZERO;
-        Self { flow, align, distrib, rules, margin }
+        let (margin, padding) = (Sides::ZERO, Sides::ZERO);
+        let (aspect, grid, wrap) = (None, None, false);
+        Self { flow, align, distrib, rules, margin, padding, aspect, grid, wrap }
     }
     /// Create a [`Container`] where children are center-aligned and
     /// fill this container on the `flow` main axis.
@@ -217,6 +507,124 @@ impl Container {
     pub const fn compact(flow: Flow) -> Self {
         Self::new(flow, Alignment::Start, Distribution::Start)
     }
+    /// Create a media box [`Container`], filling its parent and centering
+    /// its single child at the fixed `aspect` ratio (width / height).
+    ///
+    /// See [`Container::aspect`] for details.
+    #[must_use]
+    pub const fn media_box(aspect: f32) -> Self {
+        Self {
+            flow: Flow::Horizontal,
+            align: Alignment::Center,
+            distrib: Distribution::FillMain,
+            margin: Sides::ZERO,
+            padding: Sides::ZERO,
+            rules: Size::new(Rule::Parent(1.), Rule::Parent(1.)),
+            aspect: Some(aspect),
+            grid: None,
+            wrap: false,
+        }
+    }
+    /// Create a [`Container`] field by field, starting from [`Container::default`].
+    ///
+    /// ```
+    /// use cuicui_layout::{Alignment, Container, Flow, Rule, Size};
+    ///
+    /// let container = Container::builder()
+    ///     .flow(Flow::Vertical)
+    ///     .align(Alignment::Start)
+    ///     .width(Rule::Fixed(300.))
+    ///     .height(Rule::Children(1.))
+    ///     .margin(Size::new(10., 5.))
+    ///     .build();
+    /// assert_eq!(container.rules, Size::new(Rule::Fixed(300.), Rule::Children(1.)));
+    /// ```
+    pub fn builder() -> ContainerBuilder {
+        ContainerBuilder(Self::default())
+    }
+    /// The total space between the edges of this container and its content,
+    /// its [`margin`](Self::margin) plus its [`padding`](Self::padding).
+    #[must_use]
+    pub fn inset(&self) -> Sides<f32> {
+        self.margin + self.padding
+    }
+}
+
+/// Build a [`Container`] field by field, see [`Container::builder`].
+///
+/// See the [`Container`] fields for what each method sets.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[must_use]
+pub struct ContainerBuilder(Container);
+impl ContainerBuilder {
+    /// Set [`Container::flow`].
+    pub const fn flow(mut self, flow: Flow) -> Self {
+        self.0.flow = flow;
+        self
+    }
+    /// Set [`Container::align`].
+    pub const fn align(mut self, align: Alignment) -> Self {
+        self.0.align = align;
+        self
+    }
+    /// Set [`Container::distrib`].
+    pub const fn distrib(mut self, distrib: Distribution) -> Self {
+        self.0.distrib = distrib;
+        self
+    }
+    /// Set [`Container::rules`].
+    pub const fn rules(mut self, rules: Size<Rule>) -> Self {
+        self.0.rules = rules;
+        self
+    }
+    /// Set the width of [`Container::rules`].
+    pub const fn width(mut self, rule: Rule) -> Self {
+        self.0.rules.width = rule;
+        self
+    }
+    /// Set the height of [`Container::rules`].
+    pub const fn height(mut self, rule: Rule) -> Self {
+        self.0.rules.height = rule;
+        self
+    }
+    /// Set [`Container::margin`], in pixels, identical on opposite sides.
+    pub const fn margin(mut self, margin: Size<f32>) -> Self {
+        self.0.margin = Sides::symmetric(margin);
+        self
+    }
+    /// Set [`Container::margin`] of each side, in pixels.
+    pub const fn margin_sides(mut self, margin: Sides<f32>) -> Self {
+        self.0.margin = margin;
+        self
+    }
+    /// Set [`Container::padding`], in pixels.
+    pub const fn padding(mut self, padding: Sides<f32>) -> Self {
+        self.0.padding = padding;
+        self
+    }
+    /// Make this container a media box of the given `aspect` ratio
+    /// (width / height), see [`Container::aspect`].
+    pub const fn aspect(mut self, aspect: f32) -> Self {
+        self.0.aspect = Some(aspect);
+        self
+    }
+    /// Make this container a grid of `columns` cells per line,
+    /// see [`Container::grid`].
+    pub const fn grid(mut self, columns: u32) -> Self {
+        self.0.grid = Some(columns);
+        self
+    }
+    /// Wrap children that do not fit on the main axis onto new lines,
+    /// see [`Container::wrap`].
+    pub const fn wrap(mut self) -> Self {
+        self.0.wrap = true;
+        self
+    }
+    /// The [`Container`] built.
+    #[must_use]
+    pub const fn build(self) -> Container {
+        self.0
+    }
 }
 
 /// A root [`Container`].
@@ -234,10 +642,19 @@ pub struct Root {
     /// `true` by default. To debug layout, enable the `cuicui_layout/debug`
     /// cargo feature.
     pub debug: bool,
+    /// Whether to skip the layout of this root and its descendants, they keep
+    /// their last computed [`LayoutRect`].
+    ///
+    /// `false` by default. Layout is recomputed when it is set back to `false`.
+    /// The sprite backend's `cull::CullOffscreen` sets it for roots outside
+    /// of the camera view.
+    ///
+    /// [`LayoutRect`]: crate::LayoutRect
+    pub paused: bool,
 }
 impl Default for Root {
     fn default() -> Self {
-        Self { node: Container::default(), debug: true }
+        Self { node: Container::default(), debug: true, paused: false }
     }
 }
 impl Root {
@@ -295,8 +712,105 @@ impl Root {
     ) -> Self {
         use Rule::Fixed;
         let rules = Size::new(Fixed(width), Fixed(height));
-        let node = Container { flow, align, distrib, rules, margin };
-        Self { node, debug: true }
+        let (margin, padding) = (Sides::symmetric(margin), Sides::ZERO);
+        let (aspect, grid, wrap) = (None, None, false);
+        let node = Container { flow, align, distrib, rules, margin, padding, aspect, grid, wrap };
+        Self { node, debug: true, paused: false }
+    }
+    /// Create a [`Root`] field by field.
+    ///
+    /// The root is `0×0` pixels unless you set its [`RootBuilder::size`], the
+    /// other fields default to those of [`Container::default`].
+    ///
+    /// ```
+    /// use cuicui_layout::{Distribution, Flow, Root, Size};
+    ///
+    /// let root = Root::builder()
+    ///     .size(Size::new(800., 600.))
+    ///     .flow(Flow::Vertical)
+    ///     .distrib(Distribution::Start)
+    ///     .margin(Size::all(20.))
+    ///     .aspect(4. / 3.)
+    ///     .debug(false)
+    ///     .build();
+    /// assert_eq!(root.size(), Size::new(800., 600.));
+    /// assert_eq!(root.get().aspect, Some(4. / 3.));
+    /// ```
+    pub fn builder() -> RootBuilder {
+        let node = Container { rules: Size::all(Rule::Fixed(0.)), ..Container::default() };
+        RootBuilder(Self { node, debug: true, paused: false })
+    }
+}
+
+/// Build a [`Root`] field by field, see [`Root::builder`].
+///
+/// See the [`Container`] fields for what each method sets. The size of a
+/// `Root` is always fixed.
+#[must_use]
+pub struct RootBuilder(Root);
+impl RootBuilder {
+    /// Set the fixed size of the root, in pixels.
+    pub const fn size(mut self, Size { width, height }: Size<f32>) -> Self {
+        self.0.node.rules = Size::new(Rule::Fixed(width), Rule::Fixed(height));
+        self
+    }
+    /// Set [`Container::flow`].
+    pub const fn flow(mut self, flow: Flow) -> Self {
+        self.0.node.flow = flow;
+        self
+    }
+    /// Set [`Container::align`].
+    pub const fn align(mut self, align: Alignment) -> Self {
+        self.0.node.align = align;
+        self
+    }
+    /// Set [`Container::distrib`].
+    pub const fn distrib(mut self, distrib: Distribution) -> Self {
+        self.0.node.distrib = distrib;
+        self
+    }
+    /// Set [`Container::margin`], in pixels, identical on opposite sides.
+    pub const fn margin(mut self, margin: Size<f32>) -> Self {
+        self.0.node.margin = Sides::symmetric(margin);
+        self
+    }
+    /// Set [`Container::margin`] of each side, in pixels.
+    pub const fn margin_sides(mut self, margin: Sides<f32>) -> Self {
+        self.0.node.margin = margin;
+        self
+    }
+    /// Set [`Container::padding`], in pixels.
+    pub const fn padding(mut self, padding: Sides<f32>) -> Self {
+        self.0.node.padding = padding;
+        self
+    }
+    /// Make this root a media box of the given `aspect` ratio
+    /// (width / height), see [`Container::aspect`].
+    pub const fn aspect(mut self, aspect: f32) -> Self {
+        self.0.node.aspect = Some(aspect);
+        self
+    }
+    /// Make this root a grid of `columns` cells per line,
+    /// see [`Container::grid`].
+    pub const fn grid(mut self, columns: u32) -> Self {
+        self.0.node.grid = Some(columns);
+        self
+    }
+    /// Wrap children that do not fit on the main axis onto new lines,
+    /// see [`Container::wrap`].
+    pub const fn wrap(mut self) -> Self {
+        self.0.node.wrap = true;
+        self
+    }
+    /// Set [`Root::debug`].
+    pub const fn debug(mut self, debug: bool) -> Self {
+        self.0.debug = debug;
+        self
+    }
+    /// The [`Root`] built.
+    #[must_use]
+    pub const fn build(self) -> Root {
+        self.0
     }
 }
 
@@ -368,12 +882,122 @@ impl Node {
     }
 }
 
+/// How many cells this [`Node`] takes in a [grid](Container::grid) container:
+/// `main` cells of its line, on `cross` lines. One cell by default.
+///
+/// In a [`Flow::Horizontal`] grid, `main` is the number of columns and `cross`
+/// the number of rows the node takes. Spans larger than the number of cells
+/// per line of the grid are reduced to a whole line. Cells already taken by
+/// a node spanning several lines are skipped when placing the next children.
+///
+/// Use [`LayoutDsl::cell_span`] to spawn nodes with this component.
+///
+/// [`LayoutDsl::cell_span`]: crate::LayoutDsl::cell_span
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct CellSpan {
+    /// How many cells of its line the node takes.
+    pub main: u32,
+    /// How many lines the node takes.
+    pub cross: u32,
+}
+impl CellSpan {
+    /// Take `main` cells of a line, on `cross` lines.
+    #[must_use]
+    pub const fn new(main: u32, cross: u32) -> Self {
+        Self { main, cross }
+    }
+}
+impl Default for CellSpan {
+    fn default() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+/// Take this [`Node`] out of its parent's flow, and place it at the `anchor`
+/// of its parent, moved by `offset` pixels.
+///
+/// Anchored nodes do not take space in their parent: they are not counted in
+/// its [`Rule::Children`] size, and other children are laid out as if they
+/// didn't exist. They may overlap other children or overflow their parent,
+/// which is useful for tooltips, badges or close buttons.
+///
+/// Anchored nodes are placed relative to the whole rect of their parent,
+/// [margin](Container::margin) included. Their [`Rule::Parent`] and
+/// [`LeafRule::Parent`] rules are also relative to this rect.
+///
+/// Positive `offset` values move the node right and down. Use
+/// [`LayoutDsl::anchor`] and [`LayoutDsl::offset`] to spawn nodes with
+/// this component.
+///
+/// [`LayoutDsl::anchor`]: crate::LayoutDsl::anchor
+/// [`LayoutDsl::offset`]: crate::LayoutDsl::offset
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct Anchored {
+    /// Where the node is attached to its parent.
+    pub anchor: Anchor,
+    /// Move the node by this many pixels from its anchor.
+    pub offset: Size<f32>,
+}
+
+/// Clamp the size of this [`Node`] between `min` and `max`, after its rules
+/// are computed.
+///
+/// For example, a node with a `Rule::Parent(0.5)` width and a `Rule::Fixed(600.)`
+/// maximum width is half as wide as its parent, but never wider than 600 pixels.
+///
+/// Like the node's own rules, [`Rule::Parent`] bounds are relative to the
+/// parent's size. They are ignored when the parent's size depends on its
+/// children. [`Rule::Children`] bounds are always ignored. When `min` is
+/// larger than `max`, `min` wins.
+///
+/// Bounds apply before [layout hooks](crate::hooks), and have no effect on
+/// [`Root`]s. The children of a clamped container are laid out again within
+/// its clamped size.
+///
+/// Use [`LayoutDsl::min_width`], [`LayoutDsl::max_width`],
+/// [`LayoutDsl::min_height`] and [`LayoutDsl::max_height`] to spawn nodes with
+/// this component.
+///
+/// [`LayoutDsl::min_width`]: crate::LayoutDsl::min_width
+/// [`LayoutDsl::max_width`]: crate::LayoutDsl::max_width
+/// [`LayoutDsl::min_height`]: crate::LayoutDsl::min_height
+/// [`LayoutDsl::max_height`]: crate::LayoutDsl::max_height
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct SizeBounds {
+    /// The smallest size of the node, no minimum on axes set to `None`.
+    pub min: Size<Option<Rule>>,
+    /// The largest size of the node, no maximum on axes set to `None`.
+    pub max: Size<Option<Rule>>,
+}
+impl SizeBounds {
+    /// `size` clamped between `min` and `max`, in a parent of size `parent`.
+    fn clamp(&self, size: Size<f32>, parent: Size<Computed>, root: &RootLengths) -> Size<f32> {
+        let resolve = |rule: Option<Rule>, parent| match (rule?, parent) {
+            (Rule::Fixed(pixels), _) => Some(pixels),
+            (Rule::Root(ratio, unit), _) => Some(root.get(ratio, unit)),
+            (Rule::Parent(ratio), Computed::Valid(parent)) => Some(parent * ratio),
+            (Rule::Parent(_) | Rule::Children(_), _) => None,
+        };
+        let clamp = |axis| {
+            let (size, parent) = (*size.on(axis), *parent.on(axis));
+            let size = resolve(*self.max.on(axis), parent).map_or(size, |max| size.min(max));
+            resolve(*self.min.on(axis), parent).map_or(size, |min| size.max(min))
+        };
+        Size { width: clamp(WIDTH), height: clamp(HEIGHT) }
+    }
+}
+
 /// A constraint on an axis of a terminal `Node` (ie: doesn't have a `Children` constraint).
 #[derive(Clone, Copy, PartialEq, Debug)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 pub enum LeafRule {
     /// The box's size is equal to its parent's size  times `f32`.
     /// (may not be above 1)
+    ///
+    /// See [`Rule::Children`] for when the parent's size depends on its children.
     Parent(f32),
 
     /// The box's size is equal to precisely `f32` pixels.
@@ -386,6 +1010,9 @@ pub enum LeafRule {
     ///
     /// [`add_content_sized`]: crate::content_sized::AppContentSizeExt::add_content_sized
     Content(f32),
+
+    /// The box's size is `f32` times the length of [`RootUnit`].
+    Root(f32, RootUnit),
 }
 impl Default for LeafRule {
     fn default() -> Self {
@@ -407,9 +1034,24 @@ pub enum Rule {
     ///
     /// The reverse is true for [`Flow::Vertical`].
     ///
-    /// > **IMPORTANT**: When [`Rule::Children`] is used on a container's size,
-    /// > none of its children may depend on its parent size. It would lead to
-    /// > a circular dependency.
+    /// When the container's [`Distribution`] [overlaps], children are on top of
+    /// each other, so the size of children is that of the largest child on
+    /// both axis.
+    ///
+    /// > **IMPORTANT**: When [`Rule::Children`] is used on a container's main
+    /// > axis, none of its children may depend on its parent size on this axis.
+    /// > It would lead to a circular dependency. Overlapping containers are the
+    /// > exception: their main axis works like the cross axis.
+    ///
+    /// On the cross axis, children depending on their parent size, with
+    /// [`Rule::Parent`] or [`LeafRule::Parent`], fit in the size given by
+    /// the other children. The container's size is computed from the children
+    /// not depending on it, then the remaining children are laid out within
+    /// this size. For example, a separator of `100%` width in a column fits
+    /// the width of the widest other node of the column. It is an error for
+    /// all children to depend on their parent size on the cross axis.
+    ///
+    /// [overlaps]: Distribution::overlaps
     Children(f32),
 
     /// The container's size is equal to its parent's size  times `f32`.
@@ -422,6 +1064,15 @@ pub enum Rule {
 
     /// The container's size is equal to precisely `f32` pixels.
     Fixed(f32),
+
+    /// The container's size is `f32` times the length of [`RootUnit`], for
+    /// example `Rule::Root(0.5, RootUnit::Vmin)` is half the smallest axis of
+    /// the node's [`Root`], written `50vmin` in chirp files.
+    ///
+    /// Like [`Self::Fixed`], it doesn't depend on the parent's or children's
+    /// size. Nodes are laid out again when the root's size or the
+    /// [`RootFontSize`] change.
+    Root(f32, RootUnit),
 }
 #[derive(Debug, Error)]
 pub enum RuleParseError {
@@ -436,9 +1087,11 @@ pub enum RuleParseError {
     BadPercent(f32),
     #[error("The provided ratio for '*' was out of range. {0:.3} ∉ [0..1] (inclusive)")]
     BadRatio(f32),
+    #[error("Provided a negative length ({0}), provide a positive value instead.")]
+    NegativeLength(f32),
     #[error(
-        "Rule format was not recognized: '{0}', rules end with '%', '*' or 'px'. \
-        Examples: '53%', '0.35*' and '1024px'"
+        "Rule format was not recognized: '{0}', rules end with '%', '*', 'px', 'vmin', \
+        'vmax' or 'em'. Examples: '53%', '1.5*', '1024px', '50vmin' and '2em'"
     )]
     BadFormat(String),
 }
@@ -463,6 +1116,12 @@ impl FromStr for Rule {
                 return Err(RuleParseError::BadPercent(percents));
             }
             Ok(Self::Parent(percents / 100.))
+        } else if let Some((length, unit, scale)) = RootUnit::strip_suffix(s) {
+            let length: f32 = length.parse().map_err(invalid)?;
+            if length < 0. {
+                return Err(RuleParseError::NegativeLength(length));
+            }
+            Ok(Self::Root(length * scale, unit))
         } else if let Some(child_ratio) = s.strip_suffix('*') {
             let ratio: f32 = child_ratio.parse().map_err(invalid)?;
             if ratio < 1. {
@@ -475,6 +1134,18 @@ impl FromStr for Rule {
     }
 }
 
+impl RootUnit {
+    /// `s` without its `vmin`, `vmax` or `em` suffix, the unit of the suffix,
+    /// and how many of the unit `1.0` in `s` is. `vmin` and `vmax` are
+    /// percentages, like in CSS.
+    fn strip_suffix(s: &str) -> Option<(&str, Self, f32)> {
+        let units = [("vmin", Self::Vmin, 0.01), ("vmax", Self::Vmax, 0.01), ("em", Self::Em, 1.)];
+        units.into_iter().find_map(|(suffix, unit, scale)| {
+            Some((s.strip_suffix(suffix)?, unit, scale))
+        })
+    }
+}
+
 impl LeafRule {
     #[cfg(feature = "dsl")]
     pub(crate) const fn from_rule(rule: Option<Rule>) -> Self {
@@ -483,22 +1154,24 @@ impl LeafRule {
             Some(Rule::Children(_)) | None => Self::Content(0.),
             Some(Rule::Fixed(v)) => Self::Fixed(v),
             Some(Rule::Parent(v)) => Self::Parent(v),
+            Some(Rule::Root(v, unit)) => Self::Root(v, unit),
         }
     }
     /// Compute effective size, given a potentially set parent container size.
-    fn inside(self, parent_size: Computed) -> Result<f32, Entity> {
+    fn inside(self, parent_size: Computed, root: &RootLengths) -> Result<f32, Entity> {
         use LeafRule::{Content, Fixed};
         match (self, parent_size) {
             (Self::Parent(ratio), Computed::Valid(value)) => Ok(value * ratio),
             (Self::Parent(_), Computed::ChildDefined(_, parent)) => Err(parent),
             (Fixed(fixed) | Content(fixed), _) => Ok(fixed),
+            (Self::Root(ratio, unit), _) => Ok(root.get(ratio, unit)),
         }
     }
 
     const fn parent_rule(self) -> Option<f32> {
         match self {
             Self::Parent(ratio) => Some(ratio),
-            Self::Fixed(_) | Self::Content(_) => None,
+            Self::Fixed(_) | Self::Content(_) | Self::Root(..) => None,
         }
     }
 }
@@ -506,16 +1179,22 @@ impl Rule {
     const fn parent_rule(self) -> Option<f32> {
         match self {
             Self::Parent(ratio) => Some(ratio),
-            Self::Children(_) | Self::Fixed(_) => None,
+            Self::Children(_) | Self::Fixed(_) | Self::Root(..) => None,
         }
     }
     /// Compute effective size, given a potentially set parent container size.
-    fn inside(self, parent_size: Computed, this: Entity) -> Result<Computed, Entity> {
+    fn inside(
+        self,
+        parent_size: Computed,
+        this: Entity,
+        root: &RootLengths,
+    ) -> Result<Computed, Entity> {
         use Computed::{ChildDefined, Valid};
         match (self, parent_size) {
             (Self::Parent(ratio), Valid(value)) => Ok(Valid(value * ratio)),
             (Self::Parent(_), ChildDefined(_, parent)) => Err(parent),
             (Self::Fixed(fixed), _) => Ok(Valid(fixed)),
+            (Self::Root(ratio, unit), _) => Ok(Valid(root.get(ratio, unit))),
             (Self::Children(ratio), ChildDefined(_, parent)) => Ok(ChildDefined(ratio, parent)),
             (Self::Children(ratio), _) => Ok(ChildDefined(ratio, this)),
         }
@@ -526,6 +1205,13 @@ impl Rule {
 ///
 /// [`WorldQuery`]: bevy::ecs::query::WorldQuery
 pub(crate) type NodeQuery = (Entity, &'static Node, Option<&'static Children>);
+/// The optional components of a [`Node`] that affect its layout.
+pub(crate) type NodeExtras = (
+    Option<&'static Anchored>,
+    Option<&'static CellSpan>,
+    Option<&'static SizeBounds>,
+    Has<CustomConstraint>,
+);
 
 /// The layouting algorithm's inner state.
 ///
@@ -543,19 +1229,122 @@ pub(crate) type NodeQuery = (Entity, &'static Node, Option<&'static Children>);
 pub struct Layout<'a, 'w, 's, F: ReadOnlyWorldQuery> {
     // This container's entity
     pub(crate) this: Entity,
-    pub(crate) to_update: &'a mut Query<'w, 's, &'static mut LayoutRect, F>,
+    pub(crate) to_update: Rects<'a, 'w, 's, F>,
     pub(crate) nodes: &'a Query<'w, 's, NodeQuery, F>,
     pub(crate) names: &'a Query<'w, 's, &'static Name>,
+    pub(crate) extras: &'a Query<'w, 's, NodeExtras>,
+    /// The registered [layout hooks](crate::hooks) and the world they read,
+    /// `None` when no hooks are registered.
+    pub(crate) hooks: Option<(&'a LayoutHooks, &'a World)>,
+    /// The lengths of [`RootUnit`]s in the root being laid out.
+    pub(crate) root: RootLengths,
+    /// The inner width of the closest ancestor which width is known.
+    available_width: Option<f32>,
+}
+
+/// The [`LayoutRect`]s of nodes, as updated by the layouting algorithm.
+///
+/// Containers which rects didn't change since the last layout are skipped
+/// with the [`LayoutCache`].
+pub(crate) struct Rects<'a, 'w, 's, F: ReadOnlyWorldQuery> {
+    storage: RectStorage<'a, 'w, 's, F>,
+    pub(crate) cache: &'a mut LayoutCache,
+    /// The width available to content-sized nodes with a [`LeafRule::Content`]
+    /// width, see [`crate::content_sized::ComputeContentSize::compute_content_within`].
+    available: &'a mut HashMap<Entity, Option<f32>>,
+    #[cfg(feature = "profiling")]
+    updated_count: usize,
+}
+/// Where [`Rects`] writes the updated [`LayoutRect`]s.
+pub(crate) enum RectStorage<'a, 'w, 's, F: ReadOnlyWorldQuery> {
+    /// Update the [`LayoutRect`] components directly.
+    Direct(&'a mut Query<'w, 's, &'static mut LayoutRect, F>),
+    /// Collect the updated rects, to apply them to the world once the whole
+    /// layout is computed, so that [layout hooks](crate::hooks) can read
+    /// the world.
+    Deferred {
+        current: &'a Query<'w, 's, &'static LayoutRect, F>,
+        updated: &'a mut HashMap<Entity, LayoutRect>,
+    },
+}
+impl<'a, 'w, 's, F: ReadOnlyWorldQuery> RectStorage<'a, 'w, 's, F> {
+    pub(crate) fn reborrow(&mut self) -> RectStorage<'_, 'w, 's, F> {
+        match self {
+            Self::Direct(rects) => RectStorage::Direct(rects),
+            Self::Deferred { current, updated } => RectStorage::Deferred { current, updated },
+        }
+    }
+}
+impl<'a, 'w, 's, F: ReadOnlyWorldQuery> Rects<'a, 'w, 's, F> {
+    pub(crate) fn new(
+        storage: RectStorage<'a, 'w, 's, F>,
+        cache: &'a mut LayoutCache,
+        available: &'a mut HashMap<Entity, Option<f32>>,
+    ) -> Self {
+        Self {
+            storage,
+            cache,
+            available,
+            #[cfg(feature = "profiling")]
+            updated_count: 0,
+        }
+    }
+    /// How many times a node was updated so far.
+    #[cfg(feature = "profiling")]
+    pub(crate) const fn updated_count(&self) -> usize {
+        self.updated_count
+    }
+    fn get(&self, entity: Entity) -> Option<&LayoutRect> {
+        match &self.storage {
+            RectStorage::Direct(rects) => rects.get(entity).ok(),
+            RectStorage::Deferred { current, updated } => {
+                updated.get(&entity).or_else(|| current.get(entity).ok())
+            }
+        }
+    }
+    /// `None` if `entity` has no `LayoutRect` component.
+    pub(crate) fn get_mut(&mut self, entity: Entity) -> Option<&mut LayoutRect> {
+        #[cfg(feature = "profiling")]
+        {
+            self.updated_count += 1;
+        }
+        match &mut self.storage {
+            RectStorage::Direct(rects) => rects.get_mut(entity).ok().map(Mut::into_inner),
+            RectStorage::Deferred { current, updated } => {
+                let current = current.get(entity).ok()?;
+                Some(updated.entry(entity).or_insert(*current))
+            }
+        }
+    }
 }
 
 impl<'a, 'w, 's, F: ReadOnlyWorldQuery> Layout<'a, 'w, 's, F> {
     pub(crate) fn new(
         this: Entity,
-        to_update: &'a mut Query<'w, 's, &'static mut LayoutRect, F>,
+        to_update: Rects<'a, 'w, 's, F>,
         nodes: &'a Query<'w, 's, NodeQuery, F>,
         names: &'a Query<'w, 's, &'static Name>,
+        extras: &'a Query<'w, 's, NodeExtras>,
+        hooks: Option<(&'a LayoutHooks, &'a World)>,
+        root: RootLengths,
     ) -> Self {
-        Self { this, to_update, nodes, names }
+        Self { this, to_update, nodes, names, extras, hooks, root, available_width: None }
+    }
+    /// Clamp the current node of `size` in a parent of size `parent` to its
+    /// [`SizeBounds`], then apply the [layout hooks](crate::hooks) if it has
+    /// a [`CustomConstraint`].
+    fn constrain(&self, size: Size<f32>, parent: Size<Computed>) -> Size<f32> {
+        let Ok((.., bounds, hooked)) = self.extras.get(self.this) else {
+            return size;
+        };
+        let size = match bounds {
+            Some(bounds) => bounds.clamp(size, parent, &self.root),
+            None => size,
+        };
+        match self.hooks {
+            Some((hooks, world)) if hooked => hooks.constrain(self.this, size, world),
+            _ => size,
+        }
     }
 
     /// Compute layout for a [`Container`].
@@ -568,46 +1357,373 @@ impl<'a, 'w, 's, F: ReadOnlyWorldQuery> Layout<'a, 'w, 's, F> {
     #[allow(clippy::cast_precision_loss)] // count as f32
     pub(crate) fn container(
         &mut self,
-        Container { flow, distrib, align, margin, .. }: Container,
-        children: &Children,
+        container: Container,
+        children: &[Entity],
         computed_size: Size<Computed>,
     ) -> Result<Size<f32>, error::Why> {
+        let extras = self.extras;
+        let is_anchored = |entity: &Entity| matches!(extras.get(*entity), Ok((Some(_), ..)));
+        if children.iter().any(is_anchored) {
+            let (anchored, in_flow): (Vec<_>, Vec<_>) =
+                children.iter().copied().partition(is_anchored);
+            let inner_size = self.container(container, &in_flow, computed_size)?;
+            self.anchored(container, &anchored, inner_size)?;
+            return Ok(inner_size);
+        }
+        if let Some(columns) = container.grid {
+            return self.grid(container, children, computed_size, columns.max(1));
+        }
+        let Container { flow, distrib, align, rules, aspect, wrap, .. } = container;
+        let offset_start = container.inset().start();
+        #[cfg(feature = "profiling_containers")]
+        let (span, updated_before) = {
+            let (name, empty) = (Handle::of(self), field::Empty);
+            let span = info_span!("layout_container", %name, children = empty, nodes = empty);
+            (span.entered(), self.to_update.updated_count())
+        };
         let mut child_size = Oriented { main: 0., cross: 0. };
         let mut children_count: u32 = 0;
 
+        let mut computed_size = computed_size;
+        let mut children_bounds = match aspect {
+            Some(ratio) => {
+                let count = self.nodes.iter_many(children).count();
+                if count > 1 {
+                    let count = u32::try_from(count).unwrap();
+                    return Err(error::Why::MediaBoxChildren(Handle::of(self), count));
+                }
+                computed_size.letterbox(ratio)
+            }
+            None => computed_size,
+        };
+        let overlaps = distrib.overlaps();
+        let add_child = |child_size: &mut Oriented<f32>, Oriented { main, cross }: Oriented<f32>| {
+            let main = if overlaps { child_size.main.max(main) } else { child_size.main + main };
+            *child_size = Oriented::new(main, child_size.cross.max(cross));
+        };
+        // When this container's size depends on its children, children
+        // depending on it on the cross axis (or the main axis of overlapping
+        // containers) are laid out last, within the size given by the other
+        // children. See `Rule::Children`.
+        let bounds = flow.relative(children_bounds);
+        let fits = Oriented {
+            main: overlaps && !matches!(bounds.main, Computed::Valid(_)),
+            cross: !matches!(bounds.cross, Computed::Valid(_)),
+        };
+        let dependent_axis = |node: &Node| {
+            let cross_axis = flow.perpendicular();
+            let cross = fits.cross && node.parent_rule(flow, cross_axis).is_some();
+            let main = fits.main && node.parent_rule(flow, flow).is_some();
+            cross.then_some(cross_axis).or(main.then_some(flow))
+        };
         let this_entity = self.this;
+        let mut first_dependent = None;
         for (this, node, children) in self.nodes.iter_many(children) {
+            if let Some(axis) = dependent_axis(node) {
+                first_dependent = first_dependent.or(Some((this, axis)));
+                continue;
+            }
             self.this = this;
-            let Oriented { main, cross } = self.leaf(node, children, flow, computed_size)?;
-            child_size.main += main;
-            child_size.cross = child_size.cross.max(cross);
+            let size = self.leaf(node, children, flow, children_bounds)?;
+            add_child(&mut child_size, size);
             children_count += 1;
         }
+        if let Some((dependent, axis)) = first_dependent {
+            if children_count == 0 {
+                self.this = dependent;
+                return Err(error::Why::bad_rule(axis, this_entity, self));
+            }
+            let fitted = flow.relative(computed_size).with_children(child_size);
+            let fit = |size: &mut Size<Computed>| {
+                let mut oriented = flow.relative(*size);
+                if fits.main {
+                    oriented.main = fitted.main.into();
+                }
+                if fits.cross {
+                    oriented.cross = fitted.cross.into();
+                }
+                *size = flow.absolute(oriented);
+            };
+            fit(&mut computed_size);
+            fit(&mut children_bounds);
+            for (this, node, children) in self.nodes.iter_many(children) {
+                if dependent_axis(node).is_none() {
+                    continue;
+                }
+                self.this = this;
+                let size = self.leaf(node, children, flow, children_bounds)?;
+                add_child(&mut child_size, size);
+                children_count += 1;
+            }
+        }
         self.this = this_entity;
+        #[cfg(feature = "profiling_containers")]
+        {
+            span.record("children", children_count);
+            span.record("nodes", self.to_update.updated_count() - updated_before);
+        }
+        // Content-sized children of rows can only take what their siblings left.
+        if matches!(flow, Flow::Horizontal) && !overlaps && !wrap {
+            for child in children {
+                let Some(width) = self.to_update.get(*child).map(|rect| rect.size.width) else {
+                    continue;
+                };
+                if let Some(Some(available)) = self.to_update.available.get_mut(child) {
+                    *available = (*available - (child_size.main - width)).max(0.);
+                }
+            }
+        }
+        let lines = match bounds.main {
+            Computed::Valid(max_main) if wrap && !overlaps => {
+                let sizes = self.nodes.iter_many(children).map(|(child, ..)| {
+                    let rect = self.to_update.get(child);
+                    rect.map_or(Oriented::new(0., 0.), |rect| flow.relative(rect.size))
+                });
+                let lines = wrap_lines(sizes, max_main);
+                child_size = lines.iter().fold(Oriented::new(0., 0.), |total, line| {
+                    Oriented::new(total.main.max(line.size.main), total.cross + line.size.cross)
+                });
+                Some(lines)
+            }
+            _ => None,
+        };
 
         let size = flow.relative(computed_size).with_children(child_size);
         // TODO(BUG): Warn on cross max exceeds & children dependence
         if !distrib.overlaps() {
-            self.validate_size(children, flow, child_size, size)?;
+            self.validate_size(children, flow, rules, child_size, size)?;
         }
 
         trace!("Setting offsets of children of {}", Handle::of(self));
+        if let Some(lines) = lines {
+            self.place_lines(container, children, size, child_size, &lines);
+            return Ok(flow.absolute(size));
+        }
         let single_child = children_count == 1;
         let count = children_count.saturating_sub(1).max(1) as f32;
         let cross_align = align.compute(size);
         let mut main_align = distrib.compute(size.main, child_size.main, single_child, count);
-        let mut iter = self.to_update.iter_many_mut(children);
-        while let Some(mut space) = iter.fetch_next() {
+        for child in children {
+            let Some(space) = self.to_update.get_mut(*child) else {
+                continue;
+            };
             let child_size = flow.relative(space.size);
 
             let offset = Oriented::new(
                 main_align.offset(child_size.main),
                 cross_align.offset(child_size.cross),
             );
-            space.pos = flow.absolute(offset) + margin;
+            space.pos = flow.absolute(offset) + offset_start;
+        }
+        Ok(flow.absolute(size))
+    }
+
+    /// Set the offsets of the `children` of a wrapping container, split in
+    /// `lines` of a total size of `lines_size`, see [`Container::wrap`].
+    #[allow(clippy::cast_precision_loss)] // count as f32
+    fn place_lines(
+        &mut self,
+        container: Container,
+        children: &[Entity],
+        size: Oriented<f32>,
+        lines_size: Oriented<f32>,
+        lines: &[WrapLine],
+    ) {
+        let Container { flow, align, distrib, .. } = container;
+        let offset_start = container.inset().start();
+        let nodes = self.nodes;
+        let mut children = nodes.iter_many(children);
+        let mut line_offset = align.offset(size.cross, lines_size.cross);
+        for line in lines {
+            let single_child = line.count == 1;
+            let count = line.count.saturating_sub(1).max(1) as f32;
+            let mut main_align = distrib.compute(size.main, line.size.main, single_child, count);
+            for (child, ..) in children.by_ref().take(line.count) {
+                let Some(space) = self.to_update.get_mut(child) else {
+                    continue;
+                };
+                let child_size = flow.relative(space.size);
+                let offset = Oriented::new(
+                    main_align.offset(child_size.main),
+                    line_offset + align.offset(line.size.cross, child_size.cross),
+                );
+                space.pos = flow.absolute(offset) + offset_start;
+            }
+            line_offset += line.size.cross;
+        }
+    }
+
+    /// Lay out `children` in the cells of a grid of `columns` cells per line,
+    /// see [`Container::grid`].
+    ///
+    /// Like [`Self::container`], `computed_size` and the returned size are
+    /// this container's _inner size_.
+    #[allow(clippy::cast_precision_loss)] // cell counts as f32
+    fn grid(
+        &mut self,
+        container: Container,
+        children: &[Entity],
+        computed_size: Size<Computed>,
+        columns: u32,
+    ) -> Result<Size<f32>, error::Why> {
+        use Computed::Valid;
+
+        let Container { flow, align, .. } = container;
+        let cells = self.grid_cells(children, columns);
+        let lines = cells.iter().map(|(_, cell, span)| cell.cross + span.cross).max();
+        let counts = Oriented::new(columns as usize, lines.unwrap_or(1) as usize);
+
+        // Like in overlapping containers, when the grid's size depends on its
+        // children, children depending on it are laid out last, within the
+        // tracks given by the other children.
+        let bounds = flow.relative(computed_size);
+        let track = |bound: Computed, count: usize| match bound {
+            Valid(size) => vec![size / count as f32; count],
+            Computed::ChildDefined(..) => vec![0.; count],
+        };
+        let mut tracks = Oriented {
+            main: track(bounds.main, counts.main),
+            cross: track(bounds.cross, counts.cross),
+        };
+        let fits = bounds.map(|bound| !matches!(bound, Valid(_)));
+        let dependent_axis = |node: &Node| {
+            let cross_axis = flow.perpendicular();
+            let cross = fits.cross && node.parent_rule(flow, cross_axis).is_some();
+            let main = fits.main && node.parent_rule(flow, flow).is_some();
+            cross.then_some(cross_axis).or(main.then_some(flow))
+        };
+        let bound_area = |bound: Computed, tracks: &[f32], start, span| match bound {
+            Valid(_) => Valid(track_area(tracks, start, span)),
+            Computed::ChildDefined(..) => bound,
+        };
+        let this_entity = self.this;
+        let mut sized = Vec::new();
+        let mut first_dependent = None;
+        for &(entity, cell, span) in &cells {
+            let Ok((_, node, children)) = self.nodes.get(entity) else {
+                continue;
+            };
+            if let Some(axis) = dependent_axis(node) {
+                first_dependent = first_dependent.or(Some((entity, axis)));
+                continue;
+            }
+            self.this = entity;
+            let bounds = Oriented::new(
+                bound_area(bounds.main, &tracks.main, cell.main, span.main),
+                bound_area(bounds.cross, &tracks.cross, cell.cross, span.cross),
+            );
+            let size = self.leaf(node, children, flow, flow.absolute(bounds))?;
+            sized.push((cell, span, size));
+        }
+        if fits.main {
+            let spans = sized.iter().map(|(cell, span, size)| (cell.main, span.main, size.main));
+            size_tracks(&mut tracks.main, spans);
+        }
+        if fits.cross {
+            let spans = sized.iter().map(|(cell, span, size)| (cell.cross, span.cross, size.cross));
+            size_tracks(&mut tracks.cross, spans);
+        }
+        let total = |tracks: &[f32]| tracks.iter().sum();
+        let size = bounds.with_children(Oriented::new(total(&tracks.main), total(&tracks.cross)));
+        fit_tracks(&mut tracks.main, size.main);
+        fit_tracks(&mut tracks.cross, size.cross);
+        let area = |cell: Oriented<u32>, span: Oriented<u32>| {
+            Oriented::new(
+                track_area(&tracks.main, cell.main, span.main),
+                track_area(&tracks.cross, cell.cross, span.cross),
+            )
+        };
+        if let Some((dependent, axis)) = first_dependent {
+            if sized.is_empty() {
+                self.this = dependent;
+                return Err(error::Why::bad_rule(axis, this_entity, self));
+            }
+            for &(entity, cell, span) in &cells {
+                let Ok((_, node, children)) = self.nodes.get(entity) else {
+                    continue;
+                };
+                if dependent_axis(node).is_none() {
+                    continue;
+                }
+                self.this = entity;
+                let bounds = flow.absolute(area(cell, span));
+                self.leaf(node, children, flow, bounds.into())?;
+            }
+        }
+        self.this = this_entity;
+
+        trace!("Setting offsets of children of {}", Handle::of(self));
+        for (entity, cell, span) in cells {
+            let Some(space) = self.to_update.get_mut(entity) else {
+                continue;
+            };
+            let (start, child_size) = (area(Oriented::new(0, 0), cell), flow.relative(space.size));
+            let area = area(cell, span);
+            let offset = Oriented::new(
+                start.main + align.offset(area.main, child_size.main),
+                start.cross + align.offset(area.cross, child_size.cross),
+            );
+            space.pos = flow.absolute(offset) + container.inset().start();
         }
         Ok(flow.absolute(size))
     }
+    /// The first cell of each of `children` in a grid of `columns` cells per
+    /// line, and how many cells it spans, see [`CellSpan`].
+    fn grid_cells(&self, children: &[Entity], columns: u32) -> Vec<GridCell> {
+        let mut taken = HashSet::new();
+        let is_free = |taken: &HashSet<_>, cell: Oriented<u32>, span: Oriented<u32>| {
+            let mut cells = (0..span.cross).flat_map(|line| (0..span.main).map(move |c| (c, line)));
+            cells.all(|(c, line)| !taken.contains(&(cell.main + c, cell.cross + line)))
+        };
+        let mut cells = Vec::new();
+        let mut cursor = Oriented::new(0, 0);
+        for (entity, ..) in self.nodes.iter_many(children) {
+            let span = self.extras.get(entity).ok().and_then(|e| e.1).copied().unwrap_or_default();
+            let span = Oriented::new(span.main.clamp(1, columns), span.cross.max(1));
+            while cursor.main + span.main > columns || !is_free(&taken, cursor, span) {
+                cursor = if cursor.main + span.main > columns {
+                    Oriented::new(0, cursor.cross + 1)
+                } else {
+                    Oriented::new(cursor.main + 1, cursor.cross)
+                };
+            }
+            for line in 0..span.cross {
+                taken.extend((0..span.main).map(|c| (cursor.main + c, cursor.cross + line)));
+            }
+            cells.push((entity, cursor, span));
+            cursor.main += span.main;
+        }
+        cells
+    }
+
+    /// Lay out the `anchored` children of `container`, out of its flow,
+    /// see [`Anchored`].
+    ///
+    /// `inner_size` is the container's _inner size_, as returned by
+    /// [`Self::container`] for its other children.
+    fn anchored(
+        &mut self,
+        container: Container,
+        anchored: &[Entity],
+        inner_size: Size<f32>,
+    ) -> Result<(), error::Why> {
+        let Container { flow, .. } = container;
+        let size = inner_size + container.inset().total();
+        let this_entity = self.this;
+        let nodes = self.nodes;
+        for (entity, node, children) in nodes.iter_many(anchored) {
+            let Ok((Some(&Anchored { anchor, offset }), ..)) = self.extras.get(entity) else {
+                continue;
+            };
+            self.this = entity;
+            let child_size = flow.absolute(self.leaf(node, children, flow, size.into())?);
+            if let Some(space) = self.to_update.get_mut(entity) {
+                space.pos = anchor.offset(size, child_size) + offset;
+            }
+        }
+        self.this = this_entity;
+        Ok(())
+    }
 
     fn leaf(
         &mut self,
@@ -616,33 +1732,71 @@ impl<'a, 'w, 's, F: ReadOnlyWorldQuery> Layout<'a, 'w, 's, F> {
         flow: Flow,
         parent: Size<Computed>,
     ) -> Result<Oriented<f32>, error::Why> {
+        let outer_available = self.available_width;
+        if let Computed::Valid(width) = parent.width {
+            self.available_width = Some(width);
+        }
         let size = match *node {
             Node::Container(container) => match children {
                 Some(children) => {
-                    let margin = container.margin;
-                    let computed_size = parent.container_size(&container, self);
-                    let inner_size = self.container(container, children, computed_size?)?;
-                    Size {
-                        width: margin.width.mul_add(2., inner_size.width),
-                        height: margin.height.mul_add(2., inner_size.height),
+                    let key = self.to_update.cache.key(self.this, parent, &self.root);
+                    match self.to_update.cache.get(self.this, key) {
+                        Some(size) => size,
+                        None => {
+                            let size = self.constrained_container(container, children, parent)?;
+                            self.to_update.cache.insert(self.this, key, size);
+                            size
+                        }
                     }
                 }
                 None => return Err(error::Why::ChildlessContainer(Handle::of(self))),
             },
-            Node::Axis(oriented) => parent.leaf_size(flow.absolute(oriented)).transpose(self)?,
-            Node::Box(size) => parent.leaf_size(size).transpose(self)?,
+            Node::Axis(oriented) => {
+                let size = parent.leaf_size(flow.absolute(oriented), &self.root).transpose(self)?;
+                self.constrain(size, parent)
+            }
+            Node::Box(size) => {
+                if let LeafRule::Content(_) = size.width {
+                    self.to_update.available.insert(self.this, self.available_width);
+                }
+                let size = parent.leaf_size(size, &self.root).transpose(self)?;
+                self.constrain(size, parent)
+            }
         };
+        self.available_width = outer_available;
         trace!("Setting size of {}", Handle::of(self));
-        if let Ok(mut to_update) = self.to_update.get_mut(self.this) {
+        if let Some(to_update) = self.to_update.get_mut(self.this) {
             to_update.size = size;
         }
         Ok(flow.relative(size))
     }
 
+    /// Lay out `container` in a parent of size `parent`, and clamp it to its
+    /// [`SizeBounds`]. Returns the container's size, margins included.
+    fn constrained_container(
+        &mut self,
+        container: Container,
+        children: &[Entity],
+        parent: Size<Computed>,
+    ) -> Result<Size<f32>, error::Why> {
+        let inset = container.inset().total();
+        let computed_size = parent.container_size(&container, self);
+        let inner_size = self.container(container, children, computed_size?)?;
+        let size = inner_size + inset;
+        let constrained = self.constrain(size, parent);
+        if constrained != size {
+            // Lay out the children again within the constrained size.
+            let inner_size = (constrained - inset).max(Size::ZERO);
+            self.container(container, children, inner_size.into())?;
+        }
+        Ok(constrained)
+    }
+
     fn validate_size(
         &self,
-        children: &Children,
+        children: &[Entity],
         flow: Flow,
+        rules: Size<Rule>,
         oriented_child_size: Oriented<f32>,
         oriented_size: Oriented<f32>,
     ) -> Result<(), error::Why> {
@@ -655,7 +1809,7 @@ impl<'a, 'w, 's, F: ReadOnlyWorldQuery> Layout<'a, 'w, 's, F> {
         let width_too_large = child_size.width > size.width;
         let axis = if width_too_large { WIDTH } else { HEIGHT };
         let largest_child = children.iter().max_by_key(|e| {
-            let Ok(LayoutRect { size, .. }) = self.to_update.get(**e) else {
+            let Some(LayoutRect { size, .. }) = self.to_update.get(**e) else {
                 return FloatOrd(0.);
             };
             FloatOrd(if width_too_large { size.width } else { size.height })
@@ -666,6 +1820,9 @@ impl<'a, 'w, 's, F: ReadOnlyWorldQuery> Layout<'a, 'w, 's, F> {
         });
         let relative_size = relative_size.sum();
         let largest_child = *largest_child.unwrap();
+        let largest_child_size = self.to_update.get(largest_child).map_or(0., |rect| {
+            axis.relative(rect.size).main
+        });
         Err(error::Why::ContainerOverflow {
             this: Handle::of(self),
             size,
@@ -673,7 +1830,63 @@ impl<'a, 'w, 's, F: ReadOnlyWorldQuery> Layout<'a, 'w, 's, F> {
             node_children_count: u32::try_from(self.nodes.iter_many(children).count()).unwrap(),
             child_size: axis.relative(child_size).main,
             largest_child: Handle::of_entity(largest_child, self.names),
+            largest_child_size,
+            offender: largest_child,
             child_relative_size: Relative::of(axis, flow, relative_size),
+            overflow: axis.relative(child_size).main - axis.relative(size).main,
+            rule: axis.relative(rules).main,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox() {
+        use Computed::{ChildDefined, Valid};
+        let entity = Entity::from_raw(0);
+        let size = |width, height| Size::<Computed>::new(width, height);
+
+        let wide = size(Valid(400.), Valid(100.)).letterbox(2.);
+        assert_eq!(wide, size(Valid(200.), Valid(100.)));
+        let tall = size(Valid(100.), Valid(400.)).letterbox(2.);
+        assert_eq!(tall, size(Valid(100.), Valid(50.)));
+        let child_height = size(Valid(160.), ChildDefined(1., entity)).letterbox(16. / 9.);
+        assert_eq!(child_height, size(Valid(160.), Valid(90.)));
+        let child_width = size(ChildDefined(1., entity), Valid(90.)).letterbox(16. / 9.);
+        assert_eq!(child_width, size(Valid(160.), Valid(90.)));
+    }
+    #[test]
+    fn snapped_adjacent_no_gap() {
+        let rect = |pos: f32, size: f32| LayoutRect {
+            pos: Size::new(pos, 0.3),
+            size: Size::new(size, 10.6),
+        };
+        let first = rect(10.4, 20.3).snapped();
+        let second = rect(30.7, 5.).snapped();
+
+        assert_eq!(first.pos, Size::new(10., 0.));
+        assert_eq!(first.size, Size::new(21., 11.));
+        assert_eq!(first.pos.width + first.size.width, second.pos.width);
+    }
+    #[test]
+    fn rect_helpers() {
+        let rect = LayoutRect { pos: Size::new(10., 20.), size: Size::new(30., 40.) };
+        assert_eq!(Rect::from(rect), Rect::new(10., 20., 40., 60.));
+        assert_eq!(LayoutRect::from(rect.to_rect()), rect);
+        assert!(rect.contains(Vec2::new(40., 20.)));
+        assert!(!rect.contains(Vec2::new(41., 20.)));
+
+        let other = LayoutRect { pos: Size::new(30., 50.), size: Size::new(30., 30.) };
+        let overlap = LayoutRect { pos: Size::new(30., 50.), size: Size::new(10., 10.) };
+        assert_eq!(rect.intersection(&other), Some(overlap));
+        assert_eq!(rect.intersection(&(other * 2.)), None);
+
+        let expanded = LayoutRect { pos: Size::new(5., 22.), size: Size::new(40., 36.) };
+        assert_eq!(rect.expand(Size::new(5., -2.)), expanded);
+        assert_eq!(rect.expand(Size::all(-20.)).size, Size::ZERO);
+        assert_eq!((rect * 0.5).to_rect(), Rect::new(5., 10., 20., 30.));
+    }
+}
@@ -1,5 +1,6 @@
 /*!
 [`Alignment`]: Alignment
+[`AppLeafNodeExt::add_leaf_component`]: AppLeafNodeExt::add_leaf_component
 [`bevy-inspector-egui`]: bevy-inspector-egui
 [`Children`]: bevy::prelude::Children
 [`Component`]: Component
@@ -24,18 +25,25 @@
 use bevy::app::{App, Plugin as BevyPlugin, Update};
 use bevy::ecs::prelude::*;
 
-pub use alignment::{Alignment, Distribution};
+pub use alignment::{Alignment, Anchor, AnchorParseError, Distribution};
 #[cfg(feature = "dsl")]
 pub use cuicui_dsl::{dsl, DslBundle};
-pub use direction::{Flow, Oriented, Size};
+pub use direction::{Flow, Oriented, Sides, Size};
 #[cfg(feature = "dsl")]
 pub use dsl::LayoutDsl;
 pub use error::ComputeLayoutError;
-pub use labels::{ComputeLayout, ComputeLayoutSet};
-pub use layout::{Container, LayoutRect, LeafRule, Node, Root, Rule};
+pub use hooks::{AppLayoutHookExt, CustomConstraint};
+pub use labels::{ComputeLayout, ComputeLayoutSet, LayoutOutputSet, RootSizeSet, UpdateLeafNodes};
+pub use layout::{
+    Anchored, CellSpan, Container, ContainerBuilder, LayoutRect, LeafRule, Node, PixelSnap, Root,
+    RootBuilder, RootFontSize, RootUnit, Rule, SizeBounds,
+};
+pub use root_size::RootSize;
+pub use setup::{AppCuicuiExt, Backend, CuicuiConfig};
 pub use systems::{
-    compute_layout, require_layout_recompute, update_leaf_nodes, LastLayoutChange,
-    LayoutRootCamera, LeafNode, LeafNodeInsertWitness, ScreenRoot,
+    compute_hooked_layout, compute_layout, require_layout_recompute, update_leaf_nodes,
+    AppLeafNodeExt, LastLayoutChange, LayoutRootCamera, LeafNode, LeafNodeInsertWitness,
+    ScreenRoot,
 };
 
 mod alignment;
@@ -45,73 +53,221 @@ mod labels;
 mod layout;
 mod systems;
 
+pub mod attach;
 pub mod bundles;
+pub mod cache;
 pub mod content_sized;
+pub mod coords;
 #[cfg(feature = "debug")]
 pub mod debug;
 #[cfg(feature = "dsl")]
 pub mod dsl;
+pub mod hooks;
+pub mod root_size;
+pub mod setup;
+#[cfg(feature = "show_if")]
+pub mod show_if;
+#[cfg(feature = "sort_children")]
+pub mod sort_children;
+pub mod states;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// Functions to simplify using [`dsl::LayoutDsl`].
 #[cfg(feature = "dsl")]
 pub mod dsl_functions {
-    pub use crate::dsl::{child, pct, px};
+    pub use crate::dsl::{child, em, pct, px, vmax, vmin};
+}
+
+/// The most common `cuicui_layout` items, `use cuicui_layout::prelude::*;`
+/// to import them.
+///
+/// This includes the [`dsl!`] macro, [`LayoutDsl`], [`dsl::LayoutDslTrait`]
+/// and the [`dsl_functions`].
+/// The [`Plugin`] is exported as `LayoutPlugin`, to not conflict with bevy's
+/// `Plugin` trait.
+pub mod prelude {
+    #[cfg(feature = "dsl")]
+    pub use crate::dsl_functions::*;
+    #[cfg(feature = "dsl")]
+    pub use crate::{dsl, dsl::LayoutDslTrait, DslBundle, LayoutDsl};
+    pub use crate::{Alignment, Distribution, Flow, LayoutRect, LayoutRootCamera, LeafRule};
+    pub use crate::{Plugin as LayoutPlugin, Root, RootSize, Rule, ScreenRoot, Size};
+    #[cfg(feature = "dsl")]
+    pub use cuicui_dsl::EntityCommands;
 }
 
 /// Add layout-related sets and systems to the `Update` schedule.
 ///
 /// This adds:
 /// - [`compute_layout`] system as member of [`ComputeLayout`] and
-///   [`ComputeLayoutSet`].
-/// - [`ComputeLayout`]: this set only contains `compute_layout`.
+///   [`ComputeLayoutSet`], or [`compute_hooked_layout`] when
+///   [layout hooks](hooks) are registered.
+/// - [`ComputeLayout`]: this set only contains `compute_layout` and
+///   `compute_hooked_layout`.
 /// - [`ComputeLayoutSet`]: contains `compute_layout` and
 ///   [content-sized](content_sized::ComputeContentSize) systems.
+/// - [`UpdateLeafNodes`]: contains [`update_leaf_nodes`] and systems added
+///   with [`AppLeafNodeExt::add_leaf_component`].
+/// - [`root_size::update_root_size`]: sets the size of [`Root`]s with a
+///   [`RootSize`] component, member of [`RootSizeSet`].
+/// - [`states::update_state_nodes`]: swaps the [`Node`] of nodes with
+///   [`StateNodes`](states::StateNodes), before [`ComputeLayoutSet`].
+///
+/// ## System sets
+///
+/// Order your systems against those sets rather than against individual
+/// systems. In `Update`, they run in this order:
+///
+/// 1. [`RootSizeSet`]: set the size of [`Root`]s.
+/// 2. [`ComputeLayoutSet`], which contains, in order:
+///    1. [`UpdateLeafNodes`]: add or remove [`LeafNode`]s.
+///    2. [`ContentSizedComputeSystemSet`]: compute the size of
+///       content-sized nodes.
+///    3. [`ComputeLayout`]: compute the [`LayoutRect`]s.
+///    4. [`ContentSizedRemeasureSet`]: measure again content-sized nodes
+///       within the width the layout gave them.
+///    5. `compute_layout` or `compute_hooked_layout` again, if a node
+///       measured in the previous step changed size.
+/// 3. [`LayoutOutputSet`]: backend systems applying the `LayoutRect`s. The
+///    `LayoutRect`s computed this frame are visible to systems in this set.
+///
+/// [`ContentSizedComputeSystemSet`]: content_sized::ContentSizedComputeSystemSet
+/// [`ContentSizedRemeasureSet`]: content_sized::ContentSizedRemeasureSet
 ///
 /// ## Features
 ///
 /// When the `"reflect"` feature is enabled, also register all the layouting
 /// types used by `cuicui_layout`.
+///
+/// With the `"profiling"` feature, the layout of each [`Root`] is wrapped in
+/// a `layout_root` [`info_span!`], with the count of laid out nodes in the
+/// `nodes` field. Use it with a profiler such as Tracy to find which layout
+/// roots are expensive. The `"profiling_containers"` feature also adds
+/// a `layout_container` span for each container, with `children` and `nodes`
+/// fields.
+///
+/// [`info_span!`]: bevy::log::info_span
 pub struct Plugin;
 
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<LastLayoutChange>()
-            .init_resource::<LeafNodeInsertWitness>();
+            .init_resource::<LeafNodeInsertWitness>()
+            .init_resource::<content_sized::DefaultImageScale>()
+            .init_resource::<content_sized::ContentSizeEpsilon>()
+            .init_resource::<hooks::LayoutHooks>()
+            .init_resource::<PixelSnap>()
+            .init_resource::<cache::LayoutCacheStats>()
+            .init_resource::<content_sized::ContentWidths>()
+            .init_resource::<RootFontSize>();
         let should_update = LeafNodeInsertWitness::new(true);
         app.add_systems(
             Update,
             (
                 compute_layout
                     .run_if(require_layout_recompute)
+                    .run_if(hooks::no_layout_hooks)
+                    .in_set(ComputeLayout)
+                    .in_set(ComputeLayoutSet),
+                compute_hooked_layout
+                    .run_if(require_layout_recompute)
+                    .run_if(not(hooks::no_layout_hooks))
                     .in_set(ComputeLayout)
                     .in_set(ComputeLayoutSet),
-                (
-                    update_leaf_nodes,
-                    apply_deferred.run_if(resource_exists_and_equals(should_update)),
-                )
-                    .chain()
+                // Lay out again nodes measured within their actual width.
+                compute_layout
+                    .run_if(require_layout_recompute)
+                    .run_if(hooks::no_layout_hooks)
+                    .after(content_sized::ContentSizedRemeasureSet)
+                    .in_set(ComputeLayoutSet),
+                compute_hooked_layout
+                    .run_if(require_layout_recompute)
+                    .run_if(not(hooks::no_layout_hooks))
+                    .after(content_sized::ContentSizedRemeasureSet)
+                    .in_set(ComputeLayoutSet),
+                apply_deferred
+                    .run_if(not(hooks::no_layout_hooks))
+                    .after(ComputeLayout)
+                    .before(content_sized::ContentSizedRemeasureSet)
+                    .in_set(ComputeLayoutSet),
+                update_leaf_nodes
+                    .in_set(UpdateLeafNodes)
+                    .in_set(ComputeLayoutSet),
+                apply_deferred
+                    .run_if(resource_exists_and_equals(should_update))
+                    .after(UpdateLeafNodes)
                     .in_set(ComputeLayoutSet)
                     .before(content_sized::ContentSizedComputeSystemSet),
+                root_size::update_root_size.in_set(RootSizeSet),
+                states::update_state_nodes.before(ComputeLayoutSet),
+                // `compute_hooked_layout` sets `LayoutRect`s with a command.
+                apply_deferred.after(ComputeLayoutSet).before(LayoutOutputSet),
+            ),
+        )
+        .configure_sets(
+            Update,
+            (
+                RootSizeSet.before(ComputeLayoutSet),
+                UpdateLeafNodes.before(content_sized::ContentSizedComputeSystemSet),
+                content_sized::ContentSizedComputeSystemSet.before(ComputeLayout),
+                LayoutOutputSet.after(ComputeLayoutSet),
             ),
         );
         #[cfg(feature = "debug")]
         app.add_plugins(debug::Plugin);
 
+        #[cfg(feature = "show_if")]
+        app.init_resource::<show_if::UiFlags>().add_systems(
+            bevy::app::PostUpdate,
+            show_if::sync_show_if.before(bevy::render::view::VisibilitySystems::VisibilityPropagate),
+        );
+        #[cfg(feature = "sort_children")]
+        app.add_systems(Update, sort_children::sort_children.before(ComputeLayoutSet));
+
         #[cfg(feature = "reflect")]
         app.register_type::<Alignment>()
+            .register_type::<Anchor>()
+            .register_type::<Anchored>()
+            .register_type::<attach::AttachmentPoint>()
+            .register_type::<CellSpan>()
             .register_type::<Container>()
             .register_type::<Distribution>()
+            .register_type::<content_sized::DefaultImageScale>()
+            .register_type::<content_sized::ImageScale>()
+            .register_type::<content_sized::ImageSizeHint>()
+            .register_type::<content_sized::PlaceholderSize>()
+            .register_type::<content_sized::ContentSizeFactor>()
+            .register_type::<content_sized::ContentSizeEpsilon>()
+            .register_type::<CustomConstraint>()
             .register_type::<Flow>()
             .register_type::<LeafNode>()
             .register_type::<LeafRule>()
             .register_type::<Node>()
             .register_type::<Oriented<LeafRule>>()
             .register_type::<LayoutRect>()
+            .register_type::<PixelSnap>()
             .register_type::<Root>()
+            .register_type::<RootFontSize>()
+            .register_type::<RootSize>()
+            .register_type::<RootUnit>()
             .register_type::<Rule>()
             .register_type::<ScreenRoot>()
+            .register_type::<Sides<f32>>()
             .register_type::<Size<f32>>()
             .register_type::<Size<LeafRule>>()
-            .register_type::<Size<Rule>>();
+            .register_type::<Size<Option<Rule>>>()
+            .register_type::<Size<Rule>>()
+            .register_type::<SizeBounds>()
+            .register_type::<states::NodeState>()
+            .register_type::<states::StateNodes>();
+        #[cfg(all(feature = "reflect", feature = "show_if"))]
+        app.register_type::<show_if::ShowIf>().register_type::<show_if::UiFlags>();
+        #[cfg(all(feature = "reflect", feature = "sort_children"))]
+        app.register_type::<sort_children::SortChildren>();
+    }
+    #[cfg(feature = "chirp")]
+    fn finish(&self, app: &mut App) {
+        cuicui_chirp::loader::check_loader_plugin(app);
     }
 }
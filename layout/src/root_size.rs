@@ -0,0 +1,227 @@
+//! Set the size of [`Root`] containers from something else than a camera.
+//!
+//! The size of a [`ScreenRoot`] follows the viewport of the [`LayoutRootCamera`].
+//! This is handled by the backend crates (`cuicui_layout_bevy_{ui,sprite}`).
+//!
+//! This module defines other sources for the size of a [`Root`]:
+//!
+//! - [`RootSize::Fixed`]: a fixed size, in pixels.
+//! - [`RootSize::Window`]: a percentage of the primary window's logical size.
+//! - Any component implementing [`ProvideRootSize`], once registered with
+//!   [`AppRootSizeExt::add_root_size`]. Use this when the root bounds come
+//!   from somewhere else, such as an editor panel, or the size of an image
+//!   used as render target.
+//!
+//! Only use a single source per `Root`. Do not add those components to a
+//! [`ScreenRoot`], otherwise both the camera and the other source will try
+//! to set the root's size.
+//!
+//! # Example
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use cuicui_layout::root_size::{AppRootSizeExt, ProvideRootSize};
+//! use cuicui_layout::Size;
+//!
+//! /// The size of the editor panel our UI is displayed in.
+//! #[derive(Component)]
+//! struct PanelSize(Vec2);
+//!
+//! impl ProvideRootSize for PanelSize {
+//!     fn root_size(&self) -> Option<Size<f32>> {
+//!         Some(self.0.into())
+//!     }
+//! }
+//! fn build(app: &mut App) {
+//!     app.add_root_size::<PanelSize>();
+//! }
+//! ```
+//!
+//! [`LayoutRootCamera`]: crate::LayoutRootCamera
+use bevy::app::{App, Update};
+use bevy::ecs::prelude::*;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::window::{PrimaryWindow, Window};
+
+use crate::{Root, RootSizeSet, ScreenRoot, Size};
+
+/// Set the size of the [`Root`] on the same entity.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub enum RootSize {
+    /// A fixed size, in pixels.
+    Fixed(Size<f32>),
+    /// A percentage of the primary window's logical size,
+    /// `Size::all(100.)` is the whole window.
+    Window(Size<f32>),
+}
+impl Default for RootSize {
+    /// The whole primary window.
+    fn default() -> Self {
+        Self::Window(Size::all(100.))
+    }
+}
+
+/// A [`Component`] providing the size of the [`Root`] on the same entity.
+///
+/// Register it with [`AppRootSizeExt::add_root_size`], see the
+/// [module-level documentation](self) for details.
+pub trait ProvideRootSize: Component {
+    /// The size of the `Root`, in pixels.
+    ///
+    /// `None` leaves the current size unchanged.
+    fn root_size(&self) -> Option<Size<f32>>;
+}
+
+/// Extends [`App`] to support adding [`ProvideRootSize`] components.
+pub trait AppRootSizeExt {
+    /// Set the size of [`Root`]s with a `P` component to [`P::root_size`],
+    /// whenever `P` changes.
+    ///
+    /// [`P::root_size`]: ProvideRootSize::root_size
+    fn add_root_size<P: ProvideRootSize>(&mut self) -> &mut Self;
+}
+impl AppRootSizeExt for App {
+    fn add_root_size<P: ProvideRootSize>(&mut self) -> &mut Self {
+        self.add_systems(Update, update_provided_root_size::<P>.in_set(RootSizeSet))
+    }
+}
+
+/// Set `root`'s size to `size`, without triggering change detection if
+/// the size didn't change.
+fn set_root_size(mut root: Mut<Root>, size: Size<f32>) {
+    if root.size() != size {
+        let bounds = root.size_mut();
+        *bounds.width = size.width;
+        *bounds.height = size.height;
+    }
+}
+
+/// Set the size of [`Root`]s with a [`RootSize`] component.
+///
+/// This runs every frame, but only changes `Root`s when their size changes.
+pub fn update_root_size(
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut roots: Query<(&mut Root, &RootSize), Without<ScreenRoot>>,
+) {
+    let window = window.get_single().ok();
+    for (root, size) in &mut roots {
+        let size = match *size {
+            RootSize::Fixed(size) => size,
+            RootSize::Window(percent) => {
+                let Some(window) = window else {
+                    continue;
+                };
+                let window = Size::new(window.width(), window.height());
+                Size::new(
+                    window.width * percent.width / 100.,
+                    window.height * percent.height / 100.,
+                )
+            }
+        };
+        set_root_size(root, size);
+    }
+}
+
+/// Set the size of [`Root`]s with a `P` component, see [`AppRootSizeExt::add_root_size`].
+pub fn update_provided_root_size<P: ProvideRootSize>(
+    mut roots: Query<(&mut Root, &P), (Changed<P>, Without<ScreenRoot>)>,
+) {
+    for (root, provider) in &mut roots {
+        if let Some(size) = provider.root_size() {
+            set_root_size(root, size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::default;
+
+    use super::*;
+    use crate::Flow;
+
+    #[derive(Component)]
+    struct PanelSize(Option<Size<f32>>);
+    impl ProvideRootSize for PanelSize {
+        fn root_size(&self) -> Option<Size<f32>> {
+            self.0
+        }
+    }
+    fn root() -> Root {
+        Root::new(
+            Size::all(10.),
+            Flow::Horizontal,
+            default(),
+            default(),
+            Size::ZERO,
+        )
+    }
+    fn size(world: &World, entity: Entity) -> Size<f32> {
+        world.get::<Root>(entity).unwrap().size()
+    }
+    fn is_changed(world: &World, entity: Entity) -> bool {
+        world.entity(entity).get_ref::<Root>().unwrap().is_changed()
+    }
+
+    #[test]
+    fn fixed_and_window_sizes() {
+        let mut world = World::new();
+        let resolution = (800., 600.).into();
+        world.spawn((Window { resolution, ..default() }, PrimaryWindow));
+        let fixed = RootSize::Fixed(Size::new(120., 40.));
+        let fixed = world.spawn((root(), fixed)).id();
+        let window = RootSize::Window(Size::new(50., 25.));
+        let window = world.spawn((root(), window)).id();
+        let screen = world.spawn((root(), RootSize::default(), ScreenRoot)).id();
+
+        world.run_system_once(update_root_size);
+
+        assert_eq!(size(&world, fixed), Size::new(120., 40.));
+        assert_eq!(size(&world, window), Size::new(400., 150.));
+        assert_eq!(size(&world, screen), Size::all(10.));
+
+        world.clear_trackers();
+        world.run_system_once(update_root_size);
+        assert!(!is_changed(&world, fixed));
+        assert!(!is_changed(&world, window));
+    }
+
+    #[test]
+    fn window_size_without_window() {
+        let mut world = World::new();
+        let window = world.spawn((root(), RootSize::default())).id();
+
+        world.run_system_once(update_root_size);
+
+        assert_eq!(size(&world, window), Size::all(10.));
+    }
+
+    #[test]
+    fn provided_size() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_provided_root_size::<PanelSize>);
+        let panel = PanelSize(Some(Size::new(300., 200.)));
+        let panel = world.spawn((root(), panel)).id();
+        let none = world.spawn((root(), PanelSize(None))).id();
+
+        schedule.run(&mut world);
+
+        assert_eq!(size(&world, panel), Size::new(300., 200.));
+        assert_eq!(size(&world, none), Size::all(10.));
+
+        // Only changed providers update the root.
+        *world.get_mut::<Root>(panel).unwrap().size_mut().width = 50.;
+        schedule.run(&mut world);
+        assert_eq!(size(&world, panel).width, 50.);
+
+        world.get_mut::<PanelSize>(panel).unwrap().0 = Some(Size::all(80.));
+        schedule.run(&mut world);
+        assert_eq!(size(&world, panel), Size::all(80.));
+    }
+}
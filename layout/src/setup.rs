@@ -0,0 +1,259 @@
+//! Add all the plugins required to use `cuicui` in a single call.
+//!
+//! Using `cuicui` requires adding a backend plugin (such as
+//! `cuicui_layout_bevy_ui::Plugin`), the chirp loader plugin for your DSL and
+//! optionally the debug overlay. [`AppCuicuiExt::add_cuicui`] adds them all,
+//! and checks that they are compatible with each other.
+//!
+//! ```ignore
+//! use bevy::prelude::*;
+//! use cuicui_layout::{AppCuicuiExt, CuicuiConfig};
+//! use cuicui_layout_bevy_ui::{UiBackend, UiDsl};
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_cuicui::<UiDsl>(CuicuiConfig {
+//!         backend: UiBackend,
+//!         chirp: true,
+//!         debug: cfg!(debug_assertions),
+//!     });
+//! ```
+//!
+//! # Validation
+//!
+//! `add_cuicui` panics when:
+//!
+//! - `chirp` is `true`, but the `chirp` feature of `cuicui_layout` is disabled.
+//! - `debug` is `true`, but the `debug` feature of `cuicui_layout` is disabled.
+//! - It was already called with a different configuration, for example with
+//!   a different backend or DSL.
+//! - [`crate::Plugin`] was already added by something else than `backend`,
+//!   for example by another backend's plugin.
+//!
+//! Calling it several times with the same configuration is fine.
+//!
+//! When `debug` is `false` but the `debug` feature is enabled, the debug
+//! overlay is disabled.
+use std::any::type_name;
+
+use bevy::app::App;
+use bevy::ecs::prelude::Resource;
+use bevy::utils::get_short_name;
+
+/// The DSL of [`AppCuicuiExt::add_cuicui`].
+///
+/// With the `chirp` feature, this is [`cuicui_chirp::ParseDsl`], so that
+/// chirp files can be loaded with it.
+#[cfg(feature = "chirp")]
+pub trait SetupDsl: cuicui_chirp::ParseDsl + 'static {}
+#[cfg(feature = "chirp")]
+impl<D: cuicui_chirp::ParseDsl + 'static> SetupDsl for D {}
+
+/// The DSL of [`AppCuicuiExt::add_cuicui`].
+///
+/// With the `chirp` feature, this is `cuicui_chirp::ParseDsl`, so that
+/// chirp files can be loaded with it.
+#[cfg(not(feature = "chirp"))]
+pub trait SetupDsl: 'static {}
+#[cfg(not(feature = "chirp"))]
+impl<D: 'static> SetupDsl for D {}
+
+/// A `cuicui_layout` integration with a rendering crate.
+///
+/// `cuicui_layout_bevy_ui::UiBackend` and `cuicui_layout_bevy_sprite::SpriteBackend`
+/// implement this trait.
+pub trait Backend: 'static {
+    /// Add the plugins of this backend to `app`.
+    ///
+    /// This should add [`crate::Plugin`].
+    fn build(&self, app: &mut App);
+    /// Whether the plugins of this backend were already added to `app`.
+    fn is_added(&self, app: &App) -> bool;
+}
+
+/// What to add to the app in [`AppCuicuiExt::add_cuicui`].
+#[derive(Clone, Copy, Debug)]
+pub struct CuicuiConfig<B> {
+    /// The backend to use, such as `cuicui_layout_bevy_ui::UiBackend`.
+    pub backend: B,
+    /// Whether to load `.chirp` files with the DSL passed to `add_cuicui`.
+    ///
+    /// This requires the `chirp` feature.
+    pub chirp: bool,
+    /// Whether to enable the debug overlay.
+    ///
+    /// This requires the `debug` feature.
+    pub debug: bool,
+}
+impl<B: Default> Default for CuicuiConfig<B> {
+    /// Enable `chirp` and `debug` if their feature is enabled, `debug` is only
+    /// enabled in debug builds.
+    fn default() -> Self {
+        Self {
+            backend: B::default(),
+            chirp: cfg!(feature = "chirp"),
+            debug: cfg!(all(feature = "debug", debug_assertions)),
+        }
+    }
+}
+
+/// The configuration passed to the first `add_cuicui` call.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+struct CuicuiSetup {
+    backend: &'static str,
+    dsl: &'static str,
+    chirp: bool,
+    debug: bool,
+}
+
+/// Add all the plugins required to use `cuicui`, see the [module docs](self).
+pub trait AppCuicuiExt {
+    /// Add `config.backend`, the chirp loader for `D` if `config.chirp`
+    /// and the debug overlay if `config.debug`.
+    ///
+    /// # Panics
+    /// When the configuration is invalid, see the [module docs](self#validation).
+    fn add_cuicui<D: SetupDsl>(&mut self, config: CuicuiConfig<impl Backend>) -> &mut Self;
+}
+impl AppCuicuiExt for App {
+    fn add_cuicui<D: SetupDsl>(&mut self, config: CuicuiConfig<impl Backend>) -> &mut Self {
+        let CuicuiConfig { backend, chirp, debug } = config;
+        let setup = CuicuiSetup {
+            backend: backend_name(&backend),
+            dsl: type_name::<D>(),
+            chirp,
+            debug,
+        };
+        let short = |name: &str| get_short_name(name);
+        assert!(
+            !chirp || cfg!(feature = "chirp"),
+            "add_cuicui: `chirp` is true, but the `cuicui_layout/chirp` feature is disabled",
+        );
+        assert!(
+            !debug || cfg!(feature = "debug"),
+            "add_cuicui: `debug` is true, but the `cuicui_layout/debug` feature is disabled",
+        );
+        if let Some(previous) = self.world.get_resource::<CuicuiSetup>() {
+            assert!(
+                *previous == setup,
+                "add_cuicui was called twice with different configurations: \
+                first with the {} backend and the {} DSL (chirp: {}, debug: {}), \
+                then with the {} backend and the {} DSL (chirp: {chirp}, debug: {debug}). \
+                A cuicui app can only use a single configuration.",
+                short(previous.backend),
+                short(previous.dsl),
+                previous.chirp,
+                previous.debug,
+                short(setup.backend),
+                short(setup.dsl),
+            );
+            return self;
+        }
+        if !backend.is_added(self) {
+            assert!(
+                !self.is_plugin_added::<crate::Plugin>(),
+                "add_cuicui: cuicui_layout::Plugin was already added, but not by the \
+                {} backend. Is another backend plugin already added?",
+                short(setup.backend),
+            );
+            backend.build(self);
+        }
+        #[cfg(feature = "chirp")]
+        if chirp {
+            self.add_plugins(cuicui_chirp::loader::Plugin::new::<D>());
+        }
+        #[cfg(feature = "debug")]
+        if !debug {
+            self.world.remove_resource::<crate::debug::Options>();
+        }
+        self.insert_resource(setup)
+    }
+}
+fn backend_name<B: Backend>(_: &B) -> &'static str {
+    type_name::<B>()
+}
+
+#[cfg(all(test, feature = "dsl"))]
+mod tests {
+    use bevy::app::{App, Plugin};
+    use cuicui_dsl::BaseDsl;
+
+    use super::*;
+    use crate::dsl::LayoutDsl;
+
+    struct TestBackend;
+    impl Backend for TestBackend {
+        fn build(&self, app: &mut App) {
+            app.add_plugins(crate::Plugin);
+        }
+        fn is_added(&self, app: &App) -> bool {
+            app.is_plugin_added::<crate::Plugin>()
+        }
+    }
+    struct OtherPlugin;
+    impl Plugin for OtherPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_plugins(crate::Plugin);
+        }
+    }
+    struct OtherBackend;
+    impl Backend for OtherBackend {
+        fn build(&self, app: &mut App) {
+            app.add_plugins(OtherPlugin);
+        }
+        fn is_added(&self, app: &App) -> bool {
+            app.is_plugin_added::<OtherPlugin>()
+        }
+    }
+    fn config<B>(backend: B) -> CuicuiConfig<B> {
+        CuicuiConfig { backend, chirp: false, debug: false }
+    }
+
+    #[test]
+    fn same_config_twice() {
+        let mut app = App::new();
+        app.add_cuicui::<LayoutDsl>(config(TestBackend));
+        app.add_cuicui::<LayoutDsl>(config(TestBackend));
+        assert!(app.is_plugin_added::<crate::Plugin>());
+    }
+
+    #[test]
+    #[should_panic(expected = "called twice with different configurations")]
+    fn different_dsl() {
+        let mut app = App::new();
+        app.add_cuicui::<LayoutDsl>(config(TestBackend));
+        app.add_cuicui::<BaseDsl>(config(TestBackend));
+    }
+
+    #[test]
+    #[should_panic(expected = "called twice with different configurations")]
+    fn different_backend() {
+        let mut app = App::new();
+        app.add_cuicui::<LayoutDsl>(config(TestBackend));
+        app.add_cuicui::<LayoutDsl>(config(OtherBackend));
+    }
+
+    #[test]
+    #[should_panic(expected = "cuicui_layout::Plugin was already added")]
+    fn plugin_added_by_other_backend() {
+        let mut app = App::new();
+        app.add_plugins(crate::Plugin);
+        app.add_cuicui::<LayoutDsl>(config(OtherBackend));
+    }
+
+    #[test]
+    #[cfg(not(feature = "chirp"))]
+    #[should_panic(expected = "`cuicui_layout/chirp` feature is disabled")]
+    fn chirp_without_feature() {
+        let chirp = CuicuiConfig { chirp: true, ..config(TestBackend) };
+        App::new().add_cuicui::<LayoutDsl>(chirp);
+    }
+
+    #[test]
+    #[cfg(not(feature = "debug"))]
+    #[should_panic(expected = "`cuicui_layout/debug` feature is disabled")]
+    fn debug_without_feature() {
+        let debug = CuicuiConfig { debug: true, ..config(TestBackend) };
+        App::new().add_cuicui::<LayoutDsl>(debug);
+    }
+}
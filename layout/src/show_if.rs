@@ -0,0 +1,126 @@
+//! Bind the [`Visibility`] of nodes to named boolean flags, see [`UiFlags`].
+
+use bevy::ecs::prelude::*;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent, ReflectResource};
+use bevy::render::view::Visibility;
+use bevy::utils::HashMap;
+
+/// Named boolean flags, to show and hide nodes with a [`ShowIf`] component.
+///
+/// Nodes with `ShowIf(name)` are shown when the `name` flag is `true` and
+/// hidden otherwise. Flags not set yet are `false`.
+///
+/// The binding goes both ways: when a system changes the [`Visibility`] of
+/// a `ShowIf` node, its flag is updated, so are the other nodes bound to the
+/// same flag.
+///
+/// Hidden nodes are only invisible, they still take space in the layout.
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_layout::show_if::UiFlags;
+///
+/// fn toggle_pause_menu(keys: Res<Input<KeyCode>>, mut flags: ResMut<UiFlags>) {
+///     if keys.just_pressed(KeyCode::Escape) {
+///         flags.toggle("paused");
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Resource))]
+pub struct UiFlags(HashMap<String, bool>);
+impl UiFlags {
+    /// The value of flag `name`, `false` if it is not set.
+    #[must_use]
+    pub fn get(&self, name: &str) -> bool {
+        self.0.get(name).copied().unwrap_or(false)
+    }
+    /// Set flag `name` to `value`.
+    pub fn set(&mut self, name: impl Into<String>, value: bool) {
+        self.0.insert(name.into(), value);
+    }
+    /// Invert the value of flag `name`, returning the new value.
+    pub fn toggle(&mut self, name: &str) -> bool {
+        let value = !self.get(name);
+        self.set(name, value);
+        value
+    }
+}
+
+/// Show this node only when the [`UiFlags`] flag of this name is `true`.
+///
+/// Use [`LayoutDsl::show_if`] to add it with the `dsl!` macro or in chirp files.
+///
+/// [`LayoutDsl::show_if`]: crate::dsl::LayoutDsl::show_if
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct ShowIf(pub String);
+
+const fn shown_visibility(shown: bool) -> Visibility {
+    if shown {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+/// Synchronize the [`Visibility`] of [`ShowIf`] nodes with their [`UiFlags`].
+///
+/// First, nodes which `Visibility` was changed by another system set their
+/// flag, then nodes that don't match their flag are updated.
+pub fn sync_show_if(
+    mut flags: ResMut<UiFlags>,
+    mut nodes: Query<(Ref<ShowIf>, &mut Visibility)>,
+) {
+    let mut any_added = false;
+    for (show_if, visibility) in &mut nodes {
+        any_added |= show_if.is_added();
+        if !visibility.is_changed() || show_if.is_added() {
+            continue;
+        }
+        let shown = *visibility != Visibility::Hidden;
+        if flags.get(&show_if.0) != shown {
+            flags.set(show_if.0.clone(), shown);
+        }
+    }
+    if !flags.is_changed() && !any_added {
+        return;
+    }
+    for (show_if, mut visibility) in &mut nodes {
+        let shown = flags.get(&show_if.0);
+        if (*visibility != Visibility::Hidden) != shown {
+            *visibility = shown_visibility(shown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::Schedule;
+
+    use super::*;
+
+    #[test]
+    fn sync_both_ways() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(sync_show_if);
+        world.init_resource::<UiFlags>();
+        let menu = world.spawn((ShowIf("menu".into()), Visibility::Inherited)).id();
+        let other = world.spawn((ShowIf("menu".into()), Visibility::Visible)).id();
+
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Visibility>(menu), Some(&Visibility::Hidden));
+        assert_eq!(world.get::<Visibility>(other), Some(&Visibility::Hidden));
+
+        world.resource_mut::<UiFlags>().set("menu", true);
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Visibility>(menu), Some(&Visibility::Inherited));
+
+        *world.get_mut::<Visibility>(other).unwrap() = Visibility::Hidden;
+        schedule.run(&mut world);
+        assert!(!world.resource::<UiFlags>().get("menu"));
+        assert_eq!(world.get::<Visibility>(menu), Some(&Visibility::Hidden));
+    }
+}
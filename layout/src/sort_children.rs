@@ -0,0 +1,214 @@
+//! Order the children of a node by the value of one of their components,
+//! see [`SortChildren`].
+
+use std::cmp::Ordering;
+
+use bevy::ecs::prelude::*;
+use bevy::ecs::reflect::{AppTypeRegistry, ReflectComponent};
+use bevy::ecs::world::EntityRef;
+use bevy::log::error;
+use bevy::prelude::{Children, Name};
+use bevy::reflect::{GetPath, Reflect, TypeRegistry};
+use bevy::utils::HashMap;
+
+/// Sort the children of this node by the value of a component of the children.
+///
+/// `path` is the name of a component, followed by the path of a field within
+/// this component, such as `Score.0` or `Player.stats.level`. The component
+/// must be registered with [`App::register_type`] and reflect [`Component`].
+/// Fields can be numbers, strings or [`Name`]s. A `Name` component can be used
+/// directly, with `Name` as `path`.
+///
+/// Children are sorted in `ascending` or descending order. Children without
+/// this component or field are placed last, in both orders. Children with the
+/// same value keep their order.
+///
+/// Children are sorted by [`sort_children`] each frame before layout, so that
+/// they are re-sorted when their value changes. The [`Children`] component
+/// only changes when the order changes.
+///
+/// Use [`LayoutDsl::sort_children_by`] to spawn nodes with this component.
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_layout::sort_children::SortChildren;
+///
+/// #[derive(Component, Reflect, Default)]
+/// #[reflect(Component)]
+/// struct Score(u32);
+///
+/// fn spawn_leaderboard(mut cmds: Commands) {
+///     let sort = SortChildren { path: "Score.0".to_owned(), ascending: false };
+///     cmds.spawn(sort).with_children(|cmds| {
+///         cmds.spawn(Score(12));
+///         cmds.spawn(Score(530));
+///     });
+/// }
+/// ```
+///
+/// [`App::register_type`]: bevy::app::App::register_type
+/// [`LayoutDsl::sort_children_by`]: crate::dsl::LayoutDsl::sort_children_by
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct SortChildren {
+    /// The component name and field path of the value to sort by.
+    pub path: String,
+    /// Whether the smallest values come first.
+    pub ascending: bool,
+}
+
+/// The value of a child to sort by.
+#[derive(Debug, Clone, PartialEq)]
+enum SortKey {
+    Number(f64),
+    Text(String),
+    Missing,
+}
+impl SortKey {
+    #[allow(clippy::cast_precision_loss)] // integers as f64 is fine for sorting
+    fn of(value: &dyn Reflect) -> Self {
+        macro_rules! numbers {
+            ($($number:ty),*) => {$(
+                if let Some(number) = value.downcast_ref::<$number>() {
+                    return Self::Number(*number as f64);
+                }
+            )*};
+        }
+        numbers!(f32, f64, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+        if let Some(text) = value.downcast_ref::<String>() {
+            return Self::Text(text.clone());
+        }
+        if let Some(name) = value.downcast_ref::<Name>() {
+            return Self::Text(name.as_str().to_owned());
+        }
+        Self::Missing
+    }
+    /// Compare two keys, `Missing` keys are always last.
+    fn cmp(&self, other: &Self, ascending: bool) -> Ordering {
+        let ordering = match (self, other) {
+            (Self::Missing, Self::Missing) => return Ordering::Equal,
+            (Self::Missing, _) => return Ordering::Greater,
+            (_, Self::Missing) => return Ordering::Less,
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            (Self::Number(_), Self::Text(_)) => Ordering::Less,
+            (Self::Text(_), Self::Number(_)) => Ordering::Greater,
+        };
+        if ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+}
+
+/// A parsed [`SortChildren::path`].
+struct KeyPath<'a> {
+    component: &'a ReflectComponent,
+    field: &'a str,
+}
+impl<'a> KeyPath<'a> {
+    fn new(path: &'a str, registry: &'a TypeRegistry) -> Option<Self> {
+        let name_len = path.find(['.', '#', '[']).unwrap_or(path.len());
+        let (name, field) = path.split_at(name_len);
+        let registration = registry
+            .get_with_short_type_path(name)
+            .or_else(|| registry.get_with_type_path(name))?;
+        Some(Self { component: registration.data::<ReflectComponent>()?, field })
+    }
+    fn key(&self, entity: EntityRef) -> SortKey {
+        let Some(component) = self.component.reflect(entity) else {
+            return SortKey::Missing;
+        };
+        if self.field.is_empty() {
+            return SortKey::of(component);
+        }
+        component.reflect_path(self.field).map_or(SortKey::Missing, SortKey::of)
+    }
+}
+
+/// Reorder the [`Children`] of [`SortChildren`] nodes.
+///
+/// An error is logged when the component of [`SortChildren::path`] is not
+/// registered, or doesn't reflect `Component`.
+pub fn sort_children(
+    world: &mut World,
+    parents: &mut QueryState<(Entity, Ref<SortChildren>, &Children)>,
+) {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let mut to_sort = Vec::new();
+    for (entity, sort, children) in parents.iter(world) {
+        let Some(key_path) = KeyPath::new(&sort.path, &registry) else {
+            if sort.is_changed() {
+                let path = &sort.path;
+                error!("Can't sort children by '{path}': the component is not registered");
+            }
+            continue;
+        };
+        let key = |child: &Entity| key_path.key(world.entity(*child));
+        let keys: Vec<_> = children.iter().map(key).collect();
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by(|a, b| keys[*a].cmp(&keys[*b], sort.ascending));
+        if order.iter().enumerate().any(|(i, sorted)| i != *sorted) {
+            let ranks = order.iter().enumerate().map(|(rank, i)| (children[*i], rank));
+            let ranks: HashMap<_, _> = ranks.collect();
+            to_sort.push((entity, ranks));
+        }
+    }
+    for (entity, ranks) in to_sort {
+        if let Some(mut children) = world.get_mut::<Children>(entity) {
+            children.sort_by_key(|child| ranks[child]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::Schedule;
+    use bevy::prelude::BuildWorldChildren;
+
+    use super::*;
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Score(u32);
+
+    #[test]
+    fn sort_by_score() {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world.resource::<AppTypeRegistry>().write().register::<Score>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(sort_children);
+
+        let sort = SortChildren { path: "Score.0".to_owned(), ascending: false };
+        let mut children = Vec::new();
+        let parent = world
+            .spawn(sort)
+            .with_children(|cmds| {
+                children.push(cmds.spawn(Score(10)).id());
+                children.push(cmds.spawn(()).id());
+                children.push(cmds.spawn(Score(30)).id());
+                children.push(cmds.spawn(Score(20)).id());
+            })
+            .id();
+        let order = |world: &World| world.get::<Children>(parent).unwrap().to_vec();
+
+        schedule.run(&mut world);
+        assert_eq!(order(&world), [children[2], children[3], children[0], children[1]]);
+
+        world.get_mut::<Score>(children[0]).unwrap().0 = 40;
+        schedule.run(&mut world);
+        assert_eq!(order(&world), [children[0], children[2], children[3], children[1]]);
+
+        let tick = world.change_tick();
+        schedule.run(&mut world);
+        let current = world.entity(parent).get_ref::<Children>().unwrap();
+        assert!(!current.last_changed().is_newer_than(tick, world.change_tick()));
+
+        world.get_mut::<SortChildren>(parent).unwrap().ascending = true;
+        schedule.run(&mut world);
+        assert_eq!(order(&world), [children[3], children[2], children[0], children[1]]);
+    }
+}
@@ -0,0 +1,169 @@
+//! Swap the [`Node`] of a node while it is hovered, pressed or focused,
+//! see [`StateNodes`].
+//!
+//! For example, a button can be slightly larger while hovered. Changing the
+//! `Node` triggers a relayout, as any other change to layout components.
+//!
+//! `cuicui_layout` doesn't know about interactions: backends or your own
+//! systems set the [`NodeState`] of nodes. `cuicui_layout_bevy_ui` sets it
+//! based on `bevy_ui`'s `Interaction`. Set [`NodeState::focused`] yourself,
+//! for example with a navigation plugin.
+//!
+//! Use the [`LayoutDsl::hover`], [`LayoutDsl::press`] and [`LayoutDsl::focus`]
+//! methods to spawn nodes with alternate `Node`s.
+//!
+//! [`LayoutDsl::hover`]: crate::dsl::LayoutDsl::hover
+//! [`LayoutDsl::press`]: crate::dsl::LayoutDsl::press
+//! [`LayoutDsl::focus`]: crate::dsl::LayoutDsl::focus
+
+use bevy::ecs::prelude::*;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+use crate::Node;
+
+/// The interaction state of a node, selecting which of its [`StateNodes`]
+/// is its [`Node`].
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct NodeState {
+    /// The cursor is over this node.
+    pub hovered: bool,
+    /// This node is being pressed.
+    pub pressed: bool,
+    /// This node is focused.
+    pub focused: bool,
+}
+
+/// Alternate [`Node`]s of a node, used while it is in a given [`NodeState`].
+///
+/// When the `NodeState` of the node changes, [`update_state_nodes`] replaces
+/// its `Node` by the one for this state. Changes made to the `Node` by other
+/// systems are lost on the next state change.
+///
+/// The `Node` of a pressed node is `pressed`, `hovered` if `pressed` is `None`.
+/// `hovered` has priority over `focused`. Nodes in none of those states,
+/// or without a `Node` for their state, use `idle`.
+#[derive(Component, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct StateNodes {
+    /// The `Node` of this node when no other `Node` applies.
+    pub idle: Node,
+    /// The `Node` of this node while it is hovered.
+    pub hovered: Option<Node>,
+    /// The `Node` of this node while it is pressed.
+    pub pressed: Option<Node>,
+    /// The `Node` of this node while it is focused.
+    pub focused: Option<Node>,
+}
+impl StateNodes {
+    /// The [`Node`] to use in `state`.
+    #[must_use]
+    pub fn node(&self, state: NodeState) -> Node {
+        let pressed = self.pressed.filter(|_| state.pressed);
+        let hovered = self.hovered.filter(|_| state.hovered || state.pressed);
+        let focused = self.focused.filter(|_| state.focused);
+        pressed.or(hovered).or(focused).unwrap_or(self.idle)
+    }
+}
+
+/// Set the [`Node`] of nodes with [`StateNodes`] based on their [`NodeState`].
+#[allow(clippy::type_complexity)]
+pub fn update_state_nodes(
+    mut nodes: Query<
+        (&NodeState, &StateNodes, &mut Node),
+        Or<(Changed<NodeState>, Changed<StateNodes>)>,
+    >,
+) {
+    for (state, state_nodes, mut node) in &mut nodes {
+        *node = state_nodes.node(*state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::Schedule;
+    use bevy::prelude::default;
+
+    use super::*;
+    use crate::{LeafRule, Size};
+
+    fn fixed(pixels: f32) -> Node {
+        Node::Box(Size::all(LeafRule::Fixed(pixels)))
+    }
+    fn width(node: Node) -> f32 {
+        let Node::Box(Size { width: LeafRule::Fixed(width), .. }) = node else {
+            panic!("Expected a fixed box, got {node:?}");
+        };
+        width
+    }
+
+    #[test]
+    fn state_priority() {
+        let nodes = StateNodes {
+            idle: fixed(1.),
+            hovered: Some(fixed(2.)),
+            pressed: None,
+            focused: Some(fixed(4.)),
+        };
+        let state = |hovered, pressed, focused| NodeState { hovered, pressed, focused };
+        assert_eq!(width(nodes.node(state(false, false, false))), 1.);
+        assert_eq!(width(nodes.node(state(true, false, true))), 2.);
+        assert_eq!(width(nodes.node(state(false, true, false))), 2.);
+        assert_eq!(width(nodes.node(state(false, false, true))), 4.);
+        let nodes = StateNodes { pressed: Some(fixed(3.)), ..nodes };
+        assert_eq!(width(nodes.node(state(true, true, false))), 3.);
+    }
+
+    #[test]
+    fn swap_on_state_change() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_state_nodes);
+        let nodes = StateNodes { idle: fixed(1.), hovered: Some(fixed(2.)), ..default() };
+        let entity = world.spawn((nodes, NodeState::default(), fixed(1.))).id();
+
+        world.get_mut::<NodeState>(entity).unwrap().hovered = true;
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Node>(entity).copied().map(width), Some(2.));
+
+        world.get_mut::<NodeState>(entity).unwrap().hovered = false;
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Node>(entity).copied().map(width), Some(1.));
+    }
+
+    #[cfg(feature = "chirp")]
+    #[test]
+    fn hover_methods() {
+        use bevy::ecs::system::CommandQueue;
+        use cuicui_dsl::{BaseDsl, DslBundle};
+
+        use crate::{dsl::StateMethods, LayoutDsl, Rule, Sides};
+
+        assert!("width(220px) bg(red)".parse::<StateMethods>().is_err());
+        assert!("width(220px".parse::<StateMethods>().is_err());
+
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut cmds = Commands::new(&mut queue, &world);
+        let mut dsl = LayoutDsl::<BaseDsl>::default();
+        dsl.hover("width(220px) margin(10)".parse().unwrap());
+        dsl.row();
+        dsl.width(Rule::Fixed(200.));
+        let entity = cmds.spawn_empty().id();
+        dsl.insert(&mut cmds.entity(entity));
+        queue.apply(&mut world);
+
+        let nodes = world.get::<StateNodes>(entity).unwrap();
+        let (Node::Container(idle), Some(Node::Container(hovered))) = (nodes.idle, nodes.hovered)
+        else {
+            panic!("Expected containers, got {nodes:?}");
+        };
+        assert_eq!(idle.rules.width, Rule::Fixed(200.));
+        assert_eq!(hovered.rules.width, Rule::Fixed(220.));
+        assert_eq!(hovered.margin, Sides::all(10.));
+        assert_eq!(hovered.flow, idle.flow);
+        assert!(nodes.pressed.is_none());
+        assert_eq!(world.get::<NodeState>(entity), Some(&NodeState::default()));
+    }
+}
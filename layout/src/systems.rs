@@ -1,13 +1,25 @@
 #![allow(clippy::needless_pass_by_value)]
 
+use bevy::app::{App, Update};
 use bevy::ecs::{component::Tick, prelude::*, system::SystemChangeTick};
+use bevy::utils::HashMap;
 use bevy::prelude::{debug, Children, Name, Parent};
+#[cfg(feature = "profiling")]
+use bevy::{log::info_span, utils::tracing::field};
 #[cfg(feature = "reflect")]
 use bevy::prelude::{Reflect, ReflectComponent};
 use bevy_mod_sysfail::sysfail;
 
-use crate::layout::{Layout, NodeQuery};
-use crate::{error::Computed, ComputeLayoutError, LayoutRect, Node, Root, Size};
+use crate::cache::{LayoutCache, LayoutCacheStats};
+use crate::content_sized::ContentWidths;
+use crate::hooks::{CustomConstraint, LayoutHooks};
+use crate::layout::{Layout, NodeExtras, NodeQuery, RectStorage, Rects, RootLengths};
+#[cfg(feature = "profiling")]
+use crate::error::Handle;
+use crate::error::{Computed, Why};
+use crate::{Anchored, CellSpan, ComputeLayoutError, LayoutRect, LeafRule, Node, Root, Size};
+use crate::{RootFontSize, SizeBounds};
+use crate::{ComputeLayoutSet, UpdateLeafNodes};
 
 /// A [`Node`] that can't have children.
 #[derive(Component, Clone, Copy, Debug, Default)]
@@ -23,6 +35,8 @@ pub struct LeafNode;
 pub struct LayoutRootCamera;
 
 /// Set this [`Root`] to track the [`LayoutRootCamera`]'s size.
+///
+/// See [`crate::root_size`] for other ways to set the size of a `Root`.
 #[derive(Component, Clone, Copy, Debug, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
 pub struct ScreenRoot;
@@ -32,13 +46,34 @@ pub struct ScreenRoot;
 #[derive(Resource, Default)]
 pub struct LastLayoutChange {
     tick: Option<Tick>,
+    overflow: Option<Entity>,
+    error: Option<String>,
 }
 impl LastLayoutChange {
+    fn new(tick: Tick, result: &Result<(), Why>) -> Self {
+        let overflow = match result {
+            Err(Why::ContainerOverflow { offender, .. }) => Some(*offender),
+            _ => None,
+        };
+        let error = result.as_ref().err().map(ToString::to_string);
+        Self { tick: Some(tick), overflow, error }
+    }
     /// The last time [`compute_layout`] ran.
     #[must_use]
     pub const fn tick(&self) -> Option<Tick> {
         self.tick
     }
+    /// The largest child of the container that overflowed the last time
+    /// [`compute_layout`] ran, if any.
+    #[must_use]
+    pub const fn overflow(&self) -> Option<Entity> {
+        self.overflow
+    }
+    /// The error of the last time [`compute_layout`] ran, if it failed.
+    #[must_use]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
 }
 
 type LayoutRef = (
@@ -46,6 +81,10 @@ type LayoutRef = (
     Option<Ref<'static, Root>>,
     Option<Ref<'static, Children>>,
     Option<Ref<'static, Parent>>,
+    Option<Ref<'static, CustomConstraint>>,
+    Option<Ref<'static, CellSpan>>,
+    Option<Ref<'static, Anchored>>,
+    Option<Ref<'static, SizeBounds>>,
 );
 
 /// A run condition to tell whether it's necessary to recompute layout.
@@ -55,56 +94,227 @@ pub fn require_layout_recompute(
     nodes: Query<NodeQuery>,
     anything_changed: Query<LayoutRef, Or<(With<Node>, With<Root>)>>,
     last_layout_change: Res<LastLayoutChange>,
+    font_size: Option<Res<RootFontSize>>,
     system_tick: SystemChangeTick,
     mut children_removed: RemovedComponents<Children>,
     mut parent_removed: RemovedComponents<Parent>,
+    mut anchored_removed: RemovedComponents<Anchored>,
+    mut bounds_removed: RemovedComponents<SizeBounds>,
+    mut span_removed: RemovedComponents<CellSpan>,
+    mut constraint_removed: RemovedComponents<CustomConstraint>,
 ) -> bool {
     let Some(tick) = last_layout_change.tick else {
         return true;
     };
     let this_tick = system_tick.this_run();
+    if font_size.is_some_and(|f| f.last_changed().is_newer_than(tick, this_tick)) {
+        return true;
+    }
     let anything_changed = anything_changed.iter().any(|q| {
         matches!(q.0, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
             || matches!(q.1, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
             || matches!(q.2, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
             || matches!(q.3, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
+            || matches!(q.4, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
+            || matches!(q.5, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
+            || matches!(q.6, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
+            || matches!(q.7, Some(r) if r.last_changed().is_newer_than(tick, this_tick))
     });
     let mut children_removed = || children_removed.read().any(|e| nodes.contains(e));
     let mut parent_removed = || parent_removed.read().any(|e| nodes.contains(e));
+    let mut anchored_removed = || anchored_removed.read().any(|e| nodes.contains(e));
+    let mut bounds_removed = || bounds_removed.read().any(|e| nodes.contains(e));
+    let mut span_removed = || span_removed.read().any(|e| nodes.contains(e));
+    let mut constraint_removed = || constraint_removed.read().any(|e| nodes.contains(e));
 
-    anything_changed || children_removed() || parent_removed()
+    anything_changed
+        || children_removed()
+        || parent_removed()
+        || anchored_removed()
+        || bounds_removed()
+        || span_removed()
+        || constraint_removed()
 }
 
 /// Run the layout algorithm.
+///
+/// Containers which subtree didn't change are skipped, see [`crate::cache`].
+/// When [layout hooks](crate::hooks) are registered, [`compute_hooked_layout`]
+/// runs instead.
 #[sysfail(log(level = "error"))]
 pub fn compute_layout(
-    mut to_update: Query<&'static mut LayoutRect>,
+    mut rects: Query<&'static mut LayoutRect>,
     nodes: Query<NodeQuery>,
     names: Query<&'static Name>,
+    extras: Query<NodeExtras>,
     roots: Query<(Entity, &'static Root, &'static Children)>,
+    font_size: Option<Res<RootFontSize>>,
     mut last_layout_change: ResMut<LastLayoutChange>,
+    stats: Option<ResMut<LayoutCacheStats>>,
+    widths: Option<ResMut<ContentWidths>>,
+    mut state: Local<LayoutState>,
     system_tick: SystemChangeTick,
 ) -> Result<(), ComputeLayoutError> {
     debug!("Computing layout");
-    last_layout_change.tick = Some(system_tick.this_run());
-    for (entity, root, children) in &roots {
-        let root_container = *root.get();
-        let bounds = root.get_size(entity, &names)?;
-        if let Ok(mut to_update) = to_update.get_mut(entity) {
-            to_update.size = bounds;
+    state.clear_if_stale(last_layout_change.tick);
+    let queries = (&nodes, &names, &extras, None, font_size.as_deref());
+    let rects = RectStorage::Direct(&mut rects);
+    let result = layout_roots(queries, rects, &roots, &mut state);
+
+    let tick = system_tick.this_run();
+    state.last_run = Some(tick);
+    *last_layout_change = LastLayoutChange::new(tick, &result);
+    if let Some(mut stats) = stats {
+        *stats = state.cache.stats;
+    }
+    if let Some(mut widths) = widths {
+        widths.set_available(state.available.drain(), |entity| nodes.contains(entity));
+    }
+    result?;
+    Ok(())
+}
+
+/// Run the layout algorithm, when [layout hooks](crate::hooks) are registered.
+///
+/// The computed [`LayoutRect`]s are applied with a command, so that
+/// hooks can read the [`World`]. Otherwise the same as [`compute_layout`].
+#[sysfail(log(level = "error"))]
+pub fn compute_hooked_layout(
+    world: &World,
+    rects: Query<&'static LayoutRect>,
+    nodes: Query<NodeQuery>,
+    names: Query<&'static Name>,
+    extras: Query<NodeExtras>,
+    roots: Query<(Entity, &'static Root, &'static Children)>,
+    hooks: Res<LayoutHooks>,
+    mut updated: Local<HashMap<Entity, LayoutRect>>,
+    mut state: Local<LayoutState>,
+    mut cmds: Commands,
+    system_tick: SystemChangeTick,
+) -> Result<(), ComputeLayoutError> {
+    debug!("Computing layout with hooks");
+    updated.clear();
+    state.clear_if_stale(world.get_resource().and_then(LastLayoutChange::tick));
+    let queries = (&nodes, &names, &extras, Some((&*hooks, world)), world.get_resource());
+    let rects = RectStorage::Deferred { current: &rects, updated: &mut updated };
+    let result = layout_roots(queries, rects, &roots, &mut state);
+
+    let tick = system_tick.this_run();
+    state.last_run = Some(tick);
+    let last_layout_change = LastLayoutChange::new(tick, &result);
+    let stats = state.cache.stats;
+    let rects: Vec<_> = updated.drain().collect();
+    let available: Vec<_> = state.available.drain().collect();
+    cmds.add(move |world: &mut World| {
+        for (entity, rect) in rects {
+            if let Some(mut current) = world.get_mut::<LayoutRect>(entity) {
+                current.set_if_neq(rect);
+            }
         }
-        let mut layout = Layout::new(entity, &mut to_update, &nodes, &names);
-        let mut bounds: Size<Computed> = bounds.into();
-        bounds.set_margin(root_container.margin, &layout)?;
-        layout.container(root_container, children, bounds)?;
+        *world.resource_mut::<LastLayoutChange>() = last_layout_change;
+        world.insert_resource(stats);
+        if world.contains_resource::<ContentWidths>() {
+            world.resource_scope(|world, mut widths: Mut<ContentWidths>| {
+                widths.set_available(available, |entity| world.get::<Node>(entity).is_some());
+            });
+        }
+    });
+    result?;
+    Ok(())
+}
+/// The state of [`compute_layout`] and [`compute_hooked_layout`] kept
+/// between runs.
+#[derive(Default)]
+pub(crate) struct LayoutState {
+    cache: LayoutCache,
+    /// The width available to content-sized nodes, see [`ContentWidths`].
+    available: HashMap<Entity, Option<f32>>,
+    /// When this system last ran.
+    last_run: Option<Tick>,
+}
+impl LayoutState {
+    /// Forget the cached layouts when another system laid out since the
+    /// last run of this one, `last_layout` being the last time a layout ran.
+    ///
+    /// The [`LayoutRect`]s may then differ from what the cache remembers.
+    fn clear_if_stale(&mut self, last_layout: Option<Tick>) {
+        if self.last_run != last_layout {
+            self.cache = LayoutCache::default();
+        }
+    }
+}
+type LayoutQueries<'a, 'w, 's> = (
+    &'a Query<'w, 's, NodeQuery>,
+    &'a Query<'w, 's, &'static Name>,
+    &'a Query<'w, 's, NodeExtras>,
+    Option<(&'a LayoutHooks, &'a World)>,
+    Option<&'a RootFontSize>,
+);
+/// Lay out all `roots` but the [`Root::paused`] ones, returning the error of
+/// the first root that failed.
+///
+/// Roots after a failing root are still laid out, so that a broken layout
+/// doesn't prevent others, such as an error panel, from being displayed.
+fn layout_roots<'w, 's>(
+    queries: LayoutQueries<'_, 'w, 's>,
+    mut rects: RectStorage<'_, 'w, 's, ()>,
+    roots: &Query<(Entity, &'static Root, &'static Children)>,
+    state: &mut LayoutState,
+) -> Result<(), Why> {
+    let LayoutState { cache, available, .. } = state;
+    cache.clear_run();
+    let mut result = Ok(());
+    for root in roots.iter().filter(|(_, root, _)| !root.paused) {
+        let to_update = Rects::new(rects.reborrow(), cache, available);
+        let root_result = layout_root(queries, to_update, root);
+        result = result.and(root_result);
+    }
+    cache.retain_run();
+    result
+}
+fn layout_root<'w, 's>(
+    (nodes, names, extras, hooks, font_size): LayoutQueries<'_, 'w, 's>,
+    to_update: Rects<'_, 'w, 's, ()>,
+    (entity, root, children): (Entity, &Root, &Children),
+) -> Result<(), Why> {
+    #[cfg(feature = "profiling")]
+    let span = {
+        let (name, empty) = (Handle::of_entity(entity, names), field::Empty);
+        info_span!("layout_root", %name, nodes = empty).entered()
+    };
+    let root_container = *root.get();
+    let size = root.get_size(entity, names)?;
+    let lengths = RootLengths::new(Some(root), font_size);
+    to_update.cache.hash_root(extras, nodes, (entity, root, children));
+    let key = to_update.cache.key(entity, size.into(), &lengths);
+    if to_update.cache.get(entity, key).is_some() {
+        return Ok(());
+    }
+    let mut layout = Layout::new(entity, to_update, nodes, names, extras, hooks, lengths);
+    if let Some(to_update) = layout.to_update.get_mut(entity) {
+        to_update.size = size;
     }
+    let mut bounds: Size<Computed> = size.into();
+    bounds.set_inset(root_container.inset(), &layout)?;
+    layout.container(root_container, children, bounds)?;
+    layout.to_update.cache.insert(entity, key, size);
+    #[cfg(feature = "profiling")]
+    span.record("nodes", layout.to_update.updated_count());
     Ok(())
 }
 
-/// Whether a [`apply_deferred`] needs to run after the last run of [`update_leaf_nodes`].
+/// Whether a [`apply_deferred`] needs to run after the last run of the
+/// [`UpdateLeafNodes`] systems.
+///
+/// [`update_leaf_nodes`] resets it each frame. If you write your own system
+/// in [`UpdateLeafNodes`] that adds or removes [`LeafNode`] or [`Node`],
+/// run it after `update_leaf_nodes` and call [`LeafNodeInsertWitness::set_needs_apply`]
+/// when you do, so that the changes are visible to the content-sized systems
+/// on the same frame.
+///
+/// Consider using [`AppLeafNodeExt::add_leaf_component`] instead.
 ///
 /// [`apply_deferred`]: bevy::prelude::apply_deferred
-#[doc(hidden)]
 #[derive(Resource, Default, PartialEq, Eq, Clone, Copy)]
 pub struct LeafNodeInsertWitness {
     needs_apply: bool,
@@ -116,6 +326,62 @@ impl LeafNodeInsertWitness {
     pub const fn new(needs_apply: bool) -> Self {
         Self { needs_apply }
     }
+    /// Request an [`apply_deferred`] at the end of the [`UpdateLeafNodes`] set.
+    ///
+    /// [`apply_deferred`]: bevy::prelude::apply_deferred
+    pub fn set_needs_apply(&mut self) {
+        self.needs_apply = true;
+    }
+}
+
+/// Extends [`App`] to support components that make an entity a leaf [`Node`].
+pub trait AppLeafNodeExt {
+    /// Make entities with a `C` component leaf nodes of size `default_size`.
+    ///
+    /// When `C` is added to an entity without a [`Node`], this inserts a
+    /// [`Node::Box`] of `default_size`, a [`LeafNode`] and a [`LayoutRect`].
+    /// Entities that already have a `Node` are left untouched, so that
+    /// rules set explicitly (for example with the `dsl!` macro) take precedence.
+    ///
+    /// The components are inserted in the [`UpdateLeafNodes`] set, and are
+    /// visible to content-sized systems on the same frame.
+    /// Use [`LeafRule::Content`] in `default_size` to size the node with
+    /// a [content-sized](crate::content_sized) system.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy::prelude::*;
+    /// use cuicui_layout::{AppLeafNodeExt, LeafRule, Size};
+    ///
+    /// #[derive(Component)]
+    /// struct ParticleEmitter;
+    ///
+    /// let mut app = App::new();
+    /// app.add_plugins(cuicui_layout::Plugin)
+    ///     .add_leaf_component::<ParticleEmitter>(Size::all(LeafRule::Fixed(32.)));
+    /// ```
+    fn add_leaf_component<C: Component>(&mut self, default_size: Size<LeafRule>) -> &mut Self;
+}
+impl AppLeafNodeExt for App {
+    fn add_leaf_component<C: Component>(&mut self, default_size: Size<LeafRule>) -> &mut Self {
+        let insert_leaf = move |mut witness: ResMut<LeafNodeInsertWitness>,
+                                mut cmds: Commands,
+                                added: Query<Entity, (Added<C>, Without<Node>)>| {
+            for entity in &added {
+                witness.set_needs_apply();
+                let node = Node::Box(default_size);
+                cmds.entity(entity)
+                    .insert((node, LeafNode, LayoutRect::default()));
+            }
+        };
+        self.add_systems(
+            Update,
+            insert_leaf
+                .after(update_leaf_nodes)
+                .in_set(UpdateLeafNodes)
+                .in_set(ComputeLayoutSet),
+        )
+    }
 }
 
 /// Add/remove [`LeafNode`] component according to the current [`Node`] state.
@@ -145,3 +411,425 @@ pub fn update_leaf_nodes(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::{RunSystemOnce, SystemState};
+    use bevy::prelude::{default, BuildWorldChildren, WorldChildBuilder};
+
+    use super::*;
+    use crate::{bundles::LayoutBundle, Anchor, Anchored, Container, Distribution, Flow};
+    use crate::{Rule, Sides, SizeBounds};
+
+    type Queries = (
+        Query<'static, 'static, &'static LayoutRect>,
+        Query<'static, 'static, NodeQuery>,
+        Query<'static, 'static, &'static Name>,
+        Query<'static, 'static, NodeExtras>,
+        Query<'static, 'static, (Entity, &'static Root, &'static Children)>,
+    );
+    fn layout(world: &mut World) -> Result<HashMap<Entity, LayoutRect>, Why> {
+        layout_cached(world, &mut LayoutState::default())
+    }
+    fn layout_cached(
+        world: &mut World,
+        layout_state: &mut LayoutState,
+    ) -> Result<HashMap<Entity, LayoutRect>, Why> {
+        let mut state = SystemState::<Queries>::new(world);
+        let (rects, nodes, names, extras, roots) = state.get(world);
+        let mut updated = HashMap::new();
+        let queries = (&nodes, &names, &extras, None, world.get_resource());
+        let rects = RectStorage::Deferred { current: &rects, updated: &mut updated };
+        layout_roots(queries, rects, &roots, layout_state)?;
+        Ok(updated)
+    }
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> LayoutRect {
+        LayoutRect { pos: Size::new(x, y), size: Size::new(width, height) }
+    }
+    fn fixed(width: f32, height: f32) -> Size<LeafRule> {
+        Size { width, height }.map(LeafRule::Fixed)
+    }
+    /// Spawn a horizontal 400×400 root, parent of the entities spawned in `children`.
+    fn spawn_root(world: &mut World, children: impl FnOnce(&mut WorldChildBuilder)) -> Entity {
+        let root = Root::new(Size::all(400.), Flow::Horizontal, default(), default(), Size::ZERO);
+        world.spawn((root, LayoutRect::default())).with_children(children).id()
+    }
+    /// A world with `container` in a root, and `leaves` in `container`.
+    ///
+    /// The entities are `container` followed by `leaves`.
+    fn container_world<B: Bundle>(
+        container: Container,
+        leaves: impl IntoIterator<Item = B>,
+    ) -> (World, Vec<Entity>) {
+        let mut world = World::new();
+        let mut entities = Vec::new();
+        spawn_root(&mut world, |cmds| {
+            let mut container = cmds.spawn(LayoutBundle::node(container));
+            entities.push(container.id());
+            container.with_children(|cmds| {
+                entities.extend(leaves.into_iter().map(|leaf| cmds.spawn(leaf).id()));
+            });
+        });
+        (world, entities)
+    }
+    /// The rects of `entities` after laying out `world`.
+    fn rects_of(world: &mut World, entities: &[Entity]) -> Vec<LayoutRect> {
+        let rects = layout(world).unwrap();
+        entities.iter().map(|entity| rects[entity]).collect()
+    }
+    /// The size on `axis` of a horizontal container with `rule` on `axis`
+    /// and of a leaf with `leaf` on `axis`, the leaf's sibling is 40px.
+    ///
+    /// Other rules are 100px for the container and 10px for the leaf.
+    fn sizes(axis: Flow, rule: Rule, leaf: LeafRule) -> Result<(f32, f32), Why> {
+        let mut rules = Size::all(Rule::Fixed(100.));
+        *rules.on_mut(axis) = rule;
+        let mut leaf_rules = Size::all(LeafRule::Fixed(10.));
+        *leaf_rules.on_mut(axis) = leaf;
+        let container = Container { rules, ..Container::compact(Flow::Horizontal) };
+
+        let leaves = [fixed(40., 40.), leaf_rules].map(LayoutBundle::boxy);
+        let (mut world, entities) = container_world(container, leaves);
+        let rects = layout(&mut world)?;
+        Ok((*rects[&entities[0]].size.on(axis), *rects[&entities[2]].size.on(axis)))
+    }
+
+    #[test]
+    fn paused_roots() {
+        let mut world = World::new();
+        let mut leaf = Entity::PLACEHOLDER;
+        let root = spawn_root(&mut world, |cmds| {
+            leaf = cmds.spawn(LayoutBundle::boxy(fixed(40., 40.))).id();
+        });
+        world.get_mut::<Root>(root).unwrap().paused = true;
+        assert!(layout(&mut world).unwrap().is_empty());
+
+        world.get_mut::<Root>(root).unwrap().paused = false;
+        let rects = layout(&mut world).unwrap();
+        assert_eq!(rects[&leaf].size, Size::all(40.));
+    }
+    #[test]
+    fn rule_combinations() {
+        use Flow::{Horizontal as Main, Vertical as Cross};
+        use LeafRule::{Content, Fixed as LFixed, Parent as LParent};
+        use Rule::{Children, Fixed, Parent};
+
+        let table = [
+            (Main, Fixed(100.), LFixed(20.), Some((100., 20.))),
+            (Main, Fixed(100.), LParent(0.5), Some((100., 50.))),
+            (Main, Fixed(100.), Content(30.), Some((100., 30.))),
+            (Main, Parent(0.5), LFixed(20.), Some((200., 20.))),
+            (Main, Parent(0.5), LParent(0.5), Some((200., 100.))),
+            (Main, Parent(0.5), Content(30.), Some((200., 30.))),
+            (Main, Children(1.), LFixed(20.), Some((60., 20.))),
+            (Main, Children(1.), LParent(0.5), None),
+            (Main, Children(1.), Content(30.), Some((70., 30.))),
+            (Main, Children(2.), LFixed(20.), Some((120., 20.))),
+            (Main, Children(2.), LParent(0.5), None),
+            (Main, Children(2.), Content(30.), Some((140., 30.))),
+            (Cross, Fixed(100.), LFixed(20.), Some((100., 20.))),
+            (Cross, Fixed(100.), LParent(0.5), Some((100., 50.))),
+            (Cross, Fixed(100.), Content(30.), Some((100., 30.))),
+            (Cross, Parent(0.5), LFixed(20.), Some((200., 20.))),
+            (Cross, Parent(0.5), LParent(0.5), Some((200., 100.))),
+            (Cross, Parent(0.5), Content(30.), Some((200., 30.))),
+            (Cross, Children(1.), LFixed(20.), Some((40., 20.))),
+            (Cross, Children(1.), LParent(0.5), Some((40., 20.))),
+            (Cross, Children(1.), Content(30.), Some((40., 30.))),
+            (Cross, Children(2.), LFixed(20.), Some((80., 20.))),
+            (Cross, Children(2.), LParent(0.5), Some((80., 40.))),
+            (Cross, Children(2.), Content(30.), Some((80., 30.))),
+        ];
+        for (axis, rule, leaf, expected) in table {
+            let result = sizes(axis, rule, leaf);
+            let case = format!("{axis} {rule:?} {leaf:?}: {result:?}");
+            match expected {
+                Some(expected) => assert_eq!(result.ok(), Some(expected), "{case}"),
+                None => assert!(matches!(result, Err(Why::CyclicRule { .. })), "{case}"),
+            }
+        }
+    }
+    #[test]
+    fn all_children_depend_on_cross() {
+        let container = Container::compact(Flow::Horizontal);
+        let leaf = Size::new(LeafRule::Fixed(10.), LeafRule::Parent(1.));
+        let (mut world, _) = container_world(container, [leaf, leaf].map(LayoutBundle::boxy));
+        assert!(matches!(layout(&mut world), Err(Why::CyclicRule { .. })));
+    }
+    #[test]
+    fn overlapping_children() {
+        let distrib = Distribution::OverlapCenter;
+        let container = Container { distrib, ..Container::compact(Flow::Horizontal) };
+        let leaves = [fixed(40., 20.), fixed(30., 30.), Size::all(LeafRule::Parent(1.))];
+        let (mut world, entities) = container_world(container, leaves.map(LayoutBundle::boxy));
+        let rects = rects_of(&mut world, &entities);
+        assert_eq!(rects[0], rect(0., 0., 40., 30.));
+        assert_eq!(rects[1], rect(0., 0., 40., 20.));
+        assert_eq!(rects[2], rect(5., 0., 30., 30.));
+        assert_eq!(rects[3], rect(0., 0., 40., 30.));
+    }
+    /// The rects of `container` and its `leaves`, each leaf spanning the given
+    /// cells of its line.
+    fn container_rects(container: Container, leaves: &[(Size<LeafRule>, u32)]) -> Vec<LayoutRect> {
+        let span = |&(leaf, main): &(_, u32)| (leaf, CellSpan::new(main, 1));
+        let leaves: Vec<_> = leaves.iter().map(span).collect();
+        grid_rects(container, &leaves)
+    }
+    /// The rects of `container` and its `leaves`, each leaf with a [`CellSpan`].
+    fn grid_rects(container: Container, leaves: &[(Size<LeafRule>, CellSpan)]) -> Vec<LayoutRect> {
+        let leaves = leaves.iter().map(|&(leaf, span)| (LayoutBundle::boxy(leaf), span));
+        let (mut world, entities) = container_world(container, leaves);
+        rects_of(&mut world, &entities)
+    }
+    #[test]
+    fn grid_cells() {
+        let parent = Size::all(LeafRule::Parent(1.));
+
+        let rules = Size::new(Rule::Fixed(300.), Rule::Children(1.));
+        let container = Container { rules, grid: Some(3), ..Container::compact(Flow::Horizontal) };
+        let leaves = [(fixed(50., 20.), 1), (fixed(40., 30.), 2), (fixed(10., 10.), 1)];
+        let leaves = [&leaves[..], &[(fixed(10., 10.), 4), (parent, 1)]].concat();
+        let rects = container_rects(container, &leaves);
+        // Lines are as high as their highest child, the last line only has
+        // a child depending on its parent: it is as high as the highest line.
+        assert_eq!(rects[0].size, Size::new(300., 80.));
+        assert_eq!(rects[1], rect(0., 0., 50., 20.));
+        assert_eq!(rects[2], rect(100., 0., 40., 30.));
+        assert_eq!(rects[3], rect(0., 30., 10., 10.));
+        assert_eq!(rects[4], rect(0., 40., 10., 10.));
+        assert_eq!(rects[5], rect(0., 50., 100., 30.));
+
+        let rules = Size::all(Rule::Children(1.));
+        let container = Container { rules, grid: Some(2), ..default() };
+        let leaves = [(fixed(30., 10.), 1), (fixed(50., 20.), 1), (parent, 1)];
+        let rects = container_rects(container, &leaves);
+        assert_eq!(rects[0].size, Size::new(80., 40.));
+        assert_eq!(rects[1], rect(0., 5., 30., 10.));
+        assert_eq!(rects[2], rect(30., 0., 50., 20.));
+        assert_eq!(rects[3], rect(0., 20., 30., 20.));
+    }
+    #[test]
+    fn grid_spans() {
+        let leaf = |width, height, main, cross| (fixed(width, height), CellSpan::new(main, cross));
+        let rules = Size::all(Rule::Children(1.));
+        let container = Container { rules, grid: Some(2), ..Container::compact(Flow::Horizontal) };
+        let leaves = [
+            leaf(100., 10., 2, 1),
+            leaf(30., 10., 1, 1),
+            leaf(50., 20., 1, 1),
+            leaf(20., 50., 1, 2),
+            leaf(10., 10., 1, 1),
+            leaf(10., 10., 1, 1),
+        ];
+        let rects = grid_rects(container, &leaves);
+        // Columns are 30 and 50 pixels wide, the header spanning both is
+        // 20 pixels wider, so each column grows by 10 pixels.
+        // The last two lines are 10 pixels high, the tall child spanning
+        // both is 30 pixels higher, so each line grows by 15 pixels.
+        assert_eq!(rects[0].size, Size::new(100., 80.));
+        assert_eq!(rects[1], rect(0., 0., 100., 10.));
+        assert_eq!(rects[2], rect(0., 10., 30., 10.));
+        assert_eq!(rects[3], rect(40., 10., 50., 20.));
+        assert_eq!(rects[4], rect(0., 30., 20., 50.));
+        assert_eq!(rects[5], rect(40., 30., 10., 10.));
+        // The first cell of the last line is taken by the tall child.
+        assert_eq!(rects[6], rect(40., 55., 10., 10.));
+    }
+    #[test]
+    fn wrapping_children() {
+        let leaf = |width, height| (fixed(width, height), 1);
+        let rules = Size::new(Rule::Fixed(100.), Rule::Children(1.));
+
+        let container = Container { rules, wrap: true, ..Container::compact(Flow::Horizontal) };
+        let parent = Size::new(LeafRule::Parent(0.7), LeafRule::Fixed(10.));
+        let leaves = [leaf(40., 10.), leaf(50., 20.), leaf(30., 10.), (parent, 1)];
+        let rects = container_rects(container, &leaves);
+        assert_eq!(rects[0].size, Size::new(100., 30.));
+        assert_eq!(rects[1], rect(0., 0., 40., 10.));
+        assert_eq!(rects[2], rect(40., 0., 50., 20.));
+        assert_eq!(rects[3], rect(0., 20., 30., 10.));
+        assert_eq!(rects[4], rect(30., 20., 70., 10.));
+
+        let container = Container { rules, wrap: true, ..default() };
+        let leaves = [leaf(40., 10.), leaf(50., 20.), leaf(30., 10.)];
+        let rects = container_rects(container, &leaves);
+        assert_eq!(rects[0].size, Size::new(100., 30.));
+        assert_eq!(rects[1], rect(0., 5., 40., 10.));
+        assert_eq!(rects[2], rect(50., 0., 50., 20.));
+        assert_eq!(rects[3], rect(35., 20., 30., 10.));
+    }
+    #[test]
+    fn margin_sides_and_padding() {
+        let leaves = [(fixed(40., 10.), 1), (fixed(20., 30.), 1)];
+
+        let margin = Sides::new(10., 20., 5., 0.);
+        let padding = Sides::new(1., 2., 3., 4.);
+        let container = Container { margin, padding, ..Container::compact(Flow::Horizontal) };
+        let rects = container_rects(container, &leaves);
+        assert_eq!(rects[0].size, Size::new(93., 42.));
+        assert_eq!(rects[1], rect(11., 8., 40., 10.));
+        assert_eq!(rects[2], rect(51., 8., 20., 30.));
+
+        // Negative padding cancels part of the margin.
+        let padding = Sides::new(-10., 0., -5., 0.);
+        let container = Container { margin, padding, ..Container::compact(Flow::Horizontal) };
+        let rects = container_rects(container, &leaves);
+        assert_eq!(rects[0].size, Size::new(80., 30.));
+        assert_eq!(rects[1], rect(0., 0., 40., 10.));
+    }
+    #[test]
+    fn anchored_children() {
+        let parent = Size::all(LeafRule::Parent(1.));
+        let badge = Anchored { anchor: Anchor::TopRight, offset: Size::new(5., -5.) };
+        let margin = Sides::all(5.);
+        let container = Container { margin, ..Container::compact(Flow::Horizontal) };
+
+        let leaves = [fixed(40., 20.), fixed(10., 10.), fixed(30., 10.), parent];
+        let (mut world, entities) = container_world(container, leaves.map(LayoutBundle::boxy));
+        world.entity_mut(entities[2]).insert(badge);
+        world.entity_mut(entities[4]).insert(Anchored::default());
+        let rects = rects_of(&mut world, &entities);
+        // Anchored children take no space in their parent.
+        assert_eq!(rects[0].size, Size::new(80., 30.));
+        assert_eq!(rects[1], rect(5., 5., 40., 20.));
+        assert_eq!(rects[3], rect(45., 5., 30., 10.));
+        // Relative to the whole parent rect, margin included.
+        assert_eq!(rects[2], rect(75., -5., 10., 10.));
+        assert_eq!(rects[4], rect(0., 0., 80., 30.));
+    }
+    #[test]
+    fn size_bounds() {
+        let leaf = |width, height| LayoutBundle::boxy(Size { width, height });
+        let bounds = |min: Size<Option<Rule>>, max: Size<Option<Rule>>| SizeBounds { min, max };
+        let (none, fixed) = (Size::all(None), |pixels| Some(Rule::Fixed(pixels)));
+        let max_width = bounds(none, Size { width: fixed(150.), height: None });
+        // Whenever `min` is larger than `max`, `min` wins.
+        let min_size = Size { width: Some(Rule::Parent(0.1)), height: fixed(20.) };
+        let min_size = bounds(min_size, Size { width: None, height: fixed(5.) });
+        let rules = Size::new(Rule::Fixed(400.), Rule::Children(1.));
+        let row = Container { rules, ..Container::compact(Flow::Horizontal) };
+        let rules = Size::new(Rule::Parent(1.), Rule::Children(1.));
+        let container = Container { rules, ..Container::compact(Flow::Horizontal) };
+        let container_max = bounds(none, Size { width: fixed(100.), height: None });
+
+        let half = leaf(LeafRule::Parent(0.5), LeafRule::Fixed(10.));
+        let small = leaf(LeafRule::Fixed(10.), LeafRule::Fixed(10.));
+        let leaves = [half, small, LayoutBundle::node(container)];
+        let (mut world, mut entities) = container_world(row, leaves);
+        world.entity_mut(entities[1]).insert(max_width);
+        world.entity_mut(entities[2]).insert(min_size);
+        let mut container = world.entity_mut(entities[3]);
+        container.insert(container_max).with_children(|cmds| {
+            let full = leaf(LeafRule::Parent(1.), LeafRule::Fixed(10.));
+            entities.push(cmds.spawn(full).id());
+        });
+        let rects = rects_of(&mut world, &entities);
+        let sizes: Vec<_> = rects.iter().map(|rect| rect.size).collect();
+        assert_eq!(sizes[1], Size::new(150., 10.));
+        assert_eq!(sizes[2], Size::new(40., 20.));
+        // Children of clamped containers are laid out within the clamped size.
+        assert_eq!(sizes[3], Size::new(100., 10.));
+        assert_eq!(sizes[4], Size::new(100., 10.));
+    }
+    #[test]
+    fn cached_containers() {
+        let stats = |computed, skipped| LayoutCacheStats { computed, skipped };
+        let column = || LayoutBundle::node(Container::compact(Flow::Vertical));
+        let leaf = || LayoutBundle::boxy(Size::all(LeafRule::Fixed(10.)));
+
+        let mut world = World::new();
+        let (mut kept, mut changed) = (Entity::PLACEHOLDER, Entity::PLACEHOLDER);
+        spawn_root(&mut world, |cmds| {
+            cmds.spawn(column()).with_children(|cmds| kept = cmds.spawn(leaf()).id());
+            cmds.spawn(column()).with_children(|cmds| changed = cmds.spawn(leaf()).id());
+        });
+        let mut state = LayoutState::default();
+        let mut layout = |world: &mut World| {
+            let rects = layout_cached(world, &mut state).unwrap();
+            for (entity, rect) in &rects {
+                *world.get_mut::<LayoutRect>(*entity).unwrap() = *rect;
+            }
+            (rects, state.cache.stats)
+        };
+        let (rects, first_stats) = layout(&mut world);
+        assert_eq!(first_stats, stats(3, 0));
+        assert_eq!(rects.len(), 5);
+
+        // Nothing changed, the whole root is skipped.
+        let (rects, unchanged_stats) = layout(&mut world);
+        assert_eq!(unchanged_stats, stats(0, 1));
+        assert!(rects.is_empty());
+
+        *world.get_mut::<Node>(changed).unwrap() = Node::fixed(Size::all(20.));
+        let (rects, changed_stats) = layout(&mut world);
+        assert_eq!(changed_stats, stats(2, 1));
+        assert!(!rects.contains_key(&kept));
+        assert_eq!(rects[&changed].size, Size::all(20.));
+        assert_eq!(world.get::<LayoutRect>(kept).unwrap().size, Size::all(10.));
+    }
+    #[test]
+    fn root_units() {
+        use crate::{RootFontSize, RootUnit::{Em, Vmax, Vmin}};
+
+        assert_eq!("50vmin".parse().ok(), Some(Rule::Root(0.5, Vmin)));
+        assert_eq!("25vmax".parse().ok(), Some(Rule::Root(0.25, Vmax)));
+        assert_eq!("1.5em".parse().ok(), Some(Rule::Root(1.5, Em)));
+        assert!("-2em".parse::<Rule>().is_err());
+
+        let container = Container {
+            rules: Size::new(Rule::Root(0.5, Vmax), Rule::Root(0.5, Vmin)),
+            ..Container::compact(Flow::Horizontal)
+        };
+        let mut world = World::new();
+        world.insert_resource(RootFontSize(20.));
+        let size = Size::new(400., 200.);
+        let root = Root::new(size, Flow::Horizontal, default(), default(), Size::ZERO);
+        let (mut node, mut leaf) = (Entity::PLACEHOLDER, Entity::PLACEHOLDER);
+        world.spawn((root, LayoutRect::default())).with_children(|cmds| {
+            let mut container = cmds.spawn(LayoutBundle::node(container));
+            node = container.id();
+            container.with_children(|cmds| {
+                let rules = Size::new(LeafRule::Root(2., Em), LeafRule::Parent(0.5));
+                leaf = cmds.spawn(LayoutBundle::boxy(rules)).id();
+            });
+        });
+        let rects = layout(&mut world).unwrap();
+        assert_eq!(rects[&node].size, Size::new(200., 100.));
+        assert_eq!(rects[&leaf].size, Size::new(40., 50.));
+    }
+    #[test]
+    fn relayout_on_removal() {
+        let mut world = World::new();
+        let badge = Anchored { anchor: Anchor::TopRight, offset: Size::ZERO };
+        let bounds = SizeBounds { min: Size::all(None), max: Size::all(Some(Rule::Fixed(5.))) };
+        let mut leaf = Entity::PLACEHOLDER;
+        spawn_root(&mut world, |cmds| {
+            let fixed = LayoutBundle::boxy(fixed(10., 10.));
+            leaf = cmds.spawn((fixed, badge, bounds, CellSpan::new(2, 1), CustomConstraint)).id();
+        });
+        let laid_out = |world: &mut World| {
+            // Drop the removals already seen by the previous layout.
+            world.clear_trackers();
+            world.clear_trackers();
+            let tick = Some(world.change_tick());
+            world.insert_resource(LastLayoutChange { tick, ..default() });
+        };
+        laid_out(&mut world);
+        assert!(!world.run_system_once(require_layout_recompute));
+
+        world.entity_mut(leaf).remove::<Anchored>();
+        assert!(world.run_system_once(require_layout_recompute));
+
+        laid_out(&mut world);
+        world.entity_mut(leaf).remove::<SizeBounds>();
+        assert!(world.run_system_once(require_layout_recompute));
+
+        laid_out(&mut world);
+        world.entity_mut(leaf).remove::<CellSpan>();
+        assert!(world.run_system_once(require_layout_recompute));
+
+        laid_out(&mut world);
+        world.entity_mut(leaf).remove::<CustomConstraint>();
+        assert!(world.run_system_once(require_layout_recompute));
+    }
+}
@@ -0,0 +1,139 @@
+//! Utilities to test [`ComputeContentSize`] implementations without loading
+//! assets from disk.
+//!
+//! Content-sized nodes depend on the size of images and on font metrics.
+//! This module provides:
+//!
+//! - [`fake_image`]: an [`Image`] of a given size, without meaningful content.
+//! - [`test_font`]: a small monospace font embedded in `cuicui_layout`
+//!   (a subset of Fira Mono), so that text sizes do not depend on system fonts.
+//!   The font is under the SIL Open Font License, see `LICENSE-OFL`.
+//! - [`FakeAssets`]: add the above to the world's [`Assets`].
+//! - [`compute_content`]: run a [`ComputeContentSize`] on a single entity.
+//!   [`compute_content_within`] does the same with an available width.
+//! - [`layout_snapshot`] and [`assert_snapshot`]: compare the computed layout
+//!   of a scene to a golden file.
+//!
+//! Requires the `testing` feature. Enable it in your `dev-dependencies`.
+//!
+//! # Example
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use cuicui_layout::testing::FakeAssets;
+//!
+//! let mut world = World::new();
+//! let image = world.add_fake_image(40, 20);
+//! let font = world.add_test_font();
+//!
+//! assert_eq!(world.resource::<Assets<Image>>().get(&image).unwrap().width(), 40);
+//! assert!(world.resource::<Assets<Font>>().contains(&font));
+//! ```
+use bevy::asset::{Asset, Assets, Handle};
+use bevy::ecs::prelude::*;
+use bevy::ecs::system::{StaticSystemParam, SystemState};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::Image;
+use bevy::text::Font;
+
+use crate::content_sized::{ComputeContentParam, ComputeContentSize};
+use crate::Size;
+
+pub use snapshot::{assert_snapshot, layout_snapshot, UPDATE_SNAPSHOTS};
+
+mod snapshot;
+
+/// The bytes of the font returned by [`test_font`].
+pub const TEST_FONT_BYTES: &[u8] = include_bytes!("FiraMono-subset.ttf");
+
+/// An opaque white [`Image`] of `width` × `height` pixels.
+#[must_use]
+pub fn fake_image(width: u32, height: u32) -> Image {
+    let size = Extent3d { width, height, depth_or_array_layers: 1 };
+    let format = TextureFormat::Rgba8UnormSrgb;
+    Image::new_fill(size, TextureDimension::D2, &[255; 4], format)
+}
+
+/// A monospace [`Font`], a subset of Fira Mono with ASCII characters.
+///
+/// # Panics
+/// Never, the embedded font is valid.
+#[must_use]
+pub fn test_font() -> Font {
+    Font::try_from_bytes(TEST_FONT_BYTES.to_vec()).unwrap()
+}
+
+/// Add fake assets to the [`World`], initializing the [`Assets`] resource
+/// if it doesn't exist yet.
+pub trait FakeAssets {
+    /// Add a [`fake_image`] of `width` × `height` pixels.
+    fn add_fake_image(&mut self, width: u32, height: u32) -> Handle<Image>;
+    /// Add the [`test_font`].
+    fn add_test_font(&mut self) -> Handle<Font>;
+}
+fn add_asset<A: Asset>(world: &mut World, asset: A) -> Handle<A> {
+    world.init_resource::<Assets<A>>();
+    world.resource_mut::<Assets<A>>().add(asset)
+}
+impl FakeAssets for World {
+    fn add_fake_image(&mut self, width: u32, height: u32) -> Handle<Image> {
+        add_asset(self, fake_image(width, height))
+    }
+    fn add_test_font(&mut self) -> Handle<Font> {
+        add_asset(self, test_font())
+    }
+}
+impl FakeAssets for bevy::app::App {
+    fn add_fake_image(&mut self, width: u32, height: u32) -> Handle<Image> {
+        self.world.add_fake_image(width, height)
+    }
+    fn add_test_font(&mut self) -> Handle<Font> {
+        self.world.add_test_font()
+    }
+}
+
+/// Compute the content size of `entity` with the `S` [`ComputeContentSize`],
+/// as [`AppContentSizeExt::add_content_sized`] would, given the `set_size`
+/// of the node.
+///
+/// Returns `None` if `entity` doesn't match `S`'s components.
+///
+/// # Panics
+/// If the resources `S` accesses are missing from `world`.
+///
+/// [`AppContentSizeExt::add_content_sized`]: crate::content_sized::AppContentSizeExt
+pub fn compute_content<S: ComputeContentParam>(
+    world: &mut World,
+    entity: Entity,
+    set_size: Size<Option<f32>>,
+) -> Option<Size<f32>>
+where
+    for<'w, 's> S::Item<'w, 's>: ComputeContentSize<Components = S::Components>,
+{
+    let mut state = SystemState::<(StaticSystemParam<S>, Query<S::Components>)>::new(world);
+    let (param, query) = state.get_mut(world);
+    let components = query.get(entity).ok()?;
+    Some(param.compute_content(components, set_size))
+}
+
+/// Like [`compute_content`], with [`ComputeContentSize::compute_content_within`]
+/// and the `available_width` of the node.
+///
+/// Returns `None` if `entity` doesn't match `S`'s components.
+///
+/// # Panics
+/// If the resources `S` accesses are missing from `world`.
+pub fn compute_content_within<S: ComputeContentParam>(
+    world: &mut World,
+    entity: Entity,
+    set_size: Size<Option<f32>>,
+    available_width: Option<f32>,
+) -> Option<Size<f32>>
+where
+    for<'w, 's> S::Item<'w, 's>: ComputeContentSize<Components = S::Components>,
+{
+    let mut state = SystemState::<(StaticSystemParam<S>, Query<S::Components>)>::new(world);
+    let (param, query) = state.get_mut(world);
+    let components = query.get(entity).ok()?;
+    Some(param.compute_content_within(components, set_size, available_width))
+}
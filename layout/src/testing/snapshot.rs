@@ -0,0 +1,103 @@
+//! Golden-file tests of computed layouts, see [`assert_snapshot`].
+
+use std::fmt::{self, Write};
+use std::{env, fs, path::Path};
+
+use bevy::ecs::prelude::*;
+use bevy::prelude::{Children, Name};
+
+use crate::{LayoutRect, Node};
+
+/// Set this environment variable to overwrite snapshots with the current
+/// layout in [`assert_snapshot`].
+pub const UPDATE_SNAPSHOTS: &str = "CUICUI_UPDATE_SNAPSHOTS";
+
+fn write_node(snapshot: &mut String, world: &World, entity: Entity, depth: usize) -> fmt::Result {
+    let name = world.get::<Name>(entity).map_or("<unnamed>", Name::as_str);
+    write!(snapshot, "{:indent$}{name}", "", indent = depth * 2)?;
+    if let Some(rect) = world.get::<LayoutRect>(entity) {
+        let (size, pos) = (rect.size(), rect.pos());
+        let (width, height, x, y) = (size.width, size.height, pos.x, pos.y);
+        write!(snapshot, ": {width:.1}×{height:.1} at ({x:.1}, {y:.1})")?;
+    }
+    snapshot.push('\n');
+
+    let Some(children) = world.get::<Children>(entity) else {
+        return Ok(());
+    };
+    let is_layout = |e: &&Entity| world.get::<Node>(**e).is_some();
+    for child in children.iter().filter(is_layout) {
+        write_node(snapshot, world, *child, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// A text representation of the layout of `root` and its descendants.
+///
+/// Each line is a layout node, with its [`Name`], size and position relative
+/// to its parent, children are indented under their parent. Children that are
+/// not layout nodes are omitted.
+///
+/// Values are rounded to a tenth of pixel, so that snapshots do not depend on
+/// floating point noise.
+#[must_use]
+#[allow(clippy::missing_panics_doc)] // `fmt::Write` for String never fails.
+pub fn layout_snapshot(world: &World, root: Entity) -> String {
+    let mut snapshot = String::new();
+    write_node(&mut snapshot, world, root, 0).unwrap();
+    snapshot
+}
+
+/// Assert that `snapshot` is identical to the content of the file at `path`.
+///
+/// When the [`UPDATE_SNAPSHOTS`] environment variable is set, write `snapshot`
+/// to `path` instead. Review the changes to the snapshot files before
+/// committing them.
+///
+/// Use it with [`layout_snapshot`] to catch unexpected changes to
+/// the layout of complex scenes.
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use cuicui_layout::testing::{assert_snapshot, layout_snapshot};
+///
+/// fn check_menu(world: &World, menu: Entity) {
+///     let path = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/menu.txt");
+///     assert_snapshot(path, &layout_snapshot(world, menu));
+/// }
+/// ```
+///
+/// # Panics
+/// When `snapshot` differs from the content of `path`, or when `path` can't be
+/// read or written.
+#[track_caller]
+pub fn assert_snapshot(path: impl AsRef<Path>, snapshot: &str) {
+    let path = path.as_ref();
+    let display = path.display();
+    if env::var_os(UPDATE_SNAPSHOTS).is_some() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap_or_else(|err| panic!("{display}: {err}"));
+        }
+        fs::write(path, snapshot).unwrap_or_else(|err| panic!("{display}: {err}"));
+        return;
+    }
+    let Ok(expected) = fs::read_to_string(path) else {
+        panic!("No snapshot at {display}, run the test with {UPDATE_SNAPSHOTS}=1 to create it");
+    };
+    if expected == snapshot {
+        return;
+    }
+    let mut diff = String::new();
+    let line_count = expected.lines().count().max(snapshot.lines().count());
+    for i in 0..line_count {
+        let (old, new) = (expected.lines().nth(i), snapshot.lines().nth(i));
+        if old != new {
+            let (old, new) = (old.unwrap_or(""), new.unwrap_or(""));
+            writeln!(diff, "line {}:\n- {old}\n+ {new}", i + 1).unwrap();
+        }
+    }
+    panic!(
+        "Layout differs from the snapshot at {display}\n{diff}\n\
+        Run the test with {UPDATE_SNAPSHOTS}=1 to update the snapshot"
+    );
+}
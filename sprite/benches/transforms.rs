@@ -0,0 +1,64 @@
+//! Lay out a 5000 nodes column, and update the `Transform` of its nodes.
+//!
+//! - `resize`: Nodes change width, but not position. Their `Transform` is not
+//!   written to, so transform propagation has nothing to do.
+//! - `move`: Nodes change position, every `Transform` is updated and propagated.
+//!
+//! Run with `cargo bench -p cuicui_layout_bevy_sprite`.
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, Criterion};
+use cuicui_layout::bundles::LayoutBundle;
+use cuicui_layout::{Alignment, Distribution, Flow, LeafRule, Node, Root, Size};
+
+/// Nodes in the column.
+const NODES: u32 = 5_000;
+
+fn column() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin))
+        .init_resource::<Assets<Font>>()
+        .init_resource::<Assets<Image>>()
+        .init_resource::<Assets<Mesh>>()
+        .add_plugins(cuicui_layout_bevy_sprite::Plugin);
+
+    let size = Size::new(100., 100_000.);
+    let root = Root::new(size, Flow::Vertical, Alignment::Start, Distribution::FillMain, Size::ZERO);
+    let root = app.world.spawn((SpatialBundle::default(), root, LayoutBundle::default())).id();
+    for _ in 0..NODES {
+        let node = Node::Box(Size::new(LeafRule::Parent(1.), LeafRule::Fixed(10.)));
+        let child = app.world.spawn((SpatialBundle::default(), LayoutBundle { node, ..default() }));
+        let child = child.id();
+        app.world.entity_mut(root).add_child(child);
+    }
+    app.update();
+    (app, root)
+}
+
+fn transforms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("column_5k");
+
+    let (mut app, root) = column();
+    let mut toggle = false;
+    group.bench_function("resize", |b| {
+        b.iter(|| {
+            toggle = !toggle;
+            let mut root = app.world.get_mut::<Root>(root).unwrap();
+            *root.size_mut().width = if toggle { 90. } else { 100. };
+            app.update();
+        });
+    });
+
+    let (mut app, root) = column();
+    group.bench_function("move", |b| {
+        b.iter(|| {
+            toggle = !toggle;
+            let mut root = app.world.get_mut::<Root>(root).unwrap();
+            *root.size_mut().height = if toggle { 90_000. } else { 100_000. };
+            app.update();
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, transforms);
+criterion_main!(benches);
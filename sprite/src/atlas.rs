@@ -0,0 +1,310 @@
+//! Pack the images of sprites into a single texture atlas at runtime.
+//!
+//! Each distinct [`Image`] used by a sprite is a separate texture, and thus
+//! a separate draw call. Add the [`PackSprites`] component to the root of a
+//! scene (for example, the entity holding a `Handle<Chirp>`) to merge all the
+//! images of its descendant [`Sprite`]s into a single atlas texture.
+//!
+//! Once all the images are loaded, [`pack_sprites`]:
+//!
+//! 1. Builds an atlas texture out of all the distinct images.
+//! 2. Replaces the sprites' `Handle<Image>` by the atlas texture handle.
+//! 3. Sets [`Sprite::rect`] to the region of the atlas the image is at.
+//!
+//! The original image handle is kept in the [`PackedSprite`] component.
+//! Content-sized nodes are still measured with the original image's size.
+//! When new sprites are spawned under the root (for example when the chirp
+//! file is reloaded) the atlas is built again.
+//!
+//! # Limitations
+//!
+//! - `PackedSprite` holds a strong handle to the original image, so the images
+//!   are in memory twice: once as individual images, once in the atlas.
+//! - To change the image of a packed sprite, set its `Handle<Image>` and
+//!   [`Sprite::rect`] as usual, and remove its [`PackedSprite`] component.
+//!   This triggers a repack.
+//! - If the images do not fit in [`PackSprites::max_size`], an error is logged
+//!   and the sprites are left untouched.
+use bevy::asset::{AssetId, Assets, Handle};
+use bevy::ecs::prelude::*;
+use bevy::hierarchy::{Children, HierarchyQueryExt};
+use bevy::log::error;
+use bevy::math::{Rect, UVec2, Vec2};
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::render::texture::Image;
+use bevy::sprite::{Sprite, TextureAtlasBuilder};
+use bevy::utils::HashSet;
+use cuicui_layout::LeafNodeInsertWitness;
+
+use crate::clip::UnclippedSprite;
+
+/// Pack the images of all descendant [`Sprite`]s of this entity into a
+/// single atlas.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Component, Clone, Copy, Debug)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct PackSprites {
+    /// The largest size the atlas texture may have, in pixels.
+    pub max_size: UVec2,
+    /// Space in pixels between images in the atlas, this avoids colors of
+    /// neighbouring images bleeding into each other.
+    pub padding: u32,
+}
+impl Default for PackSprites {
+    fn default() -> Self {
+        Self { max_size: UVec2::splat(2048), padding: 2 }
+    }
+}
+
+/// A [`Sprite`] which `Handle<Image>` was replaced by an atlas texture.
+#[derive(Component, Clone, Debug)]
+pub struct PackedSprite {
+    /// The image this sprite had before it was packed.
+    pub original: Handle<Image>,
+    /// Position of the top left corner of [`Self::original`] in the atlas.
+    pub offset: Vec2,
+}
+
+type PackedItem = (
+    &'static mut Sprite,
+    &'static mut Handle<Image>,
+    Option<&'static mut PackedSprite>,
+    Option<&'static mut UnclippedSprite>,
+);
+
+/// Pack the sprites descendant of [`PackSprites`] entities.
+///
+/// See the [module-level documentation](self) for details.
+// false positives: bevy systems and `remap`, which is called twice
+#[allow(clippy::needless_pass_by_value, clippy::redundant_closure_call)]
+pub fn pack_sprites(
+    roots: Query<(Entity, &PackSprites)>,
+    children: Query<&Children>,
+    mut sprites: Query<PackedItem>,
+    mut images: ResMut<Assets<Image>>,
+    mut witness: ResMut<LeafNodeInsertWitness>,
+    mut cmds: Commands,
+) {
+    for (root, pack) in &roots {
+        let descendants: Vec<_> = children
+            .iter_descendants(root)
+            .filter(|e| sprites.contains(*e))
+            .collect();
+
+        let is_packed = |e: &Entity| matches!(sprites.get(*e), Ok((_, _, Some(_), _)));
+        if descendants.iter().all(is_packed) {
+            continue;
+        }
+        let original = |e: &Entity| {
+            let (_, image, packed, _) = sprites.get(*e).unwrap();
+            packed.map_or(image.id(), |p| p.original.id())
+        };
+        let originals: HashSet<AssetId<Image>> = descendants.iter().map(original).collect();
+        if originals.iter().any(|id| !images.contains(*id)) {
+            continue;
+        }
+        let mut builder = TextureAtlasBuilder::default()
+            .initial_size(Vec2::splat(256.).min(pack.max_size.as_vec2()))
+            .max_size(pack.max_size.as_vec2())
+            .padding(UVec2::splat(pack.padding));
+        for id in &originals {
+            builder.add_texture(*id, images.get(*id).unwrap());
+        }
+        let atlas = match builder.finish(&mut images) {
+            Ok(atlas) => atlas,
+            Err(err) => {
+                error!("Can't pack sprites of {root:?}, removing PackSprites: {err}");
+                cmds.entity(root).remove::<PackSprites>();
+                continue;
+            }
+        };
+        for entity in descendants {
+            let (mut sprite, mut image, packed, unclipped) = sprites.get_mut(entity).unwrap();
+            let original = packed.as_ref().map_or(&*image, |p| &p.original).clone();
+            let Some(index) = atlas.get_texture_index(original.id()) else {
+                continue;
+            };
+            let size = images.get(&original).unwrap().size_f32();
+            let from = packed.as_ref().map_or(Vec2::ZERO, |p| p.offset);
+            let to = atlas.textures[index].min;
+            let remap = |rect: Option<Rect>| {
+                let rect = rect.unwrap_or_else(|| Rect::from_corners(from, from + size));
+                Some(Rect::from_corners(
+                    rect.min - from + to,
+                    rect.max - from + to,
+                ))
+            };
+            sprite.rect = remap(sprite.rect);
+            if let Some(mut unclipped) = unclipped {
+                unclipped.remap_rect(remap);
+            }
+            *image = atlas.texture.clone();
+            if let Some(mut packed) = packed {
+                packed.offset = to;
+            } else {
+                witness.set_needs_apply();
+                cmds.entity(entity)
+                    .insert(PackedSprite { original, offset: to });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::*;
+    use bevy::sprite::Anchor;
+    use cuicui_layout::testing::FakeAssets;
+
+    use super::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<LeafNodeInsertWitness>();
+        world
+    }
+    fn sprite(world: &mut World, parent: Entity, image: &Handle<Image>) -> Entity {
+        let sprite = world.spawn((Sprite::default(), image.clone())).id();
+        world.entity_mut(parent).add_child(sprite);
+        sprite
+    }
+    fn rect(world: &World, sprite: Entity) -> Rect {
+        world.get::<Sprite>(sprite).unwrap().rect.unwrap()
+    }
+    fn original(world: &World, sprite: Entity) -> &Handle<Image> {
+        &world.get::<PackedSprite>(sprite).unwrap().original
+    }
+
+    #[test]
+    fn pack_distinct_images() {
+        let mut world = world();
+        let (wide, square) = (world.add_fake_image(20, 10), world.add_fake_image(8, 8));
+        let root = world.spawn(PackSprites::default()).id();
+        let node = world.spawn_empty().set_parent(root).id();
+        let sprites = [
+            sprite(&mut world, root, &wide),
+            sprite(&mut world, node, &wide),
+            sprite(&mut world, node, &square),
+        ];
+        world.run_system_once(pack_sprites);
+
+        let atlas = world.get::<Handle<Image>>(sprites[0]).unwrap().clone();
+        assert_ne!(atlas, wide);
+        for sprite in sprites {
+            assert_eq!(world.get::<Handle<Image>>(sprite), Some(&atlas));
+        }
+        assert_eq!(original(&world, sprites[1]), &wide);
+        assert_eq!(original(&world, sprites[2]), &square);
+
+        let (wide_rect, square_rect) = (rect(&world, sprites[0]), rect(&world, sprites[2]));
+        assert_eq!(wide_rect, rect(&world, sprites[1]));
+        assert_eq!(wide_rect.size(), Vec2::new(20., 10.));
+        assert_eq!(square_rect.size(), Vec2::new(8., 8.));
+        assert!(wide_rect.intersect(square_rect).is_empty());
+
+        let offset = world.get::<PackedSprite>(sprites[2]).unwrap().offset;
+        assert_eq!(offset, square_rect.min);
+        let witness = world.resource::<LeafNodeInsertWitness>();
+        assert!(*witness == LeafNodeInsertWitness::new(true));
+    }
+    #[test]
+    fn sub_rect_and_repack() {
+        let mut world = world();
+        let (wide, square) = (world.add_fake_image(20, 10), world.add_fake_image(8, 8));
+        let root = world.spawn(PackSprites::default()).id();
+        let first = sprite(&mut world, root, &wide);
+        world.get_mut::<Sprite>(first).unwrap().rect = Some(Rect::new(5., 2., 10., 4.));
+        world.run_system_once(pack_sprites);
+
+        let offset = world.get::<PackedSprite>(first).unwrap().offset;
+        let expected = Rect::new(5., 2., 10., 4.);
+        let expected = Rect::from_corners(expected.min + offset, expected.max + offset);
+        assert_eq!(rect(&world, first), expected);
+
+        // Packing is idempotent.
+        world.run_system_once(pack_sprites);
+        assert_eq!(rect(&world, first), expected);
+
+        // A new sprite triggers a repack, the first sprite is still at the same
+        // place within its original image.
+        let second = sprite(&mut world, root, &square);
+        world.run_system_once(pack_sprites);
+        let atlas = world.get::<Handle<Image>>(second).unwrap();
+        assert_eq!(world.get::<Handle<Image>>(first), Some(atlas));
+        let offset = world.get::<PackedSprite>(first).unwrap().offset;
+        let min = offset + Vec2::new(5., 2.);
+        assert_eq!(
+            rect(&world, first),
+            Rect::from_corners(min, min + Vec2::new(5., 2.))
+        );
+        assert_eq!(rect(&world, second).size(), Vec2::new(8., 8.));
+    }
+    #[test]
+    fn clipped_sprite() {
+        use crate::clip::{clip_changed, clip_sprites, ClipChildren};
+        use cuicui_layout::LayoutRect;
+
+        let mut world = world();
+        let image = world.add_fake_image(20, 10);
+        let clip = LayoutRect::from(Rect::new(0., 0., 10., 10.));
+        let root = world
+            .spawn((PackSprites::default(), ClipChildren, clip))
+            .id();
+        let clipped = sprite(&mut world, root, &image);
+        let node = LayoutRect::from(Rect::new(0., 0., 20., 10.));
+        let bundle = (
+            node,
+            Visibility::default(),
+            Sprite { anchor: Anchor::BottomLeft, ..default() },
+        );
+        world.entity_mut(clipped).insert(bundle);
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((pack_sprites, clip_sprites.run_if(clip_changed)).chain());
+        schedule.run(&mut world);
+
+        // Repacking moves the clipped sprite within the atlas.
+        let square = world.add_fake_image(30, 30);
+        sprite(&mut world, root, &square);
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+
+        let offset = world.get::<PackedSprite>(clipped).unwrap().offset;
+        let unclipped = world.get::<UnclippedSprite>(clipped).unwrap();
+        assert_eq!(
+            unclipped.rect,
+            Some(Rect::from_corners(offset, offset + Vec2::new(20., 10.)))
+        );
+        let visible = Rect::from_corners(offset, offset + Vec2::new(10., 10.));
+        assert_eq!(rect(&world, clipped), visible);
+    }
+    #[test]
+    fn wait_for_images() {
+        let mut world = world();
+        let image = world.add_fake_image(20, 10);
+        let root = world.spawn(PackSprites::default()).id();
+        let loaded = sprite(&mut world, root, &image);
+        let not_loaded = sprite(&mut world, root, &Handle::default());
+        world.run_system_once(pack_sprites);
+
+        assert_eq!(world.get::<Handle<Image>>(loaded), Some(&image));
+        assert!(world.get::<PackedSprite>(loaded).is_none());
+        assert!(world.get::<PackedSprite>(not_loaded).is_none());
+    }
+    #[test]
+    fn too_large() {
+        let mut world = world();
+        let image = world.add_fake_image(20, 10);
+        let pack = PackSprites { max_size: UVec2::splat(16), padding: 0 };
+        let root = world.spawn(pack).id();
+        let sprite = sprite(&mut world, root, &image);
+        world.run_system_once(pack_sprites);
+
+        assert_eq!(world.get::<Handle<Image>>(sprite), Some(&image));
+        assert!(world.get::<PackedSprite>(sprite).is_none());
+        assert!(world.get::<PackSprites>(root).is_none());
+    }
+}
@@ -0,0 +1,426 @@
+//! Clip sprites to the [`LayoutRect`] of an ancestor marked with [`ClipChildren`].
+//!
+//! `bevy_sprite` has no concept of clipping, so we emulate it: each [`Sprite`]
+//! descendant of a `ClipChildren` node is shrunk to the part of it that is
+//! within the clipping node. The texture sub-rectangle ([`Sprite::rect`]),
+//! [`Sprite::custom_size`] and [`Sprite::anchor`] are adjusted so that the
+//! visible part of the sprite stays at the same place.
+//!
+//! The original sprite values are stored in the [`UnclippedSprite`] component.
+//! Changes to the `Sprite` or its [`Visibility`] are detected and stored in
+//! `UnclippedSprite`, so you can update either of them on a clipped sprite.
+//! When the sprite stops being a descendant of a `ClipChildren` node, the
+//! original values are restored and `UnclippedSprite` is removed.
+//!
+//! A `ClipChildren` node within another `ClipChildren` node clips its
+//! descendants to the intersection of both nodes.
+//!
+//! Sprites are only clipped again when the layout, a `ClipChildren`, a clipped
+//! sprite or an image changes, see [`clip_changed`].
+//!
+//! # Limitations
+//!
+//! - Only [`Sprite`]s are clipped, `Text2d` and meshes are not.
+//! - Rotated and scaled sprites are clipped as if they had no rotation or scale.
+//! - Sprites with [`Sprite::flip_x`] or [`Sprite::flip_y`] are not supported.
+use bevy::asset::{Assets, Handle};
+use bevy::ecs::prelude::*;
+use bevy::hierarchy::{Children, HierarchyQueryExt, Parent};
+use bevy::math::{Rect, Vec2};
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::render::{texture::Image, view::Visibility};
+use bevy::sprite::{Anchor, Sprite};
+use bevy::utils::HashSet;
+use cuicui_layout::LayoutRect;
+
+/// Clip descendant [`Sprite`]s to this node's [`LayoutRect`].
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Component, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct ClipChildren;
+
+/// The state of a [`Sprite`] before it was clipped by a [`ClipChildren`] ancestor.
+#[derive(Component, Clone, Debug)]
+pub struct UnclippedSprite {
+    /// The sprite's [`Sprite::custom_size`].
+    pub custom_size: Option<Vec2>,
+    /// The sprite's [`Sprite::rect`].
+    pub rect: Option<Rect>,
+    /// The sprite's [`Sprite::anchor`].
+    pub anchor: Anchor,
+    /// The sprite's [`Visibility`].
+    pub visibility: Visibility,
+    /// The state [`clip_sprites`] last set the sprite to, to tell apart
+    /// changes to the `Sprite` from clipping.
+    clipped: SpriteState,
+}
+impl UnclippedSprite {
+    const fn new(original: SpriteState, clipped: SpriteState) -> Self {
+        let SpriteState { custom_size, rect, anchor, visibility } = original;
+        Self { custom_size, rect, anchor, visibility, clipped }
+    }
+    const fn original(&self) -> SpriteState {
+        let Self { custom_size, rect, anchor, visibility, .. } = *self;
+        SpriteState { custom_size, rect, anchor, visibility }
+    }
+    /// Change the texture rectangles of the sprite, for example when its
+    /// image is moved to an atlas.
+    #[cfg(feature = "atlas")]
+    pub(crate) fn remap_rect(&mut self, remap: impl Fn(Option<Rect>) -> Option<Rect>) {
+        self.rect = remap(self.rect);
+        self.clipped.rect = remap(self.clipped.rect);
+    }
+    /// The original state of a sprite that is currently `current`.
+    fn original_of(&self, current: SpriteState) -> SpriteState {
+        self.original().updated(self.clipped, current)
+    }
+}
+
+/// The values of a [`Sprite`] and its [`Visibility`] changed by clipping.
+#[derive(Clone, Copy, Debug)]
+struct SpriteState {
+    custom_size: Option<Vec2>,
+    rect: Option<Rect>,
+    anchor: Anchor,
+    visibility: Visibility,
+}
+impl PartialEq for SpriteState {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_sprite(other) && self.visibility == other.visibility
+    }
+}
+impl SpriteState {
+    const fn new(sprite: &Sprite, visibility: Visibility) -> Self {
+        let Sprite { custom_size, rect, anchor, .. } = *sprite;
+        Self { custom_size, rect, anchor, visibility }
+    }
+    fn same_sprite(&self, other: &Self) -> bool {
+        // `Anchor` doesn't implement `PartialEq`.
+        (self.custom_size, self.rect, self.anchor.as_vec())
+            == (other.custom_size, other.rect, other.anchor.as_vec())
+    }
+    /// `self`, with the fields that differ between `old` and `new` set to `new`.
+    fn updated(self, old: Self, new: Self) -> Self {
+        fn pick<T: PartialEq>(this: T, old: T, new: T) -> T {
+            if old == new {
+                this
+            } else {
+                new
+            }
+        }
+        let same_anchor = old.anchor.as_vec() == new.anchor.as_vec();
+        Self {
+            custom_size: pick(self.custom_size, old.custom_size, new.custom_size),
+            rect: pick(self.rect, old.rect, new.rect),
+            anchor: if same_anchor { self.anchor } else { new.anchor },
+            visibility: pick(self.visibility, old.visibility, new.visibility),
+        }
+    }
+    /// Set `sprite` and `visibility` to this state, without triggering change
+    /// detection when they already are in this state.
+    fn write(&self, sprite: &mut Mut<Sprite>, visibility: &mut Mut<Visibility>) {
+        if !self.same_sprite(&Self::new(sprite, **visibility)) {
+            sprite.custom_size = self.custom_size;
+            sprite.rect = self.rect;
+            sprite.anchor = self.anchor;
+        }
+        visibility.set_if_neq(self.visibility);
+    }
+}
+
+type ClippedItem = (
+    &'static mut Sprite,
+    &'static mut Visibility,
+    &'static Handle<Image>,
+    Option<&'static mut UnclippedSprite>,
+);
+
+/// Whether [`clip_sprites`] should run.
+///
+/// True when a [`LayoutRect`], [`ClipChildren`], [`Children`],
+/// [`UnclippedSprite`] or [`Image`] changed, a [`Sprite`] was added,
+/// the `Sprite` or [`Visibility`] of a clipped sprite changed,
+/// or a `ClipChildren` was removed.
+#[allow(clippy::type_complexity, clippy::needless_pass_by_value)]
+pub fn clip_changed(
+    rects: Query<(), Changed<LayoutRect>>,
+    clips: Query<(), Or<(Changed<ClipChildren>, Changed<Children>)>>,
+    sprites: Query<
+        (),
+        Or<(
+            Added<Sprite>,
+            Changed<UnclippedSprite>,
+            Changed<Handle<Image>>,
+        )>,
+    >,
+    clipped: Query<
+        (),
+        (
+            With<UnclippedSprite>,
+            Or<(Changed<Sprite>, Changed<Visibility>)>,
+        ),
+    >,
+    images: Res<Assets<Image>>,
+    mut clips_removed: RemovedComponents<ClipChildren>,
+) -> bool {
+    let clips_removed = clips_removed.read().count() != 0;
+    images.is_changed()
+        || !rects.is_empty()
+        || !clips.is_empty()
+        || !sprites.is_empty()
+        || !clipped.is_empty()
+        || clips_removed
+}
+
+/// Clip the [`Sprite`]s descendant of [`ClipChildren`] nodes.
+///
+/// This runs after `cuicui_layout` computed the layout, see the
+/// [module-level documentation](self) for details.
+#[allow(clippy::needless_pass_by_value)]
+pub fn clip_sprites(
+    clips: Query<(Entity, &LayoutRect, &Children), With<ClipChildren>>,
+    nodes: Query<(&LayoutRect, Option<&Children>)>,
+    parents: Query<&Parent>,
+    mut sprites: Query<ClippedItem>,
+    unclipped: Query<Entity, With<UnclippedSprite>>,
+    images: Res<Assets<Image>>,
+    mut cmds: Commands,
+) {
+    let mut stack = Vec::new();
+    let mut clipped = HashSet::new();
+    for (clip_entity, clip_rect, children) in &clips {
+        // Nested clips are handled when walking their outermost clip.
+        if parents
+            .iter_ancestors(clip_entity)
+            .any(|e| clips.contains(e))
+        {
+            continue;
+        }
+        let size = clip_rect.size();
+        let clip = Rect::new(0., 0., size.width, size.height);
+
+        stack.extend(children.iter().map(|child| (*child, Vec2::ZERO, clip)));
+        while let Some((entity, offset, clip)) = stack.pop() {
+            let Ok((rect, children)) = nodes.get(entity) else {
+                continue;
+            };
+            let pos = offset + rect.pos();
+            if let Some(children) = children {
+                let size = rect.size();
+                let inner = Rect::from_corners(pos, pos + Vec2::new(size.width, size.height));
+                let inner = if clips.contains(entity) { clip.intersect(inner) } else { clip };
+                stack.extend(children.iter().map(|child| (*child, pos, inner)));
+            }
+            let Ok((mut sprite, mut visibility, image, unclipped)) = sprites.get_mut(entity) else {
+                continue;
+            };
+            clipped.insert(entity);
+            let current = SpriteState::new(&sprite, *visibility);
+            let original = unclipped
+                .as_ref()
+                .map_or(current, |u| u.original_of(current));
+            let mut state = original;
+            let image_size = images.get(image).map(Image::size_f32);
+            clip_sprite(&mut state, pos, image_size, clip);
+            state.write(&mut sprite, &mut visibility);
+
+            match unclipped {
+                Some(unclipped)
+                    if (unclipped.original(), unclipped.clipped) == (original, state) => {}
+                Some(mut unclipped) => *unclipped = UnclippedSprite::new(original, state),
+                None => {
+                    cmds.entity(entity)
+                        .insert(UnclippedSprite::new(original, state));
+                }
+            }
+        }
+    }
+    // Restore sprites that are not descendant of a `ClipChildren` anymore.
+    for entity in unclipped.iter().filter(|e| !clipped.contains(e)) {
+        let Ok((mut sprite, mut visibility, _, Some(unclipped))) = sprites.get_mut(entity) else {
+            continue;
+        };
+        let original = unclipped.original_of(SpriteState::new(&sprite, *visibility));
+        original.write(&mut sprite, &mut visibility);
+        cmds.entity(entity).remove::<UnclippedSprite>();
+    }
+}
+
+/// Clip `sprite` positioned at `pos` to `clip`.
+fn clip_sprite(sprite: &mut SpriteState, pos: Vec2, image_size: Option<Vec2>, clip: Rect) {
+    let texture = sprite
+        .rect
+        .or_else(|| image_size.map(|s| Rect::from_corners(Vec2::ZERO, s)));
+    let Some(texture) = texture else {
+        return;
+    };
+    let size = sprite.custom_size.unwrap_or_else(|| texture.size());
+    let min = pos - (sprite.anchor.as_vec() + 0.5) * size;
+    let full = Rect::from_corners(min, min + size);
+    let visible = full.intersect(clip);
+
+    if visible.is_empty() {
+        sprite.visibility = Visibility::Hidden;
+        return;
+    }
+    if visible == full {
+        return;
+    }
+    // Sprite Y axis is up while texture Y axis is down.
+    let scale = texture.size() / size;
+    let texture_min = Vec2::new(visible.min.x - full.min.x, full.max.y - visible.max.y);
+    let texture_min = texture.min + texture_min * scale;
+    sprite.rect = Some(Rect::from_corners(
+        texture_min,
+        texture_min + visible.size() * scale,
+    ));
+    sprite.custom_size = Some(visible.size());
+    sprite.anchor = Anchor::Custom((pos - visible.center()) / visible.size());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    fn node(x: f32, y: f32, width: f32, height: f32) -> LayoutRect {
+        LayoutRect::from(Rect::new(x, y, x + width, y + height))
+    }
+    fn sprite() -> Sprite {
+        Sprite {
+            custom_size: Some(Vec2::new(100., 100.)),
+            rect: Some(Rect::new(0., 0., 100., 100.)),
+            anchor: Anchor::BottomLeft,
+            ..default()
+        }
+    }
+    fn schedule() -> Schedule {
+        let mut schedule = Schedule::default();
+        schedule.add_systems(clip_sprites.run_if(clip_changed));
+        schedule
+    }
+    /// A 100×100 sprite in a 60×60 [`ClipChildren`] node, returns `(clip, sprite)`.
+    fn clipped_sprite(world: &mut World) -> (Entity, Entity) {
+        world.init_resource::<Assets<Image>>();
+        let mut leaf = Entity::PLACEHOLDER;
+        let clip = world
+            .spawn((ClipChildren, node(0., 0., 60., 60.)))
+            .with_children(|cmds| {
+                let bundle = (
+                    node(0., 0., 100., 100.),
+                    sprite(),
+                    Handle::<Image>::default(),
+                );
+                leaf = cmds.spawn((bundle, Visibility::default())).id();
+            })
+            .id();
+        (clip, leaf)
+    }
+    fn size(world: &World, sprite: Entity) -> Option<Vec2> {
+        world.get::<Sprite>(sprite).unwrap().custom_size
+    }
+
+    #[test]
+    fn nested_clips_intersect() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Image>>();
+        let mut leaf = Entity::PLACEHOLDER;
+        let outer = world
+            .spawn((ClipChildren, node(0., 0., 80., 80.)))
+            .with_children(|cmds| {
+                cmds.spawn((ClipChildren, node(40., 0., 60., 60.)))
+                    .with_children(|cmds| {
+                        let bundle = (
+                            node(0., 0., 100., 100.),
+                            sprite(),
+                            Handle::<Image>::default(),
+                        );
+                        leaf = cmds.spawn((bundle, Visibility::default())).id();
+                    });
+            })
+            .id();
+
+        let mut schedule = schedule();
+        schedule.run(&mut world);
+        assert_eq!(size(&world, leaf), Some(Vec2::new(40., 60.)));
+
+        // Nothing changed, so the sprite is not touched anymore.
+        schedule.run(&mut world);
+        world.clear_trackers();
+        schedule.run(&mut world);
+        assert!(!world.entity(leaf).get_ref::<Sprite>().unwrap().is_changed());
+
+        world.entity_mut(outer).insert(node(0., 0., 90., 30.));
+        schedule.run(&mut world);
+        assert_eq!(size(&world, leaf), Some(Vec2::new(50., 30.)));
+    }
+    #[test]
+    fn sprite_changes_update_unclipped() {
+        let mut world = World::new();
+        let (_, leaf) = clipped_sprite(&mut world);
+        let mut schedule = schedule();
+        schedule.run(&mut world);
+        let unclipped = |world: &World| world.get::<UnclippedSprite>(leaf).unwrap().clone();
+
+        world.get_mut::<Sprite>(leaf).unwrap().custom_size = Some(Vec2::new(50., 50.));
+        schedule.run(&mut world);
+        assert_eq!(unclipped(&world).custom_size, Some(Vec2::new(50., 50.)));
+        assert_eq!(unclipped(&world).rect, Some(Rect::new(0., 0., 100., 100.)));
+        // The sprite is now within the clip.
+        assert_eq!(size(&world, leaf), Some(Vec2::new(50., 50.)));
+        assert_eq!(
+            world.get::<Sprite>(leaf).unwrap().rect,
+            Some(Rect::new(0., 0., 100., 100.))
+        );
+
+        world.get_mut::<Sprite>(leaf).unwrap().rect = Some(Rect::new(0., 0., 10., 10.));
+        *world.get_mut::<Visibility>(leaf).unwrap() = Visibility::Hidden;
+        schedule.run(&mut world);
+        assert_eq!(unclipped(&world).rect, Some(Rect::new(0., 0., 10., 10.)));
+        assert_eq!(unclipped(&world).visibility, Visibility::Hidden);
+        assert_eq!(world.get::<Visibility>(leaf), Some(&Visibility::Hidden));
+
+        // Changing the sprite's size makes it clipped again.
+        world.get_mut::<Sprite>(leaf).unwrap().custom_size = Some(Vec2::new(100., 100.));
+        schedule.run(&mut world);
+        assert_eq!(size(&world, leaf), Some(Vec2::new(60., 60.)));
+        assert_eq!(
+            world.get::<Sprite>(leaf).unwrap().rect,
+            Some(Rect::new(0., 4., 6., 10.))
+        );
+        assert_eq!(unclipped(&world).custom_size, Some(Vec2::new(100., 100.)));
+    }
+    #[test]
+    fn restore_out_of_clip() {
+        let mut world = World::new();
+        let (_, leaf) = clipped_sprite(&mut world);
+        let mut schedule = schedule();
+        schedule.run(&mut world);
+        assert_eq!(size(&world, leaf), Some(Vec2::new(60., 60.)));
+
+        let other = world.spawn(node(0., 0., 10., 10.)).id();
+        world.entity_mut(leaf).set_parent(other);
+        schedule.run(&mut world);
+        assert_eq!(size(&world, leaf), sprite().custom_size);
+        assert_eq!(world.get::<Sprite>(leaf).unwrap().rect, sprite().rect);
+        assert!(world.get::<UnclippedSprite>(leaf).is_none());
+    }
+    #[test]
+    fn restore_clip_removed() {
+        let mut world = World::new();
+        let (clip, leaf) = clipped_sprite(&mut world);
+        world.entity_mut(clip).insert(node(0., 0., 0., 0.));
+        let mut schedule = schedule();
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Visibility>(leaf), Some(&Visibility::Hidden));
+
+        world.entity_mut(clip).remove::<ClipChildren>();
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Visibility>(leaf), Some(&Visibility::Inherited));
+        assert_eq!(size(&world, leaf), sprite().custom_size);
+        assert!(world.get::<UnclippedSprite>(leaf).is_none());
+    }
+}
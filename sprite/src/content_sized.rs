@@ -5,12 +5,13 @@
 //!
 //! Sprite size is determined by either:
 //! - [`bevy::sprite::Sprite::custom_size`] if it is `Some`.
-//! - The `Handle<Image>` size of the same entity.
+//! - The `Handle<Image>` size of the same entity, divided by its
+//!   [`ImageScale`] or the [`DefaultImageScale`]. While the image is not
+//!   loaded, its [`ImageSizeHint`] is used instead, if any.
 //!
-//! Text size is determined by the [`Text2dBounds`] component.
-//! You may update the component's `size` field and have `cuicui_layout`
-//! follow it. But otherwise, `cuicui_layout` won't dynamically update
-//! the text size based on its content.
+//! Text size is determined by its content, wrapped to the node's width when
+//! it is set, or to the width available in its parent otherwise. The
+//! [`Text2dBounds`] of text nodes are then set to their size, see the `text` module.
 //!
 //! # Limitations
 //!
@@ -28,7 +29,8 @@ use bevy::sprite::Mesh2dHandle;
 #[cfg(feature = "sprite_text")]
 use bevy::text::{Font, Text, Text2dBounds};
 use cuicui_layout::content_sized::{
-    ComputeContentParam, ComputeContentSize, ContentSizedComputeSystem,
+    ComputeContentParam, ComputeContentSize, ContentSizedComputeSystem, DefaultImageScale,
+    ImageScale, ImageSizeHint,
 };
 use cuicui_layout::{require_layout_recompute, Node, Size};
 
@@ -38,18 +40,31 @@ pub(crate) struct SpriteContentSize<'w> {
     fonts: Res<'w, Assets<Font>>,
     images: Res<'w, Assets<Image>>,
     meshes: Res<'w, Assets<Mesh>>,
+    image_scale: Res<'w, DefaultImageScale>,
 }
+#[cfg(feature = "atlas")]
+type Packed = Option<&'static crate::atlas::PackedSprite>;
+#[cfg(not(feature = "atlas"))]
+type Packed = ();
+#[cfg(feature = "sprite_text")]
+type Components = (
+    AnyOf<(
+        Ref<'static, Handle<Image>>,
+        Ref<'static, Mesh2dHandle>,
+        Ref<'static, Text>,
+        Ref<'static, Text2dBounds>,
+    )>,
+    (Option<Ref<'static, ImageScale>>, Option<Ref<'static, ImageSizeHint>>),
+    Packed,
+);
+#[cfg(not(feature = "sprite_text"))]
+type Components = (
+    AnyOf<(Ref<'static, Handle<Image>>, Ref<'static, Mesh2dHandle>)>,
+    (Option<Ref<'static, ImageScale>>, Option<Ref<'static, ImageSizeHint>>),
+    Packed,
+);
 impl ComputeContentParam for SpriteContentSize<'static> {
-    #[cfg(feature = "sprite_text")]
-    type Components = AnyOf<(
-        &'static Handle<Image>,
-        &'static Mesh2dHandle,
-        &'static Text,
-        &'static Text2dBounds,
-    )>;
-
-    #[cfg(not(feature = "sprite_text"))]
-    type Components = AnyOf<(&'static Handle<Image>, &'static Mesh2dHandle)>;
+    type Components = Components;
 
     fn condition(label: ContentSizedComputeSystem<Self>) -> SystemSetConfigs {
         use bevy::ecs::schedule::common_conditions as cond;
@@ -69,21 +84,32 @@ impl ComputeContentParam for SpriteContentSize<'static> {
         let cond = cond
             .or_else(cond::resource_changed::<Assets<Image>>())
             .or_else(cond::resource_changed::<Assets<Mesh>>())
+            .or_else(cond::resource_changed::<DefaultImageScale>())
+            .or_else(changed::<ImageScale>)
             .or_else(changed::<Handle<Image>>)
             .or_else(changed::<Mesh2dHandle>);
+        #[cfg(feature = "atlas")]
+        let cond = cond.or_else(changed::<crate::atlas::PackedSprite>);
 
         label.run_if(require_layout_recompute.or_else(cond))
     }
 }
 type OptSize = Size<Option<f32>>;
 impl SpriteContentSize<'_> {
+    /// The size of `text`, wrapped at its set width, or at `available_width`
+    /// if its width is content-sized.
     #[cfg(feature = "sprite_text")]
-    fn compute_text_size(&self, text: &Text, set_size: OptSize) -> Option<Size<f32>> {
+    fn compute_text_size(
+        &self,
+        text: &Text,
+        set_size: OptSize,
+        available_width: Option<f32>,
+    ) -> Option<Size<f32>> {
         use bevy::text::TextMeasureInfo;
 
         let inf = f32::INFINITY;
         let bounds = Vec2::new(
-            set_size.width.unwrap_or(inf),
+            set_size.width.or(available_width).unwrap_or(inf),
             set_size.height.unwrap_or(inf),
         );
         // Seems like this requires an epsilon, otherwise text wraps poorly.
@@ -99,9 +125,17 @@ impl SpriteContentSize<'_> {
         Some(size.into())
     }
     // TODO(bug): Account for `Sprite::custom_size`, and all sprite fields generally.
-    fn compute_image_size(&self, image: &Handle<Image>, set_size: OptSize) -> Option<Size<f32>> {
-        let image = self.images.get(image)?;
-        let size = image.size().as_vec2();
+    fn compute_image_size(
+        &self,
+        image: &Handle<Image>,
+        (scale, hint): (Option<&ImageScale>, Option<&ImageSizeHint>),
+        set_size: OptSize,
+    ) -> Option<Size<f32>> {
+        let size = match self.images.get(image) {
+            Some(image) => image.size().as_vec2(),
+            None => hint?.0,
+        };
+        let size = ImageScale::logical_size(scale, &self.image_scale, size);
         let size = match (set_size.width, set_size.height) {
             (None, None) => size,
             (Some(width), None) => Vec2::new(width, width * size.y / size.x),
@@ -114,30 +148,169 @@ impl SpriteContentSize<'_> {
         Some(size.into())
     }
 }
+/// The image of a sprite before it was packed in an atlas.
+#[cfg(feature = "atlas")]
+fn original_image<'a>(
+    image: &'a Handle<Image>,
+    packed: Option<&'a crate::atlas::PackedSprite>,
+) -> &'a Handle<Image> {
+    packed.map_or(image, |packed| &packed.original)
+}
+#[cfg(not(feature = "atlas"))]
+const fn original_image(image: &Handle<Image>, (): ()) -> &Handle<Image> {
+    image
+}
 impl ComputeContentSize for SpriteContentSize<'_> {
-    #[cfg(feature = "sprite_text")]
-    type Components = AnyOf<(
-        &'static Handle<Image>,
-        &'static Mesh2dHandle,
-        &'static Text,
-        &'static Text2dBounds,
-    )>;
-
-    #[cfg(not(feature = "sprite_text"))]
-    type Components = AnyOf<(&'static Handle<Image>, &'static Mesh2dHandle)>;
+    type Components = Components;
 
     fn compute_content(
         &self,
         components: QueryItem<Self::Components>,
         set_size: OptSize,
     ) -> Size<f32> {
+        self.compute_content_within(components, set_size, None)
+    }
+    /// Text wraps at `available_width`, images and meshes ignore it.
+    fn compute_content_within(
+        &self,
+        components: QueryItem<Self::Components>,
+        set_size: OptSize,
+        available_width: Option<f32>,
+    ) -> Size<f32> {
+        #[cfg(not(feature = "sprite_text"))]
+        let _ = available_width;
+        let (components, (scale, hint), packed) = components;
         let size = match components {
             #[cfg(feature = "sprite_text")]
-            (.., Some(text), Some(_)) => self.compute_text_size(text, set_size),
-            (Some(image), ..) => self.compute_image_size(image, set_size),
+            (.., Some(text), Some(_)) => self.compute_text_size(&text, set_size, available_width),
+            (Some(image), ..) => {
+                let image = original_image(&image, packed);
+                self.compute_image_size(image, (scale.as_deref(), hint.as_deref()), set_size)
+            }
             (_, Some(mesh), ..) => self.compute_mesh_size(&mesh.0, set_size),
             _ => unreachable!("This is a bevy bug"),
         };
         size.unwrap_or(Size::ZERO)
     }
+
+    fn all_changed(&self) -> bool {
+        #[cfg(feature = "sprite_text")]
+        let fonts_changed = self.fonts.is_changed();
+        #[cfg(not(feature = "sprite_text"))]
+        let fonts_changed = false;
+
+        fonts_changed
+            || self.images.is_changed()
+            || self.meshes.is_changed()
+            || self.image_scale.is_changed()
+    }
+    fn content_changed(&self, components: &QueryItem<Self::Components>) -> bool {
+        let (components, (scale, hint), _) = components;
+        #[cfg(feature = "sprite_text")]
+        let (image, mesh, text_changed) = {
+            let (image, mesh, text, bounds) = components;
+            let text_changed = text.as_ref().is_some_and(DetectChanges::is_changed)
+                || bounds.as_ref().is_some_and(DetectChanges::is_changed);
+            (image, mesh, text_changed)
+        };
+        #[cfg(not(feature = "sprite_text"))]
+        let ((image, mesh), text_changed) = (components, false);
+
+        text_changed
+            || image.as_ref().is_some_and(DetectChanges::is_changed)
+            || mesh.as_ref().is_some_and(DetectChanges::is_changed)
+            || scale.as_ref().is_some_and(DetectChanges::is_changed)
+            || hint.as_ref().is_some_and(DetectChanges::is_changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::sprite::Mesh2dHandle;
+    use cuicui_layout::content_sized::{DefaultImageScale, ImageScale, ImageSizeHint};
+    use cuicui_layout::testing::{compute_content, FakeAssets};
+    use cuicui_layout::Size;
+
+    use super::SpriteContentSize;
+
+    const UNSET: Size<Option<f32>> = Size::new(None, None);
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Assets<Image>>();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<DefaultImageScale>();
+        #[cfg(feature = "sprite_text")]
+        world.init_resource::<Assets<Font>>();
+        world
+    }
+    fn size(world: &mut World, entity: Entity, set_size: Size<Option<f32>>) -> Size<f32> {
+        compute_content::<SpriteContentSize<'static>>(world, entity, set_size).unwrap()
+    }
+
+    #[test]
+    fn image_size() {
+        let mut world = world();
+        let texture = world.add_fake_image(40, 20);
+        let image = world.spawn(texture).id();
+
+        assert_eq!(size(&mut world, image, UNSET), Size::new(40., 20.));
+        let width = Size::new(Some(80.), None);
+        assert_eq!(size(&mut world, image, width), Size::new(80., 40.));
+        let height = Size::new(None, Some(10.));
+        assert_eq!(size(&mut world, image, height), Size::new(20., 10.));
+
+        world.entity_mut(image).insert(ImageScale(2.));
+        assert_eq!(size(&mut world, image, UNSET), Size::new(20., 10.));
+        assert_eq!(size(&mut world, image, width), Size::new(80., 40.));
+    }
+    #[test]
+    fn unloaded_image_size() {
+        let mut world = world();
+        let image = world.spawn(Handle::<Image>::default()).id();
+        assert_eq!(size(&mut world, image, UNSET), Size::ZERO);
+
+        world.entity_mut(image).insert(ImageSizeHint(Vec2::new(40., 20.)));
+        assert_eq!(size(&mut world, image, UNSET), Size::new(40., 20.));
+    }
+    #[test]
+    fn mesh_size() {
+        let mut world = world();
+        let quad = shape::Quad::new(Vec2::new(30., 10.));
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(quad.into());
+        let mesh = world.spawn(Mesh2dHandle(mesh)).id();
+        assert_eq!(size(&mut world, mesh, UNSET), Size::new(30., 10.));
+    }
+    #[cfg(feature = "sprite_text")]
+    #[test]
+    fn text_size() {
+        use bevy::text::Text2dBounds;
+        use cuicui_layout::testing::compute_content_within;
+
+        let mut world = world();
+        let font = world.add_test_font();
+        let style = TextStyle { font, font_size: 20., color: Color::WHITE };
+        let mut text = |value: &str| {
+            let text = Text::from_section(value, style.clone());
+            world.spawn((text, Text2dBounds::default())).id()
+        };
+        let (one, ten, two_words) = (text("A"), text("AAAAAAAAAA"), text("AAAAA AAAAA"));
+
+        let one = size(&mut world, one, UNSET);
+        let ten = size(&mut world, ten, UNSET);
+        assert!(one.width > 0. && one.height > 0.);
+        assert!((ten.width - one.width * 10.).abs() < 0.5, "{ten} is not 10 × {one}");
+        assert_eq!(ten.height, one.height);
+
+        let wrapped = size(&mut world, two_words, Size::new(Some(one.width * 7.), None));
+        assert!(wrapped.width <= one.width * 7.);
+        assert!(wrapped.height > one.height * 1.5, "{wrapped} is not two lines of {one}");
+
+        let available = Some(one.width * 7.);
+        let within = compute_content_within::<SpriteContentSize<'static>>;
+        let wrapped = within(&mut world, two_words, UNSET, available).unwrap();
+        assert!(wrapped.width <= one.width * 7.);
+        assert!(wrapped.height > one.height * 1.5, "{wrapped} is not two lines of {one}");
+    }
 }
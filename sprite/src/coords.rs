@@ -0,0 +1,145 @@
+//! Convert between layout space and world or screen space, see [`LayoutCoords`].
+//!
+//! With `bevy_sprite`, the `Transform` of a node is its layout position, so
+//! the layout space of a root is the local space of its `GlobalTransform`.
+//! Since the world Y axis goes up, **the top of the layout space is at the
+//! bottom of the root** on screen. Use `LayoutCoords` rather than converting
+//! positions yourself, so that code stays correct with `cuicui_layout_bevy_ui`,
+//! where it is the other way around.
+//!
+//! See [`cuicui_layout::coords`] for the definition of layout space.
+use bevy::ecs::{prelude::*, system::SystemParam};
+use bevy::math::Vec2;
+use bevy::prelude::{Camera, GlobalTransform};
+use bevy::render::view::RenderLayers;
+use cuicui_layout::coords::LayoutPositions;
+use cuicui_layout::{LayoutRootCamera, Root};
+
+/// Convert positions between the layout space of [`Root`]s and world or
+/// screen space.
+///
+/// World space is the 2D world coordinates of `bevy_sprite`. Screen space is
+/// logical pixels from the top left of the viewport of the [`LayoutRootCamera`]
+/// with the same [`RenderLayers`] as the root, like `Window::cursor_position`.
+///
+/// Conversions use the `GlobalTransform` of roots, so they are one frame late
+/// for roots that moved this frame, until transform propagation.
+///
+/// # Example
+///
+/// Find which button is under the cursor:
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_layout::coords::LayoutPositions;
+/// use cuicui_layout_bevy_sprite::coords::LayoutCoords;
+///
+/// #[derive(Component)]
+/// struct Button;
+///
+/// fn hovered_button(
+///     window: Query<&Window>,
+///     coords: LayoutCoords,
+///     positions: LayoutPositions,
+///     buttons: Query<Entity, With<Button>>,
+/// ) {
+///     let Some(cursor) = window.single().cursor_position() else {
+///         return;
+///     };
+///     for button in &buttons {
+///         let Some((root, rect)) = positions.rect_in_root(button) else {
+///             continue;
+///         };
+///         if coords.screen_to_layout(root, cursor).is_some_and(|p| rect.contains(p)) {
+///             info!("{button:?} is hovered");
+///         }
+///     }
+/// }
+/// # bevy::ecs::system::assert_is_system(hovered_button);
+/// ```
+#[derive(SystemParam)]
+pub struct LayoutCoords<'w, 's> {
+    positions: LayoutPositions<'w, 's>,
+    roots: Query<'w, 's, (&'static GlobalTransform, Option<&'static RenderLayers>), With<Root>>,
+    cameras: Query<
+        'w,
+        's,
+        (&'static Camera, &'static GlobalTransform, Option<&'static RenderLayers>),
+        With<LayoutRootCamera>,
+    >,
+}
+impl LayoutCoords<'_, '_> {
+    /// The world position of the top left corner, in layout space, of the
+    /// node `entity`.
+    ///
+    /// `None` if `entity` is not a node in a [`Root`].
+    #[must_use]
+    pub fn layout_to_world(&self, entity: Entity) -> Option<Vec2> {
+        let (root, position) = self.positions.in_root(entity)?;
+        let (root, _) = self.roots.get(root).ok()?;
+        Some(root.transform_point(position.extend(0.)).truncate())
+    }
+    /// The position of the world `point` in the layout space of `root`.
+    ///
+    /// `None` if `root` is not a [`Root`].
+    #[must_use]
+    pub fn world_to_layout(&self, root: Entity, point: Vec2) -> Option<Vec2> {
+        let (root, _) = self.roots.get(root).ok()?;
+        let to_layout = root.affine().inverse();
+        Some(to_layout.transform_point3(point.extend(0.)).truncate())
+    }
+    /// The screen position of the top left corner, in layout space, of the
+    /// node `entity`.
+    ///
+    /// `None` if `entity` is not a node in a [`Root`], its root has no camera,
+    /// or it is outside of the camera view.
+    #[must_use]
+    pub fn layout_to_screen(&self, entity: Entity) -> Option<Vec2> {
+        let (root, _) = self.positions.in_root(entity)?;
+        let (camera, transform) = self.camera(root)?;
+        camera.world_to_viewport(transform, self.layout_to_world(entity)?.extend(0.))
+    }
+    /// The position of the screen `point` in the layout space of `root`.
+    ///
+    /// `None` if `root` is not a [`Root`] or it has no camera.
+    #[must_use]
+    pub fn screen_to_layout(&self, root: Entity, point: Vec2) -> Option<Vec2> {
+        let (camera, transform) = self.camera(root)?;
+        self.world_to_layout(root, camera.viewport_to_world_2d(transform, point)?)
+    }
+    /// The [`LayoutRootCamera`] with the same [`RenderLayers`] as `root`.
+    fn camera(&self, root: Entity) -> Option<(&Camera, &GlobalTransform)> {
+        let (_, root_layers) = self.roots.get(root).ok()?;
+        let root_layers = root_layers.copied().unwrap_or_default();
+        let is_layer = |(c, t, l): (_, _, Option<&RenderLayers>)| {
+            (l.copied().unwrap_or_default() == root_layers).then_some((c, t))
+        };
+        self.cameras.iter().find_map(is_layer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use bevy::prelude::*;
+    use cuicui_layout::{LayoutRect, PixelSnap};
+
+    use super::*;
+
+    #[test]
+    fn layout_and_world_roundtrip() {
+        let mut world = World::new();
+        world.init_resource::<PixelSnap>();
+        let rect = |x, y| LayoutRect::from(Rect::new(x, y, x + 20., y + 10.));
+        let transform = GlobalTransform::from_xyz(100., 50., 3.);
+        let root = world.spawn((Root::default(), rect(0., 0.), transform)).id();
+        let node = world.spawn(rect(30., 40.)).set_parent(root).id();
+
+        let mut state = SystemState::<LayoutCoords>::new(&mut world);
+        let coords = state.get(&world);
+        assert_eq!(coords.layout_to_world(node), Some(Vec2::new(130., 90.)));
+        assert_eq!(coords.world_to_layout(root, Vec2::new(130., 90.)), Some(Vec2::new(30., 40.)));
+        assert_eq!(coords.world_to_layout(node, Vec2::ZERO), None);
+        assert_eq!(coords.layout_to_screen(node), None);
+    }
+}
@@ -0,0 +1,115 @@
+//! Pause the layout of world-space roots outside of the camera view, see
+//! [`CullOffscreen`].
+//!
+//! Games with many small layouts in the world, such as signposts or item
+//! labels, spend time laying out roots no one sees. Add `CullOffscreen` to
+//! those roots, and their layout is skipped until they come near a camera view.
+//!
+//! Culling compares the axis-aligned bounding box of the root with the visible
+//! area of each active camera with an [`OrthographicProjection`]. The root is
+//! laid out if it is near the view of any of them.
+//!
+//! # Limitations
+//!
+//! - Only the layout is paused, culled roots are still rendered. They are
+//!   offscreen anyway, and `bevy_sprite` already skips offscreen sprites.
+//! - Cameras with a perspective projection are ignored. Roots are never
+//!   culled when there is no active orthographic camera.
+//! - Culling uses the `GlobalTransform` of the previous frame, use
+//!   [`CullOffscreen::margin`] so that roots are laid out before they show up.
+use bevy::ecs::prelude::*;
+use bevy::math::{Rect, Vec2};
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::prelude::{Camera, GlobalTransform, OrthographicProjection};
+use cuicui_layout::Root;
+
+/// Pause the layout of this [`Root`] while it is outside of the camera view.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct CullOffscreen {
+    /// How far from the camera view, in world units, the root is still laid out.
+    pub margin: f32,
+}
+impl Default for CullOffscreen {
+    /// A margin of 100 world units.
+    fn default() -> Self {
+        Self { margin: 100. }
+    }
+}
+
+/// The world-space bounding box of the `local` rectangle transformed by `transform`.
+fn world_aabb(local: Rect, transform: &GlobalTransform) -> Rect {
+    let Rect { min, max } = local;
+    let corners = [min, Vec2::new(min.x, max.y), max, Vec2::new(max.x, min.y)];
+    let corners = corners.map(|corner| transform.transform_point(corner.extend(0.)).truncate());
+    let aabb = Rect::from_corners(corners[0], corners[2]);
+    aabb.union_point(corners[1]).union_point(corners[3])
+}
+
+/// Set [`Root::paused`] of [`CullOffscreen`] roots outside of the camera view.
+#[allow(clippy::needless_pass_by_value)]
+pub fn cull_offscreen_roots(
+    cameras: Query<(&Camera, &OrthographicProjection, &GlobalTransform)>,
+    mut roots: Query<(&mut Root, &CullOffscreen, &GlobalTransform)>,
+) {
+    let active = cameras.iter().filter(|(camera, ..)| camera.is_active);
+    let views = active.map(|(_, projection, transform)| world_aabb(projection.area, transform));
+    let views: Vec<_> = views.collect();
+    if views.is_empty() {
+        for (mut root, ..) in &mut roots {
+            if root.paused {
+                root.paused = false;
+            }
+        }
+        return;
+    }
+    for (mut root, cull, transform) in &mut roots {
+        // The `Transform` of the root is at its top left corner, see `update_layout_transform`.
+        let local = Rect::from_corners(Vec2::ZERO, root.size().into());
+        let margin = Vec2::splat(cull.margin);
+        let Rect { min, max } = world_aabb(local, transform);
+        let near = Rect { min: min - margin, max: max + margin };
+        let offscreen = views.iter().all(|view| view.intersect(near).is_empty());
+        if root.paused != offscreen {
+            root.paused = offscreen;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use cuicui_layout::{Root, Size};
+
+    use super::*;
+
+    #[test]
+    fn offscreen_roots_are_paused() {
+        let mut world = World::new();
+        let area = Rect::new(-400., -300., 400., 300.);
+        let projection = OrthographicProjection { area, ..default() };
+        world.spawn((Camera::default(), projection, GlobalTransform::default()));
+        let root = || Root::builder().size(Size::new(200., 50.)).build();
+        let cull = CullOffscreen { margin: 10. };
+        let at = |x, y| GlobalTransform::from_xyz(x, y, 0.);
+        let signposts = [at(0., 0.), at(405., 0.), at(-1000., 0.), at(0., 2000.)];
+        let signposts = signposts.map(|at| world.spawn((root(), cull, at)).id());
+
+        let mut system = IntoSystem::into_system(cull_offscreen_roots);
+        system.initialize(&mut world);
+        system.run((), &mut world);
+
+        let paused = signposts.map(|e| world.get::<Root>(e).unwrap().paused);
+        assert_eq!(paused, [false, false, true, true]);
+
+        let mut camera = world.query_filtered::<&mut GlobalTransform, With<Camera>>();
+        *camera.single_mut(&mut world) = at(-1000., 0.);
+        system.run((), &mut world);
+
+        let paused = signposts.map(|e| world.get::<Root>(e).unwrap().paused);
+        assert_eq!(paused, [true, true, false, true]);
+    }
+}
@@ -0,0 +1,57 @@
+//! Order nodes on the Z axis, so that children are drawn in front of their
+//! parent, and later siblings in front of earlier ones.
+//!
+//! [`update_layout_transform`] sets the Z of the [`Transform`] of each node
+//! with a parent to:
+//!
+//! ```text
+//! (index + 1) × LayoutDepth::step + LayoutZ
+//! ```
+//!
+//! Where `index` is the position of the node in its parent's [`Children`].
+//! Since the `Transform` is relative to the parent, children are in front of
+//! their parent. Reordering children, for example with
+//! [`cuicui_layout::sort_children`], updates their Z.
+//!
+//! Roots keep the Z of their `Transform`, use it to place a whole layout in
+//! front of or behind other sprites. [`LayoutZ`] has no effect on roots.
+//!
+//! [`Children`]: bevy::hierarchy::Children
+//! [`Transform`]: bevy::prelude::Transform
+//! [`update_layout_transform`]: crate::update_layout_transform
+use bevy::ecs::prelude::*;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+
+/// Move this node by this distance on the Z axis, in addition to the Z of its
+/// position among its siblings, see the [module docs](self).
+///
+/// Use [`SpriteDsl::z_offset`] to spawn nodes with this component.
+///
+/// [`SpriteDsl::z_offset`]: crate::SpriteDsl::z_offset
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct LayoutZ(pub f32);
+
+/// The Z distance between siblings, and between a parent and its first child,
+/// see the [module docs](self).
+///
+/// Set `step` to `0.0` to only use [`LayoutZ`] offsets.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct LayoutDepth {
+    /// The Z distance between consecutive siblings.
+    pub step: f32,
+}
+impl Default for LayoutDepth {
+    fn default() -> Self {
+        Self { step: 0.001 }
+    }
+}
+impl LayoutDepth {
+    /// The Z of the `index`th child of a node with `offset` as [`LayoutZ`].
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // sibling index as f32
+    pub fn z(&self, index: usize, offset: f32) -> f32 {
+        (index + 1) as f32 * self.step + offset
+    }
+}
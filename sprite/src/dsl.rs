@@ -1,17 +1,28 @@
 //! Bundles wrapping [`bevy::sprite`] bundles with additional [`cuicui_layout`]
 //! components.
 use bevy::asset::Handle;
-use bevy::ecs::{prelude::*, system::EntityCommands};
-use bevy::prelude::{Deref, DerefMut};
+use bevy::ecs::{prelude::*, system::EntityCommands, world::EntityWorldMut};
+use bevy::log::error;
+use bevy::prelude::{Deref, DerefMut, Vec2};
 use bevy::render::prelude::*;
 use bevy::sprite;
 #[cfg(feature = "sprite_text")]
 use bevy::text::prelude::*;
+#[cfg(feature = "sprite_text")]
+use bevy::text::TextAlignment;
 use bevy::utils::default;
-use cuicui_dsl::DslBundle;
-use cuicui_layout::dsl::IntoUiBundle;
+#[cfg(feature = "image_size")]
+use cuicui_chirp::parse_dsl::args::SizedImage;
+use cuicui_dsl::{dsl_trait, DslBundle};
+use cuicui_layout::content_sized::ImageSizeHint;
+use cuicui_layout::dsl::{InsertFn, IntoUiBundle, LayoutDsl, LayoutDslTrait};
 use thiserror::Error;
 
+use crate::clip::ClipChildren;
+use crate::cull::CullOffscreen;
+use crate::depth::LayoutZ;
+use crate::image_repeat::ImageRepeat;
+
 /// An image leaf node wrapping a [`bevy::sprite::SpriteBundle`].
 ///
 /// If a `SpriteBundle`'s layout axis is not set, it will be dynamically computed
@@ -55,16 +66,20 @@ impl From<sprite::SpriteBundle> for SpriteBundle {
     }
 }
 
+// Both methods delegate to `$to`, so that `$to`'s own `into_insert_fn` runs.
 macro_rules! from_delegate_impl {
     ([$from:ty, $to:ty]) => {
-        from_delegate_impl!([$from, $to], |self| <$to>::from(self).into_ui_bundle());
+        from_delegate_impl!([$from, $to], |self| <$to>::from(self));
     };
     ([$from:ty, $to:ty], |$s:ident| $delegate_adaptor:expr) => {
         impl IntoUiBundle<SpriteDsl> for $from {
             type Target = <$to as IntoUiBundle<SpriteDsl>>::Target;
 
             fn into_ui_bundle($s) -> Self::Target {
-                $delegate_adaptor
+                IntoUiBundle::<SpriteDsl>::into_ui_bundle($delegate_adaptor)
+            }
+            fn into_insert_fn($s) -> InsertFn {
+                IntoUiBundle::<SpriteDsl>::into_insert_fn($delegate_adaptor)
             }
         }
     };
@@ -75,7 +90,7 @@ from_delegate_impl!([&'_ str, String]);
 #[cfg(feature = "sprite_text")]
 from_delegate_impl! {
     [String, Text],
-    |self| Text::from_section(self, TextStyle::default()).into_ui_bundle()
+    |self| Text::from_section(self, TextStyle::default())
 }
 #[cfg(feature = "sprite_text")]
 from_delegate_impl!([Text, TextBundle]);
@@ -119,6 +134,27 @@ fn parse_color(
     Ok(Color::rgba(red, green, blue, alpha))
 }
 
+/// Error occuring when parsing a [`TextAlignment`] that isn't `left`,
+/// `center` or `right`.
+#[cfg(feature = "sprite_text")]
+#[derive(Debug, Error)]
+#[error("'{0}' is not a valid text alignment, expected 'left', 'center' or 'right'")]
+pub struct ParseTextAlignError(String);
+
+#[cfg(all(feature = "chirp", feature = "sprite_text"))]
+fn parse_text_align(
+    _: &bevy::reflect::TypeRegistry,
+    _: Option<&mut bevy::asset::LoadContext>,
+    input: &str,
+) -> Result<TextAlignment, ParseTextAlignError> {
+    match input {
+        "left" => Ok(TextAlignment::Left),
+        "center" => Ok(TextAlignment::Center),
+        "right" => Ok(TextAlignment::Right),
+        _ => Err(ParseTextAlignError(input.to_string())),
+    }
+}
+
 /// The [`DslBundle`] for `bevy_ui`.
 #[derive(Default, Deref, DerefMut)]
 pub struct SpriteDsl<D = cuicui_layout::dsl::LayoutDsl> {
@@ -126,10 +162,29 @@ pub struct SpriteDsl<D = cuicui_layout::dsl::LayoutDsl> {
     inner: D,
     bg_color: Option<Color>,
     bg_image: Option<Handle<Image>>,
+    image_size: Option<Vec2>,
+    clip_children: bool,
+    flip_x: bool,
+    flip_y: bool,
+    tint: Option<Color>,
+    image_repeat: Option<Vec2>,
+    z_offset: Option<f32>,
+    cull_offscreen: Option<f32>,
+    #[cfg(feature = "sprite_text")]
+    text_align: Option<TextAlignment>,
 }
+#[dsl_trait]
 #[cfg_attr(
     feature = "chirp",
-    cuicui_chirp::parse_dsl_impl(delegate = inner, type_parsers(Color = parse_color)),
+    cuicui_chirp::parse_dsl_impl(
+        namespace = sprite,
+        delegate = inner,
+        type_parsers(
+            Color = parse_color,
+            SizedImage = args::to_sized_image,
+            TextAlignment = parse_text_align,
+        ),
+    ),
 )]
 impl<D> SpriteDsl<D> {
     /// Set the node's background color.
@@ -137,13 +192,121 @@ impl<D> SpriteDsl<D> {
         self.bg_color = Some(color);
     }
     /// Set the node's background image.
+    #[cfg_attr(feature = "image_size", parse_dsl(ignore))]
     pub fn image(&mut self, image: &Handle<Image>) {
         self.bg_image = Some(image.clone());
     }
+    /// Set the node's background image, measured at `image.size` until it is loaded.
+    ///
+    /// With the `image_size` feature, this is the `image` method of chirp files.
+    /// See [`ImageSizeHint`].
+    #[cfg(feature = "image_size")]
+    #[cfg_attr(feature = "chirp", parse_dsl(alias = "image"))]
+    pub fn sized_image(&mut self, image: SizedImage) {
+        self.bg_image = Some(image.handle);
+        self.image_size = image.size.map(|size| size.as_vec2());
+    }
+    /// Do not draw the parts of children [`Sprite`]s outside of this node.
+    ///
+    /// This adds the [`ClipChildren`] component, see the [`clip`] module
+    /// for limitations.
+    ///
+    /// Note that clipping is purely visual. `cuicui_layout` still reports an error
+    /// when the layout rules of children make them overflow this node. Clipping
+    /// is for content larger than its node that isn't checked by the layout
+    /// algorithm, such as children without layout [`Node`]s, or images
+    /// larger than their fixed-size node.
+    ///
+    /// [`Node`]: cuicui_layout::Node
+    /// [`Sprite`]: bevy::sprite::Sprite
+    /// [`clip`]: crate::clip
+    pub fn clip_children(&mut self) {
+        self.clip_children = true;
+    }
+    /// Repeat the image of this node over the node, rather than drawing it
+    /// once, each tile being `width`×`height` pixels.
+    ///
+    /// This applies both to the background [`Self::image`] and to images
+    /// spawned with `ui` or `spawn`. See the [`image_repeat`] module.
+    ///
+    /// [`image_repeat`]: crate::image_repeat
+    pub fn image_repeat(&mut self, width: f32, height: f32) {
+        if width <= 0. || height <= 0. {
+            error!("'image_repeat' expects a positive tile size, got '{width}×{height}'");
+            return;
+        }
+        self.image_repeat = Some(Vec2::new(width, height));
+    }
+    /// Move this node by `offset` on the Z axis, in front of (positive) or
+    /// behind (negative) its siblings.
+    ///
+    /// This adds the [`LayoutZ`] component, see the [`depth`] module.
+    ///
+    /// [`depth`]: crate::depth
+    pub fn z_offset(&mut self, offset: f32) {
+        self.z_offset = Some(offset);
+    }
+    /// Skip the layout of this root while it is more than `margin` world
+    /// units away from the camera view.
+    ///
+    /// This adds the [`CullOffscreen`] component, only use it on world-space
+    /// roots. See the [`cull`] module.
+    ///
+    /// [`cull`]: crate::cull
+    pub fn cull_offscreen(&mut self, margin: f32) {
+        self.cull_offscreen = Some(margin);
+    }
+    /// If this node has a [`Sprite`], flip it on its X axis.
+    ///
+    /// This applies both to the background [`Self::image`] and to images
+    /// spawned with `ui` or `spawn`.
+    ///
+    /// [`Sprite`]: bevy::sprite::Sprite
+    pub fn flip_x(&mut self) {
+        self.flip_x = true;
+    }
+    /// If this node has a [`Sprite`], flip it on its Y axis.
+    ///
+    /// This applies both to the background [`Self::image`] and to images
+    /// spawned with `ui` or `spawn`.
+    ///
+    /// [`Sprite`]: bevy::sprite::Sprite
+    pub fn flip_y(&mut self) {
+        self.flip_y = true;
+    }
+    /// If this node has a [`Sprite`], multiply its colors by `color`.
+    ///
+    /// This sets the [`Sprite::color`], including of images spawned with `ui`
+    /// or `spawn`.
+    ///
+    /// [`Sprite`]: bevy::sprite::Sprite
+    /// [`Sprite::color`]: bevy::sprite::Sprite::color
+    pub fn tint(&mut self, color: Color) {
+        self.tint = Some(color);
+    }
+    /// If this node contains text, align it to the `left`, `center` or `right`
+    /// of the node.
+    ///
+    /// By default, text is aligned left. Text is always at the top of its
+    /// node, see the [`text`] module.
+    ///
+    /// [`text`]: crate::text
+    #[cfg(feature = "sprite_text")]
+    pub fn text_align(&mut self, alignment: TextAlignment) {
+        self.text_align = Some(alignment);
+    }
 }
 
+impl<L: DslBundle, D: LayoutDslTrait<L>> LayoutDslTrait<L> for SpriteDsl<D> {
+    fn from_layout_dsl(&mut self) -> &mut LayoutDsl<L> {
+        self.inner.from_layout_dsl()
+    }
+}
 impl<D: DslBundle> DslBundle for SpriteDsl<D> {
     fn insert(&mut self, cmds: &mut EntityCommands) {
+        if let (Some(size), Some(_)) = (self.image_size.take(), &self.bg_image) {
+            cmds.insert(ImageSizeHint(size));
+        }
         match (self.bg_color.take(), self.bg_image.take()) {
             (Some(color), Some(texture)) => {
                 let sprite = sprite::Sprite { color, ..default() };
@@ -156,6 +319,41 @@ impl<D: DslBundle> DslBundle for SpriteDsl<D> {
             (None, Some(texture)) => cmds.insert((sprite::SpriteBundle { texture, ..default() },)),
             (None, None) => cmds.insert(SpatialBundle::default()),
         };
+        if self.clip_children {
+            cmds.insert(ClipChildren);
+        }
+        if let Some(tile_size) = self.image_repeat.take() {
+            cmds.insert(ImageRepeat(tile_size));
+        }
+        if let Some(offset) = self.z_offset.take() {
+            cmds.insert(LayoutZ(offset));
+        }
+        if let Some(margin) = self.cull_offscreen.take() {
+            cmds.insert(CullOffscreen { margin });
+        }
         self.inner.insert(cmds);
+        // `ui` and `spawn` images are inserted by `inner`, so we update the sprite afterward.
+        let (flip_x, flip_y, tint) = (self.flip_x, self.flip_y, self.tint.take());
+        if flip_x || flip_y || tint.is_some() {
+            cmds.add(move |mut entity: EntityWorldMut| {
+                let Some(mut sprite) = entity.get_mut::<sprite::Sprite>() else {
+                    return;
+                };
+                sprite.flip_x |= flip_x;
+                sprite.flip_y |= flip_y;
+                if let Some(tint) = tint {
+                    sprite.color = tint;
+                }
+            });
+        }
+        // The text is inserted by `inner`, so we set its alignment afterward.
+        #[cfg(feature = "sprite_text")]
+        if let Some(alignment) = self.text_align.take() {
+            cmds.add(move |mut entity: EntityWorldMut| {
+                if let Some(mut text) = entity.get_mut::<Text>() {
+                    text.alignment = alignment;
+                }
+            });
+        }
     }
 }
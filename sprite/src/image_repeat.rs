@@ -0,0 +1,100 @@
+//! Tile the image of sprites over their node rather than drawing it once,
+//! see [`ImageRepeat`].
+//!
+//! The [`Sprite::rect`] of repeated sprites extends beyond their texture, and
+//! their image's sampler is set to [`ImageAddressMode::Repeat`], so that the
+//! texture repeats over the node.
+//!
+//! # Limitations
+//!
+//! The sampler is set on the [`Image`] asset, so all sprites using this image
+//! repeat it. If the image used the default [`ImagePlugin`] sampler, it is
+//! replaced by [`ImageSamplerDescriptor::default`], which may use a different
+//! filter mode.
+//!
+//! [`ImagePlugin`]: bevy::render::texture::ImagePlugin
+use bevy::asset::{Assets, Handle};
+use bevy::ecs::prelude::*;
+use bevy::math::{Rect, Vec2};
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::render::texture::{Image, ImageAddressMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::sprite::{Anchor, Sprite};
+use cuicui_layout::LayoutRect;
+
+use crate::clip::UnclippedSprite;
+
+/// Repeat the image of this sprite over its node, each tile being this size
+/// in pixels.
+///
+/// Use [`SpriteDsl::image_repeat`] to spawn nodes with this component.
+///
+/// [`SpriteDsl::image_repeat`]: crate::SpriteDsl::image_repeat
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct ImageRepeat(pub Vec2);
+
+fn set_repeat(sampler: &mut ImageSampler) {
+    let descriptor = match sampler {
+        ImageSampler::Default => {
+            *sampler = ImageSampler::Descriptor(ImageSamplerDescriptor::default());
+            let ImageSampler::Descriptor(descriptor) = sampler else { unreachable!() };
+            descriptor
+        }
+        ImageSampler::Descriptor(descriptor) => descriptor,
+    };
+    descriptor.address_mode_u = ImageAddressMode::Repeat;
+    descriptor.address_mode_v = ImageAddressMode::Repeat;
+}
+fn is_repeat(sampler: &ImageSampler) -> bool {
+    use ImageAddressMode::Repeat;
+    let ImageSampler::Descriptor(descriptor) = sampler else {
+        return false;
+    };
+    matches!((descriptor.address_mode_u, descriptor.address_mode_v), (Repeat, Repeat))
+}
+
+/// Size [`ImageRepeat`] sprites to their [`LayoutRect`], and repeat their image.
+///
+/// Clipped sprites have their [`UnclippedSprite`] updated instead of their `Sprite`.
+pub fn repeat_images(
+    mut images: ResMut<Assets<Image>>,
+    mut sprites: Query<(
+        &ImageRepeat,
+        &LayoutRect,
+        &mut Sprite,
+        Option<&mut UnclippedSprite>,
+        &Handle<Image>,
+    )>,
+) {
+    for (repeat, rect, mut sprite, unclipped, handle) in &mut sprites {
+        let Some(image) = images.get(handle) else {
+            continue;
+        };
+        let texture_size = image.size_f32();
+        if !is_repeat(&image.sampler) {
+            set_repeat(&mut images.get_mut(handle).unwrap().sampler);
+        }
+        let size = rect.size();
+        let size = Vec2::new(size.width, size.height);
+        let texture_rect = Some(Rect::from_corners(Vec2::ZERO, size / repeat.0 * texture_size));
+        let is_set = |custom_size, rect| custom_size == Some(size) && rect == texture_rect;
+        let set = |custom_size: &mut Option<Vec2>, rect: &mut Option<Rect>, anchor: &mut _| {
+            *custom_size = Some(size);
+            *rect = texture_rect;
+            // Cover the node's `LayoutRect`, which starts at the sprite's position.
+            *anchor = Anchor::BottomLeft;
+        };
+        match unclipped {
+            Some(mut unclipped) if !is_set(unclipped.custom_size, unclipped.rect) => {
+                let unclipped = &mut *unclipped;
+                set(&mut unclipped.custom_size, &mut unclipped.rect, &mut unclipped.anchor);
+            }
+            None if !is_set(sprite.custom_size, sprite.rect) => {
+                let sprite = &mut *sprite;
+                set(&mut sprite.custom_size, &mut sprite.rect, &mut sprite.anchor);
+            }
+            _ => {}
+        }
+    }
+}
@@ -15,17 +15,41 @@
 
 use bevy::app::{App, Plugin as BevyPlugin};
 use bevy::ecs::prelude::*;
-use bevy::prelude::{Camera, Camera2dBundle, OrthographicProjection, Transform, Vec2};
+use bevy::hierarchy::{Children, Parent};
+use bevy::prelude::{Camera, Camera2dBundle, GlobalTransform, OrthographicProjection};
+use bevy::prelude::{Transform, Vec2};
 use bevy::render::view::{Layer, RenderLayers};
 use bevy::utils::default;
 use bevy_mod_sysfail::quick_sysfail;
-use cuicui_layout::content_sized::AppContentSizeExt;
-use cuicui_layout::{LayoutRect, LayoutRootCamera, Root, ScreenRoot};
+use cuicui_layout::{attach::AttachmentPoint, content_sized::AppContentSizeExt};
+use cuicui_layout::{Backend, LayoutRect, LayoutRootCamera, PixelSnap, Root, ScreenRoot};
+
+use depth::{LayoutDepth, LayoutZ};
 
 pub use dsl::SpriteDsl;
 
+#[cfg(feature = "atlas")]
+pub mod atlas;
+pub mod clip;
 pub mod content_sized;
+pub mod coords;
+pub mod cull;
+pub mod depth;
 pub mod dsl;
+pub mod image_repeat;
+#[cfg(feature = "sprite_text")]
+pub mod text;
+
+/// The most common `cuicui_layout` and `cuicui_layout_bevy_sprite` items,
+/// `use cuicui_layout_bevy_sprite::prelude::*;` to import them.
+///
+/// This includes the [`cuicui_layout::prelude`], [`SpriteDsl`] and
+/// [`dsl::SpriteDslTrait`]. The [`Plugin`] is exported as `SpriteLayoutPlugin`,
+/// to not conflict with bevy's `Plugin` trait.
+pub mod prelude {
+    pub use crate::{dsl::SpriteDslTrait, Plugin as SpriteLayoutPlugin, RootBundle, SpriteDsl};
+    pub use cuicui_layout::prelude::*;
+}
 
 /// Create a [`Root`] container as the screen root, its size will dyamically
 /// follow the size of the viewport of camera marked iwth [`LayoutRootCamera`].
@@ -117,15 +141,62 @@ pub fn set_added_layout_camera_root(
     }
 }
 /// Set the [`Transform`]s according to [`LayoutRect`]'s computed from [`cuicui_layout`].
+///
+/// With [`PixelSnap`], the [snapped](LayoutRect::snapped) position is used.
+///
+/// The Z of nodes with a parent depends on their position among their siblings
+/// and their [`LayoutZ`], see the [`depth`] module.
+///
+/// The `Transform` is only written to when the position changes, so that nodes
+/// that only changed size do not trigger transform propagation.
+#[allow(clippy::type_complexity, clippy::needless_pass_by_value)]
 pub fn update_layout_transform(
-    mut query: Query<(&mut Transform, &LayoutRect), Changed<LayoutRect>>,
+    snap: Res<PixelSnap>,
+    depth: Res<LayoutDepth>,
+    mut query: Query<(
+        Entity,
+        &mut Transform,
+        Ref<LayoutRect>,
+        Option<Ref<LayoutZ>>,
+        Option<&Parent>,
+    )>,
+    children: Query<Ref<Children>>,
 ) {
-    query.for_each_mut(|(mut transform, rect)| {
-        let z = transform.translation.z;
-        transform.translation = rect.pos().extend(z);
+    let update_all = snap.is_changed() || depth.is_changed();
+    query.for_each_mut(|(entity, mut transform, rect, offset, parent)| {
+        let siblings = parent.and_then(|parent| children.get(parent.get()).ok());
+        let offset_changed = offset.as_ref().is_some_and(Ref::is_changed);
+        let siblings_changed = siblings.as_ref().is_some_and(Ref::is_changed);
+        if !update_all && !rect.is_changed() && !offset_changed && !siblings_changed {
+            return;
+        }
+        let index = siblings.and_then(|siblings| siblings.iter().position(|e| *e == entity));
+        let z = match index {
+            Some(index) => depth.z(index, offset.map_or(0., |offset| offset.0)),
+            None => transform.translation.z,
+        };
+        let translation = rect.snapped_if(*snap).pos().extend(z);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
     });
 }
 
+/// Set the [`AttachmentPoint::position`] of nodes, in world coordinates, like
+/// their `GlobalTransform`.
+pub fn update_attachment_points(
+    mut points: Query<(&mut AttachmentPoint, &LayoutRect, &GlobalTransform)>,
+) {
+    for (mut point, rect, global) in &mut points {
+        // The `Transform` of nodes is at their top left corner.
+        let local = point.local_position(rect.size(), Vec2::ZERO);
+        let position = global.transform_point(local.extend(0.)).truncate();
+        if point.position != position {
+            point.position = position;
+        }
+    }
+}
+
 /// Plugin managing position and size of `bevy_sprite` renderable components
 ///  using [`cuicui_layout`] components.
 ///
@@ -135,7 +206,16 @@ pub fn update_layout_transform(
 ///   based on their `cuicui_layout`-infered size.
 /// - Manage the size of content-sized [`cuicui_layout::Node`].
 /// - Manage size of the [`cuicui_layout::ScreenRoot`] container
-/// - Set the [`Transform`] of entities with a [`cuicui_layout::Node`] component
+/// - Set the [`Transform`] of entities with a [`cuicui_layout::Node`] component,
+///   ordering them on the Z axis, see the [`depth`] module
+/// - Set the position of [`AttachmentPoint`]s after transform propagation
+/// - Clip sprites descendant of [`clip::ClipChildren`] nodes
+/// - Pause the layout of [`cull::CullOffscreen`] roots outside of the camera view
+/// - Repeat the image of [`image_repeat::ImageRepeat`] sprites over their node
+/// - With the `sprite_text` feature, wrap and align `Text2d` within their
+///   node, see the `text` module
+/// - With the `atlas` feature, pack images of sprites descendant of
+///   `atlas::PackSprites` entities into a single texture
 ///
 /// [`Sprite`]: bevy::sprite::Sprite
 /// [`Mesh2dHandle`]: bevy::sprite::Mesh2dHandle
@@ -144,17 +224,184 @@ pub struct Plugin;
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         use bevy::prelude::Update;
-        use cuicui_layout::ComputeLayoutSet;
+        use cuicui_layout::{LayoutOutputSet, RootSizeSet};
 
         app.add_plugins(cuicui_layout::Plugin)
             .add_content_sized::<content_sized::SpriteContentSize>()
+            .init_resource::<LayoutDepth>()
             .add_systems(
                 Update,
                 (
-                    (update_layout_camera_root, set_added_layout_camera_root)
-                        .before(ComputeLayoutSet),
-                    update_layout_transform.after(ComputeLayoutSet),
+                    (update_layout_camera_root, set_added_layout_camera_root).in_set(RootSizeSet),
+                    cull::cull_offscreen_roots.in_set(RootSizeSet),
+                    update_layout_transform.in_set(LayoutOutputSet),
+                    clip::clip_sprites.run_if(clip::clip_changed).in_set(LayoutOutputSet),
+                    image_repeat::repeat_images
+                        .before(clip::clip_sprites)
+                        .in_set(LayoutOutputSet),
                 ),
+            )
+            .add_systems(
+                bevy::prelude::PostUpdate,
+                update_attachment_points
+                    .after(bevy::transform::TransformSystem::TransformPropagate)
+                    .in_set(LayoutOutputSet),
             );
+        // Runs in `UpdateLeafNodes` so that `PackedSprite` is inserted before
+        // content-sized nodes are computed.
+        #[cfg(feature = "atlas")]
+        app.add_systems(
+            Update,
+            atlas::pack_sprites
+                .after(cuicui_layout::update_leaf_nodes)
+                .in_set(cuicui_layout::UpdateLeafNodes)
+                .in_set(cuicui_layout::ComputeLayoutSet),
+        );
+        #[cfg(feature = "sprite_text")]
+        app.add_systems(Update, text::set_text_bounds.in_set(LayoutOutputSet))
+            .add_systems(
+                bevy::prelude::PostUpdate,
+                text::align_text.after(bevy::text::update_text2d_layout),
+            );
+        #[cfg(feature = "reflect")]
+        app.register_type::<clip::ClipChildren>()
+            .register_type::<cull::CullOffscreen>()
+            .register_type::<image_repeat::ImageRepeat>()
+            .register_type::<LayoutZ>();
+        #[cfg(all(feature = "reflect", feature = "atlas"))]
+        app.register_type::<atlas::PackSprites>();
+    }
+}
+
+/// The `bevy_sprite` [`Backend`], to use with [`AppCuicuiExt::add_cuicui`].
+///
+/// This adds [`Plugin`].
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use cuicui_layout::{AppCuicuiExt, CuicuiConfig};
+/// use cuicui_layout_bevy_sprite::{SpriteBackend, SpriteDsl};
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_cuicui::<SpriteDsl>(CuicuiConfig { backend: SpriteBackend, chirp: true, debug: false })
+///     .run();
+/// ```
+///
+/// [`AppCuicuiExt::add_cuicui`]: cuicui_layout::AppCuicuiExt::add_cuicui
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SpriteBackend;
+impl Backend for SpriteBackend {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Plugin);
+    }
+    fn is_added(&self, app: &App) -> bool {
+        app.is_plugin_added::<Plugin>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use cuicui_layout::{LayoutRect, PixelSnap};
+
+    use super::{update_attachment_points, update_layout_transform};
+    use crate::depth::{LayoutDepth, LayoutZ};
+
+    #[test]
+    fn unchanged_position_keeps_transform() {
+        let mut world = World::new();
+        world.init_resource::<PixelSnap>();
+        world.init_resource::<LayoutDepth>();
+        let bundle = (Transform::default(), LayoutRect::default());
+        world.spawn_batch((0..5).map(|_| bundle));
+
+        let mut system = IntoSystem::into_system(update_layout_transform);
+        system.initialize(&mut world);
+        system.run((), &mut world);
+        world.clear_trackers();
+
+        let mut rects = world.query::<&mut LayoutRect>();
+        rects.for_each_mut(&mut world, |mut rect| rect.set_changed());
+        system.run((), &mut world);
+
+        let mut transforms = world.query::<Ref<Transform>>();
+        assert_eq!(transforms.iter(&world).filter(Ref::is_changed).count(), 0);
+    }
+
+    #[test]
+    fn sibling_order_sets_z() {
+        let mut world = World::new();
+        world.init_resource::<PixelSnap>();
+        world.insert_resource(LayoutDepth { step: 0.5 });
+        let node = || (Transform::from_xyz(0., 0., 7.), LayoutRect::default());
+        let mut children = Vec::new();
+        let root = world
+            .spawn(node())
+            .with_children(|cmds| {
+                children.push(cmds.spawn(node()).id());
+                children.push(cmds.spawn((node(), LayoutZ(2.))).id());
+                children.push(cmds.spawn(node()).id());
+            })
+            .id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_layout_transform);
+        let z = |world: &World, entity| world.get::<Transform>(entity).unwrap().translation.z;
+
+        schedule.run(&mut world);
+        assert_eq!(z(&world, root), 7.);
+        let zs: Vec<_> = children.iter().map(|child| z(&world, *child)).collect();
+        assert_eq!(zs, [0.5, 3., 1.5]);
+
+        world.get_mut::<Children>(root).unwrap().swap(0, 2);
+        schedule.run(&mut world);
+        let zs: Vec<_> = children.iter().map(|child| z(&world, *child)).collect();
+        assert_eq!(zs, [1.5, 3., 0.5]);
+    }
+
+    #[test]
+    fn attachment_point_position() {
+        use cuicui_layout::{attach::AttachmentPoint, Anchor};
+
+        let mut world = World::new();
+        let rect = LayoutRect::from(Rect::new(0., 0., 100., 40.));
+        let global = GlobalTransform::from_xyz(10., 20., 0.);
+        let point = AttachmentPoint::new("socket", Anchor::BottomRight);
+        let entity = world.spawn((point, rect, global)).id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_attachment_points);
+        schedule.run(&mut world);
+        let point = world.get::<AttachmentPoint>(entity).unwrap();
+        assert_eq!(point.position, Vec2::new(110., 60.));
+    }
+
+    #[test]
+    fn ui_closure_runs() {
+        use bevy::ecs::system::CommandQueue;
+        use cuicui_dsl::dsl;
+
+        use crate::SpriteDsl;
+
+        #[derive(Component)]
+        struct Marker;
+
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut cmds = Commands::new(&mut queue, &world);
+        let mut root = cmds.spawn_empty();
+        dsl! { <SpriteDsl> &mut root,
+            Root(column) {
+                Closure(ui(|cmds: &mut EntityCommands| {
+                    cmds.insert(Marker);
+                }))
+                Tuple(ui((Marker, Visibility::Hidden)))
+            }
+        };
+        queue.apply(&mut world);
+
+        assert_eq!(world.query::<&Marker>().iter(&world).count(), 2);
     }
 }
@@ -0,0 +1,91 @@
+//! Fit `Text2d` leaf nodes to their [`LayoutRect`].
+//!
+//! `bevy_sprite` positions text relative to its [`Transform`] with its
+//! [`Anchor`], and wraps it based on its [`Text2dBounds`]. Neither know about
+//! the size of the text's layout node, so:
+//!
+//! - [`set_text_bounds`] sets the `Text2dBounds` to the `LayoutRect` size,
+//!   so that text wraps within its node.
+//! - [`align_text`] sets the `Anchor` so that text is at the top of its node,
+//!   and on the left, center or right based on its [`Text::alignment`].
+//!
+//! Use [`SpriteDsl::text_align`] to set the alignment with the `dsl!` macro
+//! or in chirp files.
+//!
+//! [`SpriteDsl::text_align`]: crate::SpriteDsl::text_align
+//! [`Transform`]: bevy::prelude::Transform
+use bevy::ecs::prelude::*;
+use bevy::math::Vec2;
+use bevy::sprite::Anchor;
+use bevy::text::{Text, Text2dBounds, TextAlignment, TextLayoutInfo};
+use cuicui_layout::LayoutRect;
+
+/// Set the [`Text2dBounds`] of text nodes to the size of their [`LayoutRect`].
+pub fn set_text_bounds(mut texts: Query<(&mut Text2dBounds, &LayoutRect), Changed<LayoutRect>>) {
+    for (mut bounds, rect) in &mut texts {
+        let size = rect.size();
+        let size = Vec2::new(size.width, size.height);
+        if bounds.size != size {
+            bounds.size = size;
+        }
+    }
+}
+
+/// The [`Anchor`] placing a text of size `text` within a node of size `node`.
+fn text_anchor(alignment: TextAlignment, node: Vec2, text: Vec2) -> Option<Anchor> {
+    if text.x <= 0. || text.y <= 0. {
+        return None;
+    }
+    let x = match alignment {
+        TextAlignment::Left => 0.,
+        TextAlignment::Center => 0.5,
+        TextAlignment::Right => 1.,
+    };
+    // The node's position is its `Transform`, the text block is offset by
+    // `-(anchor + 0.5) * text` from it.
+    let offset = Vec2::new(x, 1.) * (node - text);
+    Some(Anchor::Custom(-0.5 - offset / text))
+}
+
+/// Set the [`Anchor`] of text nodes so that the text is aligned within their
+/// [`LayoutRect`] according to their [`Text::alignment`].
+///
+/// This runs after `bevy_text` computed the text size, in `PostUpdate`.
+pub fn align_text(
+    mut texts: Query<
+        (&mut Anchor, &Text, &TextLayoutInfo, &LayoutRect),
+        Or<(Changed<LayoutRect>, Changed<Text>, Changed<TextLayoutInfo>)>,
+    >,
+) {
+    for (mut anchor, text, info, rect) in &mut texts {
+        let size = rect.size();
+        let node = Vec2::new(size.width, size.height);
+        let Some(new_anchor) = text_anchor(text.alignment, node, info.logical_size) else {
+            continue;
+        };
+        if anchor.as_vec() != new_anchor.as_vec() {
+            *anchor = new_anchor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bottom left corner of a text of size `text` placed with `anchor`.
+    fn text_min(anchor: &Anchor, text: Vec2) -> Vec2 {
+        -(anchor.as_vec() + 0.5) * text
+    }
+
+    #[test]
+    fn anchor_aligns_in_node() {
+        let (node, text) = (Vec2::new(100., 40.), Vec2::new(30., 10.));
+        let min = |alignment| text_min(&text_anchor(alignment, node, text).unwrap(), text);
+
+        assert_eq!(min(TextAlignment::Left), Vec2::new(0., 30.));
+        assert_eq!(min(TextAlignment::Center), Vec2::new(35., 30.));
+        assert_eq!(min(TextAlignment::Right), Vec2::new(70., 30.));
+        assert!(text_anchor(TextAlignment::Left, node, Vec2::ZERO).is_none());
+    }
+}
@@ -0,0 +1,63 @@
+//! Move every node of a 600 nodes menu each frame, with `bevy_ui`'s own
+//! layout running, like in a real app.
+//!
+//! - `style`: With `Positioning::Style`, positions go through `bevy_ui`'s
+//!   flexbox layout.
+//! - `direct`: With `Positioning::Direct`, positions are written to the
+//!   `GlobalTransform` of nodes, skipping `bevy_ui`'s layout.
+//!
+//! Run with `cargo bench -p cuicui_layout_bevy_ui`.
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, Criterion};
+use cuicui_layout::bundles::LayoutBundle;
+use cuicui_layout::{Alignment, Distribution, Flow, LeafRule, Node, Root, Size};
+use cuicui_layout_bevy_ui::Positioning;
+
+/// Nodes in the menu.
+const NODES: u32 = 600;
+
+fn menu(positioning: Positioning) -> (App, Entity) {
+    let mut app = App::new();
+    // `bevy_ui` without rendering: its plugin still needs the render assets.
+    app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin, AssetPlugin::default()))
+        .add_plugins((bevy::input::InputPlugin, bevy::window::WindowPlugin::default()))
+        .init_asset::<Shader>()
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlas>()
+        .add_plugins((bevy::text::TextPlugin, bevy::ui::UiPlugin, cuicui_layout_bevy_ui::Plugin))
+        .insert_resource(positioning);
+
+    let size = Size::new(100., 10_000.);
+    let root = Root::new(size, Flow::Vertical, Alignment::Start, Distribution::FillMain, Size::ZERO);
+    let root = app.world.spawn((NodeBundle::default(), root, LayoutBundle::default())).id();
+    for _ in 0..NODES {
+        let node = Node::Box(Size::all(LeafRule::Fixed(10.)));
+        let child = app.world.spawn((NodeBundle::default(), LayoutBundle { node, ..default() }));
+        let child = child.id();
+        app.world.entity_mut(root).add_child(child);
+    }
+    app.update();
+    let size = app.world.get::<bevy::ui::Node>(root).unwrap().size();
+    assert_eq!(size, Vec2::new(100., 10_000.), "bevy_ui should lay out the menu");
+    (app, root)
+}
+
+fn positioning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("move_all_nodes");
+    for (name, positioning) in [("style", Positioning::Style), ("direct", Positioning::Direct)] {
+        let (mut app, root) = menu(positioning);
+        let mut toggle = false;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                toggle = !toggle;
+                let mut root = app.world.get_mut::<Root>(root).unwrap();
+                *root.size_mut().height = if toggle { 9_000. } else { 10_000. };
+                app.update();
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, positioning);
+criterion_main!(benches);
@@ -4,27 +4,36 @@
 //! This relies on the [`bevy::ui::Node`] component.
 use bevy::asset::Assets;
 use bevy::ecs::schedule::SystemSetConfigs;
-use bevy::ecs::{prelude::*, system::SystemParam};
+use bevy::ecs::{prelude::*, query::QueryItem, system::SystemParam};
 use bevy::log::trace;
 use bevy::prelude::Vec2;
 use bevy::text::{Font, Text, TextMeasureInfo};
 use bevy::ui::widget::UiImageSize;
 use cuicui_layout::content_sized::{
-    ComputeContentParam, ComputeContentSize, ContentSizedComputeSystem,
+    ComputeContentParam, ComputeContentSize, ContentSizedComputeSystem, DefaultImageScale,
+    ImageScale, ImageSizeHint,
 };
 use cuicui_layout::{require_layout_recompute, Node, Size};
 
 #[derive(SystemParam)]
 pub(crate) struct UiContentSize<'w> {
     fonts: Res<'w, Assets<Font>>,
+    image_scale: Res<'w, DefaultImageScale>,
 }
+type Components = (
+    AnyOf<(Ref<'static, Text>, Ref<'static, UiImageSize>)>,
+    Option<Ref<'static, ImageScale>>,
+    Option<Ref<'static, ImageSizeHint>>,
+);
 impl ComputeContentParam for UiContentSize<'static> {
-    type Components = AnyOf<(&'static Text, &'static UiImageSize)>;
+    type Components = Components;
 
     fn condition(label: ContentSizedComputeSystem<Self>) -> SystemSetConfigs {
         use bevy::ecs::schedule::common_conditions as cond;
 
         let cond = cond::resource_changed::<Assets<Font>>()
+            .or_else(cond::resource_changed::<DefaultImageScale>())
+            .or_else(|c: Query<(), (Changed<ImageScale>, With<Node>)>| !c.is_empty())
             .or_else(|c: Query<(), (Changed<UiImageSize>, With<Node>)>| !c.is_empty())
             .or_else(|c: Query<(), (Changed<Text>, With<Node>)>| !c.is_empty());
 
@@ -57,25 +66,153 @@ fn compute_image_size(size: Vec2, set_size: Size<Option<f32>>) -> Vec2 {
     Vec2::select(size.is_nan_mask(), Vec2::ZERO, size)
 }
 impl ComputeContentSize for UiContentSize<'_> {
-    type Components = AnyOf<(&'static Text, &'static UiImageSize)>;
+    type Components = Components;
 
     fn compute_content(
         &self,
-        components: (Option<&Text>, Option<&UiImageSize>),
+        components: QueryItem<Components>,
         set_size: Size<Option<f32>>,
+    ) -> Size<f32> {
+        self.compute_content_within(components, set_size, None)
+    }
+    /// Text wraps at `available_width`, images ignore it.
+    fn compute_content_within(
+        &self,
+        components: QueryItem<Components>,
+        set_size: Size<Option<f32>>,
+        available_width: Option<f32>,
     ) -> Size<f32> {
         let inf = f32::INFINITY;
         let size_vec = Vec2::new(
-            set_size.width.unwrap_or(inf),
+            set_size.width.or(available_width).unwrap_or(inf),
             set_size.height.unwrap_or(inf),
         );
+        let (components, scale, hint) = components;
         let bevy_ui = match components {
-            (Some(text), _) => self.bounds(text, size_vec),
-            (None, Some(image)) => compute_image_size(image.size(), set_size),
+            (Some(text), _) => self.bounds(&text, size_vec),
+            (None, Some(image)) => {
+                // `UiImageSize` is zero until the image is loaded.
+                let size = match (image.size(), hint) {
+                    (size, Some(hint)) if size == Vec2::ZERO => hint.0,
+                    (size, _) => size,
+                };
+                let size = ImageScale::logical_size(scale.as_deref(), &self.image_scale, size);
+                compute_image_size(size, set_size)
+            }
             (None, None) => {
                 unreachable!("This is a bevy bug: AnyOf should at least have one element")
             }
         };
         bevy_ui.into()
     }
+
+    fn all_changed(&self) -> bool {
+        self.fonts.is_changed() || self.image_scale.is_changed()
+    }
+    fn content_changed(&self, ((text, image), scale, hint): &QueryItem<Components>) -> bool {
+        text.as_ref().is_some_and(DetectChanges::is_changed)
+            || image.as_ref().is_some_and(DetectChanges::is_changed)
+            || scale.as_ref().is_some_and(DetectChanges::is_changed)
+            || hint.as_ref().is_some_and(DetectChanges::is_changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::*;
+    use bevy::ui::widget::{update_image_content_size_system, UiImageSize};
+    use bevy::ui::{ContentSize, Node as UiNode};
+    use cuicui_layout::content_sized::{DefaultImageScale, ImageScale, ImageSizeHint};
+    use cuicui_layout::testing::{compute_content, compute_content_within, FakeAssets};
+    use cuicui_layout::Size;
+
+    use super::UiContentSize;
+
+    const UNSET: Size<Option<f32>> = Size::new(None, None);
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Assets<Font>>();
+        world.init_resource::<DefaultImageScale>();
+        world.init_resource::<UiScale>();
+        world
+    }
+    fn size(world: &mut World, entity: Entity, set_size: Size<Option<f32>>) -> Size<f32> {
+        compute_content::<UiContentSize<'static>>(world, entity, set_size).unwrap()
+    }
+    fn size_within(world: &mut World, entity: Entity, available_width: f32) -> Size<f32> {
+        let available_width = Some(available_width);
+        compute_content_within::<UiContentSize<'static>>(world, entity, UNSET, available_width)
+            .unwrap()
+    }
+
+    #[test]
+    fn image_size() {
+        let mut world = world();
+        let texture = world.add_fake_image(40, 20);
+        let components = (UiNode::default(), UiImage::new(texture), UiImageSize::default());
+        let image = world.spawn((components, ContentSize::default())).id();
+        world.run_system_once(update_image_content_size_system);
+
+        assert_eq!(size(&mut world, image, UNSET), Size::new(40., 20.));
+        let width = Size::new(Some(80.), None);
+        assert_eq!(size(&mut world, image, width), Size::new(80., 40.));
+        let height = Size::new(None, Some(10.));
+        assert_eq!(size(&mut world, image, height), Size::new(20., 10.));
+
+        world.entity_mut(image).insert(ImageScale(2.));
+        assert_eq!(size(&mut world, image, UNSET), Size::new(20., 10.));
+        assert_eq!(size(&mut world, image, width), Size::new(80., 40.));
+    }
+    #[test]
+    fn image_size_hint() {
+        let mut world = world();
+        let components = (UiNode::default(), UiImage::default(), UiImageSize::default());
+        let hint = ImageSizeHint(Vec2::new(40., 20.));
+        let image = world.spawn((components, ContentSize::default(), hint)).id();
+
+        assert_eq!(size(&mut world, image, UNSET), Size::new(40., 20.));
+        let width = Size::new(Some(80.), None);
+        assert_eq!(size(&mut world, image, width), Size::new(80., 40.));
+
+        let texture = world.add_fake_image(10, 10);
+        world.entity_mut(image).insert(UiImage::new(texture));
+        world.run_system_once(update_image_content_size_system);
+        assert_eq!(size(&mut world, image, UNSET), Size::new(10., 10.));
+    }
+    #[test]
+    fn text_size() {
+        let mut world = world();
+        let font = world.add_test_font();
+        let style = TextStyle { font, font_size: 20., color: Color::WHITE };
+        let mut text = |value: &str| world.spawn(Text::from_section(value, style.clone())).id();
+        let (one, ten, two_words) = (text("A"), text("AAAAAAAAAA"), text("AAAAA AAAAA"));
+
+        let one = size(&mut world, one, UNSET);
+        let ten = size(&mut world, ten, UNSET);
+        assert!(one.width > 0. && one.height > 0.);
+        assert!((ten.width - one.width * 10.).abs() < 0.5, "{ten} is not 10 × {one}");
+        assert_eq!(ten.height, one.height);
+
+        let wrapped = size(&mut world, two_words, Size::new(Some(one.width * 7.), None));
+        assert!(wrapped.width <= one.width * 7.);
+        assert!(wrapped.height > one.height * 1.5, "{wrapped} is not two lines of {one}");
+    }
+    #[test]
+    fn text_wraps_to_available_width() {
+        let mut world = world();
+        let font = world.add_test_font();
+        let style = TextStyle { font, font_size: 20., color: Color::WHITE };
+        let mut text = |value: &str| world.spawn(Text::from_section(value, style.clone())).id();
+        let (one, two_words) = (text("A"), text("AAAAA AAAAA"));
+        let one = size(&mut world, one, UNSET);
+
+        let wrapped = size_within(&mut world, two_words, one.width * 7.);
+        assert!(wrapped.width <= one.width * 7.);
+        assert!(wrapped.height > one.height * 1.5, "{wrapped} is not two lines of {one}");
+
+        let unwrapped = size_within(&mut world, two_words, one.width * 20.);
+        assert_eq!(unwrapped, size(&mut world, two_words, UNSET));
+    }
 }
@@ -0,0 +1,152 @@
+//! Convert between layout space and world or screen space, see [`LayoutCoords`].
+//!
+//! With `bevy_ui`, the `GlobalTransform` of a node is at its center, in
+//! logical pixels from the top left of the window, with the Y axis going down.
+//! Like layout space, but **unlike world space**, where the Y axis goes up.
+//! Use `LayoutCoords` rather than converting positions yourself, so that code
+//! stays correct with `cuicui_layout_bevy_sprite`, where it is the other way
+//! around.
+//!
+//! See [`cuicui_layout::coords`] for the definition of layout space.
+use bevy::ecs::{prelude::*, system::SystemParam};
+use bevy::math::Vec2;
+use bevy::prelude::{Camera, GlobalTransform};
+use bevy::render::view::RenderLayers;
+use cuicui_layout::coords::LayoutPositions;
+use cuicui_layout::{LayoutRootCamera, Root};
+
+use crate::layers;
+
+/// Convert positions between the layout space of [`Root`]s and world or
+/// screen space.
+///
+/// Screen space is logical pixels from the top left of the window, like
+/// `Window::cursor_position` and the `GlobalTransform` of UI nodes. World space
+/// is the 2D world coordinates of the [`LayoutRootCamera`] with the same
+/// [`RenderLayers`] as the root.
+///
+/// Conversions use the `GlobalTransform` of roots, so they are one frame late
+/// for roots that moved this frame, until transform propagation.
+///
+/// # Example
+///
+/// Find which button is under the cursor:
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_layout::coords::LayoutPositions;
+/// use cuicui_layout_bevy_ui::coords::LayoutCoords;
+///
+/// #[derive(Component)]
+/// struct Button;
+///
+/// fn hovered_button(
+///     window: Query<&Window>,
+///     coords: LayoutCoords,
+///     positions: LayoutPositions,
+///     buttons: Query<Entity, With<Button>>,
+/// ) {
+///     let Some(cursor) = window.single().cursor_position() else {
+///         return;
+///     };
+///     for button in &buttons {
+///         let Some((root, rect)) = positions.rect_in_root(button) else {
+///             continue;
+///         };
+///         if coords.screen_to_layout(root, cursor).is_some_and(|p| rect.contains(p)) {
+///             info!("{button:?} is hovered");
+///         }
+///     }
+/// }
+/// # bevy::ecs::system::assert_is_system(hovered_button);
+/// ```
+#[derive(SystemParam)]
+pub struct LayoutCoords<'w, 's> {
+    positions: LayoutPositions<'w, 's>,
+    roots: Query<'w, 's, (&'static GlobalTransform, Option<&'static RenderLayers>), With<Root>>,
+    cameras: Query<
+        'w,
+        's,
+        (&'static Camera, &'static GlobalTransform, Option<&'static RenderLayers>),
+        With<LayoutRootCamera>,
+    >,
+}
+impl LayoutCoords<'_, '_> {
+    /// The world position of the top left corner, in layout space, of the
+    /// node `entity`.
+    ///
+    /// `None` if `entity` is not a node in a [`Root`] or its root has no camera.
+    #[must_use]
+    pub fn layout_to_world(&self, entity: Entity) -> Option<Vec2> {
+        let (root, _) = self.positions.in_root(entity)?;
+        let (camera, transform) = self.camera(root)?;
+        camera.viewport_to_world_2d(transform, self.layout_to_screen(entity)?)
+    }
+    /// The position of the world `point` in the layout space of `root`.
+    ///
+    /// `None` if `root` is not a [`Root`], it has no camera, or `point` is
+    /// outside of the camera view.
+    #[must_use]
+    pub fn world_to_layout(&self, root: Entity, point: Vec2) -> Option<Vec2> {
+        let (camera, transform) = self.camera(root)?;
+        let screen = camera.world_to_viewport(transform, point.extend(0.))?;
+        self.screen_to_layout(root, screen)
+    }
+    /// The screen position of the top left corner, in layout space, of the
+    /// node `entity`.
+    ///
+    /// `None` if `entity` is not a node in a [`Root`].
+    #[must_use]
+    pub fn layout_to_screen(&self, entity: Entity) -> Option<Vec2> {
+        let (root, position) = self.positions.in_root(entity)?;
+        let (transform, _) = self.roots.get(root).ok()?;
+        let local = position - self.half_size(root)?;
+        Some(transform.transform_point(local.extend(0.)).truncate())
+    }
+    /// The position of the screen `point` in the layout space of `root`.
+    ///
+    /// `None` if `root` is not a [`Root`].
+    #[must_use]
+    pub fn screen_to_layout(&self, root: Entity, point: Vec2) -> Option<Vec2> {
+        let (transform, _) = self.roots.get(root).ok()?;
+        let local = transform.affine().inverse().transform_point3(point.extend(0.));
+        Some(local.truncate() + self.half_size(root)?)
+    }
+    /// Half the size of `root`, the `GlobalTransform` of UI nodes is at their center.
+    fn half_size(&self, root: Entity) -> Option<Vec2> {
+        Some(Vec2::from(self.positions.rect(root)?.size()) / 2.)
+    }
+    /// The [`LayoutRootCamera`] with the same [`RenderLayers`] as `root`.
+    fn camera(&self, root: Entity) -> Option<(&Camera, &GlobalTransform)> {
+        let (_, root_layers) = self.roots.get(root).ok()?;
+        let root_layers = layers(root_layers);
+        let is_layer = |(c, t, l)| (layers(l) == root_layers).then_some((c, t));
+        self.cameras.iter().find_map(is_layer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+    use bevy::prelude::*;
+    use cuicui_layout::{LayoutRect, PixelSnap};
+
+    use super::*;
+
+    #[test]
+    fn layout_and_screen_roundtrip() {
+        let mut world = World::new();
+        world.init_resource::<PixelSnap>();
+        let rect = |x, y, width, height| LayoutRect::from(Rect::new(x, y, x + width, y + height));
+        // The root is 200×100 and its top left corner is at (100, 50) on screen.
+        let transform = GlobalTransform::from_xyz(200., 100., 0.);
+        let root = world.spawn((Root::default(), rect(0., 0., 200., 100.), transform)).id();
+        let node = world.spawn(rect(30., 40., 20., 10.)).set_parent(root).id();
+
+        let mut state = SystemState::<LayoutCoords>::new(&mut world);
+        let coords = state.get(&world);
+        assert_eq!(coords.layout_to_screen(node), Some(Vec2::new(130., 90.)));
+        assert_eq!(coords.screen_to_layout(root, Vec2::new(130., 90.)), Some(Vec2::new(30., 40.)));
+        assert_eq!(coords.layout_to_world(node), None);
+    }
+}
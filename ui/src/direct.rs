@@ -0,0 +1,201 @@
+//! Skip `bevy_ui`'s flexbox layout for `cuicui_layout` nodes.
+//!
+//! By default ([`Positioning::Style`]), [`set_layout_style`] sets the position
+//! and size of `cuicui_layout` nodes in their [`Style`]. `bevy_ui` then runs
+//! its flexbox layout algorithm (`taffy`) to compute the node's size and
+//! transform from the `Style`. This recomputes a layout `cuicui_layout`
+//! already computed, and any change to a node position marks the whole
+//! `bevy_ui` tree as needing a layout update.
+//!
+//! With [`Positioning::Direct`], only the size of nodes is set in their
+//! `Style`, which rarely changes. Positions are directly written to the
+//! [`GlobalTransform`] of `cuicui_layout` nodes after transform propagation.
+//! Moving nodes around doesn't trigger a `bevy_ui` layout update anymore.
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use cuicui_layout_bevy_ui::Positioning;
+//!
+//! fn build(app: &mut App) {
+//!     app.insert_resource(Positioning::Direct);
+//! }
+//! ```
+//!
+//! # Limitations
+//!
+//! - The [`Transform`] of `cuicui_layout` nodes do not reflect their position
+//!   anymore, read [`GlobalTransform`] or [`LayoutRect`] instead.
+//! - Positions are not rounded to the nearest pixel like `bevy_ui` does,
+//!   unless [`PixelSnap`] is enabled.
+//!
+//! [`set_layout_style`]: crate::set_layout_style
+//! [`PixelSnap`]: cuicui_layout::PixelSnap
+//! [`Style`]: bevy::ui::Style
+//! [`Transform`]: bevy::prelude::Transform
+use bevy::ecs::prelude::*;
+use bevy::hierarchy::{Children, Parent};
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectResource};
+use bevy::prelude::{GlobalTransform, Transform, Vec2};
+use bevy::ui::Node as UiNode;
+use cuicui_layout::{LayoutRect, PixelSnap, Root};
+
+/// How [`set_layout_style`] positions `cuicui_layout` nodes.
+///
+/// See the [module-level documentation](self) for details.
+///
+/// [`set_layout_style`]: crate::set_layout_style
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Resource))]
+pub enum Positioning {
+    /// Set the position and size of nodes in their `Style`,
+    /// and let `bevy_ui` compute their `Transform`.
+    #[default]
+    Style,
+    /// Set the size of nodes in their `Style`, and directly write their
+    /// position to their `GlobalTransform`.
+    Direct,
+}
+
+type UiNodeItem = (
+    &'static UiNode,
+    &'static Transform,
+    &'static mut GlobalTransform,
+    Option<&'static LayoutRect>,
+    Has<Root>,
+);
+
+/// Run condition for [`set_global_transforms`].
+pub fn is_direct(positioning: Res<Positioning>) -> bool {
+    *positioning == Positioning::Direct
+}
+
+/// Run condition for [`set_global_transforms`], `true` when a [`LayoutRect`],
+/// a `bevy_ui` node's [`GlobalTransform`] or [`PixelSnap`] changed.
+pub fn ui_transforms_changed(
+    snap: Res<PixelSnap>,
+    rects: Query<(), Changed<LayoutRect>>,
+    globals: Query<(), (Changed<GlobalTransform>, With<UiNode>)>,
+) -> bool {
+    snap.is_changed() || !rects.is_empty() || !globals.is_empty()
+}
+
+/// Set the [`GlobalTransform`] of `cuicui_layout` nodes according to their
+/// [`LayoutRect`], when [`Positioning::Direct`] is enabled.
+///
+/// `bevy_ui` nodes that are not managed by `cuicui_layout` (such as
+/// [`Root`]s and their non-`cuicui_layout` children) keep the position
+/// computed by `bevy_ui`, relative to their parent.
+pub fn set_global_transforms(
+    snap: Res<PixelSnap>,
+    ui_roots: Query<Entity, (With<UiNode>, Without<Parent>)>,
+    children: Query<&Children>,
+    mut nodes: Query<UiNodeItem>,
+) {
+    for root in &ui_roots {
+        let Ok((node, _, global, ..)) = nodes.get(root) else {
+            continue;
+        };
+        let global = *global;
+        let top_left = global.translation().truncate() - node.size() / 2.;
+        set_children_global(root, &global, top_left, *snap, &children, &mut nodes);
+    }
+}
+fn set_children_global(
+    entity: Entity,
+    parent_global: &GlobalTransform,
+    parent_top_left: Vec2,
+    snap: PixelSnap,
+    children: &Query<&Children>,
+    nodes: &mut Query<UiNodeItem>,
+) {
+    let Ok(entity_children) = children.get(entity) else {
+        return;
+    };
+    for &child in entity_children {
+        let Ok((node, transform, mut global, rect, is_root)) = nodes.get_mut(child) else {
+            continue;
+        };
+        let (new_global, top_left) = match rect {
+            Some(rect) if !is_root => {
+                let rect = rect.snapped_if(snap);
+                let top_left = parent_top_left + rect.pos();
+                let center = top_left + Vec2::from(rect.size()) / 2.;
+                (GlobalTransform::from_translation(center.extend(0.)), top_left)
+            }
+            _ => {
+                let new_global = parent_global.mul_transform(*transform);
+                let top_left = new_global.translation().truncate() - node.size() / 2.;
+                (new_global, top_left)
+            }
+        };
+        if *global != new_global {
+            *global = new_global;
+        }
+        set_children_global(child, &new_global, top_left, snap, children, nodes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use bevy::prelude::*;
+    use cuicui_dsl::dsl;
+    use cuicui_layout::{dsl_functions::px, LeafRule, Node, RootSize, Size};
+
+    use super::Positioning;
+    use crate::UiDsl;
+
+    fn app(positioning: Positioning) -> App {
+        let mut app = crate::headless_app();
+        app.insert_resource(positioning).add_systems(Startup, |mut cmds: Commands| {
+            let mut cmds = cmds.spawn(RootSize::Fixed(Size::new(400., 300.)));
+            dsl! { <UiDsl> &mut cmds,
+                Root(column root main_margin(10.) align_start) {
+                    Title(width(px(200)) height(px(30)))
+                    Row(row width(px(300)) height(px(100)) distrib_end margin(5.)) {
+                        First(width(px(50)) height(px(40)))
+                        Second(width(px(70)) height(px(60)))
+                    }
+                }
+            };
+        });
+        app.update();
+        app
+    }
+    /// The translation of the `GlobalTransform` of each named node.
+    fn globals(app: &mut App) -> BTreeMap<String, Vec3> {
+        let mut query = app.world.query::<(&Name, &GlobalTransform)>();
+        let globals = query.iter(&app.world);
+        globals.map(|(name, global)| (name.to_string(), global.translation())).collect()
+    }
+    fn set_width(app: &mut App, name: &str, width: f32) {
+        let mut query = app.world.query::<(&Name, &mut Node)>();
+        let mut nodes = query.iter_mut(&mut app.world);
+        let (_, mut node) = nodes.find(|(node_name, _)| node_name.as_str() == name).unwrap();
+        let Node::Box(size) = &mut *node else {
+            panic!("{name} should be a leaf node");
+        };
+        size.width = LeafRule::Fixed(width);
+    }
+
+    #[test]
+    fn same_transforms_as_style() {
+        let mut style = app(Positioning::Style);
+        let mut direct = app(Positioning::Direct);
+
+        let before = globals(&mut style);
+        assert_eq!(before.len(), 5);
+        assert_eq!(before, globals(&mut direct));
+
+        // Resizing nodes moves them around.
+        for app in [&mut style, &mut direct] {
+            set_width(app, "First", 90.);
+            app.update();
+        }
+        let after = globals(&mut style);
+        assert_ne!(before["First"], after["First"]);
+        assert_eq!(after, globals(&mut direct));
+    }
+}
@@ -4,22 +4,33 @@
 use std::num::NonZeroU16;
 
 use bevy::asset::Handle;
-use bevy::ecs::{prelude::*, system::EntityCommands};
+use bevy::ecs::{prelude::*, system::EntityCommands, world::EntityWorldMut};
 use bevy::hierarchy::BuildChildren;
-use bevy::prelude::{Deref, DerefMut};
+use bevy::prelude::{Deref, DerefMut, Vec2};
+use bevy::log::error;
+use bevy::render::view::{Layer, RenderLayers};
 use bevy::render::{color::Color, texture::Image};
 use bevy::text::{BreakLineOn, Font, Text, TextAlignment, TextSection, TextStyle};
 use bevy::ui::node_bundles as bevy_ui;
 use bevy::ui::widget::UiImageSize;
 use bevy::ui::{prelude::*, ContentSize};
 use bevy::utils::default;
-use cuicui_dsl::DslBundle;
-use cuicui_layout::dsl::IntoUiBundle;
+#[cfg(feature = "image_size")]
+use cuicui_chirp::parse_dsl::args::SizedImage;
+use cuicui_dsl::{dsl_trait, DslBundle};
+use cuicui_layout::content_sized::ImageSizeHint;
+use cuicui_layout::dsl::{InsertFn, IntoUiBundle, LayoutDsl, LayoutDslTrait};
 #[cfg(doc)]
 use cuicui_layout::{LeafRule, Rule};
 use enumset::{EnumSet, EnumSetType};
 use thiserror::Error;
 
+use crate::embed::{BevyUiEmbed, CuicuiEmbed};
+use crate::text_bind::ResourceText;
+use crate::text_effects::TextEffects;
+#[cfg(doc)]
+use crate::text_bind::NumberFormat;
+
 /// An image leaf node wrapping a [`bevy_ui::ImageBundle`].
 ///
 /// By default, will stretch to fit the parent container.
@@ -79,16 +90,20 @@ impl From<bevy_ui::TextBundle> for TextBundle {
     }
 }
 
+// Both methods delegate to `$to`, so that `$to`'s own `into_insert_fn` runs.
 macro_rules! from_delegate_impl {
     ([$from:ty, $to:ty]) => {
-        from_delegate_impl!([$from, $to], |self| <$to>::from(self).into_ui_bundle());
+        from_delegate_impl!([$from, $to], |self| <$to>::from(self));
     };
     ([$from:ty, $to:ty], |$s:ident| $delegate_adaptor:expr) => {
         impl IntoUiBundle<UiDsl> for $from {
             type Target = <$to as IntoUiBundle<UiDsl>>::Target;
 
             fn into_ui_bundle($s) -> Self::Target {
-                $delegate_adaptor
+                IntoUiBundle::<UiDsl>::into_ui_bundle($delegate_adaptor)
+            }
+            fn into_insert_fn($s) -> InsertFn {
+                IntoUiBundle::<UiDsl>::into_insert_fn($delegate_adaptor)
             }
         }
     };
@@ -97,7 +112,7 @@ macro_rules! from_delegate_impl {
 from_delegate_impl!([&'_ str, String]);
 from_delegate_impl! {
     [String, Text],
-    |self| Text::from_section(self, TextStyle::default()).into_ui_bundle()
+    |self| Text::from_section(self, TextStyle::default())
 }
 // TODO(perf): bevy_ui::ImageBundle's `content_size` field force-inlines
 // ContentSize::drop, as a result, we have several copies of it here.
@@ -149,6 +164,9 @@ enum UiDslFlags {
     BreakOnChar,
     BgFlipX,
     BgFlipY,
+    ClipChildren,
+    CuicuiEmbed,
+    BevyUiEmbed,
 }
 
 /// The [`DslBundle`] for `bevy_ui`.
@@ -158,12 +176,19 @@ pub struct UiDsl<D = cuicui_layout::dsl::LayoutDsl> {
     inner: D,
     bg_color: Option<BackgroundColor>,
     bg_image: Option<Handle<Image>>,
+    image_size: Option<Vec2>,
     border_color: Option<BorderColor>,
     border_px: Option<NonZeroU16>,
     text: Option<Box<str>>,
     text_color: Color,
     font_size: u16,
     font: Option<Handle<Font>>,
+    render_layer: Option<Layer>,
+    resource_text: Option<ResourceText>,
+    tint: Option<Color>,
+    text_effects: TextEffects,
+    #[cfg(feature = "image_repeat")]
+    image_repeat: Option<Vec2>,
     flags: EnumSet<UiDslFlags>,
 }
 impl<D: Default> Default for UiDsl<D> {
@@ -172,6 +197,7 @@ impl<D: Default> Default for UiDsl<D> {
             inner: D::default(),
             bg_color: None,
             bg_image: None,
+            image_size: None,
             border_color: None,
             border_px: None,
             text: None,
@@ -179,12 +205,23 @@ impl<D: Default> Default for UiDsl<D> {
             text_color: Color::WHITE,
             font_size: 12,
             font: None,
+            render_layer: None,
+            resource_text: None,
+            tint: None,
+            text_effects: TextEffects::default(),
+            #[cfg(feature = "image_repeat")]
+            image_repeat: None,
         }
     }
 }
+#[dsl_trait]
 #[cfg_attr(
     feature = "chirp",
-    cuicui_chirp::parse_dsl_impl(delegate = inner, type_parsers(Color = parse_color)),
+    cuicui_chirp::parse_dsl_impl(
+        namespace = ui,
+        delegate = inner,
+        type_parsers(Color = parse_color, SizedImage = args::to_sized_image),
+    ),
 )]
 impl<D> UiDsl<D> {
     /// Set the node's border width, in pixels. Note that this is only visual and has
@@ -218,17 +255,82 @@ impl<D> UiDsl<D> {
         self.bg_color = Some(color.into());
     }
     /// Set the node's background image.
+    #[cfg_attr(feature = "image_size", parse_dsl(ignore))]
     pub fn image(&mut self, image: &Handle<Image>) {
         self.bg_image = Some(image.clone());
     }
-    /// If this node has a background image, flip it on its X axis.
+    /// Set the node's background image, measured at `image.size` until it is loaded.
+    ///
+    /// With the `image_size` feature, this is the `image` method of chirp files.
+    /// See [`ImageSizeHint`].
+    #[cfg(feature = "image_size")]
+    #[cfg_attr(feature = "chirp", parse_dsl(alias = "image"))]
+    pub fn sized_image(&mut self, image: SizedImage) {
+        self.bg_image = Some(image.handle);
+        self.image_size = image.size.map(|size| size.as_vec2());
+    }
+    /// Repeat the background [`Self::image`] over the node, rather than
+    /// stretching it, each tile being `width`×`height` pixels.
+    ///
+    /// Flipping doesn't apply to repeated images. See the [`image_repeat`] module.
+    ///
+    /// [`image_repeat`]: crate::image_repeat
+    #[cfg(feature = "image_repeat")]
+    pub fn image_repeat(&mut self, width: f32, height: f32) {
+        if width <= 0. || height <= 0. {
+            error!("'image_repeat' expects a positive tile size, got '{width}×{height}'");
+            return;
+        }
+        self.image_repeat = Some(Vec2::new(width, height));
+    }
+    /// If this node has an image, flip it on its X axis.
+    ///
+    /// This applies both to the background [`Self::image`] and to images
+    /// spawned with `ui` or `spawn`.
     pub fn flip_x(&mut self) {
         self.flags |= UiDslFlags::BgFlipX;
     }
-    /// If this node has a background image, flip it on its Y axis.
+    /// If this node has an image, flip it on its Y axis.
+    ///
+    /// This applies both to the background [`Self::image`] and to images
+    /// spawned with `ui` or `spawn`.
     pub fn flip_y(&mut self) {
         self.flags |= UiDslFlags::BgFlipY;
     }
+    /// If this node has an image, multiply its colors by `color`.
+    ///
+    /// This sets the [`BackgroundColor`] of nodes with a [`UiImage`], including
+    /// images spawned with `ui` or `spawn`.
+    pub fn tint(&mut self, color: Color) {
+        self.tint = Some(color);
+    }
+    /// Do not draw the parts of children outside of this node.
+    ///
+    /// This sets the `bevy_ui` [`Style::overflow`] to [`Overflow::clip`].
+    ///
+    /// Note that clipping is purely visual. `cuicui_layout` still reports an error
+    /// when the layout rules of children make them overflow this node. Clipping
+    /// is for content larger than its node that isn't checked by the layout
+    /// algorithm, such as children without layout [`Node`]s, or images
+    /// larger than their fixed-size node.
+    ///
+    /// [`Node`]: cuicui_layout::Node
+    /// [`Style::overflow`]: bevy::ui::Style::overflow
+    /// [`Overflow::clip`]: bevy::ui::Overflow::clip
+    pub fn clip_children(&mut self) {
+        self.flags |= UiDslFlags::ClipChildren;
+    }
+    /// Let the `bevy_ui` parent of this [`Root`] position it, see [`CuicuiEmbed`].
+    ///
+    /// [`Root`]: cuicui_layout::Root
+    pub fn cuicui_embed(&mut self) {
+        self.flags |= UiDslFlags::CuicuiEmbed;
+    }
+    /// Let `bevy_ui` compute the size of this content-sized node based on its
+    /// `bevy_ui` children, see [`BevyUiEmbed`].
+    pub fn bevy_ui_embed(&mut self) {
+        self.flags |= UiDslFlags::BevyUiEmbed;
+    }
     /// Set the node's text.
     pub fn text(&mut self, text: &str) {
         self.text = Some(text.into());
@@ -260,6 +362,20 @@ impl<D> UiDsl<D> {
         use UiDslFlags::{AlignLeft, AlignRight};
         self.flags.remove_all(AlignLeft | AlignRight);
     }
+    /// Show in this node's text the number at `path` in a resource, formatted
+    /// with `format`, updated when the resource changes.
+    ///
+    /// `path` is the resource name followed by the path of the number in it,
+    /// such as `Score.points`. See [`NumberFormat`] for the `format` syntax,
+    /// for example `"Score: {:06}"` or `"{:t}"` for times.
+    ///
+    /// See [`ResourceText`].
+    pub fn text_bind_res(&mut self, path: &str, format: &str) {
+        match ResourceText::new(path, format) {
+            Ok(resource_text) => self.resource_text = Some(resource_text),
+            Err(err) => error!("'text_bind_res': {err}"),
+        }
+    }
     /// Set the text size for this node.
     pub fn font_size(&mut self, size: u16) {
         self.font_size = size;
@@ -268,6 +384,39 @@ impl<D> UiDsl<D> {
     pub fn font(&mut self, font: &Handle<Font>) {
         self.font = Some(font.clone());
     }
+    /// Draw a shadow of this node's text with `color`, moved by `x` and `y`
+    /// pixels (positive `y` is down).
+    ///
+    /// See the [`text_effects`] module for how this works and its limitations.
+    ///
+    /// [`text_effects`]: crate::text_effects
+    pub fn text_shadow(&mut self, x: f32, y: f32, color: Color) {
+        self.text_effects.shadow = Some((Vec2::new(x, y), color));
+    }
+    /// Draw an outline `width` pixels wide around this node's text with `color`.
+    ///
+    /// See the [`text_effects`] module for how this works and its limitations.
+    ///
+    /// [`text_effects`]: crate::text_effects
+    pub fn text_outline(&mut self, width: f32, color: Color) {
+        self.text_effects.outline = Some((width, color));
+    }
+    /// Add this node to the given [`RenderLayers`] `layer`.
+    ///
+    /// On a [`ScreenRoot`], this selects the [`LayoutRootCamera`] the root
+    /// gets its size from, see [`update_ui_camera_root`].
+    ///
+    /// [`LayoutRootCamera`]: cuicui_layout::LayoutRootCamera
+    /// [`ScreenRoot`]: cuicui_layout::ScreenRoot
+    /// [`update_ui_camera_root`]: crate::update_ui_camera_root
+    pub fn render_layer(&mut self, layer: Layer) {
+        if layer >= RenderLayers::TOTAL_LAYERS as Layer {
+            let max = RenderLayers::TOTAL_LAYERS - 1;
+            error!("'render_layer' expects a layer between 0 and {max}, got '{layer}'");
+            return;
+        }
+        self.render_layer = Some(layer);
+    }
 }
 impl<D> UiDsl<D> {
     fn text_alignment(&self) -> TextAlignment {
@@ -288,6 +437,11 @@ impl<D> UiDsl<D> {
     }
 }
 
+impl<L: DslBundle, D: LayoutDslTrait<L>> LayoutDslTrait<L> for UiDsl<D> {
+    fn from_layout_dsl(&mut self) -> &mut LayoutDsl<L> {
+        self.inner.from_layout_dsl()
+    }
+}
 impl<D: DslBundle> DslBundle for UiDsl<D> {
     fn insert(&mut self, cmds: &mut EntityCommands) {
         let mut node_bundle = bevy_ui::NodeBundle::default();
@@ -297,6 +451,18 @@ impl<D: DslBundle> DslBundle for UiDsl<D> {
         if let Some(background_color) = self.bg_color.take() {
             node_bundle.background_color = background_color;
         }
+        if self.flags.contains(UiDslFlags::ClipChildren) {
+            node_bundle.style.overflow = bevy::ui::Overflow::clip();
+        }
+        if self.flags.contains(UiDslFlags::CuicuiEmbed) {
+            cmds.insert(CuicuiEmbed);
+        }
+        if self.flags.contains(UiDslFlags::BevyUiEmbed) {
+            cmds.insert(BevyUiEmbed);
+        }
+        if let Some(layer) = self.render_layer.take() {
+            cmds.insert(RenderLayers::layer(layer));
+        }
         if let (Some(pixels), Some(border_color)) = (self.border_px, self.border_color.take()) {
             let child_bundle = NodeBundle {
                 border_color,
@@ -313,6 +479,10 @@ impl<D: DslBundle> DslBundle for UiDsl<D> {
                 c.spawn(child_bundle);
             });
         }
+        if let Some(resource_text) = self.resource_text.take() {
+            self.text.get_or_insert_with(Box::default);
+            cmds.insert(resource_text);
+        }
         if let Some(text) = self.text.take() {
             let mut text_style = TextStyle {
                 font_size: f32::from(self.font_size),
@@ -322,24 +492,49 @@ impl<D: DslBundle> DslBundle for UiDsl<D> {
             if let Some(font) = self.font.take() {
                 text_style.font = font;
             }
-            let text = Text {
+            let mut text = Text {
                 sections: vec![TextSection::new(text, text_style)],
                 alignment: self.text_alignment(),
                 linebreak_behavior: self.break_line_on(),
             };
+            let effects = std::mem::take(&mut self.text_effects);
+            if !effects.is_empty() {
+                effects.spawn(&mut text, cmds);
+            }
             cmds.insert(TextBundle { text, ..default() });
         }
+        #[cfg(feature = "image_repeat")]
+        if let (Some(tile_size), Some(texture)) = (self.image_repeat, self.bg_image.take()) {
+            let tint = self.tint.take().unwrap_or(node_bundle.background_color.0);
+            node_bundle.background_color = Color::NONE.into();
+            cmds.insert(crate::image_repeat::ImageRepeat { texture, tile_size, tint });
+        }
         match self.bg_image.take() {
             Some(image) => {
-                let ui_image = UiImage {
-                    texture: image,
-                    flip_x: self.flags.contains(UiDslFlags::BgFlipX),
-                    flip_y: self.flags.contains(UiDslFlags::BgFlipY),
-                };
+                let ui_image = UiImage::new(image);
+                if let Some(size) = self.image_size.take() {
+                    cmds.insert(ImageSizeHint(size));
+                }
                 cmds.insert(ImageBundle::from(ui_image)).insert(node_bundle)
             }
             None => cmds.insert(node_bundle),
         };
         self.inner.insert(cmds);
+        // `ui` and `spawn` images are inserted by `inner`, so we update the image afterward.
+        let flip_x = self.flags.contains(UiDslFlags::BgFlipX);
+        let flip_y = self.flags.contains(UiDslFlags::BgFlipY);
+        let tint = self.tint.take();
+        if flip_x || flip_y || tint.is_some() {
+            cmds.add(move |mut entity: EntityWorldMut| {
+                let Some(mut image) = entity.get_mut::<UiImage>() else {
+                    return;
+                };
+                image.flip_x |= flip_x;
+                image.flip_y |= flip_y;
+                if let Some(tint) = tint {
+                    entity.insert(BackgroundColor(tint));
+                }
+            });
+        }
     }
 }
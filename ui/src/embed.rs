@@ -0,0 +1,179 @@
+//! Mix `cuicui_layout` and `bevy_ui`'s flexbox layout in the same tree.
+//!
+//! By default, [`set_layout_style`] sets the [`Style`] of all `cuicui_layout`
+//! nodes to an absolute position and a fixed size. This hides them from
+//! `bevy_ui`'s layout, so mixing both in a single tree doesn't work.
+//!
+//! Use those components at the boundary between the two layout algorithms:
+//!
+//! - [`CuicuiEmbed`]: On a `cuicui_layout` [`Root`] that is the child of a
+//!   flexbox node. The root is positioned by `bevy_ui`, while its size is
+//!   set by `cuicui_layout` and reported to `bevy_ui`, so that its flexbox
+//!   parent and siblings make room for it. Set its size with [`RootSize`]
+//!   or [`ProvideRootSize`].
+//! - [`BevyUiEmbed`]: On a content-sized `cuicui_layout` leaf node, its
+//!   `bevy_ui` children are laid out with flexbox. The node is positioned by
+//!   `cuicui_layout`, while its size on content-sized axis is the one
+//!   computed by `bevy_ui`.
+//!
+//! # Example
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use cuicui_layout::{dsl, dsl_functions::px, RootSize, Size};
+//! use cuicui_layout_bevy_ui::UiDsl as Dsl;
+//!
+//! fn setup(mut cmds: Commands) {
+//!     // A regular bevy_ui flexbox node
+//!     cmds.spawn(NodeBundle::default()).with_children(|flex| {
+//!         flex.spawn(TextBundle::from_section("Flexbox text", default()));
+//!         let mut cmds = flex.spawn(RootSize::Fixed(Size::new(200., 100.)));
+//!         dsl! { &mut cmds,
+//!             Panel(column root cuicui_embed) {
+//!                 Entity(text("cuicui text"))
+//!                 // Size of this node is computed by bevy_ui, based on its children.
+//!                 Flexbox(bevy_ui_embed width(px(150)))
+//!             }
+//!         };
+//!     });
+//! }
+//! ```
+//!
+//! # Limitations
+//!
+//! - `cuicui_layout` reads the size of [`BevyUiEmbed`] nodes after `bevy_ui`
+//!   computed it, so changes to the `bevy_ui` children are visible one frame
+//!   late.
+//! - [`BevyUiEmbed`] nodes must not have `cuicui_layout` children.
+//!
+//! [`set_layout_style`]: crate::set_layout_style
+//! [`Style`]: bevy::ui::Style
+//! [`Root`]: cuicui_layout::Root
+//! [`RootSize`]: cuicui_layout::RootSize
+//! [`ProvideRootSize`]: cuicui_layout::root_size::ProvideRootSize
+use bevy::ecs::prelude::*;
+use bevy::ecs::schedule::SystemSetConfigs;
+use bevy::ecs::system::SystemParam;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::ui::Node as UiNode;
+use cuicui_layout::content_sized::{
+    ComputeContentParam, ComputeContentSize, ContentSizedComputeSystem,
+};
+use cuicui_layout::{require_layout_recompute, Size};
+
+/// A `cuicui_layout` [`Root`] positioned by its `bevy_ui` parent.
+///
+/// See the [module-level documentation](self) for details.
+///
+/// [`Root`]: cuicui_layout::Root
+#[derive(Component, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct CuicuiEmbed;
+
+/// A `cuicui_layout` content-sized leaf node sized by `bevy_ui`.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Component, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct BevyUiEmbed;
+
+/// Compute the content size of [`BevyUiEmbed`] nodes, using the size
+/// computed by `bevy_ui`.
+#[derive(SystemParam)]
+pub(crate) struct EmbedContentSize;
+
+type Components = (&'static UiNode, With<BevyUiEmbed>);
+
+impl ComputeContentParam for EmbedContentSize {
+    type Components = Components;
+
+    fn condition(label: ContentSizedComputeSystem<Self>) -> SystemSetConfigs {
+        let cond = |c: Query<(), (Changed<UiNode>, With<BevyUiEmbed>)>| !c.is_empty();
+        label.run_if(require_layout_recompute.or_else(cond))
+    }
+}
+impl ComputeContentSize for EmbedContentSize {
+    type Components = Components;
+
+    fn compute_content(&self, (node, ()): (&UiNode, ()), _: Size<Option<f32>>) -> Size<f32> {
+        node.size().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use cuicui_dsl::dsl;
+    use cuicui_layout::{dsl_functions::px, LayoutRect, RootSize, Size};
+
+    use crate::UiDsl;
+
+    fn app(setup: fn(Commands)) -> App {
+        let mut app = crate::headless_app();
+        app.add_systems(Startup, setup);
+        // `cuicui_layout` reads sizes computed by `bevy_ui` one frame late.
+        for _ in 0..3 {
+            app.update();
+        }
+        app
+    }
+    fn flex_node(width: f32, height: f32) -> NodeBundle {
+        let (width, height) = (Val::Px(width), Val::Px(height));
+        let style = Style { width, height, ..default() };
+        NodeBundle { style, ..default() }
+    }
+    fn named(app: &mut App, name: &str) -> Entity {
+        let mut query = app.world.query::<(Entity, &Name)>();
+        let mut entities = query.iter(&app.world).filter(|(_, n)| n.as_str() == name);
+        entities.next().unwrap().0
+    }
+    fn ui_size(app: &App, entity: Entity) -> Vec2 {
+        app.world.get::<Node>(entity).unwrap().size()
+    }
+    fn left(app: &App, entity: Entity) -> f32 {
+        let global = app.world.get::<GlobalTransform>(entity).unwrap();
+        global.translation().x - ui_size(app, entity).x / 2.
+    }
+
+    #[test]
+    fn cuicui_embed_takes_space_in_flexbox() {
+        let mut app = app(|mut cmds| {
+            cmds.spawn(flex_node(600., 400.)).with_children(|flex| {
+                flex.spawn((Name::new("Before"), flex_node(100., 50.)));
+                let mut cmds = flex.spawn(RootSize::Fixed(Size::new(200., 100.)));
+                dsl! { <UiDsl> &mut cmds,
+                    Panel(column root cuicui_embed) {
+                        Entity(width(px(50)) height(px(20)))
+                    }
+                };
+                flex.spawn((Name::new("After"), flex_node(50., 50.)));
+            });
+        });
+        let (panel, after) = (named(&mut app, "Panel"), named(&mut app, "After"));
+
+        assert_eq!(ui_size(&app, panel), Vec2::new(200., 100.));
+        assert_eq!(left(&app, panel), 100.);
+        assert_eq!(left(&app, after), 300.);
+    }
+
+    #[test]
+    fn bevy_ui_embed_sized_by_children() {
+        let mut app = app(|mut cmds| {
+            let mut cmds = cmds.spawn(RootSize::Fixed(Size::all(400.)));
+            dsl! { <UiDsl> &mut cmds,
+                Panel(column root) {
+                    Flexbox(bevy_ui_embed width(px(150))) {
+                        code(let cmds) { cmds.insert(flex_node(60., 30.)); }
+                        code(let cmds) { cmds.insert(flex_node(40., 45.)); }
+                    }
+                }
+            };
+        });
+        let flexbox = named(&mut app, "Flexbox");
+
+        assert_eq!(ui_size(&app, flexbox), Vec2::new(150., 45.));
+        let rect = app.world.get::<LayoutRect>(flexbox).unwrap();
+        assert_eq!(rect.size(), Size::new(150., 45.));
+    }
+}
@@ -0,0 +1,228 @@
+//! An in-game panel listing the chirp files that failed to load and the last
+//! layout error, see [`ErrorPanel`].
+//!
+//! When iterating on chirp files with hot reloading, errors show up in the
+//! game window, not only in the terminal. Each chirp file in the panel has a
+//! "reload" button, to load it again, for example after fixing a file it imports.
+//!
+//! The panel is a [`ScreenRoot`] on the right side of the screen, above other
+//! `bevy_ui` nodes. It is spawned when there are errors and despawned once
+//! they are all fixed.
+//!
+//! Requires the `error_panel` cargo feature.
+//!
+//! [`ScreenRoot`]: cuicui_layout::ScreenRoot
+use bevy::app::{App, Update};
+use bevy::asset::{AssetEvent, AssetId, AssetPath, AssetServer, Assets};
+use bevy::ecs::prelude::*;
+use bevy::hierarchy::{BuildChildren, DespawnRecursiveExt};
+use bevy::render::color::Color;
+use bevy::ui::{Interaction, ZIndex};
+use bevy::utils::{default, HashMap};
+use cuicui_chirp::report::{ReportConfig, ReportFormat};
+use cuicui_chirp::Chirp;
+use cuicui_layout::{dsl, dsl_functions::pct, LastLayoutChange, Rule};
+
+use crate::UiDsl;
+
+const BACKGROUND: Color = Color::rgba(0.15, 0.02, 0.02, 0.9);
+const BUTTON: Color = Color::rgb(0.4, 0.1, 0.1);
+
+/// A chirp file that failed to load, listed in the [`ErrorPanel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChirpError {
+    /// The path of the chirp file.
+    pub path: AssetPath<'static>,
+    /// The errors of the file, rendered as plain text.
+    pub report: String,
+}
+
+/// The errors listed in the [`ErrorPanel`].
+///
+/// Updated by [`collect_chirp_errors`] and [`collect_layout_error`].
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActiveErrors {
+    /// The chirp files that failed to load.
+    pub chirp: HashMap<AssetId<Chirp>, ChirpError>,
+    /// The error of the last layout computation, if it failed.
+    pub layout: Option<String>,
+}
+impl ActiveErrors {
+    /// Whether there is no error to list.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.chirp.is_empty() && self.layout.is_none()
+    }
+}
+
+/// The root entity of the error panel, see the [module docs](self).
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ErrorPanel;
+
+/// A button of the [`ErrorPanel`], reloading the chirp file at this path
+/// when pressed.
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct ReloadChirp(pub AssetPath<'static>);
+
+/// Add and remove the chirp files that failed to load in [`ActiveErrors`].
+#[allow(clippy::needless_pass_by_value)]
+pub fn collect_chirp_errors(
+    mut asset_events: EventReader<AssetEvent<Chirp>>,
+    chirps: Res<Assets<Chirp>>,
+    server: Res<AssetServer>,
+    mut errors: ResMut<ActiveErrors>,
+) {
+    let config = ReportConfig { format: ReportFormat::Narrow, color: false, ..default() };
+    for event in asset_events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                let chirp_errors = chirps.get(*id).and_then(Chirp::errors);
+                let report = chirp_errors.map(|chirp_errors| config.render(chirp_errors));
+                match (report, server.get_path(*id)) {
+                    (Some(report), Some(path)) => {
+                        let path = path.into_owned();
+                        errors.chirp.insert(*id, ChirpError { path, report });
+                    }
+                    _ if errors.chirp.contains_key(id) => {
+                        errors.chirp.remove(id);
+                    }
+                    _ => {}
+                }
+            }
+            AssetEvent::Removed { id } if errors.chirp.contains_key(id) => {
+                errors.chirp.remove(id);
+            }
+            AssetEvent::Removed { .. } | AssetEvent::LoadedWithDependencies { .. } => {}
+        }
+    }
+}
+
+/// Set the layout error of [`ActiveErrors`] to the error of the last layout
+/// computation.
+#[allow(clippy::needless_pass_by_value)]
+pub fn collect_layout_error(
+    last_layout: Res<LastLayoutChange>,
+    mut errors: ResMut<ActiveErrors>,
+) {
+    if last_layout.is_changed() && errors.layout.as_deref() != last_layout.error() {
+        errors.layout = last_layout.error().map(str::to_owned);
+    }
+}
+
+/// Reload the chirp file of pressed [`ReloadChirp`] buttons.
+#[allow(clippy::needless_pass_by_value)]
+pub fn reload_pressed(
+    server: Res<AssetServer>,
+    buttons: Query<(&Interaction, &ReloadChirp), Changed<Interaction>>,
+) {
+    for (interaction, reload) in &buttons {
+        if *interaction == Interaction::Pressed {
+            server.reload(reload.0.clone());
+        }
+    }
+}
+
+/// Despawn the [`ErrorPanel`] and spawn it again listing the [`ActiveErrors`],
+/// if there are any.
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_panel(
+    mut cmds: Commands,
+    errors: Res<ActiveErrors>,
+    panels: Query<Entity, With<ErrorPanel>>,
+) {
+    for panel in &panels {
+        cmds.entity(panel).despawn_recursive();
+    }
+    if errors.is_empty() {
+        return;
+    }
+    let mut chirp_errors: Vec<_> = errors.chirp.values().collect();
+    chirp_errors.sort_by_key(|error| error.path.to_string());
+    let chirp_entries = chirp_errors.into_iter().map(|ChirpError { path, report }| {
+        (format!("{path}\n{report}"), Some(path.clone()))
+    });
+    let layout_entry = errors.layout.as_ref();
+    let layout_entry = layout_entry.map(|error| (format!("Layout error: {error}"), None));
+    let entries: Vec<_> = chirp_entries.chain(layout_entry).collect();
+
+    // All sizes are relative to the parent, so that the panel never overflows,
+    // even when its text doesn't fit.
+    #[allow(clippy::cast_precision_loss)] // entry count as f32
+    let entry_height = Rule::Parent(0.9 / entries.len() as f32);
+    let title = format!("{} error(s)", entries.len());
+    let mut panel = cmds.spawn((ErrorPanel, ZIndex::Global(i32::MAX)));
+    dsl!(<UiDsl> &mut panel, ErrorPanel(screen_root row distrib_end align_start));
+    panel.with_children(|cmds| {
+        let mut list = cmds.spawn_empty();
+        dsl!(<UiDsl> &mut list,
+            ErrorList(column distrib_start margin(4.) rules(pct(40), pct(100)) bg(BACKGROUND)
+                clip_children)
+        );
+        list.with_children(|cmds| {
+            let mut title_cmds = cmds.spawn_empty();
+            dsl!(<UiDsl> &mut title_cmds,
+                Title(text(&title) font_size(16) rules(pct(100), pct(5)))
+            );
+            for (report, path) in &entries {
+                let mut entry = cmds.spawn_empty();
+                dsl!(<UiDsl> &mut entry,
+                    Entry(row distrib_start align_start rules(pct(100), entry_height)) {
+                        Report(text(report) font_size(12) rules(pct(75), pct(100)))
+                    }
+                );
+                let Some(path) = path else {
+                    continue;
+                };
+                entry.with_children(|cmds| {
+                    let reload = (ReloadChirp(path.clone()), Interaction::default());
+                    let mut reload = cmds.spawn(reload);
+                    dsl!(<UiDsl> &mut reload,
+                        Reload(text("reload") font_size(14) bg(BUTTON) rules(pct(20), pct(10)))
+                    );
+                });
+            }
+        });
+    });
+}
+
+pub(crate) fn build(app: &mut App) {
+    let collect_chirp_errors = collect_chirp_errors.run_if(resource_exists::<Assets<Chirp>>());
+    let update_panel = update_panel.run_if(resource_changed::<ActiveErrors>());
+    app.init_resource::<ActiveErrors>()
+        .add_systems(Update, (collect_chirp_errors, collect_layout_error, update_panel).chain())
+        .add_systems(Update, reload_pressed.run_if(resource_exists::<AssetServer>()));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::Schedule;
+
+    use super::*;
+
+    #[test]
+    fn spawn_and_despawn_panel() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_panel);
+        world.init_resource::<ActiveErrors>();
+
+        let report = "Unknown method 'wdth'".to_owned();
+        let error = ChirpError { path: AssetPath::from("menu.chirp"), report };
+        let mut errors = world.resource_mut::<ActiveErrors>();
+        errors.chirp.insert(AssetId::default(), error);
+        errors.layout = Some("Container overflow".to_owned());
+        schedule.run(&mut world);
+        assert_eq!(world.query_filtered::<(), With<ErrorPanel>>().iter(&world).count(), 1);
+        let buttons: Vec<_> = world.query::<&ReloadChirp>().iter(&world).cloned().collect();
+        assert_eq!(buttons, vec![ReloadChirp(AssetPath::from("menu.chirp"))]);
+
+        world.resource_mut::<ActiveErrors>().chirp.clear();
+        schedule.run(&mut world);
+        assert_eq!(world.query_filtered::<(), With<ErrorPanel>>().iter(&world).count(), 1);
+        assert_eq!(world.query::<&ReloadChirp>().iter(&world).count(), 0);
+
+        world.resource_mut::<ActiveErrors>().layout = None;
+        schedule.run(&mut world);
+        assert_eq!(world.query_filtered::<(), With<ErrorPanel>>().iter(&world).count(), 0);
+    }
+}
@@ -0,0 +1,157 @@
+//! Events to give feedback, such as sounds, when the user interacts with
+//! layout nodes, see [`UiFeedback`].
+//!
+//! This only emits events, map them to sounds or other effects in your own
+//! systems:
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use cuicui_layout_bevy_ui::feedback::{FeedbackKind, UiFeedback};
+//!
+//! fn play_ui_sounds(mut feedback: EventReader<UiFeedback>) {
+//!     for event in feedback.read() {
+//!         let name = event.name.as_ref().map_or("", |n| n.as_str());
+//!         match event.kind {
+//!             FeedbackKind::HoverEnter if name.ends_with("button") => { /* play "tick" */ }
+//!             FeedbackKind::Press => { /* play "click" */ }
+//!             FeedbackKind::ScreenOpen => { /* play "whoosh" */ }
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Requires the `feedback` cargo feature.
+use bevy::core::Name;
+use bevy::ecs::prelude::*;
+use bevy::render::view::Visibility;
+use bevy::ui::Interaction;
+use bevy::utils::HashMap;
+use cuicui_layout::{show_if::ShowIf, Node, ScreenRoot};
+
+/// What happened to the node of a [`UiFeedback`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedbackKind {
+    /// The pointer started hovering the node.
+    HoverEnter,
+    /// The pointer stopped hovering the node.
+    HoverLeave,
+    /// The node was pressed.
+    Press,
+    /// A [`ScreenRoot`] was spawned, or a [`ShowIf`] node was shown.
+    ScreenOpen,
+    /// A [`ScreenRoot`] was despawned, or a [`ShowIf`] node was hidden.
+    ScreenClose,
+}
+
+/// An [`Event`] sent when the user interacts with a layout node, or when a
+/// screen opens or closes.
+///
+/// Hover and press events are sent for layout [`Node`]s with an [`Interaction`]
+/// component, such as buttons. Screen events are sent for [`ScreenRoot`]s and
+/// for nodes with a [`ShowIf`] component.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct UiFeedback {
+    /// The node the event is about. It may already be despawned on
+    /// [`FeedbackKind::ScreenClose`].
+    pub entity: Entity,
+    /// The [`Name`] of the node, if it has one.
+    pub name: Option<Name>,
+    /// What happened.
+    pub kind: FeedbackKind,
+}
+
+/// The feedback of an [`Interaction`] change from `old` to `new`.
+fn interaction_feedback(old: Interaction, new: Interaction) -> &'static [FeedbackKind] {
+    use FeedbackKind::{HoverEnter, HoverLeave, Press};
+    use Interaction::{Hovered, None, Pressed};
+    match (old, new) {
+        (None, Hovered) => &[HoverEnter],
+        (None, Pressed) => &[HoverEnter, Press],
+        (Hovered, Pressed) => &[Press],
+        (Hovered | Pressed, None) => &[HoverLeave],
+        _ => &[],
+    }
+}
+
+/// Send [`UiFeedback`] events.
+///
+/// Added to `Last` by the [`Plugin`](crate::Plugin) with the `feedback` feature.
+#[allow(clippy::needless_pass_by_value)]
+pub fn send_feedback(
+    mut events: EventWriter<UiFeedback>,
+    interactions: Query<(Entity, &Interaction, Option<&Name>), (Changed<Interaction>, With<Node>)>,
+    screens: Query<(Entity, Option<&Name>), Added<ScreenRoot>>,
+    toggled: Query<(Entity, Ref<Visibility>, Option<&Name>), (Changed<Visibility>, With<ShowIf>)>,
+    mut closed: RemovedComponents<ScreenRoot>,
+    mut last_interactions: Local<HashMap<Entity, Interaction>>,
+    mut screen_names: Local<HashMap<Entity, Option<Name>>>,
+) {
+    let mut send = |entity, name: Option<&Name>, kind| {
+        events.send(UiFeedback { entity, name: name.cloned(), kind });
+    };
+    for (entity, interaction, name) in &interactions {
+        let old = last_interactions.insert(entity, *interaction);
+        for kind in interaction_feedback(old.unwrap_or(Interaction::None), *interaction) {
+            send(entity, name, *kind);
+        }
+    }
+    for (entity, visibility, name) in &toggled {
+        if visibility.is_added() {
+            continue;
+        }
+        let shown = *visibility != Visibility::Hidden;
+        let kind = if shown { FeedbackKind::ScreenOpen } else { FeedbackKind::ScreenClose };
+        send(entity, name, kind);
+    }
+    for (entity, name) in &screens {
+        screen_names.insert(entity, name.cloned());
+        send(entity, name, FeedbackKind::ScreenOpen);
+    }
+    for entity in closed.read() {
+        last_interactions.remove(&entity);
+        let name = screen_names.remove(&entity).flatten();
+        send(entity, name.as_ref(), FeedbackKind::ScreenClose);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{event::Events, schedule::Schedule};
+    use cuicui_layout::{LeafRule, Size};
+
+    use super::*;
+    use FeedbackKind::{HoverEnter, HoverLeave, Press, ScreenClose, ScreenOpen};
+
+    fn kinds(world: &mut World) -> Vec<FeedbackKind> {
+        let mut events = world.resource_mut::<Events<UiFeedback>>();
+        events.drain().map(|event| event.kind).collect()
+    }
+
+    #[test]
+    fn interactions_and_screens() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(send_feedback);
+        world.init_resource::<Events<UiFeedback>>();
+
+        let node = Node::Box(Size::all(LeafRule::Fixed(10.)));
+        let button = world.spawn((node, Interaction::None, Name::new("button"))).id();
+        let screen = world.spawn(ScreenRoot).id();
+        schedule.run(&mut world);
+        assert_eq!(kinds(&mut world), vec![ScreenOpen]);
+
+        *world.get_mut::<Interaction>(button).unwrap() = Interaction::Hovered;
+        schedule.run(&mut world);
+        assert_eq!(kinds(&mut world), vec![HoverEnter]);
+
+        *world.get_mut::<Interaction>(button).unwrap() = Interaction::Pressed;
+        schedule.run(&mut world);
+        assert_eq!(kinds(&mut world), vec![Press]);
+
+        *world.get_mut::<Interaction>(button).unwrap() = Interaction::None;
+        world.despawn(screen);
+        schedule.run(&mut world);
+        assert_eq!(kinds(&mut world), vec![HoverLeave, ScreenClose]);
+    }
+}
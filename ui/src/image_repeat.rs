@@ -0,0 +1,89 @@
+//! Tile the background image of nodes rather than stretching it, see [`ImageRepeat`].
+//!
+//! `bevy_ui` always stretches images over their node, so repeated images are
+//! drawn with the [`TiledImage`] [`UiMaterial`] instead of an [`UiImage`].
+//!
+//! Requires the `image_repeat` cargo feature.
+//!
+//! [`UiImage`]: bevy::ui::UiImage
+use bevy::app::{App, Update};
+use bevy::asset::{load_internal_asset, Asset, Assets, Handle};
+use bevy::ecs::prelude::*;
+use bevy::math::Vec2;
+use bevy::reflect::TypePath;
+use bevy::render::color::Color;
+use bevy::render::render_resource::{AsBindGroup, Shader, ShaderRef};
+use bevy::render::texture::Image;
+use bevy::ui::{UiMaterial, UiMaterialPlugin};
+use cuicui_layout::{LayoutOutputSet, LayoutRect};
+
+const SHADER: Handle<Shader> = Handle::weak_from_u128(0x2e6c_0a4f_81d3_4b27_9c5e_f1a8_d36b_7042);
+
+/// Tile `texture` over this node, each tile being `tile_size` pixels.
+///
+/// The [`TiledImage`] material of the node is created and kept in sync with
+/// the node's [`LayoutRect`] by [`update_tiled_images`].
+///
+/// Use [`UiDsl::image_repeat`] to spawn nodes with this component.
+///
+/// [`UiDsl::image_repeat`]: crate::UiDsl::image_repeat
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct ImageRepeat {
+    /// The tiled image.
+    pub texture: Handle<Image>,
+    /// The size in pixels of a single tile.
+    pub tile_size: Vec2,
+    /// Multiply the colors of the image by this color.
+    pub tint: Color,
+}
+
+/// The [`UiMaterial`] drawing [`ImageRepeat`] nodes.
+#[derive(AsBindGroup, Asset, TypePath, Clone, Debug)]
+pub struct TiledImage {
+    /// Multiply the colors of the image by this color.
+    #[uniform(0)]
+    pub tint: Color,
+    /// How many times the image is repeated on each axis.
+    #[uniform(0)]
+    pub tiles: Vec2,
+    /// The tiled image.
+    #[texture(1)]
+    #[sampler(2)]
+    pub texture: Handle<Image>,
+}
+impl UiMaterial for TiledImage {
+    fn fragment_shader() -> ShaderRef {
+        SHADER.into()
+    }
+}
+
+/// Create and update the [`TiledImage`] of [`ImageRepeat`] nodes.
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_tiled_images(
+    mut cmds: Commands,
+    mut materials: ResMut<Assets<TiledImage>>,
+    nodes: Query<(Entity, Ref<ImageRepeat>, Ref<LayoutRect>, Option<&Handle<TiledImage>>)>,
+) {
+    for (entity, repeat, rect, handle) in &nodes {
+        let size = rect.size();
+        let tiles = Vec2::new(size.width, size.height) / repeat.tile_size;
+        let ImageRepeat { texture, tint, .. } = repeat.clone();
+        let Some(handle) = handle else {
+            let material = materials.add(TiledImage { tint, tiles, texture });
+            cmds.entity(entity).insert(material);
+            continue;
+        };
+        if !repeat.is_changed() && !rect.is_changed() {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(handle) {
+            *material = TiledImage { tint, tiles, texture };
+        }
+    }
+}
+
+pub(crate) fn build(app: &mut App) {
+    load_internal_asset!(app, SHADER, "image_repeat.wgsl", Shader::from_wgsl);
+    app.add_plugins(UiMaterialPlugin::<TiledImage>::default())
+        .add_systems(Update, update_tiled_images.in_set(LayoutOutputSet));
+}
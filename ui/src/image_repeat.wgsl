@@ -0,0 +1,22 @@
+#import bevy_ui::ui_vertex_output::UiVertexOutput
+
+struct TiledImage {
+    tint: vec4<f32>,
+    tiles: vec2<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> material: TiledImage;
+@group(1) @binding(1)
+var texture: texture_2d<f32>;
+@group(1) @binding(2)
+var texture_sampler: sampler;
+
+@fragment
+fn fragment(in: UiVertexOutput) -> @location(0) vec4<f32> {
+    let uv = in.uv * material.tiles;
+    // Use the gradient of the unwrapped `uv`, so that mipmaps are not
+    // sampled at the tile seams.
+    let color = textureSampleGrad(texture, texture_sampler, fract(uv), dpdx(uv), dpdy(uv));
+    return color * material.tint;
+}
@@ -5,6 +5,19 @@
 //!
 //! It contains:
 //! * A [`dsl`] to use with the [`cuicui_dsl::dsl!`] macro.
+//! * A [`coords`] module to convert positions between layout space and
+//!   world or screen space, for hit-testing and anchoring.
+//! * A [`text_effects`] module with shadows and outlines for text.
+//! * With the `text_cursor` feature, a `text_cursor` module to locate glyphs
+//!   within text nodes, useful for text editing.
+//! * With the `image_repeat` feature, an `image_repeat` module to tile
+//!   background images rather than stretching them.
+//! * With the `feedback` feature, a `feedback` module sending events when nodes
+//!   are hovered or pressed and when screens open or close, to play UI sounds.
+//! * With the `error_panel` feature, an `error_panel` module displaying chirp
+//!   and layout errors in game.
+//! * With the `widgets` feature, a `widgets` module with ready-made layouts,
+//!   such as settings menu rows with aligned columns.
 //!
 //! Note that **unlike `cuicui_layout_bevy_ui`, this uses a Y axis down**
 //! coordinate space, (like `bevy_sprite`)
@@ -53,33 +66,82 @@
 
 use bevy::app::{App, Plugin as BevyPlugin};
 use bevy::ecs::prelude::*;
-use bevy::render::camera::Camera;
+use bevy::prelude::GlobalTransform;
+use bevy::render::{camera::Camera, view::RenderLayers};
 use bevy::ui::Style;
 use bevy_mod_sysfail::quick_sysfail;
-use cuicui_layout::content_sized::AppContentSizeExt;
-use cuicui_layout::{LayoutRect, LayoutRootCamera, Root, ScreenRoot};
+use cuicui_layout::{attach::AttachmentPoint, content_sized::AppContentSizeExt};
+use cuicui_layout::{
+    Backend, LayoutRect, LayoutRootCamera, LeafRule, Node, PixelSnap, Root, ScreenRoot, Size,
+};
 
+pub use direct::Positioning;
 pub use dsl::UiDsl;
+pub use embed::{BevyUiEmbed, CuicuiEmbed};
 
 mod fixup;
 
 pub mod content_sized;
+pub mod coords;
+pub mod direct;
 pub mod dsl;
+pub mod embed;
+#[cfg(feature = "error_panel")]
+pub mod error_panel;
+#[cfg(feature = "feedback")]
+pub mod feedback;
+#[cfg(feature = "image_repeat")]
+pub mod image_repeat;
+pub mod states;
+pub mod text_bind;
+pub mod text_effects;
+#[cfg(feature = "text_cursor")]
+pub mod text_cursor;
+#[cfg(feature = "widgets")]
+pub mod widgets;
+
+/// The most common `cuicui_layout` and `cuicui_layout_bevy_ui` items,
+/// `use cuicui_layout_bevy_ui::prelude::*;` to import them.
+///
+/// This includes the [`cuicui_layout::prelude`], [`UiDsl`] and
+/// [`dsl::UiDslTrait`]. The [`Plugin`] is exported as `UiLayoutPlugin`, to not
+/// conflict with bevy's `Plugin` trait.
+pub mod prelude {
+    pub use crate::{dsl::UiDslTrait, Plugin as UiLayoutPlugin, UiDsl};
+    pub use cuicui_layout::prelude::*;
+}
 
 #[cfg(doctest)]
 #[doc = include_str!("../../README.md")]
 pub struct TestWorkspaceReadme;
 
+/// The [`RenderLayers`] of an entity, entities without `RenderLayers` are in
+/// the default layer, like for rendering.
+fn layers(layers: Option<&RenderLayers>) -> RenderLayers {
+    layers.copied().unwrap_or_default()
+}
+
 /// System updating the [`ScreenRoot`] [`cuicui_layout::Node`] with the
 /// [`LayoutRootCamera`]'s viewport size, whenever it changes.
+///
+/// A root is associated with the cameras with identical [`RenderLayers`],
+/// entities without `RenderLayers` are in the default layer. This lets
+/// several cameras, for example a HUD and a menu camera, size their own roots.
+///
+/// Note that `bevy_ui` draws all UI nodes on all cameras where
+/// [`UiCameraConfig::show_ui`] is `true`, regardless of `RenderLayers`.
+///
+/// [`UiCameraConfig::show_ui`]: bevy::ui::camera_config::UiCameraConfig::show_ui
 #[quick_sysfail]
 pub fn update_ui_camera_root(
-    ui_cameras: Query<&Camera, (With<LayoutRootCamera>, Changed<Camera>)>,
-    mut roots: Query<&mut Root, With<ScreenRoot>>,
+    ui_cameras: Query<(&Camera, Option<&RenderLayers>), (With<LayoutRootCamera>, Changed<Camera>)>,
+    mut roots: Query<(&mut Root, Option<&RenderLayers>), With<ScreenRoot>>,
 ) {
-    for cam in &ui_cameras {
+    for (cam, cam_layers) in &ui_cameras {
         let size = cam.logical_viewport_size()?;
-        for mut root in &mut roots {
+        let cam_layers = layers(cam_layers);
+        let is_layer = |(r, l)| (layers(l) == cam_layers).then_some(r);
+        for mut root in roots.iter_mut().filter_map(is_layer) {
             let bounds = root.size_mut();
             *bounds.width = size.x;
             *bounds.height = size.y;
@@ -95,11 +157,13 @@ pub fn update_ui_camera_root(
 /// - `set_added_camera_root` sets size for **newly added roots** on **pre-existing cameras**
 #[quick_sysfail]
 pub fn set_added_camera_root(
-    ui_cameras: Query<&Camera, With<LayoutRootCamera>>,
-    mut roots: Query<&mut Root, Added<ScreenRoot>>,
+    ui_cameras: Query<(&Camera, Option<&RenderLayers>), With<LayoutRootCamera>>,
+    mut roots: Query<(&mut Root, Option<&RenderLayers>), Added<ScreenRoot>>,
 ) {
-    for mut root in &mut roots {
-        let Some(camera) = ui_cameras.iter().next() else {
+    for (mut root, root_layers) in &mut roots {
+        let root_layers = layers(root_layers);
+        let is_layer = |(c, l)| (layers(l) == root_layers).then_some(c);
+        let Some(camera) = ui_cameras.iter().find_map(is_layer) else {
             continue;
         };
         let size = camera.logical_viewport_size()?;
@@ -109,36 +173,108 @@ pub fn set_added_camera_root(
     }
 }
 
+fn set_if_neq<T: PartialEq>(changed: &mut bool, field: &mut T, value: T) {
+    if *field != value {
+        *field = value;
+        *changed = true;
+    }
+}
+
 /// Set the [`Style`]'s `{min_,max_,}size.{width,height}` and `position.{left,right}`
 /// according to [`LayoutRect`]'s computed from [`cuicui_layout`].
-pub fn set_layout_style(mut query: Query<(&mut Style, &LayoutRect), Changed<LayoutRect>>) {
+///
+/// - [`CuicuiEmbed`] nodes only get their size set.
+/// - [`BevyUiEmbed`] nodes do not get their size set on content-sized axis,
+///   so that `bevy_ui` can compute it.
+/// - With [`Positioning::Direct`], the position is not set, see the [`direct`] module.
+/// - With [`PixelSnap`], the [snapped](LayoutRect::snapped) position and size are used.
+///
+/// See the [`embed`] module for details.
+pub fn set_layout_style(
+    positioning: Res<Positioning>,
+    snap: Res<PixelSnap>,
+    mut query: Query<(
+        &mut Style,
+        Ref<LayoutRect>,
+        Option<&Node>,
+        Has<CuicuiEmbed>,
+        Has<BevyUiEmbed>,
+    )>,
+) {
     use bevy::ui::{PositionType, Val};
-    query.for_each_mut(|(mut style, pos)| {
-        style.position_type = PositionType::Absolute;
-        style.left = Val::Px(pos.pos().x);
-        style.top = Val::Px(pos.pos().y);
-
-        let width = Val::Px(pos.size().width);
-        style.min_width = width;
-        style.max_width = width;
-        style.width = width;
-
-        let height = Val::Px(pos.size().height);
-        style.min_height = height;
-        style.max_height = height;
-        style.height = height;
+    let direct = *positioning == Positioning::Direct;
+    let update_all = positioning.is_changed() || snap.is_changed();
+    query.for_each_mut(|(mut style, rect, node, cuicui_embed, bevy_ui_embed)| {
+        if !update_all && !rect.is_changed() {
+            return;
+        }
+        let pos = rect.snapped_if(*snap);
+        // Only trigger change detection when the style actually changed, since
+        // `bevy_ui` recomputes the layout of the whole tree when it does.
+        let mut changed = false;
+        let style_mut = style.bypass_change_detection();
+        if !cuicui_embed {
+            let (left, top) = match direct {
+                true => (Val::Auto, Val::Auto),
+                false => (Val::Px(pos.pos().x), Val::Px(pos.pos().y)),
+            };
+            set_if_neq(&mut changed, &mut style_mut.position_type, PositionType::Absolute);
+            set_if_neq(&mut changed, &mut style_mut.left, left);
+            set_if_neq(&mut changed, &mut style_mut.top, top);
+        }
+        let content_sized = match node {
+            Some(Node::Box(size)) if bevy_ui_embed => {
+                size.map(|rule| matches!(rule, LeafRule::Content(_)))
+            }
+            _ => Size::all(false),
+        };
+        let val = |pixels, content_sized| if content_sized { Val::Auto } else { Val::Px(pixels) };
+
+        let width = val(pos.size().width, content_sized.width);
+        set_if_neq(&mut changed, &mut style_mut.min_width, width);
+        set_if_neq(&mut changed, &mut style_mut.max_width, width);
+        set_if_neq(&mut changed, &mut style_mut.width, width);
+
+        let height = val(pos.size().height, content_sized.height);
+        set_if_neq(&mut changed, &mut style_mut.min_height, height);
+        set_if_neq(&mut changed, &mut style_mut.max_height, height);
+        set_if_neq(&mut changed, &mut style_mut.height, height);
+
+        if changed {
+            style.set_changed();
+        }
     });
 }
 
+/// Set the [`AttachmentPoint::position`] of nodes, in logical pixels from the
+/// top left of the window, like their `GlobalTransform`.
+pub fn update_attachment_points(
+    mut points: Query<(&mut AttachmentPoint, &bevy::ui::Node, &GlobalTransform)>,
+) {
+    for (mut point, node, global) in &mut points {
+        let origin = node.size() / 2.;
+        let local = point.local_position(node.size().into(), origin);
+        let position = global.transform_point(local.extend(0.)).truncate();
+        if point.position != position {
+            point.position = position;
+        }
+    }
+}
+
 /// Plugin managing position and size of UI elements using [`cuicui_layout`]
 /// components.
 ///
 /// What this does:
 ///
 /// - **Manage size of text and image elements**
+/// - **Update the text shadows and outlines**, see the [`text_effects`] module
 /// - **Manage size of the [`cuicui_layout::ScreenRoot`] container**
 /// - **Set the [`Style`] flex parameters according to [`cuicui_layout`] computed values**
+/// - **Manage size of [`BevyUiEmbed`] nodes**, see the [`embed`] module
+/// - **Swap the layout of hovered and pressed nodes**, see the [`states`] module
+/// - **Directly set the [`GlobalTransform`] of nodes**, with [`Positioning::Direct`]
 /// - **Compute [`cuicui_layout::Node`] layouts**
+/// - **Set the position of [`AttachmentPoint`]s** after transform propagation
 ///
 /// [`spawn_ui`]: cuicui_layout::dsl::LayoutDsl::spawn_ui
 /// [`ContentSized`]: cuicui_layout::ContentSized
@@ -146,19 +282,120 @@ pub struct Plugin;
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         use bevy::prelude::{Last, PostUpdate, Update};
-        use bevy::ui::UiSystem;
-        use cuicui_layout::ComputeLayoutSet;
+        use bevy::transform::TransformSystem;
+        use bevy::ui::{update::update_clipping_system, UiSystem};
+        use cuicui_layout::{LayoutOutputSet, RootSizeSet};
 
         app.add_plugins(cuicui_layout::Plugin)
             .add_content_sized::<content_sized::UiContentSize>()
+            .add_content_sized::<embed::EmbedContentSize>()
             .add_systems(
                 Update,
-                (update_ui_camera_root, set_added_camera_root).before(ComputeLayoutSet),
+                (update_ui_camera_root, set_added_camera_root).in_set(RootSizeSet),
+            )
+            .add_systems(
+                Update,
+                text_bind::update_resource_texts.before(cuicui_layout::ComputeLayoutSet),
+            )
+            .add_systems(
+                Update,
+                states::sync_node_states.before(cuicui_layout::states::update_state_nodes),
+            )
+            .init_resource::<Positioning>()
+            .add_systems(
+                PostUpdate,
+                set_layout_style.before(UiSystem::Layout).in_set(LayoutOutputSet),
+            )
+            .add_systems(
+                PostUpdate,
+                text_effects::update_text_copies.before(UiSystem::Layout),
+            )
+            .add_systems(
+                PostUpdate,
+                direct::set_global_transforms
+                    .after(TransformSystem::TransformPropagate)
+                    .before(update_clipping_system)
+                    .in_set(LayoutOutputSet)
+                    .run_if(direct::is_direct.and_then(direct::ui_transforms_changed)),
+            )
+            .add_systems(
+                PostUpdate,
+                update_attachment_points
+                    .after(TransformSystem::TransformPropagate)
+                    .after(direct::set_global_transforms)
+                    .in_set(LayoutOutputSet),
             )
-            .add_systems(PostUpdate, set_layout_style.before(UiSystem::Layout))
             .add_systems(
                 Last,
-                (fixup::add_text_components, fixup::add_image_components),
+                (
+                    fixup::add_text_components,
+                    fixup::add_image_components,
+                    states::add_interactions,
+                ),
             );
+        #[cfg(feature = "image_repeat")]
+        image_repeat::build(app);
+        #[cfg(feature = "feedback")]
+        app.add_event::<feedback::UiFeedback>().add_systems(Last, feedback::send_feedback);
+        #[cfg(feature = "error_panel")]
+        error_panel::build(app);
+        #[cfg(feature = "reflect")]
+        app.register_type::<CuicuiEmbed>()
+            .register_type::<BevyUiEmbed>()
+            .register_type::<text_bind::ResourceText>()
+            .register_type::<text_effects::TextCopy>()
+            .register_type::<Positioning>();
     }
 }
+
+/// The `bevy_ui` [`Backend`], to use with [`AppCuicuiExt::add_cuicui`].
+///
+/// This adds [`Plugin`].
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use cuicui_layout::{AppCuicuiExt, CuicuiConfig};
+/// use cuicui_layout_bevy_ui::{UiBackend, UiDsl};
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_cuicui::<UiDsl>(CuicuiConfig { backend: UiBackend, chirp: true, debug: false })
+///     .run();
+/// ```
+///
+/// [`AppCuicuiExt::add_cuicui`]: cuicui_layout::AppCuicuiExt::add_cuicui
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UiBackend;
+impl Backend for UiBackend {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Plugin);
+    }
+    fn is_added(&self, app: &App) -> bool {
+        app.is_plugin_added::<Plugin>()
+    }
+}
+
+/// An app with the `bevy_ui` layout and [`Plugin`], without rendering or
+/// a window backend, for tests.
+#[cfg(test)]
+fn headless_app() -> App {
+    use bevy::prelude::*;
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        WindowPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        bevy::input::InputPlugin,
+    ))
+    // Assets usually added by the render plugins
+    .init_asset::<Shader>()
+    .init_asset::<Image>()
+    .init_asset::<TextureAtlas>()
+    .add_plugins((bevy::text::TextPlugin, bevy::ui::UiPlugin, Plugin));
+    app
+}
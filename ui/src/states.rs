@@ -0,0 +1,39 @@
+//! Set the [`NodeState`] of nodes based on their `bevy_ui` [`Interaction`],
+//! so that nodes declared with `hover` and `press` methods change layout
+//! when hovered or pressed.
+//!
+//! See [`cuicui_layout::states`] for details.
+
+use bevy::ecs::prelude::*;
+use bevy::ui::Interaction;
+use cuicui_layout::states::{NodeState, StateNodes};
+
+/// Add an [`Interaction`] to nodes with a hovered or pressed [`StateNodes`],
+/// so that `bevy_ui` tracks whether they are hovered or pressed.
+#[allow(clippy::needless_pass_by_value)]
+pub fn add_interactions(
+    to_add: Query<(Entity, &StateNodes), Without<Interaction>>,
+    mut cmds: Commands,
+) {
+    let interactive = |(_, nodes): &(Entity, &StateNodes)| {
+        nodes.hovered.is_some() || nodes.pressed.is_some()
+    };
+    let to_add: Vec<(Entity, Interaction)> =
+        to_add.iter().filter(interactive).map(|(e, _)| (e, Interaction::None)).collect();
+    cmds.insert_or_spawn_batch(to_add);
+}
+
+/// Set [`NodeState::hovered`] and [`NodeState::pressed`] based on the node's
+/// [`Interaction`].
+pub fn sync_node_states(
+    mut nodes: Query<(&Interaction, &mut NodeState), Changed<Interaction>>,
+) {
+    for (interaction, mut state) in &mut nodes {
+        let pressed = *interaction == Interaction::Pressed;
+        let hovered = *interaction == Interaction::Hovered;
+        if state.pressed != pressed || state.hovered != hovered {
+            state.pressed = pressed;
+            state.hovered = hovered;
+        }
+    }
+}
@@ -0,0 +1,428 @@
+//! Text nodes showing a number read from a resource, see [`ResourceText`].
+//!
+//! Use [`UiDsl::text_bind_res`] to spawn them.
+//!
+//! [`UiDsl::text_bind_res`]: crate::UiDsl::text_bind_res
+
+use std::{fmt::Write, str::FromStr, time::Duration};
+
+use bevy::ecs::{component::Tick, prelude::*, reflect::ReflectResource};
+use bevy::ecs::storage::ResourceData;
+use bevy::log::error;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::reflect::{GetPath, Reflect as DynReflect, TypeRegistration, TypeRegistry};
+use bevy::text::Text;
+use bevy::utils::HashSet;
+use thiserror::Error;
+
+/// Error occuring when parsing a [`NumberFormat`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[allow(missing_docs)] // Error messages already good documentation.
+pub enum FormatError {
+    #[error("The number format '{0}' has no '{{}}' placeholder")]
+    NoPlaceholder(String),
+    #[error("The number format '{0}' has an unclosed '{{'")]
+    Unclosed(String),
+    #[error(
+        "'{0}' is not a valid number format specification, expected '[0][width][,][.decimals]' \
+        or 't[.decimals]'"
+    )]
+    BadSpec(String),
+}
+
+/// Error occuring when reading the value of a [`ResourceText`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[allow(missing_docs)] // Error messages already good documentation.
+enum ReadError {
+    #[error("No resource named '{0}' is registered, register it with `app.register_type`")]
+    Unregistered(String),
+    #[error("'{0}' is not a resource, add `#[reflect(Resource)]` to its definition")]
+    NotResource(String),
+    #[error("The '{0}' resource doesn't exist in the world")]
+    Missing(String),
+    #[error("'{0}' is not a valid path in the '{1}' resource")]
+    BadPath(String, String),
+    #[error("'{0}.{1}' is a {2}, not a number or a Duration")]
+    NotNumber(String, String, String),
+}
+
+/// How to format the number of a [`ResourceText`].
+///
+/// The format is a text with a `{}` placeholder replaced by the number, for
+/// example `"Score: {}"`. The placeholder accepts a specification, similar to
+/// rust's `format!`, in the form `{:[0][width][,][.decimals]}`:
+///
+/// - `0`: pad the number with zeros rather than spaces.
+/// - `width`: the minimum number of characters of the number.
+/// - `,`: separate thousands with commas. When zero-padding, the zeros are
+///   separated too, like in python's `format`.
+/// - `.decimals`: how many digits to show after the decimal point. Integers
+///   are shown without decimal point by default, floats with as many digits
+///   as needed.
+///
+/// Use `{:t}` to show a number of seconds or a [`Duration`] as time, in the
+/// form `m:ss`, or `h:mm:ss` above one hour. `{:t.N}` shows `N` digits of
+/// fractions of a second.
+///
+/// ```
+/// use cuicui_layout_bevy_ui::text_bind::NumberFormat;
+///
+/// let format = |format: &str, value| format.parse::<NumberFormat>().unwrap().format(value);
+/// assert_eq!(format("Score: {:06}", 1234.), "Score: 001234");
+/// assert_eq!(format("{:,}", 1234567.), "1,234,567");
+/// assert_eq!(format("{:08,}", 1234.), "0,001,234");
+/// assert_eq!(format("{:.2}%", 99.5), "99.50%");
+/// assert_eq!(format("{:t}", 75.), "1:15");
+/// assert_eq!(format("{:t.1}", 3725.25), "1:02:05.2");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct NumberFormat {
+    prefix: String,
+    suffix: String,
+    zero_pad: bool,
+    width: usize,
+    thousands: bool,
+    decimals: Option<usize>,
+    time: bool,
+}
+impl FromStr for NumberFormat {
+    type Err = FormatError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        let Some((prefix, rest)) = format.split_once('{') else {
+            return Err(FormatError::NoPlaceholder(format.to_owned()));
+        };
+        let Some((spec, suffix)) = rest.split_once('}') else {
+            return Err(FormatError::Unclosed(format.to_owned()));
+        };
+        let bad_spec = || FormatError::BadSpec(spec.to_owned());
+        let mut result =
+            Self { prefix: prefix.to_owned(), suffix: suffix.to_owned(), ..Self::default() };
+        let Some(mut spec) = spec.strip_prefix(':') else {
+            return if spec.is_empty() { Ok(result) } else { Err(bad_spec()) };
+        };
+        if let Some((rest, decimals)) = spec.split_once('.') {
+            result.decimals = Some(decimals.parse().map_err(|_| bad_spec())?);
+            spec = rest;
+        }
+        if spec == "t" {
+            result.time = true;
+            return Ok(result);
+        }
+        if let Some(rest) = spec.strip_suffix(',') {
+            result.thousands = true;
+            spec = rest;
+        }
+        if let Some(rest) = spec.strip_prefix('0') {
+            result.zero_pad = true;
+            spec = rest;
+        }
+        if !spec.is_empty() {
+            result.width = spec.parse().map_err(|_| bad_spec())?;
+        }
+        Ok(result)
+    }
+}
+impl NumberFormat {
+    /// The text of this format with `value`.
+    #[must_use]
+    pub fn format(&self, value: f64) -> String {
+        self.format_number(Number::Float(value))
+    }
+    fn format_number(&self, value: Number) -> String {
+        let number = if self.time { self.time(value.as_f64()) } else { self.number(value) };
+        format!("{}{number}{}", self.prefix, self.suffix)
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn time(&self, seconds: f64) -> String {
+        let sign = if seconds < 0. { "-" } else { "" };
+        let seconds = seconds.abs();
+        let whole = seconds.trunc() as u64;
+        let (hours, minutes, secs) = (whole / 3600, whole / 60 % 60, whole % 60);
+        let mut time = match hours {
+            0 => format!("{sign}{minutes}:{secs:02}"),
+            hours => format!("{sign}{hours}:{minutes:02}:{secs:02}"),
+        };
+        if let Some(decimals) = self.decimals.filter(|d| *d > 0).map(|d| d.min(9)) {
+            // Truncate rather than round, to never show 60 seconds.
+            let digits = (seconds.fract() * 10_f64.powi(decimals as i32)).trunc() as u64;
+            write!(time, ".{digits:0decimals$}").unwrap();
+        }
+        time
+    }
+    fn number(&self, value: Number) -> String {
+        let number = match (value, self.decimals) {
+            (Number::Int(value), None) => value.to_string(),
+            (Number::Float32(value), None) => value.to_string(),
+            (Number::Float32(value), Some(decimals)) => format!("{value:.decimals$}"),
+            (value, Some(decimals)) => format!("{:.decimals$}", value.as_f64()),
+            (Number::Float(value), None) => value.to_string(),
+        };
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", number.as_str()),
+        };
+        let separate = |digits: &str| match self.thousands {
+            true => separate_thousands(digits),
+            false => digits.to_owned(),
+        };
+        if self.zero_pad {
+            // Add zeros one at a time, since separators may be added with them.
+            let mut digits = digits.to_owned();
+            loop {
+                let number = format!("{sign}{}", separate(&digits));
+                if number.chars().count() >= self.width {
+                    return number;
+                }
+                digits.insert(0, '0');
+            }
+        }
+        let number = format!("{sign}{}", separate(digits));
+        let padding = self.width.saturating_sub(number.chars().count());
+        " ".repeat(padding) + &number
+    }
+}
+
+/// Separate thousands of the unsigned `number` with commas.
+fn separate_thousands(number: &str) -> String {
+    let (integer, fraction) = number.split_at(number.find('.').unwrap_or(number.len()));
+    let mut separated = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i != 0 && (integer.len() - i) % 3 == 0 {
+            separated.push(',');
+        }
+        separated.push(digit);
+    }
+    separated + fraction
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+    /// Formatted as a `f32`, so that `0.1_f32` is shown as `0.1`.
+    Float32(f32),
+}
+impl Number {
+    #[allow(clippy::cast_precision_loss)]
+    fn as_f64(self) -> f64 {
+        match self {
+            Self::Int(value) => value as f64,
+            Self::Float(value) => value,
+            Self::Float32(value) => f64::from(value),
+        }
+    }
+    fn from_reflect(value: &dyn DynReflect) -> Option<Self> {
+        macro_rules! downcast {
+            ($($int:ty),*) => {
+                $(if let Some(value) = value.downcast_ref::<$int>() {
+                    return Some(Self::Int(i128::try_from(*value).ok()?));
+                })*
+            };
+        }
+        downcast!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+        if let Some(value) = value.downcast_ref::<f32>() {
+            return Some(Self::Float32(*value));
+        }
+        if let Some(value) = value.downcast_ref::<f64>() {
+            return Some(Self::Float(*value));
+        }
+        value.downcast_ref::<Duration>().map(|value| Self::Float(value.as_secs_f64()))
+    }
+}
+
+/// Show in this text node a number read from a resource, updated when it changes.
+///
+/// The number can be any integer or float type, or a [`Duration`], such as
+/// the elapsed time of a `Stopwatch`. The resource must be registered with
+/// `#[reflect(Resource)]`.
+///
+/// Use [`UiDsl::text_bind_res`] to spawn text nodes with this component.
+///
+/// [`UiDsl::text_bind_res`]: crate::UiDsl::text_bind_res
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct ResourceText {
+    /// The short or full type path of the resource, such as `Score`.
+    pub resource: String,
+    /// The path of the number in the resource, using the [`GetPath`] syntax,
+    /// such as `points` or `0`. Empty if the resource itself is the number.
+    pub path: String,
+    /// How to show the number.
+    pub format: NumberFormat,
+}
+impl ResourceText {
+    /// Show the number at `path`, in the form `Resource.field.subfield`,
+    /// formatted with `format`.
+    ///
+    /// # Errors
+    /// When `format` is not a valid [`NumberFormat`].
+    pub fn new(path: &str, format: &str) -> Result<Self, FormatError> {
+        let (resource, path) = path.split_once('.').unwrap_or((path, ""));
+        let (resource, path) = (resource.to_owned(), path.to_owned());
+        Ok(Self { resource, path, format: format.parse()? })
+    }
+    fn registration<'r>(
+        &self,
+        registry: &'r TypeRegistry,
+    ) -> Result<&'r TypeRegistration, ReadError> {
+        let resource = &self.resource;
+        registry
+            .get_with_short_type_path(resource)
+            .or_else(|| registry.get_with_type_path(resource))
+            .ok_or_else(|| ReadError::Unregistered(resource.clone()))
+    }
+    /// Whether the resource changed between `last_run` and `this_run`.
+    ///
+    /// `false` if it doesn't exist.
+    fn resource_changed(&self, world: &World, last_run: Tick, this_run: Tick) -> bool {
+        let registry = world.resource::<AppTypeRegistry>().read();
+        let Ok(registration) = self.registration(&registry) else {
+            return false;
+        };
+        let id = world.components().get_resource_id(registration.type_id());
+        let resource = id.and_then(|id| world.storages().resources.get(id));
+        let ticks = resource.and_then(ResourceData::get_ticks);
+        ticks.is_some_and(|ticks| ticks.is_changed(last_run, this_run))
+    }
+    fn read(&self, world: &World) -> Result<Number, ReadError> {
+        let Self { resource, path, .. } = self;
+        let registry = world.resource::<AppTypeRegistry>().read();
+        let registration = self.registration(&registry)?;
+        let reflect = registration.data::<ReflectResource>();
+        let reflect = reflect.ok_or_else(|| ReadError::NotResource(resource.clone()))?;
+        let value = reflect.reflect(world).ok_or_else(|| ReadError::Missing(resource.clone()))?;
+        let value = match path.as_str() {
+            "" => value,
+            path => {
+                let bad_path = |_| ReadError::BadPath(path.to_owned(), resource.clone());
+                value.reflect_path(format!(".{path}").as_str()).map_err(bad_path)?
+            }
+        };
+        let not_number = || {
+            let ty = value.reflect_short_type_path().to_owned();
+            ReadError::NotNumber(resource.clone(), path.clone(), ty)
+        };
+        Number::from_reflect(value).ok_or_else(not_number)
+    }
+}
+
+/// Update the text of [`ResourceText`] nodes, when their resource or
+/// the `ResourceText` itself changed.
+///
+/// Errors are logged once per entity.
+pub fn update_resource_texts(
+    world: &mut World,
+    texts: &mut QueryState<(Entity, Ref<ResourceText>)>,
+    mut reported: Local<HashSet<Entity>>,
+) {
+    // In exclusive systems, `last_change_tick` is the last run of the system.
+    let (last_run, this_run) = (world.last_change_tick(), world.read_change_tick());
+    let mut updates = Vec::new();
+    for (entity, text) in texts.iter(world) {
+        if !text.is_changed() && !text.resource_changed(world, last_run, this_run) {
+            continue;
+        }
+        match text.read(world) {
+            Ok(value) => updates.push((entity, text.format.format_number(value))),
+            Err(err) if reported.insert(entity) => error!("Text of {entity:?}: {err}"),
+            Err(_) => {}
+        }
+    }
+    for (entity, value) in updates {
+        let Some(mut text) = world.get_mut::<Text>(entity) else {
+            continue;
+        };
+        match text.sections.first() {
+            Some(section) if section.value == value => {}
+            Some(_) => text.sections[0].value = value,
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::app::{App, Update};
+    use bevy::text::TextStyle;
+
+    use super::*;
+
+    fn format(format: &str, value: Number) -> String {
+        format.parse::<NumberFormat>().unwrap().format_number(value)
+    }
+    #[test]
+    fn parse_errors() {
+        let parse = |format: &str| format.parse::<NumberFormat>().unwrap_err();
+        assert_eq!(parse("Score"), FormatError::NoPlaceholder("Score".to_owned()));
+        assert_eq!(parse("Score {:"), FormatError::Unclosed("Score {:".to_owned()));
+        assert_eq!(parse("{:x}"), FormatError::BadSpec(":x".to_owned()));
+        assert_eq!(parse("{:.x}"), FormatError::BadSpec(":.x".to_owned()));
+        assert_eq!(parse("{nope}"), FormatError::BadSpec("nope".to_owned()));
+    }
+    #[test]
+    fn numbers() {
+        assert_eq!(format("{}", Number::Int(-42)), "-42");
+        assert_eq!(format("{:.1}", Number::Int(3)), "3.0");
+        assert_eq!(format("{}", Number::Float32(0.1)), "0.1");
+        assert_eq!(format("{:.3}", Number::Float32(0.1)), "0.100");
+        assert_eq!(format("{}", Number::Float(0.1)), "0.1");
+        assert_eq!(format("{:5}", Number::Int(42)), "   42");
+        assert_eq!(format("{:05}", Number::Int(-42)), "-0042");
+        assert_eq!(format("{:,}", Number::Int(-1_234_567)), "-1,234,567");
+        assert_eq!(format("{:,.2}", Number::Float(1234.5)), "1,234.50");
+    }
+    #[test]
+    fn zero_padded_thousands() {
+        assert_eq!(format("{:010,}", Number::Int(1_234_567)), "01,234,567");
+        assert_eq!(format("{:012,}", Number::Int(1234)), "0,000,001,234");
+        assert_eq!(format("{:08,}", Number::Int(-1234)), "-001,234");
+        assert_eq!(format("{:08,.1}", Number::Float(12.5)), "00,012.5");
+        assert_eq!(format("{:04,}", Number::Int(1)), "0,001");
+        assert_eq!(format("{:10,}", Number::Int(1234)), "     1,234");
+    }
+    #[test]
+    fn times() {
+        assert_eq!(format("{:t}", Number::Int(59)), "0:59");
+        assert_eq!(format("{:t}", Number::Float(-75.5)), "-1:15");
+        assert_eq!(format("{:t.2}", Number::Float32(0.1)), "0:00.10");
+        assert_eq!(format("{:t}", Number::Int(36_000)), "10:00:00");
+    }
+    #[test]
+    fn from_reflect() {
+        let number = |value: &dyn DynReflect| Number::from_reflect(value).map(|n| format("{}", n));
+        assert_eq!(number(&0.1_f32), Some("0.1".to_owned()));
+        assert_eq!(number(&0.1_f64), Some("0.1".to_owned()));
+        assert_eq!(number(&7_u8), Some("7".to_owned()));
+        assert_eq!(number(&Duration::from_millis(1500)), Some("1.5".to_owned()));
+        assert_eq!(number(&"7".to_owned()), None);
+    }
+
+    #[derive(Resource, bevy::reflect::Reflect, Default)]
+    #[reflect(Resource)]
+    struct Score(u32);
+
+    #[test]
+    fn update_on_change() {
+        let mut app = App::new();
+        app.register_type::<Score>()
+            .init_resource::<Score>()
+            .add_systems(Update, update_resource_texts);
+        let text = Text::from_section("", TextStyle::default());
+        let entity = app.world.spawn((text, ResourceText::new("Score.0", "{}").unwrap())).id();
+        let text = |app: &App| app.world.get::<Text>(entity).unwrap().sections[0].value.clone();
+
+        app.update();
+        assert_eq!(text(&app), "0");
+
+        app.world.get_mut::<Text>(entity).unwrap().sections[0].value = "edited".to_owned();
+        app.update();
+        assert_eq!(text(&app), "edited", "the resource didn't change");
+
+        app.world.resource_mut::<Score>().0 = 3;
+        app.update();
+        assert_eq!(text(&app), "3");
+    }
+}
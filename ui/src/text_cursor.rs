@@ -0,0 +1,306 @@
+//! Locate glyphs of a text node within its [`LayoutRect`], to draw carets
+//! and selection highlights.
+//!
+//! All rectangles returned by [`TextGlyphs`] are in logical pixels, relative
+//! to the top left corner of the text node's [`LayoutRect`], with the Y axis
+//! pointing down.
+//!
+//! Glyph indices are indices in [`TextLayoutInfo::glyphs`]. Note that
+//! whitespace characters have no glyphs. Use [`TextGlyphs::glyph_at_byte`] to
+//! convert a byte offset in a [`Text`] section into a glyph index.
+//!
+//! # Example
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use bevy::text::TextLayoutInfo;
+//! use bevy::window::PrimaryWindow;
+//! use cuicui_layout::LayoutRect;
+//! use cuicui_layout_bevy_ui::text_cursor::TextGlyphs;
+//!
+//! #[derive(Component)]
+//! struct Caret { glyph: usize }
+//!
+//! fn place_caret(
+//!     window: Query<&Window, With<PrimaryWindow>>,
+//!     texts: Query<(&TextLayoutInfo, &LayoutRect, &Caret)>,
+//! ) {
+//!     let Ok(window) = window.get_single() else { return; };
+//!     for (info, rect, caret) in &texts {
+//!         let glyphs = TextGlyphs::new(info, rect, window.scale_factor());
+//!         if let Some(caret_rect) = glyphs.caret_rect(caret.glyph, 2.0) {
+//!             // Move the caret entity to `caret_rect`.
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! [`Text`]: bevy::text::Text
+use std::ops::Range;
+
+use bevy::math::{Rect, Vec2};
+use bevy::text::{PositionedGlyph, TextLayoutInfo};
+use cuicui_layout::LayoutRect;
+
+/// Glyphs of a text node, positioned within its [`LayoutRect`].
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Clone, Copy)]
+pub struct TextGlyphs<'a> {
+    glyphs: &'a [PositionedGlyph],
+    bounds: Rect,
+    inverse_scale: f32,
+}
+impl<'a> TextGlyphs<'a> {
+    /// Glyphs of `info`, the text layout of the node at `rect`.
+    ///
+    /// `scale_factor` is the scale factor used to render the text, usually
+    /// [`Window::scale_factor`] multiplied by [`UiScale`].
+    ///
+    /// [`Window::scale_factor`]: bevy::window::Window::scale_factor
+    /// [`UiScale`]: bevy::ui::UiScale
+    #[must_use]
+    pub fn new(info: &'a TextLayoutInfo, rect: &LayoutRect, scale_factor: f64) -> Self {
+        #[allow(clippy::cast_possible_truncation)]
+        let inverse_scale = (1.0 / scale_factor) as f32;
+        let size = rect.size();
+        Self {
+            glyphs: &info.glyphs,
+            bounds: Rect::new(0., 0., size.width, size.height),
+            inverse_scale,
+        }
+    }
+    /// How many glyphs there are in this text.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+    /// Whether this text has no glyphs.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+    /// The index of the glyph at `byte_index` in the `section_index` section
+    /// of the text, or the first glyph following it.
+    ///
+    /// Returns `None` if there is no glyph at or after `byte_index`.
+    #[must_use]
+    pub fn glyph_at_byte(&self, section_index: usize, byte_index: usize) -> Option<usize> {
+        self.glyphs.iter().position(|glyph| {
+            (glyph.section_index, glyph.byte_index) >= (section_index, byte_index)
+        })
+    }
+    /// The index of the glyph closest to `point`, relative to the node's top left.
+    ///
+    /// Use this to place the caret where the user clicked.
+    #[must_use]
+    pub fn glyph_at(&self, point: Vec2) -> Option<usize> {
+        let distance = |i: &usize| {
+            let rect = self.glyph_rect(*i).unwrap_or(self.bounds);
+            let closest = point.clamp(rect.min, rect.max);
+            // Prefer glyphs on the same line over glyphs closer horizontally.
+            let Vec2 { x, y } = (point - closest).abs();
+            x + y * 1000.
+        };
+        (0..self.len()).min_by(|l, r| distance(l).total_cmp(&distance(r)))
+    }
+    /// The rectangle occupied by glyph at `index`, `None` if out of bound.
+    #[must_use]
+    pub fn glyph_rect(&self, index: usize) -> Option<Rect> {
+        let glyph = self.glyphs.get(index)?;
+        let center = glyph.position * self.inverse_scale;
+        let half_size = glyph.size * self.inverse_scale / 2.;
+        let rect = Rect::from_corners(center - half_size, center + half_size);
+        Some(rect.intersect(self.bounds))
+    }
+    /// A `width` wide caret placed just before glyph at `index`, spanning
+    /// the line's height.
+    ///
+    /// When `index` is [`Self::len`], the caret is placed after the last glyph.
+    /// Returns `None` if `index` is greater than `len`, or the text is empty.
+    #[must_use]
+    pub fn caret_rect(&self, index: usize, width: f32) -> Option<Rect> {
+        let (x, line) = if index == self.len() {
+            (self.glyph_rect(index.checked_sub(1)?)?.max.x, index - 1)
+        } else {
+            (self.glyph_rect(index)?.min.x, index)
+        };
+        let line = self.line_rect(self.line_of(line));
+        let caret = Rect::new(x - width / 2., line.min.y, x + width / 2., line.max.y);
+        Some(caret.intersect(self.bounds))
+    }
+    /// Rectangles covering glyphs in `range`, one per line.
+    ///
+    /// Use this to draw a selection highlight.
+    #[must_use]
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let range = range.start..range.end.min(self.len());
+        let mut rects: Vec<Rect> = Vec::new();
+        let mut current_line = None;
+        for index in range {
+            let line = self.line_of(index);
+            let Some(glyph) = self.glyph_rect(index) else {
+                continue;
+            };
+            let line_rect = self.line_rect(line);
+            let glyph = Rect::new(glyph.min.x, line_rect.min.y, glyph.max.x, line_rect.max.y);
+            match rects.last_mut() {
+                Some(last) if current_line == Some(line) => *last = last.union(glyph),
+                _ => rects.push(glyph),
+            }
+            current_line = Some(line);
+        }
+        rects
+    }
+    /// Whether glyph at `index` is the first of its line.
+    ///
+    /// A new line starts when a glyph is left of the previous glyph.
+    fn is_line_start(&self, index: usize) -> bool {
+        index == 0 || self.glyphs[index].position.x < self.glyphs[index - 1].position.x
+    }
+    /// Index of the first glyph of the line `index` is part of.
+    fn line_of(&self, index: usize) -> usize {
+        (0..=index)
+            .rev()
+            .find(|i| self.is_line_start(*i))
+            .unwrap_or(0)
+    }
+    /// Rectangle covering all glyphs of the line starting at glyph `line_start`.
+    fn line_rect(&self, line_start: usize) -> Rect {
+        let mut rect = self.glyph_rect(line_start).unwrap_or_default();
+        let line = (line_start + 1..self.len()).take_while(|i| !self.is_line_start(*i));
+        for index in line {
+            rect = rect.union(self.glyph_rect(index).unwrap_or(rect));
+        }
+        rect
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::text::{BreakLineOn, FontAtlasSets, FontAtlasWarning, TextPipeline, TextSettings};
+    use bevy::text::{TextLayoutInfo, YAxisOrientation};
+    use cuicui_layout::{testing::test_font, LayoutRect};
+
+    use super::TextGlyphs;
+
+    /// Lay out `text` with the test font, wrapping it at `width`.
+    fn layout(text: &str, width: f32, scale_factor: f64) -> TextLayoutInfo {
+        let mut fonts = Assets::<Font>::default();
+        let font = fonts.add(test_font());
+        let sections = [TextSection::new(
+            text,
+            TextStyle { font, font_size: 20., ..default() },
+        )];
+        #[allow(clippy::cast_possible_truncation)]
+        let bounds = Vec2::new(width, f32::INFINITY) * scale_factor as f32;
+        let info = TextPipeline::default().queue_text(
+            &fonts,
+            &sections,
+            scale_factor,
+            TextAlignment::Left,
+            BreakLineOn::WordBoundary,
+            bounds,
+            &mut FontAtlasSets::default(),
+            &mut Assets::default(),
+            &mut Assets::default(),
+            &TextSettings::default(),
+            &mut FontAtlasWarning::default(),
+            YAxisOrientation::TopToBottom,
+        );
+        info.unwrap()
+    }
+    fn rect(width: f32, height: f32) -> LayoutRect {
+        LayoutRect::from(Rect::new(0., 0., width, height))
+    }
+
+    #[test]
+    fn glyph_rects() {
+        let info = layout("AB CD", 400., 1.);
+        let glyphs = TextGlyphs::new(&info, &rect(400., 100.), 1.);
+        assert_eq!(glyphs.len(), 4, "whitespace has no glyphs");
+        assert_eq!(glyphs.glyph_at_byte(0, 2), Some(2));
+        assert_eq!(glyphs.glyph_at_byte(0, 5), None);
+
+        let (a, b) = (glyphs.glyph_rect(0).unwrap(), glyphs.glyph_rect(1).unwrap());
+        assert!(a.width() > 0. && a.height() > 0.);
+        assert!(a.center().x < b.center().x, "{a:?} is not left of {b:?}");
+        assert_eq!((a.min.y, a.max.y), (b.min.y, b.max.y));
+        assert_eq!(glyphs.glyph_rect(4), None);
+
+        let first = glyphs.caret_rect(0, 2.).unwrap();
+        assert_eq!(a.min.x, 0.);
+        assert_eq!(
+            (first.min.x, first.max.x),
+            (0., 1.),
+            "the caret is clipped to the node"
+        );
+        assert!(
+            first.min.y <= a.min.y && first.max.y >= a.max.y,
+            "{first:?} doesn't span the line"
+        );
+        let last = glyphs.caret_rect(4, 2.).unwrap();
+        assert_eq!(last.center().x, glyphs.glyph_rect(3).unwrap().max.x);
+        assert_eq!(glyphs.caret_rect(5, 2.), None);
+
+        assert_eq!(glyphs.glyph_at(b.center()), Some(1));
+        assert_eq!(glyphs.glyph_at(Vec2::new(1000., a.center().y)), Some(3));
+    }
+    #[test]
+    fn scale_factor() {
+        let info = layout("AB CD", 400., 1.);
+        let scaled_info = layout("AB CD", 400., 2.);
+        let glyphs = TextGlyphs::new(&info, &rect(400., 100.), 1.);
+        let scaled = TextGlyphs::new(&scaled_info, &rect(400., 100.), 2.);
+
+        let (rect, scaled) = (glyphs.glyph_rect(1).unwrap(), scaled.glyph_rect(1).unwrap());
+        // Glyphs are rasterized at the scaled font size, their bitmap size differs slightly.
+        assert!(
+            (rect.center() - scaled.center()).length() < 1.,
+            "{rect:?} != {scaled:?}"
+        );
+        assert!(
+            (rect.size() - scaled.size()).length() < 2.,
+            "{rect:?} != {scaled:?}"
+        );
+    }
+    #[test]
+    fn clipped_to_node() {
+        let info = layout("AB CD", 400., 1.);
+        let glyphs = TextGlyphs::new(&info, &rect(400., 100.), 1.);
+        let b = glyphs.glyph_rect(1).unwrap();
+
+        let clipped = TextGlyphs::new(&info, &rect(b.center().x, 100.), 1.);
+        assert_eq!(clipped.glyph_rect(1).unwrap().max.x, b.center().x);
+        assert!(clipped.glyph_rect(3).unwrap().is_empty());
+    }
+    #[test]
+    fn selection_lines() {
+        let one_line = layout("AAA", 400., 1.);
+        let word = TextGlyphs::new(&one_line, &rect(400., 100.), 1.)
+            .glyph_rect(2)
+            .unwrap();
+
+        // Wraps after the first word.
+        let info = layout("AAA BBB CC", word.max.x * 1.5, 1.);
+        let glyphs = TextGlyphs::new(&info, &rect(400., 100.), 1.);
+        assert_eq!(glyphs.len(), 8);
+
+        let rects = glyphs.selection_rects(1..8);
+        assert_eq!(rects.len(), 3, "{rects:?}");
+        let (first, second) = (rects[0], rects[1]);
+        assert!(
+            first.max.y <= second.min.y,
+            "{first:?} is not above {second:?}"
+        );
+        assert_eq!(first.min.x, glyphs.glyph_rect(1).unwrap().min.x);
+        assert_eq!(second.min.x, glyphs.glyph_rect(3).unwrap().min.x);
+        assert_eq!(second.max.x, glyphs.glyph_rect(5).unwrap().max.x);
+
+        assert_eq!(glyphs.selection_rects(2..100).len(), 3);
+        assert_eq!(glyphs.glyph_at(Vec2::new(0., second.center().y)), Some(3));
+        let caret = glyphs.caret_rect(3, 2.).unwrap();
+        assert_eq!((caret.min.y, caret.max.y), (second.min.y, second.max.y));
+    }
+}
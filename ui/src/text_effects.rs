@@ -0,0 +1,181 @@
+//! Text shadows and outlines, see [`UiDsl::text_shadow`] and
+//! [`UiDsl::text_outline`].
+//!
+//! `bevy_ui` can't draw text effects, so they are copies of the text of a node,
+//! spawned as children of the node, each with a [`TextCopy`] component.
+//! Children are drawn over their parent, so the text of the node itself is
+//! transparent, and the last copy, the _face_, is drawn with the text color
+//! over the shadow and the outline.
+//!
+//! Copies fill their parent node, moved by the shadow offset or the outline
+//! width. Their text is laid out in the same size as the node's, so they
+//! follow the size of the node from the layout, including where text wraps.
+//!
+//! [`update_text_copies`] copies the text of nodes to their `TextCopy`
+//! children when it changes, for example with [`UiDsl::text_bind_res`].
+//!
+//! # Example
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use cuicui_layout::dsl;
+//! use cuicui_layout_bevy_ui::UiDsl as Dsl;
+//!
+//! fn setup(mut cmds: Commands) {
+//!     dsl! { &mut cmds.spawn_empty(),
+//!         Menu(column screen_root) {
+//!             Title(text("Cuicui") font_size(40) text_shadow(3., 3., Color::BLACK))
+//!             Play(text("Play") text_outline(2., Color::BLACK))
+//!         }
+//!     };
+//! }
+//! # bevy::ecs::system::assert_is_system(setup);
+//! ```
+//!
+//! # Limitations
+//!
+//! - The outline is 8 copies of the text around it, thick outlines have gaps
+//!   at the sharp corners of glyphs.
+//! - To change the text color of a node with effects, change the `color` of
+//!   its face, the last `TextCopy` child, rather than its [`Text`].
+//! - Effects are [clipped](UiDsl::clip_children) like other children.
+//!
+//! [`UiDsl::text_shadow`]: crate::UiDsl::text_shadow
+//! [`UiDsl::text_outline`]: crate::UiDsl::text_outline
+//! [`UiDsl::text_bind_res`]: crate::UiDsl::text_bind_res
+//! [`UiDsl::clip_children`]: crate::UiDsl::clip_children
+use std::f32::consts::FRAC_PI_4;
+use std::iter;
+
+use bevy::ecs::{prelude::*, system::EntityCommands};
+use bevy::hierarchy::{BuildChildren, Children};
+use bevy::math::Vec2;
+#[cfg(feature = "reflect")]
+use bevy::prelude::{Reflect, ReflectComponent};
+use bevy::render::color::Color;
+use bevy::text::Text;
+use bevy::ui::{node_bundles::TextBundle, PositionType, Style, Val};
+use bevy::utils::default;
+
+/// A copy of the [`Text`] of its parent, drawn with `color`.
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Component))]
+pub struct TextCopy {
+    /// The color of the text of this copy.
+    pub color: Color,
+}
+
+/// The text effects of a node, spawned with [`TextEffects::spawn`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct TextEffects {
+    /// The offset and color of the shadow.
+    pub(crate) shadow: Option<(Vec2, Color)>,
+    /// The width and color of the outline.
+    pub(crate) outline: Option<(f32, Color)>,
+}
+impl TextEffects {
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.shadow.is_none() && self.outline.is_none()
+    }
+    /// Spawn the copies of `text` as children of `cmds`, and make `text`
+    /// transparent.
+    pub(crate) fn spawn(self, text: &mut Text, cmds: &mut EntityCommands) {
+        let face = text.sections.first().map_or(Color::WHITE, |section| section.style.color);
+        let outline = self.outline.into_iter().flat_map(|(width, color)| {
+            #[allow(clippy::cast_precision_loss)] // allow: i is at most 7
+            let offset = move |i: u8| Vec2::from_angle(f32::from(i) * FRAC_PI_4) * width;
+            (0..8).map(move |i| (offset(i), color))
+        });
+        let copies = self.shadow.into_iter().chain(outline).chain(iter::once((Vec2::ZERO, face)));
+        cmds.with_children(|cmds| {
+            for (offset, color) in copies {
+                cmds.spawn(copy(text, offset, color));
+            }
+        });
+        for section in &mut text.sections {
+            section.style.color = Color::NONE;
+        }
+    }
+}
+/// A copy of `text` drawn with `color`, moved by `offset` from its parent.
+fn copy(text: &Text, offset: Vec2, color: Color) -> impl Bundle {
+    let mut text = text.clone();
+    for section in &mut text.sections {
+        section.style.color = color;
+    }
+    let style = Style {
+        position_type: PositionType::Absolute,
+        left: Val::Px(offset.x),
+        top: Val::Px(offset.y),
+        width: Val::Percent(100.),
+        height: Val::Percent(100.),
+        ..default()
+    };
+    (TextBundle { text, style, ..default() }, TextCopy { color })
+}
+
+/// Copy the [`Text`] of nodes to their [`TextCopy`] children, when either
+/// changes.
+#[allow(clippy::needless_pass_by_value)] // false positive, bevy systems
+pub fn update_text_copies(
+    texts: Query<(Ref<Text>, &Children), Without<TextCopy>>,
+    mut copies: Query<(&mut Text, Ref<TextCopy>)>,
+) {
+    for (text, children) in &texts {
+        let mut children = copies.iter_many_mut(children);
+        while let Some((mut copy, effect)) = children.fetch_next() {
+            if !text.is_changed() && !effect.is_changed() {
+                continue;
+            }
+            copy.sections.clone_from(&text.sections);
+            copy.alignment = text.alignment;
+            copy.linebreak_behavior = text.linebreak_behavior;
+            for section in &mut copy.sections {
+                section.style.color = effect.color;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::{CommandQueue, RunSystemOnce};
+    use bevy::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn copies_follow_text() {
+        let mut world = World::new();
+        let text = Text::from_section("Play", TextStyle { color: Color::RED, ..default() });
+        let effects = TextEffects {
+            shadow: Some((Vec2::new(2., 3.), Color::BLACK)),
+            outline: Some((1., Color::BLUE)),
+        };
+        let node = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+        let mut cmds = Commands::new(&mut queue, &world);
+        let mut text_cmds = cmds.entity(node);
+        let mut node_text = text;
+        effects.spawn(&mut node_text, &mut text_cmds);
+        text_cmds.insert(node_text);
+        queue.apply(&mut world);
+
+        let children = world.get::<Children>(node).unwrap().to_vec();
+        assert_eq!(children.len(), 10);
+        let color = |entity| world.get::<Text>(entity).unwrap().sections[0].style.color;
+        assert_eq!(color(node), Color::NONE);
+        assert_eq!(color(children[0]), Color::BLACK);
+        assert_eq!(color(children[9]), Color::RED);
+        let style = world.get::<Style>(children[0]).unwrap();
+        assert_eq!((style.left, style.top), (Val::Px(2.), Val::Px(3.)));
+
+        world.get_mut::<Text>(node).unwrap().sections[0].value = "Quit".to_owned();
+        world.run_system_once(update_text_copies);
+        let face = world.get::<Text>(children[9]).unwrap();
+        assert_eq!(face.sections[0].value, "Quit");
+        assert_eq!(face.sections[0].style.color, Color::RED);
+    }
+}
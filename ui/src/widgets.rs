@@ -0,0 +1,114 @@
+//! Ready-made layouts for common UI patterns, such as [`settings_row`].
+//!
+//! Requires the `widgets` cargo feature.
+use cuicui_dsl::{dsl, EntityCommands};
+use cuicui_layout::dsl_functions::{child, pct};
+
+use crate::UiDsl;
+
+/// Widths of the label and control columns of [`settings_row`]s, in percent
+/// of the row width.
+///
+/// Rows spawned with the same `Columns` in the same container line up, since
+/// all rows take the full width of the container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns {
+    /// Width of the label column, in percent of the row width.
+    pub label: u8,
+    /// Width of the control column, in percent of the row width.
+    pub control: u8,
+}
+impl Default for Columns {
+    /// Half the row for the label, a bit less than half for the control.
+    fn default() -> Self {
+        Self { label: 50, control: 45 }
+    }
+}
+
+/// Spawn a settings menu row on `cmds`: a `label` text on the left and a
+/// control on the right.
+///
+/// `control` is called with the only child of the control column, use it to
+/// spawn your button, slider etc. Give it a width relative to its parent
+/// (such as `pct(100)`) for it to fill the column.
+///
+/// The row takes the full width of its parent, so spawn rows within a
+/// `column` container with the same [`Columns`] to get aligned columns.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use cuicui_layout::{dsl, dsl_functions::pct};
+/// use cuicui_layout_bevy_ui::{widgets::{settings_row, Columns}, UiDsl};
+///
+/// fn setup(mut cmds: Commands) {
+///     let columns = Columns { label: 40, control: 60 };
+///     let mut menu = cmds.spawn_empty();
+///     dsl!(<UiDsl> &mut menu, Settings(screen_root column));
+///     menu.with_children(|cmds| {
+///         for option in ["Window mode", "Resolution"] {
+///             settings_row(&mut cmds.spawn_empty(), columns, option, |cmds| {
+///                 dsl!(<UiDsl> cmds, Entity(text("Default") width(pct(100))));
+///             });
+///         }
+///     });
+/// }
+/// ```
+pub fn settings_row(
+    cmds: &mut EntityCommands,
+    columns: Columns,
+    label: &str,
+    control: impl FnOnce(&mut EntityCommands),
+) {
+    dsl! { <UiDsl> cmds,
+        SettingsRow(row rules(pct(100), child(1.))) {
+            Label(text(label) width(pct(columns.label)))
+            Control(row rules(pct(columns.control), child(1.))) {
+                code(let cmds) {
+                    control(cmds);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::prelude::*;
+    use bevy::hierarchy::Children;
+    use cuicui_layout::{LeafRule, Node};
+
+    use super::*;
+
+    #[test]
+    fn rows_share_column_widths() {
+        #[derive(Component)]
+        struct MyControl;
+
+        let mut world = World::new();
+        let mut queue = bevy::ecs::system::CommandQueue::default();
+        let mut cmds = Commands::new(&mut queue, &world);
+        let columns = Columns { label: 30, control: 70 };
+        let rows = ["Window mode", "Mouse lock"].map(|label| {
+            let mut row = cmds.spawn_empty();
+            settings_row(&mut row, columns, label, |cmds| {
+                cmds.insert(MyControl);
+            });
+            row.id()
+        });
+        queue.apply(&mut world);
+
+        assert_eq!(world.query::<&MyControl>().iter(&world).count(), 2);
+        for row in rows {
+            let children = world.get::<Children>(row).unwrap();
+            let [label, _control] = children[..] else {
+                panic!("settings row should have two children");
+            };
+            let Some(Node::Box(size)) = world.get::<Node>(label) else {
+                panic!("settings row label should be a box node");
+            };
+            assert_eq!(size.width, LeafRule::Parent(0.3));
+        }
+    }
+}